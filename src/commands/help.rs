@@ -6,6 +6,8 @@ use nu_protocol::{
     engine::{Command, EngineState, Stack},
 };
 
+use crate::engine::get_mcp_client_manager_sync;
+
 #[derive(Clone)]
 pub struct McpHelpCommand;
 
@@ -69,8 +71,37 @@ impl Command for McpHelpCommand {
         Call a specific MCP tool:
             tool fs.read_file Cargo.toml | from toml
 
+        Look at the last tool call's result again, or at what was called and how long it took:
+            $mcp_last
+            $mcp_last_call
+
         You can also learn more at https://github.com/wycats/mcp-repl and https://www.nushell.sh/book/"#;
 
+            let manager = get_mcp_client_manager_sync();
+            let with_instructions: Vec<String> = manager
+                .get_servers()
+                .iter()
+                .filter(|(_, server)| server.client.instructions().is_some())
+                .map(|(name, _)| name.clone())
+                .collect();
+            let offline = manager.is_offline();
+            drop(manager);
+
+            let mut msg = msg.to_string();
+
+            if offline {
+                msg.push_str(
+                    "\n\n        Running with --offline: tools are registered from cached schemas and calls are not executed.",
+                );
+            }
+
+            if !with_instructions.is_empty() {
+                msg.push_str(&format!(
+                    "\n\n        These servers provide usage instructions (see `mcp instructions <server>`): {}",
+                    with_instructions.join(", ")
+                ));
+            }
+
             Ok(Value::string(msg, head).into_pipeline_data())
         } else if find.is_some() {
             HelpCommands {}.run(engine_state, stack, call, PipelineData::Empty)
@@ -1,9 +1,19 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use nu_engine::CallExt;
 use nu_protocol::{
-    Category, PipelineData, ShellError, Signature, Value,
+    Category, Example, ListStream, PipelineData, ShellError, Signals, Signature, Span, Spanned,
+    SyntaxShape, Type, Value,
     engine::{Command, EngineState, Stack},
 };
+use rmcp::model::Resource;
 
-use crate::engine::get_mcp_client_manager_sync;
+use super::utils::{ReplClient, add_output_flag, apply_output_format};
+use crate::{
+    config::DEFAULT_STUCK_AFTER,
+    engine::{BlockOnError, Watchdog, block_on_shared, get_mcp_client_manager_sync},
+    util::mime::mime_matches,
+};
 
 /// List MCP resources command
 #[derive(Clone)]
@@ -15,55 +25,247 @@ impl Command for ListResourcesCommand {
     }
 
     fn signature(&self) -> Signature {
-        Signature::build(String::from("mcp-list-resources"))
-            .category(Category::Custom(String::from("mcp")))
+        add_output_flag(
+            Signature::build("resources list")
+                .category(Category::Custom(String::from("mcp")))
+                .named(
+                    "server",
+                    SyntaxShape::String,
+                    "Only list the resources of this server",
+                    Some('s'),
+                )
+                .named(
+                    "mime",
+                    SyntaxShape::String,
+                    "Only list resources whose MIME type matches this glob (e.g. 'text/*')",
+                    Some('m'),
+                )
+                .switch(
+                    "refresh",
+                    "Ask the servers for their resources instead of using the list loaded at connect",
+                    Some('r'),
+                )
+                .named(
+                    "limit",
+                    SyntaxShape::Int,
+                    "Stop after this many resources, without fetching further pages",
+                    Some('l'),
+                )
+                .input_output_types(vec![(Type::Nothing, Type::Table(vec![].into()))]),
+        )
     }
 
     fn description(&self) -> &'static str {
         "List all available MCP resources"
     }
 
+    fn extra_description(&self) -> &'static str {
+        "Rows are produced as a stream, so with --refresh each server is only asked for its resources when the pipeline gets to it, and a page is only fetched when it's needed."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "List the text resources of the fs server",
+                example: "resources list --server fs --mime 'text/*'",
+                result: None,
+            },
+            Example {
+                description: "Fetch the first 20 resources from the servers again",
+                example: "resources list --refresh --limit 20",
+                result: None,
+            },
+        ]
+    }
+
     fn run(
         &self,
-        _engine_state: &EngineState,
-        _stack: &mut Stack,
+        engine_state: &EngineState,
+        stack: &mut Stack,
         call: &nu_protocol::engine::Call<'_>,
         _input: PipelineData,
     ) -> Result<PipelineData, ShellError> {
         let span = call.head;
+        let server: Option<Spanned<String>> = call.get_flag(engine_state, stack, "server")?;
+        let mime: Option<String> = call.get_flag(engine_state, stack, "mime")?;
+        let refresh = call.has_flag(engine_state, stack, "refresh")?;
+        let limit: Option<usize> = call.get_flag(engine_state, stack, "limit")?;
 
         let binding = get_mcp_client_manager_sync();
-        let servers = binding.get_servers();
+        let servers: VecDeque<(String, Arc<ReplClient>)> = binding
+            .get_servers()
+            .iter()
+            .filter(|(name, _)| server.as_ref().is_none_or(|server| &server.item == *name))
+            .map(|(name, server)| (name.clone(), server.client.clone()))
+            .collect();
+        drop(binding);
 
-        let mut table = Vec::new();
+        if let Some(server) = &server {
+            if servers.is_empty() {
+                return Err(ShellError::GenericError {
+                    error: format!("Unknown MCP server '{}'", server.item),
+                    msg: "no server with this name is connected".into(),
+                    span: Some(server.span),
+                    help: Some("Run `mcp list` to see the connected servers".into()),
+                    inner: Vec::new(),
+                });
+            }
+        }
 
-        for (namespace, server) in servers {
-            let resources = server.client.get_resources();
-            for resource in resources {
-                let mut record = crate::util::NuValueMap::default();
+        let rows = ResourceRows {
+            servers,
+            current: None,
+            cursor: None,
+            rows: VecDeque::new(),
+            mime,
+            refresh,
+            signals: engine_state.signals().clone(),
+            span,
+        };
 
-                record.add_string("uri", resource.uri.clone(), span);
-                record.add_string("client", namespace.clone(), span);
-                record.add_string("name", resource.name.clone(), span);
+        let stream = ListStream::new(
+            rows.take(limit.unwrap_or(usize::MAX)),
+            span,
+            engine_state.signals().clone(),
+        );
 
-                match &resource.mime_type {
-                    Some(mime) => record.add_string("type", mime.clone(), span),
-                    None => record.add("type", Value::nothing(span)),
-                }
+        apply_output_format(
+            engine_state,
+            stack,
+            call,
+            PipelineData::ListStream(stream, None),
+        )
+    }
+}
 
-                if let Some(desc) = &resource.description {
-                    record.add_string("description", desc.clone(), span);
+/// Produces one row per resource, going through the servers in order
+///
+/// Without `refresh`, the resources loaded at connect are used. With it, the
+/// server is asked for one page at a time as the rows are consumed.
+struct ResourceRows {
+    servers: VecDeque<(String, Arc<ReplClient>)>,
+    current: Option<(String, Arc<ReplClient>)>,
+    /// The cursor of the current server's next page, if there is one
+    cursor: Option<String>,
+    rows: VecDeque<Value>,
+    mime: Option<String>,
+    refresh: bool,
+    signals: Signals,
+    span: Span,
+}
+
+impl Iterator for ResourceRows {
+    type Item = Value;
+
+    fn next(&mut self) -> Option<Value> {
+        loop {
+            if let Some(row) = self.rows.pop_front() {
+                return Some(row);
+            }
+
+            if self.signals.interrupted() {
+                return None;
+            }
+
+            let more_pages = self.current.is_some() && self.cursor.is_some();
+
+            if !more_pages {
+                let (name, client) = self.servers.pop_front()?;
+                self.current = Some((name.clone(), client.clone()));
+
+                if !self.refresh {
+                    self.push_resources(&name, client.get_resources());
+                    continue;
                 }
+            }
 
-                if let Some(meta) = &resource.annotations {
-                    record.add_string("metadata", format!("{meta:?}"), span);
+            if let Err(err) = self.fetch_page() {
+                return Some(Value::error(err, self.span));
+            }
+        }
+    }
+}
+
+impl ResourceRows {
+    fn fetch_page(&mut self) -> Result<(), ShellError> {
+        let Some((name, client)) = self.current.clone() else {
+            return Ok(());
+        };
+
+        let cursor = self.cursor.take();
+        let label = format!("{name} resources/list");
+        let watchdog = Watchdog {
+            label: &label,
+            stuck_after: DEFAULT_STUCK_AFTER,
+        };
+
+        let fetch = async move { client.list_resources_page(cursor).await };
+
+        let (resources, next_cursor) =
+            match block_on_shared(fetch, &self.signals, Some(watchdog)) {
+                Ok(result) => result.map_err(|err| format!("{err:#}")),
+                Err(BlockOnError::Interrupted) => Err("Interrupted by Ctrl-C".to_string()),
+                Err(BlockOnError::Panicked(message)) => {
+                    Err(format!("The request panicked: {message}"))
                 }
+            }
+            .map_err(|msg| ShellError::GenericError {
+                error: format!("Failed to list the resources of '{name}'"),
+                msg,
+                span: Some(self.span),
+                help: None,
+                inner: Vec::new(),
+            })?;
 
-                table.push(record.into_value(span));
+        self.cursor = next_cursor;
+        self.push_resources(&name, &resources);
+
+        Ok(())
+    }
+
+    fn push_resources(&mut self, namespace: &str, resources: &[Resource]) {
+        for resource in resources {
+            if let Some(pattern) = &self.mime {
+                let matches = resource
+                    .mime_type
+                    .as_deref()
+                    .is_some_and(|mime| mime_matches(pattern, mime));
+
+                if !matches {
+                    continue;
+                }
             }
+
+            self.rows
+                .push_back(resource_row(namespace, resource, self.span));
         }
+    }
+}
 
-        drop(binding);
-        Ok(PipelineData::Value(Value::list(table, span), None))
+/// The row shown for a resource by `resources list` and `resources search`
+pub fn resource_row(namespace: &str, resource: &Resource, span: Span) -> Value {
+    let mut record = crate::util::NuValueMap::default();
+
+    record.add_string("uri", resource.uri.clone(), span);
+    record.add_string("client", namespace, span);
+    record.add_string("name", resource.name.clone(), span);
+
+    match &resource.mime_type {
+        Some(mime) => record.add_string("type", mime.clone(), span),
+        None => record.add("type", Value::nothing(span)),
+    }
+
+    if let Some(desc) = &resource.description {
+        record.add_string("description", desc.clone(), span);
+    }
+
+    if let Some(size) = resource.size {
+        record.add("size", Value::filesize(i64::from(size), span));
     }
+
+    if let Some(meta) = &resource.annotations {
+        record.add_string("metadata", format!("{meta:?}"), span);
+    }
+
+    record.into_value(span)
 }
@@ -0,0 +1,210 @@
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+use log::info;
+use nu_protocol::{PipelineData, ShellError, Span, Value, engine::StateWorkingSet};
+use rmcp::model::Tool;
+use serde_json::{Value as JsonValue, json};
+
+use super::{
+    tool::{RunFn, register_dynamic_tool},
+    tool_mapper,
+};
+use crate::{
+    config::{LOCAL_PARAM_TYPES, LOCAL_TOOLS_SERVER, LocalToolConfig},
+    util::{eval::eval_closure_source, format::json_to_nu},
+};
+
+/// Register the `[tools.local]` tools as `tool local.<name>` commands
+///
+/// Each tool is described to the tool mapper with a schema built from its
+/// `params`, so its command takes arguments exactly like a server's tool
+/// with the same parameters would. Returns the tools that were registered,
+/// keyed by name, for `tool list`.
+pub fn register_local_tools(
+    working_set: &mut StateWorkingSet,
+    tools: &IndexMap<String, LocalToolConfig>,
+) -> IndexMap<String, Tool> {
+    let mut registered = IndexMap::new();
+
+    for (name, config) in tools {
+        if let Some(param) = config
+            .params
+            .iter()
+            .find(|param| !LOCAL_PARAM_TYPES.contains(&param.param_type.as_str()))
+        {
+            crate::warning!(
+                "Skipping local tool '{}': parameter '{}' has unknown type '{}'",
+                name,
+                param.name,
+                param.param_type
+            );
+            continue;
+        }
+
+        let tool = local_tool(name, config);
+        let command_name = format!("tool {LOCAL_TOOLS_SERVER}.{name}");
+        info!("Registering local tool as command: {command_name}");
+
+        // Local tools are written in the config, so every parameter gets a flag
+        let mut signature = tool_mapper::map_tool_to_signature(&tool, "mcp", 0);
+        signature.name.clone_from(&command_name);
+
+        register_dynamic_tool(
+            working_set,
+            &command_name,
+            signature,
+            config.description.clone(),
+            "A local tool from the [tools.local] config; its arguments are passed to the closure as a record in $in.".into(),
+            local_tool_run_function(tool.clone(), config.body.clone()),
+        );
+        registered.insert(name.clone(), tool);
+    }
+
+    registered
+}
+
+/// The MCP tool a local tool looks like, with a schema built from its params
+fn local_tool(name: &str, config: &LocalToolConfig) -> Tool {
+    let properties: serde_json::Map<String, JsonValue> = config
+        .params
+        .iter()
+        .map(|param| {
+            let mut schema = json!({ "type": param.param_type });
+            if let Some(description) = &param.description {
+                schema["description"] = json!(description);
+            }
+            (param.name.clone(), schema)
+        })
+        .collect();
+
+    let required: Vec<&str> = config
+        .params
+        .iter()
+        .filter(|param| param.required)
+        .map(|param| param.name.as_str())
+        .collect();
+
+    let JsonValue::Object(schema) = json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    }) else {
+        unreachable!("the schema is an object literal");
+    };
+
+    Tool::new(
+        name.to_string(),
+        config.description.clone(),
+        Arc::new(schema),
+    )
+}
+
+/// Evaluate the tool's closure with the mapped arguments as `$in`
+///
+/// Every declared parameter is a column of the record, `null` when it
+/// wasn't given, so the closure doesn't have to check which columns exist.
+fn local_tool_run_function(tool: Tool, body: String) -> Box<RunFn> {
+    Box::new(move |engine_state, stack, call, _input| {
+        let span = call.head;
+        let params = tool_mapper::map_call_args_to_tool_params(engine_state, stack, call, &tool, 0)
+            .and_then(|params| {
+                tool_mapper::validate_tool_params(&tool, &params, span).map(|()| params)
+            })
+            .map_err(ShellError::from)?;
+
+        eval_closure_source(
+            engine_state,
+            stack,
+            &body,
+            PipelineData::Value(arguments_record(&tool, &params, span), None),
+            span,
+            false,
+        )
+    })
+}
+
+/// The record a local tool's closure gets: one column per parameter
+fn arguments_record(tool: &Tool, params: &serde_json::Map<String, JsonValue>, span: Span) -> Value {
+    let mut record = nu_protocol::Record::new();
+
+    for param in tool_mapper::tool_parameters(tool) {
+        let value = params.get(&param.name).map_or_else(
+            || Value::nothing(span),
+            |value| json_to_nu(value, Some(span)),
+        );
+        record.push(param.name, value);
+    }
+
+    Value::record(record, span)
+}
+
+#[cfg(test)]
+mod tests {
+    use nu_protocol::engine::Stack;
+
+    use super::*;
+    use crate::{commands::builtin::add_shell_command_context, config::LocalToolParam};
+
+    #[test]
+    fn test_local_tool_runs_closure_with_arguments() {
+        let config = LocalToolConfig {
+            description: "Label some text".into(),
+            params: vec![
+                LocalToolParam {
+                    name: "text".into(),
+                    param_type: "string".into(),
+                    required: true,
+                    description: None,
+                },
+                LocalToolParam {
+                    name: "times".into(),
+                    param_type: "integer".into(),
+                    required: false,
+                    description: None,
+                },
+            ],
+            body: r#"{|| $"($in.text) x($in.times | default 1)" }"#.into(),
+        };
+
+        let mut engine_state = add_shell_command_context(nu_cmd_lang::create_default_context());
+        let mut working_set = StateWorkingSet::new(&engine_state);
+        let tools = register_local_tools(
+            &mut working_set,
+            &IndexMap::from([("echo".to_string(), config)]),
+        );
+        let delta = working_set.render();
+        engine_state.merge_delta(delta).unwrap();
+        assert!(tools.contains_key("echo"));
+
+        let run = |source: &str| {
+            let mut working_set = StateWorkingSet::new(&engine_state);
+            let block = nu_parser::parse(&mut working_set, None, source.as_bytes(), false);
+            assert!(working_set.parse_errors.is_empty(), "{source}");
+            let delta = working_set.render();
+            let mut engine_state = engine_state.clone();
+            engine_state.merge_delta(delta).unwrap();
+            nu_engine::eval_block::<nu_protocol::debugger::WithoutDebug>(
+                &engine_state,
+                &mut Stack::new(),
+                &block,
+                PipelineData::empty(),
+            )
+            .and_then(|data| data.into_value(Span::test_data()))
+        };
+
+        assert_eq!(
+            run("tool local.echo ab").unwrap().as_str().unwrap(),
+            "ab x1"
+        );
+        assert_eq!(
+            run("tool local.echo --text ab --times 3")
+                .unwrap()
+                .as_str()
+                .unwrap(),
+            "ab x3"
+        );
+        assert!(run("tool local.echo").is_err());
+    }
+}
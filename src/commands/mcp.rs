@@ -0,0 +1,661 @@
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, PipelineData, Record, ShellError, Signature, Span, Spanned, SyntaxShape,
+    Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+
+use super::utils::{add_output_flag, apply_output_format};
+use crate::{
+    config::{McpConnectionType, McpReplConfig},
+    engine::get_mcp_client_manager_sync,
+    mcp::SUPPORTED_PROTOCOL_VERSIONS,
+    mcp_manager::{DISCONNECTED, RegisteredServer},
+    util::format::{json_to_nu, terminal_width, wrap_markdown},
+};
+
+/// What `mcp instructions` prints for a server that didn't send any
+const NO_INSTRUCTIONS: &str = "no instructions provided";
+
+/// Parent command for the `mcp` management family, mirroring `tool`
+#[derive(Clone)]
+pub struct McpCommand;
+
+impl Command for McpCommand {
+    fn name(&self) -> &'static str {
+        "mcp"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("mcp")
+            .category(Category::Custom("mcp".into()))
+            .input_output_types(vec![(Type::Nothing, Type::String)])
+    }
+
+    fn description(&self) -> &'static str {
+        "Commands for managing MCP server connections"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        r"You must use one of the following subcommands. Using this command as-is will only produce this help message.
+
+A session goes through these steps:
+  1. connect: servers in the config file are connected when the REPL starts
+  2. list: `mcp list` shows the connected servers, `mcp info <server>` the details of one
+  3. tool list: `tool list` shows the tools the servers provide, `tool <server>` the tools of one
+  4. call: `tool <server>.<tool>` or `tool call <server> <tool>` runs a tool
+     (`mcp restart <server>` restarts a server, e.g. after rebuilding it,
+     and `mcp profile switch <name>` swaps in another profile's servers)
+  5. disconnect: servers are disconnected when the REPL exits
+
+Servers are configured in the first of these files that exists:
+  $MCP_CONFIG, ./mcp-repl.toml, ~/.config/mcp-repl/config.toml, /etc/mcp-repl/config.toml
+`mcp save` writes the session's server entries to ./mcp-repl.toml, and
+`mcp doctor` checks the config files, the servers and the environment."
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        Ok(PipelineData::Value(
+            Value::string(
+                nu_engine::get_full_help(self, engine_state, stack),
+                call.head,
+            ),
+            None,
+        ))
+    }
+}
+
+/// Command to list the connected MCP servers
+#[derive(Clone)]
+pub struct McpListCommand;
+
+impl Command for McpListCommand {
+    fn name(&self) -> &'static str {
+        "mcp list"
+    }
+
+    fn signature(&self) -> Signature {
+        add_output_flag(
+            Signature::build("mcp list")
+                .category(Category::Custom("mcp".into()))
+                .switch(
+                    "stats",
+                    "Add uptime, connect time, request, failure and SSE reconnect counts, and the last error",
+                    None,
+                )
+                .input_output_types(vec![(Type::Nothing, Type::Table(vec![].into()))]),
+        )
+    }
+
+    fn description(&self) -> &'static str {
+        "List the connected MCP servers"
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Show the protocol version each server negotiated",
+                example: "mcp list | select name protocol_version",
+                result: None,
+            },
+            Example {
+                description: "See which config file each server's definition came from",
+                example: "mcp list | select name source",
+                result: None,
+            },
+            Example {
+                description: "Find the servers whose calls have been failing",
+                example: "mcp list --stats | where failures > 0 | select name failures last_error",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let stats = call.has_flag(engine_state, stack, "stats")?;
+        let manager = get_mcp_client_manager_sync();
+
+        let rows = manager
+            .get_servers()
+            .iter()
+            .map(|(name, server)| {
+                let mut record = server_summary(name, server, manager.config(), span);
+                if stats {
+                    record.extend(server.stats.to_record(span));
+                }
+                Value::record(record, span)
+            })
+            .collect();
+
+        drop(manager);
+
+        apply_output_format(
+            engine_state,
+            stack,
+            call,
+            PipelineData::Value(Value::list(rows, span), None),
+        )
+    }
+}
+
+/// Command to show details about a single MCP server
+#[derive(Clone)]
+pub struct McpInfoCommand;
+
+impl Command for McpInfoCommand {
+    fn name(&self) -> &'static str {
+        "mcp info"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("mcp info")
+            .category(Category::Custom("mcp".into()))
+            .required("server", SyntaxShape::String, "The name of the MCP server")
+            .switch(
+                "raw",
+                "Return the server's answer to initialize as it was sent",
+                Some('r'),
+            )
+            .input_output_types(vec![(Type::Nothing, Type::Record(vec![].into()))])
+    }
+
+    fn description(&self) -> &'static str {
+        "Show details about a connected MCP server"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "--raw returns the result of the initialize handshake, with the server info, capabilities, instructions and protocol version, unchanged. It includes fields this REPL doesn't know about, such as experimental capabilities. Offline, and for SSE servers, only the fields the REPL knows are there."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Show the details of the github server",
+                example: "mcp info github",
+                result: None,
+            },
+            Example {
+                description: "Get the experimental capabilities the github server declared",
+                example: "mcp info github --raw | get capabilities.experimental?",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let name: Spanned<String> = call.req(engine_state, stack, 0)?;
+
+        let manager = get_mcp_client_manager_sync();
+        let Some(server) = manager.get_server(&name.item) else {
+            return Err(unknown_server_error(&name));
+        };
+
+        if call.has_flag(engine_state, stack, "raw")? {
+            let raw = json_to_nu(server.client.initialize_result(), Some(span));
+            return Ok(PipelineData::Value(raw, None));
+        }
+
+        let mut record = server_summary(&name.item, server, manager.config(), span);
+        record.extend(server.stats.to_record(span));
+        record.push(
+            "resources",
+            Value::int(count(server.client.get_resources().len()), span),
+        );
+        record.push(
+            "skipped_tools",
+            Value::list(
+                server
+                    .skipped
+                    .iter()
+                    .map(|skipped| {
+                        let mut row = Record::new();
+                        row.push("tool", Value::string(&skipped.tool, span));
+                        row.push("reason", Value::string(&skipped.reason, span));
+                        Value::record(row, span)
+                    })
+                    .collect(),
+                span,
+            ),
+        );
+        record.push(
+            "initialize_params",
+            server.client.initialize_params().map_or_else(
+                || Value::nothing(span),
+                |params| {
+                    serde_json::to_value(params).map_or_else(
+                        |_| Value::nothing(span),
+                        |json| json_to_nu(&json, Some(span)),
+                    )
+                },
+            ),
+        );
+        drop(manager);
+        record.push(
+            "supported_protocol_versions",
+            Value::list(
+                SUPPORTED_PROTOCOL_VERSIONS
+                    .iter()
+                    .map(|version| Value::string(*version, span))
+                    .collect(),
+                span,
+            ),
+        );
+
+        Ok(PipelineData::Value(Value::record(record, span), None))
+    }
+}
+
+/// Command to show or reset the request counters of the servers
+#[derive(Clone)]
+pub struct McpStatsCommand;
+
+impl Command for McpStatsCommand {
+    fn name(&self) -> &'static str {
+        "mcp stats"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("mcp stats")
+            .category(Category::Custom("mcp".into()))
+            .optional(
+                "server",
+                SyntaxShape::String,
+                "The server to show or reset (default: all servers)",
+            )
+            .switch(
+                "reset",
+                "Clear the request counters and the last error",
+                None,
+            )
+            .input_output_types(vec![
+                (Type::Nothing, Type::Table(vec![].into())),
+                (Type::Nothing, Type::Nothing),
+            ])
+    }
+
+    fn description(&self) -> &'static str {
+        "Show the request counters of the MCP servers"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "The counters cover tool calls and resource reads since the server connected, and carry on across `mcp restart`. --reset clears them, but not the uptime."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Show how many requests each server handled",
+                example: "mcp stats | select name requests failures uptime",
+                result: None,
+            },
+            Example {
+                description: "Start counting the github server's requests from zero",
+                example: "mcp stats github --reset",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let name: Option<Spanned<String>> = call.opt(engine_state, stack, 0)?;
+        let reset = call.has_flag(engine_state, stack, "reset")?;
+
+        let manager = get_mcp_client_manager_sync();
+        let servers: Vec<(&String, &RegisteredServer)> = match &name {
+            Some(name) => {
+                let Some((key, server)) = manager.get_servers().get_key_value(&name.item) else {
+                    return Err(unknown_server_error(name));
+                };
+                vec![(key, server)]
+            }
+            None => manager.get_servers().iter().collect(),
+        };
+
+        if reset {
+            for (_, server) in servers {
+                server.stats.reset();
+            }
+            return Ok(PipelineData::empty());
+        }
+
+        let rows = servers
+            .into_iter()
+            .map(|(name, server)| {
+                let mut record = Record::new();
+                record.push("name", Value::string(name, span));
+                record.extend(server.stats.to_record(span));
+                Value::record(record, span)
+            })
+            .collect();
+
+        Ok(PipelineData::Value(Value::list(rows, span), None))
+    }
+}
+
+/// Command to show the usage instructions servers sent when connecting
+#[derive(Clone)]
+pub struct McpInstructionsCommand;
+
+impl Command for McpInstructionsCommand {
+    fn name(&self) -> &'static str {
+        "mcp instructions"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("mcp instructions")
+            .category(Category::Custom("mcp".into()))
+            .optional(
+                "server",
+                SyntaxShape::String,
+                "The server to show instructions for (default: all servers)",
+            )
+            .input_output_types(vec![
+                (Type::Nothing, Type::String),
+                (Type::Nothing, Type::Table(vec![].into())),
+            ])
+    }
+
+    fn description(&self) -> &'static str {
+        "Show the instructions an MCP server provides for using its tools"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "Servers can explain how their tools are meant to be used when they connect. With a server name, the instructions are wrapped to the terminal width; without one, a table of every server's instructions is returned."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Read the github server's instructions",
+                example: "mcp instructions github",
+                result: None,
+            },
+            Example {
+                description: "List which servers provide instructions",
+                example: "mcp instructions | where instructions != null",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let name: Option<Spanned<String>> = call.opt(engine_state, stack, 0)?;
+
+        let manager = get_mcp_client_manager_sync();
+
+        if let Some(name) = name {
+            let Some(server) = manager.get_server(&name.item) else {
+                return Err(unknown_server_error(&name));
+            };
+
+            let text = server.client.instructions().map_or_else(
+                || NO_INSTRUCTIONS.to_string(),
+                |instructions| wrap_markdown(instructions, terminal_width()),
+            );
+            return Ok(PipelineData::Value(Value::string(text, span), None));
+        }
+
+        let rows = manager
+            .get_servers()
+            .iter()
+            .map(|(name, server)| {
+                let mut record = Record::new();
+                record.push("server", Value::string(name, span));
+                record.push(
+                    "instructions",
+                    server
+                        .client
+                        .instructions()
+                        .map_or_else(|| Value::nothing(span), |text| Value::string(text, span)),
+                );
+                Value::record(record, span)
+            })
+            .collect();
+
+        Ok(PipelineData::Value(Value::list(rows, span), None))
+    }
+}
+
+/// Command to compare the capabilities of the connected servers
+#[derive(Clone)]
+pub struct McpCapabilitiesCommand;
+
+impl Command for McpCapabilitiesCommand {
+    fn name(&self) -> &'static str {
+        "mcp capabilities"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("mcp capabilities")
+            .category(Category::Custom("mcp".into()))
+            .switch(
+                "json",
+                "Emit the raw capability objects as JSON, keyed by server",
+                Some('j'),
+            )
+            .input_output_types(vec![
+                (Type::Nothing, Type::Table(vec![].into())),
+                (Type::Nothing, Type::String),
+            ])
+    }
+
+    fn description(&self) -> &'static str {
+        "Show which capabilities each connected MCP server supports"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "Capabilities come from the server's answer to `initialize`. Every server gets every column, and capabilities a server didn't declare show up as false."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Find the servers that support resource subscriptions",
+                example: "mcp capabilities | where resources_subscribe",
+                result: None,
+            },
+            Example {
+                description: "Show the capability objects exactly as the servers sent them",
+                example: "mcp capabilities --json",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let json = call.has_flag(engine_state, stack, "json")?;
+
+        let manager = get_mcp_client_manager_sync();
+        let capabilities = manager.get_servers().iter().map(|(name, server)| {
+            let raw =
+                serde_json::to_value(&server.client.server_info().capabilities).unwrap_or_default();
+            (name, server, raw)
+        });
+
+        if json {
+            let raw: serde_json::Map<String, serde_json::Value> = capabilities
+                .map(|(name, _, raw)| (name.clone(), raw))
+                .collect();
+            let text =
+                serde_json::to_string_pretty(&raw).map_err(|err| ShellError::GenericError {
+                    error: "Failed to serialize capabilities".into(),
+                    msg: err.to_string(),
+                    span: Some(span),
+                    help: None,
+                    inner: Vec::new(),
+                })?;
+            return Ok(PipelineData::Value(Value::string(text, span), None));
+        }
+
+        let rows = capabilities
+            .map(|(name, server, raw)| {
+                Value::record(capability_row(name, server.tools.len(), &raw, span), span)
+            })
+            .collect();
+
+        Ok(PipelineData::Value(Value::list(rows, span), None))
+    }
+}
+
+/// One row of `mcp capabilities`, read from the serialized capabilities so
+/// that sections added in newer protocol revisions are picked up too
+fn capability_row(name: &str, tools: usize, raw: &serde_json::Value, span: Span) -> Record {
+    let section = |key: &str| raw.get(key).filter(|value| !value.is_null());
+    let flag = |key: &str, field: &str| {
+        Value::bool(
+            section(key)
+                .and_then(|section| section.get(field))
+                .and_then(serde_json::Value::as_bool)
+                .unwrap_or(false),
+            span,
+        )
+    };
+    let present = |key: &str| Value::bool(section(key).is_some(), span);
+
+    let mut record = Record::new();
+    record.push("server", Value::string(name, span));
+    record.push("tools", present("tools"));
+    record.push("tool_count", Value::int(count(tools), span));
+    record.push("tools_list_changed", flag("tools", "listChanged"));
+    record.push("resources", present("resources"));
+    record.push("resources_subscribe", flag("resources", "subscribe"));
+    record.push("resources_list_changed", flag("resources", "listChanged"));
+    record.push("prompts", present("prompts"));
+    record.push("prompts_list_changed", flag("prompts", "listChanged"));
+    record.push("logging", present("logging"));
+    record.push("completions", present("completions"));
+    record.push(
+        "experimental",
+        Value::list(
+            section("experimental")
+                .and_then(serde_json::Value::as_object)
+                .map(|experimental| {
+                    experimental
+                        .keys()
+                        .map(|key| Value::string(key, span))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            span,
+        ),
+    );
+
+    record
+}
+
+/// The columns shared by `mcp list` and `mcp info`
+pub fn server_summary(
+    name: &str,
+    server: &RegisteredServer,
+    config: &McpReplConfig,
+    span: Span,
+) -> Record {
+    let info = server.client.server_info();
+    let mut record = Record::new();
+
+    record.push("name", Value::string(name, span));
+    record.push(
+        "status",
+        Value::string(
+            if server.client.is_offline() {
+                "offline (cached)"
+            } else if server.failure.as_deref() == Some(DISCONNECTED) {
+                "disconnected"
+            } else if server.failure.is_some() {
+                "failed"
+            } else {
+                "connected"
+            },
+            span,
+        ),
+    );
+
+    let (transport, target) = match config.servers.get(name).map(|server| &server.connection) {
+        Some(McpConnectionType::Sse { url, .. }) => ("sse", Value::string(url, span)),
+        Some(McpConnectionType::Command { command, .. }) => {
+            ("command", Value::string(command, span))
+        }
+        None => ("unknown", Value::nothing(span)),
+    };
+    record.push("transport", Value::string(transport, span));
+    record.push("target", target);
+    record.push(
+        "source",
+        config.server_source(name).map_or_else(
+            || Value::nothing(span),
+            |source| Value::string(source, span),
+        ),
+    );
+    record.push(
+        "profile",
+        server.profile.as_ref().map_or_else(
+            || Value::nothing(span),
+            |profile| Value::string(profile, span),
+        ),
+    );
+
+    record.push("server", Value::string(&info.server_info.name, span));
+    record.push("version", Value::string(&info.server_info.version, span));
+    record.push(
+        "protocol_version",
+        Value::string(server.client.protocol_version(), span),
+    );
+    record.push("tools", Value::int(count(server.tools.len()), span));
+
+    record
+}
+
+fn count(len: usize) -> i64 {
+    i64::try_from(len).unwrap_or(i64::MAX)
+}
+
+fn unknown_server_error(name: &Spanned<String>) -> ShellError {
+    ShellError::GenericError {
+        error: format!("Unknown MCP server '{}'", name.item),
+        msg: "no server with this name is connected".into(),
+        span: Some(name.span),
+        help: Some("Run `mcp list` to see the connected servers".into()),
+        inner: Vec::new(),
+    }
+}
@@ -0,0 +1,77 @@
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, PipelineData, ShellError, Signature, Spanned, SyntaxShape, Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+
+use super::mcp_restart::{changes_record, restart_server};
+use crate::{engine::get_mcp_client_manager_sync, util::auth};
+
+/// Command to pick up a server's rotated credentials
+#[derive(Clone)]
+pub struct McpAuthRefreshCommand;
+
+impl Command for McpAuthRefreshCommand {
+    fn name(&self) -> &'static str {
+        "mcp auth refresh"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("mcp auth refresh")
+            .category(Category::Custom("mcp".into()))
+            .required("server", SyntaxShape::String, "The server to refresh")
+            .input_output_types(vec![(Type::Nothing, Type::Record(vec![].into()))])
+    }
+
+    fn description(&self) -> &'static str {
+        "Pick up a server's rotated credentials"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "The server's configuration and environment are read again and the server is reconnected, since neither transport can change its credentials on a live connection. The result's `method` says how the credentials were refreshed and `reason` says why, along with any tools that changed, as `mcp restart` reports them. Tool calls made meanwhile wait for the new connection when the server sets `queue_while_reconnecting`.
+
+Set `auth_refresh_interval` on a server (e.g. `\"50m\"`) to do this automatically."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example {
+            description: "Reconnect to github after rotating its token",
+            example: "$env.GITHUB_TOKEN = (open token.txt); mcp auth refresh github",
+            result: None,
+        }]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let name: Spanned<String> = call.req(engine_state, stack, 0)?;
+
+        let reason = get_mcp_client_manager_sync()
+            .config()
+            .servers
+            .get(&name.item)
+            .map(|server| auth::reconnect_reason(&server.connection));
+
+        let changes = restart_server(engine_state, stack, &name)?;
+
+        // Reconnecting may also have picked up tools that changed
+        let Value::Record { val, .. } = changes_record(&name.item, &changes, span) else {
+            unreachable!("changes_record returns a record");
+        };
+        let mut record = val.into_owned();
+        record.insert("method", Value::string("reconnect", span));
+        record.push(
+            "reason",
+            reason.map_or_else(
+                || Value::nothing(span),
+                |reason| Value::string(reason, span),
+            ),
+        );
+        Ok(PipelineData::Value(Value::record(record, span), None))
+    }
+}
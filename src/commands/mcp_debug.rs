@@ -0,0 +1,96 @@
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, IntoPipelineData, PipelineData, Record, ShellError, Signature, Spanned,
+    SyntaxShape, Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+
+use super::utils::{add_output_flag, apply_output_format};
+use crate::util::payload_log;
+
+/// Command to show and adjust how tool call payloads are shown in verbose mode
+#[derive(Clone)]
+pub struct McpDebugCommand;
+
+impl Command for McpDebugCommand {
+    fn name(&self) -> &'static str {
+        "mcp debug"
+    }
+
+    fn signature(&self) -> Signature {
+        add_output_flag(
+            Signature::build("mcp debug")
+                .category(Category::Custom("mcp".into()))
+                .named(
+                    "max-lines",
+                    SyntaxShape::Int,
+                    "Show at most this many lines of each payload (0 shows all)",
+                    None,
+                )
+                .input_output_types(vec![(Type::Nothing, Type::Record(vec![].into()))]),
+        )
+    }
+
+    fn description(&self) -> &'static str {
+        "Show or change how tool call payloads are shown with --verbose"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "With --verbose, each tool call's request and response are written to stderr. Longer payloads show only their first and last lines, 40 by default, each cut to the terminal width; with --log-file, the full payload is written to the log file too. The limit lasts for the rest of the session."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Show shorter payloads",
+                example: "mcp debug --max-lines 10",
+                result: None,
+            },
+            Example {
+                description: "Show every line of each payload",
+                example: "mcp debug --max-lines 0",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+
+        if let Some(max_lines) = call.get_flag::<Spanned<i64>>(engine_state, stack, "max-lines")? {
+            let max_lines =
+                usize::try_from(max_lines.item).map_err(|_| ShellError::GenericError {
+                    error: "Invalid line limit".into(),
+                    msg: "expected zero or a positive number".into(),
+                    span: Some(max_lines.span),
+                    help: None,
+                    inner: Vec::new(),
+                })?;
+            payload_log::set_max_lines(max_lines);
+        }
+
+        let mut record = Record::new();
+        record.push("verbose", Value::bool(payload_log::is_verbose(), span));
+        record.push("log_file", Value::bool(payload_log::has_log_file(), span));
+        record.push(
+            "max_lines",
+            Value::int(
+                i64::try_from(payload_log::max_lines()).unwrap_or(i64::MAX),
+                span,
+            ),
+        );
+
+        apply_output_format(
+            engine_state,
+            stack,
+            call,
+            Value::record(record, span).into_pipeline_data(),
+        )
+    }
+}
@@ -0,0 +1,118 @@
+use nu_protocol::{
+    Category, Example, PipelineData, ShellError, Signature, Type,
+    engine::{Call, Command, EngineState, Stack},
+};
+
+use super::server_targets::{add_target_args, select_targets, summary};
+use crate::{
+    config::DEFAULT_STUCK_AFTER,
+    engine::{BlockOnError, Watchdog, block_on_shared, get_mcp_client_manager_sync},
+};
+
+/// Command to close the connection to one or more servers
+#[derive(Clone)]
+pub struct McpDisconnectCommand;
+
+impl Command for McpDisconnectCommand {
+    fn name(&self) -> &'static str {
+        "mcp disconnect"
+    }
+
+    fn signature(&self) -> Signature {
+        add_target_args(
+            Signature::build("mcp disconnect")
+                .category(Category::Custom("mcp".into()))
+                .input_output_types(vec![(Type::Nothing, Type::Table(vec![].into()))]),
+            "disconnect",
+        )
+    }
+
+    fn description(&self) -> &'static str {
+        "Close the connection to MCP servers"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "Servers are named exactly or by glob patterns such as 'proj-*'; a pattern that matches no server is an error. Without any names, the REPL lists the servers and asks which to disconnect; a script has to pass --all to disconnect every server.
+
+A command server's process is stopped. The server stays in `mcp list` as disconnected, and its tools fail until `mcp restart` connects it again. The result has a row per server saying whether it was disconnected."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Disconnect the servers of one project",
+                example: "mcp disconnect 'proj-*'",
+                result: None,
+            },
+            Example {
+                description: "Disconnect every server from a script",
+                example: "mcp disconnect --all",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let servers = select_targets(engine_state, stack, call, "disconnect")?;
+
+        let results = servers
+            .into_iter()
+            .map(|name| {
+                let result = disconnect_server(engine_state, &name);
+                (name, result)
+            })
+            .collect();
+
+        Ok(PipelineData::Value(summary(results, span), None))
+    }
+}
+
+/// Close a server's connection and mark it disconnected
+fn disconnect_server(engine_state: &EngineState, name: &str) -> Result<String, ShellError> {
+    let manager = get_mcp_client_manager_sync();
+    let Some(server) = manager.get_server(name) else {
+        return Err(ShellError::GenericError {
+            error: format!("Unknown MCP server '{name}'"),
+            msg: "the server was removed".into(),
+            span: None,
+            help: None,
+            inner: Vec::new(),
+        });
+    };
+    if server.failure.is_some() {
+        return Ok("already disconnected".into());
+    }
+    let client = server.client.clone();
+    drop(manager);
+
+    let label = format!("disconnect {name}");
+    let watchdog = Watchdog {
+        label: &label,
+        stuck_after: DEFAULT_STUCK_AFTER,
+    };
+    let disconnect = async move { client.client.disconnect().await };
+
+    let disconnected = match block_on_shared(disconnect, engine_state.signals(), Some(watchdog)) {
+        Ok(()) => Ok(()),
+        Err(BlockOnError::Interrupted) => Err("Interrupted by Ctrl-C".to_string()),
+        Err(BlockOnError::Panicked(message)) => Err(format!("Disconnecting panicked: {message}")),
+    };
+
+    disconnected.map_err(|msg| ShellError::GenericError {
+        error: format!("Failed to disconnect '{name}'"),
+        msg,
+        span: None,
+        help: None,
+        inner: Vec::new(),
+    })?;
+
+    get_mcp_client_manager_sync().mark_disconnected(name);
+    Ok("disconnected".into())
+}
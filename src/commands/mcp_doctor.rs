@@ -0,0 +1,526 @@
+use std::{
+    net::{TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::Duration,
+};
+
+use indexmap::IndexMap;
+use nu_protocol::{
+    Category, Example, PipelineData, Record, ShellError, Signature, Span, Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+use regex::Regex;
+
+use super::utils::{add_output_flag, apply_output_format};
+use crate::{
+    config::{McpConnectionType, McpServerConfig, config_search_paths},
+    engine::get_mcp_client_manager_sync,
+    mcp::{SUPPORTED_PROTOCOL_VERSIONS, program_path},
+    mcp_manager::RegisteredServer,
+    util::{program::check_program, state_dir},
+};
+
+/// How long to wait when checking that a server's URL accepts connections
+const CONNECT_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// `${NAME}` in a server's command, URL or env
+static ENV_REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap());
+
+/// Command to diagnose the configuration and the environment it runs in
+#[derive(Clone)]
+pub struct McpDoctorCommand;
+
+impl Command for McpDoctorCommand {
+    fn name(&self) -> &'static str {
+        "mcp doctor"
+    }
+
+    fn signature(&self) -> Signature {
+        add_output_flag(
+            Signature::build("mcp doctor")
+                .category(Category::Custom("mcp".into()))
+                .input_output_types(vec![(Type::Nothing, Type::Table(vec![].into()))]),
+        )
+    }
+
+    fn description(&self) -> &'static str {
+        "Check the configuration, the servers and the environment for common problems"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "Lists which config files exist, then checks each configured server: that a command's program is on PATH, that a URL accepts connections, and that every ${VAR} it refers to is set. It also checks that ~/.mcp-repl (or $MCP_STATE_DIR) is writable for history and cached results, reports the protocol version each connected server negotiated, and lists the problems found while registering tools. Each row has a check, a status of ok, warn or fail, and a detail; the number of failures and warnings is printed afterwards."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Show only the checks that need attention",
+                example: "mcp doctor | where status != ok",
+                result: None,
+            },
+            Example {
+                description: "Count the failures from a script",
+                example: "mcp doctor --output json | from json | where status == fail | length",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let manager = get_mcp_client_manager_sync();
+        let cwd = engine_state.cwd(Some(stack))?.into_std_path_buf();
+
+        let mut checks = config_files(&config_search_paths());
+        for (name, server) in &manager.config().servers {
+            checks.extend(server_entry(name, server, &cwd));
+        }
+        checks.push(match state_dir::state_dir() {
+            Ok(dir) => writable_dir(&dir),
+            Err(err) => Check::new("state directory", Status::Fail, format!("{err:#}")),
+        });
+        checks.extend(protocol_versions(manager.get_servers()));
+        checks.extend(registration_diagnostics(manager.get_servers()));
+        drop(manager);
+
+        let count = |status| checks.iter().filter(|check| check.status == status).count();
+        let (failed, warned) = (count(Status::Fail), count(Status::Warn));
+        if failed + warned == 0 {
+            crate::success!("All {} checks passed", checks.len());
+        } else {
+            crate::warning!("{} failed, {} warnings", failed, warned);
+        }
+
+        let rows = checks.iter().map(|check| check.to_value(span)).collect();
+        apply_output_format(
+            engine_state,
+            stack,
+            call,
+            PipelineData::Value(Value::list(rows, span), None),
+        )
+    }
+}
+
+/// How a check turned out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+impl Status {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Warn => "warn",
+            Self::Fail => "fail",
+        }
+    }
+}
+
+/// One row of `mcp doctor`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Check {
+    pub check: String,
+    pub status: Status,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(check: impl Into<String>, status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            check: check.into(),
+            status,
+            detail: detail.into(),
+        }
+    }
+
+    fn to_value(&self, span: Span) -> Value {
+        let mut record = Record::new();
+        record.push("check", Value::string(&self.check, span));
+        record.push("status", Value::string(self.status.as_str(), span));
+        record.push("detail", Value::string(&self.detail, span));
+        Value::record(record, span)
+    }
+}
+
+/// Which of the config files exist; it's a warning if none of them do
+#[must_use]
+pub fn config_files(paths: &[PathBuf]) -> Vec<Check> {
+    let mut checks: Vec<Check> = paths
+        .iter()
+        .map(|path| {
+            let detail = if path.is_file() { "found" } else { "not found" };
+            Check::new(
+                format!("config file {}", path.display()),
+                Status::Ok,
+                detail,
+            )
+        })
+        .collect();
+
+    if !paths.iter().any(|path| path.is_file()) {
+        checks.push(Check::new(
+            "config files",
+            Status::Warn,
+            "no config file was found; run `mcp-repl init` to create one",
+        ));
+    }
+
+    checks
+}
+
+/// Whether a server could be started: its program or URL, and the
+/// variables it refers to
+#[must_use]
+pub fn server_entry(name: &str, server: &McpServerConfig, cwd: &Path) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    match &server.connection {
+        McpConnectionType::Command { command, env, .. } => {
+            let env = env.clone().unwrap_or_default();
+            checks.push(program(name, command, &env, cwd, server.skip_path_check));
+            checks.extend(env_references(
+                name,
+                std::iter::once(command).chain(env.values()),
+                |var| std::env::var_os(var).is_some(),
+            ));
+        }
+        McpConnectionType::Sse { url, .. } => {
+            checks.push(url_reachable(name, url, CONNECT_CHECK_TIMEOUT));
+            checks.extend(env_references(name, [url], |var| {
+                std::env::var_os(var).is_some()
+            }));
+        }
+    }
+
+    checks
+}
+
+/// Whether a command server's program can be found
+fn program(
+    name: &str,
+    command: &str,
+    env: &IndexMap<String, String>,
+    cwd: &Path,
+    skip_path_check: bool,
+) -> Check {
+    let check = format!("servers.{name} program");
+    let program = match shell_words::split(command) {
+        Ok(words) if !words.is_empty() => words[0].clone(),
+        Ok(_) => return Check::new(check, Status::Fail, "the command is empty"),
+        Err(err) => return Check::new(check, Status::Fail, format!("{err}")),
+    };
+
+    if skip_path_check {
+        return Check::new(check, Status::Ok, format!("{program} (skip_path_check)"));
+    }
+    match check_program(&program, cwd, program_path(env).as_deref()) {
+        Ok(()) => Check::new(check, Status::Ok, program),
+        Err(err) => Check::new(check, Status::Fail, format!("{err:#}")),
+    }
+}
+
+/// Whether each `${VAR}` in `values` is set, by `is_set`
+#[must_use]
+pub fn env_references<'a>(
+    name: &str,
+    values: impl IntoIterator<Item = &'a String>,
+    is_set: impl Fn(&str) -> bool,
+) -> Vec<Check> {
+    let mut seen = Vec::new();
+    for value in values {
+        for reference in ENV_REFERENCE.captures_iter(value) {
+            let var = reference[1].to_string();
+            if !seen.contains(&var) {
+                seen.push(var);
+            }
+        }
+    }
+
+    seen.into_iter()
+        .map(|var| {
+            let check = format!("servers.{name} env ${{{var}}}");
+            if is_set(&var) {
+                Check::new(check, Status::Ok, "set")
+            } else {
+                Check::new(check, Status::Fail, format!("{var} is not set"))
+            }
+        })
+        .collect()
+}
+
+/// Whether the host in `url` accepts a TCP connection within `timeout`
+#[must_use]
+pub fn url_reachable(name: &str, url: &str, timeout: Duration) -> Check {
+    let check = format!("servers.{name} url");
+    let Some(address) = host_and_port(url) else {
+        return Check::new(
+            check,
+            Status::Fail,
+            format!("'{url}' is not an http(s) URL"),
+        );
+    };
+
+    let addrs = match address.to_socket_addrs() {
+        Ok(addrs) => addrs.collect::<Vec<_>>(),
+        Err(err) => {
+            return Check::new(
+                check,
+                Status::Fail,
+                format!("can't resolve {address}: {err}"),
+            );
+        }
+    };
+    let mut last_error = None;
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(_) => {
+                return Check::new(check, Status::Ok, format!("{address} accepts connections"));
+            }
+            Err(err) => last_error = Some(err),
+        }
+    }
+
+    Check::new(
+        check,
+        Status::Fail,
+        last_error.map_or_else(
+            || format!("{address} resolved to no addresses"),
+            |err| format!("can't connect to {address}: {err}"),
+        ),
+    )
+}
+
+/// `host:port` of an http or https URL, with the scheme's default port
+fn host_and_port(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://")?;
+    let default_port = match scheme {
+        "http" => 80,
+        "https" => 443,
+        _ => return None,
+    };
+
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host_port = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    if host_port.is_empty() {
+        return None;
+    }
+
+    let has_port = match host_port.rsplit_once(':') {
+        // An IPv6 address without a port ends in `]`
+        Some((_, port)) => port.parse::<u16>().is_ok() && !host_port.ends_with(']'),
+        None => false,
+    };
+    Some(if has_port {
+        host_port.to_string()
+    } else {
+        format!("{host_port}:{default_port}")
+    })
+}
+
+/// Whether files can be created in `dir`, creating it if needed
+#[must_use]
+pub fn writable_dir(dir: &Path) -> Check {
+    let check = format!("{} writable", dir.display());
+    let probe = dir.join(format!(".doctor-{}", std::process::id()));
+
+    let written = std::fs::create_dir_all(dir)
+        .and_then(|()| std::fs::write(&probe, b""))
+        .and_then(|()| std::fs::remove_file(&probe));
+    match written {
+        Ok(()) => Check::new(check, Status::Ok, "history and cached results can be saved"),
+        Err(err) => Check::new(check, Status::Fail, err.to_string()),
+    }
+}
+
+/// The protocol version each server negotiated; a server that isn't
+/// connected is a warning
+#[must_use]
+pub fn protocol_versions(servers: &IndexMap<String, RegisteredServer>) -> Vec<Check> {
+    servers
+        .iter()
+        .map(|(name, server)| {
+            let check = format!("{name} protocol");
+            if let Some(failure) = &server.failure {
+                return Check::new(check, Status::Warn, format!("not connected: {failure}"));
+            }
+            if server.client.is_offline() {
+                return Check::new(check, Status::Warn, "offline; registered from its snapshot");
+            }
+
+            let version = server.client.protocol_version();
+            if SUPPORTED_PROTOCOL_VERSIONS.contains(&version.as_str()) {
+                Check::new(check, Status::Ok, version)
+            } else {
+                Check::new(
+                    check,
+                    Status::Warn,
+                    format!("{version} is not a supported version"),
+                )
+            }
+        })
+        .collect()
+}
+
+/// The problems found while registering each server's tools
+#[must_use]
+pub fn registration_diagnostics(servers: &IndexMap<String, RegisteredServer>) -> Vec<Check> {
+    servers
+        .iter()
+        .flat_map(|(name, server)| {
+            let skipped = server.skipped.iter().map(move |skipped| {
+                Check::new(
+                    format!("{name}.{} registration", skipped.tool),
+                    Status::Warn,
+                    format!("skipped: {}", skipped.reason),
+                )
+            });
+            let diagnostics = server.diagnostics.iter().map(move |diagnostic| {
+                let detail = match &diagnostic.parameter {
+                    Some(parameter) => format!("{parameter}: {}", diagnostic.problem),
+                    None => diagnostic.problem.clone(),
+                };
+                Check::new(
+                    format!("{name}.{} registration", diagnostic.tool),
+                    Status::Warn,
+                    detail,
+                )
+            });
+            skipped.chain(diagnostics)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("mcp-repl-doctor-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_config_files() {
+        let dir = temp_dir("config");
+        let found = dir.join("config.toml");
+        std::fs::write(&found, "").unwrap();
+        let missing = dir.join("missing.toml");
+
+        let checks = config_files(&[missing.clone(), found]);
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[0].detail, "not found");
+        assert_eq!(checks[1].detail, "found");
+
+        let checks = config_files(&[missing]);
+        assert_eq!(checks.last().unwrap().status, Status::Warn);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_env_references() {
+        let values = [
+            "docker run -e TOKEN=${GITHUB_TOKEN} ${IMAGE}".to_string(),
+            "${GITHUB_TOKEN}".to_string(),
+        ];
+        let checks = env_references("github", &values, |var| var == "IMAGE");
+
+        assert_eq!(
+            checks,
+            vec![
+                Check::new(
+                    "servers.github env ${GITHUB_TOKEN}",
+                    Status::Fail,
+                    "GITHUB_TOKEN is not set"
+                ),
+                Check::new("servers.github env ${IMAGE}", Status::Ok, "set"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_url_reachable() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let check = url_reachable(
+            "local",
+            &format!("http://127.0.0.1:{port}/sse"),
+            CONNECT_CHECK_TIMEOUT,
+        );
+        assert_eq!(check.status, Status::Ok, "{}", check.detail);
+
+        let check = url_reachable("local", "localhost:8080", CONNECT_CHECK_TIMEOUT);
+        assert_eq!(check.status, Status::Fail);
+    }
+
+    #[test]
+    fn test_host_and_port() {
+        assert_eq!(
+            host_and_port("https://example.com/sse").as_deref(),
+            Some("example.com:443")
+        );
+        assert_eq!(
+            host_and_port("http://localhost:8080").as_deref(),
+            Some("localhost:8080")
+        );
+        assert_eq!(
+            host_and_port("http://user:secret@[::1]/sse").as_deref(),
+            Some("[::1]:80")
+        );
+        assert_eq!(host_and_port("ftp://example.com"), None);
+    }
+
+    #[test]
+    fn test_writable_dir() {
+        let dir = temp_dir("writable");
+        assert_eq!(writable_dir(&dir.join("nested")).status, Status::Ok);
+
+        let file = dir.join("file");
+        std::fs::write(&file, "").unwrap();
+        assert_eq!(writable_dir(&file).status, Status::Fail);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_protocol_versions_and_diagnostics() {
+        let client = crate::mcp_manager::tests::mock_client("fs", &["read"]);
+        let mut server = RegisteredServer::new(
+            client,
+            IndexMap::new(),
+            IndexMap::new(),
+            Vec::new(),
+            vec![crate::mcp_manager::SkippedTool {
+                tool: "bad name".into(),
+                reason: "not a valid command name".into(),
+            }],
+        );
+        server.failure = Some("connection refused".into());
+        let servers = IndexMap::from([("fs".to_string(), server)]);
+
+        let versions = protocol_versions(&servers);
+        assert_eq!(versions[0].status, Status::Warn);
+        assert!(versions[0].detail.contains("connection refused"));
+
+        let diagnostics = registration_diagnostics(&servers);
+        assert_eq!(diagnostics[0].check, "fs.bad name registration");
+    }
+}
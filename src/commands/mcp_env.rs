@@ -0,0 +1,202 @@
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, IntoPipelineData, PipelineData, Record, ShellError, Signature, Span,
+    Spanned, SyntaxShape, Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+
+use super::utils::{add_output_flag, apply_output_format};
+use crate::{engine::get_mcp_client_manager_sync, mcp::Launch};
+
+/// What a hidden value is shown as
+const MASK: &str = "********";
+
+/// Command to show how a command server's process was started
+#[derive(Clone)]
+pub struct McpEnvCommand;
+
+impl Command for McpEnvCommand {
+    fn name(&self) -> &'static str {
+        "mcp env"
+    }
+
+    fn signature(&self) -> Signature {
+        add_output_flag(
+            Signature::build("mcp env")
+                .category(Category::Custom("mcp".into()))
+                .required("server", SyntaxShape::String, "The command server to show")
+                .switch(
+                    "show-values",
+                    "Show the variables' values; only for trusted servers",
+                    None,
+                )
+                .input_output_types(vec![(Type::Nothing, Type::Record(vec![].into()))]),
+        )
+    }
+
+    fn description(&self) -> &'static str {
+        "Show the command line, directory and environment a command server was started with"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "This is what was actually computed when the process started: the variables inherited under the server's `inherit_env` policy, then its `env` entries, or the `-e KEY=VALUE` arguments added for `docker run -i`. So a variable the config sets but the server can't see shows up here as missing.
+
+Values are hidden unless --show-values is given, which only works for servers marked `trusted`. After `mcp restart`, this shows the new process."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Check whether github was started with a token",
+                example: "mcp env github | get env | columns | where $it =~ TOKEN",
+                result: None,
+            },
+            Example {
+                description: "Show the values too, for a trusted server",
+                example: "mcp env github --show-values",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let server: Spanned<String> = call.req(engine_state, stack, 0)?;
+        let show_values = call.has_flag(engine_state, stack, "show-values")?;
+
+        let manager = get_mcp_client_manager_sync();
+        let Some(registered) = manager.get_server(&server.item) else {
+            return Err(ShellError::GenericError {
+                error: format!("Unknown MCP server '{}'", server.item),
+                msg: "no server with this name is connected".into(),
+                span: Some(server.span),
+                help: Some("Run `mcp list` to see the connected servers".into()),
+                inner: Vec::new(),
+            });
+        };
+
+        if show_values && !manager.config().trust_policy(&server.item).is_trusted() {
+            return Err(ShellError::GenericError {
+                error: format!("'{}' isn't trusted", server.item),
+                msg: "--show-values is only allowed for trusted servers".into(),
+                span: call.get_flag_span(stack, "show-values"),
+                help: Some(format!(
+                    "Set `trusted = true` for '{}' to see the values",
+                    server.item
+                )),
+                inner: Vec::new(),
+            });
+        }
+
+        let Some(launch) = registered.client.launch().cloned() else {
+            return Err(ShellError::GenericError {
+                error: format!("'{}' has no process", server.item),
+                msg: "only command servers are started as a process".into(),
+                span: Some(server.span),
+                help: registered
+                    .client
+                    .is_offline()
+                    .then(|| "The session was started with --offline".into()),
+                inner: Vec::new(),
+            });
+        };
+        drop(manager);
+
+        apply_output_format(
+            engine_state,
+            stack,
+            call,
+            launch_record(&launch, show_values, span).into_pipeline_data(),
+        )
+    }
+}
+
+/// The launch as a record of `argv`, `cwd` and `env`
+fn launch_record(launch: &Launch, show_values: bool, span: Span) -> Value {
+    let mut argv = Vec::with_capacity(launch.argv.len());
+    let mut after_env_flag = false;
+    for arg in &launch.argv {
+        // Docker's `-e KEY=VALUE` pairs carry the same values as `env`
+        let shown = match arg.split_once('=') {
+            Some((key, _)) if after_env_flag && !show_values => format!("{key}={MASK}"),
+            _ => arg.clone(),
+        };
+        after_env_flag = arg == "-e";
+        argv.push(Value::string(shown, span));
+    }
+
+    let env = launch
+        .env
+        .iter()
+        .map(|(key, value)| {
+            let value = if show_values { value.as_str() } else { MASK };
+            (key.clone(), Value::string(value, span))
+        })
+        .collect::<Record>();
+
+    let mut record = Record::new();
+    record.push("argv", Value::list(argv, span));
+    record.push(
+        "cwd",
+        Value::string(launch.cwd.to_string_lossy().into_owned(), span),
+    );
+    record.push("env", Value::record(env, span));
+    Value::record(record, span)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use indexmap::IndexMap;
+    use nu_protocol::record;
+
+    use super::*;
+
+    #[test]
+    fn test_launch_record_masks_values() {
+        let span = Span::test_data();
+        let launch = Launch {
+            argv: ["docker", "run", "-i", "-e", "TOKEN=secret", "image"]
+                .map(String::from)
+                .to_vec(),
+            cwd: PathBuf::from("/work"),
+            env: IndexMap::from([("PATH".to_string(), "/usr/bin".to_string())]),
+        };
+
+        let masked = launch_record(&launch, false, span);
+        assert_eq!(
+            masked.get_data_by_key("argv").unwrap(),
+            Value::list(
+                ["docker", "run", "-i", "-e", "TOKEN=********", "image"]
+                    .map(Value::test_string)
+                    .to_vec(),
+                span
+            )
+        );
+        assert_eq!(
+            masked.get_data_by_key("env").unwrap(),
+            Value::test_record(record! { "PATH" => Value::test_string(MASK) })
+        );
+
+        let shown = launch_record(&launch, true, span);
+        assert_eq!(
+            shown.get_data_by_key("env").unwrap(),
+            Value::test_record(record! { "PATH" => Value::test_string("/usr/bin") })
+        );
+        assert!(
+            shown
+                .get_data_by_key("argv")
+                .unwrap()
+                .as_list()
+                .unwrap()
+                .contains(&Value::test_string("TOKEN=secret"))
+        );
+    }
+}
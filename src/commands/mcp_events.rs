@@ -0,0 +1,197 @@
+use std::{collections::VecDeque, time::Duration};
+
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, ListStream, PipelineData, Record, ShellError, Signals, Signature, Span,
+    SyntaxShape, Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+
+use crate::{
+    engine::get_mcp_client_manager_sync,
+    util::{
+        events::{EventLog, ServerEvent},
+        glob::glob_matches,
+    },
+};
+
+/// How often `mcp events --follow` checks for new notifications
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Command to show the notifications the servers sent
+#[derive(Clone)]
+pub struct McpEventsCommand;
+
+impl Command for McpEventsCommand {
+    fn name(&self) -> &'static str {
+        "mcp events"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("mcp events")
+            .category(Category::Custom("mcp".into()))
+            .switch(
+                "follow",
+                "Keep streaming new notifications until Ctrl-C",
+                Some('f'),
+            )
+            .named(
+                "server",
+                SyntaxShape::String,
+                "Only show notifications from this server",
+                Some('s'),
+            )
+            .named(
+                "method",
+                SyntaxShape::String,
+                "Only show notifications whose method matches this glob (e.g. 'notifications/resources/*')",
+                Some('m'),
+            )
+            .input_output_types(vec![(Type::Nothing, Type::Table(vec![].into()))])
+    }
+
+    fn description(&self) -> &'static str {
+        "Show the notifications received from the MCP servers"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "Notifications such as progress, log messages and list changes are kept in a buffer of `event_buffer` entries (1000 by default) and at most `memory.events.bytes` bytes. When the buffer is full the oldest entries are dropped; with --follow, notifications dropped before they could be shown are reported with a warning. `mcp memory` shows how full the buffer is."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Show the log messages the fs server sent",
+                example: "mcp events --server fs --method notifications/message",
+                result: None,
+            },
+            Example {
+                description: "Watch for resource changes as they arrive",
+                example: "mcp events --follow --method 'notifications/resources/*'",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let follow = call.has_flag(engine_state, stack, "follow")?;
+        let server: Option<String> = call.get_flag(engine_state, stack, "server")?;
+        let method: Option<String> = call.get_flag(engine_state, stack, "method")?;
+
+        let log = get_mcp_client_manager_sync().events().clone();
+
+        let feed = EventFeed {
+            log,
+            next_seq: 0,
+            started: false,
+            pending: VecDeque::new(),
+            server,
+            method,
+            follow,
+            signals: engine_state.signals().clone(),
+            span,
+        };
+
+        if follow {
+            let stream = ListStream::new(feed, span, engine_state.signals().clone());
+            Ok(PipelineData::ListStream(stream, None))
+        } else {
+            Ok(PipelineData::Value(Value::list(feed.collect(), span), None))
+        }
+    }
+}
+
+/// Produces a row per buffered event, then polls for new ones when following
+struct EventFeed {
+    log: EventLog,
+    /// The sequence number of the first event not fetched yet
+    next_seq: u64,
+    /// Whether the buffered events were fetched; later gaps are evictions
+    started: bool,
+    pending: VecDeque<ServerEvent>,
+    server: Option<String>,
+    method: Option<String>,
+    follow: bool,
+    signals: Signals,
+    span: Span,
+}
+
+impl Iterator for EventFeed {
+    type Item = Value;
+
+    fn next(&mut self) -> Option<Value> {
+        let mut fetched = false;
+
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event_row(&event, self.span));
+            }
+
+            if fetched {
+                if !self.follow || self.signals.interrupted() {
+                    return None;
+                }
+                std::thread::sleep(FOLLOW_POLL_INTERVAL);
+            }
+
+            self.fetch();
+            fetched = true;
+        }
+    }
+}
+
+impl EventFeed {
+    fn fetch(&mut self) {
+        let events = self.log.since(self.next_seq);
+
+        if let Some(first) = events.first().filter(|_| self.started) {
+            let missed = first.seq - self.next_seq;
+            if missed > 0 {
+                crate::warning!(
+                    "{} notifications were dropped before they could be shown; raise memory.events to keep more",
+                    missed
+                );
+            }
+        }
+        self.started = true;
+
+        if let Some(last) = events.last() {
+            self.next_seq = last.seq + 1;
+        }
+
+        let matching: Vec<_> = events
+            .into_iter()
+            .filter(|event| self.matches(event))
+            .collect();
+        self.pending.extend(matching);
+    }
+
+    fn matches(&self, event: &ServerEvent) -> bool {
+        self.server
+            .as_ref()
+            .is_none_or(|server| *server == event.server)
+            && self
+                .method
+                .as_ref()
+                .is_none_or(|pattern| glob_matches(pattern, &event.method))
+    }
+}
+
+fn event_row(event: &ServerEvent, span: Span) -> Value {
+    let mut record = Record::new();
+    record.push(
+        "timestamp",
+        Value::date(event.timestamp.fixed_offset(), span),
+    );
+    record.push("server", Value::string(&event.server, span));
+    record.push("method", Value::string(&event.method, span));
+    record.push("params", event.params.clone().with_span(span));
+    Value::record(record, span)
+}
@@ -0,0 +1,208 @@
+use nu_protocol::{
+    Category, Example, IntoPipelineData, PipelineData, ShellError, Signature, Span, Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+    record,
+};
+
+use super::utils::{add_output_flag, apply_output_format};
+use crate::{
+    engine::get_mcp_client_manager_sync,
+    util::{
+        memory::{self, Limit, Store, Usage},
+        status,
+    },
+};
+
+/// Command to show how much the session keeps in memory
+#[derive(Clone)]
+pub struct McpMemoryCommand;
+
+impl Command for McpMemoryCommand {
+    fn name(&self) -> &'static str {
+        "mcp memory"
+    }
+
+    fn signature(&self) -> Signature {
+        add_output_flag(
+            Signature::build("mcp memory")
+                .category(Category::Custom("mcp".into()))
+                .input_output_types(vec![(Type::Nothing, Type::Record(vec![].into()))]),
+        )
+    }
+
+    fn description(&self) -> &'static str {
+        "Show the results, events and messages kept in memory, and their limits"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "`stores` has a row per store: each server's cached tool results, the notifications `mcp events` shows, and the background messages held back at the prompt. `entries` and `bytes` are what it holds now, `bytes` approximately; `evicted` counts the entries dropped to stay within `max_entries` and `max_bytes`, which are set in the `[memory]` table. `resident` is the process's resident memory, where the platform reports it."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example {
+            description: "Find the stores that have dropped entries",
+            example: "mcp memory | get stores | where evicted > 0",
+            result: None,
+        }]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+
+        let manager = get_mcp_client_manager_sync();
+        let config = manager.config();
+        let mut stores: Vec<_> = manager
+            .get_servers()
+            .iter()
+            .map(|(name, server)| StoreRow {
+                store: Store::Results,
+                server: Some(name.clone()),
+                usage: server.client.cache.usage(),
+                limit: config.memory_limit(Store::Results),
+            })
+            .collect();
+        stores.push(StoreRow {
+            store: Store::Events,
+            server: None,
+            usage: manager.events().usage(),
+            limit: config.memory_limit(Store::Events),
+        });
+        stores.push(StoreRow {
+            store: Store::Output,
+            server: None,
+            usage: status::OUTPUT.usage(),
+            limit: config.memory_limit(Store::Output),
+        });
+        drop(manager);
+
+        apply_output_format(
+            engine_state,
+            stack,
+            call,
+            report(&stores, memory::resident_bytes(), span).into_pipeline_data(),
+        )
+    }
+}
+
+/// One store's usage and limit
+struct StoreRow {
+    store: Store,
+    /// The server whose results these are
+    server: Option<String>,
+    usage: Usage,
+    limit: Limit,
+}
+
+fn report(stores: &[StoreRow], resident: Option<u64>, span: Span) -> Value {
+    let size = |bytes: usize| Value::filesize(i64::try_from(bytes).unwrap_or(i64::MAX), span);
+    let count = |count: usize| Value::int(i64::try_from(count).unwrap_or(i64::MAX), span);
+
+    let rows = stores
+        .iter()
+        .map(|row| {
+            Value::record(
+                record! {
+                    "store" => Value::string(row.store.name(), span),
+                    "server" => row.server.as_ref().map_or_else(
+                        || Value::nothing(span),
+                        |server| Value::string(server, span),
+                    ),
+                    "entries" => count(row.usage.entries),
+                    "bytes" => size(row.usage.bytes),
+                    "max_entries" => count(row.limit.entries),
+                    "max_bytes" => size(row.limit.bytes),
+                    "evicted" => Value::int(
+                        i64::try_from(row.usage.evicted).unwrap_or(i64::MAX),
+                        span,
+                    ),
+                },
+                span,
+            )
+        })
+        .collect();
+
+    Value::record(
+        record! {
+            "stores" => Value::list(rows, span),
+            "resident" => resident.map_or_else(
+                || Value::nothing(span),
+                |bytes| Value::filesize(i64::try_from(bytes).unwrap_or(i64::MAX), span),
+            ),
+        },
+        span,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rmcp::model::Content;
+
+    use super::*;
+    use crate::util::{cache::ToolResultCache, events::EventLog};
+
+    #[test]
+    fn test_report_counts_what_each_store_kept() {
+        let limit = Limit {
+            entries: 2,
+            bytes: 1024,
+        };
+        let cache = ToolResultCache::default();
+        for query in ["a", "b", "c"] {
+            cache.insert_within(query.into(), vec![Content::text(query)], limit);
+        }
+        let events = EventLog::new(2);
+        for method in ["a", "b", "c", "d"] {
+            events.push("fs", method, &serde_json::Value::Null);
+        }
+
+        let stores = [
+            StoreRow {
+                store: Store::Results,
+                server: Some("github".into()),
+                usage: cache.usage(),
+                limit,
+            },
+            StoreRow {
+                store: Store::Events,
+                server: None,
+                usage: events.usage(),
+                limit: Limit {
+                    entries: 2,
+                    ..Store::Events.default_limit()
+                },
+            },
+        ];
+        let report = report(&stores, Some(4096), Span::test_data());
+
+        let rows = report
+            .get_data_by_key("stores")
+            .unwrap()
+            .into_list()
+            .unwrap();
+        let column = |row: usize, column: &str| rows[row].get_data_by_key(column).unwrap();
+
+        assert_eq!(column(0, "store"), Value::test_string("results"));
+        assert_eq!(column(0, "server"), Value::test_string("github"));
+        assert_eq!(column(0, "entries"), Value::test_int(2));
+        assert_eq!(
+            column(0, "bytes"),
+            Value::test_filesize(i64::try_from(cache.usage().bytes).unwrap())
+        );
+        assert_eq!(column(0, "evicted"), Value::test_int(1));
+        assert_eq!(column(1, "store"), Value::test_string("events"));
+        assert_eq!(column(1, "server"), Value::test_nothing());
+        assert_eq!(column(1, "entries"), Value::test_int(2));
+        assert_eq!(column(1, "max_entries"), Value::test_int(2));
+        assert_eq!(column(1, "evicted"), Value::test_int(2));
+        assert_eq!(
+            report.get_data_by_key("resident"),
+            Some(Value::test_filesize(4096))
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use nu_engine::CallExt;
+use nu_protocol::{
+    ByteStream, ByteStreamType, Category, Example, IntoPipelineData, PipelineData, Record,
+    ShellError, Signals, Signature, Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+
+use crate::util::{
+    format::{hyperlinks_enabled, link_uris},
+    status::OUTPUT,
+};
+
+/// Command the REPL's prompt hooks use to tell the output broker where it is
+#[derive(Clone)]
+pub struct McpOutputCommand;
+
+impl Command for McpOutputCommand {
+    fn name(&self) -> &'static str {
+        "mcp output"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("mcp output")
+            .category(Category::Custom("mcp".into()))
+            .switch(
+                "prompt-shown",
+                "The prompt is about to be drawn; hold messages back",
+                None,
+            )
+            .switch(
+                "prompt-left",
+                "A command is starting; write the messages held back",
+                None,
+            )
+            .switch(
+                "links",
+                "Make the URIs in rendered output hyperlinks, as `hyperlinks` allows",
+                None,
+            )
+            .input_output_types(vec![
+                (Type::Nothing, Type::Record(vec![].into())),
+                (Type::Nothing, Type::Nothing),
+                (Type::Any, Type::Any),
+            ])
+    }
+
+    fn description(&self) -> &'static str {
+        "Show whether background messages are being held back for the prompt"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "Messages from the background, such as failed credential refreshes, resource watches and reconnects, aren't written while you're typing at the prompt, since they would break up the line being edited. They're held back and written, in order, when the next command starts. The REPL's pre_prompt and pre_execution hooks run this command with --prompt-shown and --prompt-left to keep track, and its display_output hook passes each rendered result through --links, which makes http(s) and file URIs clickable when the `hyperlinks` setting allows."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example {
+            description: "Show how many messages are waiting",
+            example: "mcp output",
+            result: None,
+        }]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+
+        if call.has_flag(engine_state, stack, "links")? {
+            return Ok(if hyperlinks_enabled() {
+                with_links(input, engine_state.signals())
+            } else {
+                input
+            });
+        }
+        if call.has_flag(engine_state, stack, "prompt-shown")? {
+            OUTPUT.prompt_shown();
+            return Ok(PipelineData::empty());
+        }
+        if call.has_flag(engine_state, stack, "prompt-left")? {
+            OUTPUT.prompt_left();
+            return Ok(PipelineData::empty());
+        }
+
+        let mut record = Record::new();
+        record.push("at_prompt", Value::bool(OUTPUT.at_prompt(), span));
+        record.push(
+            "queued",
+            Value::int(i64::try_from(OUTPUT.queued()).unwrap_or(i64::MAX), span),
+        );
+        Ok(Value::record(record, span).into_pipeline_data())
+    }
+}
+
+/// Rendered output with its URIs made hyperlinks
+///
+/// Streams are rewritten a line at a time, so a long table still shows as
+/// it's rendered. Anything but text is passed through.
+fn with_links(input: PipelineData, signals: &Signals) -> PipelineData {
+    match input {
+        PipelineData::Value(Value::String { val, internal_span }, metadata) => {
+            PipelineData::Value(Value::string(link_uris(&val), internal_span), metadata)
+        }
+        PipelineData::ByteStream(stream, metadata) if stream.type_() == ByteStreamType::String => {
+            let span = stream.span();
+            let Some(lines) = stream.lines() else {
+                return PipelineData::empty();
+            };
+            let linked = lines.map(|line| line.map(|line| format!("{}\n", link_uris(&line))));
+            PipelineData::ByteStream(
+                ByteStream::from_result_iter(linked, span, signals.clone(), ByteStreamType::String),
+                metadata,
+            )
+        }
+        input => input,
+    }
+}
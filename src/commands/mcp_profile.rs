@@ -0,0 +1,306 @@
+use indexmap::IndexMap;
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, IntoPipelineData, PipelineData, Record, ShellError, Signature, Span,
+    Spanned, SyntaxShape, Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+
+use super::{
+    mcp_tools::restarted_server,
+    utils::{add_output_flag, apply_output_format},
+};
+use crate::{
+    config::DEFAULT_STUCK_AFTER,
+    engine::{BlockOnError, Watchdog, block_on_shared, get_mcp_client_manager_sync},
+};
+
+/// Parent command for switching between sets of servers
+#[derive(Clone)]
+pub struct McpProfileCommand;
+
+impl Command for McpProfileCommand {
+    fn name(&self) -> &'static str {
+        "mcp profile"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("mcp profile")
+            .category(Category::Custom("mcp".into()))
+            .input_output_types(vec![(Type::Nothing, Type::String)])
+    }
+
+    fn description(&self) -> &'static str {
+        "Commands for switching between profiles of MCP servers"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        r#"You must use one of the following subcommands. Using this command as-is will only produce this help message.
+
+A profile is a named set of servers in the config file, connected along with the shared servers in [servers]:
+
+  [profiles.work.servers.jira]
+  command = "jira-mcp-server"
+
+The profile connected at startup is set with `profile = "work"` in the config file or with --profile."#
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        Ok(Value::string(
+            nu_engine::get_full_help(self, engine_state, stack),
+            call.head,
+        )
+        .into_pipeline_data())
+    }
+}
+
+/// Command to list the profiles in the config file
+#[derive(Clone)]
+pub struct McpProfileListCommand;
+
+impl Command for McpProfileListCommand {
+    fn name(&self) -> &'static str {
+        "mcp profile list"
+    }
+
+    fn signature(&self) -> Signature {
+        add_output_flag(
+            Signature::build("mcp profile list")
+                .category(Category::Custom("mcp".into()))
+                .input_output_types(vec![(Type::Nothing, Type::Table(vec![].into()))]),
+        )
+    }
+
+    fn description(&self) -> &'static str {
+        "List the configured profiles and show which one is active"
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let manager = get_mcp_client_manager_sync();
+        let active = manager.active_profile();
+
+        let rows = manager
+            .base_config()
+            .profiles
+            .iter()
+            .map(|(name, profile)| {
+                let mut record = Record::new();
+                record.push("name", Value::string(name, span));
+                record.push("active", Value::bool(active == Some(name.as_str()), span));
+                record.push(
+                    "servers",
+                    Value::list(
+                        profile
+                            .servers
+                            .keys()
+                            .map(|server| Value::string(server, span))
+                            .collect(),
+                        span,
+                    ),
+                );
+                Value::record(record, span)
+            })
+            .collect();
+        drop(manager);
+
+        apply_output_format(
+            engine_state,
+            stack,
+            call,
+            Value::list(rows, span).into_pipeline_data(),
+        )
+    }
+}
+
+/// Command to replace the active profile's servers with another profile's
+#[derive(Clone)]
+pub struct McpProfileSwitchCommand;
+
+impl Command for McpProfileSwitchCommand {
+    fn name(&self) -> &'static str {
+        "mcp profile switch"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("mcp profile switch")
+            .category(Category::Custom("mcp".into()))
+            .required("profile", SyntaxShape::String, "The profile to switch to")
+            .input_output_types(vec![(Type::Nothing, Type::Record(vec![].into()))])
+    }
+
+    fn description(&self) -> &'static str {
+        "Disconnect the active profile's servers and connect another profile's"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "Shared servers stay connected. The new profile's servers are connected first; then, in one step, the old profile's servers are removed and the new ones added, so no command ever sees half of the old profile. A server that fails to connect is reported under `failed` and the others stay connected.
+
+Commands for the new profile's tools are registered the next time the REPL starts; until then, use `tool call`. Commands for the old profile's tools report that their server is not connected."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example {
+            description: "Switch to the servers for another project",
+            example: "mcp profile switch work",
+            result: None,
+        }]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let profile: Spanned<String> = call.req(engine_state, stack, 0)?;
+
+        let manager = get_mcp_client_manager_sync();
+
+        if manager.is_offline() {
+            return Err(ShellError::GenericError {
+                error: "Can't switch profiles in offline mode".into(),
+                msg: "the session was started with --offline".into(),
+                span: Some(profile.span),
+                help: Some("Restart the REPL with --profile to use another profile".into()),
+                inner: Vec::new(),
+            });
+        }
+
+        let config = manager
+            .base_config()
+            .with_profile(Some(&profile.item))
+            .map_err(|err| ShellError::GenericError {
+                error: format!("Can't switch to profile '{}'", profile.item),
+                msg: format!("{err:#}"),
+                span: Some(profile.span),
+                help: Some("Run `mcp profile list` to see the profiles".into()),
+                inner: Vec::new(),
+            })?;
+        let joining = config.profiles[&profile.item].servers.clone();
+        let events = manager.events().clone();
+        drop(manager);
+
+        let cwd = engine_state.cwd(Some(stack))?.into_std_path_buf();
+
+        let mut connected = IndexMap::new();
+        let mut failed = Vec::new();
+
+        for (name, server_config) in joining {
+            let connect_timeout = match config.connect_timeout(&server_config) {
+                Ok(timeout) => timeout,
+                Err(err) => {
+                    failed.push((name, format!("{err:#}")));
+                    continue;
+                }
+            };
+
+            let label = format!("connect {name}");
+            let watchdog = Watchdog {
+                label: &label,
+                stuck_after: DEFAULT_STUCK_AFTER,
+            };
+
+            let connect = {
+                let (name, events, cwd) = (name.clone(), events.clone(), cwd.clone());
+                async move {
+                    server_config
+                        .to_client(&name, &events, connect_timeout, &cwd)
+                        .await
+                }
+            };
+
+            match block_on_shared(connect, engine_state.signals(), Some(watchdog)) {
+                Ok(Ok(client)) => {
+                    if let Err(err) = client.snapshot().save(&name) {
+                        log::warn!("Failed to cache the schema for '{name}': {err:#}");
+                    }
+                    let server = restarted_server(&name, engine_state, &client, &config);
+                    connected.insert(name, server);
+                }
+                Ok(Err(err)) => failed.push((name, format!("{err:#}"))),
+                Err(BlockOnError::Interrupted) => {
+                    failed.push((name, "Interrupted by Ctrl-C".into()));
+                }
+                Err(BlockOnError::Panicked(message)) => {
+                    failed.push((name, format!("Connecting panicked: {message}")));
+                }
+            }
+        }
+
+        let joined: Vec<String> = connected.keys().cloned().collect();
+        let removed =
+            get_mcp_client_manager_sync().switch_profile(&profile.item, config, connected);
+
+        // The old servers are already out of the manager, so nothing can
+        // reach them while they shut down
+        let left: Vec<String> = removed.iter().map(|(name, _)| name.clone()).collect();
+        let disconnect = async move {
+            for (_, server) in removed {
+                server.client.disconnect().await;
+            }
+        };
+        if block_on_shared(disconnect, engine_state.signals(), None).is_err() {
+            crate::warning!("Some servers of the old profile may not have shut down");
+        }
+
+        for (name, error) in &failed {
+            crate::warning!("Failed to connect '{}': {}", name, error);
+        }
+
+        Ok(PipelineData::Value(
+            switch_record(&profile.item, &left, &joined, &failed, span),
+            None,
+        ))
+    }
+}
+
+fn switch_record(
+    profile: &str,
+    disconnected: &[String],
+    connected: &[String],
+    failed: &[(String, String)],
+    span: Span,
+) -> Value {
+    let names = |names: &[String]| {
+        Value::list(
+            names.iter().map(|name| Value::string(name, span)).collect(),
+            span,
+        )
+    };
+
+    let mut record = Record::new();
+    record.push("profile", Value::string(profile, span));
+    record.push("disconnected", names(disconnected));
+    record.push("connected", names(connected));
+    record.push(
+        "failed",
+        Value::list(
+            failed
+                .iter()
+                .map(|(server, error)| {
+                    let mut row = Record::new();
+                    row.push("server", Value::string(server, span));
+                    row.push("error", Value::string(error, span));
+                    Value::record(row, span)
+                })
+                .collect(),
+            span,
+        ),
+    );
+    Value::record(record, span)
+}
@@ -0,0 +1,234 @@
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, PipelineData, Record, ShellError, Signature, Span, Spanned, Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+
+use super::{
+    mcp_tools::restarted_server,
+    server_targets::{add_target_args, select_targets, summary},
+};
+use crate::{
+    config::DEFAULT_STUCK_AFTER,
+    engine::{BlockOnError, Watchdog, block_on_shared, get_mcp_client_manager_sync},
+    mcp_manager::ToolChanges,
+};
+
+/// Command to restart a server and pick up its new tools
+#[derive(Clone)]
+pub struct McpRestartCommand;
+
+impl Command for McpRestartCommand {
+    fn name(&self) -> &'static str {
+        "mcp restart"
+    }
+
+    fn signature(&self) -> Signature {
+        add_target_args(
+            Signature::build("mcp restart")
+                .category(Category::Custom("mcp".into()))
+                .input_output_types(vec![
+                    (Type::Nothing, Type::Record(vec![].into())),
+                    (Type::Nothing, Type::Table(vec![].into())),
+                ]),
+            "restart",
+        )
+    }
+
+    fn description(&self) -> &'static str {
+        "Restart an MCP server and reload its tools"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "A command server's process is killed and reaped, then started again with the same command and environment. It starts in the REPL's current directory ($env.PWD), and a relative `command` such as `./server` is resolved against it. An SSE server's connection is closed and opened again. The result lists the tools that were added or removed, the ones whose input schema changed, and the ones that stayed the same. Cached results are kept except for tools that changed or were removed; `tool describe` shows each tool's `schema_hash`.
+
+Several servers can be named at once, exactly or by glob patterns such as 'proj-*'. Without any names, the REPL lists the servers and asks which to restart; a script has to pass --all to restart every server. A single exact name gives the record of that server's changes; otherwise the result has a row per server with a count of its changes, or why its restart failed.
+
+Existing `tool` commands use the restarted server right away. Commands for added tools, and new signatures for changed ones, are registered the next time the REPL starts; until then, use `tool call`. If the restart fails, the server is listed as failed and stays disconnected until it is restarted successfully."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Restart a server after rebuilding it",
+                example: "mcp restart myserver",
+                result: None,
+            },
+            Example {
+                description: "Restart the servers of one project",
+                example: "mcp restart 'proj-*'",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let names: Vec<Spanned<String>> = call.rest(engine_state, stack, 0)?;
+        let all = call.has_flag(engine_state, stack, "all")?;
+
+        // One exact name restarts just that server and shows its changes
+        let single = match names.as_slice() {
+            [name] if !all && !name.item.contains(['*', '?']) => Some(name),
+            _ => None,
+        };
+        if let Some(name) = single {
+            let changes = restart_server(engine_state, stack, name)?;
+            return Ok(PipelineData::Value(
+                changes_record(&name.item, &changes, span),
+                None,
+            ));
+        }
+
+        let servers = select_targets(engine_state, stack, call, "restart")?;
+        let results = servers
+            .into_iter()
+            .map(|name| {
+                let name = Spanned { item: name, span };
+                let result = restart_server(engine_state, stack, &name)
+                    .map(|changes| changes_summary(&changes));
+                (name.item, result)
+            })
+            .collect();
+
+        Ok(PipelineData::Value(summary(results, span), None))
+    }
+}
+
+/// Restart a server, register its tools again and report what changed
+///
+/// Also used by `mcp auth refresh`, which reconnects to pick up new
+/// credentials.
+pub fn restart_server(
+    engine_state: &EngineState,
+    stack: &mut Stack,
+    name: &Spanned<String>,
+) -> Result<ToolChanges, ShellError> {
+    let manager = get_mcp_client_manager_sync();
+
+    if manager.is_offline() {
+        return Err(ShellError::GenericError {
+            error: "Can't restart servers in offline mode".into(),
+            msg: "the session was started with --offline".into(),
+            span: Some(name.span),
+            help: Some("Restart the REPL without --offline to connect to servers".into()),
+            inner: Vec::new(),
+        });
+    }
+
+    let (Some(server), Some(server_config)) = (
+        manager.get_server(&name.item),
+        manager.config().servers.get(&name.item),
+    ) else {
+        return Err(ShellError::GenericError {
+            error: format!("Unknown MCP server '{}'", name.item),
+            msg: "no server with this name is connected".into(),
+            span: Some(name.span),
+            help: Some("Run `mcp list` to see the connected servers".into()),
+            inner: Vec::new(),
+        });
+    };
+
+    let client = server.client.clone();
+    let server_config = server_config.clone();
+    let events = manager.events().clone();
+    let connect_timeout = manager
+        .config()
+        .connect_timeout(&server_config)
+        .map_err(|err| ShellError::GenericError {
+            error: format!("Invalid configuration for '{}'", name.item),
+            msg: format!("{err:#}"),
+            span: Some(name.span),
+            help: None,
+            inner: Vec::new(),
+        })?;
+    drop(manager);
+
+    // Relative commands are resolved against the directory the REPL is
+    // in now, which may differ from the one the server first started in
+    let cwd = engine_state.cwd(Some(stack))?.into_std_path_buf();
+
+    let label = format!("restart {}", name.item);
+    let watchdog = Watchdog {
+        label: &label,
+        stuck_after: DEFAULT_STUCK_AFTER,
+    };
+
+    let restart = async move {
+        server_config
+            .restart_client(&client, &events, connect_timeout, &cwd)
+            .await
+    };
+
+    let restarted = match block_on_shared(restart, engine_state.signals(), Some(watchdog)) {
+        Ok(result) => result.map_err(|err| format!("{err:#}")),
+        Err(BlockOnError::Interrupted) => Err("Interrupted by Ctrl-C".to_string()),
+        Err(BlockOnError::Panicked(message)) => Err(format!("The restart panicked: {message}")),
+    };
+
+    let client = match restarted {
+        Ok(client) => client,
+        Err(msg) => {
+            get_mcp_client_manager_sync().mark_failed(&name.item, msg.clone());
+            return Err(ShellError::GenericError {
+                error: format!("Failed to restart '{}'", name.item),
+                msg,
+                span: Some(name.span),
+                help: Some(format!(
+                    "The server is disconnected; run `mcp restart {}` to try again",
+                    name.item
+                )),
+                inner: Vec::new(),
+            });
+        }
+    };
+
+    if let Err(err) = client.snapshot().save(&name.item) {
+        log::warn!("Failed to cache the schema for '{}': {err:#}", name.item);
+    }
+
+    let mut manager = get_mcp_client_manager_sync();
+    let registered = restarted_server(&name.item, engine_state, &client, manager.config());
+    let changes = manager.replace_server(&name.item, registered);
+    drop(manager);
+
+    for tool in changes.stale() {
+        client.cache.invalidate_tool(tool);
+    }
+
+    Ok(changes)
+}
+
+/// How a server's tools changed, in a few words
+fn changes_summary(changes: &ToolChanges) -> String {
+    format!(
+        "{} added, {} removed, {} changed, {} unchanged",
+        changes.added.len(),
+        changes.removed.len(),
+        changes.changed.len(),
+        changes.unchanged.len()
+    )
+}
+
+pub fn changes_record(server: &str, changes: &ToolChanges, span: Span) -> Value {
+    let names = |names: &[String]| {
+        Value::list(
+            names.iter().map(|name| Value::string(name, span)).collect(),
+            span,
+        )
+    };
+
+    let mut record = Record::new();
+    record.push("server", Value::string(server, span));
+    record.push("added", names(&changes.added));
+    record.push("removed", names(&changes.removed));
+    record.push("changed", names(&changes.changed));
+    record.push("unchanged", names(&changes.unchanged));
+    Value::record(record, span)
+}
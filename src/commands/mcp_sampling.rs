@@ -0,0 +1,184 @@
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, IntoPipelineData, PipelineData, Record, ShellError, Signature, Spanned,
+    SyntaxShape, Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+
+use super::utils::{add_output_flag, apply_output_format};
+use crate::{
+    config::SamplingPolicy, engine::get_mcp_client_manager_sync, mcp_manager::McpClientManager,
+    util::sampling,
+};
+
+/// Command to show how each server's sampling requests are handled
+#[derive(Clone)]
+pub struct McpSamplingCommand;
+
+impl Command for McpSamplingCommand {
+    fn name(&self) -> &'static str {
+        "mcp sampling"
+    }
+
+    fn signature(&self) -> Signature {
+        add_output_flag(
+            Signature::build("mcp sampling")
+                .category(Category::Custom("mcp".into()))
+                .input_output_types(vec![(Type::Nothing, Type::Table(vec![].into()))]),
+        )
+    }
+
+    fn description(&self) -> &'static str {
+        "Show how each server's sampling requests are handled"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        r#"Servers are refused sampling unless they're trusted and configured with `sampling = "prompt"`. Each request from such a server is then shown in full and needs consent: `y` for small requests, `yes` typed out for requests estimated at more than `sampling_confirm_tokens` tokens (4096 by default). The question is asked while the command that led to the request is running; a request that arrives while the REPL sits at its prompt is refused.
+
+`mcp sampling allow <server>` consents to every request from a server for the rest of the session, and `mcp sampling revoke <server>` asks again."#
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let manager = get_mcp_client_manager_sync();
+
+        let rows = manager
+            .config()
+            .servers
+            .iter()
+            .map(|(name, server)| {
+                let policy = match server.sampling {
+                    _ if !server.trusted => "refuse (untrusted)",
+                    SamplingPolicy::Refuse => "refuse",
+                    SamplingPolicy::Prompt => "prompt",
+                };
+
+                let mut record = Record::new();
+                record.push("server", Value::string(name, span));
+                record.push("policy", Value::string(policy, span));
+                record.push("allowed", Value::bool(sampling::is_allowed(name), span));
+                Value::record(record, span)
+            })
+            .collect();
+        drop(manager);
+
+        apply_output_format(
+            engine_state,
+            stack,
+            call,
+            Value::list(rows, span).into_pipeline_data(),
+        )
+    }
+}
+
+/// Command to consent to a server's sampling requests for the session
+#[derive(Clone)]
+pub struct McpSamplingAllowCommand;
+
+impl Command for McpSamplingAllowCommand {
+    fn name(&self) -> &'static str {
+        "mcp sampling allow"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("mcp sampling allow")
+            .category(Category::Custom("mcp".into()))
+            .required("server", SyntaxShape::String, "The server to allow")
+            .input_output_types(vec![(Type::Nothing, Type::Nothing)])
+    }
+
+    fn description(&self) -> &'static str {
+        "Allow a server's sampling requests without asking, for the rest of the session"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        r#"Only applies to trusted servers with `sampling = "prompt"`; other servers are still refused."#
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example {
+            description: "Always allow sampling from github",
+            example: "mcp sampling allow github",
+            result: None,
+        }]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let server: Spanned<String> = call.req(engine_state, stack, 0)?;
+        check_server(&get_mcp_client_manager_sync(), &server)?;
+
+        sampling::allow(&server.item);
+        crate::success!("Sampling requests from '{}' are allowed", server.item);
+
+        Ok(PipelineData::empty())
+    }
+}
+
+/// Command to ask again before a server's sampling requests
+#[derive(Clone)]
+pub struct McpSamplingRevokeCommand;
+
+impl Command for McpSamplingRevokeCommand {
+    fn name(&self) -> &'static str {
+        "mcp sampling revoke"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("mcp sampling revoke")
+            .category(Category::Custom("mcp".into()))
+            .required("server", SyntaxShape::String, "The server to revoke")
+            .input_output_types(vec![(Type::Nothing, Type::Nothing)])
+    }
+
+    fn description(&self) -> &'static str {
+        "Ask again before a server's sampling requests"
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let server: Spanned<String> = call.req(engine_state, stack, 0)?;
+        check_server(&get_mcp_client_manager_sync(), &server)?;
+
+        if sampling::revoke(&server.item) {
+            crate::success!(
+                "Sampling requests from '{}' will be asked about",
+                server.item
+            );
+        } else {
+            crate::info!("Sampling requests from '{}' weren't allowed", server.item);
+        }
+
+        Ok(PipelineData::empty())
+    }
+}
+
+fn check_server(manager: &McpClientManager, server: &Spanned<String>) -> Result<(), ShellError> {
+    if manager.config().servers.contains_key(&server.item) {
+        return Ok(());
+    }
+
+    Err(ShellError::GenericError {
+        error: format!("Unknown server '{}'", server.item),
+        msg: "no server with this name is configured".into(),
+        span: Some(server.span),
+        help: Some("Run `mcp sampling` to see the configured servers".into()),
+        inner: Vec::new(),
+    })
+}
@@ -0,0 +1,189 @@
+use std::path::{Path, PathBuf};
+
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, PipelineData, Record, ShellError, Signature, Span, Spanned, SyntaxShape,
+    Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+
+use crate::{config::merge_servers_into_toml, engine::get_mcp_client_manager_sync, util::prompt};
+
+/// Where `mcp save` writes when no `--to` path is given
+const DEFAULT_SAVE_PATH: &str = "mcp-repl.toml";
+
+/// Command to write the session's server entries back into a config file
+#[derive(Clone)]
+pub struct McpSaveCommand;
+
+impl Command for McpSaveCommand {
+    fn name(&self) -> &'static str {
+        "mcp save"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("mcp save")
+            .category(Category::Custom("mcp".into()))
+            .named(
+                "to",
+                SyntaxShape::Filepath,
+                "The config file to write (defaults to ./mcp-repl.toml)",
+                Some('t'),
+            )
+            .switch(
+                "force",
+                "Overwrite an existing file without asking",
+                Some('f'),
+            )
+            .input_output_types(vec![(Type::Nothing, Type::Record(vec![].into()))])
+    }
+
+    fn description(&self) -> &'static str {
+        "Save the session's MCP server entries to a config file"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "Each server registered in this session, whether it came from a config file or the command line, is written under [servers.<name>] with the connection and tool settings it was started with. Nothing the session records on its own is saved, such as consent given with `mcp sampling allow`. Servers from a profile are left out; they are already under [profiles.<name>.servers]. Entries for other servers and unrelated keys already in the file are kept. The saved file is picked up by the normal config loading on the next start."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Save the session's servers to ./mcp-repl.toml",
+                example: "mcp save",
+                result: None,
+            },
+            Example {
+                description: "Save to the user config file without a confirmation prompt",
+                example: "mcp save --to ~/.config/mcp-repl/config.toml --force",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let to: Option<Spanned<String>> = call.get_flag(engine_state, stack, "to")?;
+        let force = call.has_flag(engine_state, stack, "force")?;
+
+        let path_span = to.as_ref().map_or(span, |to| to.span);
+        let cwd = engine_state.cwd(Some(stack))?;
+        let path: PathBuf = nu_path::expand_path_with(
+            to.as_ref().map_or(DEFAULT_SAVE_PATH, |to| to.item.as_str()),
+            cwd,
+            true,
+        );
+
+        let save_error = |error: String, msg: String| ShellError::GenericError {
+            error,
+            msg,
+            span: Some(path_span),
+            help: None,
+            inner: Vec::new(),
+        };
+
+        let existing = if path.exists() {
+            if !force {
+                confirm_overwrite(engine_state, &path, path_span)?;
+            }
+            std::fs::read_to_string(&path).map_err(|err| {
+                save_error(
+                    format!("Failed to read {}", path.display()),
+                    err.to_string(),
+                )
+            })?
+        } else {
+            String::new()
+        };
+
+        let manager = get_mcp_client_manager_sync();
+        let config = manager.config();
+        // Only the session's registered servers are saved, each with the entry
+        // it was started from. Profile servers are already in the config
+        // file, under their profile
+        let servers: Vec<_> = manager
+            .get_servers()
+            .iter()
+            .filter(|(_, server)| server.profile.is_none())
+            .filter_map(|(name, _)| {
+                config
+                    .servers
+                    .get(name)
+                    .map(|server| (name.as_str(), server))
+            })
+            .collect();
+
+        let merged =
+            merge_servers_into_toml(&existing, servers.iter().copied()).map_err(|err| {
+                save_error(
+                    "Failed to update the config file".into(),
+                    format!("{err:#}"),
+                )
+            })?;
+
+        let saved = servers
+            .iter()
+            .map(|(name, _)| Value::string(*name, span))
+            .collect();
+        drop(manager);
+
+        std::fs::write(&path, merged).map_err(|err| {
+            save_error(
+                format!("Failed to write {}", path.display()),
+                err.to_string(),
+            )
+        })?;
+
+        let mut record = Record::new();
+        record.push("path", Value::string(path.display().to_string(), span));
+        record.push("servers", Value::list(saved, span));
+        Ok(PipelineData::Value(Value::record(record, span), None))
+    }
+}
+
+/// Ask before writing over a file that already exists
+fn confirm_overwrite(
+    engine_state: &EngineState,
+    path: &Path,
+    span: Span,
+) -> Result<(), ShellError> {
+    if !engine_state.is_interactive {
+        return Err(ShellError::GenericError {
+            error: format!("{} already exists", path.display()),
+            msg: "cannot ask for confirmation in a non-interactive session".into(),
+            span: Some(span),
+            help: Some("Pass --force to update the existing file".into()),
+            inner: Vec::new(),
+        });
+    }
+
+    let confirmed = prompt::confirm(&format!(
+        "Update the servers in {}? Other settings in the file are kept.",
+        path.display()
+    ))
+    .map_err(|err| ShellError::GenericError {
+        error: "Failed to read confirmation".into(),
+        msg: err.to_string(),
+        span: Some(span),
+        help: None,
+        inner: Vec::new(),
+    })?;
+
+    if confirmed {
+        Ok(())
+    } else {
+        Err(ShellError::GenericError {
+            error: format!("{} was not updated", path.display()),
+            msg: "cancelled by user".into(),
+            span: Some(span),
+            help: None,
+            inner: Vec::new(),
+        })
+    }
+}
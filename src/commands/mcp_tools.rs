@@ -1,30 +1,84 @@
-use std::{borrow::Cow, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
+use base64::Engine;
 use indexmap::IndexMap;
-use log::info;
-use nu_protocol::{PipelineData, ShellError, Span, Value, engine::EngineState};
-use rmcp::model::Tool;
+use log::{debug, info};
+use nu_engine::CallExt;
+use nu_protocol::{
+    DataSource, ListStream, PipelineData, PipelineMetadata, ShellError, Signals, Span, Spanned,
+    Value,
+    ast::CellPath,
+    engine::{Call, EngineState, Stack},
+};
+use rmcp::model::{CallToolResult, Content, Tool};
 use serde_json::Value as JsonValue;
-use tokio::runtime::Runtime;
 
-use super::{tool::RunFn, tool_mapper, utils::ReplClient};
+use super::{
+    resource_templates::{read_resource_contents, register_resource_templates_in_working_set},
+    tool::RunFn,
+    tool_mapper::{
+        self, ALL_PAGES_SWITCH, ARGS_FILE_FLAG, AUDIENCE_FLAG, DEFAULT_MAX_PAGES,
+        FOLLOW_LINKS_SWITCH, GET_FLAG, KEEP_EMPTY_SWITCH, MAX_PAGES_FLAG,
+    },
+    tool_prompt::{self, INTERACTIVE_SWITCH},
+    utils::{OUTPUT_FLAG, ReplClient, apply_output_format, tool_source, with_source},
+};
 use crate::{
-    commands::tool::register_dynamic_tool,
-    mcp_manager::{RegisteredServer, RegisteredTool},
-    util::format::json_to_nu,
+    commands::tool::{register_dynamic_tool, register_server_module, register_server_namespace},
+    config::{EffectiveToolSettings, McpReplConfig, PaginationConfig, ResultFormat},
+    engine::{BlockOnError, RunningCall, Watchdog, block_on_shared, get_mcp_client_manager_sync},
+    mcp_manager::{RegisteredServer, RegisteredTool, SkippedTool, ToolDiagnostic},
+    util::{
+        annotations::{Audience, BlockAnnotations},
+        args_file::with_args_file,
+        cache::ToolResultCache,
+        deprecation::{self, Deprecation},
+        error::{ToolCallError, ToolErrorKind, try_outcome_to_value},
+        eval::eval_closure_source,
+        format::{json_to_nu, summarize_text, text_lines},
+        hash::json_hash,
+        json_numbers,
+        json_stream::JsonArrayElements,
+        last_call::{self, LastCall},
+        last_error, prompt,
+        resource_link::{MAX_FOLLOWED_LINKS, ResourceLink},
+        spill,
+    },
 };
 
+/// How much of a server's instructions to show in each tool's help
+const INSTRUCTIONS_SUMMARY_CHARS: usize = 200;
+
+/// Line-split results with more lines than this are streamed instead of collected
+const STREAM_LINES_AFTER: usize = 10_000;
+
+/// With `format = "json"`, arrays longer than this many bytes of text are
+/// streamed instead of collected
+const STREAM_JSON_BYTES_AFTER: usize = 1024 * 1024;
+
 /// Register all MCP tools as Nushell commands using `StateWorkingSet` directly
 /// This allows us to register tools even from within a command that only has
 /// an immutable reference to `EngineState`
+///
+/// Each tool either registers or is skipped with a reason, so one broken
+/// tool never takes the rest of the server down with it. Disabled tools are
+/// left out of the results.
 pub fn register_mcp_tools_in_working_set(
     name: &str,
     working_set: &mut nu_protocol::engine::StateWorkingSet,
     client: &Arc<ReplClient>,
-) -> IndexMap<String, RegisteredTool> {
+    config: &McpReplConfig,
+    diagnostics: &mut Vec<ToolDiagnostic>,
+) -> Vec<Result<RegisteredTool, SkippedTool>> {
     let tools = client.get_tools();
-    let mut registered_tools = IndexMap::new();
+    let mut results = Vec::new();
+    let mut seen = HashSet::new();
 
     info!(
         "Registering {} MCP tools from client '{}' (raw name: {}) under namespace 'tool'",
@@ -34,25 +88,97 @@ pub fn register_mcp_tools_in_working_set(
     );
 
     for tool in tools {
-        // Extract the raw schema JSON before registration
+        let settings = config
+            .trust_policy(name)
+            .tool_settings(tool, config.tool_settings(name, &tool.name));
+
+        if settings.disabled {
+            info!("Skipping disabled MCP tool: {name}.{}", tool.name);
+            continue;
+        }
+
+        let skip = |reason: String| {
+            Err(SkippedTool {
+                tool: tool.name.to_string(),
+                reason,
+            })
+        };
+
+        if tool.name.trim().is_empty() || tool.name.chars().any(char::is_whitespace) {
+            results.push(skip(format!(
+                "the name {:?} can't be used in a command name",
+                tool.name
+            )));
+            continue;
+        }
+
+        if !seen.insert(tool.name.to_string()) {
+            results.push(skip(
+                "the server listed another tool with the same name".into(),
+            ));
+            continue;
+        }
+
+        // Schema problems are worked around by the mapper, but reported so
+        // server authors can fix them
+        match tool_mapper::diagnose_tool_schema(tool) {
+            Ok(problems) => {
+                diagnostics.extend(problems.into_iter().map(|problem| ToolDiagnostic {
+                    tool: tool.name.to_string(),
+                    parameter: problem.parameter,
+                    problem: problem.problem,
+                    skipped: false,
+                }));
+            }
+            Err(problem) => {
+                results.push(skip(match problem.parameter {
+                    Some(parameter) => format!("parameter '{parameter}': {}", problem.problem),
+                    None => problem.problem,
+                }));
+                continue;
+            }
+        }
+
+        // The schema is hashed now; it's converted to a Nushell value only
+        // if something asks for it
         let schema = tool.input_schema.as_ref();
         let raw_schema = serde_json::to_value(schema).unwrap_or(JsonValue::Null);
 
         // Register the tool as a command
-        register_mcp_tool_in_working_set(name, working_set, tool, client);
-        registered_tools.insert(
-            tool.name.to_string(),
-            RegisteredTool {
-                tool: tool.clone(),
-                namespace: client.name.clone(),
-                name: tool.name.to_string(),
-                raw_schema: json_to_nu(&raw_schema, Some(Span::unknown())),
-                client: client.clone(),
-            },
-        );
+        register_mcp_tool_in_working_set(name, working_set, tool, client, &settings);
+        results.push(Ok(RegisteredTool {
+            tool: tool.clone(),
+            namespace: client.name.clone(),
+            name: tool.name.to_string(),
+            raw_schema: OnceLock::new(),
+            schema_hash: json_hash(&raw_schema),
+            client: client.clone(),
+            settings,
+            deprecation: Deprecation::of(tool),
+        }));
     }
 
-    registered_tools
+    warn_unknown_tool_settings(name, tools, config);
+
+    results
+}
+
+/// Warn about per-tool settings that name a tool the server doesn't have
+fn warn_unknown_tool_settings(name: &str, tools: &[Tool], config: &McpReplConfig) {
+    let Some(server_config) = config.servers.get(name) else {
+        return;
+    };
+
+    for configured in server_config.tools.keys() {
+        if !tools.iter().any(|tool| tool.name == configured.as_str()) {
+            crate::warning!(
+                "Config for servers.{}.tools.{} doesn't match any tool on '{}'",
+                name,
+                configured,
+                name
+            );
+        }
+    }
 }
 
 /// Register all MCP tools as Nushell commands using the standard approach with mutable `EngineState`
@@ -60,6 +186,7 @@ pub fn register_mcp_tools(
     name: &str,
     engine_state: &mut EngineState,
     client: &Arc<ReplClient>,
+    config: &McpReplConfig,
 ) -> Result<RegisteredServer> {
     let tools = client.get_tools();
 
@@ -72,14 +199,77 @@ pub fn register_mcp_tools(
 
     // Use StateWorkingSet internally for consistency
     let mut working_set = nu_protocol::engine::StateWorkingSet::new(engine_state);
-
-    let registered_tools = register_mcp_tools_in_working_set(name, &mut working_set, client);
+    let server = registered_server_in_working_set(name, &mut working_set, client, config);
 
     // Apply the changes to the engine state
     let delta = working_set.render();
     engine_state.merge_delta(delta)?;
 
-    Ok(RegisteredServer::new(client.clone(), registered_tools))
+    Ok(server)
+}
+
+/// Build the manager's record of a server that was restarted from a command
+///
+/// A command can't change the engine state, so the generated commands are
+/// thrown away: existing commands reach the restarted server through the
+/// client they captured, and `tool call` sees the new tool list right away.
+pub fn restarted_server(
+    name: &str,
+    engine_state: &EngineState,
+    client: &Arc<ReplClient>,
+    config: &McpReplConfig,
+) -> RegisteredServer {
+    let mut working_set = nu_protocol::engine::StateWorkingSet::new(engine_state);
+    registered_server_in_working_set(name, &mut working_set, client, config)
+}
+
+fn registered_server_in_working_set(
+    name: &str,
+    working_set: &mut nu_protocol::engine::StateWorkingSet,
+    client: &Arc<ReplClient>,
+    config: &McpReplConfig,
+) -> RegisteredServer {
+    register_server_namespace(working_set, name, client);
+
+    let mut diagnostics = Vec::new();
+    let results =
+        register_mcp_tools_in_working_set(name, working_set, client, config, &mut diagnostics);
+    let registered_templates =
+        register_resource_templates_in_working_set(name, working_set, client, config);
+
+    let total = results.len();
+    let mut registered_tools = IndexMap::new();
+    let mut skipped = Vec::new();
+
+    for result in results {
+        match result {
+            Ok(tool) => {
+                registered_tools.insert(tool.name.clone(), tool);
+            }
+            Err(tool) => skipped.push(tool),
+        }
+    }
+
+    let tool_names: Vec<&str> = registered_tools.keys().map(String::as_str).collect();
+    register_server_module(working_set, name, &tool_names);
+
+    if !skipped.is_empty() {
+        crate::warning!(
+            "Skipped {} of {} tools from '{}'; run `tool diagnostics` or `mcp info {}` for details",
+            skipped.len(),
+            total,
+            name,
+            name
+        );
+    }
+
+    RegisteredServer::new(
+        client.clone(),
+        registered_tools,
+        registered_templates,
+        diagnostics,
+        skipped,
+    )
 }
 
 /// Register a single MCP tool as a Nushell command using `StateWorkingSet`
@@ -89,6 +279,7 @@ fn register_mcp_tool_in_working_set(
     working_set: &mut nu_protocol::engine::StateWorkingSet,
     tool: &Tool,
     client: &Arc<ReplClient>,
+    settings: &EffectiveToolSettings,
 ) {
     // Get tool information
     let tool_name = tool.name.clone();
@@ -100,7 +291,19 @@ fn register_mcp_tool_in_working_set(
     let command_name = format!("tool {namespaced_tool_name}");
 
     // Generate the command signature
-    let signature = tool_mapper::map_tool_to_signature(tool, "tool");
+    let mut signature = tool_mapper::map_tool_to_signature(tool, "tool", settings.max_flags);
+    // `help --find github` lists the server's tools
+    signature.search_terms.insert(0, mcp_namespace.to_string());
+    let signature = tool_mapper::add_call_flags(signature, tool);
+    // Positional parameters may come from --args-file or a prompt instead,
+    // so a missing one is reported by validation rather than the parser
+    let signature = if settings.prompt_missing_args
+        || tool_mapper::call_switch_available(tool, ARGS_FILE_FLAG)
+    {
+        tool_mapper::optional_positionals(signature)
+    } else {
+        signature
+    };
 
     info!("Registering MCP tool as command: {command_name}");
 
@@ -108,13 +311,30 @@ fn register_mcp_tool_in_working_set(
     let description = tool_description;
 
     // Create a run function that will call the tool when the command is invoked
-    let run_fn = create_tool_run_function(tool.clone(), client);
+    let run_fn = create_tool_run_function(tool.clone(), &command_name, client, settings.clone());
 
     // Create a dynamic command using a custom implementation
     // that follows the same pattern as super::tool::register_dynamic_tool
     // but works with StateWorkingSet
 
-    let desc_clone = description.clone().unwrap_or(Cow::Borrowed(""));
+    let mut desc_clone = description.clone().unwrap_or(Cow::Borrowed(""));
+
+    if tool_mapper::declares_no_schema(tool) {
+        desc_clone.to_mut().push_str(
+            "\n\nThis tool declared no input schema; pass its arguments as a record with --args or through the pipeline. They are not validated.",
+        );
+    }
+
+    // Point at the server's own usage notes, if it sent any
+    let extra_description = client
+        .instructions()
+        .map(|instructions| {
+            format!(
+                "Server instructions: {}\nRun `mcp instructions {mcp_namespace}` for the full text.",
+                summarize_text(instructions, INSTRUCTIONS_SUMMARY_CHARS)
+            )
+        })
+        .unwrap_or_default();
 
     // We need to create a Command implementation
     register_dynamic_tool(
@@ -122,139 +342,1902 @@ fn register_mcp_tool_in_working_set(
         &command_name,
         signature,
         desc_clone.to_string(),
+        extra_description,
         run_fn,
     );
 }
 
 /// Create a run function for the MCP tool
-fn create_tool_run_function(tool: Tool, client: &Arc<ReplClient>) -> Box<RunFn> {
+fn create_tool_run_function(
+    tool: Tool,
+    command_name: &str,
+    client: &Arc<ReplClient>,
+    settings: EffectiveToolSettings,
+) -> Box<RunFn> {
     let client = client.clone();
-    Box::new(move |engine_state, stack, call, _input| {
+    let command_name = command_name.to_string();
+    Box::new(move |engine_state, stack, call, input| {
         let span = call.head;
-        let tool_name = tool.name.to_string();
-
-        // Map call arguments to tool parameters
-        let params =
-            match tool_mapper::map_call_args_to_tool_params(engine_state, stack, call, &tool) {
-                Ok(params) => params,
-                Err(err) => {
-                    return Err(ShellError::GenericError {
-                        error: "Failed to parse tool parameters".into(),
-                        msg: err.to_string(),
-                        span: Some(span),
-                        help: Some(
-                            "Check that the provided arguments match the tool's requirements"
-                                .into(),
-                        ),
-                        inner: Vec::new(),
-                    });
-                }
+        let client = current_client(&client, span)?;
+        let try_mode = tool_mapper::call_switch_available(&tool, "try")
+            && call.has_flag(engine_state, stack, "try")?;
+        let lines = tool_mapper::call_switch_available(&tool, "lines")
+            && call.has_flag(engine_state, stack, "lines")?;
+        let full = tool_mapper::call_switch_available(&tool, "full")
+            && call.has_flag(engine_state, stack, "full")?;
+        let keep_empty = tool_mapper::call_switch_available(&tool, KEEP_EMPTY_SWITCH)
+            && call.has_flag(engine_state, stack, KEEP_EMPTY_SWITCH)?;
+        let interactive = settings.prompt_missing_args
+            || (tool_mapper::call_switch_available(&tool, INTERACTIVE_SWITCH)
+                && call.has_flag(engine_state, stack, INTERACTIVE_SWITCH)?);
+
+        // Map call arguments to tool parameters, or take them verbatim when
+        // the tool declared no schema to map them onto
+        let params = if tool_mapper::declares_no_schema(&tool) {
+            tool_mapper::schemaless_tool_params(engine_state, stack, call, input)
+        } else {
+            tool_mapper::map_call_args_to_tool_params(
+                engine_state,
+                stack,
+                call,
+                &tool,
+                settings.max_flags,
+            )
+        }
+        .map_err(|err| ShellError::GenericError {
+            error: "Failed to parse tool parameters".into(),
+            msg: err.to_string(),
+            span: Some(span),
+            help: Some("Check that the provided arguments match the tool's requirements".into()),
+            inner: Vec::new(),
+        });
+
+        let args_file: Option<Spanned<String>> =
+            if tool_mapper::call_switch_available(&tool, ARGS_FILE_FLAG) {
+                call.get_flag(engine_state, stack, ARGS_FILE_FLAG)?
+            } else {
+                None
             };
+        let params = params
+            .and_then(|params| with_args_file(engine_state, stack, args_file.as_ref(), params));
 
-        // Create the arguments JSON value
-        let args_json = serde_json::json!(params);
-
-        // We need to avoid calling block_on within a Tokio runtime, which causes panic
-        // Use a separate thread with its own runtime to execute the async call
-        let client_clone = client.clone();
-        let tool_name_clone = tool_name.clone();
-
-        // Create a channel to receive the result
-        let (sender, receiver) = std::sync::mpsc::channel();
-
-        // Spawn a new thread that will handle the async work
-        std::thread::spawn(move || {
-            // Create a new runtime in this separate thread
-            let rt = match Runtime::new() {
-                Ok(rt) => rt,
-                Err(e) => {
-                    let _ = sender.send(Err(anyhow::anyhow!("Failed to create runtime: {}", e)));
-                    return;
-                }
+        // Prompting needs a terminal; otherwise validation reports what's missing
+        let params = if interactive && engine_state.is_interactive {
+            params.and_then(|params| {
+                tool_prompt::prompt_missing_params(
+                    &command_name,
+                    &tool,
+                    settings.max_flags,
+                    params,
+                    span,
+                )
+            })
+        } else {
+            params
+        };
+
+        let settings = if lines {
+            settings.with_format(ResultFormat::Lines)
+        } else {
+            settings.clone()
+        };
+        let settings = if full {
+            settings.without_result_limit()
+        } else {
+            settings
+        };
+        let settings = if keep_empty {
+            settings.keeping_empty()
+        } else {
+            settings
+        };
+        let all_pages = all_pages(engine_state, stack, call, &tool, &settings)?;
+
+        let follow_links = tool_mapper::call_switch_available(&tool, FOLLOW_LINKS_SWITCH)
+            && call.has_flag(engine_state, stack, FOLLOW_LINKS_SWITCH)?;
+        let audience: Option<Spanned<String>> =
+            if tool_mapper::call_switch_available(&tool, AUDIENCE_FLAG) {
+                call.get_flag(engine_state, stack, AUDIENCE_FLAG)?
+            } else {
+                None
             };
+        let audience = audience
+            .map(|audience| Audience::parse(&audience))
+            .transpose()?
+            .unwrap_or_default();
+        let get: Option<CellPath> = if tool_mapper::call_switch_available(&tool, GET_FLAG) {
+            call.get_flag(engine_state, stack, GET_FLAG)?
+        } else {
+            None
+        };
+
+        let original = OnceLock::new();
+        let options = CallOptions {
+            settings: &settings,
+            try_mode,
+            all_pages,
+            follow_links,
+            raw: false,
+            audience,
+            original: Some(&original),
+        };
+        let args = params.as_ref().ok().cloned().unwrap_or_default();
+        let started = Instant::now();
+        let result = run_tool_call(engine_state, stack, &client, &tool, params, options, span)?;
+        let last = LastCall {
+            server: &client.name,
+            tool: &tool.name,
+            args: &args,
+            duration: started.elapsed(),
+            original: original.get().map(String::as_str),
+        };
+        let result = remember_call(engine_state, stack, &last, try_mode, result, span);
+        let result = follow_get_path(result, get.as_ref(), try_mode, span)?;
+
+        if tool_mapper::call_switch_available(&tool, OUTPUT_FLAG) {
+            apply_output_format(engine_state, stack, call, result)
+        } else {
+            Ok(result)
+        }
+    })
+}
+
+/// Keep a successful call's result in `$mcp_last`, and its details in
+/// `$mcp_last_call`
+///
+/// A result that streams is passed on untouched rather than collected, so
+/// `$mcp_last` is empty after it. A `--try` call only counts if it succeeded,
+/// and then `$mcp_last` is its `data`.
+fn remember_call(
+    engine_state: &EngineState,
+    stack: &mut Stack,
+    call: &LastCall<'_>,
+    try_mode: bool,
+    result: PipelineData,
+    span: Span,
+) -> PipelineData {
+    let PipelineData::Value(value, metadata) = result else {
+        last_call::remember(engine_state, stack, call, Value::nothing(span), span);
+        return result;
+    };
+
+    let remembered = if try_mode {
+        match value.get_data_by_key("ok") {
+            Some(Value::Bool { val: true, .. }) => value.get_data_by_key("data"),
+            _ => None,
+        }
+    } else {
+        Some(value.clone())
+    };
+    if let Some(remembered) = remembered {
+        last_call::remember(engine_state, stack, call, remembered, span);
+    }
+
+    PipelineData::Value(value, metadata)
+}
 
-            // Execute the async call in the new runtime
-            let result = rt.block_on(async {
-                // Pass the debug flag from the ReplClient
-                client_clone.call_tool(&tool_name_clone, args_json).await
-            });
+/// Return the `--get` cell path of a call's result
+///
+/// The path is followed in the converted result, before `--output` renders
+/// it. With `--try`, it's followed in the `data` of a successful result and
+/// a failure is returned as it is. A path the result doesn't have is the
+/// usual cell path error, pointing at the path.
+pub fn follow_get_path(
+    result: PipelineData,
+    path: Option<&CellPath>,
+    try_mode: bool,
+    span: Span,
+) -> Result<PipelineData, ShellError> {
+    let Some(path) = path else {
+        return Ok(result);
+    };
+    let metadata = result.metadata();
+    let value = result.into_value(span)?;
 
-            // Send the result back through the channel
-            let _ = sender.send(result);
+    let value = if try_mode {
+        if !matches!(
+            value.get_data_by_key("ok"),
+            Some(Value::Bool { val: true, .. })
+        ) {
+            return Ok(PipelineData::Value(value, metadata));
+        }
+        let data = value
+            .get_data_by_key("data")
+            .unwrap_or_else(|| Value::nothing(span))
+            .follow_cell_path(&path.members, false)?;
+        let mut record = value.into_record()?;
+        record.insert("data", data);
+        Value::record(record, span)
+    } else {
+        value.follow_cell_path(&path.members, false)?
+    };
+
+    Ok(PipelineData::Value(value, metadata))
+}
+
+/// How many pages to fetch when a generated command was given `--all`
+fn all_pages(
+    engine_state: &EngineState,
+    stack: &mut Stack,
+    call: &Call,
+    tool: &Tool,
+    settings: &EffectiveToolSettings,
+) -> Result<Option<usize>, ShellError> {
+    let flag = |name| tool_mapper::call_switch_available(tool, name);
+
+    if !(flag(ALL_PAGES_SWITCH) && call.has_flag(engine_state, stack, ALL_PAGES_SWITCH)?) {
+        return Ok(None);
+    }
+
+    if settings.pagination.is_none() {
+        return Err(ShellError::GenericError {
+            error: format!("'{}' doesn't say how its results are paginated", tool.name),
+            msg: "--all needs pagination hints for this tool".into(),
+            span: call.get_flag_span(stack, ALL_PAGES_SWITCH),
+            help: Some(format!(
+                "Set `pagination = {{ cursor_param = \"cursor\", cursor_field = \"nextCursor\", items_field = \"items\" }}` in servers.<server>.tools.{}",
+                tool.name
+            )),
+            inner: Vec::new(),
         });
+    }
+
+    let max_pages: Option<Spanned<i64>> = if flag(MAX_PAGES_FLAG) {
+        call.get_flag(engine_state, stack, MAX_PAGES_FLAG)?
+    } else {
+        None
+    };
+
+    match max_pages {
+        Some(max_pages) if max_pages.item < 1 => Err(ShellError::IncorrectValue {
+            msg: "--max-pages must be at least 1".into(),
+            val_span: max_pages.span,
+            call_span: call.head,
+        }),
+        Some(max_pages) => Ok(Some(usize::try_from(max_pages.item).unwrap_or(usize::MAX))),
+        None => Ok(Some(DEFAULT_MAX_PAGES)),
+    }
+}
+
+/// The client the manager has now for a generated command's server
+///
+/// Generated commands stay registered after `mcp profile switch` removes
+/// their server, and a server the new profile connects under the same name
+/// has a new client. A server that left the manager any other way keeps its
+/// captured client, marked disconnected, so the call fails with the
+/// disconnected error.
+fn current_client(captured: &Arc<ReplClient>, span: Span) -> Result<Arc<ReplClient>, ShellError> {
+    let manager = get_mcp_client_manager_sync();
+    if let Some(server) = manager.get_server(&captured.name) {
+        return Ok(server.client.clone());
+    }
+
+    match manager.left_with_profile(&captured.name) {
+        Some(profile) => Err(ShellError::GenericError {
+            error: format!("Server '{}' is not connected", captured.name),
+            msg: format!("it belongs to the profile '{profile}', which is no longer active"),
+            span: Some(span),
+            help: Some(
+                "Run `mcp profile list` to see the profiles, and `mcp profile switch` to change back"
+                    .into(),
+            ),
+            inner: Vec::new(),
+        }),
+        None => {
+            captured.client.mark_disconnected();
+            Ok(captured.clone())
+        }
+    }
+}
+
+/// How `run_tool_call` makes a call and reports its outcome
+#[derive(Clone, Copy)]
+pub struct CallOptions<'a> {
+    /// The tool's settings, with any per-call overrides applied
+    pub settings: &'a EffectiveToolSettings,
+    /// Return every failure as `{ok: false, error}` and successes as
+    /// `{ok: true, data}` instead of raising a `ShellError`
+    pub try_mode: bool,
+    /// Follow the tool's pagination cursor for up to this many pages (`--all`)
+    pub all_pages: Option<usize>,
+    /// Replace resource links in the result with what they point at
+    pub follow_links: bool,
+    /// Return the server's response as it was sent, without converting its
+    /// content, for `tool raw`
+    pub raw: bool,
+    /// Drop the result blocks that aren't meant for this audience (`--audience`)
+    pub audience: Audience,
+    /// Where to keep the text of a blank result, which is returned as
+    /// nothing; see [`format_tool_contents`]
+    pub original: Option<&'a OnceLock<String>>,
+}
 
-        // Receive the result from the channel
-        let result = match receiver.recv() {
-            Ok(result) => result,
-            Err(err) => {
-                return Err(ShellError::GenericError {
-                    error: "Failed to call MCP tool".into(),
-                    msg: format!("Channel error: {err}"),
-                    span: Some(span),
-                    help: Some(format!("Error calling tool: {tool_name}")),
-                    inner: Vec::new(),
-                });
+/// Validate, confirm and make a tool call, shared by generated commands and `tool call`
+///
+/// A failed call, including one made with `--try`, is recorded in
+/// `$env.LAST_MCP_ERROR`, and a successful one clears it.
+pub fn run_tool_call(
+    engine_state: &EngineState,
+    stack: &mut Stack,
+    client: &Arc<ReplClient>,
+    tool: &Tool,
+    params: Result<serde_json::Map<String, JsonValue>, ShellError>,
+    options: CallOptions<'_>,
+    span: Span,
+) -> Result<PipelineData, ShellError> {
+    let try_mode = options.try_mode;
+
+    match make_tool_call(engine_state, stack, client, tool, params, options, span) {
+        Ok(data) => {
+            let failure = match &data {
+                PipelineData::Value(value, _) if try_mode => match value.get_data_by_key("ok") {
+                    Some(Value::Bool { val: false, .. }) => value.get_data_by_key("error"),
+                    _ => None,
+                },
+                _ => None,
+            };
+            match failure {
+                Some(error) => last_error::remember_value(stack, error),
+                None => last_error::clear(engine_state, stack),
+            }
+            Ok(data)
+        }
+        Err((error, err)) => {
+            last_error::remember(stack, &error, span);
+            Err(err)
+        }
+    }
+}
+
+/// Make the call, returning what failed along with the error to raise
+fn make_tool_call(
+    engine_state: &EngineState,
+    stack: &Stack,
+    client: &Arc<ReplClient>,
+    tool: &Tool,
+    params: Result<serde_json::Map<String, JsonValue>, ShellError>,
+    options: CallOptions<'_>,
+    span: Span,
+) -> Result<PipelineData, (ToolCallError, ShellError)> {
+    let CallOptions {
+        settings,
+        try_mode,
+        all_pages,
+        follow_links,
+        raw,
+        audience,
+        original,
+    } = options;
+    let server = client.name.as_str();
+    let tool_name: &str = &tool.name;
+    let source = tool_source(server, tool_name);
+    let signals = engine_state.signals();
+    let fail = |error: ToolCallError| (error.clone(), error.into_shell_error(span));
+    let transport_error = |err: ShellError| {
+        ToolCallError::from_shell_error(ToolErrorKind::Transport, &err, server, tool_name)
+    };
+
+    let prepared = params
+        .map_err(|err| (ToolErrorKind::Validation, err))
+        .and_then(|params| prepare_tool_call(engine_state, client, tool, params, settings, span));
+
+    let PreparedCall { params, .. } = match prepared {
+        Ok(prepared) => prepared,
+        Err((kind, err)) if !try_mode => {
+            return Err((
+                ToolCallError::from_shell_error(kind, &err, server, tool_name),
+                err,
+            ));
+        }
+        Err((kind, err)) => {
+            let error = ToolCallError::from_shell_error(kind, &err, server, tool_name);
+            return Ok(with_source(
+                PipelineData::Value(try_outcome_to_value(Err(error), span), None),
+                &source,
+                None,
+            ));
+        }
+    };
+
+    if raw {
+        // The cache only keeps content, so a raw call always goes to the server
+        let settings = settings.without_cache();
+        return call_tool_classified(client, tool_name, params, &settings, signals)
+            .map(|result| {
+                with_source(
+                    PipelineData::Value(raw_result_to_value(&result, span), None),
+                    &source,
+                    None,
+                )
+            })
+            .map_err(fail);
+    }
+
+    let display =
+        |data| apply_display_hook(engine_state, stack, settings, server, tool_name, data, span);
+    let format = |contents: Vec<Content>| {
+        let contents = audience.filter(contents);
+        if let Some((original, text)) = original
+            .filter(|_| !settings.keep_empty)
+            .zip(blank_text(&contents))
+        {
+            let _ = original.set(text.to_string());
+        }
+        format_tool_contents(contents, settings, span, signals)
+    };
+
+    if !try_mode {
+        let data = match all_pages {
+            Some(max_pages) => PipelineData::Value(
+                fetch_all_pages(
+                    client, tool_name, params, settings, max_pages, signals, span,
+                )
+                .map_err(fail)?,
+                None,
+            ),
+            None => {
+                let mut result = call_tool_classified(client, tool_name, params, settings, signals)
+                    .map_err(fail)?;
+                if follow_links {
+                    follow_resource_links(engine_state, client, &mut result.content, settings);
+                }
+                format(result.content).map_err(|err| (transport_error(err.clone()), err))?
             }
         };
+        return display(data)
+            .map(|data| with_source(data, &source, None))
+            .map_err(|err| (transport_error(err.clone()), err));
+    }
 
-        // Process the result
-        match result {
-            Ok(contents) => {
-                // Convert the result to Nushell values
-                let mut values = Vec::new();
+    let data = match all_pages {
+        Some(max_pages) => fetch_all_pages(
+            client, tool_name, params, settings, max_pages, signals, span,
+        )
+        .map(|items| PipelineData::Value(items, None)),
+        None => call_tool_classified(client, tool_name, params, settings, signals)
+            .and_then(|result| check_tool_result(result, server, tool_name))
+            .and_then(|mut contents| {
+                if follow_links {
+                    follow_resource_links(engine_state, client, &mut contents, settings);
+                }
+                format(contents).map_err(transport_error)
+            }),
+    };
+    let outcome = data.and_then(|data| {
+        display(data)
+            .and_then(|data| data.into_value(span))
+            .map_err(transport_error)
+    });
 
-                for content in contents {
-                    // Extract the raw content from the annotated wrapper
-                    let raw_content = &content.raw;
+    Ok(with_source(
+        PipelineData::Value(try_outcome_to_value(outcome, span), None),
+        &source,
+        None,
+    ))
+}
 
-                    match raw_content {
-                        rmcp::model::RawContent::Text(text_content) => {
-                            values.push(Value::string(&text_content.text, span));
-                        }
-                        rmcp::model::RawContent::Image(image_content) => {
-                            values.push(Value::string(
-                                format!(
-                                    "[Image: {} bytes, type: {}]",
-                                    image_content.data.len(),
-                                    image_content.mime_type
-                                ),
-                                span,
-                            ));
-                        }
-                        rmcp::model::RawContent::Resource(resource) => {
-                            // Handle embedded resources
-                            match &resource.resource {
-                                rmcp::model::ResourceContents::TextResourceContents {
-                                    text,
-                                    ..
-                                } => {
-                                    values.push(Value::string(text, span));
-                                }
-                                rmcp::model::ResourceContents::BlobResourceContents { .. } => {
-                                    values
-                                        .push(Value::string("[Resource: Non-text resource]", span));
-                                }
-                            }
+/// A tool result exactly as the server sent it, including `isError` and
+/// each content block's fields
+#[must_use]
+pub fn raw_result_to_value(result: &CallToolResult, span: Span) -> Value {
+    match serde_json::to_value(result) {
+        Ok(json) => json_to_nu(&json, Some(span)),
+        Err(err) => Value::error(
+            ShellError::GenericError {
+                error: "Failed to serialize the tool result".into(),
+                msg: err.to_string(),
+                span: Some(span),
+                help: None,
+                inner: Vec::new(),
+            },
+            span,
+        ),
+    }
+}
+
+/// Read the resources that the `resource_link` blocks of a result point at
+///
+/// Each link then carries what the read returned, or the error if it failed,
+/// so one broken link doesn't fail the call. At most [`MAX_FOLLOWED_LINKS`]
+/// links are followed; the rest are returned as references.
+fn follow_resource_links(
+    engine_state: &EngineState,
+    client: &Arc<ReplClient>,
+    contents: &mut [Content],
+    settings: &EffectiveToolSettings,
+) {
+    let mut followed = 0;
+    let mut skipped = 0;
+
+    for content in contents {
+        let Some(mut link) = ResourceLink::from_content(&content.raw) else {
+            continue;
+        };
+
+        if followed == MAX_FOLLOWED_LINKS {
+            skipped += 1;
+            continue;
+        }
+        followed += 1;
+
+        match read_resource_contents(engine_state, client, &link.uri, settings) {
+            Ok(result) => link.contents = Some(result.contents),
+            Err(err) => link.error = Some(err),
+        }
+        content.raw = link.to_content();
+    }
+
+    if skipped > 0 {
+        crate::warning!(
+            "Followed the first {} resource links; the other {} are returned as references",
+            MAX_FOLLOWED_LINKS,
+            skipped
+        );
+    }
+}
+
+/// Call a paginated tool until it runs out of pages, returning every item
+///
+/// Each page's text is parsed as JSON. Its items are taken from the
+/// configured `items_field`, and the cursor in `cursor_field` is sent back
+/// in `cursor_param` to get the next page.
+fn fetch_all_pages(
+    client: &Arc<ReplClient>,
+    tool_name: &str,
+    mut params: serde_json::Map<String, JsonValue>,
+    settings: &EffectiveToolSettings,
+    max_pages: usize,
+    signals: &Signals,
+    span: Span,
+) -> Result<Value, ToolCallError> {
+    let server = client.name.as_str();
+    let Some(pagination) = &settings.pagination else {
+        return Err(ToolCallError::new(
+            ToolErrorKind::Validation,
+            "--all needs the tool's `pagination` setting",
+            server,
+            tool_name,
+        ));
+    };
+
+    let mut items = Vec::new();
+
+    for page in 1..=max_pages {
+        let result = call_tool_classified(client, tool_name, params.clone(), settings, signals)?;
+        let contents = check_tool_result(result, server, tool_name)?;
+        let (page_items, cursor) = read_page(&contents, pagination).map_err(|problem| {
+            ToolCallError::new(
+                ToolErrorKind::Tool,
+                format!("page {page}: {problem}"),
+                server,
+                tool_name,
+            )
+        })?;
+
+        items.extend(page_items.iter().map(|item| json_to_nu(item, Some(span))));
+        crate::info!(
+            "Fetched page {} of {}.{} ({} items)",
+            page,
+            server,
+            tool_name,
+            items.len()
+        );
+
+        let Some(cursor) = cursor else {
+            return Ok(Value::list(items, span));
+        };
+        params.insert(pagination.cursor_param.clone(), cursor);
+    }
+
+    crate::warning!(
+        "Stopped after {} pages of {}.{}; pass a larger --max-pages to fetch the rest",
+        max_pages,
+        server,
+        tool_name
+    );
+    Ok(Value::list(items, span))
+}
+
+/// The items on one page of results, and the cursor for the next page
+///
+/// A missing, null or empty cursor means this was the last page.
+fn read_page(
+    contents: &[Content],
+    pagination: &PaginationConfig,
+) -> Result<(Vec<JsonValue>, Option<JsonValue>), String> {
+    let text: String = contents
+        .iter()
+        .filter_map(|content| match &content.raw {
+            rmcp::model::RawContent::Text(text) => Some(text.text.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let page: JsonValue =
+        json_numbers::parse(&text).map_err(|err| format!("the result isn't JSON: {err}"))?;
+    let JsonValue::Object(mut page) = page else {
+        return Err("the result isn't a JSON object".into());
+    };
+
+    let Some(JsonValue::Array(items)) = page.remove(&pagination.items_field) else {
+        return Err(format!(
+            "the result has no '{}' list",
+            pagination.items_field
+        ));
+    };
+
+    let cursor = page
+        .remove(&pagination.cursor_field)
+        .filter(|cursor| !cursor.is_null() && cursor.as_str() != Some(""));
+
+    Ok((items, cursor))
+}
+
+/// Pipe a successful result through the tool's `display` closure
+///
+/// The output keeps the result's metadata. A closure that fails to parse or
+/// run doesn't fail the call: the result is returned as it was, with a
+/// warning.
+pub fn apply_display_hook(
+    engine_state: &EngineState,
+    stack: &Stack,
+    settings: &EffectiveToolSettings,
+    server: &str,
+    tool_name: &str,
+    data: PipelineData,
+    span: Span,
+) -> Result<PipelineData, ShellError> {
+    let Some(display) = settings.display.as_deref() else {
+        return Ok(data);
+    };
+
+    // The closure consumes its input, so keep the result to fall back on.
+    // Collecting its output also surfaces errors raised while streaming.
+    let metadata = data.metadata();
+    let value = data.into_value(span)?;
+    let input = PipelineData::Value(value.clone(), metadata.clone());
+
+    // Display hooks come from the config, so they run restricted
+    match eval_closure_source(engine_state, stack, display, input, span, true)
+        .and_then(|output| output.into_value(span))
+    {
+        Ok(output) => Ok(PipelineData::Value(output, metadata)),
+        Err(err) => {
+            crate::warning!(
+                "The display hook for '{}.{}' failed, showing the raw result: {}",
+                server,
+                tool_name,
+                err
+            );
+            Ok(PipelineData::Value(value, metadata))
+        }
+    }
+}
+
+/// Treat a result the server flagged with `isError` as a failed call
+pub fn check_tool_result(
+    result: CallToolResult,
+    server: &str,
+    tool_name: &str,
+) -> Result<Vec<Content>, ToolCallError> {
+    if result.is_error != Some(true) {
+        return Ok(result.content);
+    }
+
+    let message = result
+        .content
+        .iter()
+        .filter_map(|content| match &content.raw {
+            rmcp::model::RawContent::Text(text) => Some(text.text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Err(ToolCallError::new(
+        ToolErrorKind::Tool,
+        if message.is_empty() {
+            "the tool reported an error".to_string()
+        } else {
+            message
+        },
+        server,
+        tool_name,
+    ))
+}
+
+/// A tool call that has passed [`prepare_tool_call`]'s checks
+///
+/// Commands that call a tool outside of [`run_tool_call`] make their calls
+/// through one of these, so none of them can skip a check.
+pub struct PreparedCall {
+    client: Arc<ReplClient>,
+    tool_name: String,
+    params: serde_json::Map<String, JsonValue>,
+    settings: EffectiveToolSettings,
+}
+
+impl PreparedCall {
+    /// The settings the call is made with
+    #[must_use]
+    pub const fn settings(&self) -> &EffectiveToolSettings {
+        &self.settings
+    }
+
+    /// Make the call on the shared runtime, treating a result flagged with
+    /// `isError` as a failure
+    pub fn call(&self, signals: &Signals) -> Result<Vec<Content>, ToolCallError> {
+        call_tool_classified(
+            &self.client,
+            &self.tool_name,
+            self.params.clone(),
+            &self.settings,
+            signals,
+        )
+        .and_then(|result| check_tool_result(result, &self.client.name, &self.tool_name))
+    }
+
+    /// The call as a future, for making several at once
+    ///
+    /// Like [`call`](Self::call), a result flagged with `isError` is a
+    /// failure. Watching for Ctrl-C and stuck calls is left to whatever
+    /// drives the future.
+    pub fn future(
+        &self,
+    ) -> impl Future<Output = Result<Vec<Content>, ToolCallError>> + Send + use<> {
+        let server = self.client.name.clone();
+        let tool_name = self.tool_name.clone();
+        let call = tool_call_future(
+            &self.client,
+            &self.tool_name,
+            self.params.clone(),
+            &self.settings,
+        );
+
+        async move { check_tool_result(call.await?, &server, &tool_name) }
+    }
+}
+
+/// Check a tool call before it's made
+///
+/// The arguments are validated, a deprecated tool is refused when
+/// `deny_deprecated` is set, a call in offline mode is stopped, and the user
+/// is asked to confirm when the settings require it. That includes the
+/// confirmation [`TrustPolicy`](crate::config::TrustPolicy) forces on
+/// destructive tools of untrusted servers. A command that calls the tool
+/// several times prepares it once.
+pub fn prepare_tool_call(
+    engine_state: &EngineState,
+    client: &Arc<ReplClient>,
+    tool: &Tool,
+    params: serde_json::Map<String, JsonValue>,
+    settings: &EffectiveToolSettings,
+    span: Span,
+) -> Result<PreparedCall, (ToolErrorKind, ShellError)> {
+    let server = client.name.as_str();
+    let tool_name: &str = &tool.name;
+
+    tool_mapper::validate_tool_params(tool, &params, span)
+        .map_err(|err| (ToolErrorKind::Validation, err))?;
+    deprecation::check_call(server, tool, settings.deny_deprecated, span)
+        .map_err(|err| (ToolErrorKind::Deprecated, err))?;
+    refuse_offline_call(client, tool_name, &params, span)
+        .map_err(|err| (ToolErrorKind::Offline, err))?;
+    confirm_tool_call(engine_state, server, tool_name, settings, span)
+        .map_err(|err| (ToolErrorKind::Cancelled, err))?;
+
+    Ok(PreparedCall {
+        client: client.clone(),
+        tool_name: tool_name.to_string(),
+        params,
+        settings: settings.clone(),
+    })
+}
+
+/// Stop a call made in offline mode, showing the request that would have been sent
+fn refuse_offline_call(
+    client: &ReplClient,
+    tool_name: &str,
+    params: &serde_json::Map<String, JsonValue>,
+    span: Span,
+) -> Result<(), ShellError> {
+    if !client.is_offline() {
+        return Ok(());
+    }
+
+    let payload = serde_json::json!({
+        "method": "tools/call",
+        "params": { "name": tool_name, "arguments": params },
+    });
+
+    Err(ShellError::GenericError {
+        error: "offline mode: call not executed".into(),
+        msg: format!(
+            "would have sent to '{}':\n{}",
+            client.name,
+            serde_json::to_string_pretty(&payload).unwrap_or_default()
+        ),
+        span: Some(span),
+        help: Some("Restart the REPL without --offline to call tools".into()),
+        inner: Vec::new(),
+    })
+}
+
+/// Ask the user to confirm a call when the tool's settings require it
+fn confirm_tool_call(
+    engine_state: &EngineState,
+    server_name: &str,
+    tool_name: &str,
+    settings: &EffectiveToolSettings,
+    span: Span,
+) -> Result<(), ShellError> {
+    if !settings.confirm {
+        return Ok(());
+    }
+
+    if !engine_state.is_interactive {
+        return Err(ShellError::GenericError {
+            error: format!("Tool '{server_name}.{tool_name}' requires confirmation"),
+            msg: "cannot ask for confirmation in a non-interactive session".into(),
+            span: Some(span),
+            help: Some(format!(
+                "Remove `confirm = true` from servers.{server_name}.tools.{tool_name} to allow unattended calls; destructive tools on a server without `trusted = true` always ask"
+            )),
+            inner: Vec::new(),
+        });
+    }
+
+    let confirmed =
+        prompt::confirm(&format!("Call tool '{server_name}.{tool_name}'?")).map_err(|err| {
+            ShellError::GenericError {
+                error: "Failed to read confirmation".into(),
+                msg: err.to_string(),
+                span: Some(span),
+                help: None,
+                inner: Vec::new(),
+            }
+        })?;
+
+    if confirmed {
+        Ok(())
+    } else {
+        Err(ShellError::GenericError {
+            error: format!("Call to '{server_name}.{tool_name}' was not confirmed"),
+            msg: "cancelled by user".into(),
+            span: Some(span),
+            help: None,
+            inner: Vec::new(),
+        })
+    }
+}
+
+/// Call an MCP tool, classifying any failure by kind
+///
+/// The call honors the tool's resolved settings; see [`tool_call_future`].
+///
+/// The call runs on the shared runtime. Ctrl-C cancels it, a warning is
+/// printed if it runs past `stuck_after` (see [`call_watchdog`]), and a panic
+/// while calling is reported as a transport error rather than hanging the
+/// shell.
+fn call_tool_classified(
+    client: &Arc<ReplClient>,
+    tool_name: &str,
+    params: serde_json::Map<String, JsonValue>,
+    settings: &EffectiveToolSettings,
+    signals: &Signals,
+) -> Result<CallToolResult, ToolCallError> {
+    let label = format!("{}.{tool_name}", client.name);
+    let watchdog = call_watchdog(&label, settings);
+    // `--max-runtime` still names the call when there's no watchdog
+    let _running = watchdog.is_none().then(|| RunningCall::start(&label));
+    let call = tool_call_future(client, tool_name, params, settings);
+
+    match block_on_shared(call, signals, watchdog) {
+        Ok(result) => result,
+        Err(BlockOnError::Interrupted) => Err(ToolCallError::new(
+            ToolErrorKind::Cancelled,
+            "Interrupted by Ctrl-C",
+            &client.name,
+            tool_name,
+        )),
+        Err(BlockOnError::Panicked(message)) => Err(ToolCallError::new(
+            ToolErrorKind::Transport,
+            format!("The tool call panicked: {message}"),
+            &client.name,
+            tool_name,
+        )),
+    }
+}
+
+/// The watchdog for a call to `label`, if the call can get stuck
+///
+/// A call whose timeout ends it before `stuck_after` can't be stuck, so
+/// it's only watched without a timeout or with a longer one.
+fn call_watchdog<'a>(label: &'a str, settings: &EffectiveToolSettings) -> Option<Watchdog<'a>> {
+    (settings.timeout.is_zero() || settings.timeout > settings.stuck_after).then_some(Watchdog {
+        label,
+        stuck_after: settings.stuck_after,
+    })
+}
+
+/// The future that calls an MCP tool, for running several calls at once
+///
+/// Results are served from the cache while they are younger than
+/// `cached_ttl`, each attempt is bounded by `timeout`, and attempts that
+/// failed before the request was sent are retried `retry` times. Calls to a
+/// disconnected or offline server fail without sending anything.
+fn tool_call_future(
+    client: &Arc<ReplClient>,
+    tool_name: &str,
+    params: serde_json::Map<String, JsonValue>,
+    settings: &EffectiveToolSettings,
+) -> impl Future<Output = Result<CallToolResult, ToolCallError>> + Send + use<> {
+    let client = client.clone();
+    let tool_name = tool_name.to_string();
+    let cached_ttl = settings.cached_ttl;
+    let timeout = settings.timeout;
+    let attempts = settings.retry + 1;
+
+    async move {
+        let cache_key = cached_ttl.map(|ttl| (ToolResultCache::key(&tool_name, &params), ttl));
+
+        if let Some((key, ttl)) = &cache_key {
+            if let Some(contents) = client.cache.get(key, *ttl) {
+                debug!("Serving '{tool_name}' from the result cache");
+                return Ok(CallToolResult::success(contents));
+            }
+        }
+
+        if let Some(ago) = client.disconnected_for() {
+            let server = client.name.as_str();
+            return Err(ToolCallError::new(
+                ToolErrorKind::Disconnected,
+                format!(
+                    "server '{server}' is no longer connected (disconnected {} ago); run `mcp restart {server}`",
+                    humantime::format_duration(Duration::from_secs(ago.as_secs()))
+                ),
+                server,
+                &tool_name,
+            ));
+        }
+
+        if client.is_offline() {
+            return Err(ToolCallError::new(
+                ToolErrorKind::Offline,
+                "offline mode: call not executed",
+                client.name.as_str(),
+                &tool_name,
+            ));
+        }
+
+        // Create the arguments JSON value
+        let args_json = JsonValue::Object(params);
+        let mut last_error = None;
+
+        for attempt in 1..=attempts {
+            let request_id = OnceLock::new();
+            let call = client.call_tool(&tool_name, args_json.clone(), &request_id);
+
+            let outcome = if timeout.is_zero() {
+                Ok(call.await)
+            } else {
+                tokio::time::timeout(timeout, call).await
+            };
+
+            let (kind, problem) = match outcome {
+                Ok(Ok(result)) => {
+                    if let Some((key, _)) = cache_key {
+                        if result.is_error != Some(true) {
+                            client.cache.insert(key, result.content.clone());
                         }
                     }
+                    return Ok(result);
                 }
+                Ok(Err(err)) => (ToolErrorKind::Transport, format!("{err:#}")),
+                Err(_) => (
+                    ToolErrorKind::Timeout,
+                    format!("timed out after {}", humantime::format_duration(timeout)),
+                ),
+            };
+
+            // A call that failed before it was sent has no id to report
+            let request_id = request_id.into_inner();
+            // A request that was sent may have been acted on, even if it
+            // failed or timed out, so only one that never left is retried
+            let sent = request_id.is_some();
+            let message = match &request_id {
+                Some(id) => format!(
+                    "request id {id} to server {} failed: {problem}",
+                    client.name
+                ),
+                None => format!("request to server {} failed: {problem}", client.name),
+            };
+            last_error = Some(
+                ToolCallError::new(kind, message, &client.name, &tool_name)
+                    .with_request_id(request_id),
+            );
+
+            if sent {
+                break;
+            }
+            if attempt < attempts {
+                debug!("Retrying '{tool_name}' (attempt {attempt} of {attempts} failed)");
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            ToolCallError::new(
+                ToolErrorKind::Transport,
+                "Tool call was not attempted",
+                &client.name,
+                &tool_name,
+            )
+        }))
+    }
+}
+
+/// Convert the content blocks of a tool result into pipeline data
+///
+/// When there are several blocks and any of them is annotated, each becomes
+/// a `{content, audience, priority}` row.
+pub fn contents_to_pipeline_data(
+    contents: Vec<Content>,
+    max_result_bytes: u64,
+    span: Span,
+) -> PipelineData {
+    let annotations: Vec<Option<BlockAnnotations>> =
+        contents.iter().map(BlockAnnotations::of).collect();
+    let annotated = contents.len() > 1 && annotations.iter().any(Option::is_some);
+
+    // Convert the result to Nushell values, moving the text out of each block
+    let mut values: Vec<Value> = contents
+        .into_iter()
+        .map(|content| {
+            spill_oversized(&content, max_result_bytes, span)
+                .unwrap_or_else(|| content_to_value(content.raw, span))
+        })
+        .collect();
+
+    if annotated {
+        values = values
+            .into_iter()
+            .zip(&annotations)
+            .map(|(value, annotations)| BlockAnnotations::to_row(annotations.as_ref(), value, span))
+            .collect();
+    }
+
+    // Return appropriate data based on number of values
+    if values.is_empty() {
+        PipelineData::Value(Value::nothing(span), None)
+    } else if values.len() == 1 {
+        PipelineData::Value(values.remove(0), None)
+    } else {
+        PipelineData::Value(Value::list(values, span), None)
+    }
+}
+
+/// Convert the content blocks of a tool result using the tool's result format
+///
+/// With [`ResultFormat::Lines`] or [`ResultFormat::Ndjson`], text blocks are
+/// split into one value per line and any other blocks follow the lines.
+/// Results with more than [`STREAM_LINES_AFTER`] lines are streamed.
+///
+/// Blocks larger than the `max_result_bytes` setting are written to a file
+/// and replaced by a record pointing at it.
+///
+/// A result without blocks is nothing, and so is one whose only block is
+/// empty or whitespace-only text, unless `keep_empty` is set; see
+/// [`blank_text`]. Such a result has the [`BLANK_TEXT_CONTENT_TYPE`], and a
+/// call given a [`CallOptions::original`] keeps the text it held there.
+pub fn format_tool_contents(
+    contents: Vec<Content>,
+    settings: &EffectiveToolSettings,
+    span: Span,
+    signals: &Signals,
+) -> Result<PipelineData, ShellError> {
+    let format = settings.format;
+
+    if contents.is_empty() {
+        return Ok(PipelineData::Value(Value::nothing(span), None));
+    }
+    if blank_text(&contents).is_some() && !settings.keep_empty {
+        return Ok(PipelineData::Value(
+            Value::nothing(span),
+            Some(PipelineMetadata {
+                data_source: DataSource::None,
+                content_type: Some(BLANK_TEXT_CONTENT_TYPE.into()),
+            }),
+        ));
+    }
+
+    if format == ResultFormat::Text {
+        return Ok(contents_to_pipeline_data(
+            contents,
+            settings.max_result_bytes,
+            span,
+        ));
+    }
+    if format == ResultFormat::Json {
+        return json_contents_to_pipeline_data(contents, settings, span, signals);
+    }
+
+    let mut lines = Vec::new();
+    let mut others = Vec::new();
+
+    // Each block's text is dropped once it has been split, so a large result
+    // is only held twice one block at a time
+    for content in contents {
+        if let Some(spilled) = spill_oversized(&content, settings.max_result_bytes, span) {
+            others.push(spilled);
+            continue;
+        }
+
+        match content.raw {
+            rmcp::model::RawContent::Text(text_content) => {
+                lines.extend(
+                    text_lines(&text_content.text)
+                        .into_iter()
+                        .map(str::to_string),
+                );
+            }
+            raw => others.push(content_to_value(raw, span)),
+        }
+    }
+
+    let stream = lines.len() > STREAM_LINES_AFTER;
+
+    let values: Box<dyn Iterator<Item = Value> + Send> = match format {
+        ResultFormat::Ndjson => Box::new(
+            lines
+                .into_iter()
+                .enumerate()
+                .filter(|(_, line)| !line.trim().is_empty())
+                .map(move |(index, line)| parse_ndjson_line(&line, index + 1, span)),
+        ),
+        _ => match json_object_lines(&lines, span) {
+            Some(records) => Box::new(records.into_iter()),
+            None => Box::new(lines.into_iter().map(move |line| Value::string(line, span))),
+        },
+    };
+    let values = values.chain(others);
 
-                // Return appropriate data based on number of values
-                if values.is_empty() {
-                    Ok(PipelineData::Value(Value::nothing(span), None))
-                } else if values.len() == 1 {
-                    Ok(PipelineData::Value(values[0].clone(), None))
-                } else {
-                    Ok(PipelineData::Value(Value::list(values, span), None))
+    if stream {
+        return Ok(PipelineData::ListStream(
+            ListStream::new(values, span, signals.clone()),
+            None,
+        ));
+    }
+
+    let values: Vec<Value> = values.collect();
+
+    if let Some(error) = values.iter().find_map(|value| match value {
+        Value::Error { error, .. } => Some(error.as_ref().clone()),
+        _ => None,
+    }) {
+        return Err(error);
+    }
+
+    Ok(PipelineData::Value(Value::list(values, span), None))
+}
+
+/// The media type of the nothing that a blank text result becomes, so
+/// `metadata` tells it apart from a result without blocks
+pub const BLANK_TEXT_CONTENT_TYPE: &str = "text/plain";
+
+/// The text of a result whose only block is empty or whitespace-only text
+///
+/// Servers send these to mean "no results", and as a string they trip up
+/// `from json` and table rendering. Results with several blocks are left
+/// alone.
+fn blank_text(contents: &[Content]) -> Option<&str> {
+    match contents {
+        [content] => match &content.raw {
+            rmcp::model::RawContent::Text(text) if text.text.trim().is_empty() => Some(&text.text),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Convert the content blocks of a result with `format = "json"`
+///
+/// Each text block is parsed as JSON, and kept as text if it isn't JSON.
+/// The elements of an array are parsed one at a time; when the result is a
+/// single array of more than [`STREAM_JSON_BYTES_AFTER`] bytes they're
+/// streamed, so a pipeline like `first 10` stops reading early and the
+/// whole array is never held as values at once.
+fn json_contents_to_pipeline_data(
+    contents: Vec<Content>,
+    settings: &EffectiveToolSettings,
+    span: Span,
+    signals: &Signals,
+) -> Result<PipelineData, ShellError> {
+    let blocks = contents.len();
+    let mut values = Vec::new();
+
+    for content in contents {
+        if let Some(spilled) = spill_oversized(&content, settings.max_result_bytes, span) {
+            values.push(spilled);
+            continue;
+        }
+
+        let rmcp::model::RawContent::Text(text_content) = content.raw else {
+            values.push(content_to_value(content.raw, span));
+            continue;
+        };
+        let text = text_content.text;
+        let stream = blocks == 1 && text.len() > STREAM_JSON_BYTES_AFTER;
+
+        match JsonArrayElements::new(text) {
+            Ok(elements) => {
+                let elements = elements.map(move |element| json_element_to_value(element, span));
+                if stream {
+                    return Ok(PipelineData::ListStream(
+                        ListStream::new(elements, span, signals.clone()),
+                        None,
+                    ));
+                }
+
+                let elements: Vec<Value> = elements.collect();
+                if let Some(Value::Error { error, .. }) = elements.last() {
+                    return Err(error.as_ref().clone());
                 }
+                values.push(Value::list(elements, span));
             }
-            Err(err) => Err(ShellError::GenericError {
-                error: "Tool execution failed".into(),
-                msg: err.to_string(),
-                span: Some(span),
-                help: Some("Check tool parameters and try again".into()),
-                inner: Vec::new(),
+            Err(text) => values.push(match json_numbers::parse(&text) {
+                Ok(json) => json_to_nu(&json, Some(span)),
+                Err(_) => Value::string(text, span),
             }),
         }
+    }
+
+    Ok(match values.len() {
+        0 => PipelineData::Value(Value::nothing(span), None),
+        1 => PipelineData::Value(values.remove(0), None),
+        _ => PipelineData::Value(Value::list(values, span), None),
     })
 }
+
+/// Convert one element of a JSON array, or report where the array is malformed
+fn json_element_to_value(element: Result<JsonValue, String>, span: Span) -> Value {
+    match element {
+        Ok(json) => json_to_nu(&json, Some(span)),
+        Err(msg) => Value::error(
+            ShellError::GenericError {
+                error: "Invalid JSON array in tool result".into(),
+                msg,
+                span: Some(span),
+                help: Some(
+                    "Set `format = \"text\"` for this tool to get the result as text".into(),
+                ),
+                inner: Vec::new(),
+            },
+            span,
+        ),
+    }
+}
+
+/// Parse the lines as records when every non-blank line is a JSON object
+fn json_object_lines(lines: &[String], span: Span) -> Option<Vec<Value>> {
+    if lines.is_empty() {
+        return None;
+    }
+
+    lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| match json_numbers::parse(line) {
+            Ok(json @ JsonValue::Object(_)) => Some(json_to_nu(&json, Some(span))),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Parse a single line of an NDJSON result, numbering lines from 1
+fn parse_ndjson_line(line: &str, number: usize, span: Span) -> Value {
+    match json_numbers::parse(line) {
+        Ok(json) => json_to_nu(&json, Some(span)),
+        Err(err) => Value::error(
+            ShellError::GenericError {
+                error: "Invalid NDJSON in tool result".into(),
+                msg: format!("line {number}: {err}"),
+                span: Some(span),
+                help: Some(
+                    "Set `format = \"lines\"` for this tool to get the lines as text".into(),
+                ),
+                inner: Vec::new(),
+            },
+            span,
+        ),
+    }
+}
+
+/// Write a block larger than `max_result_bytes` to a file, returning the
+/// record that stands in for it
+///
+/// A limit of zero never spills.
+fn spill_oversized(content: &Content, max_result_bytes: u64, span: Span) -> Option<Value> {
+    use rmcp::model::{RawContent, ResourceContents};
+
+    let (data, extension, text) = match &content.raw {
+        RawContent::Text(text_content) => (&text_content.text, "txt", true),
+        RawContent::Image(image) => (
+            &image.data,
+            image.mime_type.rsplit('/').next().unwrap_or("bin"),
+            false,
+        ),
+        RawContent::Resource(resource) => match &resource.resource {
+            ResourceContents::TextResourceContents { text, .. } => (text, "txt", true),
+            ResourceContents::BlobResourceContents { blob, .. } => (blob, "bin", false),
+        },
+    };
+
+    if max_result_bytes == 0 || u64::try_from(data.len()).unwrap_or(u64::MAX) <= max_result_bytes {
+        return None;
+    }
+
+    if text {
+        return Some(spill::spill(data.as_bytes(), extension, true, span));
+    }
+
+    // Binary blocks are saved decoded when they are valid base64
+    Some(
+        match base64::engine::general_purpose::STANDARD.decode(data) {
+            Ok(bytes) => spill::spill(&bytes, extension, false, span),
+            Err(_) => spill::spill(data.as_bytes(), "b64", false, span),
+        },
+    )
+}
+
+/// Convert a single content block into a value
+fn content_to_value(raw: rmcp::model::RawContent, span: Span) -> Value {
+    if let Some(link) = ResourceLink::from_content(&raw) {
+        return link.to_value(span);
+    }
+
+    match raw {
+        rmcp::model::RawContent::Text(text_content) => Value::string(text_content.text, span),
+        rmcp::model::RawContent::Image(image_content) => Value::string(
+            format!(
+                "[Image: {} bytes, type: {}]",
+                image_content.data.len(),
+                image_content.mime_type
+            ),
+            span,
+        ),
+        rmcp::model::RawContent::Resource(resource) => {
+            // Handle embedded resources
+            match resource.resource {
+                rmcp::model::ResourceContents::TextResourceContents { text, .. } => {
+                    Value::string(text, span)
+                }
+                rmcp::model::ResourceContents::BlobResourceContents { .. } => {
+                    Value::string("[Resource: Non-text resource]", span)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(text: &str, format: ResultFormat) -> Result<Value, ShellError> {
+        let span = Span::test_data();
+        let settings = EffectiveToolSettings::default().with_format(format);
+        format_tool_contents(
+            vec![Content::text(text)],
+            &settings,
+            span,
+            &Signals::empty(),
+        )?
+        .into_value(span)
+    }
+
+    #[test]
+    fn test_raw_result_keeps_protocol_fields() {
+        let span = Span::test_data();
+        let result = CallToolResult::error(vec![Content::text(r#"{"parsed": false}"#)]);
+        let value = raw_result_to_value(&result, span);
+
+        assert!(value.get_data_by_key("isError").unwrap().as_bool().unwrap());
+        let block = value.get_data_by_key("content").unwrap().as_list().unwrap()[0].clone();
+        assert_eq!(
+            block.get_data_by_key("type").unwrap().as_str().unwrap(),
+            "text"
+        );
+        // The text is left as it was sent, not parsed as JSON
+        assert_eq!(
+            block.get_data_by_key("text").unwrap().as_str().unwrap(),
+            r#"{"parsed": false}"#
+        );
+    }
+
+    #[test]
+    fn test_follow_get_path() {
+        use nu_protocol::{ast::PathMember, record};
+
+        let span = Span::test_data();
+        let path_span = Span::new(40, 45);
+        let path = |members: &[&str]| CellPath {
+            members: members
+                .iter()
+                .map(|member| PathMember::string((*member).to_string(), false, path_span))
+                .collect(),
+        };
+        let repo = Value::test_record(record! {
+            "name" => Value::test_string("nushell"),
+            "owner" => Value::test_record(record! {"login" => Value::test_string("nushell")}),
+        });
+        let get = |value: &Value, members: &[&str], try_mode: bool| {
+            follow_get_path(
+                PipelineData::Value(value.clone(), None),
+                Some(&path(members)),
+                try_mode,
+                span,
+            )
+            .and_then(|data| data.into_value(span))
+        };
+
+        assert_eq!(
+            get(&repo, &["owner", "login"], false).unwrap(),
+            Value::test_string("nushell")
+        );
+        let err = get(&repo, &["stars"], false).unwrap_err();
+        assert!(
+            matches!(err, ShellError::CantFindColumn { span: Some(span), .. } if span == path_span),
+            "{err:?}"
+        );
+
+        // With --try, the path goes into `data`, and failures pass through
+        let ok = try_outcome_to_value(Ok(repo.clone()), span);
+        assert_eq!(
+            get(&ok, &["name"], true).unwrap(),
+            Value::test_record(record! {
+                "ok" => Value::test_bool(true),
+                "data" => Value::test_string("nushell"),
+            })
+        );
+        let failed = try_outcome_to_value(
+            Err(ToolCallError::new(
+                ToolErrorKind::Tool,
+                "no",
+                "github",
+                "get_repo",
+            )),
+            span,
+        );
+        assert_eq!(get(&failed, &["name"], true).unwrap(), failed);
+
+        let unchanged = follow_get_path(PipelineData::Value(repo.clone(), None), None, false, span)
+            .and_then(|data| data.into_value(span))
+            .unwrap();
+        assert_eq!(unchanged, repo);
+    }
+
+    #[test]
+    fn test_tool_results_record_their_source() {
+        let client = crate::mcp_manager::tests::mock_client("fs", &["read"]);
+        let tool = client.get_tools()[0].clone();
+
+        // Offline calls fail, and `--try` turns the failure into a result
+        let data = run_tool_call(
+            &EngineState::new(),
+            &mut Stack::new(),
+            &client,
+            &tool,
+            Ok(serde_json::Map::new()),
+            CallOptions {
+                settings: &EffectiveToolSettings::default(),
+                try_mode: true,
+                all_pages: None,
+                follow_links: false,
+                raw: false,
+                audience: Audience::All,
+                original: None,
+            },
+            Span::test_data(),
+        )
+        .unwrap();
+
+        let metadata = data.metadata().unwrap();
+        assert!(matches!(
+            &metadata.data_source,
+            nu_protocol::DataSource::FilePath(path) if path.to_str() == Some("mcp://fs/read")
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_last_mcp_error_is_set_and_cleared() {
+        // A server that fails calls to `broken` and answers the others with "ok"
+        let dir = tempfile::TempDir::new().unwrap();
+        let script = dir.path().join("server.sh");
+        std::fs::write(
+            &script,
+            r#"while read -r line; do
+  id=$(printf '%s' "$line" | sed -n 's/.*"id":\([0-9]*\).*/\1/p')
+  case "$line" in
+    *'"method":"initialize"'*) printf '{"jsonrpc":"2.0","id":%s,"result":{"protocolVersion":"2025-03-26","capabilities":{},"serverInfo":{"name":"mock","version":"1.0.0"}}}\n' "$id" ;;
+    *'"name":"broken"'*) printf '{"jsonrpc":"2.0","id":%s,"error":{"code":-32000,"message":"it broke"}}\n' "$id" ;;
+    *'"method":"tools/call"'*) printf '{"jsonrpc":"2.0","id":%s,"result":{"content":[{"type":"text","text":"ok"}]}}\n' "$id" ;;
+  esac
+done
+"#,
+        )
+        .unwrap();
+
+        let connection = crate::config::McpConnectionType::Command {
+            command: format!("sh {}", script.display()),
+            env: None,
+            connect_timeout: None,
+        };
+        let options = crate::mcp::ConnectOptions {
+            server_name: "live".into(),
+            ..crate::mcp::ConnectOptions::default()
+        };
+        let live = crate::engine::shared_runtime()
+            .block_on(crate::mcp::McpClient::connect(connection, &options, false))
+            .unwrap();
+        let live = Arc::new(ReplClient {
+            name: "live".into(),
+            client: live,
+            cache: crate::util::cache::ToolResultCache::default(),
+            _debug: false,
+        });
+        let offline = crate::mcp_manager::tests::mock_client("fs", &["read", "broken"]);
+        let tools = offline.get_tools();
+
+        let engine_state = EngineState::new();
+        let mut stack = Stack::new();
+        let settings = EffectiveToolSettings::default();
+        let call = |stack: &mut Stack, client: &Arc<ReplClient>, tool: &Tool| {
+            let options = CallOptions {
+                settings: &settings,
+                try_mode: false,
+                all_pages: None,
+                follow_links: false,
+                raw: false,
+                audience: Audience::All,
+                original: None,
+            };
+            run_tool_call(
+                &engine_state,
+                stack,
+                client,
+                tool,
+                Ok(serde_json::Map::new()),
+                options,
+                Span::test_data(),
+            )
+            .map(|_| ())
+        };
+
+        let recorded = |stack: &Stack| {
+            stack
+                .get_env_var(&engine_state, last_error::LAST_MCP_ERROR_ENV)
+                .map(|error| {
+                    ["server", "tool", "kind"].map(|name| {
+                        error
+                            .get_data_by_key(name)
+                            .unwrap()
+                            .coerce_into_string()
+                            .unwrap()
+                    })
+                })
+        };
+
+        // Refused before anything is sent, so the error is the REPL's own
+        let Err(ShellError::GenericError { error, .. }) = call(&mut stack, &offline, &tools[0])
+        else {
+            panic!("an offline call should fail");
+        };
+        assert_eq!(error, "offline mode: call not executed");
+        assert_eq!(recorded(&stack).unwrap(), ["fs", "read", "offline"]);
+
+        let Err(ShellError::GenericError { error, msg, .. }) = call(&mut stack, &live, &tools[1])
+        else {
+            panic!("a call to broken should fail");
+        };
+        assert_eq!(error, "[mcp:live] broken failed");
+        assert!(msg.contains("it broke"), "{msg}");
+        assert_eq!(recorded(&stack).unwrap(), ["live", "broken", "transport"]);
+
+        call(&mut stack, &live, &tools[0]).unwrap();
+        assert!(recorded(&stack).is_none());
+
+        // A command that outlives its server's connection says so
+        crate::engine::shared_runtime().block_on(live.disconnect());
+        let Err(ShellError::GenericError { msg, help, .. }) = call(&mut stack, &live, &tools[0])
+        else {
+            panic!("a call after disconnecting should fail");
+        };
+        assert!(
+            msg.starts_with("server 'live' is no longer connected (disconnected "),
+            "{msg}"
+        );
+        assert!(msg.ends_with("ago); run `mcp restart live`"), "{msg}");
+        assert_eq!(
+            help.as_deref(),
+            Some("Run `mcp restart live` to connect it again")
+        );
+        assert_eq!(recorded(&stack).unwrap(), ["live", "read", "disconnected"]);
+    }
+
+    #[test]
+    fn test_server_module_use() {
+        let mut engine_state = crate::commands::builtin::add_shell_command_context(
+            nu_cmd_lang::create_default_context(),
+        );
+        let client = crate::mcp_manager::tests::mock_client("fs", &["read", "write"]);
+        register_mcp_tools("fs", &mut engine_state, &client, &McpReplConfig::default()).unwrap();
+
+        let run = |source: &str| {
+            let mut working_set = nu_protocol::engine::StateWorkingSet::new(&engine_state);
+            let block = nu_parser::parse(&mut working_set, None, source.as_bytes(), false);
+            assert!(working_set.parse_errors.is_empty(), "{source}");
+            let delta = working_set.render();
+            let mut engine_state = engine_state.clone();
+            engine_state.merge_delta(delta).unwrap();
+            nu_engine::eval_block::<nu_protocol::debugger::WithoutDebug>(
+                &engine_state,
+                &mut Stack::new(),
+                &block,
+                PipelineData::empty(),
+            )
+            .and_then(|data| data.into_value(Span::test_data()))
+        };
+
+        // Every form reaches the tool, which refuses the call since the mock
+        // server is offline
+        for source in [
+            "tool fs.read",
+            "use fs *; read",
+            "use fs read; read",
+            "use fs; fs write",
+        ] {
+            let err = run(source).unwrap_err();
+            assert!(format!("{err:?}").contains("offline"), "{source}: {err:?}");
+        }
+    }
+
+    #[test]
+    fn test_read_page() {
+        let pagination = PaginationConfig::default();
+        let page = |text: &str| read_page(&[Content::text(text)], &pagination);
+
+        let (items, cursor) = page(r#"{"items": [1, 2], "nextCursor": "abc"}"#).unwrap();
+        assert_eq!(items, vec![serde_json::json!(1), serde_json::json!(2)]);
+        assert_eq!(cursor, Some(serde_json::json!("abc")));
+
+        for last in [
+            r#"{"items": []}"#,
+            r#"{"items": [], "nextCursor": null}"#,
+            r#"{"items": [], "nextCursor": ""}"#,
+        ] {
+            assert_eq!(page(last).unwrap().1, None, "{last}");
+        }
+
+        assert!(page("not json").is_err());
+        assert!(page(r#"{"results": []}"#).is_err());
+    }
+
+    #[test]
+    fn test_only_blocks_over_the_limit_are_spilled() {
+        let content = Content::text("x".repeat(100));
+
+        assert!(spill_oversized(&content, 100, Span::test_data()).is_none());
+        assert!(spill_oversized(&content, 0, Span::test_data()).is_none());
+    }
+
+    #[test]
+    fn test_annotated_blocks() {
+        let span = Span::test_data();
+        let contents: Vec<Content> = serde_json::from_value(serde_json::json!([
+            {"type": "text", "text": "3 issues", "annotations": {"audience": ["user"], "priority": 1.0}},
+            {"type": "text", "text": "issue ids: 1, 2, 3", "annotations": {"audience": ["assistant"]}},
+            {"type": "text", "text": "fetched in 20ms"},
+        ]))
+        .unwrap();
+
+        let rows = contents_to_pipeline_data(contents.clone(), 0, span)
+            .into_value(span)
+            .unwrap();
+        let rows = rows.as_list().unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            rows[0]
+                .get_data_by_key("content")
+                .unwrap()
+                .as_str()
+                .unwrap(),
+            "3 issues"
+        );
+        assert_eq!(
+            rows[0].get_data_by_key("priority"),
+            Some(Value::float(1.0, span))
+        );
+        assert!(rows[2].get_data_by_key("audience").unwrap().is_nothing());
+
+        // The unannotated block is for everyone, so two are left
+        let user = contents_to_pipeline_data(Audience::User.filter(contents.clone()), 0, span)
+            .into_value(span)
+            .unwrap();
+        assert_eq!(user.as_list().unwrap().len(), 2);
+
+        // A single block left after filtering is returned as it is
+        let annotated = contents[..2].to_vec();
+        let value = contents_to_pipeline_data(Audience::Assistant.filter(annotated), 0, span)
+            .into_value(span)
+            .unwrap();
+        assert_eq!(value.as_str().unwrap(), "issue ids: 1, 2, 3");
+    }
+
+    #[test]
+    fn test_lines_format_splits_text() {
+        let value = format("first\nsecond\n\n", ResultFormat::Lines).unwrap();
+        let lines: Vec<String> = value
+            .into_list()
+            .unwrap()
+            .into_iter()
+            .map(|line| line.into_string().unwrap())
+            .collect();
+
+        assert_eq!(lines, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn test_lines_format_detects_json_objects() {
+        let value = format("{\"a\": 1}\n{\"a\": 2}\n", ResultFormat::Lines).unwrap();
+        let rows = value.into_list().unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|row| row.as_record().is_ok()));
+    }
+
+    #[test]
+    fn test_blank_results_are_nothing() {
+        let span = Span::test_data();
+        let convert = |contents: Vec<Content>, format: ResultFormat, keep_empty: bool| {
+            let settings = EffectiveToolSettings::default().with_format(format);
+            let settings = if keep_empty {
+                settings.keeping_empty()
+            } else {
+                settings
+            };
+            format_tool_contents(contents, &settings, span, &Signals::empty()).unwrap()
+        };
+
+        for format in [ResultFormat::Text, ResultFormat::Lines, ResultFormat::Json] {
+            for text in ["", "  \n\t"] {
+                let data = convert(vec![Content::text(text)], format, false);
+                let content_type = data.metadata().and_then(|metadata| metadata.content_type);
+                assert_eq!(content_type.as_deref(), Some(BLANK_TEXT_CONTENT_TYPE));
+                assert!(data.into_value(span).unwrap().is_nothing(), "{text:?}");
+            }
+
+            // No blocks at all is nothing too, not an empty list
+            let data = convert(Vec::new(), format, false);
+            assert!(data.into_value(span).unwrap().is_nothing());
+        }
+
+        let kept = convert(vec![Content::text("  ")], ResultFormat::Text, true);
+        assert_eq!(kept.into_value(span).unwrap(), Value::test_string("  "));
+
+        // Several blocks are left as they are, blank or not
+        let several = convert(
+            vec![Content::text(""), Content::text("found")],
+            ResultFormat::Text,
+            false,
+        );
+        assert_eq!(
+            several.into_value(span).unwrap(),
+            Value::test_list(vec![Value::test_string(""), Value::test_string("found")])
+        );
+    }
+
+    #[test]
+    fn test_timed_calls_dont_arm_the_watchdog() {
+        let settings = |timeout| EffectiveToolSettings {
+            timeout: Duration::from_secs(timeout),
+            stuck_after: Duration::from_secs(60),
+            ..EffectiveToolSettings::default()
+        };
+
+        assert!(call_watchdog("fs.read", &settings(30)).is_none());
+        assert!(call_watchdog("fs.read", &settings(60)).is_none());
+        assert!(call_watchdog("fs.read", &settings(0)).is_some());
+        assert!(call_watchdog("fs.read", &settings(120)).is_some());
+    }
+
+    #[test]
+    fn test_display_hooks_keep_metadata() {
+        let span = Span::test_data();
+        let engine_state = crate::commands::builtin::add_shell_command_context(
+            nu_cmd_lang::create_default_context(),
+        );
+        let settings = EffectiveToolSettings {
+            display: Some("default 'no results'".into()),
+            ..EffectiveToolSettings::default()
+        };
+
+        let data = format_tool_contents(
+            vec![Content::text("\n")],
+            &settings,
+            span,
+            &Signals::empty(),
+        )
+        .unwrap();
+        let data = apply_display_hook(
+            &engine_state,
+            &Stack::new(),
+            &settings,
+            "fs",
+            "search",
+            data,
+            span,
+        )
+        .unwrap();
+
+        let content_type = data.metadata().and_then(|metadata| metadata.content_type);
+        assert_eq!(content_type.as_deref(), Some(BLANK_TEXT_CONTENT_TYPE));
+        assert_eq!(
+            data.into_value(span).unwrap(),
+            Value::test_string("no results")
+        );
+    }
+
+    #[test]
+    fn test_ndjson_format_reports_bad_lines() {
+        assert!(format("{\"a\": 1}\n[1, 2]\n", ResultFormat::Ndjson).is_ok());
+        assert!(format("{\"a\": 1}\nnot json\n", ResultFormat::Ndjson).is_err());
+    }
+
+    #[test]
+    fn test_json_format() {
+        let value = format(r#"{"a": 1}"#, ResultFormat::Json).unwrap();
+        assert_eq!(value.get_data_by_key("a").unwrap().as_int().unwrap(), 1);
+        assert_eq!(
+            format("not json", ResultFormat::Json)
+                .unwrap()
+                .as_str()
+                .unwrap(),
+            "not json"
+        );
+        assert_eq!(
+            format("[1, 2, 3]", ResultFormat::Json)
+                .unwrap()
+                .as_list()
+                .unwrap()
+                .len(),
+            3
+        );
+        assert!(format("[1, 2", ResultFormat::Json).is_err());
+    }
+
+    #[test]
+    fn test_json_format_streams_large_arrays() {
+        let span = Span::test_data();
+        let text = format!(
+            "[{}]",
+            (0..100_000)
+                .map(|id| format!(r#"{{"id": {id}}}"#))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let settings = EffectiveToolSettings::default().with_format(ResultFormat::Json);
+
+        let data = format_tool_contents(
+            vec![Content::text(text)],
+            &settings.without_result_limit(),
+            span,
+            &Signals::empty(),
+        )
+        .unwrap();
+        assert!(matches!(data, PipelineData::ListStream(..)));
+
+        let first: Vec<Value> = data.into_iter().take(2).collect();
+        assert_eq!(first[1].get_data_by_key("id").unwrap().as_int().unwrap(), 1);
+    }
+}
@@ -3,23 +3,135 @@ use nu_protocol::engine::{EngineState, StateWorkingSet};
 pub mod builtin;
 pub mod help;
 pub mod list_resources;
+pub mod local_tools;
+pub mod mcp;
+pub mod mcp_auth;
+pub mod mcp_debug;
+pub mod mcp_disconnect;
+pub mod mcp_doctor;
+pub mod mcp_env;
+pub mod mcp_events;
+pub mod mcp_memory;
+pub mod mcp_output;
+pub mod mcp_profile;
+pub mod mcp_restart;
+pub mod mcp_sampling;
+pub mod mcp_save;
 pub mod mcp_tools;
+pub mod prompts_render;
+pub mod resource_templates;
+pub mod resources;
+pub mod resources_on_change;
+pub mod schema_example;
+pub mod server_targets;
 pub mod tool;
+pub mod tool_call;
+pub mod tool_completions_dump;
+pub mod tool_copy;
+pub mod tool_describe;
+pub mod tool_diagnostics;
+pub mod tool_expect;
+pub mod tool_export_catalog;
+pub mod tool_grep;
 pub mod tool_mapper;
+pub mod tool_par_call;
+pub mod tool_prompt;
+pub mod tool_raw;
+pub mod tool_signature;
+pub mod tool_try;
+pub mod tool_watch;
 pub mod utils;
 
 use list_resources::ListResourcesCommand;
-use tool::{ToolCommand, ToolListCommand};
+use mcp::{
+    McpCapabilitiesCommand, McpCommand, McpInfoCommand, McpInstructionsCommand, McpListCommand,
+    McpStatsCommand,
+};
+use mcp_auth::McpAuthRefreshCommand;
+use mcp_debug::McpDebugCommand;
+use mcp_disconnect::McpDisconnectCommand;
+use mcp_doctor::McpDoctorCommand;
+use mcp_env::McpEnvCommand;
+use mcp_events::McpEventsCommand;
+use mcp_memory::McpMemoryCommand;
+use mcp_output::McpOutputCommand;
+use mcp_profile::{McpProfileCommand, McpProfileListCommand, McpProfileSwitchCommand};
+use mcp_restart::McpRestartCommand;
+use mcp_sampling::{McpSamplingAllowCommand, McpSamplingCommand, McpSamplingRevokeCommand};
+use mcp_save::McpSaveCommand;
+use prompts_render::PromptsRenderCommand;
+use resource_templates::ResourceTemplatesCommand;
+use resources::{ResourcesCommand, ResourcesReadCommand, ResourcesSearchCommand};
+use resources_on_change::ResourcesOnChangeCommand;
+use tool::{ToolCommand, ToolListCommand, ToolLsCommand};
+use tool_call::ToolCallCommand;
+use tool_completions_dump::ToolCompletionsDumpCommand;
+use tool_copy::ToolCopyCommand;
+use tool_describe::ToolDescribeCommand;
+use tool_diagnostics::ToolDiagnosticsCommand;
+use tool_expect::ToolExpectCommand;
+use tool_export_catalog::ToolExportCatalogCommand;
+use tool_grep::ToolGrepCommand;
+use tool_par_call::ToolParCallCommand;
+use tool_raw::ToolRawCommand;
+use tool_signature::ToolSignatureCommand;
+use tool_try::ToolTryCommand;
+use tool_watch::ToolWatchCommand;
 
 // Register all custom commands
 pub fn register_all(engine_state: &mut EngineState) {
     // Create a working set to register commands
     let mut working_set = StateWorkingSet::new(engine_state);
 
+    // Completers must exist before the commands that refer to them
+    utils::register_output_completer(&mut working_set);
+
     // Register custom MCP commands
     working_set.add_decl(Box::new(ToolCommand {}));
     working_set.add_decl(Box::new(ToolListCommand {}));
+    working_set.add_decl(Box::new(ToolLsCommand {}));
+    working_set.add_decl(Box::new(ToolCallCommand {}));
+    working_set.add_decl(Box::new(ToolCopyCommand {}));
+    working_set.add_decl(Box::new(ToolDescribeCommand {}));
+    working_set.add_decl(Box::new(ToolSignatureCommand {}));
+    working_set.add_decl(Box::new(ToolWatchCommand {}));
+    working_set.add_decl(Box::new(ToolParCallCommand {}));
+    working_set.add_decl(Box::new(ToolTryCommand {}));
+    working_set.add_decl(Box::new(ToolExpectCommand {}));
+    working_set.add_decl(Box::new(ToolRawCommand {}));
+    working_set.add_decl(Box::new(ToolGrepCommand {}));
+    working_set.add_decl(Box::new(ToolDiagnosticsCommand {}));
+    working_set.add_decl(Box::new(ToolExportCatalogCommand {}));
+    working_set.add_decl(Box::new(ToolCompletionsDumpCommand {}));
+    working_set.add_decl(Box::new(ResourcesCommand {}));
     working_set.add_decl(Box::new(ListResourcesCommand {}));
+    working_set.add_decl(Box::new(ResourcesReadCommand {}));
+    working_set.add_decl(Box::new(ResourceTemplatesCommand {}));
+    working_set.add_decl(Box::new(ResourcesSearchCommand {}));
+    working_set.add_decl(Box::new(ResourcesOnChangeCommand {}));
+    working_set.add_decl(Box::new(PromptsRenderCommand {}));
+    working_set.add_decl(Box::new(McpCommand {}));
+    working_set.add_decl(Box::new(McpListCommand {}));
+    working_set.add_decl(Box::new(McpInfoCommand {}));
+    working_set.add_decl(Box::new(McpInstructionsCommand {}));
+    working_set.add_decl(Box::new(McpCapabilitiesCommand {}));
+    working_set.add_decl(Box::new(McpStatsCommand {}));
+    working_set.add_decl(Box::new(McpDoctorCommand {}));
+    working_set.add_decl(Box::new(McpEnvCommand {}));
+    working_set.add_decl(Box::new(McpDebugCommand {}));
+    working_set.add_decl(Box::new(McpOutputCommand {}));
+    working_set.add_decl(Box::new(McpSaveCommand {}));
+    working_set.add_decl(Box::new(McpEventsCommand {}));
+    working_set.add_decl(Box::new(McpMemoryCommand {}));
+    working_set.add_decl(Box::new(McpRestartCommand {}));
+    working_set.add_decl(Box::new(McpDisconnectCommand {}));
+    working_set.add_decl(Box::new(McpAuthRefreshCommand {}));
+    working_set.add_decl(Box::new(McpProfileCommand {}));
+    working_set.add_decl(Box::new(McpProfileListCommand {}));
+    working_set.add_decl(Box::new(McpProfileSwitchCommand {}));
+    working_set.add_decl(Box::new(McpSamplingCommand {}));
+    working_set.add_decl(Box::new(McpSamplingAllowCommand {}));
+    working_set.add_decl(Box::new(McpSamplingRevokeCommand {}));
 
     // Apply the changes
     let delta = working_set.render();
@@ -27,3 +139,48 @@ pub fn register_all(engine_state: &mut EngineState) {
         log::warn!("Error registering custom commands: {err:?}");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use nu_protocol::Category;
+
+    use super::*;
+
+    #[test]
+    fn test_command_families_use_mcp_category() {
+        let mut engine_state = EngineState::new();
+        register_all(&mut engine_state);
+
+        let families = ["mcp", "tool", "resources"];
+        let mut checked = 0;
+
+        for (name, decl_id) in engine_state.get_decls_sorted(true) {
+            let name = String::from_utf8_lossy(&name).to_string();
+            let family = name.split(' ').next().unwrap_or_default();
+
+            if !families.contains(&family) {
+                continue;
+            }
+
+            let signature = engine_state.get_decl(decl_id).signature();
+            assert_eq!(
+                signature.category,
+                Category::Custom("mcp".into()),
+                "`{name}` is not in the mcp category"
+            );
+            assert_eq!(
+                signature.name, name,
+                "`{name}` has a mismatched signature name"
+            );
+            checked += 1;
+        }
+
+        for parent in families {
+            assert!(
+                engine_state.find_decl(parent.as_bytes(), &[]).is_some(),
+                "missing `{parent}` parent command"
+            );
+        }
+        assert!(checked > families.len());
+    }
+}
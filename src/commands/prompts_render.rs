@@ -0,0 +1,419 @@
+use std::sync::Arc;
+
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, IntoPipelineData, PipelineData, Record, ShellError, Signature, Span,
+    Spanned, SyntaxShape, Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+use rmcp::model::{JsonObject, Prompt};
+use serde_json::Value as JsonValue;
+
+use super::{
+    tool_copy::copy_to_clipboard,
+    utils::{ReplClient, convert_nu_value_to_json_value},
+};
+use crate::engine::{BlockOnError, Watchdog, block_on_shared, get_mcp_client_manager_sync};
+
+/// Command to get a prompt and flatten its messages into one text
+#[derive(Clone)]
+pub struct PromptsRenderCommand;
+
+impl Command for PromptsRenderCommand {
+    fn name(&self) -> &'static str {
+        "prompts render"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("prompts render")
+            .category(Category::Custom("mcp".into()))
+            .required(
+                "name",
+                SyntaxShape::String,
+                "The namespaced prompt name (server.prompt)",
+            )
+            .named(
+                "args",
+                SyntaxShape::Record(vec![]),
+                "The prompt's arguments",
+                Some('a'),
+            )
+            .named(
+                "role",
+                SyntaxShape::String,
+                "Which messages to include: user, assistant or all (default: all)",
+                Some('r'),
+            )
+            .switch(
+                "clipboard",
+                "Copy the text to the system clipboard",
+                Some('c'),
+            )
+            .input_output_types(vec![
+                (Type::Nothing, Type::String),
+                (Type::Nothing, Type::Nothing),
+            ])
+    }
+
+    fn description(&self) -> &'static str {
+        "Get an MCP prompt with its arguments filled in, as one text"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "The prompt's messages are joined in order under a header for each role. Content that isn't text is shown as a placeholder such as [image: image/png]. The arguments are checked against the ones the prompt declares, and every required one has to be given. The clipboard needs a build with the `clipboard` feature."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Render a prompt with its arguments",
+                example: "prompts render github.review_pr --args {pr: '42'}",
+                result: None,
+            },
+            Example {
+                description: "Copy only the user's messages",
+                example: "prompts render github.review_pr --args {pr: '42'} --role user --clipboard",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let name: Spanned<String> = call.req(engine_state, stack, 0)?;
+        let args: Option<Spanned<Record>> = call.get_flag(engine_state, stack, "args")?;
+        let role: Option<Spanned<String>> = call.get_flag(engine_state, stack, "role")?;
+        let clipboard = call.has_flag(engine_state, stack, "clipboard")?;
+
+        let roles = role
+            .map(|role| {
+                Roles::parse(&role.item).ok_or_else(|| ShellError::GenericError {
+                    error: format!("Unknown role '{}'", role.item),
+                    msg: "expected user, assistant or all".into(),
+                    span: Some(role.span),
+                    help: None,
+                    inner: Vec::new(),
+                })
+            })
+            .transpose()?
+            .unwrap_or(Roles::All);
+
+        let (server, prompt_name) = name
+            .item
+            .split_once('.')
+            .ok_or_else(|| unknown_prompt(&name))?;
+        let client = get_mcp_client_manager_sync()
+            .get_server(server)
+            .map(|registered| registered.client.clone())
+            .ok_or_else(|| ShellError::GenericError {
+                error: format!("Unknown MCP server '{server}'"),
+                msg: "no server with this name is connected".into(),
+                span: Some(name.span),
+                help: Some("Run `mcp list` to see the connected servers".into()),
+                inner: Vec::new(),
+            })?;
+        if !client.supports_prompts() {
+            return Err(ShellError::GenericError {
+                error: format!("'{server}' has no prompts"),
+                msg: "the server doesn't offer the prompts capability".into(),
+                span: Some(name.span),
+                help: None,
+                inner: Vec::new(),
+            });
+        }
+
+        let prompts = wait_for(engine_state, &client, &name.item, {
+            let client = client.clone();
+            async move { client.list_prompts().await }
+        })
+        .map_err(|msg| failed(&name.item, msg, span))?;
+        let prompt = prompts
+            .iter()
+            .find(|prompt| prompt.name == prompt_name)
+            .ok_or_else(|| unknown_prompt(&name))?;
+
+        let arguments = prompt_arguments(prompt, args.as_ref(), span)?;
+        let result = wait_for(engine_state, &client, &name.item, {
+            let client = client.clone();
+            let prompt_name = prompt_name.to_string();
+            async move { client.get_prompt(&prompt_name, arguments).await }
+        })
+        .map_err(|msg| failed(&name.item, msg, span))?;
+
+        let messages = serde_json::to_value(&result.messages).unwrap_or(JsonValue::Null);
+        let text = render_messages(&messages, roles);
+        if clipboard {
+            copy_to_clipboard(&text).map_err(|msg| ShellError::GenericError {
+                error: "Failed to copy to the clipboard".into(),
+                msg,
+                span: Some(span),
+                help: Some("Leave out --clipboard to get the text as a string instead".into()),
+                inner: Vec::new(),
+            })?;
+            crate::success!("Copied {} characters", text.chars().count());
+            return Ok(PipelineData::empty());
+        }
+
+        Ok(Value::string(text, span).into_pipeline_data())
+    }
+}
+
+/// Which roles' messages are rendered
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Roles {
+    User,
+    Assistant,
+    All,
+}
+
+impl Roles {
+    fn parse(role: &str) -> Option<Self> {
+        match role {
+            "user" => Some(Self::User),
+            "assistant" => Some(Self::Assistant),
+            "all" => Some(Self::All),
+            _ => None,
+        }
+    }
+
+    fn includes(self, role: &str) -> bool {
+        match self {
+            Self::User => role == "user",
+            Self::Assistant => role == "assistant",
+            Self::All => true,
+        }
+    }
+}
+
+/// The arguments to send, checked against the ones `prompt` declares
+///
+/// MCP prompt arguments are strings, so other values are sent as JSON.
+fn prompt_arguments(
+    prompt: &Prompt,
+    args: Option<&Spanned<Record>>,
+    span: Span,
+) -> Result<JsonObject, ShellError> {
+    let declared = prompt.arguments.as_deref().unwrap_or_default();
+    let args_span = args.map_or(span, |args| args.span);
+
+    let mut arguments = JsonObject::new();
+    for (key, value) in args.map(|args| args.item.iter()).into_iter().flatten() {
+        if !declared.iter().any(|argument| argument.name == *key) {
+            let names: Vec<&str> = declared
+                .iter()
+                .map(|argument| argument.name.as_str())
+                .collect();
+            return Err(ShellError::GenericError {
+                error: format!("Unknown argument '{key}' for prompt '{}'", prompt.name),
+                msg: "the prompt doesn't declare this argument".into(),
+                span: Some(value.span()),
+                help: Some(if names.is_empty() {
+                    "The prompt takes no arguments".into()
+                } else {
+                    format!("The prompt takes: {}", names.join(", "))
+                }),
+                inner: Vec::new(),
+            });
+        }
+
+        let text = match value {
+            Value::String { val, .. } => val.clone(),
+            value => convert_nu_value_to_json_value(value, span)
+                .map_err(ShellError::from)?
+                .to_string(),
+        };
+        arguments.insert(key.clone(), JsonValue::String(text));
+    }
+
+    let missing: Vec<&str> = declared
+        .iter()
+        .filter(|argument| argument.required == Some(true))
+        .map(|argument| argument.name.as_str())
+        .filter(|name| !arguments.contains_key(*name))
+        .collect();
+    if !missing.is_empty() {
+        return Err(ShellError::GenericError {
+            error: format!("Missing arguments for prompt '{}'", prompt.name),
+            msg: format!("required: {}", missing.join(", ")),
+            span: Some(args_span),
+            help: Some(format!(
+                "Pass them with --args {{{}}}",
+                missing
+                    .iter()
+                    .map(|name| format!("{name}: ..."))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            inner: Vec::new(),
+        });
+    }
+
+    Ok(arguments)
+}
+
+/// The messages of the selected roles as one text, with a header wherever
+/// the role changes
+#[must_use]
+pub fn render_messages(messages: &JsonValue, roles: Roles) -> String {
+    let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+
+    for message in messages.as_array().into_iter().flatten() {
+        let role = message
+            .get("role")
+            .and_then(JsonValue::as_str)
+            .unwrap_or("unknown");
+        if !roles.includes(role) {
+            continue;
+        }
+
+        let text = message.get("content").map(content_text).unwrap_or_default();
+        match sections.last_mut() {
+            Some((last_role, texts)) if last_role == role => texts.push(text),
+            _ => sections.push((role.to_string(), vec![text])),
+        }
+    }
+
+    sections
+        .iter()
+        .map(|(role, texts)| format!("## {role}\n\n{}", texts.join("\n\n")))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// A content block's text, or a placeholder for content that isn't text
+fn content_text(content: &JsonValue) -> String {
+    let field = |name: &str| content.get(name).and_then(JsonValue::as_str);
+    // Some clients nest the resource's contents one level deeper
+    let resource_uri = || {
+        let resource = content.get("resource")?;
+        resource
+            .get("uri")
+            .or_else(|| resource.get("resource")?.get("uri"))
+            .and_then(JsonValue::as_str)
+    };
+
+    match field("type") {
+        Some("text") => field("text").unwrap_or_default().to_string(),
+        Some(kind @ ("image" | "audio")) => {
+            format!("[{kind}: {}]", field("mimeType").unwrap_or("unknown type"))
+        }
+        Some("resource") => format!("[resource: {}]", resource_uri().unwrap_or("embedded")),
+        Some("resource_link") => format!("[resource link: {}]", field("uri").unwrap_or("unknown")),
+        Some(kind) => format!("[{kind}]"),
+        None => "[content]".to_string(),
+    }
+}
+
+/// Wait for a prompt request, stopping on Ctrl-C
+fn wait_for<T: Send + 'static>(
+    engine_state: &EngineState,
+    client: &Arc<ReplClient>,
+    name: &str,
+    request: impl Future<Output = anyhow::Result<T>> + Send + 'static,
+) -> Result<T, String> {
+    let label = format!("prompt {name}");
+    let watchdog = Watchdog {
+        label: &label,
+        stuck_after: get_mcp_client_manager_sync()
+            .config()
+            .server_settings(&client.name)
+            .stuck_after,
+    };
+
+    match block_on_shared(request, engine_state.signals(), Some(watchdog)) {
+        Ok(result) => result.map_err(|err| format!("{err:#}")),
+        Err(BlockOnError::Interrupted) => Err("Interrupted by Ctrl-C".to_string()),
+        Err(BlockOnError::Panicked(message)) => Err(format!("The request panicked: {message}")),
+    }
+}
+
+fn unknown_prompt(name: &Spanned<String>) -> ShellError {
+    ShellError::GenericError {
+        error: format!("Unknown prompt '{}'", name.item),
+        msg: "no connected server offers a prompt with this name".into(),
+        span: Some(name.span),
+        help: Some("Prompt names have the form server.prompt".into()),
+        inner: Vec::new(),
+    }
+}
+
+fn failed(name: &str, msg: String, span: Span) -> ShellError {
+    ShellError::GenericError {
+        error: format!("Failed to get prompt {name}"),
+        msg,
+        span: Some(span),
+        help: None,
+        inner: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nu_protocol::record;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_render_messages() {
+        let messages = json!([
+            {"role": "user", "content": {"type": "text", "text": "Review PR 42."}},
+            {"role": "user", "content": {"type": "image", "data": "aGk=", "mimeType": "image/png"}},
+            {"role": "assistant", "content": {"type": "text", "text": "Looking at it."}},
+            {"role": "user", "content": {"type": "resource", "resource": {"uri": "file:///diff", "text": "..."}}}
+        ]);
+
+        assert_eq!(
+            render_messages(&messages, Roles::All),
+            "## user\n\nReview PR 42.\n\n[image: image/png]\n\n## assistant\n\nLooking at it.\n\n## user\n\n[resource: file:///diff]"
+        );
+        assert_eq!(
+            render_messages(&messages, Roles::User),
+            "## user\n\nReview PR 42.\n\n[image: image/png]\n\n[resource: file:///diff]"
+        );
+    }
+
+    #[test]
+    fn test_prompt_arguments() {
+        let prompt: Prompt = serde_json::from_value(json!({
+            "name": "review_pr",
+            "arguments": [
+                {"name": "pr", "required": true},
+                {"name": "focus"}
+            ]
+        }))
+        .unwrap();
+        let span = Span::test_data();
+        let args = |record: Record| Spanned { item: record, span };
+
+        let arguments = prompt_arguments(
+            &prompt,
+            Some(&args(record! {"pr" => Value::test_int(42)})),
+            span,
+        )
+        .unwrap();
+        assert_eq!(JsonValue::Object(arguments), json!({"pr": "42"}));
+
+        let err = prompt_arguments(&prompt, None, span).unwrap_err();
+        assert!(format!("{err:?}").contains("required: pr"), "{err:?}");
+
+        let err = prompt_arguments(
+            &prompt,
+            Some(&args(
+                record! {"pr" => Value::test_string("1"), "depth" => Value::test_int(1)},
+            )),
+            span,
+        )
+        .unwrap_err();
+        assert!(
+            format!("{err:?}").contains("The prompt takes: pr, focus"),
+            "{err:?}"
+        );
+    }
+}
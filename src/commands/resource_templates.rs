@@ -0,0 +1,300 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use indexmap::IndexMap;
+use log::info;
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, PipelineData, Record, ShellError, Signature, Span, SyntaxShape, Type, Value,
+    engine::{Call, Command, EngineState, Stack, StateWorkingSet},
+};
+use rmcp::model::{ReadResourceResult, ResourceContents};
+
+use super::{
+    tool::{RunFn, register_dynamic_tool},
+    utils::{ReplClient, with_source},
+};
+use crate::{
+    config::{EffectiveToolSettings, McpReplConfig},
+    engine::{BlockOnError, Watchdog, block_on_shared, get_mcp_client_manager_sync},
+    mcp_manager::RegisteredTemplate,
+    util::{mime::resource_contents_to_value, uri_template::UriTemplate},
+};
+
+/// Register a `resource <server>.<template>` command for every resource
+/// template the server provides
+pub fn register_resource_templates_in_working_set(
+    name: &str,
+    working_set: &mut StateWorkingSet,
+    client: &Arc<ReplClient>,
+    config: &McpReplConfig,
+) -> IndexMap<String, RegisteredTemplate> {
+    let mut registered = IndexMap::new();
+
+    for template in client.get_templates() {
+        let command_name = format!("resource {name}.{}", command_friendly_name(&template.name));
+
+        let uri_template = match UriTemplate::parse(&template.uri_template) {
+            Ok(uri_template) => uri_template,
+            Err(err) => {
+                crate::warning!(
+                    "Skipping resource template '{}' on '{}': {}",
+                    template.name,
+                    name,
+                    err
+                );
+                continue;
+            }
+        };
+
+        info!("Registering MCP resource template as command: {command_name}");
+
+        let mut signature = Signature::build(command_name.clone())
+            .category(Category::Custom("mcp".into()))
+            .input_output_types(vec![(Type::Nothing, Type::Any)]);
+
+        for variable in uri_template.variables() {
+            let description = format!("The `{}` part of the resource URI", variable.name);
+            signature = if variable.required {
+                signature.required(variable.name, SyntaxShape::String, description)
+            } else {
+                signature.named(variable.name, SyntaxShape::String, description, None)
+            };
+        }
+
+        let description = template
+            .description
+            .clone()
+            .unwrap_or_else(|| format!("Read the {} resource", template.name));
+
+        let settings = config.tool_settings(name, &template.name);
+        let run_fn = create_template_run_function(uri_template.clone(), client, settings);
+
+        register_dynamic_tool(
+            working_set,
+            &command_name,
+            signature,
+            description,
+            format!("Reads resources matching `{}`.", template.uri_template),
+            run_fn,
+        );
+
+        registered.insert(
+            command_friendly_name(&template.name),
+            RegisteredTemplate {
+                template: template.clone(),
+                name: command_friendly_name(&template.name),
+                uri_template,
+            },
+        );
+    }
+
+    registered
+}
+
+/// Turn a template's display name into something usable in a command name
+fn command_friendly_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '-' {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Create the run function for a resource template command
+fn create_template_run_function(
+    uri_template: UriTemplate,
+    client: &Arc<ReplClient>,
+    settings: EffectiveToolSettings,
+) -> Box<RunFn> {
+    let client = client.clone();
+
+    Box::new(move |engine_state, stack, call, _input| {
+        let span = call.head;
+        let mut values = IndexMap::new();
+        let mut position = 0;
+
+        for variable in uri_template.variables() {
+            let value: Option<String> = if variable.required {
+                position += 1;
+                Some(call.req(engine_state, stack, position - 1)?)
+            } else {
+                call.get_flag(engine_state, stack, &variable.name)?
+            };
+
+            if let Some(value) = value {
+                values.insert(variable.name, value);
+            }
+        }
+
+        let uri = uri_template
+            .expand(&values)
+            .map_err(|err| ShellError::GenericError {
+                error: "Failed to expand resource URI".into(),
+                msg: err,
+                span: Some(span),
+                help: None,
+                inner: Vec::new(),
+            })?;
+
+        read_resource_sync(engine_state, &client, &uri, &settings, span)
+    })
+}
+
+/// Read a resource from a synchronous command context
+pub fn read_resource_sync(
+    engine_state: &EngineState,
+    client: &Arc<ReplClient>,
+    uri: &str,
+    settings: &EffectiveToolSettings,
+    span: Span,
+) -> Result<PipelineData, ShellError> {
+    let result = read_resource_contents(engine_state, client, uri, settings).map_err(|msg| {
+        ShellError::GenericError {
+            error: format!("Failed to read resource {uri}"),
+            msg,
+            span: Some(span),
+            help: None,
+            inner: Vec::new(),
+        }
+    })?;
+
+    let mut values: Vec<Value> = result
+        .contents
+        .iter()
+        .map(|contents| resource_contents_to_value(contents, span))
+        .collect();
+
+    // A single block's MIME type describes the whole result
+    let content_type = match result.contents.as_slice() {
+        [ResourceContents::TextResourceContents { mime_type, .. }]
+        | [ResourceContents::BlobResourceContents { mime_type, .. }] => mime_type.clone(),
+        _ => None,
+    };
+
+    let data = match values.len() {
+        0 => PipelineData::Value(Value::nothing(span), None),
+        1 => PipelineData::Value(values.remove(0), None),
+        _ => PipelineData::Value(Value::list(values, span), None),
+    };
+
+    Ok(with_source(data, uri, content_type))
+}
+
+/// Read a resource, waiting for it with the server's timeout
+///
+/// Errors are returned as a message, to be reported the way the caller sees fit.
+pub fn read_resource_contents(
+    engine_state: &EngineState,
+    client: &Arc<ReplClient>,
+    uri: &str,
+    settings: &EffectiveToolSettings,
+) -> Result<ReadResourceResult, String> {
+    let read_client = client.clone();
+    let read_uri = uri.to_string();
+    let timeout = settings.timeout;
+
+    let read = async move {
+        let read = read_client.read_resource(&read_uri);
+        if timeout.is_zero() {
+            read.await
+        } else {
+            tokio::time::timeout(timeout, read)
+                .await
+                .map_err(|_| anyhow!("Timed out after {}", humantime::format_duration(timeout)))?
+        }
+    };
+
+    let label = format!("{} {uri}", client.name);
+    let watchdog = Watchdog {
+        label: &label,
+        stuck_after: settings.stuck_after,
+    };
+
+    match block_on_shared(read, engine_state.signals(), Some(watchdog)) {
+        Ok(result) => result.map_err(|err| format!("{err:#}")),
+        Err(BlockOnError::Interrupted) => Err("Interrupted by Ctrl-C".to_string()),
+        Err(BlockOnError::Panicked(message)) => Err(format!("The read panicked: {message}")),
+    }
+}
+
+/// Command to list the registered resource templates
+#[derive(Clone)]
+pub struct ResourceTemplatesCommand;
+
+impl Command for ResourceTemplatesCommand {
+    fn name(&self) -> &'static str {
+        "resources templates"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("resources templates")
+            .category(Category::Custom("mcp".into()))
+            .input_output_types(vec![(Type::Nothing, Type::Table(vec![].into()))])
+    }
+
+    fn description(&self) -> &'static str {
+        "List the resource templates and the commands generated for them"
+    }
+
+    fn run(
+        &self,
+        _engine_state: &EngineState,
+        _stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let manager = get_mcp_client_manager_sync();
+        let mut rows = Vec::new();
+
+        for (server_name, server) in manager.get_servers() {
+            for registered in server.templates.values() {
+                let template = &registered.template;
+                let mut record = Record::new();
+
+                record.push("server", Value::string(server_name, span));
+                record.push(
+                    "command",
+                    Value::string(format!("resource {server_name}.{}", registered.name), span),
+                );
+                record.push("uri_template", Value::string(&template.uri_template, span));
+                record.push(
+                    "variables",
+                    Value::list(
+                        registered
+                            .uri_template
+                            .variables()
+                            .into_iter()
+                            .map(|variable| Value::string(variable.name, span))
+                            .collect(),
+                        span,
+                    ),
+                );
+                record.push(
+                    "mime_type",
+                    template
+                        .mime_type
+                        .as_ref()
+                        .map_or_else(|| Value::nothing(span), |mime| Value::string(mime, span)),
+                );
+                record.push(
+                    "description",
+                    template
+                        .description
+                        .as_ref()
+                        .map_or_else(|| Value::nothing(span), |desc| Value::string(desc, span)),
+                );
+
+                rows.push(Value::record(record, span));
+            }
+        }
+
+        Ok(PipelineData::Value(Value::list(rows, span), None))
+    }
+}
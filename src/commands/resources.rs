@@ -0,0 +1,269 @@
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, IntoPipelineData, PipelineData, ShellError, Signature, Spanned, SyntaxShape,
+    Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+
+use super::{
+    list_resources::resource_row, resource_templates::read_resource_sync, utils::ReplClient,
+};
+use crate::{engine::get_mcp_client_manager_sync, mcp_manager::RegisteredServer};
+
+/// Parent command for the `resources` family, mirroring `tool`
+#[derive(Clone)]
+pub struct ResourcesCommand;
+
+impl Command for ResourcesCommand {
+    fn name(&self) -> &'static str {
+        "resources"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("resources")
+            .category(Category::Custom("mcp".into()))
+            .input_output_types(vec![(Type::Nothing, Type::String)])
+    }
+
+    fn description(&self) -> &'static str {
+        "Various commands for working with MCP resources"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "You must use one of the following subcommands. Using this command as-is will only produce this help message."
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let mut help = nu_engine::get_full_help(self, engine_state, stack);
+
+        // Examples can only point at real URIs once a server is connected
+        let manager = get_mcp_client_manager_sync();
+        let example = manager.get_servers().iter().find_map(|(name, server)| {
+            server
+                .client
+                .get_resources()
+                .first()
+                .map(|resource| (name.clone(), resource.uri.clone()))
+        });
+        drop(manager);
+
+        if let Some((server, uri)) = example {
+            help.push_str(&format!(
+                "\nWith the connected servers:\n\n  List the resources of {server}:\n  > resources list --server {server}\n\n  Read one of them:\n  > resources read '{uri}'\n"
+            ));
+        }
+
+        Ok(Value::string(help, call.head).into_pipeline_data())
+    }
+}
+
+/// Command to read a resource by URI
+#[derive(Clone)]
+pub struct ResourcesReadCommand;
+
+impl Command for ResourcesReadCommand {
+    fn name(&self) -> &'static str {
+        "resources read"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("resources read")
+            .category(Category::Custom("mcp".into()))
+            .required(
+                "uri",
+                SyntaxShape::String,
+                "The URI of the resource to read",
+            )
+            .named(
+                "server",
+                SyntaxShape::String,
+                "The server to read from (default: the server that listed the URI)",
+                Some('s'),
+            )
+            .input_output_types(vec![(Type::Nothing, Type::Any)])
+    }
+
+    fn description(&self) -> &'static str {
+        "Read an MCP resource by URI"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "JSON resources are parsed into structured values, other text is returned as a string and blobs as binary."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example {
+            description: "Read the first resource the fs server lists",
+            example: "resources read (resources list --server fs | first | get uri)",
+            result: None,
+        }]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let uri: Spanned<String> = call.req(engine_state, stack, 0)?;
+        let server: Option<Spanned<String>> = call.get_flag(engine_state, stack, "server")?;
+
+        let manager = get_mcp_client_manager_sync();
+        let (name, client) = resolve_server(manager.get_servers(), &uri, server.as_ref())?;
+        let settings = manager.config().server_settings(&name);
+        drop(manager);
+
+        read_resource_sync(engine_state, &client, &uri.item, &settings, span)
+    }
+}
+
+/// Pick the server to read a URI from
+///
+/// An explicit `--server` always wins. Otherwise the URI must have been
+/// listed by exactly one server, unless only one server is connected.
+pub fn resolve_server(
+    servers: &IndexMap<String, RegisteredServer>,
+    uri: &Spanned<String>,
+    server: Option<&Spanned<String>>,
+) -> Result<(String, Arc<ReplClient>), ShellError> {
+    if let Some(server) = server {
+        return servers
+            .get(&server.item)
+            .map(|registered| (server.item.clone(), registered.client.clone()))
+            .ok_or_else(|| ShellError::GenericError {
+                error: format!("Unknown MCP server '{}'", server.item),
+                msg: "no server with this name is connected".into(),
+                span: Some(server.span),
+                help: Some("Run `mcp list` to see the connected servers".into()),
+                inner: Vec::new(),
+            });
+    }
+
+    let listing: Vec<_> = servers
+        .iter()
+        .filter(|(_, registered)| {
+            registered
+                .client
+                .get_resources()
+                .iter()
+                .any(|resource| resource.uri == uri.item)
+        })
+        .collect();
+
+    let candidates = if listing.is_empty() && servers.len() == 1 {
+        servers.iter().collect()
+    } else {
+        listing
+    };
+
+    match candidates.as_slice() {
+        [(name, registered)] => Ok(((*name).clone(), registered.client.clone())),
+        [] => Err(ShellError::GenericError {
+            error: format!("No server lists the resource '{}'", uri.item),
+            msg: "pass --server to choose the server to read from".into(),
+            span: Some(uri.span),
+            help: Some("Run `resources list` to see the known resources".into()),
+            inner: Vec::new(),
+        }),
+        several => Err(ShellError::GenericError {
+            error: format!("Several servers list the resource '{}'", uri.item),
+            msg: format!(
+                "listed by {}",
+                several
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            span: Some(uri.span),
+            help: Some("Pass --server to choose the server to read from".into()),
+            inner: Vec::new(),
+        }),
+    }
+}
+
+/// Command to search the resources the servers listed
+#[derive(Clone)]
+pub struct ResourcesSearchCommand;
+
+impl Command for ResourcesSearchCommand {
+    fn name(&self) -> &'static str {
+        "resources search"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("resources search")
+            .category(Category::Custom("mcp".into()))
+            .required(
+                "term",
+                SyntaxShape::String,
+                "Text to look for in the URI, name and description",
+            )
+            .input_output_types(vec![(Type::Nothing, Type::Table(vec![].into()))])
+    }
+
+    fn description(&self) -> &'static str {
+        "Search the resources of the connected MCP servers"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "The search is case-insensitive and covers the resources loaded when each server connected. The rows have the same columns as `resources list`."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example {
+            description: "Find resources about the README",
+            example: "resources search readme",
+            result: None,
+        }]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let term: String = call.req(engine_state, stack, 0)?;
+        let term = term.to_lowercase();
+
+        let manager = get_mcp_client_manager_sync();
+        let rows = manager
+            .get_servers()
+            .iter()
+            .flat_map(|(name, server)| {
+                server
+                    .client
+                    .get_resources()
+                    .iter()
+                    .filter(|resource| {
+                        [
+                            Some(resource.uri.as_str()),
+                            Some(resource.name.as_str()),
+                            resource.description.as_deref(),
+                        ]
+                        .into_iter()
+                        .flatten()
+                        .any(|text| text.to_lowercase().contains(&term))
+                    })
+                    .map(move |resource| resource_row(name, resource, span))
+            })
+            .collect();
+        drop(manager);
+
+        Ok(PipelineData::Value(Value::list(rows, span), None))
+    }
+}
@@ -0,0 +1,485 @@
+use std::{
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use nu_engine::{CallExt, ClosureEval};
+use nu_protocol::{
+    Category, Example, PipelineData, ShellError, Signature, Span, Spanned, SyntaxShape, Type,
+    Value,
+    engine::{Call, Closure, Command, EngineState, Stack},
+};
+use rmcp::model::ResourceContents;
+
+use super::resources::resolve_server;
+use crate::{
+    engine::{BlockOnError, block_on_shared, get_mcp_client_manager, get_mcp_client_manager_sync},
+    util::{
+        events::{EventLog, ServerEvent},
+        format::render_value,
+        mime::resource_contents_to_value,
+        tasks,
+    },
+};
+
+/// The notification a server sends when a subscribed resource changes
+const RESOURCE_UPDATED: &str = "notifications/resources/updated";
+
+/// How often a watch checks the event log for new notifications
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long a watch waits for more changes when `--debounce` isn't given
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// The watches started by `resources on-change`, until they're cancelled
+static WATCHES: Mutex<Vec<ActiveWatch>> = Mutex::new(Vec::new());
+
+struct ActiveWatch {
+    server: String,
+    uri: String,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Command to run a closure whenever a subscribed resource changes
+#[derive(Clone)]
+pub struct ResourcesOnChangeCommand;
+
+impl Command for ResourcesOnChangeCommand {
+    fn name(&self) -> &'static str {
+        "resources on-change"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("resources on-change")
+            .category(Category::Custom("mcp".into()))
+            .required(
+                "uri",
+                SyntaxShape::String,
+                "The URI of the resource to watch",
+            )
+            .optional(
+                "closure",
+                SyntaxShape::Closure(Some(vec![SyntaxShape::Any])),
+                "The closure to run with the fresh contents each time the resource changes",
+            )
+            .named(
+                "server",
+                SyntaxShape::String,
+                "The server to subscribe to (default: the server that listed the URI)",
+                Some('s'),
+            )
+            .named(
+                "debounce",
+                SyntaxShape::Duration,
+                "Wait until the resource has been quiet this long before running (default 200ms)",
+                Some('d'),
+            )
+            .switch(
+                "follow",
+                "Wait here until Ctrl-C, then cancel the watch",
+                Some('f'),
+            )
+            .switch("cancel", "Stop watching the resource", None)
+            .input_output_types(vec![(Type::Nothing, Type::Nothing)])
+    }
+
+    fn description(&self) -> &'static str {
+        "Run a closure each time a subscribed MCP resource changes"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "The resource is subscribed to, and each `notifications/resources/updated` for it makes the REPL read it again and run the closure with the contents as its argument and $in. The output is printed above the prompt. The watch runs in the background until `resources on-change --cancel <uri>`; an error in the closure is reported for that change only."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Print the number of open alerts whenever they change",
+                example: "resources on-change monitor://alerts { |alerts| $alerts | length }",
+                result: None,
+            },
+            Example {
+                description: "Follow a log until Ctrl-C, after a burst of writes settles",
+                example: "resources on-change file:///var/log/app.log { |log| $log | lines | last 5 } --debounce 1sec --follow",
+                result: None,
+            },
+            Example {
+                description: "Stop watching a resource",
+                example: "resources on-change --cancel monitor://alerts",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let uri: Spanned<String> = call.req(engine_state, stack, 0)?;
+        let closure: Option<Closure> = call.opt(engine_state, stack, 1)?;
+        let server: Option<Spanned<String>> = call.get_flag(engine_state, stack, "server")?;
+        let debounce = match call.get_flag::<Value>(engine_state, stack, "debounce")? {
+            Some(value) => Duration::from_nanos(u64::try_from(value.as_duration()?).unwrap_or(0)),
+            None => DEFAULT_DEBOUNCE,
+        };
+        let follow = call.has_flag(engine_state, stack, "follow")?;
+
+        if call.has_flag(engine_state, stack, "cancel")? {
+            if !cancel_watches(
+                &uri.item,
+                server.as_ref().map(|server| server.item.as_str()),
+            ) {
+                return Err(ShellError::GenericError {
+                    error: format!("'{}' isn't being watched", uri.item),
+                    msg: "no `resources on-change` watch for this URI".into(),
+                    span: Some(uri.span),
+                    help: None,
+                    inner: Vec::new(),
+                });
+            }
+            return Ok(PipelineData::empty());
+        }
+
+        let Some(closure) = closure else {
+            return Err(ShellError::MissingParameter {
+                param_name: "closure".into(),
+                span,
+            });
+        };
+
+        let manager = get_mcp_client_manager_sync();
+        let (name, client) = resolve_server(manager.get_servers(), &uri, server.as_ref())?;
+        let events = manager.events().clone();
+        drop(manager);
+
+        if !client.supports_subscriptions() {
+            return Err(ShellError::GenericError {
+                error: format!("'{name}' doesn't support resource subscriptions"),
+                msg: "the server didn't advertise `resources.subscribe`".into(),
+                span: Some(uri.span),
+                help: Some(
+                    "Use `tool watch` or a loop over `resources read` to poll it instead".into(),
+                ),
+                inner: Vec::new(),
+            });
+        }
+
+        // Replacing a watch on the same resource keeps a single subscription
+        cancel_watches(&uri.item, Some(&name));
+
+        let subscribe_client = client.clone();
+        let subscribe_uri = uri.item.clone();
+        let subscribed = block_on_shared(
+            async move { subscribe_client.subscribe_resource(&subscribe_uri).await },
+            engine_state.signals(),
+            None,
+        );
+        let subscribe_error = |msg: String| ShellError::GenericError {
+            error: format!("Failed to subscribe to {}", uri.item),
+            msg,
+            span: Some(uri.span),
+            help: None,
+            inner: Vec::new(),
+        };
+        match subscribed {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => return Err(subscribe_error(format!("{err:#}"))),
+            Err(BlockOnError::Interrupted) => {
+                return Err(subscribe_error("Interrupted by Ctrl-C".into()));
+            }
+            Err(BlockOnError::Panicked(message)) => {
+                return Err(subscribe_error(format!("The request panicked: {message}")));
+            }
+        }
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        WATCHES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(ActiveWatch {
+                server: name.clone(),
+                uri: uri.item.clone(),
+                cancelled: cancelled.clone(),
+            });
+
+        let watch = Watch {
+            server: name.clone(),
+            uri: uri.item.clone(),
+            debounce,
+            next_seq: events.next_seq(),
+            events,
+            cancelled: cancelled.clone(),
+            job: Arc::new(ClosureJob {
+                engine_state: engine_state.clone(),
+                stack: stack.clone(),
+                closure,
+            }),
+        };
+        tasks::spawn(
+            format!("resources on-change {} on '{}'", uri.item, name),
+            watch.run(),
+        );
+
+        if follow {
+            while !cancelled.load(Ordering::Relaxed) && !engine_state.signals().interrupted() {
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            cancel_watches(&uri.item, Some(&name));
+        } else {
+            crate::info!(
+                "Watching {} on '{}'; stop with `resources on-change --cancel '{}'`",
+                uri.item,
+                name,
+                uri.item
+            );
+        }
+
+        Ok(PipelineData::empty())
+    }
+}
+
+/// Cancel the watches on `uri` (on `server`, if given); false if there were none
+fn cancel_watches(uri: &str, server: Option<&str>) -> bool {
+    let mut watches = WATCHES.lock().unwrap_or_else(PoisonError::into_inner);
+    let before = watches.len();
+
+    watches.retain(|watch| {
+        let matches = watch.uri == uri && server.is_none_or(|server| watch.server == server);
+        if matches {
+            watch.cancelled.store(true, Ordering::Relaxed);
+        }
+        !matches
+    });
+
+    watches.len() < before
+}
+
+/// The closure a watch runs, with the state it was created in
+struct ClosureJob {
+    engine_state: EngineState,
+    stack: Stack,
+    closure: Closure,
+}
+
+impl ClosureJob {
+    /// Run the closure with `contents` and render what it returned
+    fn run(&self, contents: Value) -> Result<Option<String>, ShellError> {
+        let span = contents.span();
+        let output = ClosureEval::new(&self.engine_state, &self.stack, self.closure.clone())
+            .run_with_value(contents)?
+            .into_value(span)?;
+
+        if output.is_nothing() {
+            return Ok(None);
+        }
+
+        let mut stack = self.stack.clone();
+        Ok(Some(render_value(&self.engine_state, &mut stack, output)))
+    }
+}
+
+/// A background task that runs a closure when its resource changes
+struct Watch {
+    server: String,
+    uri: String,
+    debounce: Duration,
+    events: EventLog,
+    next_seq: u64,
+    cancelled: Arc<AtomicBool>,
+    job: Arc<ClosureJob>,
+}
+
+impl Watch {
+    async fn run(mut self) {
+        let mut debounce = Debounce::new(self.debounce);
+
+        while !self.cancelled.load(Ordering::Relaxed) {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            let events = self.events.since(self.next_seq);
+            if let Some(last) = events.last() {
+                self.next_seq = last.seq + 1;
+            }
+            if events
+                .iter()
+                .any(|event| is_update_for(event, &self.server, &self.uri))
+            {
+                debounce.changed(Instant::now());
+            }
+
+            if debounce.ready(Instant::now()) && !self.cancelled.load(Ordering::Relaxed) {
+                self.on_change().await;
+            }
+        }
+
+        self.unsubscribe().await;
+    }
+
+    /// Read the resource again and run the closure with it
+    async fn on_change(&self) {
+        // Look the client up each time, so a restarted server is picked up
+        let client = get_mcp_client_manager()
+            .await
+            .get_server(&self.server)
+            .map(|server| server.client.clone());
+        let Some(client) = client else {
+            crate::error!(
+                "on-change {}: '{}' is no longer connected",
+                self.uri,
+                self.server
+            );
+            return;
+        };
+
+        let contents = match client.read_resource(&self.uri).await {
+            Ok(result) => contents_value(&result.contents, Span::unknown()),
+            Err(err) => {
+                crate::error!("on-change {}: {err:#}", self.uri);
+                return;
+            }
+        };
+
+        let job = self.job.clone();
+        match tokio::task::spawn_blocking(move || job.run(contents)).await {
+            Ok(Ok(Some(output))) => crate::info!("{} changed:\n{output}", self.uri),
+            Ok(Ok(None)) => {}
+            Ok(Err(err)) => crate::error!("on-change {}: the closure failed: {err}", self.uri),
+            Err(err) => crate::error!("on-change {}: the closure panicked: {err}", self.uri),
+        }
+    }
+
+    async fn unsubscribe(&self) {
+        // A watch that replaced this one still needs the subscription
+        let replaced = WATCHES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .any(|watch| watch.server == self.server && watch.uri == self.uri);
+        if replaced {
+            return;
+        }
+
+        let client = get_mcp_client_manager()
+            .await
+            .get_server(&self.server)
+            .map(|server| server.client.clone());
+
+        if let Some(client) = client {
+            if let Err(err) = client.unsubscribe_resource(&self.uri).await {
+                log::warn!("{err:#}");
+            }
+        }
+    }
+}
+
+/// Whether a notification says `uri` on `server` changed
+fn is_update_for(event: &ServerEvent, server: &str, uri: &str) -> bool {
+    event.server == server
+        && event.method == RESOURCE_UPDATED
+        && event
+            .params
+            .get_data_by_key("uri")
+            .is_some_and(|value| value.as_str().is_ok_and(|value| value == uri))
+}
+
+/// The resource's contents as the closure gets them, like `resources read` returns them
+fn contents_value(contents: &[ResourceContents], span: Span) -> Value {
+    let mut values: Vec<Value> = contents
+        .iter()
+        .map(|contents| resource_contents_to_value(contents, span))
+        .collect();
+
+    match values.len() {
+        0 => Value::nothing(span),
+        1 => values.remove(0),
+        _ => Value::list(values, span),
+    }
+}
+
+/// Fires once after a burst of changes has been quiet for a while
+struct Debounce {
+    quiet: Duration,
+    last_change: Option<Instant>,
+}
+
+impl Debounce {
+    const fn new(quiet: Duration) -> Self {
+        Self {
+            quiet,
+            last_change: None,
+        }
+    }
+
+    const fn changed(&mut self, now: Instant) {
+        self.last_change = Some(now);
+    }
+
+    /// Whether it's time to run, which resets it until the next change
+    fn ready(&mut self, now: Instant) -> bool {
+        let ready = self
+            .last_change
+            .is_some_and(|last_change| now.duration_since(last_change) >= self.quiet);
+        if ready {
+            self.last_change = None;
+        }
+        ready
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debounce_waits_for_quiet() {
+        let start = Instant::now();
+        let mut debounce = Debounce::new(Duration::from_millis(200));
+        assert!(!debounce.ready(start));
+
+        debounce.changed(start);
+        debounce.changed(start + Duration::from_millis(150));
+        assert!(!debounce.ready(start + Duration::from_millis(250)));
+        assert!(debounce.ready(start + Duration::from_millis(350)));
+        assert!(!debounce.ready(start + Duration::from_millis(600)));
+    }
+
+    #[test]
+    fn test_only_updates_for_the_uri_count() {
+        let log = EventLog::new(10);
+        log.push(
+            "fs",
+            RESOURCE_UPDATED,
+            &serde_json::json!({"uri": "file:///a"}),
+        );
+        log.push(
+            "fs",
+            RESOURCE_UPDATED,
+            &serde_json::json!({"uri": "file:///b"}),
+        );
+        log.push(
+            "db",
+            RESOURCE_UPDATED,
+            &serde_json::json!({"uri": "file:///a"}),
+        );
+        log.push(
+            "fs",
+            "notifications/message",
+            &serde_json::json!({"uri": "file:///a"}),
+        );
+
+        let matching: Vec<u64> = log
+            .since(0)
+            .iter()
+            .filter(|event| is_update_for(event, "fs", "file:///a"))
+            .map(|event| event.seq)
+            .collect();
+        assert_eq!(matching, [0]);
+    }
+}
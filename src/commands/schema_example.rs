@@ -0,0 +1,277 @@
+//! Example values synthesized from a parameter's JSON schema
+//!
+//! Used to show what a parameter expects, both in validation errors and in
+//! the `example` column of `tool describe`.
+
+use serde_json::{Value as JsonValue, json};
+
+use super::tool_mapper::describe_schema_type;
+
+/// A plausible value for a parameter, derived from its schema
+///
+/// In order of preference: the first `enum` value, the first of the
+/// schema's own `examples`, its `default`, a value for its `format`, a
+/// number within its bounds, or a canned value for its type.
+#[must_use]
+pub fn example_value(schema: &JsonValue) -> JsonValue {
+    if let Some(first) = schema
+        .get("enum")
+        .and_then(JsonValue::as_array)
+        .and_then(|choices| choices.first())
+    {
+        return first.clone();
+    }
+
+    if let Some(example) = schema
+        .get("examples")
+        .and_then(JsonValue::as_array)
+        .and_then(|examples| examples.first())
+        .or_else(|| schema.get("example"))
+        .or_else(|| schema.get("default"))
+    {
+        return example.clone();
+    }
+
+    match primary_type(schema) {
+        Some("integer") => json!(bounded_integer(schema)),
+        Some("number") => json!(bounded_number(schema)),
+        Some("boolean") => json!(true),
+        Some("array") => {
+            let item = schema
+                .get("items")
+                .map_or_else(|| json!("item"), example_value);
+            json!([item])
+        }
+        Some("object") => {
+            let properties = schema
+                .get("properties")
+                .and_then(JsonValue::as_object)
+                .map(|properties| {
+                    required_names(schema)
+                        .filter_map(|name| {
+                            properties
+                                .get(name)
+                                .map(|property| (name.to_string(), example_value(property)))
+                        })
+                        .collect::<serde_json::Map<_, _>>()
+                })
+                .unwrap_or_default();
+            JsonValue::Object(properties)
+        }
+        _ => json!(format_example(schema).unwrap_or("text")),
+    }
+}
+
+/// The constraints a schema puts on a value, e.g. `max: 100` or `format: email`
+#[must_use]
+pub fn constraint_hints(schema: &JsonValue) -> Vec<String> {
+    let mut hints = Vec::new();
+
+    if let Some(choices) = schema.get("enum").and_then(JsonValue::as_array) {
+        let choices: Vec<String> = choices.iter().map(ToString::to_string).collect();
+        hints.push(format!("one of: {}", choices.join(", ")));
+    }
+    for (key, label) in [
+        ("minimum", "min"),
+        ("maximum", "max"),
+        ("minLength", "min length"),
+        ("maxLength", "max length"),
+        ("minItems", "min items"),
+        ("maxItems", "max items"),
+    ] {
+        if let Some(bound) = schema.get(key).filter(|bound| bound.is_number()) {
+            hints.push(format!("{label}: {bound}"));
+        }
+    }
+    if let Some(format) = schema.get("format").and_then(JsonValue::as_str) {
+        hints.push(format!("format: {format}"));
+    }
+    if let Some(pattern) = schema.get("pattern").and_then(JsonValue::as_str) {
+        hints.push(format!("pattern: {pattern}"));
+    }
+
+    hints
+}
+
+/// What a parameter expects, with an example of passing it as a flag
+///
+/// For example: parameter 'limit' expects an integer (max: 100), e.g. `--limit 50`
+#[must_use]
+pub fn expectation(name: &str, schema: &JsonValue) -> String {
+    let hints = constraint_hints(schema);
+    let constraints = if hints.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", hints.join(", "))
+    };
+
+    format!(
+        "parameter '{name}' expects {}{constraints}, e.g. `--{name} {}`",
+        with_article(&describe_schema_type(schema)),
+        example_value(schema)
+    )
+}
+
+/// The schema's type, ignoring `null` in a list of types
+fn primary_type(schema: &JsonValue) -> Option<&str> {
+    match schema.get("type") {
+        Some(JsonValue::String(schema_type)) => Some(schema_type),
+        Some(JsonValue::Array(types)) => types
+            .iter()
+            .filter_map(JsonValue::as_str)
+            .find(|schema_type| *schema_type != "null"),
+        _ => None,
+    }
+}
+
+fn required_names(schema: &JsonValue) -> impl Iterator<Item = &str> {
+    schema
+        .get("required")
+        .and_then(JsonValue::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(JsonValue::as_str)
+}
+
+/// An integer that respects the schema's bounds, like [`bounded_number`]
+fn bounded_integer(schema: &JsonValue) -> i64 {
+    let minimum = schema.get("minimum").and_then(JsonValue::as_i64);
+    let maximum = schema.get("maximum").and_then(JsonValue::as_i64);
+
+    match (minimum, maximum) {
+        (Some(minimum), Some(maximum)) => minimum + (maximum - minimum) / 2,
+        (Some(minimum), None) => minimum.max(10),
+        (None, Some(maximum)) => maximum / 2,
+        (None, None) => 10,
+    }
+}
+
+/// A number that respects the schema's bounds: halfway between them, or
+/// close to the one that's given
+fn bounded_number(schema: &JsonValue) -> f64 {
+    let minimum = schema.get("minimum").and_then(JsonValue::as_f64);
+    let maximum = schema.get("maximum").and_then(JsonValue::as_f64);
+
+    match (minimum, maximum) {
+        (Some(minimum), Some(maximum)) => (minimum + maximum) / 2.0,
+        (Some(minimum), None) => minimum.max(10.0),
+        (None, Some(maximum)) => maximum / 2.0,
+        (None, None) => 10.0,
+    }
+}
+
+/// A string in one of the common `format`s
+fn format_example(schema: &JsonValue) -> Option<&'static str> {
+    let example = match schema.get("format")?.as_str()? {
+        "date-time" => "2025-01-31T12:00:00Z",
+        "date" => "2025-01-31",
+        "time" => "12:00:00",
+        "email" => "user@example.com",
+        "uri" | "url" => "https://example.com",
+        "uuid" => "123e4567-e89b-12d3-a456-426614174000",
+        "hostname" => "example.com",
+        "ipv4" => "192.0.2.1",
+        "ipv6" => "2001:db8::1",
+        _ => return None,
+    };
+    Some(example)
+}
+
+fn with_article(schema_type: &str) -> String {
+    match schema_type {
+        "any" => "any value".into(),
+        "null" => "null".into(),
+        vowel if vowel.starts_with(['a', 'e', 'i', 'o', 'u']) => format!("an {vowel}"),
+        other => format!("a {other}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example_from_enum_and_examples() {
+        assert_eq!(
+            example_value(&json!({"type": "string", "enum": ["open", "closed"]})),
+            json!("open")
+        );
+        assert_eq!(
+            example_value(&json!({"type": "string", "examples": ["octocat"]})),
+            json!("octocat")
+        );
+        assert_eq!(
+            example_value(&json!({"type": "integer", "default": 30})),
+            json!(30)
+        );
+    }
+
+    #[test]
+    fn test_example_from_format() {
+        assert_eq!(
+            example_value(&json!({"type": "string", "format": "email"})),
+            json!("user@example.com")
+        );
+        assert_eq!(
+            example_value(&json!({"type": "string", "format": "date-time"})),
+            json!("2025-01-31T12:00:00Z")
+        );
+        assert_eq!(
+            example_value(&json!({"type": "string", "format": "unknown"})),
+            json!("text")
+        );
+    }
+
+    #[test]
+    fn test_example_within_bounds() {
+        assert_eq!(
+            example_value(&json!({"type": "integer", "maximum": 100})),
+            json!(50)
+        );
+        assert_eq!(
+            example_value(&json!({"type": "integer", "minimum": 1, "maximum": 5})),
+            json!(3)
+        );
+        assert_eq!(
+            example_value(&json!({"type": "integer", "minimum": 20})),
+            json!(20)
+        );
+        assert_eq!(example_value(&json!({"type": "number"})), json!(10.0));
+    }
+
+    #[test]
+    fn test_example_per_type() {
+        assert_eq!(example_value(&json!({"type": "boolean"})), json!(true));
+        assert_eq!(
+            example_value(&json!({"type": ["string", "null"]})),
+            json!("text")
+        );
+        assert_eq!(
+            example_value(&json!({"type": "array", "items": {"type": "integer"}})),
+            json!([10])
+        );
+        assert_eq!(
+            example_value(&json!({
+                "type": "object",
+                "properties": {"name": {"type": "string"}, "tag": {"type": "string"}},
+                "required": ["name"]
+            })),
+            json!({"name": "text"})
+        );
+    }
+
+    #[test]
+    fn test_expectation() {
+        assert_eq!(
+            expectation("limit", &json!({"type": "integer", "maximum": 100})),
+            "parameter 'limit' expects an integer (max: 100), e.g. `--limit 50`"
+        );
+        assert_eq!(
+            expectation(
+                "state",
+                &json!({"type": "string", "enum": ["open", "closed"]})
+            ),
+            r#"parameter 'state' expects a string (one of: "open", "closed"), e.g. `--state "open"`"#
+        );
+    }
+}
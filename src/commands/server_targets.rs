@@ -0,0 +1,279 @@
+//! Choosing the servers a command acts on, and reporting how it went
+//!
+//! `mcp disconnect` and `mcp restart` take any number of server names and
+//! glob patterns. Without any, an interactive session asks which servers to
+//! pick from a checkbox list, and a script has to pass `--all`.
+
+use nu_engine::CallExt;
+use nu_protocol::{
+    ShellError, Signature, Span, Spanned, SyntaxShape, Value,
+    engine::{Call, EngineState, Stack},
+    record,
+};
+
+use crate::{
+    commands::utils::error_text,
+    engine::get_mcp_client_manager_sync,
+    util::{glob::glob_matches, prompt},
+};
+
+/// Add the server names and `--all` to a command's signature
+pub fn add_target_args(signature: Signature, action: &str) -> Signature {
+    signature
+        .rest(
+            "servers",
+            SyntaxShape::String,
+            format!("Names or glob patterns of the servers to {action}"),
+        )
+        .switch(
+            "all",
+            format!(
+                "{} every server; needed without names outside the REPL",
+                capitalize(action)
+            ),
+            None,
+        )
+}
+
+fn capitalize(action: &str) -> String {
+    let mut chars = action.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// The servers a command was asked to `action`
+///
+/// Names and patterns are expanded against the registered servers. With
+/// `--all`, every server is picked; with neither, an interactive session
+/// asks and any other is an error.
+pub fn select_targets(
+    engine_state: &EngineState,
+    stack: &mut Stack,
+    call: &Call,
+    action: &str,
+) -> Result<Vec<String>, ShellError> {
+    let span = call.head;
+    let patterns: Vec<Spanned<String>> = call.rest(engine_state, stack, 0)?;
+    let all = call.has_flag(engine_state, stack, "all")?;
+
+    let manager = get_mcp_client_manager_sync();
+    let servers: Vec<String> = manager.get_servers().keys().cloned().collect();
+    let labels: Vec<String> = manager
+        .get_servers()
+        .iter()
+        .map(|(name, server)| match &server.failure {
+            Some(failure) => format!("{name} (not connected: {failure})"),
+            None => name.clone(),
+        })
+        .collect();
+    drop(manager);
+
+    if !patterns.is_empty() {
+        return expand_targets(&patterns, &servers);
+    }
+    if all {
+        return Ok(servers);
+    }
+    if servers.is_empty() {
+        return Err(ShellError::GenericError {
+            error: "No MCP servers are registered".into(),
+            msg: format!("there is nothing to {action}"),
+            span: Some(span),
+            help: None,
+            inner: Vec::new(),
+        });
+    }
+    if !engine_state.is_interactive {
+        return Err(ShellError::GenericError {
+            error: format!("No servers to {action}"),
+            msg: "name the servers, or pass --all for every one".into(),
+            span: Some(span),
+            help: Some("A server list is only offered in an interactive session".into()),
+            inner: Vec::new(),
+        });
+    }
+
+    let picked = prompt::choose_many(&format!("Servers to {action}:"), &labels).map_err(|err| {
+        ShellError::GenericError {
+            error: "Failed to read the selection".into(),
+            msg: err.to_string(),
+            span: Some(span),
+            help: None,
+            inner: Vec::new(),
+        }
+    })?;
+    if picked.is_empty() {
+        return Err(ShellError::GenericError {
+            error: "No servers were picked".into(),
+            msg: "cancelled at the prompt".into(),
+            span: Some(span),
+            help: None,
+            inner: Vec::new(),
+        });
+    }
+
+    Ok(picked
+        .into_iter()
+        .map(|index| servers[index].clone())
+        .collect())
+}
+
+/// The servers named by `patterns`, in the order they're registered
+///
+/// A pattern with `*` or `?` is a glob; anything else has to be a server's
+/// exact name. It's an error if any pattern matches no server.
+pub fn expand_targets(
+    patterns: &[Spanned<String>],
+    servers: &[String],
+) -> Result<Vec<String>, ShellError> {
+    let mut picked = vec![false; servers.len()];
+
+    for pattern in patterns {
+        let is_glob = pattern.item.contains(['*', '?']);
+        let mut matched = false;
+        for (index, server) in servers.iter().enumerate() {
+            let matches = if is_glob {
+                glob_matches(&pattern.item, server)
+            } else {
+                pattern.item == *server
+            };
+            if matches {
+                picked[index] = true;
+                matched = true;
+            }
+        }
+
+        if !matched {
+            return Err(ShellError::GenericError {
+                error: if is_glob {
+                    format!("No MCP server matches '{}'", pattern.item)
+                } else {
+                    format!("Unknown MCP server '{}'", pattern.item)
+                },
+                msg: if is_glob {
+                    "the pattern matches no registered server".into()
+                } else {
+                    "no server with this name is registered".into()
+                },
+                span: Some(pattern.span),
+                help: Some("Run `mcp list` to see the servers".into()),
+                inner: Vec::new(),
+            });
+        }
+    }
+
+    Ok(servers
+        .iter()
+        .zip(picked)
+        .filter_map(|(server, picked)| picked.then(|| server.clone()))
+        .collect())
+}
+
+/// A row per server: `ok` with what was done, or `failed` with why
+pub fn summary(results: Vec<(String, Result<String, ShellError>)>, span: Span) -> Value {
+    let rows = results
+        .into_iter()
+        .map(|(server, result)| {
+            let (status, detail) = match result {
+                Ok(detail) => ("ok", detail),
+                Err(err) => ("failed", error_text(&err)),
+            };
+            Value::record(
+                record! {
+                    "server" => Value::string(server, span),
+                    "status" => Value::string(status, span),
+                    "detail" => Value::string(detail, span),
+                },
+                span,
+            )
+        })
+        .collect();
+
+    Value::list(rows, span)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(patterns: &[&str]) -> Vec<Spanned<String>> {
+        patterns
+            .iter()
+            .map(|pattern| Spanned {
+                item: (*pattern).to_string(),
+                span: Span::test_data(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_expand_targets() {
+        let servers: Vec<String> = ["proj-api", "github", "proj-db", "proj"]
+            .map(String::from)
+            .to_vec();
+
+        assert_eq!(
+            expand_targets(&patterns(&["proj-*"]), &servers).unwrap(),
+            ["proj-api", "proj-db"]
+        );
+        assert_eq!(
+            expand_targets(&patterns(&["proj-db", "github", "proj-?b"]), &servers).unwrap(),
+            ["github", "proj-db"]
+        );
+        assert_eq!(
+            expand_targets(&patterns(&["proj*"]), &servers).unwrap(),
+            ["proj-api", "proj-db", "proj"]
+        );
+
+        let err = expand_targets(&patterns(&["github", "gitlab"]), &servers).unwrap_err();
+        assert!(
+            format!("{err:?}").contains("Unknown MCP server 'gitlab'"),
+            "{err:?}"
+        );
+        let err = expand_targets(&patterns(&["web-*"]), &servers).unwrap_err();
+        assert!(
+            format!("{err:?}").contains("No MCP server matches 'web-*'"),
+            "{err:?}"
+        );
+    }
+
+    #[test]
+    fn test_summary() {
+        let span = Span::test_data();
+        let failure = ShellError::GenericError {
+            error: "Failed to restart 'proj-db'".into(),
+            msg: "connection refused".into(),
+            span: None,
+            help: None,
+            inner: Vec::new(),
+        };
+
+        let table = summary(
+            vec![
+                ("proj-api".into(), Ok("disconnected".into())),
+                ("proj-db".into(), Err(failure)),
+            ],
+            span,
+        );
+
+        assert_eq!(
+            table,
+            Value::test_list(vec![
+                Value::test_record(record! {
+                    "server" => Value::test_string("proj-api"),
+                    "status" => Value::test_string("ok"),
+                    "detail" => Value::test_string("disconnected"),
+                }),
+                Value::test_record(record! {
+                    "server" => Value::test_string("proj-db"),
+                    "status" => Value::test_string("failed"),
+                    "detail" => Value::test_string(
+                        "Failed to restart 'proj-db': connection refused"
+                    ),
+                }),
+            ])
+        );
+    }
+}
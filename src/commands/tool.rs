@@ -3,10 +3,11 @@ use std::sync::Arc;
 use anyhow::Result;
 use nu_engine::CallExt;
 use nu_protocol::{
-    Category, IntoPipelineData, PipelineData, ShellError, Signature, Span, Type, Value,
+    Category, Example, IntoPipelineData, Module, PipelineData, ShellError, Signature, Span,
+    Spanned, SyntaxShape, Type, Value,
     engine::{Call, Command, EngineState, Stack, StateWorkingSet},
 };
-use tokio::runtime::Runtime;
+use rmcp::model::Tool;
 // Command for dynamic tool usage
 #[derive(Clone)]
 pub struct ToolCommand;
@@ -57,14 +58,27 @@ impl Command for ToolListCommand {
     }
 
     fn signature(&self) -> Signature {
-        Signature::build("tool list")
-            .category(Category::Custom("mcp".into()))
-            .switch(
-                "protocol",
-                "Include protocol information for each tool",
-                Some('p'),
-            )
-            .input_output_types(vec![(Type::Any, Type::Table(vec![].into()))])
+        add_output_flag(
+            Signature::build("tool list")
+                .category(Category::Custom("mcp".into()))
+                .switch(
+                    "protocol",
+                    "Include protocol information for each tool",
+                    Some('p'),
+                )
+                .switch(
+                    "full-descriptions",
+                    "Show each description in full rather than its first sentence",
+                    Some('f'),
+                )
+                .named(
+                    "server",
+                    SyntaxShape::String,
+                    "Only list the tools from this server",
+                    Some('s'),
+                )
+                .input_output_types(vec![(Type::Any, Type::Table(vec![].into()))]),
+        )
     }
 
     fn description(&self) -> &'static str {
@@ -72,7 +86,7 @@ impl Command for ToolListCommand {
     }
 
     fn extra_description(&self) -> &'static str {
-        "Display a list of all registered dynamic commands"
+        "Display a list of all registered dynamic commands, one row per tool sorted by server and then name. The `id` column (`server.tool`) stays the same across runs, so it can be used to pick tools out of the table. Tools their server marks as deprecated have `deprecated` set; `tool list | where deprecated` finds them. The `description` column shows each description's first sentence, cut to `[tools] description_chars` characters, unless --full-descriptions is given; `tool describe` shows the full text."
     }
 
     fn run(
@@ -82,12 +96,91 @@ impl Command for ToolListCommand {
         call: &Call,
         _input: PipelineData,
     ) -> Result<PipelineData, ShellError> {
+        let server: Option<Spanned<String>> = call.get_flag(engine_state, stack, "server")?;
+        let description_chars =
+            description_chars(call.has_flag(engine_state, stack, "full-descriptions")?);
+
         // Use our new implementation that lists only tool namespace commands
-        Ok(list_tool_commands(
+        let tools = list_tool_commands(
             engine_state,
             call,
+            server.as_ref(),
             call.get_flag_span(stack, "protocol"),
-        ))
+            description_chars,
+        )?;
+
+        apply_output_format(engine_state, stack, call, tools)
+    }
+}
+
+/// Command to list the tools one line each, for many tools or narrow terminals
+#[derive(Clone)]
+pub struct ToolLsCommand;
+
+impl Command for ToolLsCommand {
+    fn name(&self) -> &'static str {
+        "tool ls"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("tool ls")
+            .category(Category::Custom("mcp".into()))
+            .named(
+                "server",
+                SyntaxShape::String,
+                "Only list the tools from this server",
+                Some('s'),
+            )
+            .switch(
+                "wide",
+                "Don't cut lines to the terminal width, e.g. for a pager",
+                Some('w'),
+            )
+            .input_output_types(vec![(Type::Nothing, Type::String)])
+    }
+
+    fn description(&self) -> &'static str {
+        "List the tools compactly, one line per tool"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "Each line is `server.tool — first sentence of the description (N params)`, under a heading for each server, sorted like `tool list`. Lines are cut to the terminal width unless --wide is given. Use `tool list` for a table to filter and sort."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "List the github tools",
+                example: "tool ls --server github",
+                result: None,
+            },
+            Example {
+                description: "Page through every tool without cutting lines",
+                example: "tool ls --wide | less",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let server: Option<Spanned<String>> = call.get_flag(engine_state, stack, "server")?;
+        let width = (!call.has_flag(engine_state, stack, "wide")?).then(terminal_width);
+        let use_color = engine_state
+            .get_config()
+            .use_ansi_coloring
+            .get(engine_state);
+
+        let lines = with_listed_tools(engine_state, server.as_ref(), |tools, _| {
+            compact_tool_lines(tools, width, use_color)
+        })?;
+
+        Ok(Value::string(lines, call.head).into_pipeline_data())
     }
 }
 
@@ -97,6 +190,7 @@ pub fn register_dynamic_tool(
     name: &str,
     signature: Signature,
     description: String,
+    extra_description: String,
     run_fn: Box<RunFn>,
 ) {
     // Create a dynamic command that wraps the function
@@ -104,6 +198,7 @@ pub fn register_dynamic_tool(
         name: name.to_string(),
         signature,
         description,
+        extra_description,
         run_fn: Arc::from(run_fn),
     };
 
@@ -111,6 +206,132 @@ pub fn register_dynamic_tool(
     working_set.add_decl(Box::new(command));
 }
 
+/// Register `tool <server>`, which lists the server's tools
+///
+/// Nushell's help shows the server's `tool <server>.<tool>` commands as
+/// subcommands of this one, the way `help str` shows the `str` family. A
+/// server named after a built-in `tool` subcommand gets no such command.
+pub fn register_server_namespace(
+    working_set: &mut StateWorkingSet,
+    server: &str,
+    client: &ReplClient,
+) {
+    let command_name = format!("tool {server}");
+    let description = format!("List the tools from the '{server}' MCP server");
+
+    // Re-registering a server replaces its own command, but nothing else
+    if let Some(existing) = working_set.find_decl(command_name.as_bytes()) {
+        if working_set.get_decl(existing).description() != description {
+            crate::warning!(
+                "'{}' is already a command, so it can't list the tools from '{}'; use `tool list --server {}`",
+                command_name,
+                server,
+                server
+            );
+            return;
+        }
+    }
+
+    let signature = add_output_flag(
+        Signature::build(&command_name)
+            .category(Category::Custom("mcp".into()))
+            .switch(
+                "protocol",
+                "Include protocol information for each tool",
+                Some('p'),
+            )
+            .switch(
+                "full-descriptions",
+                "Show each description in full rather than its first sentence",
+                Some('f'),
+            )
+            .input_output_types(vec![(Type::Nothing, Type::Table(vec![].into()))]),
+    );
+
+    let extra_description = client.instructions().map_or_else(
+        || format!("The same table as `tool list --server {server}`."),
+        |instructions| format!("Server instructions: {instructions}"),
+    );
+
+    let server = server.to_string();
+    let run_fn: Box<RunFn> = Box::new(move |engine_state, stack, call, _input| {
+        let manager = get_mcp_client_manager_sync();
+        let Some(registered) = manager.get_server(&server) else {
+            return Err(ShellError::GenericError {
+                error: format!("Server '{server}' is not connected"),
+                msg: "its tools are no longer available".into(),
+                span: Some(call.head),
+                help: Some("Run `mcp list` to see the connected servers".into()),
+                inner: Vec::new(),
+            });
+        };
+        let trusted = manager.config().trust_policy(&server).is_trusted();
+        let description_chars = if call.has_flag(engine_state, stack, "full-descriptions")? {
+            None
+        } else {
+            manager.config().description_chars()
+        };
+        let rows = tool_list_rows(
+            server_tools([(&server, registered)]),
+            |_| trusted,
+            call.get_flag_span(stack, "protocol"),
+            description_chars,
+            call.head,
+        );
+        drop(manager);
+
+        apply_output_format(
+            engine_state,
+            stack,
+            call,
+            Value::list(rows, call.head).into_pipeline_data(),
+        )
+    });
+
+    register_dynamic_tool(
+        working_set,
+        &command_name,
+        signature,
+        description,
+        extra_description,
+        run_fn,
+    );
+}
+
+/// Register a module named after the server that exports its tool commands
+///
+/// The module's commands are the `tool <server>.<tool>` commands under the
+/// tool's own name, so `use github *` makes `create_issue` available and
+/// `use github` makes `github create_issue` available. A module that isn't
+/// one of these, such as `std`, is left alone.
+pub fn register_server_module(working_set: &mut StateWorkingSet, server: &str, tools: &[&str]) {
+    let prefix = format!("tool {server}.");
+
+    if let Some(existing) = working_set.find_module(server.as_bytes()) {
+        let ours = working_set
+            .get_module(existing)
+            .decls
+            .values()
+            .all(|decl| working_set.get_decl(*decl).name().starts_with(&prefix));
+        if !ours {
+            crate::warning!(
+                "'{}' is already a module, so `use {}` can't import its tools",
+                server,
+                server
+            );
+            return;
+        }
+    }
+
+    let mut module = Module::new(server.as_bytes().to_vec());
+    for tool in tools {
+        if let Some(decl) = working_set.find_decl(format!("{prefix}{tool}").as_bytes()) {
+            module.add_decl(tool.as_bytes().to_vec(), decl);
+        }
+    }
+    working_set.add_module(server, module, Vec::new());
+}
+
 pub type RunFn = dyn Fn(&EngineState, &mut Stack, &Call, PipelineData) -> Result<PipelineData, ShellError>
     + Send
     + Sync
@@ -122,6 +343,7 @@ struct DynamicToolCommand {
     name: String,
     signature: Signature,
     description: String,
+    extra_description: String,
     run_fn: Arc<RunFn>,
 }
 
@@ -138,6 +360,18 @@ impl Command for DynamicToolCommand {
         &self.description
     }
 
+    fn extra_description(&self) -> &str {
+        &self.extra_description
+    }
+
+    fn search_terms(&self) -> Vec<&str> {
+        self.signature
+            .search_terms
+            .iter()
+            .map(String::as_str)
+            .collect()
+    }
+
     fn run(
         &self,
         engine_state: &EngineState,
@@ -149,74 +383,505 @@ impl Command for DynamicToolCommand {
     }
 }
 
-use crate::{engine::EngineStateExt, util::format::json_to_nu};
+use super::{
+    tool_mapper::tool_parameters,
+    utils::{ReplClient, add_output_flag, apply_output_format},
+};
+use crate::{
+    config::LOCAL_TOOLS_SERVER,
+    engine::get_mcp_client_manager_sync,
+    mcp_manager::{RegisteredServer, RegisteredTool},
+    util::{
+        format::{description_summary, first_sentence, json_to_nu, terminal_width, truncate_line},
+        status,
+    },
+};
+
+/// How many characters of each description `tool list` shows, or `None` for
+/// the full text
+fn description_chars(full_descriptions: bool) -> Option<usize> {
+    if full_descriptions {
+        None
+    } else {
+        get_mcp_client_manager_sync().config().description_chars()
+    }
+}
 
 /// List all commands under the tool namespace
 ///
-/// # Panics
-///
-/// Panics if the runtime cannot be initialized
+/// Every row has the same columns, so the table works with `sort-by`,
+/// `group-by server` and `explore`. An empty table is returned when no
+/// tools are registered.
 pub fn list_tool_commands(
     engine_state: &EngineState,
     call: &Call,
+    server: Option<&Spanned<String>>,
     protocol: Option<Span>,
-) -> PipelineData {
-    // Get the registered tools from the MCP client manager
-    let rt = Runtime::new().unwrap();
+    description_chars: Option<usize>,
+) -> Result<PipelineData, ShellError> {
+    let values = with_listed_tools(engine_state, server, |tools, is_trusted| {
+        tool_list_rows(tools, is_trusted, protocol, description_chars, call.head)
+    })?;
 
-    let client_manager = rt.block_on(engine_state.get_mcp_client_manager());
-    let servers = client_manager.get_servers();
+    Ok(Value::list(values, call.head).into_pipeline_data())
+}
 
-    let mut values = Vec::new();
-    let mut idx = 0;
+/// Hand the tools shown by `tool list` and `tool ls` to `list`, along with
+/// whether each server is trusted
+///
+/// With `server`, only that server's tools are listed, and it's an error
+/// if no such server is connected.
+fn with_listed_tools<T>(
+    engine_state: &EngineState,
+    server: Option<&Spanned<String>>,
+    list: impl FnOnce(Vec<(&str, &str, ListedTool<'_>)>, &dyn Fn(&str) -> bool) -> T,
+) -> Result<T, ShellError> {
+    let manager = get_mcp_client_manager_sync();
+    let servers = manager.get_servers();
+    let local_tools = manager.local_tools();
+
+    if let Some(server) = server {
+        let is_local = server.item == LOCAL_TOOLS_SERVER && !local_tools.is_empty();
+        if !servers.contains_key(&server.item) && !is_local {
+            return Err(ShellError::GenericError {
+                error: format!("Server '{}' is not connected", server.item),
+                msg: "no connected server has this name".into(),
+                span: Some(server.span),
+                help: Some("Run `mcp list` to see the connected servers".into()),
+                inner: Vec::new(),
+            });
+        }
+    }
 
-    // Create a record for each registered tool
-    for (client_name, server) in servers {
-        for (tool_name, registered_tool) in &server.tools {
-            let tool = &registered_tool.tool;
-            let mut record = nu_protocol::Record::new();
+    let wanted = |name: &str| server.is_none_or(|server| server.item == name);
+    let local = local_tools
+        .iter()
+        .filter(|_| wanted(LOCAL_TOOLS_SERVER))
+        .map(|(name, tool)| (LOCAL_TOOLS_SERVER, name.as_str(), ListedTool::Local(tool)));
+
+    let tools: Vec<_> = server_tools(servers.iter().filter(|(name, _)| wanted(name)))
+        .chain(local)
+        .collect();
+    if tools.is_empty() && engine_state.is_interactive {
+        crate::info!("No registered MCP tools found. Try connecting to an MCP server first.");
+    }
+
+    Ok(list(tools, &|server| {
+        server == LOCAL_TOOLS_SERVER || manager.config().trust_policy(server).is_trusted()
+    }))
+}
+
+/// A tool listed by `tool list` or `tool ls`
+#[derive(Clone, Copy)]
+enum ListedTool<'a> {
+    Server(&'a RegisteredTool),
+    Local(&'a Tool),
+}
 
-            record.push("#", Value::int(i64::from(idx), call.head));
-            idx += 1;
+impl<'a> ListedTool<'a> {
+    const fn tool(self) -> &'a Tool {
+        match self {
+            Self::Server(registered) => &registered.tool,
+            Self::Local(tool) => tool,
+        }
+    }
 
-            // Add the client name for filtering/grouping
-            record.push("client", Value::string(client_name.clone(), call.head));
+    /// Whether the server marked the tool as deprecated; local tools never are
+    const fn is_deprecated(self) -> bool {
+        match self {
+            Self::Server(registered) => registered.deprecation.is_some(),
+            Self::Local(_) => false,
+        }
+    }
 
-            // The fully qualified tool name (client.tool format)
-            record.push("name", Value::string(tool_name, call.head));
+    /// The input schema for the `protocol` column
+    ///
+    /// A server tool's schema is converted once and kept; local tools
+    /// declare only a few parameters, so theirs is converted each time.
+    fn schema(self, span: Span) -> Value {
+        match self {
+            Self::Server(registered) => registered.raw_schema().clone(),
+            Self::Local(tool) => json_to_nu(&tool.schema_as_json_value(), Some(span)),
+        }
+    }
+}
 
-            // Add description if available
-            if let Some(desc) = &tool.description {
-                record.push("description", Value::string(desc.clone(), call.head));
-            } else {
-                record.push("description", Value::string("", call.head));
-            }
+/// The tools of each server, as `(server, tool, registration)`
+fn server_tools<'a>(
+    servers: impl IntoIterator<Item = (&'a String, &'a RegisteredServer)>,
+) -> impl Iterator<Item = (&'a str, &'a str, ListedTool<'a>)> {
+    servers.into_iter().flat_map(|(server_name, server)| {
+        server.tools.iter().map(move |(tool_name, registered)| {
+            (
+                server_name.as_str(),
+                tool_name.as_str(),
+                ListedTool::Server(registered),
+            )
+        })
+    })
+}
+
+/// Build one row per tool, sorted by server and then tool name
+///
+/// The `id` column (`server.tool`) names the tool the same way across runs,
+/// unlike a row index. The `trusted` column is the server's `trusted`
+/// setting, as told by `is_trusted`, and `deprecated` says whether the
+/// server marked the tool as deprecated. Schemas are only looked at for the
+/// `protocol` column, so listing without it doesn't depend on their size.
+/// Descriptions are summarized to `description_chars`, when it's given.
+fn tool_list_rows<'a>(
+    tools: impl IntoIterator<Item = (&'a str, &'a str, ListedTool<'a>)>,
+    is_trusted: impl Fn(&str) -> bool,
+    protocol: Option<Span>,
+    description_chars: Option<usize>,
+    span: Span,
+) -> Vec<Value> {
+    sorted_tools(tools)
+        .into_iter()
+        .map(|(server_name, tool_name, listed)| {
+            let tool = listed.tool();
+            let mut record = nu_protocol::Record::new();
+
+            record.push(
+                "id",
+                Value::string(format!("{server_name}.{tool_name}"), span),
+            );
+            record.push("server", Value::string(server_name, span));
+            record.push("name", Value::string(tool_name, span));
+            let description = tool.description.as_deref().unwrap_or_default();
+            record.push(
+                "description",
+                Value::string(
+                    description_chars.map_or_else(
+                        || description.to_string(),
+                        |chars| description_summary(description, chars),
+                    ),
+                    span,
+                ),
+            );
+            record.push("trusted", Value::bool(is_trusted(server_name), span));
+            record.push("deprecated", Value::bool(listed.is_deprecated(), span));
 
             if let Some(protocol) = protocol {
                 record.push(
                     "protocol",
-                    json_to_nu(&tool.schema_as_json_value(), Some(protocol)),
+                    match listed.schema(protocol) {
+                        value @ Value::Record { .. } => value,
+                        _ => Value::nothing(protocol),
+                    },
                 );
             }
 
-            values.push(Value::record(record, call.head));
+            Value::record(record, span)
+        })
+        .collect()
+}
+
+/// One line per tool, `server.tool — first sentence (N params)`, under a
+/// heading for each server
+///
+/// Lines are cut to `width` characters, unless it's `None`, and headings
+/// are bold with `use_color`.
+fn compact_tool_lines<'a>(
+    tools: impl IntoIterator<Item = (&'a str, &'a str, ListedTool<'a>)>,
+    width: Option<usize>,
+    use_color: bool,
+) -> String {
+    let mut lines = Vec::new();
+    let mut heading = None;
+
+    for (server_name, tool_name, listed) in sorted_tools(tools) {
+        if heading != Some(server_name) {
+            if heading.is_some() {
+                lines.push(String::new());
+            }
+            lines.push(status::heading(server_name, use_color));
+            heading = Some(server_name);
         }
+
+        let tool = listed.tool();
+        let params = match tool_parameters(tool).len() {
+            1 => "1 param".to_string(),
+            count => format!("{count} params"),
+        };
+        let summary = first_sentence(tool.description.as_deref().unwrap_or_default());
+        let line = if summary.is_empty() {
+            format!("  {server_name}.{tool_name} ({params})")
+        } else {
+            format!("  {server_name}.{tool_name} — {summary} ({params})")
+        };
+
+        lines.push(match width {
+            Some(width) => truncate_line(&line, width),
+            None => line,
+        });
     }
 
-    drop(client_manager);
+    lines.join("\n")
+}
+
+/// The tools sorted by server and then tool name
+fn sorted_tools<'a>(
+    tools: impl IntoIterator<Item = (&'a str, &'a str, ListedTool<'a>)>,
+) -> Vec<(&'a str, &'a str, ListedTool<'a>)> {
+    let mut tools: Vec<_> = tools.into_iter().collect();
+    tools.sort_by(|(a_server, a_tool, _), (b_server, b_tool, _)| {
+        a_server.cmp(b_server).then_with(|| a_tool.cmp(b_tool))
+    });
+    tools
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::mcp_manager::{
+        McpClientManager,
+        tests::{mock_client, mock_client_with_schema},
+    };
+
+    #[test]
+    fn test_tool_list_columns_and_order() {
+        let mut engine_state = EngineState::new();
+        let mut manager = McpClientManager::default();
+
+        for (name, tools) in [("web", &["search", "fetch"]), ("fs", &["write", "read"])] {
+            manager
+                .register_client(
+                    name.to_string(),
+                    &mock_client(name, tools),
+                    &mut engine_state,
+                )
+                .unwrap();
+        }
 
-    if values.is_empty() {
-        // Add a message when no tools are found
-        let mut record = nu_protocol::Record::new();
-        record.push(
-            "message",
-            Value::string(
-                "No registered MCP tools found. Try connecting to an MCP server first.",
-                call.head,
+        let rows = tool_list_rows(
+            server_tools(manager.get_servers()),
+            |server| server == "fs",
+            None,
+            None,
+            Span::test_data(),
+        );
+
+        let columns: Vec<_> = rows[0]
+            .as_record()
+            .unwrap()
+            .columns()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                "id",
+                "server",
+                "name",
+                "description",
+                "trusted",
+                "deprecated"
+            ]
+        );
+
+        let ids: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                row.get_data_by_key("id")
+                    .unwrap()
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(ids, vec!["fs.read", "fs.write", "web.fetch", "web.search"]);
+        let trusted: Vec<_> = rows
+            .iter()
+            .map(|row| row.get_data_by_key("trusted").unwrap().as_bool().unwrap())
+            .collect();
+        assert_eq!(trusted, vec![true, true, false, false]);
+
+        let rows = tool_list_rows(
+            server_tools(manager.get_servers()),
+            |_| false,
+            Some(Span::test_data()),
+            None,
+            Span::test_data(),
+        );
+        assert!(
+            rows.iter()
+                .all(|row| matches!(row.get_data_by_key("protocol"), Some(Value::Record { .. })))
+        );
+    }
+
+    #[test]
+    fn test_tool_list_summarizes_descriptions() {
+        let mut engine_state = EngineState::new();
+        let mut manager = McpClientManager::default();
+        manager
+            .register_client(
+                "fs".to_string(),
+                &mock_client("fs", &["read"]),
+                &mut engine_state,
+            )
+            .unwrap();
+
+        let description = |chars| {
+            tool_list_rows(
+                server_tools(manager.get_servers()),
+                |_| false,
+                None,
+                chars,
+                Span::test_data(),
+            )[0]
+            .get_data_by_key("description")
+            .unwrap()
+        };
+        assert_eq!(description(Some(6)), Value::test_string("A moc…"));
+        assert_eq!(description(None), Value::test_string("A mock tool"));
+    }
+
+    #[test]
+    fn test_tool_list_flags_deprecated_tools() {
+        let mut engine_state = EngineState::new();
+        let mut manager = McpClientManager::default();
+        let clients = [
+            mock_client("new", &["search"]),
+            mock_client_with_schema(
+                "old",
+                &["search"],
+                json!({"type": "object", "x-deprecated": "new.search"}),
             ),
+        ];
+        for client in &clients {
+            manager
+                .register_client(client.name.clone(), client, &mut engine_state)
+                .unwrap();
+        }
+
+        let rows = tool_list_rows(
+            server_tools(manager.get_servers()),
+            |_| false,
+            None,
+            None,
+            Span::test_data(),
+        );
+        let deprecated: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                row.get_data_by_key("deprecated")
+                    .unwrap()
+                    .as_bool()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(deprecated, vec![false, true]);
+    }
+
+    #[test]
+    fn test_compact_tool_lines() {
+        let mut engine_state = EngineState::new();
+        let mut manager = McpClientManager::default();
+        let clients = [
+            mock_client_with_schema(
+                "web",
+                &["search"],
+                json!({"type": "object", "properties": {"query": {"type": "string"}}}),
+            ),
+            mock_client("fs", &["write", "read"]),
+        ];
+        for client in &clients {
+            manager
+                .register_client(client.name.clone(), client, &mut engine_state)
+                .unwrap();
+        }
+
+        let wide = compact_tool_lines(server_tools(manager.get_servers()), None, false);
+        assert_eq!(
+            wide,
+            "fs\n  fs.read — A mock tool (0 params)\n  fs.write — A mock tool (0 params)\n\nweb\n  web.search — A mock tool (1 param)"
         );
-        values.push(Value::record(record, call.head));
+
+        let narrow = compact_tool_lines(server_tools(manager.get_servers()), Some(12), true);
+        let lines: Vec<_> = narrow.lines().collect();
+        assert_eq!(lines[0], status::heading("fs", true));
+        assert_eq!(lines[1], "  fs.read —…");
     }
 
-    Value::list(values, call.head).into_pipeline_data()
+    #[test]
+    fn test_tool_list_converts_schemas_only_for_protocol() {
+        let mut engine_state = EngineState::new();
+        let mut manager = McpClientManager::default();
+
+        // Many tools with large schemas, as some servers have
+        let properties: serde_json::Map<_, _> = (0..200)
+            .map(|i| {
+                (
+                    format!("param{i}"),
+                    json!({"type": "string", "description": "x"}),
+                )
+            })
+            .collect();
+        let names: Vec<String> = (0..250).map(|i| format!("tool{i}")).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let client = mock_client_with_schema(
+            "big",
+            &names,
+            json!({"type": "object", "properties": properties}),
+        );
+        manager
+            .register_client("big".to_string(), &client, &mut engine_state)
+            .unwrap();
+
+        let converted = |manager: &McpClientManager| {
+            manager.get_servers()["big"]
+                .tools
+                .values()
+                .filter(|tool| tool.raw_schema.get().is_some())
+                .count()
+        };
+        assert_eq!(converted(&manager), 0, "registration converted schemas");
+
+        let rows = tool_list_rows(
+            server_tools(manager.get_servers()),
+            |_| false,
+            None,
+            None,
+            Span::test_data(),
+        );
+        assert_eq!(rows.len(), 250);
+        assert_eq!(converted(&manager), 0, "listing converted schemas");
+
+        tool_list_rows(
+            server_tools(manager.get_servers()),
+            |_| false,
+            Some(Span::test_data()),
+            None,
+            Span::test_data(),
+        );
+        assert_eq!(converted(&manager), 250);
+    }
+
+    #[test]
+    fn test_server_namespace_commands() {
+        let mut engine_state = EngineState::new();
+        let mut working_set = StateWorkingSet::new(&engine_state);
+        working_set.add_decl(Box::new(ToolListCommand));
+
+        let client = mock_client("fs", &["read"]);
+        register_server_namespace(&mut working_set, "fs", &client);
+        register_server_namespace(&mut working_set, "fs", &client);
+        register_server_namespace(&mut working_set, "list", &mock_client("list", &[]));
+
+        let delta = working_set.render();
+        engine_state.merge_delta(delta).unwrap();
+
+        let description = |name: &str| {
+            let decl = engine_state.find_decl(name.as_bytes(), &[]).unwrap();
+            engine_state.get_decl(decl).description().to_string()
+        };
+        assert_eq!(
+            description("tool fs"),
+            "List the tools from the 'fs' MCP server"
+        );
+        assert_eq!(description("tool list"), ToolListCommand.description());
+    }
 }
@@ -0,0 +1,393 @@
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, PipelineData, ShellError, Signature, Span, Spanned, SyntaxShape, Type,
+    Value,
+    ast::CellPath,
+    engine::{Call, Command, EngineState, Stack},
+};
+use serde_json::Value as JsonValue;
+
+use super::{
+    mcp_tools::{CallOptions, follow_get_path, run_tool_call},
+    tool_mapper::{GET_FLAG, KEEP_EMPTY_SWITCH},
+    utils::{add_output_flag, apply_output_format, convert_nu_value_to_json_value},
+};
+use crate::{
+    config::ResultFormat,
+    engine::get_mcp_client_manager_sync,
+    util::{annotations::Audience, args_file::with_args_file},
+};
+
+/// Command to call an MCP tool by server and tool name
+#[derive(Clone)]
+pub struct ToolCallCommand;
+
+impl Command for ToolCallCommand {
+    fn name(&self) -> &'static str {
+        "tool call"
+    }
+
+    fn signature(&self) -> Signature {
+        let signature = Signature::build("tool call")
+            .category(Category::Custom("mcp".into()))
+            .required("server", SyntaxShape::String, "The name of the MCP server")
+            .required("tool", SyntaxShape::String, "The name of the tool to call")
+            .optional(
+                "args",
+                SyntaxShape::Record(vec![]),
+                "A record of arguments to pass to the tool",
+            )
+            .named(
+                "json",
+                SyntaxShape::String,
+                "The tool arguments as a JSON object string",
+                Some('j'),
+            )
+            .named(
+                "args-file",
+                SyntaxShape::Filepath,
+                "Read the arguments from a json, nuon, toml or yaml file (explicit arguments win)",
+                None,
+            )
+            .switch(
+                "try",
+                "Return {ok: true, data} or {ok: false, error} instead of raising an error",
+                None,
+            )
+            .switch(
+                "lines",
+                "Split text results into a list of lines (a table if every line is a JSON object)",
+                None,
+            )
+            .switch(
+                "full",
+                "Return the whole result, even blocks larger than `max_result_bytes`",
+                None,
+            )
+            .switch(
+                KEEP_EMPTY_SWITCH,
+                "Return an empty or whitespace-only text result as that text instead of nothing",
+                None,
+            )
+            .named(
+                GET_FLAG,
+                SyntaxShape::CellPath,
+                "Return only this cell path of the result, e.g. items.0.name",
+                None,
+            );
+
+        add_output_flag(signature).input_output_types(vec![
+            (Type::Nothing, Type::Any),
+            (Type::Record(vec![].into()), Type::Any),
+        ])
+    }
+
+    fn description(&self) -> &'static str {
+        "Call an MCP tool with a record of arguments"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "Arguments can be passed as a record, piped in as a record, or given as JSON text with --json. Only one source of arguments may be used per call. Arguments from --args-file are merged under whichever source is used.
+
+--get follows a cell path into the result after it's converted and before --output renders it, so `--get items.0 --output json` is the JSON of the first item. With --try, the path is followed into `data` and failures are returned whole. Generated tool commands take --get too."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Call a tool with an inline record of arguments",
+                example: "tool call github create_issue {title: \"x\"}",
+                result: None,
+            },
+            Example {
+                description: "Use a piped record as the arguments",
+                example: "open payload.json | tool call github create_issue",
+                result: None,
+            },
+            Example {
+                description: "Collect failures instead of aborting the pipeline",
+                example: "$issues | each { tool call github create_issue $in --try } | where ok == false",
+                result: None,
+            },
+            Example {
+                description: "Get a log-style result as one row per line",
+                example: "tool call github search_code {q: \"todo\"} --lines | first 20",
+                result: None,
+            },
+            Example {
+                description: "Load a large payload from a file and override one field",
+                example: "tool call deploy apply {replicas: 3} --args-file spec.yaml",
+                result: None,
+            },
+            Example {
+                description: "Return just one field of the result",
+                example: "tool call github get_repo {repo: \"nushell/nushell\"} --get stargazers_count",
+                result: None,
+            },
+            Example {
+                description: "Pass the arguments as JSON text",
+                example: "tool call github create_issue --json '{\"title\": \"x\"}'",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let server_name: Spanned<String> = call.req(engine_state, stack, 0)?;
+        let tool_name: Spanned<String> = call.req(engine_state, stack, 1)?;
+        let args: Option<Value> = call.opt(engine_state, stack, 2)?;
+        let json: Option<Spanned<String>> = call.get_flag(engine_state, stack, "json")?;
+        let args_file: Option<Spanned<String>> = call.get_flag(engine_state, stack, "args-file")?;
+        let try_mode = call.has_flag(engine_state, stack, "try")?;
+        let lines = call.has_flag(engine_state, stack, "lines")?;
+        let full = call.has_flag(engine_state, stack, "full")?;
+        let keep_empty = call.has_flag(engine_state, stack, KEEP_EMPTY_SWITCH)?;
+        let get: Option<CellPath> = call.get_flag(engine_state, stack, GET_FLAG)?;
+
+        let piped = match input {
+            PipelineData::Empty => None,
+            other => match other.into_value(span)? {
+                Value::Nothing { .. } => None,
+                value => Some(value),
+            },
+        };
+
+        let params = collect_call_args(args, piped, json, span)
+            .and_then(|params| with_args_file(engine_state, stack, args_file.as_ref(), params));
+
+        let manager = get_mcp_client_manager_sync();
+        let Some(server) = manager.get_server(&server_name.item) else {
+            return Err(ShellError::GenericError {
+                error: format!("Unknown MCP server '{}'", server_name.item),
+                msg: "no server with this name is connected".into(),
+                span: Some(server_name.span),
+                help: Some("Run `tool list` to see the connected servers and their tools".into()),
+                inner: Vec::new(),
+            });
+        };
+
+        let registered = server.tools.get(&tool_name.item).cloned();
+        drop(manager);
+
+        // Calls go through the same validation as the generated tool commands
+        let Some(registered) = registered else {
+            return Err(ShellError::GenericError {
+                error: format!(
+                    "Unknown tool '{}' on server '{}'",
+                    tool_name.item, server_name.item
+                ),
+                msg: "the server did not report a tool with this name".into(),
+                span: Some(tool_name.span),
+                help: Some("Run `tool list` to see the available tools".into()),
+                inner: Vec::new(),
+            });
+        };
+
+        let settings = if lines {
+            registered.settings.with_format(ResultFormat::Lines)
+        } else {
+            registered.settings.clone()
+        };
+        let settings = if full {
+            settings.without_result_limit()
+        } else {
+            settings
+        };
+        let settings = if keep_empty {
+            settings.keeping_empty()
+        } else {
+            settings
+        };
+
+        let options = CallOptions {
+            settings: &settings,
+            try_mode,
+            all_pages: None,
+            follow_links: false,
+            raw: false,
+            audience: Audience::All,
+            original: None,
+        };
+        let result = run_tool_call(
+            engine_state,
+            stack,
+            &registered.client,
+            &registered.tool,
+            params,
+            options,
+            span,
+        )?;
+        drop(registered);
+        let result = follow_get_path(result, get.as_ref(), try_mode, span)?;
+
+        apply_output_format(engine_state, stack, call, result)
+    }
+}
+
+/// Resolve the single source of arguments for a call
+///
+/// Arguments can come from the positional record, the pipeline, or the
+/// `--json` flag. Supplying more than one of them is an error, and supplying
+/// none of them means the tool is called without arguments.
+pub fn collect_call_args(
+    args: Option<Value>,
+    piped: Option<Value>,
+    json: Option<Spanned<String>>,
+    span: Span,
+) -> Result<serde_json::Map<String, JsonValue>, ShellError> {
+    let sources = [args.is_some(), piped.is_some(), json.is_some()]
+        .iter()
+        .filter(|present| **present)
+        .count();
+
+    if sources > 1 {
+        return Err(ShellError::GenericError {
+            error: "Multiple sources of tool arguments".into(),
+            msg: "arguments were given in more than one way".into(),
+            span: Some(span),
+            help: Some(
+                "Pass arguments as a positional record, a piped record, or --json, but not more than one"
+                    .into(),
+            ),
+            inner: Vec::new(),
+        });
+    }
+
+    if let Some(json) = json {
+        let parsed: JsonValue =
+            serde_json::from_str(&json.item).map_err(|err| ShellError::GenericError {
+                error: "Invalid JSON arguments".into(),
+                msg: err.to_string(),
+                span: Some(json.span),
+                help: Some("The --json flag expects a JSON object".into()),
+                inner: Vec::new(),
+            })?;
+
+        return match parsed {
+            JsonValue::Object(map) => Ok(map),
+            other => Err(ShellError::GenericError {
+                error: "Invalid JSON arguments".into(),
+                msg: format!("expected a JSON object, got {other}"),
+                span: Some(json.span),
+                help: Some("The --json flag expects a JSON object".into()),
+                inner: Vec::new(),
+            }),
+        };
+    }
+
+    args.or(piped).map_or_else(
+        || Ok(serde_json::Map::new()),
+        |value| record_to_params(&value, span),
+    )
+}
+
+/// Convert a Nushell record into a JSON arguments object
+pub fn record_to_params(
+    value: &Value,
+    span: Span,
+) -> Result<serde_json::Map<String, JsonValue>, ShellError> {
+    let value_span = value.span();
+
+    match value {
+        Value::Record { .. } => match convert_nu_value_to_json_value(value, span)? {
+            JsonValue::Object(map) => Ok(map),
+            _ => Ok(serde_json::Map::new()),
+        },
+        other => Err(ShellError::OnlySupportsThisInputType {
+            exp_input_type: "record".into(),
+            wrong_type: other.get_type().to_string(),
+            dst_span: span,
+            src_span: value_span,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nu_protocol::record;
+
+    use super::*;
+
+    fn json_flag(text: &str) -> Option<Spanned<String>> {
+        Some(Spanned {
+            item: text.to_string(),
+            span: Span::test_data(),
+        })
+    }
+
+    #[test]
+    fn test_collect_call_args_rejects_multiple_sources() {
+        let span = Span::test_data();
+        let record = Value::test_record(record! { "a" => Value::test_int(1) });
+
+        for (args, piped, json) in [
+            (Some(record.clone()), Some(record.clone()), None),
+            (Some(record.clone()), None, json_flag(r#"{"a": 1}"#)),
+            (None, Some(record.clone()), json_flag(r#"{"a": 1}"#)),
+        ] {
+            let Err(ShellError::GenericError { error, .. }) =
+                collect_call_args(args, piped, json, span)
+            else {
+                panic!("expected an error for more than one argument source");
+            };
+            assert_eq!(error, "Multiple sources of tool arguments");
+        }
+    }
+
+    #[test]
+    fn test_collect_call_args_requires_a_json_object() {
+        let span = Span::test_data();
+
+        for text in ["[1, 2]", "42", r#""text""#] {
+            let Err(ShellError::GenericError { error, msg, .. }) =
+                collect_call_args(None, None, json_flag(text), span)
+            else {
+                panic!("expected an error for --json {text}");
+            };
+            assert_eq!(error, "Invalid JSON arguments");
+            assert!(msg.starts_with("expected a JSON object"), "{msg}");
+        }
+
+        let Err(ShellError::GenericError { error, .. }) =
+            collect_call_args(None, None, json_flag("{not json"), span)
+        else {
+            panic!("expected an error for malformed --json");
+        };
+        assert_eq!(error, "Invalid JSON arguments");
+
+        let params = collect_call_args(None, None, json_flag(r#"{"a": [1]}"#), span).unwrap();
+        assert_eq!(params["a"], serde_json::json!([1]));
+    }
+
+    #[test]
+    fn test_collect_call_args_takes_a_piped_record() {
+        let span = Span::test_data();
+        let piped = Value::test_record(record! {
+            "path" => Value::test_string("/tmp"),
+            "depth" => Value::test_int(2),
+        });
+
+        let params = collect_call_args(None, Some(piped), None, span).unwrap();
+        assert_eq!(
+            JsonValue::Object(params),
+            serde_json::json!({"path": "/tmp", "depth": 2})
+        );
+
+        assert!(matches!(
+            collect_call_args(None, Some(Value::test_int(1)), None, span),
+            Err(ShellError::OnlySupportsThisInputType { .. })
+        ));
+        assert!(
+            collect_call_args(None, None, None, span)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
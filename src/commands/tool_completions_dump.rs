@@ -0,0 +1,220 @@
+use nu_protocol::{
+    Category, Example, IntoPipelineData, PipelineData, Record, ShellError, Signature, Span, Type,
+    Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+use rmcp::model::Tool;
+use serde_json::Value as JsonValue;
+
+use super::{
+    tool_mapper::{self, tool_parameters},
+    utils::{add_output_flag, apply_output_format},
+};
+use crate::engine::get_mcp_client_manager_sync;
+
+/// Command to list the tool commands and their flags for an external completer
+#[derive(Clone)]
+pub struct ToolCompletionsDumpCommand;
+
+impl Command for ToolCompletionsDumpCommand {
+    fn name(&self) -> &'static str {
+        "tool completions-dump"
+    }
+
+    fn signature(&self) -> Signature {
+        add_output_flag(
+            Signature::build("tool completions-dump").category(Category::Custom("mcp".into())),
+        )
+        .input_output_types(vec![(Type::Nothing, Type::List(Box::new(Type::Any)))])
+    }
+
+    fn description(&self) -> &'static str {
+        "List the registered tool commands and their flags for an external completer"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "Each row has the command, its description and its flags. Each flag has a `value` (`--name`) and `description`, the form Nushell's external completers return, along with its short form, type, whether it's required and, for parameters with an `enum`, the `candidates` it accepts. Use --output json to hand the list to a completer such as carapace.\n\nA completer that keeps running can listen on a unix socket and set $env.MCP_COMPLETER_SOCKET to its path before the REPL starts. Whenever the registered tools change, after a restart, a profile switch or a server's tool list changing, a line like {\"event\":\"tools_changed\",\"commands\":42} is written to it, and the completer should run this command again."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Dump the tool commands as JSON",
+                example: "tool completions-dump --output json",
+                result: None,
+            },
+            Example {
+                description: "List the values a tool's flags accept",
+                example: "tool completions-dump | where command == \"tool github.list_issues\" | get flags.0",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let manager = get_mcp_client_manager_sync();
+
+        let rows = manager
+            .get_servers()
+            .iter()
+            .flat_map(|(server_name, server)| {
+                server.tools.values().map(move |registered| {
+                    completion_entry(
+                        &format!("tool {server_name}.{}", registered.tool.name),
+                        &registered.tool,
+                        registered.settings.max_flags,
+                        span,
+                    )
+                })
+            })
+            .collect();
+        drop(manager);
+
+        apply_output_format(
+            engine_state,
+            stack,
+            call,
+            Value::list(rows, span).into_pipeline_data(),
+        )
+    }
+}
+
+/// The completion row for a tool command: its name, description and flags
+fn completion_entry(command: &str, tool: &Tool, max_flags: usize, span: Span) -> Value {
+    // The same flags the command was registered with
+    let signature = tool_mapper::map_tool_to_signature(tool, "tool", max_flags);
+    let signature = tool_mapper::add_call_flags(signature, tool);
+    let parameters = tool_parameters(tool);
+
+    let flags = signature
+        .named
+        .iter()
+        .map(|flag| {
+            let candidates = parameters
+                .iter()
+                .find(|param| param.name == flag.long)
+                .and_then(|param| param.schema.get("enum"))
+                .and_then(JsonValue::as_array)
+                .map(|choices| {
+                    choices
+                        .iter()
+                        .map(|choice| {
+                            let choice = match choice {
+                                JsonValue::String(choice) => choice.clone(),
+                                other => other.to_string(),
+                            };
+                            Value::string(choice, span)
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            let mut row = Record::new();
+            row.push("value", Value::string(format!("--{}", flag.long), span));
+            row.push(
+                "short",
+                flag.short.map_or_else(
+                    || Value::nothing(span),
+                    |short| Value::string(format!("-{short}"), span),
+                ),
+            );
+            row.push("description", Value::string(flag.desc.clone(), span));
+            row.push(
+                "type",
+                Value::string(
+                    flag.arg
+                        .as_ref()
+                        .map_or_else(|| "switch".into(), ToString::to_string),
+                    span,
+                ),
+            );
+            row.push("required", Value::bool(flag.required, span));
+            row.push("candidates", Value::list(candidates, span));
+            Value::record(row, span)
+        })
+        .collect();
+
+    let mut record = Record::new();
+    record.push("command", Value::string(command, span));
+    record.push(
+        "description",
+        Value::string(
+            tool.description
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            span,
+        ),
+    );
+    record.push("flags", Value::list(flags, span));
+    Value::record(record, span)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_completion_entry() {
+        let JsonValue::Object(schema) = json!({
+            "type": "object",
+            "properties": {
+                "repo": {"type": "string", "description": "The repository"},
+                "state": {"type": "string", "enum": ["open", "closed"]},
+            },
+            "required": ["repo"],
+        }) else {
+            unreachable!()
+        };
+        let tool = Tool::new("list_issues", "List the issues", Arc::new(schema));
+
+        let entry = completion_entry("tool github.list_issues", &tool, 0, Span::test_data());
+        let entry = entry.as_record().unwrap();
+        assert_eq!(
+            entry.get("command").unwrap().as_str().unwrap(),
+            "tool github.list_issues"
+        );
+
+        let flags = entry.get("flags").unwrap().as_list().unwrap();
+        let flag = |name: &str| {
+            flags
+                .iter()
+                .map(|flag| flag.as_record().unwrap())
+                .find(|flag| flag.get("value").unwrap().as_str().unwrap() == name)
+                .unwrap()
+        };
+
+        assert_eq!(
+            flag("--repo").get("description").unwrap().as_str().unwrap(),
+            "The repository"
+        );
+        let candidates: Vec<&str> = flag("--state")
+            .get("candidates")
+            .unwrap()
+            .as_list()
+            .unwrap()
+            .iter()
+            .map(|candidate| candidate.as_str().unwrap())
+            .collect();
+        assert_eq!(candidates, ["open", "closed"]);
+        assert!(
+            flag("--repo")
+                .get("candidates")
+                .unwrap()
+                .as_list()
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
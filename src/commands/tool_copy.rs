@@ -0,0 +1,255 @@
+use std::sync::PoisonError;
+
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, IntoPipelineData, PipelineData, ShellError, Signature, Spanned, SyntaxShape,
+    Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+use rmcp::model::Tool;
+use serde_json::Value as JsonValue;
+
+use super::tool_mapper::{self, ARGS_FLAG, ToolParameter};
+use crate::engine::get_mcp_client_manager_sync;
+
+/// Command to compose a template invocation of a tool
+#[derive(Clone)]
+pub struct ToolCopyCommand;
+
+impl Command for ToolCopyCommand {
+    fn name(&self) -> &'static str {
+        "tool copy"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("tool copy")
+            .category(Category::Custom("mcp".into()))
+            .required(
+                "name",
+                SyntaxShape::String,
+                "The namespaced tool name (server.tool)",
+            )
+            .switch(
+                "clipboard",
+                "Copy the invocation to the system clipboard",
+                Some('c'),
+            )
+            .switch(
+                "print",
+                "Return the invocation instead of putting it on the command line",
+                Some('p'),
+            )
+            .input_output_types(vec![
+                (Type::Nothing, Type::Nothing),
+                (Type::Nothing, Type::String),
+            ])
+    }
+
+    fn description(&self) -> &'static str {
+        "Compose a ready-to-edit invocation of an MCP tool"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "Every required parameter gets a placeholder for its type: a quoted \"<name>\" for strings, 0 for numbers, false for booleans, [] for lists and {} for records. In the REPL the invocation replaces the command line, ready to edit; otherwise it is returned. The clipboard needs a build with the `clipboard` feature."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Put a template call on the command line",
+                example: "tool copy github.create_issue",
+                result: None,
+            },
+            Example {
+                description: "Copy a template call to the clipboard",
+                example: "tool copy github.create_issue --clipboard",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let name: Spanned<String> = call.req(engine_state, stack, 0)?;
+        let clipboard = call.has_flag(engine_state, stack, "clipboard")?;
+        let print = call.has_flag(engine_state, stack, "print")?;
+
+        let manager = get_mcp_client_manager_sync();
+        let Some((_, registered)) = manager.find_tool(&name.item) else {
+            return Err(ShellError::GenericError {
+                error: format!("Unknown tool '{}'", name.item),
+                msg: "no registered tool has this name".into(),
+                span: Some(name.span),
+                help: Some(
+                    "Tool names have the form server.tool; run `tool list` to see them".into(),
+                ),
+                inner: Vec::new(),
+            });
+        };
+        let invocation = template_invocation(&format!("tool {}", name.item), &registered.tool);
+        drop(manager);
+
+        if clipboard {
+            copy_to_clipboard(&invocation).map_err(|msg| ShellError::GenericError {
+                error: "Failed to copy to the clipboard".into(),
+                msg,
+                span: Some(span),
+                help: Some("Use --print to get the invocation as a string instead".into()),
+                inner: Vec::new(),
+            })?;
+            crate::success!("Copied: {}", invocation);
+            return Ok(PipelineData::empty());
+        }
+
+        if engine_state.is_interactive && !print {
+            // Like `commandline edit --replace`, the REPL shows this at the next prompt
+            let mut repl = engine_state
+                .repl_state
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            repl.cursor_pos = invocation.len();
+            repl.buffer = invocation;
+            return Ok(PipelineData::empty());
+        }
+
+        Ok(Value::string(invocation, span).into_pipeline_data())
+    }
+}
+
+/// A call of `command_name` with a placeholder for every required parameter
+///
+/// Parameters are placed the way the generated command takes them:
+/// positionally when the tool mapper maps them onto positional arguments,
+/// and as flags otherwise.
+pub fn template_invocation(command_name: &str, tool: &Tool) -> String {
+    let mut words = vec![command_name.to_string()];
+
+    if tool_mapper::declares_no_schema(tool) {
+        words.push(format!("--{ARGS_FLAG} {{}}"));
+        return words.join(" ");
+    }
+
+    let positionals = tool_mapper::positional_parameters(tool);
+    let required: Vec<ToolParameter> = tool_mapper::tool_parameters(tool)
+        .into_iter()
+        .filter(|param| param.required)
+        .collect();
+
+    for name in &positionals {
+        if let Some(param) = required.iter().find(|param| param.name == *name) {
+            words.push(placeholder(param));
+        }
+    }
+
+    for param in required
+        .iter()
+        .filter(|param| !positionals.contains(&param.name))
+    {
+        words.push(format!("--{} {}", param.name, placeholder(param)));
+    }
+
+    words.join(" ")
+}
+
+/// A Nushell literal standing in for a parameter's value
+fn placeholder(param: &ToolParameter) -> String {
+    if let Some(first) = param
+        .schema
+        .get("enum")
+        .and_then(JsonValue::as_array)
+        .and_then(|choices| choices.first())
+    {
+        return first.to_string();
+    }
+
+    let schema_type = match param.schema.get("type") {
+        Some(JsonValue::String(schema_type)) => Some(schema_type.as_str()),
+        Some(JsonValue::Array(types)) => types
+            .iter()
+            .filter_map(JsonValue::as_str)
+            .find(|schema_type| *schema_type != "null"),
+        _ => None,
+    };
+
+    match schema_type {
+        Some("integer" | "number") => "0".into(),
+        Some("boolean") => "false".into(),
+        Some("array") => "[]".into(),
+        Some("object") => "{}".into(),
+        _ => format!("\"<{}>\"", param.name),
+    }
+}
+
+/// Put `text` on the system clipboard, in builds with the `clipboard` feature
+#[cfg(feature = "clipboard")]
+pub fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text.to_string()))
+        .map_err(|err| err.to_string())
+}
+
+/// Put `text` on the system clipboard, in builds with the `clipboard` feature
+#[cfg(not(feature = "clipboard"))]
+pub fn copy_to_clipboard(_text: &str) -> Result<(), String> {
+    Err("this build has no clipboard support; rebuild with `--features clipboard`".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn tool(schema: JsonValue) -> Tool {
+        let JsonValue::Object(schema) = schema else {
+            unreachable!();
+        };
+        Tool::new("create_issue", "Create an issue", Arc::new(schema))
+    }
+
+    #[test]
+    fn test_template_invocation() {
+        let positional = tool(json!({
+            "type": "object",
+            "properties": {
+                "body": {"type": "string"},
+                "labels": {"type": "array"},
+                "title": {"type": "string"}
+            },
+            "required": ["body", "title"]
+        }));
+        assert_eq!(
+            template_invocation("tool github.create_issue", &positional),
+            r#"tool github.create_issue "<body>" "<title>""#
+        );
+
+        let flags = tool(json!({
+            "type": "object",
+            "properties": {
+                "count": {"type": "integer"},
+                "filter": {"type": "object"},
+                "state": {"type": "string", "enum": ["open", "closed"]},
+                "verbose": {"type": "boolean"}
+            },
+            "required": ["count", "filter", "state"]
+        }));
+        assert_eq!(
+            template_invocation("tool github.search", &flags),
+            r#"tool github.search --count 0 --filter {} --state "open""#
+        );
+
+        let schemaless = tool(json!({}));
+        assert_eq!(
+            template_invocation("tool misc.run", &schemaless),
+            "tool misc.run --args {}"
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, IntoPipelineData, PipelineData, Record, ShellError, Signature, Spanned,
+    SyntaxShape, Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+
+use super::{
+    schema_example::example_value,
+    tool_mapper::{
+        declares_no_schema, describe_schema_type, parameter_description, tool_parameters,
+    },
+};
+use crate::{engine::get_mcp_client_manager_sync, util::format::json_to_nu};
+
+/// Command to show everything known about a single tool
+#[derive(Clone)]
+pub struct ToolDescribeCommand;
+
+impl Command for ToolDescribeCommand {
+    fn name(&self) -> &'static str {
+        "tool describe"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("tool describe")
+            .category(Category::Custom("mcp".into()))
+            .required(
+                "name",
+                SyntaxShape::String,
+                "The namespaced tool name (server.tool)",
+            )
+            .input_output_types(vec![(Type::Nothing, Type::Record(vec![].into()))])
+    }
+
+    fn description(&self) -> &'static str {
+        "Describe an MCP tool, its parameters and its effective settings"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "The settings column shows the configuration that applies to the tool after per-tool, per-server and global settings were resolved. `schema_declared` is false for tools that sent no input schema; their arguments are passed through --args without validation."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example {
+            description: "Describe a tool",
+            example: "tool describe github.create_issue",
+            result: None,
+        }]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let name: Spanned<String> = call.req(engine_state, stack, 0)?;
+
+        let manager = get_mcp_client_manager_sync();
+        let Some((server_name, registered)) = manager.find_tool(&name.item) else {
+            return Err(ShellError::GenericError {
+                error: format!("Unknown tool '{}'", name.item),
+                msg: "no registered tool has this name".into(),
+                span: Some(name.span),
+                help: Some(
+                    "Tool names have the form server.tool; run `tool list` to see them".into(),
+                ),
+                inner: Vec::new(),
+            });
+        };
+
+        let tool = &registered.tool;
+        let mut record = Record::new();
+
+        record.push("name", Value::string(name.item.clone(), span));
+        record.push("server", Value::string(server_name, span));
+        record.push("tool", Value::string(tool.name.to_string(), span));
+        record.push(
+            "description",
+            Value::string(
+                tool.description
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+                span,
+            ),
+        );
+
+        let parameters = tool_parameters(tool)
+            .iter()
+            .map(|param| {
+                let mut row = Record::new();
+                row.push("name", Value::string(param.name.clone(), span));
+                row.push(
+                    "type",
+                    Value::string(describe_schema_type(&param.schema), span),
+                );
+                row.push("required", Value::bool(param.required, span));
+                row.push(
+                    "description",
+                    Value::string(parameter_description(param), span),
+                );
+                row.push(
+                    "example",
+                    json_to_nu(&example_value(&param.schema), Some(span)),
+                );
+                Value::record(row, span)
+            })
+            .collect();
+
+        record.push("parameters", Value::list(parameters, span));
+        // Arguments to these tools are sent as-is, so nothing is validated
+        record.push(
+            "schema_declared",
+            Value::bool(!declares_no_schema(tool), span),
+        );
+        record.push("settings", registered.settings.to_value(span));
+        record.push("schema", registered.raw_schema().clone());
+        record.push(
+            "schema_hash",
+            Value::string(registered.schema_hash.clone(), span),
+        );
+
+        drop(manager);
+
+        Ok(Value::record(record, span).into_pipeline_data())
+    }
+}
@@ -0,0 +1,114 @@
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, PipelineData, Record, ShellError, Signature, Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+
+use crate::engine::get_mcp_client_manager_sync;
+
+/// Command to list problems found while mapping tool schemas
+#[derive(Clone)]
+pub struct ToolDiagnosticsCommand;
+
+impl Command for ToolDiagnosticsCommand {
+    fn name(&self) -> &'static str {
+        "tool diagnostics"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("tool diagnostics")
+            .category(Category::Custom("mcp".into()))
+            .switch(
+                "drift",
+                "List the tools whose schema changed since the last session instead",
+                None,
+            )
+            .input_output_types(vec![(Type::Nothing, Type::Table(vec![].into()))])
+    }
+
+    fn description(&self) -> &'static str {
+        "List problems found in tool schemas while registering tools"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "Parameters with malformed schemas are registered so that they accept any value. Tools whose schema can't be used at all, whose name can't be a command name, or that the server listed twice are skipped while the rest of the server's tools register. Each row names the server, tool and parameter involved, which makes for a precise bug report to the server's author. Commands registered by more than one server are listed too: the server registered last wins, and the one it replaced has its row marked as skipped. With --drift, the tools that were added, removed or changed since the schemas cached in the last session are listed instead, which explains why a saved pipeline stopped working."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Show the tools that were skipped",
+                example: "tool diagnostics | where skipped",
+                result: None,
+            },
+            Example {
+                description: "Show how the tools changed since the last session",
+                example: "tool diagnostics --drift",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let manager = get_mcp_client_manager_sync();
+        let mut rows = Vec::new();
+
+        if call.has_flag(engine_state, stack, "drift")? {
+            for (server_name, server) in manager.get_servers() {
+                for drift in &server.drift {
+                    let mut record = Record::new();
+                    record.push("server", Value::string(server_name, span));
+                    record.push("tool", Value::string(&drift.tool, span));
+                    record.push("change", Value::string(drift.change, span));
+                    record.push(
+                        "detail",
+                        drift.detail.as_ref().map_or_else(
+                            || Value::nothing(span),
+                            |detail| Value::string(detail, span),
+                        ),
+                    );
+                    rows.push(Value::record(record, span));
+                }
+            }
+
+            return Ok(PipelineData::Value(Value::list(rows, span), None));
+        }
+
+        for (server_name, server) in manager.get_servers() {
+            for skipped in &server.skipped {
+                let mut record = Record::new();
+                record.push("server", Value::string(server_name, span));
+                record.push("tool", Value::string(&skipped.tool, span));
+                record.push("parameter", Value::nothing(span));
+                record.push("problem", Value::string(&skipped.reason, span));
+                record.push("skipped", Value::bool(true, span));
+                rows.push(Value::record(record, span));
+            }
+
+            for diagnostic in &server.diagnostics {
+                let mut record = Record::new();
+                record.push("server", Value::string(server_name, span));
+                record.push("tool", Value::string(&diagnostic.tool, span));
+                record.push(
+                    "parameter",
+                    diagnostic
+                        .parameter
+                        .as_ref()
+                        .map_or_else(|| Value::nothing(span), |name| Value::string(name, span)),
+                );
+                record.push("problem", Value::string(&diagnostic.problem, span));
+                record.push("skipped", Value::bool(diagnostic.skipped, span));
+                rows.push(Value::record(record, span));
+            }
+        }
+
+        Ok(PipelineData::Value(Value::list(rows, span), None))
+    }
+}
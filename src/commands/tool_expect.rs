@@ -0,0 +1,383 @@
+use std::time::{Duration, Instant};
+
+use nu_engine::{CallExt, ClosureEval};
+use nu_protocol::{
+    Category, Example, IntoPipelineData, PipelineData, ShellError, Signals, Signature, Span,
+    Spanned, SyntaxShape, Type, Value,
+    engine::{Call, Closure, Command, EngineState, Stack},
+};
+
+use super::{
+    mcp_tools::{PreparedCall, format_tool_contents, prepare_tool_call},
+    tool_call::record_to_params,
+};
+use crate::{engine::get_mcp_client_manager_sync, util::format::render_value};
+
+/// How long to wait between attempts when `--retry-delay` isn't given
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// How often the sleep between attempts checks for Ctrl-C
+const INTERRUPT_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Command to call a tool and fail unless its result meets an expectation
+#[derive(Clone)]
+pub struct ToolExpectCommand;
+
+impl Command for ToolExpectCommand {
+    fn name(&self) -> &'static str {
+        "tool expect"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("tool expect")
+            .category(Category::Custom("mcp".into()))
+            .required(
+                "name",
+                SyntaxShape::String,
+                "The namespaced tool name (server.tool)",
+            )
+            .named(
+                "args",
+                SyntaxShape::Record(vec![]),
+                "A record of arguments to pass to the tool",
+                Some('a'),
+            )
+            .named(
+                "where",
+                SyntaxShape::Closure(Some(vec![SyntaxShape::Any])),
+                "A closure that gets the result and returns true if it's as expected",
+                Some('w'),
+            )
+            .named(
+                "timeout",
+                SyntaxShape::Duration,
+                "How long each call may take (default: the tool's timeout)",
+                Some('t'),
+            )
+            .named(
+                "retries",
+                SyntaxShape::Int,
+                "Try again this many times while the call fails or the result isn't as expected",
+                Some('r'),
+            )
+            .named(
+                "retry-delay",
+                SyntaxShape::Duration,
+                "How long to wait between attempts (default 1sec)",
+                None,
+            )
+            .input_output_types(vec![(Type::Nothing, Type::Any)])
+    }
+
+    fn description(&self) -> &'static str {
+        "Call an MCP tool and fail unless the result is as expected"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "The call fails if the tool call errors, the server flags the result as an error, or the --where closure returns false. The error shows the closure and the result that was received, so a script run with --commands exits with a nonzero code and says why. Without --where, any successful call passes. With --retries, a failed attempt is tried again after --retry-delay, for servers that only become consistent after a while. On success the result is returned."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Check that a repository can be fetched and has the right name",
+                example: "tool expect github.get_repo --args {owner: \"x\", repo: \"y\"} --where {|r| $r.full_name == \"x/y\"}",
+                result: None,
+            },
+            Example {
+                description: "Wait for a deployment to finish, checking every 5 seconds for up to a minute",
+                example: "tool expect deploy.status --args {id: 42} --where {|s| $s.state == \"done\"} --retries 12 --retry-delay 5sec",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let name: Spanned<String> = call.req(engine_state, stack, 0)?;
+        let args: Option<Value> = call.get_flag(engine_state, stack, "args")?;
+        let predicate: Option<Closure> = call.get_flag(engine_state, stack, "where")?;
+        let timeout: Option<Value> = call.get_flag(engine_state, stack, "timeout")?;
+        let retries: Option<Spanned<i64>> = call.get_flag(engine_state, stack, "retries")?;
+        let retry_delay: Option<Value> = call.get_flag(engine_state, stack, "retry-delay")?;
+
+        let retries = match retries {
+            Some(retries) if retries.item < 0 => {
+                return Err(ShellError::IncorrectValue {
+                    msg: "--retries can't be negative".into(),
+                    val_span: retries.span,
+                    call_span: span,
+                });
+            }
+            Some(retries) => u32::try_from(retries.item).unwrap_or(u32::MAX),
+            None => 0,
+        };
+        let retry_delay = match retry_delay {
+            Some(value) => duration_from_nanos(value.as_duration()?),
+            None => DEFAULT_RETRY_DELAY,
+        };
+
+        let params = match &args {
+            Some(args) => record_to_params(args, span)?,
+            None => serde_json::Map::new(),
+        };
+
+        let manager = get_mcp_client_manager_sync();
+        let Some((_, registered)) = manager.find_tool(&name.item) else {
+            return Err(ShellError::GenericError {
+                error: format!("Unknown tool '{}'", name.item),
+                msg: "no registered tool has this name".into(),
+                span: Some(name.span),
+                help: Some(
+                    "Tool names have the form server.tool; run `tool list` to see them".into(),
+                ),
+                inner: Vec::new(),
+            });
+        };
+        let registered = registered.clone();
+        drop(manager);
+
+        let settings = match timeout {
+            Some(value) => registered
+                .settings
+                .with_timeout(duration_from_nanos(value.as_duration()?)),
+            None => registered.settings.clone(),
+        };
+        // Checked and confirmed once, however many attempts follow
+        let prepared = prepare_tool_call(
+            engine_state,
+            &registered.client,
+            &registered.tool,
+            params,
+            &settings,
+            span,
+        )
+        .map_err(|(_, err)| err)?;
+        let expectation = Expectation {
+            prepared,
+            signals: engine_state.signals().clone(),
+            span,
+        };
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let failure = match expectation.call() {
+                Ok(value) => match &predicate {
+                    None => return Ok(value.into_pipeline_data()),
+                    Some(closure) => {
+                        if holds(engine_state, stack, closure, &value, span)? {
+                            return Ok(value.into_pipeline_data());
+                        }
+                        Failure::Unexpected(value)
+                    }
+                },
+                Err(err) => Failure::Error(err),
+            };
+
+            if attempt > retries || !wait(retry_delay, engine_state.signals()) {
+                return Err(failure.into_shell_error(
+                    engine_state,
+                    stack,
+                    &name.item,
+                    predicate.as_ref(),
+                    attempt,
+                    span,
+                ));
+            }
+        }
+    }
+}
+
+/// One call that `tool expect` makes, possibly several times
+struct Expectation {
+    prepared: PreparedCall,
+    signals: Signals,
+    span: Span,
+}
+
+impl Expectation {
+    /// Call the tool, treating a result flagged with `isError` as a failure
+    fn call(&self) -> Result<Value, ShellError> {
+        self.prepared
+            .call(&self.signals)
+            .map_err(|err| err.into_shell_error(self.span))
+            .and_then(|contents| {
+                format_tool_contents(contents, self.prepared.settings(), self.span, &self.signals)?
+                    .into_value(self.span)
+            })
+    }
+}
+
+/// Why the last attempt didn't meet the expectation
+enum Failure {
+    Error(ShellError),
+    Unexpected(Value),
+}
+
+impl Failure {
+    fn into_shell_error(
+        self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        tool: &str,
+        predicate: Option<&Closure>,
+        attempts: u32,
+        span: Span,
+    ) -> ShellError {
+        let attempts = if attempts == 1 {
+            String::new()
+        } else {
+            format!(" after {attempts} attempts")
+        };
+
+        match self {
+            Self::Error(err) => ShellError::GenericError {
+                error: format!("Expectation failed for {tool}{attempts}"),
+                msg: "the call failed".into(),
+                span: Some(span),
+                help: None,
+                inner: vec![err],
+            },
+            Self::Unexpected(value) => ShellError::GenericError {
+                error: format!("Expectation failed for {tool}{attempts}"),
+                msg: "the result wasn't as expected".into(),
+                span: Some(span),
+                help: Some(format!(
+                    "Expected: {}\nReceived:\n{}",
+                    predicate
+                        .map_or_else(String::new, |closure| closure_source(engine_state, closure)),
+                    render_value(engine_state, stack, value)
+                )),
+                inner: Vec::new(),
+            },
+        }
+    }
+}
+
+/// Run the `--where` closure on a result
+fn holds(
+    engine_state: &EngineState,
+    stack: &Stack,
+    closure: &Closure,
+    value: &Value,
+    span: Span,
+) -> Result<bool, ShellError> {
+    let output = ClosureEval::new(engine_state, stack, closure.clone())
+        .run_with_value(value.clone())?
+        .into_value(span)?;
+
+    match output {
+        Value::Bool { val, .. } => Ok(val),
+        other => Err(ShellError::GenericError {
+            error: "The --where closure must return a bool".into(),
+            msg: format!("it returned {}", other.get_type()),
+            span: Some(other.span()),
+            help: None,
+            inner: Vec::new(),
+        }),
+    }
+}
+
+/// The source of a closure as it was written
+fn closure_source(engine_state: &EngineState, closure: &Closure) -> String {
+    engine_state
+        .get_block(closure.block_id)
+        .span
+        .map(|span| String::from_utf8_lossy(engine_state.get_span_contents(span)).into_owned())
+        .unwrap_or_else(|| "the --where closure".into())
+}
+
+/// Sleep between attempts, returning false if interrupted while waiting
+fn wait(delay: Duration, signals: &Signals) -> bool {
+    let deadline = Instant::now() + delay;
+
+    while Instant::now() < deadline {
+        if signals.interrupted() {
+            return false;
+        }
+        std::thread::sleep(INTERRUPT_CHECK_INTERVAL.min(deadline - Instant::now()));
+    }
+
+    !signals.interrupted()
+}
+
+fn duration_from_nanos(nanos: i64) -> Duration {
+    Duration::from_nanos(u64::try_from(nanos).unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use nu_protocol::engine::StateWorkingSet;
+
+    use super::*;
+
+    fn closure(engine_state: &mut EngineState, source: &str) -> Closure {
+        let mut working_set = StateWorkingSet::new(engine_state);
+        let block = nu_parser::parse(&mut working_set, None, source.as_bytes(), false);
+        let block_id = match block.pipelines[0].elements[0].expr.expr {
+            nu_protocol::ast::Expr::Closure(block_id) => block_id,
+            _ => panic!("not a closure: {source}"),
+        };
+        let delta = working_set.render();
+        engine_state.merge_delta(delta).unwrap();
+
+        Closure {
+            block_id,
+            captures: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_expectation_failure_shows_closure_and_value() {
+        let mut engine_state = EngineState::new();
+        let mut stack = Stack::new();
+        let span = Span::test_data();
+        let source = "{|r| $r == 2 }";
+        let predicate = closure(&mut engine_state, source);
+
+        assert!(
+            holds(
+                &engine_state,
+                &stack,
+                &predicate,
+                &Value::int(2, span),
+                span
+            )
+            .unwrap()
+        );
+        assert!(
+            !holds(
+                &engine_state,
+                &stack,
+                &predicate,
+                &Value::int(3, span),
+                span
+            )
+            .unwrap()
+        );
+
+        let ShellError::GenericError { error, help, .. } = Failure::Unexpected(Value::int(3, span))
+            .into_shell_error(
+                &engine_state,
+                &mut stack,
+                "math.add",
+                Some(&predicate),
+                3,
+                span,
+            )
+        else {
+            panic!("expected a generic error");
+        };
+        assert_eq!(error, "Expectation failed for math.add after 3 attempts");
+        let help = help.unwrap();
+        assert!(help.contains(source), "{help}");
+        assert!(help.contains("Received:\n3"), "{help}");
+    }
+}
@@ -0,0 +1,318 @@
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, PipelineData, Record, ShellError, Signature, Spanned, SyntaxShape, Type,
+    Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+use regex::Regex;
+
+use crate::engine::get_mcp_client_manager_sync;
+
+/// Command to search tool names, schemas and descriptions with a regex
+#[derive(Clone)]
+pub struct ToolGrepCommand;
+
+impl Command for ToolGrepCommand {
+    fn name(&self) -> &'static str {
+        "tool grep"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("tool grep")
+            .category(Category::Custom("mcp".into()))
+            .required("pattern", SyntaxShape::String, "The regex to search for")
+            .named(
+                "in",
+                SyntaxShape::String,
+                "Where to search: names, descriptions, params, enums or all (default)",
+                Some('i'),
+            )
+            .input_output_types(vec![(Type::Nothing, Type::Table(vec![].into()))])
+    }
+
+    fn description(&self) -> &'static str {
+        "Search tool names, parameters, descriptions and enum values with a regex"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "Each match is reported with the JSON path where it was found in the tool's input schema, e.g. `properties.repository.description`. Tool names and descriptions are reported with the paths `name` and `description`."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Find tools that accept a repository parameter",
+                example: "tool grep '^repository$' --in params",
+                result: None,
+            },
+            Example {
+                description: "Find every mention of pagination",
+                example: "tool grep '(?i)page|cursor'",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let pattern: Spanned<String> = call.req(engine_state, stack, 0)?;
+        let scope: Option<Spanned<String>> = call.get_flag(engine_state, stack, "in")?;
+
+        let regex = Regex::new(&pattern.item).map_err(|err| ShellError::GenericError {
+            error: "Invalid regex".into(),
+            msg: err.to_string(),
+            span: Some(pattern.span),
+            help: None,
+            inner: Vec::new(),
+        })?;
+
+        let scope = match scope {
+            Some(scope) => {
+                GrepScope::parse(&scope.item).ok_or_else(|| ShellError::InvalidValue {
+                    valid: "names, descriptions, params, enums or all".into(),
+                    actual: scope.item.clone(),
+                    span: scope.span,
+                })?
+            }
+            None => GrepScope::All,
+        };
+
+        let manager = get_mcp_client_manager_sync();
+        let mut rows = Vec::new();
+
+        for (server_name, server) in manager.get_servers() {
+            for (tool_name, registered) in &server.tools {
+                let mut candidates = vec![Candidate {
+                    kind: MatchKind::Name,
+                    path: "name".into(),
+                    text: tool_name.clone(),
+                }];
+
+                if let Some(description) = &registered.tool.description {
+                    candidates.push(Candidate {
+                        kind: MatchKind::Description,
+                        path: "description".into(),
+                        text: description.to_string(),
+                    });
+                }
+
+                collect_schema_candidates(
+                    registered.raw_schema(),
+                    &mut Vec::new(),
+                    &mut candidates,
+                );
+
+                for candidate in candidates {
+                    if !scope.includes(candidate.kind) || !regex.is_match(&candidate.text) {
+                        continue;
+                    }
+
+                    let mut record = Record::new();
+                    record.push("server", Value::string(server_name, span));
+                    record.push("tool", Value::string(tool_name, span));
+                    record.push("kind", Value::string(candidate.kind.as_str(), span));
+                    record.push("path", Value::string(candidate.path, span));
+                    record.push(
+                        "match",
+                        Value::string(highlight_matches(&regex, &candidate.text), span),
+                    );
+                    rows.push(Value::record(record, span));
+                }
+            }
+        }
+
+        Ok(PipelineData::Value(Value::list(rows, span), None))
+    }
+}
+
+/// The parts of a tool that `tool grep --in` can be limited to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum GrepScope {
+    Names,
+    Descriptions,
+    Params,
+    Enums,
+    All,
+}
+
+impl GrepScope {
+    fn parse(scope: &str) -> Option<Self> {
+        match scope {
+            "names" => Some(Self::Names),
+            "descriptions" => Some(Self::Descriptions),
+            "params" => Some(Self::Params),
+            "enums" => Some(Self::Enums),
+            "all" => Some(Self::All),
+            _ => None,
+        }
+    }
+
+    const fn includes(self, kind: MatchKind) -> bool {
+        matches!(
+            (self, kind),
+            (Self::All, _)
+                | (Self::Names, MatchKind::Name)
+                | (Self::Descriptions, MatchKind::Description)
+                | (Self::Params, MatchKind::Param)
+                | (Self::Enums, MatchKind::Enum)
+        )
+    }
+}
+
+/// What kind of text a candidate is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MatchKind {
+    Name,
+    Description,
+    Param,
+    Enum,
+}
+
+impl MatchKind {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Description => "description",
+            Self::Param => "param",
+            Self::Enum => "enum",
+        }
+    }
+}
+
+/// A piece of text that can be matched, along with where it came from
+#[derive(Debug, PartialEq, Eq)]
+struct Candidate {
+    kind: MatchKind,
+    path: String,
+    text: String,
+}
+
+/// Walk a schema, collecting property names, descriptions and enum values
+fn collect_schema_candidates(value: &Value, path: &mut Vec<String>, out: &mut Vec<Candidate>) {
+    match value {
+        Value::Record { val, .. } => {
+            let in_properties = path.last().is_some_and(|segment| segment == "properties");
+
+            for (key, child) in val.iter() {
+                path.push(key.clone());
+
+                if in_properties {
+                    out.push(Candidate {
+                        kind: MatchKind::Param,
+                        path: path.join("."),
+                        text: key.clone(),
+                    });
+                }
+
+                match (key.as_str(), child) {
+                    ("description", Value::String { val, .. }) if !in_properties => {
+                        out.push(Candidate {
+                            kind: MatchKind::Description,
+                            path: path.join("."),
+                            text: val.clone(),
+                        });
+                    }
+                    ("enum", Value::List { vals, .. }) if !in_properties => {
+                        for (index, item) in vals.iter().enumerate() {
+                            if let Ok(text) = item.coerce_string() {
+                                out.push(Candidate {
+                                    kind: MatchKind::Enum,
+                                    path: format!("{}.{index}", path.join(".")),
+                                    text,
+                                });
+                            }
+                        }
+                    }
+                    _ => collect_schema_candidates(child, path, out),
+                }
+
+                path.pop();
+            }
+        }
+        Value::List { vals, .. } => {
+            for (index, item) in vals.iter().enumerate() {
+                path.push(index.to_string());
+                collect_schema_candidates(item, path, out);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Highlight every match of the regex in the text
+fn highlight_matches(regex: &Regex, text: &str) -> String {
+    let style = nu_ansi_term::Style::new()
+        .fg(nu_ansi_term::Color::Red)
+        .bold();
+
+    regex
+        .replace_all(text, |captures: &regex::Captures<'_>| {
+            style.paint(&captures[0]).to_string()
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use nu_protocol::{Span, record};
+
+    use super::*;
+
+    #[test]
+    fn test_collect_schema_candidates() {
+        let span = Span::test_data();
+        let schema = Value::test_record(record! {
+            "type" => Value::string("object", span),
+            "properties" => Value::test_record(record! {
+                "repository" => Value::test_record(record! {
+                    "type" => Value::string("string", span),
+                    "description" => Value::string("owner/name of the repository", span),
+                }),
+                "state" => Value::test_record(record! {
+                    "enum" => Value::list(
+                        vec![Value::string("open", span), Value::string("closed", span)],
+                        span,
+                    ),
+                }),
+            }),
+        });
+
+        let mut candidates = Vec::new();
+        collect_schema_candidates(&schema, &mut Vec::new(), &mut candidates);
+
+        let summary: Vec<(MatchKind, &str, &str)> = candidates
+            .iter()
+            .map(|c| (c.kind, c.path.as_str(), c.text.as_str()))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (MatchKind::Param, "properties.repository", "repository"),
+                (
+                    MatchKind::Description,
+                    "properties.repository.description",
+                    "owner/name of the repository"
+                ),
+                (MatchKind::Param, "properties.state", "state"),
+                (MatchKind::Enum, "properties.state.enum.0", "open"),
+                (MatchKind::Enum, "properties.state.enum.1", "closed"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_grep_scope() {
+        assert_eq!(GrepScope::parse("params"), Some(GrepScope::Params));
+        assert_eq!(GrepScope::parse("everything"), None);
+        assert!(GrepScope::All.includes(MatchKind::Enum));
+        assert!(!GrepScope::Names.includes(MatchKind::Param));
+    }
+}
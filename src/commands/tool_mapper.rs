@@ -1,13 +1,18 @@
-use log::trace;
+use log::{debug, trace};
 use nu_engine::CallExt;
 use nu_protocol::{
-    Category, Signature, SyntaxShape,
+    Category, PipelineData, ShellError, Signature, Span, SyntaxShape, Value,
     engine::{EngineState, Stack},
 };
 use rmcp::model::Tool;
 use serde_json::Value as JsonValue;
 
-use crate::util::error::McpResult;
+use super::{
+    schema_example,
+    tool_prompt::INTERACTIVE_SWITCH,
+    utils::{OUTPUT_FLAG, add_output_flag},
+};
+use crate::util::error::{McpError, McpResult, generic_error};
 
 /// Maps an MCP tool to a Nushell command signature
 /// Following the mapping strategy in MAPPING.md:
@@ -16,143 +21,495 @@ use crate::util::error::McpResult;
 /// 3. If the tool has exactly one or two required parameters and all of the rest of the arguments are optional, map the required parameters onto positional arguments and the optional parameters onto flags.
 /// 4. Optional parameters that are booleans should be mapped to switches (e.g., `--verbose`).
 /// 5. All other optional parameters should be mapped to flags (e.g., `--limit 10`).
-pub fn map_tool_to_signature(tool: &Tool, category: &str) -> Signature {
+/// 6. Positional parameters can also be given as flags, but not both ways at once.
+/// 7. Optional parameters beyond the first `max_flags` are given in a `--rest` record.
+///
+/// The decisions are made by [`plan_parameters`]; this turns them into a signature.
+pub fn map_tool_to_signature(tool: &Tool, category: &str, max_flags: usize) -> Signature {
     let name = tool.name.to_string();
 
     // DEBUG: Output the raw schema for inspection
     trace!("DEBUG: Tool {} schema: {:?}", name, tool.input_schema);
 
-    let mut signature =
-        Signature::build(name.clone()).category(Category::Custom(category.to_string()));
+    let mut signature = Signature::build(name.clone())
+        .category(Category::Custom(category.to_string()))
+        .search_terms(search_terms(tool));
 
-    // Get all schema properties
-    if let Some(schema_props) = get_schema_properties(tool) {
-        // DEBUG: Output the properties we found
-        trace!(
-            "DEBUG: Properties for tool {}: {:?}",
-            name,
-            schema_props.keys().collect::<Vec<_>>()
+    let plan = plan_parameters(tool, max_flags);
+    trace!("DEBUG: Parameter plan for tool {name}: {plan:?}");
+
+    for param in plan.parameters {
+        signature = match param.placement {
+            // Positionals are optional to the parser, since they can also be
+            // given as flags; a missing required one fails validation, so the
+            // help says which ones are required
+            Placement::Positional => signature.optional(param.name, param.shape, param.description),
+            Placement::Switch => signature.switch(param.name, param.description, None),
+            Placement::Flag => signature.named(param.name, param.shape, param.description, None),
+            Placement::Rest => signature,
+        };
+    }
+
+    if !plan.overflow.is_empty() {
+        signature = signature.named(
+            REST_FLAG,
+            SyntaxShape::Record(vec![]),
+            format!(
+                "A record of the parameters without a flag of their own: {}",
+                plan.overflow.join(", ")
+            ),
+            None,
         );
+    }
 
-        // Convert properties to vec for sorting
-        let prop_vec: Vec<(String, JsonValue)> = schema_props.into_iter().collect();
+    signature
+}
 
-        // Identify required and optional parameters
-        let required_params: Vec<(String, JsonValue)> = prop_vec
-            .iter()
-            .filter(|(name, _)| is_parameter_required(tool, name))
-            .map(|(name, schema)| (name.clone(), schema.clone()))
-            .collect();
+/// Where a tool parameter goes in its command's signature
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// An optional positional argument
+    Positional,
+    /// A flag that takes a value
+    Flag,
+    /// A flag without a value
+    Switch,
+    /// A key of the `--rest` record
+    Rest,
+}
+
+/// The mapping rule behind a placement, numbered as in [`map_tool_to_signature`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MappingRule {
+    OnlyParameter,
+    TwoRequired,
+    OneRequired,
+    OptionalBoolean,
+    Flag,
+    AlsoFlag,
+    Overflow,
+}
+
+impl MappingRule {
+    /// The rule's number and a few words on it
+    #[must_use]
+    pub const fn describe(self) -> &'static str {
+        match self {
+            Self::OnlyParameter => "rule 1: the only param",
+            Self::TwoRequired => "rule 2: two required params",
+            Self::OneRequired => "rule 3: one required param, the rest optional",
+            Self::OptionalBoolean => "rule 4: optional boolean",
+            Self::Flag => "rule 5: other params are flags",
+            Self::AlsoFlag => "rule 6: positionals can also be flags",
+            Self::Overflow => "rule 7: beyond max_flags",
+        }
+    }
+}
+
+/// One placement of a tool parameter
+///
+/// A positional parameter is planned twice, once as a positional and once
+/// as the flag it can also be given as.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlannedParameter {
+    pub name: String,
+    pub shape: SyntaxShape,
+    pub description: String,
+    pub required: bool,
+    pub placement: Placement,
+    pub rule: MappingRule,
+}
+
+/// How each of a tool's parameters is given to its command
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParameterPlan {
+    /// Positionals first, in order, then the flags in schema order
+    pub parameters: Vec<PlannedParameter>,
+    /// The parameters given in the `--rest` record
+    pub overflow: Vec<String>,
+}
 
-        // Determine positional parameters based on the new rules
-        let total_param_count = prop_vec.len();
-        let positional_count = if total_param_count == 1 {
-            // Rule 1: If exactly one parameter (required or optional), make it positional
-            1
-        } else if required_params.len() == 2 {
-            // Rule 2: If exactly two required parameters, make them positional
-            2
+/// Decide where each of a tool's parameters goes, and why
+#[must_use]
+pub fn plan_parameters(tool: &Tool, max_flags: usize) -> ParameterPlan {
+    let Some(schema_props) = get_schema_properties(tool) else {
+        return ParameterPlan::default();
+    };
+
+    let positionals = positional_parameters(tool);
+    let overflow = overflow_parameters(tool, max_flags);
+    let positional_rule = if schema_props.len() == 1 {
+        MappingRule::OnlyParameter
+    } else if positionals.len() == 2 {
+        MappingRule::TwoRequired
+    } else {
+        MappingRule::OneRequired
+    };
+
+    let mut parameters = Vec::new();
+
+    // Positional parameters come first, in order
+    for param_name in &positionals {
+        let param_schema = &schema_props[param_name];
+        let required = is_parameter_required(tool, param_name);
+
+        // Get parameter description
+        let description = get_parameter_description(param_schema)
+            .unwrap_or_else(|| format!("{param_name} parameter"));
+
+        parameters.push(PlannedParameter {
+            name: param_name.clone(),
+            shape: map_json_schema_to_syntax_shape(param_schema),
+            description: if required {
+                format!("{description} (required)")
+            } else {
+                description
+            },
+            required,
+            placement: Placement::Positional,
+            rule: positional_rule,
+        });
+    }
+
+    // Every parameter, positional or not, can be given as a flag, up to
+    // the cap on optional ones
+    for (param_name, param_schema) in &schema_props {
+        let required = is_parameter_required(tool, param_name);
+        let boolean = !required && is_boolean_parameter(param_schema);
+
+        // Get parameter description with better fallback
+        let description = get_parameter_description(param_schema)
+            .or_else(|| {
+                // If no description found, extract useful information from schema
+                extract_useful_schema_info(param_schema, param_name)
+            })
+            .unwrap_or_else(|| format!("{param_name} parameter"));
+
+        let (placement, rule) = if overflow.contains(param_name) {
+            (Placement::Rest, MappingRule::Overflow)
+        } else if positionals.contains(param_name) {
+            let placement = if boolean {
+                Placement::Switch
+            } else {
+                Placement::Flag
+            };
+            (placement, MappingRule::AlsoFlag)
+        } else if boolean {
+            // For boolean optional parameters, use switch (--param_name with no value)
+            (Placement::Switch, MappingRule::OptionalBoolean)
         } else {
-            usize::from(
-                required_params.len() == 1
-                    && prop_vec
-                        .iter()
-                        .any(|(name, _)| !is_parameter_required(tool, name)),
-            )
+            (Placement::Flag, MappingRule::Flag)
         };
 
-        // Process positional parameters first based on our rules
-        for i in 0..positional_count {
-            let param_name: &str;
-            let param_schema: &JsonValue;
-
-            // For tools with a single parameter (required or optional)
-            if total_param_count == 1 {
-                let (name, schema) = &prop_vec[0];
-                param_name = name;
-                param_schema = schema;
-            } else if i < required_params.len() {
-                // Required parameters get priority for positional slots
-                let (name, schema) = &required_params[i];
-                param_name = name;
-                param_schema = schema;
-            } else {
-                // This should not happen based on our rules, but handle it gracefully
-                continue;
-            }
+        parameters.push(PlannedParameter {
+            name: param_name.clone(),
+            shape: map_json_schema_to_syntax_shape(param_schema),
+            description,
+            required,
+            placement,
+            rule,
+        });
+    }
+
+    ParameterPlan {
+        parameters,
+        overflow,
+    }
+}
 
-            // Get parameter description
-            let description = get_parameter_description(param_schema)
-                .unwrap_or_else(|| format!("{param_name} parameter"));
+/// How many search terms a tool command gets, so a long description doesn't
+/// make it match every search
+pub const MAX_SEARCH_TERMS: usize = 24;
 
-            // Determine parameter type/shape
-            let syntax_shape = map_json_schema_to_syntax_shape(param_schema);
+/// Words too common to be worth searching for
+const SEARCH_STOPWORDS: &[&str] = &[
+    "and", "are", "for", "from", "has", "into", "its", "not", "that", "the", "this", "with", "you",
+    "your",
+];
 
-            // Determine if parameter is required or optional
-            let is_required = is_parameter_required(tool, param_name);
+/// The words `help --find` matches a tool command on
+///
+/// The tool's description and parameter names are split into lowercase
+/// words, so `create_issue` and "Opens issues" both give `issue`-like terms.
+/// Words shorter than three letters and common ones are left out, and only
+/// the first [`MAX_SEARCH_TERMS`] distinct words are kept.
+#[must_use]
+pub fn search_terms(tool: &Tool) -> Vec<String> {
+    let description = tool.description.as_deref().unwrap_or_default();
+    let parameters = get_schema_properties(tool)
+        .map(|properties| properties.keys().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
 
-            if is_required {
-                // Add as required positional parameter
-                signature = signature.required(param_name, syntax_shape, description);
-            } else {
-                // Add as optional positional parameter
-                signature = signature.optional(param_name, syntax_shape, description);
-            }
+    let mut terms: Vec<String> = Vec::new();
+    let words = std::iter::once(description)
+        .chain(parameters.iter().map(String::as_str))
+        .flat_map(|text| text.split(|c: char| !c.is_alphanumeric()))
+        .map(str::to_lowercase);
+    for word in words {
+        if terms.len() == MAX_SEARCH_TERMS {
+            break;
         }
+        if word.chars().count() >= 3
+            && !SEARCH_STOPWORDS.contains(&word.as_str())
+            && !terms.contains(&word)
+        {
+            terms.push(word);
+        }
+    }
+    terms
+}
 
-        // Process remaining parameters as flags
-        for (param_name, param_schema) in prop_vec {
-            // Skip parameters we've already processed as positional
-            if positional_count > 0
-                && required_params
-                    .iter()
-                    .take(positional_count)
-                    .any(|(name, _)| name == &param_name)
-            {
-                continue;
-            }
+/// The optional parameters that don't get a flag of their own
+///
+/// Only the first `max_flags` optional parameters, in schema order, become
+/// flags; the rest are given in the `--rest` record. Zero means no cap. A
+/// tool with its own `rest` parameter has no `--rest` flag, so it isn't
+/// capped either.
+#[must_use]
+pub fn overflow_parameters(tool: &Tool, max_flags: usize) -> Vec<String> {
+    if max_flags == 0 || !call_switch_available(tool, REST_FLAG) {
+        return Vec::new();
+    }
 
-            // Get parameter description with better fallback
-            let description = get_parameter_description(&param_schema)
-                .or_else(|| {
-                    // If no description found, extract useful information from schema
-                    extract_useful_schema_info(&param_schema, &param_name)
-                })
-                .unwrap_or_else(|| format!("{param_name} parameter"));
-
-            // Determine parameter type/shape
-            let syntax_shape = map_json_schema_to_syntax_shape(&param_schema);
-
-            // Determine if parameter is required
-            let is_required = is_parameter_required(tool, &param_name);
-
-            // Handle boolean parameters as switches if optional
-            if !is_required && is_boolean_parameter(&param_schema) {
-                // For boolean optional parameters, use switch (--param_name with no value)
-                signature = signature.switch(param_name.clone(), description, None);
-            } else if is_required {
-                // For required parameters beyond the first 2, use flags with named parameters
-                signature = signature.named(
-                    param_name.clone(),
-                    syntax_shape,
-                    description,
-                    None, // No short flag
-                );
-            } else {
-                // Optional non-boolean - add as optional flag with named parameters
-                signature = signature.named(
-                    param_name.clone(),
-                    syntax_shape,
-                    description,
-                    None, // No short flag
-                );
-            }
+    let positionals = positional_parameters(tool);
+    tool_parameters(tool)
+        .into_iter()
+        .filter(|param| !param.required && !positionals.contains(&param.name))
+        .skip(max_flags)
+        .map(|param| param.name)
+        .collect()
+}
+
+/// Switches that every generated tool command accepts in addition to the
+/// tool's own parameters
+pub const CALL_SWITCHES: &[(&str, &str)] = &[
+    (
+        "try",
+        "Return {ok: true, data} or {ok: false, error} instead of raising an error",
+    ),
+    (
+        "lines",
+        "Split text results into a list of lines (a table if every line is a JSON object)",
+    ),
+    (
+        "full",
+        "Return the whole result, even blocks larger than `max_result_bytes`",
+    ),
+    (
+        KEEP_EMPTY_SWITCH,
+        "Return an empty or whitespace-only text result as that text instead of nothing",
+    ),
+    (
+        ALL_PAGES_SWITCH,
+        "Follow the tool's pagination cursor and return the items from every page",
+    ),
+    (
+        FOLLOW_LINKS_SWITCH,
+        "Read the resources that resource links in the result point at, and return their contents",
+    ),
+    (
+        STRICT_TYPES_SWITCH,
+        "Send strings as they are, even to parameters that take numbers or booleans",
+    ),
+];
+
+/// The switch that keeps an empty text result instead of returning nothing
+pub const KEEP_EMPTY_SWITCH: &str = "keep-empty";
+
+/// The switch that turns off parsing strings into the numbers and booleans
+/// a schema asks for
+pub const STRICT_TYPES_SWITCH: &str = "strict-types";
+
+/// The switch that reads the resources a result links to
+pub const FOLLOW_LINKS_SWITCH: &str = "follow-links";
+
+/// The switch that follows a tool's pagination cursor
+pub const ALL_PAGES_SWITCH: &str = "all";
+
+/// The flag that bounds how many pages `--all` fetches
+pub const MAX_PAGES_FLAG: &str = "max-pages";
+
+/// How many pages `--all` fetches when `--max-pages` isn't given
+pub const DEFAULT_MAX_PAGES: usize = 100;
+
+/// The flag that tools without an input schema take their arguments from
+pub const ARGS_FLAG: &str = "args";
+
+/// The flag that reads a tool's arguments from a JSON, nuon, TOML or YAML file
+pub const ARGS_FILE_FLAG: &str = "args-file";
+
+/// The flag that takes the optional parameters beyond a tool's `max_flags`
+pub const REST_FLAG: &str = "rest";
+
+/// The flag that keeps only the result blocks meant for the user or the model
+pub const AUDIENCE_FLAG: &str = "audience";
+
+/// The flag that returns one cell path of a call's result
+pub const GET_FLAG: &str = "get";
+
+/// Add the standard call switches and flags to a generated tool signature
+///
+/// A switch or flag is left out when the tool's schema has a property with
+/// the same name, so the tool's own parameter always wins.
+#[must_use]
+pub fn add_call_flags(mut signature: Signature, tool: &Tool) -> Signature {
+    for (name, description) in CALL_SWITCHES {
+        if call_switch_available(tool, name) {
+            signature = signature.switch(*name, *description, None);
         }
     }
 
+    if call_switch_available(tool, INTERACTIVE_SWITCH) {
+        signature = signature.switch(
+            INTERACTIVE_SWITCH,
+            "Prompt for any required parameter that wasn't given",
+            Some('i'),
+        );
+    }
+
+    if call_switch_available(tool, MAX_PAGES_FLAG) {
+        signature = signature.named(
+            MAX_PAGES_FLAG,
+            SyntaxShape::Int,
+            "The most pages --all fetches (default 100)",
+            None,
+        );
+    }
+
+    if declares_no_schema(tool) {
+        signature = signature.named(
+            ARGS_FLAG,
+            SyntaxShape::Record(vec![]),
+            "The arguments object, sent to the tool as-is (a piped record works too)",
+            None,
+        );
+    }
+
+    if call_switch_available(tool, ARGS_FILE_FLAG) {
+        signature = signature.named(
+            ARGS_FILE_FLAG,
+            SyntaxShape::Filepath,
+            "Read the arguments from a json, nuon, toml or yaml file (explicit arguments win)",
+            None,
+        );
+    }
+
+    if call_switch_available(tool, AUDIENCE_FLAG) {
+        signature = signature.named(
+            AUDIENCE_FLAG,
+            SyntaxShape::String,
+            "Only keep result blocks meant for this audience: user, assistant or all (default)",
+            None,
+        );
+    }
+
+    if call_switch_available(tool, GET_FLAG) {
+        signature = signature.named(
+            GET_FLAG,
+            SyntaxShape::CellPath,
+            "Return only this cell path of the result, e.g. items.0.name",
+            None,
+        );
+    }
+
+    if call_switch_available(tool, OUTPUT_FLAG) {
+        signature = add_output_flag(signature);
+    }
+
     signature
 }
 
+/// Make a signature's required positional arguments optional
+///
+/// Used when missing parameters are prompted for, so that the parser lets
+/// the call through to the prompt instead of rejecting it.
+#[must_use]
+pub fn optional_positionals(mut signature: Signature) -> Signature {
+    let required = std::mem::take(&mut signature.required_positional);
+    signature.optional_positional.splice(0..0, required);
+    signature
+}
+
+/// Whether a standard call switch or flag is available on the tool's generated command
+#[must_use]
+pub fn call_switch_available(tool: &Tool, name: &str) -> bool {
+    get_schema_properties(tool).is_none_or(|properties| !properties.contains_key(name))
+}
+
+/// Whether the tool's input schema declares no properties at all
+///
+/// Minimal servers sometimes omit the schema or send an empty object. There
+/// is nothing to map arguments onto, so they are taken from `--args` instead.
+#[must_use]
+pub fn declares_no_schema(tool: &Tool) -> bool {
+    get_schema_properties(tool).is_none()
+}
+
+/// Collect the arguments for a tool without a schema from `--args` or a piped record
+///
+/// The record is passed through verbatim, without any validation.
+pub fn schemaless_tool_params(
+    engine_state: &EngineState,
+    stack: &mut Stack,
+    call: &nu_protocol::engine::Call<'_>,
+    input: PipelineData,
+) -> McpResult<serde_json::Map<String, JsonValue>> {
+    let span = call.head;
+    let args: Option<Value> = match call.get_flag(engine_state, stack, ARGS_FLAG)? {
+        Some(args) => Some(args),
+        None => match input {
+            PipelineData::Empty => None,
+            input => Some(input.into_value(span)?),
+        },
+    };
+
+    match args {
+        None | Some(Value::Nothing { .. }) => Ok(serde_json::Map::new()),
+        Some(value @ Value::Record { .. }) => {
+            match super::utils::convert_nu_value_to_json_value(&value, span)? {
+                JsonValue::Object(params) => Ok(params),
+                _ => Ok(serde_json::Map::new()),
+            }
+        }
+        Some(other) => Err(generic_error(
+            "Tool arguments must be a record",
+            format!(
+                "Got {}; pass a record with --args or pipe one in",
+                other.get_type()
+            ),
+            other.span(),
+        )),
+    }
+}
+
+/// A single parameter declared in a tool's input schema
+#[derive(Clone, Debug)]
+pub struct ToolParameter {
+    pub name: String,
+    pub schema: JsonValue,
+    pub required: bool,
+}
+
+/// List the parameters declared in a tool's input schema, in schema order
+pub fn tool_parameters(tool: &Tool) -> Vec<ToolParameter> {
+    get_schema_properties(tool)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, schema)| ToolParameter {
+            required: is_parameter_required(tool, &name),
+            name,
+            schema,
+        })
+        .collect()
+}
+
+/// Get the description of a parameter, falling back to information derived from its schema
+pub fn parameter_description(param: &ToolParameter) -> String {
+    get_parameter_description(&param.schema)
+        .or_else(|| extract_useful_schema_info(&param.schema, &param.name))
+        .unwrap_or_default()
+}
+
 /// Check if a parameter is a boolean type
 fn is_boolean_parameter(param_schema: &JsonValue) -> bool {
     if let JsonValue::Object(obj) = param_schema {
@@ -340,6 +697,249 @@ fn map_json_schema_to_syntax_shape(param_schema: &JsonValue) -> SyntaxShape {
     }
 }
 
+/// A problem found while mapping a tool's schema onto a command signature
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SchemaProblem {
+    /// The parameter the problem is about, or `None` for the whole schema
+    pub parameter: Option<String>,
+    pub problem: String,
+}
+
+impl SchemaProblem {
+    fn new(parameter: Option<&str>, problem: impl Into<String>) -> Self {
+        Self {
+            parameter: parameter.map(str::to_string),
+            problem: problem.into(),
+        }
+    }
+}
+
+/// Check a tool's input schema for problems the mapper has to work around
+///
+/// Per-parameter problems are returned so they can be reported while the
+/// parameter is mapped to `SyntaxShape::Any`. An `Err` means the schema is
+/// unusable and the tool can't be registered at all.
+pub fn diagnose_tool_schema(tool: &Tool) -> Result<Vec<SchemaProblem>, SchemaProblem> {
+    let schema = tool.schema_as_json_value();
+    let Some(obj) = schema.as_object() else {
+        return Err(SchemaProblem::new(None, "input schema is not an object"));
+    };
+
+    match obj.get("type") {
+        None | Some(JsonValue::Null) => {}
+        Some(JsonValue::String(type_str)) if type_str == "object" => {}
+        Some(other) => {
+            return Err(SchemaProblem::new(
+                None,
+                format!("input schema has type {other}, expected \"object\""),
+            ));
+        }
+    }
+
+    let properties = match obj.get("properties") {
+        None => return Ok(Vec::new()),
+        Some(JsonValue::Object(properties)) => properties,
+        Some(other) => {
+            return Err(SchemaProblem::new(
+                None,
+                format!(
+                    "`properties` is {}, not an object, so no parameters can be mapped",
+                    json_type_name(other)
+                ),
+            ));
+        }
+    };
+
+    let mut problems = Vec::new();
+
+    for (name, param_schema) in properties {
+        if name.is_empty() || name.chars().any(char::is_whitespace) {
+            problems.push(SchemaProblem::new(
+                Some(name),
+                "the name can't be used as a flag because it is empty or contains whitespace",
+            ));
+        }
+
+        let Some(param_obj) = param_schema.as_object() else {
+            problems.push(SchemaProblem::new(
+                Some(name),
+                format!(
+                    "the schema is {}, not an object; accepting any value",
+                    json_type_name(param_schema)
+                ),
+            ));
+            continue;
+        };
+
+        match param_obj.get("type") {
+            None => {}
+            Some(JsonValue::String(type_str))
+                if matches!(
+                    type_str.as_str(),
+                    "string" | "number" | "integer" | "boolean" | "array" | "object" | "null"
+                ) => {}
+            Some(other) => problems.push(SchemaProblem::new(
+                Some(name),
+                format!("unsupported type {other}; accepting any value"),
+            )),
+        }
+    }
+
+    match obj.get("required") {
+        None => {}
+        Some(JsonValue::Array(required)) => {
+            for entry in required {
+                match entry.as_str() {
+                    Some(required_name) if !properties.contains_key(required_name) => {
+                        problems.push(SchemaProblem::new(
+                            Some(required_name),
+                            "listed in `required` but not declared in `properties`",
+                        ));
+                    }
+                    Some(_) => {}
+                    None => problems.push(SchemaProblem::new(
+                        None,
+                        format!("`required` contains {entry}, which is not a parameter name"),
+                    )),
+                }
+            }
+        }
+        Some(other) => problems.push(SchemaProblem::new(
+            None,
+            format!(
+                "`required` is {}, not an array; treating every parameter as optional",
+                json_type_name(other)
+            ),
+        )),
+    }
+
+    Ok(problems)
+}
+
+/// Validate a set of tool arguments against the tool's input schema
+///
+/// This checks that every required parameter is present, that no undeclared
+/// parameters are passed when the schema forbids additional properties, and
+/// that each provided value matches the JSON type declared for it.
+pub fn validate_tool_params(
+    tool: &Tool,
+    params: &serde_json::Map<String, JsonValue>,
+    span: Span,
+) -> McpResult<()> {
+    let schema = tool.schema_as_json_value();
+    let properties = get_schema_properties(tool).unwrap_or_default();
+
+    if let JsonValue::Object(obj) = &schema {
+        if let Some(JsonValue::Array(required)) = obj.get("required") {
+            for name in required.iter().filter_map(JsonValue::as_str) {
+                if !params.contains_key(name) {
+                    return Err(generic_error(
+                        format!("Missing required parameter '{name}'"),
+                        format!("The tool '{}' requires '{name}' to be provided", tool.name),
+                        span,
+                    ));
+                }
+            }
+        }
+
+        let allows_additional = !matches!(
+            obj.get("additionalProperties"),
+            Some(JsonValue::Bool(false))
+        );
+
+        for (name, value) in params {
+            let Some(param_schema) = properties.get(name) else {
+                if allows_additional {
+                    continue;
+                }
+
+                return Err(generic_error(
+                    format!("Unknown parameter '{name}'"),
+                    format!(
+                        "Valid parameters are: {}",
+                        properties.keys().cloned().collect::<Vec<_>>().join(", ")
+                    ),
+                    span,
+                ));
+            };
+
+            if !json_value_matches_schema_type(param_schema, value) {
+                let mut help = format!(
+                    "The {}; got {}",
+                    schema_example::expectation(name, param_schema),
+                    json_type_name(value)
+                );
+                if let Some(description) = get_parameter_description(param_schema) {
+                    help.push_str(&format!("\n'{name}': {description}"));
+                }
+
+                return Err(generic_error(
+                    format!("Invalid value for parameter '{name}'"),
+                    help,
+                    span,
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Check a JSON value against the `type` keyword of a parameter schema
+///
+/// Schemas without a `type` (or with a type we don't recognize) accept any value.
+fn json_value_matches_schema_type(param_schema: &JsonValue, value: &JsonValue) -> bool {
+    let Some(type_value) = param_schema.get("type") else {
+        return true;
+    };
+
+    match type_value {
+        JsonValue::String(type_str) => json_type_matches(type_str, value),
+        JsonValue::Array(types) => types
+            .iter()
+            .filter_map(JsonValue::as_str)
+            .any(|type_str| json_type_matches(type_str, value)),
+        _ => true,
+    }
+}
+
+fn json_type_matches(type_str: &str, value: &JsonValue) -> bool {
+    match type_str {
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// Describe the JSON type(s) a parameter schema accepts, e.g. `string` or `string or null`
+pub fn describe_schema_type(param_schema: &JsonValue) -> String {
+    match param_schema.get("type") {
+        Some(JsonValue::String(type_str)) => type_str.clone(),
+        Some(JsonValue::Array(types)) => types
+            .iter()
+            .filter_map(JsonValue::as_str)
+            .collect::<Vec<_>>()
+            .join(" or "),
+        _ => "any".to_string(),
+    }
+}
+
+const fn json_type_name(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "boolean",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+    }
+}
+
 /// Map Nushell values to JSON values for tool parameters
 /// Following the mapping strategy in MAPPING.md:
 /// 1. If the tool has exactly one required or optional parameter, map it onto a positional argument.
@@ -347,101 +947,703 @@ fn map_json_schema_to_syntax_shape(param_schema: &JsonValue) -> SyntaxShape {
 /// 3. If the tool has exactly one or two required parameters and all of the rest of the arguments are optional, map the required parameters onto positional arguments and the optional parameters onto flags.
 /// 4. Optional parameters that are booleans should be mapped to switches (e.g., `--verbose`).
 /// 5. All other optional parameters should be mapped to flags (e.g., `--limit 10`).
+/// 6. Parameters in the `--rest` record are added, unless given as flags too.
+///
+/// Arguments read from text, such as CSV cells or environment variables, are
+/// strings; a string given where the schema takes an integer, number or
+/// boolean is parsed into one, unless `--strict-types` is given.
 pub fn map_call_args_to_tool_params(
     engine_state: &EngineState,
     stack: &mut Stack,
     call: &nu_protocol::engine::Call<'_>,
     tool: &Tool,
+    max_flags: usize,
 ) -> McpResult<serde_json::Map<String, JsonValue>> {
     let mut params = serde_json::Map::new();
     let span = call.head;
 
-    // Get schema properties from the tool
-    if let Some(properties) = get_schema_properties(tool) {
-        let mut prop_vec: Vec<(String, JsonValue)> = properties.into_iter().collect();
+    let Some(properties) = get_schema_properties(tool) else {
+        return Ok(params);
+    };
+    let coerce = call_switch_available(tool, STRICT_TYPES_SWITCH)
+        && !call.has_flag(engine_state, stack, STRICT_TYPES_SWITCH)?;
+    let to_json = |param_name: &str, value: &Value| {
+        let schema = &properties[param_name];
+        match value {
+            Value::String { val, .. } if coerce => {
+                coerce_argument(param_name, val, schema, value.span())
+                    .unwrap_or_else(|| super::utils::convert_nu_value_to_json_value(value, span))
+            }
+            _ => super::utils::convert_nu_value_to_json_value(value, span),
+        }
+    };
 
-        // Sort properties so required ones are first (helps with positional args mapping)
-        prop_vec.sort_by(|(name1, _), (name2, _)| {
-            let req1 = is_parameter_required(tool, name1);
-            let req2 = is_parameter_required(tool, name2);
-            req2.cmp(&req1) // required first
-        });
+    // A positional parameter may be given positionally or as a flag, but
+    // not both: sending either one would silently drop the other
+    for (index, param_name) in positional_parameters(tool).iter().enumerate() {
+        let positional: Option<Value> = call.opt(engine_state, stack, index)?;
+        let flag: Option<Value> = call.get_flag(engine_state, stack, param_name)?;
+
+        let value = match (positional, flag) {
+            (Some(positional), Some(flag)) => {
+                return Err(given_twice(param_name, positional.span(), flag.span()));
+            }
+            (Some(value), None) => value,
+            (None, Some(value)) => {
+                debug!("'{param_name}' was given as --{param_name} instead of positionally");
+                value
+            }
+            (None, None) => continue,
+        };
+
+        let json_value = to_json(param_name, &value)?;
+        params.insert(param_name.clone(), json_value);
+    }
+
+    // Every other parameter can only be given as a flag
+    for param_name in properties.keys() {
+        if params.contains_key(param_name) {
+            continue;
+        }
+
+        if let Some(value) = call.get_flag::<Value>(engine_state, stack, param_name)? {
+            let json_value = to_json(param_name, &value)?;
+            params.insert(param_name.clone(), json_value);
+        }
+    }
+
+    if overflow_parameters(tool, max_flags).is_empty() {
+        return Ok(params);
+    }
+
+    // Explicit flags win over the same parameter in `--rest`; validation
+    // checks the rest against the schema like any other argument
+    if let Some(rest) = call.get_flag::<Value>(engine_state, stack, REST_FLAG)? {
+        let JsonValue::Object(rest_record) =
+            super::utils::convert_nu_value_to_json_value(&rest, span)?
+        else {
+            return Err(generic_error(
+                format!("--{REST_FLAG} must be a record"),
+                format!("Got {}", rest.get_type()),
+                rest.span(),
+            ));
+        };
+        for (name, value) in rest_record {
+            if params.contains_key(&name) {
+                debug!("'{name}' was given as a flag and in --{REST_FLAG}; using the flag");
+                continue;
+            }
+            let coerced = match (&value, properties.get(&name)) {
+                (JsonValue::String(text), Some(schema)) if coerce => {
+                    coerce_argument(&name, text, schema, rest.span())
+                }
+                _ => None,
+            };
+            params.insert(name, coerced.transpose()?.unwrap_or(value));
+        }
+    }
+
+    Ok(params)
+}
+
+/// A string argument parsed into the integer, number or boolean its
+/// parameter's schema takes
+///
+/// `None` when the schema takes strings, or none of those types, so the
+/// string is sent as it is. Surrounding whitespace is ignored, and booleans
+/// can be written `true`, `false`, `1` or `0`.
+fn coerce_argument(
+    param_name: &str,
+    text: &str,
+    param_schema: &JsonValue,
+    span: Span,
+) -> Option<McpResult<JsonValue>> {
+    let types: Vec<&str> = match param_schema.get("type") {
+        Some(JsonValue::String(type_str)) => vec![type_str.as_str()],
+        Some(JsonValue::Array(types)) => types.iter().filter_map(JsonValue::as_str).collect(),
+        _ => return None,
+    };
+    if types.contains(&"string") {
+        return None;
+    }
 
-        // Identify required and optional parameters
-        let required_params: Vec<(String, JsonValue)> = prop_vec
+    let trimmed = text.trim();
+    let mut expected = Vec::new();
+    for type_str in types {
+        let parsed = match type_str {
+            "integer" => trimmed
+                .parse::<i64>()
+                .map(JsonValue::from)
+                .or_else(|_| trimmed.parse::<u64>().map(JsonValue::from))
+                .ok(),
+            "number" => trimmed
+                .parse::<i64>()
+                .map(JsonValue::from)
+                .ok()
+                .or_else(|| {
+                    trimmed
+                        .parse::<f64>()
+                        .ok()
+                        .and_then(serde_json::Number::from_f64)
+                        .map(JsonValue::Number)
+                }),
+            "boolean" => match trimmed.to_ascii_lowercase().as_str() {
+                "true" | "1" => Some(JsonValue::Bool(true)),
+                "false" | "0" => Some(JsonValue::Bool(false)),
+                _ => None,
+            },
+            _ => continue,
+        };
+        if let Some(parsed) = parsed {
+            return Some(Ok(parsed));
+        }
+        expected.push(type_str);
+    }
+
+    if expected.is_empty() {
+        return None;
+    }
+    Some(Err(McpError::from(ShellError::GenericError {
+        error: format!("'{param_name}' must be {}", expected.join(" or ")),
+        msg: format!("'{text}' isn't {}", expected.join(" or ")),
+        span: Some(span),
+        help: Some(format!(
+            "Pass --{STRICT_TYPES_SWITCH} to send it as a string anyway"
+        )),
+        inner: Vec::new(),
+    })))
+}
+
+/// The parameters that map onto positional arguments, in order
+///
+/// 1. A tool with a single parameter takes it positionally.
+/// 2. A tool with exactly two required parameters takes both positionally.
+/// 3. A tool with one required parameter and some optional ones takes the
+///    required one positionally.
+pub fn positional_parameters(tool: &Tool) -> Vec<String> {
+    let Some(properties) = get_schema_properties(tool) else {
+        return Vec::new();
+    };
+
+    let required: Vec<&String> = properties
+        .keys()
+        .filter(|name| is_parameter_required(tool, name))
+        .collect();
+
+    if properties.len() == 1 {
+        properties.keys().cloned().collect()
+    } else if required.len() == 2 || (required.len() == 1 && properties.len() > 1) {
+        required.into_iter().cloned().collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// The error for a parameter given both positionally and as a flag
+fn given_twice(param_name: &str, positional: Span, flag: Span) -> McpError {
+    McpError::from(ShellError::GenericError {
+        error: format!("'{param_name}' was given twice"),
+        msg: format!("given as --{param_name} here"),
+        span: Some(flag),
+        help: Some(format!(
+            "Pass '{param_name}' either positionally or as --{param_name}, not both"
+        )),
+        inner: vec![ShellError::GenericError {
+            error: format!("'{param_name}' was also given positionally"),
+            msg: "given positionally here".into(),
+            span: Some(positional),
+            help: None,
+            inner: Vec::new(),
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::config::DEFAULT_MAX_FLAGS;
+
+    fn tool_with_schema(schema: JsonValue) -> Tool {
+        let JsonValue::Object(schema) = schema else {
+            panic!("test schemas must be objects");
+        };
+        Tool::new("broken", "A tool with a broken schema", Arc::new(schema))
+    }
+
+    #[test]
+    fn test_search_terms() {
+        let JsonValue::Object(schema) = json!({
+            "type": "object",
+            "properties": {"repo_owner": {"type": "string"}, "labels": {"type": "array"}}
+        }) else {
+            unreachable!()
+        };
+        let tool = Tool::new(
+            "create_issue",
+            "Opens a new issue in the repository. An issue has a title.",
+            Arc::new(schema),
+        );
+
+        assert_eq!(
+            search_terms(&tool),
+            [
+                "opens",
+                "new",
+                "issue",
+                "repository",
+                "title",
+                "repo",
+                "owner",
+                "labels"
+            ]
+        );
+        assert_eq!(
+            map_tool_to_signature(&tool, "tool", DEFAULT_MAX_FLAGS).search_terms,
+            search_terms(&tool)
+        );
+
+        let long = Tool::new(
+            "long",
+            (0..100).map(|i| format!("word{i} ")).collect::<String>(),
+            Arc::new(serde_json::Map::new()),
+        );
+        assert_eq!(search_terms(&long).len(), MAX_SEARCH_TERMS);
+    }
+
+    #[test]
+    fn test_diagnose_valid_schema() {
+        let tool = tool_with_schema(json!({
+            "type": "object",
+            "properties": {"path": {"type": "string"}},
+            "required": ["path"]
+        }));
+
+        assert_eq!(diagnose_tool_schema(&tool), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_diagnose_properties_array() {
+        let tool = tool_with_schema(json!({
+            "type": "object",
+            "properties": [{"name": "path"}]
+        }));
+
+        let problem = diagnose_tool_schema(&tool).unwrap_err();
+        assert_eq!(problem.parameter, None);
+        assert!(problem.problem.contains("`properties` is array"));
+    }
+
+    #[test]
+    fn test_diagnose_parameter_problems() {
+        let tool = tool_with_schema(json!({
+            "type": "object",
+            "properties": {
+                "path": "string",
+                "mode": {"type": ["string", "null"]},
+                "file name": {"type": "string"}
+            },
+            "required": ["path", "missing", 3]
+        }));
+
+        let problems = diagnose_tool_schema(&tool).unwrap();
+        let parameters: Vec<Option<&str>> = problems
             .iter()
-            .filter(|(name, _)| is_parameter_required(tool, name))
-            .map(|(name, schema)| (name.clone(), schema.clone()))
+            .map(|problem| problem.parameter.as_deref())
             .collect();
 
-        // Determine positional parameters based on the new rules
-        let total_param_count = prop_vec.len();
-        let positional_count = if total_param_count == 1 {
-            // Rule 1: If exactly one parameter (required or optional), make it positional
-            1
-        } else if required_params.len() == 2 {
-            // Rule 2: If exactly two required parameters, make them positional
-            2
-        } else {
-            usize::from(
-                required_params.len() == 1
-                    && prop_vec
-                        .iter()
-                        .any(|(name, _)| !is_parameter_required(tool, name)),
-            )
+        assert_eq!(
+            parameters,
+            vec![
+                Some("path"),
+                Some("mode"),
+                Some("file name"),
+                Some("missing"),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_broken_schema_still_maps() {
+        let tool = tool_with_schema(json!({
+            "type": "object",
+            "properties": {
+                "path": "string",
+                "mode": {"type": ["string", "null"]}
+            },
+            "required": "path"
+        }));
+
+        let signature = map_tool_to_signature(&tool, "tool", DEFAULT_MAX_FLAGS);
+        let flags: Vec<&str> = signature
+            .named
+            .iter()
+            .map(|flag| flag.long.as_str())
+            .collect();
+
+        assert!(flags.contains(&"path"));
+        assert!(flags.contains(&"mode"));
+    }
+
+    #[test]
+    fn test_schemaless_tool_takes_args_record() {
+        let flags = |tool: &Tool| -> Vec<String> {
+            add_call_flags(map_tool_to_signature(tool, "tool", DEFAULT_MAX_FLAGS), tool)
+                .named
+                .into_iter()
+                .map(|flag| flag.long)
+                .collect()
         };
 
-        // Process positional parameters based on our rules
-        for i in 0..positional_count {
-            let param_name: &str;
-
-            // For tools with a single parameter (required or optional)
-            if total_param_count == 1 {
-                let (name, _) = &prop_vec[0];
-                param_name = name;
-            } else if i < required_params.len() {
-                // Required parameters get priority for positional slots
-                let (name, _) = &required_params[i];
-                param_name = name;
-            } else {
-                // This shouldn't happen with our rules, but just in case
-                continue;
-            }
+        let schemaless = tool_with_schema(json!({}));
+        assert!(declares_no_schema(&schemaless));
+        assert!(flags(&schemaless).contains(&ARGS_FLAG.to_string()));
 
-            // Try to get it as a positional argument
-            let value_result = match i {
-                0 => call.opt(engine_state, stack, 0),
-                1 => call.opt(engine_state, stack, 1),
-                _ => unreachable!(), // Our rules limit to at most 2 positional parameters
-            };
+        let no_params = tool_with_schema(json!({"type": "object", "properties": {}}));
+        assert!(!declares_no_schema(&no_params));
+        assert!(!flags(&no_params).contains(&ARGS_FLAG.to_string()));
+    }
 
-            if let Ok(Some(value)) = value_result {
-                let json_value = super::utils::convert_nu_value_to_json_value(&value, span)?;
-                params.insert(param_name.to_string(), json_value);
-                continue; // Skip to next parameter
-            }
+    #[test]
+    fn test_diagnose_non_object_type() {
+        let tool = tool_with_schema(json!({"type": "string"}));
+        assert!(diagnose_tool_schema(&tool).is_err());
+    }
 
-            // If not found as positional, try as flag (fallback)
-            if let Some(value) = call.get_flag(engine_state, stack, param_name)? {
-                let json_value = super::utils::convert_nu_value_to_json_value(&value, span)?;
-                params.insert(param_name.to_string(), json_value);
-            }
+    /// Parse `args` against the tool's generated signature and map them
+    fn map_args(tool: &Tool, args: &str) -> McpResult<serde_json::Map<String, JsonValue>> {
+        map_args_capped(tool, args, DEFAULT_MAX_FLAGS)
+    }
+
+    fn map_args_capped(
+        tool: &Tool,
+        args: &str,
+        max_flags: usize,
+    ) -> McpResult<serde_json::Map<String, JsonValue>> {
+        use nu_protocol::{
+            ast::Expr,
+            engine::{Call, StateWorkingSet},
+        };
+
+        let mut engine_state = EngineState::new();
+        let mut working_set = StateWorkingSet::new(&engine_state);
+        super::super::tool::register_dynamic_tool(
+            &mut working_set,
+            &tool.name,
+            map_tool_to_signature(tool, "tool", max_flags),
+            String::new(),
+            String::new(),
+            Box::new(|_, _, _, input| Ok(input)),
+        );
+        let delta = working_set.render();
+        engine_state.merge_delta(delta).unwrap();
+
+        let mut working_set = StateWorkingSet::new(&engine_state);
+        let source = format!("{} {args}", tool.name);
+        let block = nu_parser::parse(&mut working_set, None, source.as_bytes(), false);
+        assert!(
+            working_set.parse_errors.is_empty(),
+            "{source} failed to parse"
+        );
+
+        let Expr::Call(call) = &block.pipelines[0].elements[0].expr.expr else {
+            panic!("{source} didn't parse into a call");
+        };
+        map_call_args_to_tool_params(
+            &engine_state,
+            &mut Stack::new(),
+            &Call::from(&**call),
+            tool,
+            max_flags,
+        )
+    }
+
+    fn given_twice_error(result: McpResult<serde_json::Map<String, JsonValue>>) -> String {
+        match result.map_err(ShellError::from) {
+            Err(ShellError::GenericError { error, inner, .. }) if inner.len() == 1 => error,
+            other => panic!("expected an error pointing at both arguments, got {other:?}"),
         }
+    }
 
-        // Process all parameters (including the remaining required ones) as flags
-        for (param_name, _) in &prop_vec {
-            // Skip parameters we've already processed as positional arguments
-            if params.contains_key(param_name) {
-                continue;
-            }
+    #[test]
+    fn test_positional_parameters_are_also_flags() {
+        let tool = tool_with_schema(json!({
+            "type": "object",
+            "properties": {"src": {"type": "string"}, "dst": {"type": "string"}},
+            "required": ["src", "dst"]
+        }));
+
+        let signature = map_tool_to_signature(&tool, "tool", DEFAULT_MAX_FLAGS);
+        let positionals: Vec<&str> = signature
+            .optional_positional
+            .iter()
+            .map(|positional| positional.name.as_str())
+            .collect();
+        assert_eq!(positionals, vec!["src", "dst"]);
+        assert!(signature.required_positional.is_empty());
+        assert!(
+            signature.optional_positional[0]
+                .desc
+                .ends_with("(required)")
+        );
+        assert!(signature.named.iter().any(|flag| flag.long == "src"));
+        assert!(signature.named.iter().any(|flag| flag.long == "dst"));
+    }
+
+    #[test]
+    fn test_map_positional_or_flag() {
+        let tool = tool_with_schema(json!({
+            "type": "object",
+            "properties": {"path": {"type": "string"}, "mode": {"type": "string"}},
+            "required": ["path"]
+        }));
 
-            // Process remaining parameters as flags
-            if let Some(value) = call.get_flag(engine_state, stack, &param_name.to_string())? {
-                let json_value = super::utils::convert_nu_value_to_json_value(&value, span)?;
-                params.insert(param_name.to_string(), json_value);
+        let expected = json!({"path": "a"});
+        assert_eq!(JsonValue::Object(map_args(&tool, "a").unwrap()), expected);
+        assert_eq!(
+            JsonValue::Object(map_args(&tool, "--path a").unwrap()),
+            expected
+        );
+        assert_eq!(
+            JsonValue::Object(map_args(&tool, "a --mode x").unwrap()),
+            json!({"path": "a", "mode": "x"})
+        );
+        assert_eq!(
+            given_twice_error(map_args(&tool, "--path a b")),
+            "'path' was given twice"
+        );
+        assert_eq!(
+            given_twice_error(map_args(&tool, "b --path a")),
+            "'path' was given twice"
+        );
+    }
+
+    #[test]
+    fn test_wide_schema_takes_rest_record() {
+        let tool = tool_with_schema(json!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "string"},
+                "opt_a": {"type": "string"},
+                "opt_b": {"type": "string"},
+                "opt_c": {"type": "string"},
+                "opt_d": {"type": "string"}
+            },
+            "required": ["id"]
+        }));
+
+        assert_eq!(overflow_parameters(&tool, 2), vec!["opt_c", "opt_d"]);
+        assert!(overflow_parameters(&tool, 0).is_empty());
+        assert!(overflow_parameters(&tool, DEFAULT_MAX_FLAGS).is_empty());
+
+        let signature = map_tool_to_signature(&tool, "tool", 2);
+        let flags: Vec<&str> = signature
+            .named
+            .iter()
+            .map(|flag| flag.long.as_str())
+            .collect();
+        assert!(flags.contains(&"opt_b"));
+        assert!(!flags.contains(&"opt_c"));
+        let rest = signature
+            .named
+            .iter()
+            .find(|flag| flag.long == REST_FLAG)
+            .unwrap();
+        assert!(rest.desc.ends_with("opt_c, opt_d"), "{}", rest.desc);
+
+        // An explicit flag wins over the same key in --rest
+        assert_eq!(
+            JsonValue::Object(
+                map_args_capped(&tool, "x --opt_a one --rest {opt_c: three, opt_a: nine}", 2)
+                    .unwrap()
+            ),
+            json!({"id": "x", "opt_a": "one", "opt_c": "three"})
+        );
+    }
+
+    #[test]
+    fn test_map_two_positionals_or_flags() {
+        let tool = tool_with_schema(json!({
+            "type": "object",
+            "properties": {"src": {"type": "string"}, "dst": {"type": "string"}},
+            "required": ["src", "dst"]
+        }));
+
+        let expected = json!({"src": "a", "dst": "b"});
+        for args in ["a b", "--src a --dst b", "a --dst b", "--dst b a"] {
+            assert_eq!(
+                JsonValue::Object(map_args(&tool, args).unwrap()),
+                expected,
+                "{args}"
+            );
+        }
+
+        // The first positional argument always fills `src`
+        assert_eq!(
+            given_twice_error(map_args(&tool, "--src a b")),
+            "'src' was given twice"
+        );
+        assert_eq!(
+            given_twice_error(map_args(&tool, "a b --dst c")),
+            "'dst' was given twice"
+        );
+    }
+
+    #[test]
+    fn test_map_single_optional_parameter() {
+        let tool = tool_with_schema(json!({
+            "type": "object",
+            "properties": {"limit": {"type": "integer"}}
+        }));
+
+        assert!(map_args(&tool, "").unwrap().is_empty());
+        assert_eq!(
+            JsonValue::Object(map_args(&tool, "--limit 1").unwrap()),
+            json!({"limit": 1})
+        );
+        assert_eq!(
+            given_twice_error(map_args(&tool, "--limit 1 2")),
+            "'limit' was given twice"
+        );
+    }
+
+    #[test]
+    fn test_coerce_argument_matrix() {
+        let texts = [
+            "42", " 42 ", "-7", "2.5", "1e3", "true", "FALSE", "1", "0", "yes", "",
+        ];
+        let schema_types = [
+            json!("integer"),
+            json!("number"),
+            json!("boolean"),
+            json!("string"),
+            json!(["integer", "null"]),
+            json!(["boolean", "string"]),
+            json!("array"),
+        ];
+        let as_integer = |text: &str| text.parse::<i64>().ok().map(JsonValue::from);
+        let as_number =
+            |text: &str| as_integer(text).or_else(|| text.parse::<f64>().ok().map(JsonValue::from));
+        let as_boolean = |text: &str| match text {
+            "true" | "1" => Some(json!(true)),
+            "FALSE" | "0" => Some(json!(false)),
+            _ => None,
+        };
+
+        for text in texts {
+            for schema_type in &schema_types {
+                let trimmed = text.trim();
+                // `None` for a string that's sent as it is, `Some(None)` for
+                // one that can't be what the schema takes
+                let expected = match schema_type.to_string().as_str() {
+                    r#""integer""# | r#"["integer","null"]"# => Some(as_integer(trimmed)),
+                    r#""number""# => Some(as_number(trimmed)),
+                    r#""boolean""# => Some(as_boolean(trimmed)),
+                    _ => None,
+                };
+
+                let coerced =
+                    coerce_argument("p", text, &json!({"type": schema_type}), Span::test_data());
+                match (coerced, expected) {
+                    (None, None) => {}
+                    (Some(Ok(value)), Some(Some(expected))) => {
+                        assert_eq!(value, expected, "{text:?} as {schema_type}");
+                    }
+                    (Some(Err(err)), Some(None)) => {
+                        let err = ShellError::from(err).to_string();
+                        assert!(err.contains("'p' must be"), "{err}");
+                    }
+                    (coerced, expected) => {
+                        panic!("{text:?} as {schema_type}: got {coerced:?}, expected {expected:?}")
+                    }
+                }
             }
         }
     }
 
-    Ok(params)
+    #[test]
+    fn test_map_coerces_string_arguments() {
+        let tool = tool_with_schema(json!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "string"},
+                "limit": {"type": ["integer", "null"]},
+                "count": {"type": "integer"},
+                "dry_run": {"type": "boolean"}
+            },
+            "required": ["id"]
+        }));
+        let map = |args| map_args_capped(&tool, args, 1).map(JsonValue::Object);
+
+        assert_eq!(
+            map("'7' --limit '5' --rest {count: ' 42 ', dry_run: '1'}").unwrap(),
+            json!({"id": "7", "limit": 5, "count": 42, "dry_run": true})
+        );
+        assert_eq!(
+            map("x --strict-types --limit '5' --rest {count: '42'}").unwrap(),
+            json!({"id": "x", "limit": "5", "count": "42"})
+        );
+
+        let err = ShellError::from(map("x --rest {count: 'many'}").unwrap_err());
+        let ShellError::GenericError { error, msg, .. } = err else {
+            panic!("expected a generic error, got {err:?}");
+        };
+        assert_eq!(error, "'count' must be integer");
+        assert_eq!(msg, "'many' isn't integer");
+    }
+
+    #[test]
+    fn test_parameter_plan() {
+        let tool = tool_with_schema(json!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "string"},
+                "verbose": {"type": "boolean"},
+                "limit": {"type": "integer"},
+                "cursor": {"type": "string"}
+            },
+            "required": ["id"]
+        }));
+
+        let plan = plan_parameters(&tool, 2);
+        let decisions: Vec<(&str, Placement, MappingRule)> = plan
+            .parameters
+            .iter()
+            .map(|param| (param.name.as_str(), param.placement, param.rule))
+            .collect();
+        assert_eq!(
+            decisions,
+            vec![
+                ("id", Placement::Positional, MappingRule::OneRequired),
+                ("id", Placement::Flag, MappingRule::AlsoFlag),
+                ("verbose", Placement::Switch, MappingRule::OptionalBoolean),
+                ("limit", Placement::Flag, MappingRule::Flag),
+                ("cursor", Placement::Rest, MappingRule::Overflow),
+            ]
+        );
+        assert_eq!(plan.overflow, vec!["cursor"]);
+        assert_eq!(plan.parameters[3].shape, SyntaxShape::Int);
+
+        let pair = tool_with_schema(json!({
+            "type": "object",
+            "properties": {"src": {"type": "string"}, "dst": {"type": "string"}},
+            "required": ["src", "dst"]
+        }));
+        assert!(
+            plan_parameters(&pair, DEFAULT_MAX_FLAGS)
+                .parameters
+                .iter()
+                .filter(|param| param.placement == Placement::Positional)
+                .all(|param| param.rule == MappingRule::TwoRequired && param.required)
+        );
+
+        assert_eq!(
+            plan_parameters(&tool_with_schema(json!({})), DEFAULT_MAX_FLAGS),
+            ParameterPlan::default()
+        );
+    }
 }
@@ -0,0 +1,344 @@
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, PipelineData, Record, ShellError, Signature, Span, Spanned, SyntaxShape,
+    Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+use serde_json::Value as JsonValue;
+
+use super::{
+    mcp_tools::{apply_display_hook, format_tool_contents, prepare_tool_call},
+    tool_call::record_to_params,
+};
+use crate::{
+    config::DEFAULT_STUCK_AFTER,
+    engine::{BlockOnError, Watchdog, block_on_shared, get_mcp_client_manager_sync},
+    mcp_manager::{McpClientManager, RegisteredTool},
+};
+
+/// How many servers `tool par-call` calls at once
+const MAX_CONCURRENT_CALLS: usize = 8;
+
+/// Command to call the same tool on several servers concurrently
+#[derive(Clone)]
+pub struct ToolParCallCommand;
+
+impl Command for ToolParCallCommand {
+    fn name(&self) -> &'static str {
+        "tool par-call"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("tool par-call")
+            .category(Category::Custom("mcp".into()))
+            .required(
+                "name",
+                SyntaxShape::String,
+                "The tool to call, either bare or as server.tool",
+            )
+            .named(
+                "args",
+                SyntaxShape::Record(vec![]),
+                "A record of arguments to pass to every call",
+                Some('a'),
+            )
+            .named(
+                "servers",
+                SyntaxShape::List(Box::new(SyntaxShape::String)),
+                "The servers to call (default: every server that has the tool)",
+                Some('s'),
+            )
+            .input_output_types(vec![(Type::Nothing, Type::Table(vec![].into()))])
+    }
+
+    fn description(&self) -> &'static str {
+        "Call an identically-named tool on several MCP servers at once"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "Returns one row per server with whether the call succeeded, how long it took, and the result. Servers that don't have the tool, or whose call fails, appear as error rows instead of aborting the whole call. Up to 8 servers are called at once. Each result goes through its tool's display hook, as a single call's would. Unlike a single call, this doesn't set $mcp_last, $mcp_last_call or $env.LAST_MCP_ERROR, since every row has its own result and error."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Query the search tool on two servers",
+                example: "tool par-call search.query --args {q: \"foo\"} --servers [projA projB]",
+                result: None,
+            },
+            Example {
+                description: "Query every server that has a `query` tool",
+                example: "tool par-call query --args {q: \"foo\"}",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let name: Spanned<String> = call.req(engine_state, stack, 0)?;
+        let args: Option<Value> = call.get_flag(engine_state, stack, "args")?;
+        let servers: Option<Vec<String>> = call.get_flag(engine_state, stack, "servers")?;
+
+        let params = match &args {
+            Some(args) => record_to_params(args, span)?,
+            None => serde_json::Map::new(),
+        };
+
+        let manager = get_mcp_client_manager_sync();
+
+        // `search.query` names the tool `query`; the server prefix is only
+        // there so the name reads the same as the generated commands.
+        let tool_name = match name.item.split_once('.') {
+            Some((server, tool)) if manager.get_server(server).is_some() => tool.to_string(),
+            _ => name.item.clone(),
+        };
+
+        let servers = servers.unwrap_or_else(|| servers_with_tool(&manager, &tool_name));
+        if servers.is_empty() {
+            return Err(ShellError::GenericError {
+                error: format!("No server has a tool named '{tool_name}'"),
+                msg: "nothing to call".into(),
+                span: Some(name.span),
+                help: Some("Run `tool list` to see the available tools".into()),
+                inner: Vec::new(),
+            });
+        }
+
+        let targets = resolve_targets(&manager, servers, &tool_name);
+        drop(manager);
+
+        let rows = call_targets(engine_state, stack, targets, &tool_name, &params, span)?;
+        Ok(PipelineData::Value(Value::list(rows, span), None))
+    }
+}
+
+/// The servers that have a tool named `tool_name`, in registration order
+fn servers_with_tool(manager: &McpClientManager, tool_name: &str) -> Vec<String> {
+    manager
+        .get_servers()
+        .iter()
+        .filter(|(_, server)| server.tools.contains_key(tool_name))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Each server's tool, or why it can't be called
+fn resolve_targets(
+    manager: &McpClientManager,
+    servers: Vec<String>,
+    tool_name: &str,
+) -> Vec<(String, Result<RegisteredTool, String>)> {
+    servers
+        .into_iter()
+        .map(|server_name| {
+            let target = manager.get_server(&server_name).map_or_else(
+                || Err(format!("Unknown MCP server '{server_name}'")),
+                |server| {
+                    server.tools.get(tool_name).cloned().ok_or_else(|| {
+                        format!("Server '{server_name}' has no tool named '{tool_name}'")
+                    })
+                },
+            );
+            (server_name, target)
+        })
+        .collect()
+}
+
+/// Call the tool on every target, at most [`MAX_CONCURRENT_CALLS`] at once,
+/// and return one row per target in the order they were given
+///
+/// Each call is prepared first, one target at a time, so any confirmation
+/// is asked before the calls start. A target that can't be called, isn't
+/// confirmed, or whose call fails or is flagged with `isError`, becomes an
+/// error row. A result is converted and goes through the tool's display
+/// hook the way a single call's is.
+fn call_targets(
+    engine_state: &EngineState,
+    stack: &Stack,
+    targets: Vec<(String, Result<RegisteredTool, String>)>,
+    tool_name: &str,
+    params: &serde_json::Map<String, JsonValue>,
+    span: Span,
+) -> Result<Vec<Value>, ShellError> {
+    let signals = engine_state.signals();
+    let calls: Vec<_> = targets
+        .into_iter()
+        .map(|(server_name, target)| {
+            let call = target.and_then(|registered| {
+                let prepared = prepare_tool_call(
+                    engine_state,
+                    &registered.client,
+                    &registered.tool,
+                    params.clone(),
+                    &registered.settings,
+                    span,
+                )
+                .map_err(|(_, err)| err.to_string())?;
+                let future = prepared.future();
+                Ok((prepared, future))
+            });
+
+            async move {
+                let started = Instant::now();
+                let outcome = match call {
+                    Ok((prepared, future)) => future
+                        .await
+                        .map(|contents| (prepared, contents))
+                        .map_err(|err| err.message),
+                    Err(err) => Err(err),
+                };
+                (server_name, outcome, started.elapsed())
+            }
+        })
+        .collect();
+    let all = futures::stream::iter(calls)
+        .buffered(MAX_CONCURRENT_CALLS)
+        .collect::<Vec<_>>();
+
+    let label = format!("tool par-call {tool_name}");
+    let watchdog = Watchdog {
+        label: &label,
+        stuck_after: DEFAULT_STUCK_AFTER,
+    };
+    let finished = match block_on_shared(all, signals, Some(watchdog)) {
+        Ok(finished) => Ok(finished),
+        Err(BlockOnError::Interrupted) => Err("Interrupted by Ctrl-C".to_string()),
+        Err(BlockOnError::Panicked(message)) => Err(format!("The tool calls panicked: {message}")),
+    }
+    .map_err(|msg| ShellError::GenericError {
+        error: format!("Failed to call '{tool_name}'"),
+        msg,
+        span: Some(span),
+        help: None,
+        inner: Vec::new(),
+    })?;
+
+    Ok(finished
+        .into_iter()
+        .map(|(server_name, outcome, duration)| {
+            let outcome = outcome.and_then(|(prepared, contents)| {
+                let settings = prepared.settings();
+                format_tool_contents(contents, settings, span, signals)
+                    .and_then(|data| {
+                        apply_display_hook(
+                            engine_state,
+                            stack,
+                            settings,
+                            &server_name,
+                            tool_name,
+                            data,
+                            span,
+                        )
+                    })
+                    .and_then(|data| data.into_value(span))
+                    .map_err(|err| err.to_string())
+            });
+            observation_row(server_name, outcome, duration, span)
+        })
+        .collect())
+}
+
+fn observation_row(
+    server: String,
+    outcome: Result<Value, String>,
+    duration: Duration,
+    span: Span,
+) -> Value {
+    let mut record = Record::new();
+    record.push("server", Value::string(server, span));
+    record.push("success", Value::bool(outcome.is_ok(), span));
+    record.push(
+        "duration",
+        Value::duration(i64::try_from(duration.as_nanos()).unwrap_or(i64::MAX), span),
+    );
+
+    match outcome {
+        Ok(result) => {
+            record.push("result", result);
+            record.push("error", Value::nothing(span));
+        }
+        Err(error) => {
+            record.push("result", Value::nothing(span));
+            record.push("error", Value::string(error, span));
+        }
+    }
+
+    Value::record(record, span)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp_manager::tests::mock_client;
+
+    fn manager() -> McpClientManager {
+        let mut engine_state = EngineState::new();
+        let mut manager = McpClientManager::default();
+        let clients = [
+            mock_client("a", &["query"]),
+            mock_client("b", &["other"]),
+            mock_client("c", &["other", "query"]),
+        ];
+        for client in &clients {
+            manager
+                .register_client(client.name.clone(), client, &mut engine_state)
+                .unwrap();
+        }
+        manager
+    }
+
+    #[test]
+    fn test_servers_with_tool() {
+        let manager = manager();
+        assert_eq!(servers_with_tool(&manager, "query"), ["a", "c"]);
+        assert_eq!(servers_with_tool(&manager, "other"), ["b", "c"]);
+        assert!(servers_with_tool(&manager, "missing").is_empty());
+    }
+
+    #[test]
+    fn test_every_server_gets_a_row() {
+        let manager = manager();
+        let servers = vec!["a".to_string(), "gone".to_string(), "b".to_string()];
+        let targets = resolve_targets(&manager, servers, "query");
+        let rows = call_targets(
+            &EngineState::new(),
+            &Stack::new(),
+            targets,
+            "query",
+            &serde_json::Map::new(),
+            Span::test_data(),
+        )
+        .unwrap();
+
+        let column = |key: &str| -> Vec<Value> {
+            rows.iter()
+                .map(|row| row.get_data_by_key(key).unwrap())
+                .collect()
+        };
+        assert_eq!(column("server"), ["a", "gone", "b"].map(Value::test_string));
+        assert_eq!(column("success"), [false; 3].map(Value::test_bool));
+        // The mock clients are offline, so even the server with the tool
+        // fails, and its failure doesn't stop the others
+        assert_eq!(
+            column("error"),
+            [
+                "offline mode: call not executed",
+                "Unknown MCP server 'gone'",
+                "Server 'b' has no tool named 'query'",
+            ]
+            .map(Value::test_string)
+        );
+        assert_eq!(column("result"), [(); 3].map(|()| Value::test_nothing()));
+    }
+}
@@ -0,0 +1,304 @@
+//! Prompting for tool parameters on the terminal
+
+use nu_protocol::{ShellError, Signature, Span};
+use rmcp::model::Tool;
+use serde_json::Value as JsonValue;
+
+use super::tool_mapper::{self, ToolParameter};
+use crate::util::prompt;
+
+/// The switch that turns on prompting for a single call
+pub const INTERACTIVE_SWITCH: &str = "interactive";
+
+/// Ask for every required parameter missing from `params`
+///
+/// Each prompt shows the parameter's description and type (or its choices,
+/// for enums), and the answer is converted according to the schema. When
+/// anything was prompted for, the equivalent command line is printed so the
+/// call can be repeated without prompting.
+pub fn prompt_missing_params(
+    command_name: &str,
+    tool: &Tool,
+    max_flags: usize,
+    mut params: serde_json::Map<String, JsonValue>,
+    span: Span,
+) -> Result<serde_json::Map<String, JsonValue>, ShellError> {
+    let missing: Vec<_> = tool_mapper::tool_parameters(tool)
+        .into_iter()
+        .filter(|param| param.required && !params.contains_key(&param.name))
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(params);
+    }
+
+    for param in &missing {
+        let value = ask(param).map_err(|msg| ShellError::GenericError {
+            error: format!("Missing required parameter '{}'", param.name),
+            msg,
+            span: Some(span),
+            help: None,
+            inner: Vec::new(),
+        })?;
+        if let Some(value) = value {
+            params.insert(param.name.clone(), value);
+        }
+    }
+
+    let signature = tool_mapper::map_tool_to_signature(tool, "tool", max_flags);
+    crate::info!("{}", command_line(command_name, &signature, &params));
+
+    Ok(params)
+}
+
+/// Ask for one parameter, repeating the prompt until the answer converts
+///
+/// An empty answer skips an optional parameter (`Ok(None)`) and cancels when
+/// the parameter is required.
+pub fn ask(param: &ToolParameter) -> Result<Option<JsonValue>, String> {
+    let choices = enum_choices(&param.schema);
+    let description = tool_mapper::parameter_description(param);
+    let show = |text: String| {
+        prompt::show(&text).map_err(|err| format!("failed to write to the terminal: {err}"))
+    };
+
+    if !description.is_empty() {
+        show(format!("{}: {description}", param.name))?;
+    }
+    if let Some(default) = param.schema.get("default") {
+        show(format!("  default: {}", display_value(default)))?;
+    }
+
+    let hint = if choices.is_empty() {
+        tool_mapper::describe_schema_type(&param.schema)
+    } else {
+        for (index, choice) in choices.iter().enumerate() {
+            show(format!("  {}) {}", index + 1, display_value(choice)))?;
+        }
+        "number or value".to_string()
+    };
+
+    let empty = if param.required {
+        "empty to cancel"
+    } else {
+        "empty to skip"
+    };
+
+    loop {
+        let answer = prompt::read_line(&format!("{} ({hint}, {empty}): ", param.name))
+            .map_err(|err| format!("failed to read input: {err}"))?;
+
+        if answer.trim().is_empty() {
+            return if param.required {
+                Err("cancelled at the prompt".into())
+            } else {
+                Ok(None)
+            };
+        }
+
+        match convert_answer(&param.schema, &choices, answer.trim()) {
+            Ok(value) => return Ok(Some(value)),
+            Err(problem) => crate::warning!("{}", problem),
+        }
+    }
+}
+
+fn enum_choices(schema: &JsonValue) -> Vec<JsonValue> {
+    match schema.get("enum") {
+        Some(JsonValue::Array(choices)) => choices.clone(),
+        _ => Vec::new(),
+    }
+}
+
+/// Convert a line of input into a value of the parameter's type
+///
+/// Enum answers may be a choice's number, its value, or a prefix that
+/// matches only one choice.
+fn convert_answer(
+    schema: &JsonValue,
+    choices: &[JsonValue],
+    answer: &str,
+) -> Result<JsonValue, String> {
+    if !choices.is_empty() {
+        if let Some(choice) = answer
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| index.checked_sub(1))
+            .and_then(|index| choices.get(index))
+        {
+            return Ok(choice.clone());
+        }
+
+        let matching: Vec<_> = choices
+            .iter()
+            .filter(|choice| display_value(choice).starts_with(answer))
+            .collect();
+
+        return match matching.as_slice() {
+            [choice] => Ok((*choice).clone()),
+            _ => match choices
+                .iter()
+                .find(|choice| display_value(choice) == answer)
+            {
+                Some(choice) => Ok(choice.clone()),
+                None => Err(format!("'{answer}' is not one of the choices")),
+            },
+        };
+    }
+
+    let invalid = |expected: &str| format!("'{answer}' is not {expected}");
+
+    match schema.get("type").and_then(JsonValue::as_str) {
+        Some("string") => Ok(JsonValue::String(answer.to_string())),
+        Some("integer") => answer
+            .parse::<i64>()
+            .map(JsonValue::from)
+            .map_err(|_| invalid("an integer")),
+        Some("number") => answer
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(JsonValue::Number)
+            .ok_or_else(|| invalid("a number")),
+        Some("boolean") => match answer.to_lowercase().as_str() {
+            "true" | "yes" | "y" => Ok(JsonValue::Bool(true)),
+            "false" | "no" | "n" => Ok(JsonValue::Bool(false)),
+            _ => Err(invalid("true or false")),
+        },
+        Some("array") => match serde_json::from_str(answer) {
+            Ok(value @ JsonValue::Array(_)) => Ok(value),
+            _ => Err(invalid("a JSON array")),
+        },
+        Some("object") => match serde_json::from_str(answer) {
+            Ok(value @ JsonValue::Object(_)) => Ok(value),
+            _ => Err(invalid("a JSON object")),
+        },
+        // Without a usable type, take JSON if it parses and a string otherwise
+        _ => {
+            Ok(serde_json::from_str(answer)
+                .unwrap_or_else(|_| JsonValue::String(answer.to_string())))
+        }
+    }
+}
+
+/// Render a call as the command line that would make it without prompting
+pub fn command_line(
+    command_name: &str,
+    signature: &Signature,
+    params: &serde_json::Map<String, JsonValue>,
+) -> String {
+    let mut words = vec![command_name.to_string()];
+    let positionals: Vec<_> = signature
+        .required_positional
+        .iter()
+        .chain(&signature.optional_positional)
+        .map(|positional| positional.name.as_str())
+        .collect();
+
+    for name in &positionals {
+        match params.get(*name) {
+            Some(value) => words.push(nu_literal(value)),
+            None => break,
+        }
+    }
+
+    // Parameters without a flag of their own go in the `--rest` record
+    let has_rest = signature
+        .named
+        .iter()
+        .any(|flag| flag.long == tool_mapper::REST_FLAG);
+    let mut rest = serde_json::Map::new();
+
+    for (name, value) in params {
+        if positionals.contains(&name.as_str()) {
+            continue;
+        }
+
+        let flag = signature.named.iter().find(|flag| flag.long == *name);
+        match (flag, value) {
+            (None, value) if has_rest => {
+                rest.insert(name.clone(), value.clone());
+            }
+            (Some(flag), JsonValue::Bool(true)) if flag.arg.is_none() => {
+                words.push(format!("--{name}"));
+            }
+            (Some(flag), _) if flag.arg.is_none() => {}
+            (_, value) => words.push(format!("--{name} {}", nu_literal(value))),
+        }
+    }
+
+    if !rest.is_empty() {
+        words.push(format!(
+            "--{} {}",
+            tool_mapper::REST_FLAG,
+            nu_literal(&JsonValue::Object(rest))
+        ));
+    }
+
+    words.join(" ")
+}
+
+/// Write a JSON value as a Nushell literal
+///
+/// JSON strings, numbers, lists and objects are all valid Nushell syntax.
+fn nu_literal(value: &JsonValue) -> String {
+    value.to_string()
+}
+
+fn display_value(value: &JsonValue) -> String {
+    match value {
+        JsonValue::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::config::DEFAULT_MAX_FLAGS;
+
+    #[test]
+    fn test_convert_answer() {
+        let choices = vec![json!("fast"), json!("slow")];
+        assert_eq!(convert_answer(&json!({}), &choices, "2"), Ok(json!("slow")));
+        assert_eq!(convert_answer(&json!({}), &choices, "f"), Ok(json!("fast")));
+        assert!(convert_answer(&json!({}), &choices, "medium").is_err());
+
+        let integer = json!({"type": "integer"});
+        assert_eq!(convert_answer(&integer, &[], "42"), Ok(json!(42)));
+        assert!(convert_answer(&integer, &[], "forty-two").is_err());
+
+        let string = json!({"type": "string"});
+        assert_eq!(convert_answer(&string, &[], "42"), Ok(json!("42")));
+    }
+
+    #[test]
+    fn test_command_line() {
+        let serde_json::Value::Object(schema) = json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string"},
+                "recursive": {"type": "boolean"},
+                "depth": {"type": "integer"}
+            },
+            "required": ["path"]
+        }) else {
+            unreachable!();
+        };
+        let tool = Tool::new("list", "List a directory", Arc::new(schema));
+        let signature = tool_mapper::map_tool_to_signature(&tool, "tool", DEFAULT_MAX_FLAGS);
+
+        let JsonValue::Object(params) = json!({"path": "src", "recursive": true}) else {
+            unreachable!();
+        };
+
+        assert_eq!(
+            command_line("tool fs.list", &signature, &params),
+            r#"tool fs.list "src" --recursive"#
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, PipelineData, ShellError, Signature, Spanned, SyntaxShape, Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+
+use super::{
+    mcp_tools::{CallOptions, run_tool_call},
+    tool_call::collect_call_args,
+};
+use crate::{engine::get_mcp_client_manager_sync, util::annotations::Audience};
+
+/// Command to call an MCP tool and return the response without converting it
+#[derive(Clone)]
+pub struct ToolRawCommand;
+
+impl Command for ToolRawCommand {
+    fn name(&self) -> &'static str {
+        "tool raw"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("tool raw")
+            .category(Category::Custom("mcp".into()))
+            .required(
+                "name",
+                SyntaxShape::String,
+                "The namespaced tool name (server.tool)",
+            )
+            .optional(
+                "args",
+                SyntaxShape::Record(vec![]),
+                "A record of arguments to pass to the tool",
+            )
+            .named(
+                "json",
+                SyntaxShape::String,
+                "The tool arguments as a JSON object string",
+                Some('j'),
+            )
+            .input_output_types(vec![
+                (Type::Nothing, Type::Record(vec![].into())),
+                (Type::Record(vec![].into()), Type::Record(vec![].into())),
+            ])
+    }
+
+    fn description(&self) -> &'static str {
+        "Call an MCP tool and return the server's response as it was sent"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "The arguments are checked and sent exactly as `tool call` sends them, but none of the result's content is converted: text isn't parsed as JSON, and images and resources are left as they are. The result is the whole response, with `isError` and each content block's fields, including annotations. Cached results are ignored, so the call always reaches the server."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "See exactly what a tool returned",
+                example: "tool raw github.get_issue {owner: \"wycats\", repo: \"mcp-repl\", number: 1}",
+                result: None,
+            },
+            Example {
+                description: "Check whether the server flagged the result as an error",
+                example: "tool raw fs.read_file {path: \"missing.txt\"} | get isError",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let name: Spanned<String> = call.req(engine_state, stack, 0)?;
+        let args: Option<Value> = call.opt(engine_state, stack, 1)?;
+        let json: Option<Spanned<String>> = call.get_flag(engine_state, stack, "json")?;
+
+        let piped = match input {
+            PipelineData::Empty => None,
+            other => match other.into_value(span)? {
+                Value::Nothing { .. } => None,
+                value => Some(value),
+            },
+        };
+        let params = collect_call_args(args, piped, json, span);
+
+        let manager = get_mcp_client_manager_sync();
+        let Some((_, registered)) = manager.find_tool(&name.item) else {
+            return Err(ShellError::GenericError {
+                error: format!("Unknown tool '{}'", name.item),
+                msg: "no registered tool has this name".into(),
+                span: Some(name.span),
+                help: Some(
+                    "Tool names have the form server.tool; run `tool list` to see them".into(),
+                ),
+                inner: Vec::new(),
+            });
+        };
+        let registered = registered.clone();
+        drop(manager);
+
+        let options = CallOptions {
+            settings: &registered.settings,
+            try_mode: false,
+            all_pages: None,
+            follow_links: false,
+            raw: true,
+            audience: Audience::All,
+            original: None,
+        };
+        run_tool_call(
+            engine_state,
+            stack,
+            &registered.client,
+            &registered.tool,
+            params,
+            options,
+            span,
+        )
+    }
+}
@@ -0,0 +1,265 @@
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, Flag, IntoPipelineData, PipelineData, PositionalArg, ShellError, Signature,
+    Span, Spanned, SyntaxShape, Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+    record,
+};
+
+use super::{
+    tool_mapper::{self, MappingRule, ParameterPlan, Placement},
+    utils::{add_output_flag, apply_output_format},
+};
+use crate::engine::get_mcp_client_manager_sync;
+
+/// Command to show the signature generated for a tool, and why
+#[derive(Clone)]
+pub struct ToolSignatureCommand;
+
+impl Command for ToolSignatureCommand {
+    fn name(&self) -> &'static str {
+        "tool signature"
+    }
+
+    fn signature(&self) -> Signature {
+        add_output_flag(
+            Signature::build("tool signature")
+                .category(Category::Custom("mcp".into()))
+                .required(
+                    "name",
+                    SyntaxShape::String,
+                    "The namespaced tool name (server.tool)",
+                )
+                .input_output_types(vec![(Type::Nothing, Type::Record(vec![].into()))]),
+        )
+    }
+
+    fn description(&self) -> &'static str {
+        "Show the signature generated for an MCP tool and the mapping rule behind each parameter"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "The signature is the one the tool's command was registered with. Each positional and flag comes with the rule that placed it, numbered as in MAPPING.md; flags every tool command has are marked as call flags. `overflow` lists the parameters given in the --rest record."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example {
+            description: "See why a parameter became a flag",
+            example: "tool signature github.create_issue | get named",
+            result: None,
+        }]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let name: Spanned<String> = call.req(engine_state, stack, 0)?;
+
+        let manager = get_mcp_client_manager_sync();
+        let Some((_, registered)) = manager.find_tool(&name.item) else {
+            return Err(ShellError::GenericError {
+                error: format!("Unknown tool '{}'", name.item),
+                msg: "no registered tool has this name".into(),
+                span: Some(name.span),
+                help: Some(
+                    "Tool names have the form server.tool; run `tool list` to see them".into(),
+                ),
+                inner: Vec::new(),
+            });
+        };
+        let plan = tool_mapper::plan_parameters(&registered.tool, registered.settings.max_flags);
+        drop(manager);
+
+        let command_name = format!("tool {}", name.item);
+        let signature = engine_state
+            .find_decl(command_name.as_bytes(), &[])
+            .map(|decl_id| engine_state.get_decl(decl_id).signature())
+            .ok_or_else(|| ShellError::GenericError {
+                error: format!("'{command_name}' isn't registered"),
+                msg: "the tool has no command".into(),
+                span: Some(name.span),
+                help: Some("Run `mcp restart` to register its command again".into()),
+                inner: Vec::new(),
+            })?;
+
+        apply_output_format(
+            engine_state,
+            stack,
+            call,
+            signature_to_value(&signature, &plan, span).into_pipeline_data(),
+        )
+    }
+}
+
+/// The signature's parameters, each with the rule that placed it
+fn signature_to_value(signature: &Signature, plan: &ParameterPlan, span: Span) -> Value {
+    let rule = |name: &str, positional: bool| {
+        plan.parameters
+            .iter()
+            .find(|param| {
+                param.name == name && (param.placement == Placement::Positional) == positional
+            })
+            .map_or("call flag", |param| param.rule.describe())
+    };
+    let required = |name: &str| {
+        plan.parameters
+            .iter()
+            .any(|param| param.name == name && param.required)
+    };
+
+    let positional_row = |arg: &PositionalArg| {
+        Value::record(
+            record! {
+                "name" => Value::string(&arg.name, span),
+                "shape" => Value::string(arg.shape.to_string(), span),
+                "required" => Value::bool(required(&arg.name), span),
+                "rule" => Value::string(rule(&arg.name, true), span),
+            },
+            span,
+        )
+    };
+    let named_row = |flag: &Flag| {
+        let rule = if flag.long == tool_mapper::REST_FLAG && !plan.overflow.is_empty() {
+            MappingRule::Overflow.describe()
+        } else {
+            rule(&flag.long, false)
+        };
+        Value::record(
+            record! {
+                "name" => Value::string(&flag.long, span),
+                "shape" => flag.arg.as_ref().map_or_else(
+                    || Value::nothing(span),
+                    |shape| Value::string(shape.to_string(), span),
+                ),
+                "switch" => Value::bool(flag.arg.is_none(), span),
+                "short" => flag.short.map_or_else(
+                    || Value::nothing(span),
+                    |short| Value::string(short.to_string(), span),
+                ),
+                "rule" => Value::string(rule, span),
+            },
+            span,
+        )
+    };
+
+    let positional = signature
+        .required_positional
+        .iter()
+        .chain(&signature.optional_positional)
+        .map(positional_row)
+        .collect();
+    let named = signature
+        .named
+        .iter()
+        .filter(|flag| flag.long != "help")
+        .map(named_row)
+        .collect();
+    let rest = signature
+        .rest_positional
+        .as_ref()
+        .map_or_else(|| Value::nothing(span), positional_row);
+
+    Value::record(
+        record! {
+            "name" => Value::string(&signature.name, span),
+            "positional" => Value::list(positional, span),
+            "named" => Value::list(named, span),
+            "rest" => rest,
+            "overflow" => Value::list(
+                plan.overflow
+                    .iter()
+                    .map(|name| Value::string(name, span))
+                    .collect(),
+                span,
+            ),
+        },
+        span,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rmcp::model::Tool;
+    use serde_json::{Value as JsonValue, json};
+
+    use super::*;
+
+    #[test]
+    fn test_signature_to_value() {
+        let JsonValue::Object(schema) = json!({
+            "type": "object",
+            "properties": {
+                "repo": {"type": "string"},
+                "draft": {"type": "boolean"},
+                "labels": {"type": "array"}
+            },
+            "required": ["repo"]
+        }) else {
+            unreachable!();
+        };
+        let tool = Tool::new("create_issue", "Create an issue", Arc::new(schema));
+        let plan = tool_mapper::plan_parameters(&tool, 1);
+        let signature = tool_mapper::add_call_flags(
+            tool_mapper::map_tool_to_signature(&tool, "tool", 1),
+            &tool,
+        );
+        let span = Span::test_data();
+
+        let value = signature_to_value(&signature, &plan, span);
+        let row = |column: &str, name: &str| {
+            value
+                .get_data_by_key(column)
+                .and_then(|rows| rows.into_list().ok())
+                .and_then(|rows| {
+                    rows.into_iter().find(|row| {
+                        row.get_data_by_key("name").is_some_and(|row_name| {
+                            row_name.as_str().is_ok_and(|row_name| row_name == name)
+                        })
+                    })
+                })
+                .unwrap_or_else(|| panic!("no {column} row for {name}"))
+        };
+        let rule = |column: &str, name: &str| {
+            row(column, name)
+                .get_data_by_key("rule")
+                .and_then(|rule| rule.coerce_into_string().ok())
+        };
+
+        assert_eq!(
+            rule("positional", "repo").as_deref(),
+            Some("rule 3: one required param, the rest optional")
+        );
+        assert_eq!(
+            row("positional", "repo").get_data_by_key("required"),
+            Some(Value::test_bool(true))
+        );
+        assert_eq!(
+            rule("named", "repo").as_deref(),
+            Some("rule 6: positionals can also be flags")
+        );
+        assert_eq!(
+            rule("named", "draft").as_deref(),
+            Some("rule 4: optional boolean")
+        );
+        assert_eq!(
+            row("named", "draft").get_data_by_key("switch"),
+            Some(Value::test_bool(true))
+        );
+        assert_eq!(
+            rule("named", "rest").as_deref(),
+            Some("rule 7: beyond max_flags")
+        );
+        assert_eq!(rule("named", "try").as_deref(), Some("call flag"));
+        assert_eq!(
+            value.get_data_by_key("overflow"),
+            Some(Value::test_list(vec![Value::test_string("labels")]))
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, PipelineData, ShellError, Signature, Spanned, SyntaxShape, Type,
+    engine::{Call, Command, EngineState, Stack},
+};
+use serde_json::Value as JsonValue;
+
+use super::{
+    mcp_tools::{CallOptions, run_tool_call},
+    tool_mapper::{self, tool_parameters},
+    tool_prompt,
+    utils::{add_output_flag, apply_output_format},
+};
+use crate::{
+    engine::get_mcp_client_manager_sync,
+    util::{annotations::Audience, prompt},
+};
+
+/// Command to build a tool call by answering a prompt for each parameter
+#[derive(Clone)]
+pub struct ToolTryCommand;
+
+impl Command for ToolTryCommand {
+    fn name(&self) -> &'static str {
+        "tool try"
+    }
+
+    fn signature(&self) -> Signature {
+        add_output_flag(
+            Signature::build("tool try")
+                .category(Category::Custom("mcp".into()))
+                .required(
+                    "name",
+                    SyntaxShape::String,
+                    "The namespaced tool name (server.tool)",
+                )
+                .input_output_types(vec![(Type::Nothing, Type::Any)]),
+        )
+    }
+
+    fn description(&self) -> &'static str {
+        "Build a call to an MCP tool interactively, one parameter at a time"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "Each parameter is asked for in schema order, showing its type, description, default and choices. Leave an optional parameter empty to skip it; an answer that doesn't fit the type is reported and asked for again. Before running, the command line and JSON arguments are shown for confirmation, and the command line is printed again afterwards so the call can be repeated."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example {
+            description: "Try out a tool without knowing its parameters",
+            example: "tool try github.create_issue",
+            result: None,
+        }]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let name: Spanned<String> = call.req(engine_state, stack, 0)?;
+
+        if !engine_state.is_interactive {
+            return Err(ShellError::GenericError {
+                error: "`tool try` needs a terminal".into(),
+                msg: "cannot prompt for parameters in a non-interactive session".into(),
+                span: Some(span),
+                help: Some(format!(
+                    "Call `tool {}` or `tool call` with the arguments instead",
+                    name.item
+                )),
+                inner: Vec::new(),
+            });
+        }
+
+        let manager = get_mcp_client_manager_sync();
+        let Some((_, registered)) = manager.find_tool(&name.item) else {
+            return Err(ShellError::GenericError {
+                error: format!("Unknown tool '{}'", name.item),
+                msg: "no registered tool has this name".into(),
+                span: Some(name.span),
+                help: Some(
+                    "Tool names have the form server.tool; run `tool list` to see them".into(),
+                ),
+                inner: Vec::new(),
+            });
+        };
+        let registered = registered.clone();
+        drop(manager);
+
+        let show = |text: String| {
+            prompt::show(&text).map_err(|err| ShellError::GenericError {
+                error: "Failed to write to the terminal".into(),
+                msg: err.to_string(),
+                span: Some(span),
+                help: None,
+                inner: Vec::new(),
+            })
+        };
+
+        let tool = &registered.tool;
+        if let Some(description) = tool.description.as_deref() {
+            show(format!("{description}\n"))?;
+        }
+
+        let mut params = serde_json::Map::new();
+        for param in tool_parameters(tool) {
+            let answer = tool_prompt::ask(&param).map_err(|msg| ShellError::GenericError {
+                error: format!("Missing required parameter '{}'", param.name),
+                msg,
+                span: Some(span),
+                help: None,
+                inner: Vec::new(),
+            })?;
+            if let Some(value) = answer {
+                params.insert(param.name, value);
+            }
+        }
+
+        let signature =
+            tool_mapper::map_tool_to_signature(tool, "tool", registered.settings.max_flags);
+        let command_line =
+            tool_prompt::command_line(&format!("tool {}", name.item), &signature, &params);
+        let payload =
+            serde_json::to_string_pretty(&JsonValue::Object(params.clone())).unwrap_or_default();
+
+        show(format!("\n{command_line}\n{payload}"))?;
+
+        let confirmed =
+            prompt::confirm("Run this call?").map_err(|err| ShellError::GenericError {
+                error: "Failed to read confirmation".into(),
+                msg: err.to_string(),
+                span: Some(span),
+                help: None,
+                inner: Vec::new(),
+            })?;
+        if !confirmed {
+            crate::info!("Not run");
+            return Ok(PipelineData::Empty);
+        }
+
+        let options = CallOptions {
+            settings: &registered.settings,
+            try_mode: false,
+            all_pages: None,
+            follow_links: false,
+            raw: false,
+            audience: Audience::All,
+            original: None,
+        };
+        let result = run_tool_call(
+            engine_state,
+            stack,
+            &registered.client,
+            tool,
+            Ok(params),
+            options,
+            span,
+        );
+
+        crate::info!("To run this again: {}", command_line);
+
+        apply_output_format(engine_state, stack, call, result?)
+    }
+}
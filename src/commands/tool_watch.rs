@@ -0,0 +1,332 @@
+use std::time::{Duration, Instant};
+
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, Example, ListStream, PipelineData, Record, ShellError, Signals, Signature, Span,
+    Spanned, SyntaxShape, Type, Value,
+    engine::{Call, Command, EngineState, Stack},
+};
+
+use super::{
+    mcp_tools::{PreparedCall, format_tool_contents, prepare_tool_call},
+    tool_call::record_to_params,
+    utils::{tool_source, with_source},
+};
+use crate::engine::get_mcp_client_manager_sync;
+
+/// How often the sleep between polls checks for Ctrl-C
+const INTERRUPT_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Command to call a tool repeatedly at an interval
+#[derive(Clone)]
+pub struct ToolWatchCommand;
+
+impl Command for ToolWatchCommand {
+    fn name(&self) -> &'static str {
+        "tool watch"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("tool watch")
+            .category(Category::Custom("mcp".into()))
+            .required(
+                "name",
+                SyntaxShape::String,
+                "The namespaced tool name (server.tool)",
+            )
+            .optional(
+                "args",
+                SyntaxShape::Record(vec![]),
+                "A record of arguments to pass to the tool on every call",
+            )
+            .named(
+                "interval",
+                SyntaxShape::Duration,
+                "How long to wait between calls (default 5sec)",
+                Some('i'),
+            )
+            .named(
+                "times",
+                SyntaxShape::Int,
+                "Stop after this many calls (default: until interrupted)",
+                Some('n'),
+            )
+            .switch(
+                "changes-only",
+                "Only emit an observation when the result or error differs from the previous one",
+                Some('c'),
+            )
+            .switch(
+                "collect",
+                "Return the full table of observations at the end instead of streaming them",
+                None,
+            )
+            .input_output_types(vec![(Type::Nothing, Type::Table(vec![].into()))])
+    }
+
+    fn description(&self) -> &'static str {
+        "Call an MCP tool repeatedly at an interval"
+    }
+
+    fn extra_description(&self) -> &'static str {
+        "Each observation is a row with the time of the call, the call number, and either the result or the error. Press Ctrl-C to stop watching; with --collect, the observations made so far are returned."
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Poll a CI status tool every 10 seconds, 30 times",
+                example: "tool watch ci.get_status --interval 10sec --times 30",
+                result: None,
+            },
+            Example {
+                description: "Collect only the changes in queue depth into a table",
+                example: "tool watch queue.depth {queue: \"jobs\"} --changes-only --collect",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let name: Spanned<String> = call.req(engine_state, stack, 0)?;
+        let args: Option<Value> = call.opt(engine_state, stack, 1)?;
+        let interval = match call.get_flag::<Value>(engine_state, stack, "interval")? {
+            Some(value) => watch_interval(value.as_duration()?, value.span(), span)?,
+            None => Duration::from_secs(5),
+        };
+        let times: Option<Spanned<i64>> = call.get_flag(engine_state, stack, "times")?;
+        let changes_only = call.has_flag(engine_state, stack, "changes-only")?;
+        let collect = call.has_flag(engine_state, stack, "collect")?;
+
+        let times = match times {
+            Some(times) if times.item < 1 => {
+                return Err(ShellError::IncorrectValue {
+                    msg: "--times must be at least 1".into(),
+                    val_span: times.span,
+                    call_span: span,
+                });
+            }
+            Some(times) => Some(u64::try_from(times.item).unwrap_or(u64::MAX)),
+            None => None,
+        };
+
+        let params = match &args {
+            Some(args) => record_to_params(args, span)?,
+            None => serde_json::Map::new(),
+        };
+
+        let manager = get_mcp_client_manager_sync();
+        let Some((_, registered)) = manager.find_tool(&name.item) else {
+            return Err(ShellError::GenericError {
+                error: format!("Unknown tool '{}'", name.item),
+                msg: "no registered tool has this name".into(),
+                span: Some(name.span),
+                help: Some(
+                    "Tool names have the form server.tool; run `tool list` to see them".into(),
+                ),
+                inner: Vec::new(),
+            });
+        };
+        let registered = registered.clone();
+        drop(manager);
+
+        // Checked and confirmed once, before the first call
+        let prepared = prepare_tool_call(
+            engine_state,
+            &registered.client,
+            &registered.tool,
+            params,
+            &registered.settings,
+            span,
+        )
+        .map_err(|(_, err)| err)?;
+
+        let source = tool_source(&registered.client.name, &registered.tool.name);
+        let watcher = Watcher {
+            prepared,
+            interval,
+            remaining: times,
+            changes_only,
+            count: 0,
+            last: None,
+            signals: engine_state.signals().clone(),
+            span,
+        };
+
+        let data = if collect {
+            // Interrupting stops the watcher, which returns what was collected so far
+            let observations: Vec<Value> = watcher.collect();
+            PipelineData::Value(Value::list(observations, span), None)
+        } else {
+            PipelineData::ListStream(
+                ListStream::new(watcher, span, engine_state.signals().clone()),
+                None,
+            )
+        };
+
+        Ok(with_source(data, &source, None))
+    }
+}
+
+/// An iterator that calls a tool once per interval and yields observations
+struct Watcher {
+    prepared: PreparedCall,
+    interval: Duration,
+    remaining: Option<u64>,
+    changes_only: bool,
+    count: u64,
+    /// The last result and error, which --changes-only compares against
+    last: Option<(Value, Value)>,
+    signals: Signals,
+    span: Span,
+}
+
+impl Watcher {
+    /// Sleep for the interval, returning false if interrupted while waiting
+    fn wait(&self) -> bool {
+        let deadline = Instant::now() + self.interval;
+
+        while Instant::now() < deadline {
+            if self.signals.interrupted() {
+                return false;
+            }
+            std::thread::sleep(INTERRUPT_CHECK_INTERVAL.min(deadline - Instant::now()));
+        }
+
+        !self.signals.interrupted()
+    }
+
+    /// Call the tool once, returning its result and error
+    ///
+    /// A result the server flagged with `isError` is an error.
+    fn observe(&self) -> (Value, Value) {
+        let result = self
+            .prepared
+            .call(&self.signals)
+            .map_err(|err| err.message)
+            .and_then(|contents| {
+                format_tool_contents(contents, self.prepared.settings(), self.span, &self.signals)
+                    .and_then(|data| data.into_value(self.span))
+                    .map_err(|err| err.to_string())
+            });
+
+        match result {
+            Ok(value) => (value, Value::nothing(self.span)),
+            Err(error) => (Value::nothing(self.span), Value::string(error, self.span)),
+        }
+    }
+}
+
+impl Iterator for Watcher {
+    type Item = Value;
+
+    fn next(&mut self) -> Option<Value> {
+        loop {
+            if self.remaining == Some(0) || self.signals.interrupted() {
+                return None;
+            }
+
+            if self.count > 0 && !self.wait() {
+                return None;
+            }
+
+            self.count += 1;
+            if let Some(remaining) = &mut self.remaining {
+                *remaining -= 1;
+            }
+
+            let time = chrono::Local::now().fixed_offset();
+            let (result, error) = self.observe();
+
+            if self.changes_only && !is_change(self.last.as_ref(), &result, &error) {
+                continue;
+            }
+            self.last = Some((result.clone(), error.clone()));
+
+            let mut record = Record::new();
+            record.push("time", Value::date(time, self.span));
+            record.push(
+                "call",
+                Value::int(i64::try_from(self.count).unwrap_or(i64::MAX), self.span),
+            );
+            record.push("result", result);
+            record.push("error", error);
+
+            return Some(Value::record(record, self.span));
+        }
+    }
+}
+
+/// The time between calls, from an `--interval` of `nanos` nanoseconds
+///
+/// A zero or negative interval would call the server in a tight loop, so it
+/// is rejected.
+fn watch_interval(nanos: i64, val_span: Span, call_span: Span) -> Result<Duration, ShellError> {
+    match u64::try_from(nanos) {
+        Ok(nanos) if nanos > 0 => Ok(Duration::from_nanos(nanos)),
+        _ => Err(ShellError::IncorrectValue {
+            msg: "--interval must be greater than zero".into(),
+            val_span,
+            call_span,
+        }),
+    }
+}
+
+/// Whether an observation differs from the `last` one in its result or its
+/// error
+fn is_change(last: Option<&(Value, Value)>, result: &Value, error: &Value) -> bool {
+    last.is_none_or(|(last_result, last_error)| last_result != result || last_error != error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch_interval() {
+        let span = Span::test_data();
+
+        assert_eq!(
+            watch_interval(1_500_000_000, span, span).unwrap(),
+            Duration::from_millis(1500)
+        );
+        for nanos in [0, -1, -5_000_000_000] {
+            assert!(matches!(
+                watch_interval(nanos, span, span),
+                Err(ShellError::IncorrectValue { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_is_change() {
+        let nothing = Value::test_nothing();
+        let ok = (Value::test_int(1), nothing.clone());
+        let failed = (nothing.clone(), Value::test_string("timed out"));
+
+        assert!(is_change(None, &ok.0, &ok.1));
+        assert!(!is_change(Some(&ok), &Value::test_int(1), &nothing));
+        assert!(is_change(Some(&ok), &Value::test_int(2), &nothing));
+
+        // A different error is a change even though the result is nothing
+        // both times
+        assert!(!is_change(
+            Some(&failed),
+            &nothing,
+            &Value::test_string("timed out")
+        ));
+        assert!(is_change(
+            Some(&failed),
+            &nothing,
+            &Value::test_string("refused")
+        ));
+        assert!(is_change(Some(&ok), &failed.0, &failed.1));
+    }
+}
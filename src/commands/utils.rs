@@ -1,16 +1,28 @@
-use std::ops::Deref;
+use std::{ops::Deref, path::PathBuf, sync::OnceLock};
 
-use nu_protocol::{Record, Span, Value, ast::PathMember};
+use nu_engine::CallExt;
+use nu_protocol::{
+    Category, DataSource, PipelineData, PipelineMetadata, Record, ShellError, Signature, Span,
+    Spanned, SyntaxShape, Type, Value,
+    ast::PathMember,
+    engine::{Call, Command, EngineState, Stack},
+};
 
 use crate::{
+    config::JsonNumbers,
     mcp::McpClient,
-    util::error::{McpResult, generic_error},
+    util::{
+        cache::ToolResultCache,
+        error::{McpResult, generic_error},
+        json_numbers,
+    },
 };
 
 #[derive(Clone, Debug)]
 pub struct ReplClient {
     pub(crate) name: String,
     pub(crate) client: McpClient,
+    pub(crate) cache: ToolResultCache,
     pub(crate) _debug: bool,
 }
 
@@ -24,11 +36,40 @@ impl Deref for ReplClient {
 
 /// Convert a JSON value to a Nushell value.
 ///
+/// Numbers that don't fit an int or float are converted as the
+/// `json_numbers` setting says, see [`json_numbers`].
 ///
 /// # Errors
 ///
 /// This function will return an error if the JSON value cannot be converted to a Nushell value.
 pub fn convert_json_value_to_nu_value(v: &serde_json::Value, span: Span) -> McpResult<Value> {
+    convert_json_value_with_policy(v, json_numbers::policy(), span)
+}
+
+/// Convert a JSON value to a Nushell value under a given `json_numbers` policy
+pub fn convert_json_value_with_policy(
+    v: &serde_json::Value,
+    policy: JsonNumbers,
+    span: Span,
+) -> McpResult<Value> {
+    convert_json_value_at(v, &mut Vec::new(), policy, span)
+}
+
+/// `path` is the cell path to `v`, for errors about its numbers
+fn convert_json_value_at(
+    v: &serde_json::Value,
+    path: &mut Vec<String>,
+    policy: JsonNumbers,
+    span: Span,
+) -> McpResult<Value> {
+    let at = |path: &[String]| {
+        if path.is_empty() {
+            "the top level".to_string()
+        } else {
+            path.join(".")
+        }
+    };
+
     let result = match v {
         serde_json::Value::Null => Value::Nothing {
             internal_span: span,
@@ -37,36 +78,24 @@ pub fn convert_json_value_to_nu_value(v: &serde_json::Value, span: Span) -> McpR
             val: *b,
             internal_span: span,
         },
-        serde_json::Value::Number(n) => {
-            if let Some(val) = n.as_i64() {
-                Value::Int {
-                    val,
-                    internal_span: span,
-                }
-            } else if let Some(val) = n.as_f64() {
-                Value::Float {
-                    val,
-                    internal_span: span,
-                }
-            } else {
-                return Err(generic_error(
-                    format!("Unexpected numeric value, cannot convert {n} into i64 or f64"),
-                    None,
-                    None,
-                ));
-            }
-        }
-        serde_json::Value::String(val) => Value::String {
-            val: val.clone(),
-            internal_span: span,
+        serde_json::Value::Number(n) => json_numbers::number_to_value(n, &at(path), policy, span)?,
+        serde_json::Value::String(val) => match json_numbers::marked_number(val) {
+            Some(literal) => json_numbers::imprecise_to_value(literal, &at(path), policy, span)?,
+            None => Value::String {
+                val: val.clone(),
+                internal_span: span,
+            },
         },
         serde_json::Value::Array(a) => {
-            let t = a
-                .iter()
-                .map(|x| convert_json_value_to_nu_value(x, span))
-                .collect::<McpResult<Vec<Value>>>()?;
+            let mut vals = Vec::with_capacity(a.len());
+            for (index, x) in a.iter().enumerate() {
+                path.push(index.to_string());
+                let val = convert_json_value_at(x, path, policy, span);
+                path.pop();
+                vals.push(val?);
+            }
             Value::List {
-                vals: t,
+                vals,
                 internal_span: span,
             }
         }
@@ -76,7 +105,10 @@ pub fn convert_json_value_to_nu_value(v: &serde_json::Value, span: Span) -> McpR
 
             for (k, v) in o {
                 cols.push(k.clone());
-                vals.push(convert_json_value_to_nu_value(v, span)?);
+                path.push(k.clone());
+                let val = convert_json_value_at(v, path, policy, span);
+                path.pop();
+                vals.push(val?);
             }
 
             let record = Record::from_raw_cols_vals(cols, vals, span, span).unwrap();
@@ -159,3 +191,176 @@ fn json_list(input: &[Value], span: Span) -> McpResult<Vec<serde_json::Value>> {
 
     Ok(out)
 }
+
+/// The name of the flag that selects how a command's result is rendered
+pub const OUTPUT_FLAG: &str = "output";
+
+/// The values accepted by `--output`
+const OUTPUT_FORMATS: &[&str] = &["json", "nuon", "table"];
+
+/// The completer offering the `--output` values, set when it is registered
+static OUTPUT_COMPLETER: OnceLock<nu_protocol::DeclId> = OnceLock::new();
+
+/// How a command's result should be handed back
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// A JSON string
+    Json,
+    /// A NUON string
+    Nuon,
+    /// Structured data, rendered as a table by the REPL
+    Table,
+}
+
+impl OutputFormat {
+    fn parse(format: &Spanned<String>) -> Result<Self, ShellError> {
+        match format.item.as_str() {
+            "json" => Ok(Self::Json),
+            "nuon" => Ok(Self::Nuon),
+            "table" => Ok(Self::Table),
+            other => Err(ShellError::InvalidValue {
+                valid: OUTPUT_FORMATS.join(", "),
+                actual: other.to_string(),
+                span: format.span,
+            }),
+        }
+    }
+}
+
+/// Add the `--output` flag to a command signature
+#[must_use]
+pub fn add_output_flag(signature: Signature) -> Signature {
+    let shape = OUTPUT_COMPLETER
+        .get()
+        .map_or(SyntaxShape::String, |decl_id| {
+            SyntaxShape::CompleterWrapper(Box::new(SyntaxShape::String), *decl_id)
+        });
+
+    signature.named(
+        OUTPUT_FLAG,
+        shape,
+        "Output format: json, nuon or table (default)",
+        None,
+    )
+}
+
+/// Post-process a command's result according to its `--output` flag
+///
+/// Every command with `--output` goes through this so JSON and NUON
+/// serialization behave the same everywhere.
+pub fn apply_output_format(
+    engine_state: &EngineState,
+    stack: &mut Stack,
+    call: &Call,
+    data: PipelineData,
+) -> Result<PipelineData, ShellError> {
+    let span = call.head;
+    let format: Option<Spanned<String>> = call.get_flag(engine_state, stack, OUTPUT_FLAG)?;
+    let metadata = data.metadata();
+
+    let format = match format {
+        Some(format) => OutputFormat::parse(&format)?,
+        None => OutputFormat::Table,
+    };
+
+    let text = match format {
+        OutputFormat::Table => return Ok(data),
+        OutputFormat::Json => {
+            let json = convert_nu_value_to_json_value(&data.into_value(span)?, span)?;
+            serde_json::to_string_pretty(&json).map_err(|err| ShellError::GenericError {
+                error: "Failed to serialize output as JSON".into(),
+                msg: err.to_string(),
+                span: Some(span),
+                help: None,
+                inner: Vec::new(),
+            })?
+        }
+        OutputFormat::Nuon => nuon::to_nuon(
+            engine_state,
+            &data.into_value(span)?,
+            nuon::ToStyle::Raw,
+            Some(span),
+            false,
+        )?,
+    };
+
+    Ok(PipelineData::Value(Value::string(text, span), metadata))
+}
+
+/// The source recorded for the results of a tool, e.g. `mcp://github/get_issue`
+#[must_use]
+pub fn tool_source(server: &str, tool: &str) -> String {
+    format!("mcp://{server}/{tool}")
+}
+
+/// The message of an error, with its label when it has one
+///
+/// A `GenericError` displays as its headline alone, which drops the label
+/// that usually says what went wrong.
+#[must_use]
+pub(crate) fn error_text(err: &ShellError) -> String {
+    match err {
+        ShellError::GenericError { error, msg, .. } => format!("{error}: {msg}"),
+        err => err.to_string(),
+    }
+}
+
+/// Record where a result came from, so `metadata` can show it
+///
+/// Nushell has no data source for URLs, so the origin is stored as a file
+/// path, which `metadata` reports as the `source` column. Without a
+/// `content_type`, the one already on `data` is kept.
+#[must_use]
+pub fn with_source(data: PipelineData, source: &str, content_type: Option<String>) -> PipelineData {
+    let content_type =
+        content_type.or_else(|| data.metadata().and_then(|metadata| metadata.content_type));
+    data.set_metadata(Some(PipelineMetadata {
+        data_source: DataSource::FilePath(PathBuf::from(source)),
+        content_type,
+    }))
+}
+
+/// Register the completer for `--output` values
+///
+/// This has to happen before any command using [`add_output_flag`] is
+/// parsed, so it is the first thing `register_all` does.
+pub fn register_output_completer(working_set: &mut nu_protocol::engine::StateWorkingSet) {
+    let decl_id = working_set.add_decl(Box::new(OutputFormatCompleter));
+    let _ = OUTPUT_COMPLETER.set(decl_id);
+}
+
+/// Completes the values of `--output`
+#[derive(Clone)]
+struct OutputFormatCompleter;
+
+impl Command for OutputFormatCompleter {
+    fn name(&self) -> &'static str {
+        "nu-complete mcp output"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("nu-complete mcp output")
+            .category(Category::Custom("mcp".into()))
+            .input_output_types(vec![(Type::Nothing, Type::List(Box::new(Type::String)))])
+    }
+
+    fn description(&self) -> &'static str {
+        "Complete the values of the --output flag"
+    }
+
+    fn run(
+        &self,
+        _engine_state: &EngineState,
+        _stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let formats = OUTPUT_FORMATS
+            .iter()
+            .map(|format| Value::string(*format, span))
+            .collect();
+
+        Ok(PipelineData::Value(Value::list(formats, span), None))
+    }
+}
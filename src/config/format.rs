@@ -1,61 +1,952 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
-use anyhow::Result;
+use anyhow::{Context, Result, anyhow};
 use config::{Config, Environment, File, FileFormat, FileSourceFile, FileSourceString};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
-use super::parse_env;
-use crate::{CliArgs, commands::utils::ReplClient, mcp::McpClient};
+use super::{
+    ConfigDuration, DEFAULT_CONNECT_TIMEOUT, EffectiveToolSettings, ToolSettings, TrustPolicy,
+    parse_env,
+};
+use crate::{
+    CliArgs,
+    commands::utils::ReplClient,
+    mcp::{ConnectOptions, McpClient, program_path},
+    util::{
+        cache::ToolResultCache,
+        events::{DEFAULT_EVENT_BUFFER, EventLog},
+        memory::{Limit, Store},
+        program::check_program,
+        reconnect::DEFAULT_RECONNECT_QUEUE,
+        sampling::{DEFAULT_SAMPLING_CONFIRM_TOKENS, SamplingOptions},
+        snapshot::SchemaSnapshot,
+    },
+};
 
 // Define an enum that encapsulates the different possible config sources
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum ConfigSource {
     FilePath(File<FileSourceFile, FileFormat>),
     #[allow(dead_code)]
     FileContent(File<FileSourceString, FileFormat>),
 }
 
-impl McpConnectionType {
-    pub async fn to_client(&self, name: &str) -> Result<Arc<ReplClient>> {
-        let client = McpClient::connect(self.clone(), false).await?;
-        Ok(Arc::new(ReplClient {
+impl McpServerConfig {
+    /// What the server may do, given its `trusted` setting
+    #[must_use]
+    pub const fn trust_policy(&self) -> TrustPolicy {
+        TrustPolicy::new(self.trusted)
+    }
+
+    /// The options used when connecting to this server
+    #[must_use]
+    pub fn connect_options(&self) -> ConnectOptions {
+        ConnectOptions {
+            protocol_version: self.protocol_version.clone(),
+            inherit_env: self.trust_policy().inherit_env(self.inherit_env.as_ref()),
+            trust: self.trust_policy(),
+            initialize_options: self.initialize_options.clone(),
+            reconnect_queue: if self.queue_while_reconnecting {
+                self.reconnect_queue_limit
+                    .unwrap_or(DEFAULT_RECONNECT_QUEUE)
+            } else {
+                0
+            },
+            path_check: !self.skip_path_check,
+            sampling: SamplingOptions {
+                policy: self.sampling,
+                confirm_tokens: self
+                    .sampling_confirm_tokens
+                    .unwrap_or(DEFAULT_SAMPLING_CONFIRM_TOKENS),
+            },
+            ..ConnectOptions::default()
+        }
+    }
+
+    /// Connect to the server, recording its notifications in `events`
+    ///
+    /// A command server starts in `cwd`, and a relative `command` is
+    /// resolved against it.
+    pub async fn to_client(
+        &self,
+        name: &str,
+        events: &EventLog,
+        connect_timeout: Duration,
+        cwd: &Path,
+    ) -> Result<Arc<ReplClient>> {
+        let options = self.client_options(name, events, connect_timeout, cwd);
+        let client = McpClient::connect(self.connection.clone(), &options, false).await?;
+        Ok(Self::repl_client(name, client))
+    }
+
+    /// Restart the server behind `client` with this configuration
+    ///
+    /// Commands registered with the old client keep working, since they share
+    /// its connection with the returned client.
+    pub async fn restart_client(
+        &self,
+        client: &ReplClient,
+        events: &EventLog,
+        connect_timeout: Duration,
+        cwd: &Path,
+    ) -> Result<Arc<ReplClient>> {
+        let options = self.client_options(&client.name, events, connect_timeout, cwd);
+        let restarted = client
+            .client
+            .restart(self.connection.clone(), &options)
+            .await?;
+        // Keep cached results; `mcp restart` drops the ones for tools whose
+        // schema changed
+        Ok(Self::repl_client_with_cache(
+            &client.name,
+            restarted,
+            client.cache.clone(),
+        ))
+    }
+
+    fn client_options(
+        &self,
+        name: &str,
+        events: &EventLog,
+        connect_timeout: Duration,
+        cwd: &Path,
+    ) -> ConnectOptions {
+        ConnectOptions {
+            server_name: name.to_string(),
+            events: events.clone(),
+            connect_timeout,
+            cwd: Some(cwd.to_path_buf()),
+            ..self.connect_options()
+        }
+    }
+
+    /// Build a client from the server's schema snapshot, without connecting
+    ///
+    /// Returns `None` when the server has never been connected, so there is
+    /// no snapshot to load.
+    pub fn to_offline_client(name: &str) -> Result<Option<Arc<ReplClient>>> {
+        Ok(SchemaSnapshot::load(name)?
+            .map(|snapshot| Self::repl_client(name, McpClient::offline(name, snapshot, false))))
+    }
+
+    fn repl_client(name: &str, client: McpClient) -> Arc<ReplClient> {
+        Self::repl_client_with_cache(name, client, ToolResultCache::default())
+    }
+
+    fn repl_client_with_cache(
+        name: &str,
+        client: McpClient,
+        cache: ToolResultCache,
+    ) -> Arc<ReplClient> {
+        Arc::new(ReplClient {
             name: name.to_string(),
             client,
+            cache,
             _debug: false,
-        }))
+        })
     }
 }
 
 /// Type of MCP connection to establish
+///
+/// `Command` comes first: every field of `Sse` has a default, so it matches
+/// any server without a `command`.
 #[derive(Clone, Debug, Deserialize, Serialize, clap::Parser)]
 #[serde(untagged)]
 pub enum McpConnectionType {
-    /// SSE-based MCP server (HTTP Server-Sent Events)
-    Sse { url: String },
     /// Command-based MCP server (launches a subprocess)
     Command {
         command: String,
         #[arg(value_parser = parse_env(), long, action = clap::ArgAction::Append)]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         env: Option<IndexMap<String, String>>,
+        /// How long to wait for the process to finish the `initialize` handshake
+        #[arg(skip)]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        connect_timeout: Option<String>,
     },
+    /// SSE-based MCP server (HTTP Server-Sent Events)
+    Sse {
+        /// The full URL; empty when `path` is set instead
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        url: String,
+        /// A path joined to the top-level `base_url`, replaced by `url` when
+        /// the config is loaded
+        #[arg(skip)]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+        /// How long to wait for the connection and the `initialize` handshake
+        #[arg(skip)]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        connect_timeout: Option<String>,
+    },
+}
+
+impl McpConnectionType {
+    /// The `connect_timeout` set for this server, if any
+    ///
+    /// The value is kept as a string until it's used: a parse error inside
+    /// an untagged enum would otherwise be reported as the server matching
+    /// no connection type at all.
+    pub fn connect_timeout(&self) -> Result<Option<Duration>> {
+        let (Self::Sse {
+            connect_timeout, ..
+        }
+        | Self::Command {
+            connect_timeout, ..
+        }) = self;
+
+        connect_timeout
+            .as_deref()
+            .map(|raw| {
+                humantime::parse_duration(raw)
+                    .with_context(|| format!("invalid connect_timeout '{raw}'"))
+            })
+            .transpose()
+    }
+
+    /// What's wrong with an SSE server's `url` and `path`, if anything
+    ///
+    /// Exactly one of them has to be set, and `path` needs a `base_url` to
+    /// be joined to.
+    fn url_problem(&self, base_url: Option<&str>) -> Option<&'static str> {
+        let Self::Sse { url, path, .. } = self else {
+            return None;
+        };
+
+        match (url.is_empty(), path) {
+            (false, Some(_)) => Some("set either url or path, not both"),
+            (true, None) => Some("set url, or path together with a top-level base_url"),
+            (true, Some(_)) if base_url.is_none() => Some("path needs a top-level base_url"),
+            (true, Some(path)) if path.contains("://") => {
+                Some("path is joined to base_url, so it can't be a full URL; use url instead")
+            }
+            _ => None,
+        }
+    }
+
+    /// Replace an SSE server's `path` with the `url` it resolves to
+    ///
+    /// Servers with a problem are left as they are, for
+    /// [`McpReplConfig::validate`] to report.
+    fn resolve_path(&mut self, base_url: Option<&str>) {
+        if self.url_problem(base_url).is_some() {
+            return;
+        }
+        if let (Self::Sse { url, path, .. }, Some(base_url)) = (self, base_url) {
+            if let Some(path) = path.take() {
+                *url = join_url(base_url, &path);
+            }
+        }
+    }
+}
+
+/// Join `path` to `base`, with exactly one `/` between them
+///
+/// A query string on either one is kept, the base's first, so
+/// `https://host/api?key=1` and `/sse?v=2` give
+/// `https://host/api/sse?key=1&v=2`.
+fn join_url(base: &str, path: &str) -> String {
+    let split_query = |url: &str| {
+        url.split_once('?')
+            .map_or((url.to_string(), None), |(url, query)| {
+                (url.to_string(), Some(query.to_string()))
+            })
+    };
+    let (base, base_query) = split_query(base);
+    let (path, path_query) = split_query(path);
+
+    let mut url = format!(
+        "{}/{}",
+        base.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    );
+    let query: Vec<String> = [base_query, path_query]
+        .into_iter()
+        .flatten()
+        .filter(|query| !query.is_empty())
+        .collect();
+    if !query.is_empty() {
+        url.push('?');
+        url.push_str(&query.join("&"));
+    }
+    url
+}
+
+/// Configuration for a single MCP server
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct McpServerConfig {
+    /// How to connect to the server
+    #[serde(flatten)]
+    pub connection: McpConnectionType,
+    /// Tool call settings that apply to every tool on this server
+    #[serde(flatten)]
+    pub settings: ToolSettings,
+    /// Tool call settings for individual tools, keyed by tool name
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub tools: IndexMap<String, ToolSettings>,
+    /// Pin the MCP protocol version requested from the server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol_version: Option<String>,
+    /// Which of the REPL's environment variables a command server receives
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inherit_env: Option<InheritEnv>,
+    /// Lift the restrictions on untrusted servers; see [`TrustPolicy`]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub trusted: bool,
+    /// Fields merged over the parameters of the `initialize` request, such
+    /// as `capabilities.experimental`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initialize_options: Option<serde_json::Map<String, serde_json::Value>>,
+    /// Hold tool calls made while the server restarts, instead of failing them
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub queue_while_reconnecting: bool,
+    /// How many calls may wait for a restart; 16 if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reconnect_queue_limit: Option<usize>,
+    /// Start a command server without first checking that its program is on
+    /// `PATH`, for programs that only exist inside a wrapper's environment
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip_path_check: bool,
+    /// What to do with the server's sampling requests
+    #[serde(default, skip_serializing_if = "SamplingPolicy::is_refuse")]
+    pub sampling: SamplingPolicy,
+    /// Sampling requests estimated at more tokens than this need `yes` typed
+    /// to allow; 4096 if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sampling_confirm_tokens: Option<u64>,
+    /// Reconnect this often to pick up rotated credentials, as
+    /// `mcp auth refresh` does
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_refresh_interval: Option<ConfigDuration>,
+}
+
+/// How a trusted server's sampling requests are handled
+///
+/// Untrusted servers are refused whatever this says.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SamplingPolicy {
+    /// Refuse every request
+    #[default]
+    Refuse,
+    /// Show each request and ask before going on, unless the server was
+    /// allowed with `mcp sampling allow`
+    Prompt,
+}
+
+impl SamplingPolicy {
+    #[must_use]
+    pub const fn is_refuse(&self) -> bool {
+        matches!(self, Self::Refuse)
+    }
+}
+
+/// What happens to JSON numbers that don't fit a Nushell int or float
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum JsonNumbers {
+    /// Convert them to the nearest float
+    Lossy,
+    /// Fail, naming where the number is
+    Strict,
+    /// Keep their digits as a string
+    #[default]
+    StringFallback,
+}
+
+/// When URIs in the REPL's output are made clickable terminal hyperlinks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Hyperlinks {
+    /// When stdout is a terminal that looks like it supports them
+    #[default]
+    Auto,
+    /// Always, even when the output is piped
+    Always,
+    Never,
+}
+
+/// The variables a command server inherits when `inherit_env` isn't set
+pub const DEFAULT_INHERITED_ENV: &[&str] = &["PATH", "HOME", "LANG", "TMPDIR"];
+
+/// Which of the REPL's environment variables a command server receives
+///
+/// Written as `"all"`, `"none"` or a list of variable names. Entries in the
+/// server's `env` are always passed, whatever the policy.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "RawInheritEnv", into = "RawInheritEnv")]
+pub enum InheritEnv {
+    All,
+    None,
+    Only(Vec<String>),
+}
+
+impl Default for InheritEnv {
+    fn default() -> Self {
+        Self::Only(
+            DEFAULT_INHERITED_ENV
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        )
+    }
+}
+
+impl InheritEnv {
+    /// Whether the variable is passed to the server
+    #[must_use]
+    pub fn inherits(&self, name: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::None => false,
+            Self::Only(names) => names.iter().any(|allowed| allowed == name),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+enum RawInheritEnv {
+    Mode(String),
+    Only(Vec<String>),
+}
+
+impl TryFrom<RawInheritEnv> for InheritEnv {
+    type Error = String;
+
+    fn try_from(raw: RawInheritEnv) -> Result<Self, Self::Error> {
+        match raw {
+            RawInheritEnv::Mode(mode) if mode == "all" => Ok(Self::All),
+            RawInheritEnv::Mode(mode) if mode == "none" => Ok(Self::None),
+            RawInheritEnv::Mode(mode) => Err(format!(
+                "invalid inherit_env '{mode}': expected \"all\", \"none\" or a list of variable names"
+            )),
+            RawInheritEnv::Only(names) => Ok(Self::Only(names)),
+        }
+    }
+}
+
+impl From<InheritEnv> for RawInheritEnv {
+    fn from(inherit: InheritEnv) -> Self {
+        match inherit {
+            InheritEnv::All => Self::Mode("all".into()),
+            InheritEnv::None => Self::Mode("none".into()),
+            InheritEnv::Only(names) => Self::Only(names),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct McpReplConfig {
     /// List of configured MCP servers
     #[serde(default)]
-    pub servers: IndexMap<String, McpConnectionType>,
+    pub servers: IndexMap<String, McpServerConfig>,
+    /// Tool call settings that apply to every server
+    #[serde(default)]
+    pub defaults: ToolSettings,
+    /// How many server notifications `mcp events` keeps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_buffer: Option<usize>,
+    /// The connect timeout for servers that don't set their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout: Option<ConfigDuration>,
+    /// The URL that SSE servers' `path` is joined to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// Named sets of servers connected on top of `servers`
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub profiles: IndexMap<String, ProfileConfig>,
+    /// How long a `--commands` script may run before it is cancelled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_runtime: Option<ConfigDuration>,
+    /// Nushell commands run in order once the servers are connected, before
+    /// the prompt or a `--commands` script
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub startup_commands: Vec<String>,
+    /// Stop at a startup command that fails instead of reporting it and
+    /// going on
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub startup_strict: bool,
+    /// Whether the session has the `http` commands
+    ///
+    /// Startup commands and display hooks never have them, nor `source` and
+    /// `source-env`; external programs stay available either way.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enable_http_commands: Option<bool>,
+    /// The profile whose servers are connected at startup
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// What happens to JSON numbers that don't fit a Nushell int or float
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_numbers: Option<JsonNumbers>,
+    /// When URIs in the output are made terminal hyperlinks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hyperlinks: Option<Hyperlinks>,
+    /// Limits on the results, events and messages kept in memory
+    #[serde(default)]
+    pub memory: MemoryConfig,
+    /// Closures that change how results are shown
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    /// Tools implemented in Nushell instead of by a server
+    #[serde(default)]
+    pub tools: ToolsConfig,
+    /// Which layer each server's definition came from, keyed by its config
+    /// path (`servers.github` or `profiles.work.servers.jira`): a file's
+    /// path, `cli` or `env`
+    #[serde(skip)]
+    pub server_sources: IndexMap<String, String>,
 }
 
 impl Default for McpReplConfig {
     fn default() -> Self {
         Self {
             servers: IndexMap::new(),
+            defaults: ToolSettings::default(),
+            event_buffer: None,
+            connect_timeout: None,
+            base_url: None,
+            profiles: IndexMap::new(),
+            profile: None,
+            max_runtime: None,
+            startup_commands: Vec::new(),
+            startup_strict: false,
+            enable_http_commands: None,
+            json_numbers: None,
+            hyperlinks: None,
+            memory: MemoryConfig::default(),
+            hooks: HooksConfig::default(),
+            tools: ToolsConfig::default(),
+            server_sources: IndexMap::new(),
         }
     }
 }
 
+/// The `[memory]` table, with the limits of each store
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct MemoryConfig {
+    #[serde(default)]
+    pub results: StoreLimitConfig,
+    #[serde(default)]
+    pub events: StoreLimitConfig,
+    #[serde(default)]
+    pub output: StoreLimitConfig,
+}
+
+/// How many entries and approximate bytes a store may keep
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct StoreLimitConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entries: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<usize>,
+}
+
+/// The `[hooks]` table
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct HooksConfig {
+    /// The `display` closure for tools that don't set their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_tool_output: Option<String>,
+}
+
+/// The `[tools]` table
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct ToolsConfig {
+    /// Tools registered as `tool local.<name>`, keyed by name
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub local: IndexMap<String, LocalToolConfig>,
+    /// How many characters of each description `tool list` shows; 0 shows
+    /// them in full
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description_chars: Option<usize>,
+}
+
+/// How many characters of each description `tool list` shows by default
+pub const DEFAULT_DESCRIPTION_CHARS: usize = 80;
+
+/// The server name local tools are listed under, as in `tool local.<name>`
+pub const LOCAL_TOOLS_SERVER: &str = "local";
+
+/// The types a local tool's parameters can have
+pub const LOCAL_PARAM_TYPES: &[&str] =
+    &["string", "integer", "number", "boolean", "array", "object"];
+
+/// A tool whose body is a Nushell closure
+///
+/// The closure gets the arguments as a record in `$in`, so
+/// `{|| $in.path | open }` reads the `path` parameter.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LocalToolConfig {
+    #[serde(default)]
+    pub description: String,
+    /// The parameters, in the order they are mapped onto the command
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<LocalToolParam>,
+    /// The closure source, like `{|| $in.name | str upcase }`
+    pub body: String,
+}
+
+/// A parameter of a local tool
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LocalToolParam {
+    pub name: String,
+    /// A JSON Schema type: `string`, `integer`, `number`, `boolean`,
+    /// `array` or `object`
+    #[serde(rename = "type", default = "LocalToolParam::default_type")]
+    pub param_type: String,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl LocalToolParam {
+    fn default_type() -> String {
+        "string".into()
+    }
+}
+
+/// A named set of servers, e.g. for one project
+///
+/// The servers in the top-level `[servers]` table are shared by every
+/// profile; `mcp profile switch` only replaces the profile's own servers.
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct ProfileConfig {
+    #[serde(default)]
+    pub servers: IndexMap<String, McpServerConfig>,
+}
+
+impl McpReplConfig {
+    /// Resolve the effective settings for a tool on a server
+    ///
+    /// Per-tool settings win over per-server settings, which win over the
+    /// global `[defaults]`. A tool without a `display` closure uses
+    /// `hooks.display_tool_output`.
+    #[must_use]
+    pub fn tool_settings(&self, server: &str, tool: &str) -> EffectiveToolSettings {
+        let settings = self.servers.get(server).map_or_else(
+            || ToolSettings::resolve(&self.defaults, &ToolSettings::default(), None),
+            |server| {
+                ToolSettings::resolve(&self.defaults, &server.settings, server.tools.get(tool))
+            },
+        );
+
+        EffectiveToolSettings {
+            display: settings
+                .display
+                .or_else(|| self.hooks.display_tool_output.clone()),
+            ..settings
+        }
+    }
+
+    /// How many server notifications to keep before dropping the oldest
+    #[must_use]
+    pub fn event_buffer(&self) -> usize {
+        self.event_buffer.unwrap_or(DEFAULT_EVENT_BUFFER)
+    }
+
+    /// How many characters of each description `tool list` shows, or `None`
+    /// for the full text
+    #[must_use]
+    pub fn description_chars(&self) -> Option<usize> {
+        match self.tools.description_chars {
+            Some(0) => None,
+            chars => Some(chars.unwrap_or(DEFAULT_DESCRIPTION_CHARS)),
+        }
+    }
+
+    /// The limit of a store, from `[memory]`
+    ///
+    /// The event feed's entries fall back to `event_buffer`.
+    #[must_use]
+    pub fn memory_limit(&self, store: Store) -> Limit {
+        let configured = match store {
+            Store::Results => &self.memory.results,
+            Store::Events => &self.memory.events,
+            Store::Output => &self.memory.output,
+        };
+        let default = match store {
+            Store::Events => Limit {
+                entries: self.event_buffer(),
+                ..store.default_limit()
+            },
+            _ => store.default_limit(),
+        };
+
+        Limit {
+            entries: configured.entries.unwrap_or(default.entries),
+            bytes: configured.bytes.unwrap_or(default.bytes),
+        }
+    }
+
+    /// What happens to JSON numbers that don't fit a Nushell int or float
+    #[must_use]
+    pub fn json_numbers(&self) -> JsonNumbers {
+        self.json_numbers.unwrap_or_default()
+    }
+
+    /// Whether the session has the `http` commands, as it does by default
+    #[must_use]
+    pub fn http_commands_enabled(&self) -> bool {
+        self.enable_http_commands.unwrap_or(true)
+    }
+
+    /// When URIs in the output are made terminal hyperlinks
+    #[must_use]
+    pub fn hyperlinks(&self) -> Hyperlinks {
+        self.hyperlinks.unwrap_or_default()
+    }
+
+    /// How long to wait for a server to connect: its own `connect_timeout`,
+    /// then the global one, then 30s
+    pub fn connect_timeout(&self, server: &McpServerConfig) -> Result<Duration> {
+        Ok(server
+            .connection
+            .connect_timeout()?
+            .or_else(|| self.connect_timeout.map(|timeout| timeout.0))
+            .unwrap_or(DEFAULT_CONNECT_TIMEOUT))
+    }
+
+    /// The configuration with a profile's servers added to the shared ones
+    ///
+    /// With `None`, only the shared servers are configured. A profile can't
+    /// define a server with the same name as a shared one.
+    pub fn with_profile(&self, profile: Option<&str>) -> Result<Self> {
+        let mut config = Self {
+            profile: profile.map(ToString::to_string),
+            ..self.clone()
+        };
+
+        let Some(profile) = profile else {
+            return Ok(config);
+        };
+
+        let profile_config = self.profiles.get(profile).with_context(|| {
+            format!(
+                "Unknown profile '{profile}'; the config defines {}",
+                if self.profiles.is_empty() {
+                    "no profiles".to_string()
+                } else {
+                    self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+                }
+            )
+        })?;
+
+        for (name, server) in &profile_config.servers {
+            if self.servers.contains_key(name) {
+                return Err(anyhow!(
+                    "profiles.{profile}.servers.{name} has the same name as a shared server"
+                ));
+            }
+            config.servers.insert(name.clone(), server.clone());
+        }
+
+        Ok(config)
+    }
+
+    /// Join each SSE server's `path` to `base_url`, so every server has a
+    /// full `url` from here on
+    fn resolve_paths(&mut self) {
+        let base_url = self.base_url.as_deref();
+        let servers = self.servers.values_mut().chain(
+            self.profiles
+                .values_mut()
+                .flat_map(|profile| profile.servers.values_mut()),
+        );
+        for server in servers {
+            server.connection.resolve_path(base_url);
+        }
+    }
+
+    /// Check the settings that are only parsed when a server connects
+    pub fn validate(&self) -> Result<()> {
+        let profile_servers = self.profiles.iter().flat_map(|(profile, config)| {
+            config
+                .servers
+                .iter()
+                .map(move |(name, server)| (format!("profiles.{profile}.servers.{name}"), server))
+        });
+
+        let mut problems: Vec<String> = self
+            .servers
+            .iter()
+            .map(|(name, server)| (format!("servers.{name}"), server))
+            .chain(profile_servers)
+            .flat_map(|(path, server)| {
+                let timeout = server
+                    .connection
+                    .connect_timeout()
+                    .err()
+                    .map(|err| format!("{path}: {err:#}"));
+                let url = server
+                    .connection
+                    .url_problem(self.base_url.as_deref())
+                    .map(|problem| format!("{path}: {problem}"));
+                timeout.into_iter().chain(url)
+            })
+            .collect();
+
+        if let Some(base_url) = &self.base_url {
+            if !base_url.contains("://") {
+                problems.push(format!(
+                    "base_url: '{base_url}' isn't a full URL like https://host/mcp"
+                ));
+            }
+        }
+
+        for (profile, config) in &self.profiles {
+            problems.extend(
+                config
+                    .servers
+                    .keys()
+                    .filter(|name| self.servers.contains_key(*name))
+                    .map(|name| {
+                        format!(
+                            "profiles.{profile}.servers.{name}: has the same name as a shared server"
+                        )
+                    }),
+            );
+        }
+
+        if !self.tools.local.is_empty() {
+            problems.extend(
+                self.servers
+                    .keys()
+                    .chain(self.profiles.values().flat_map(|config| config.servers.keys()))
+                    .filter(|name| *name == LOCAL_TOOLS_SERVER)
+                    .map(|_| {
+                        format!(
+                            "servers.{LOCAL_TOOLS_SERVER}: clashes with the commands for [tools.local]"
+                        )
+                    }),
+            );
+        }
+
+        for (name, tool) in &self.tools.local {
+            problems.extend(
+                tool.params
+                    .iter()
+                    .filter(|param| !LOCAL_PARAM_TYPES.contains(&param.param_type.as_str()))
+                    .map(|param| {
+                        format!(
+                            "tools.local.{name}.params.{}: unknown type '{}', expected one of {}",
+                            param.name,
+                            param.param_type,
+                            LOCAL_PARAM_TYPES.join(", ")
+                        )
+                    }),
+            );
+        }
+
+        if let Some(profile) = &self.profile {
+            if !self.profiles.contains_key(profile) {
+                problems.push(format!("profile: there is no profile named '{profile}'"));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Invalid configuration:\n  {}",
+                problems.join("\n  ")
+            ))
+        }
+    }
+
+    /// Check that each command server's program can be found, as connecting
+    /// would, for `--check-config`
+    ///
+    /// Servers with `skip_path_check` are left out.
+    pub fn check_programs(&self) -> Result<()> {
+        let cwd = std::env::current_dir().context("Failed to read the current directory")?;
+        let profile_servers = self.profiles.iter().flat_map(|(profile, config)| {
+            config
+                .servers
+                .iter()
+                .map(move |(name, server)| (format!("profiles.{profile}.servers.{name}"), server))
+        });
+
+        let problems: Vec<String> = self
+            .servers
+            .iter()
+            .map(|(name, server)| (format!("servers.{name}"), server))
+            .chain(profile_servers)
+            .filter(|(_, server)| !server.skip_path_check)
+            .filter_map(|(path, server)| {
+                let McpConnectionType::Command { command, env, .. } = &server.connection else {
+                    return None;
+                };
+                let program = shell_words::split(command)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|words| {
+                        words
+                            .into_iter()
+                            .next()
+                            .ok_or_else(|| anyhow!("command is empty"))
+                    })
+                    .and_then(|program| {
+                        let env = env.clone().unwrap_or_default();
+                        check_program(&program, &cwd, program_path(&env).as_deref())
+                    });
+                program.err().map(|err| format!("{path}: {err:#}"))
+            })
+            .collect();
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Invalid configuration:\n  {}",
+                problems.join("\n  ")
+            ))
+        }
+    }
+
+    /// Resolve the settings for requests to a server that aren't tool calls
+    #[must_use]
+    pub fn server_settings(&self, server: &str) -> EffectiveToolSettings {
+        let server_settings = self
+            .servers
+            .get(server)
+            .map(|server| server.settings.clone())
+            .unwrap_or_default();
+
+        ToolSettings::resolve(&self.defaults, &server_settings, None)
+    }
+
+    /// The layer the server's definition came from, if it's configured
+    ///
+    /// A server of the selected profile is looked up under that profile.
+    #[must_use]
+    pub fn server_source(&self, server: &str) -> Option<&str> {
+        self.server_sources
+            .get(&format!("servers.{server}"))
+            .or_else(|| {
+                let profile = self.profile.as_ref()?;
+                self.server_sources
+                    .get(&format!("profiles.{profile}.servers.{server}"))
+            })
+            .map(String::as_str)
+    }
+
+    /// What a server may do; a server missing from the config is untrusted
+    #[must_use]
+    pub fn trust_policy(&self, server: &str) -> TrustPolicy {
+        self.servers
+            .get(server)
+            .map(McpServerConfig::trust_policy)
+            .unwrap_or_default()
+    }
+}
+
 pub trait McpConfigLoader {
     fn load_raw_env(&self) -> IndexMap<String, String>;
 
@@ -147,19 +1038,44 @@ impl McpReplConfig {
             FileFormat::Toml,
         ));
 
-        builder = add_config_source(builder, loader.load_system_config()?);
-        builder = add_config_source(builder, loader.load_user_config()?);
-        builder = add_config_source(builder, loader.load_local_config()?);
-        builder = add_config_source(builder, loader.load_env_config()?);
+        let files = [
+            (
+                system_config_path().display().to_string(),
+                loader.load_system_config()?,
+            ),
+            (user_config_label(), loader.load_user_config()?),
+            ("./mcp-repl.toml".to_string(), loader.load_local_config()?),
+            (
+                loader
+                    .load_raw_env()
+                    .get("MCP_CONFIG")
+                    .cloned()
+                    .unwrap_or_default(),
+                loader.load_env_config()?,
+            ),
+        ];
+        let files: Vec<(String, ConfigSource)> = files
+            .into_iter()
+            .filter_map(|(label, source)| Some((label, source?)))
+            .collect();
+
+        for (_, source) in &files {
+            builder = add_config_source(builder, source.clone());
+        }
 
         // Environment variable overrides
         builder = builder.add_source(loader.load_env());
+        let layers = layer_servers(config, loader, &files);
 
         // Build the config
         let result = match builder.build() {
             Ok(config) => {
                 log::debug!("{config:#?}");
-                Ok(config.try_deserialize()?)
+                check_config_files(&files)?;
+                let mut result: Self = config.try_deserialize()?;
+                result.server_sources = server_sources(layers);
+                result.resolve_paths();
+                Ok(result)
             }
             Err(e) => return Err(anyhow::anyhow!("Config error: {}", e)),
         };
@@ -168,23 +1084,180 @@ impl McpReplConfig {
     }
 }
 
+/// The servers each layer defines, as `(label, config paths)` in the order
+/// the layers are merged
+///
+/// Each layer is built on its own, since the merged config can't tell
+/// where a server came from. A layer that doesn't build defines nothing
+/// here; the merged build reports its error.
+fn layer_servers(
+    cli: &CliArgs,
+    loader: &dyn McpConfigLoader,
+    files: &[(String, ConfigSource)],
+) -> Vec<(String, Vec<String>)> {
+    let build = |builder: config::ConfigBuilder<config::builder::DefaultState>| {
+        builder
+            .build()
+            .map(|config| defined_servers(&config))
+            .unwrap_or_default()
+    };
+
+    let mut layers = vec![(
+        "cli".to_string(),
+        build(Config::builder().add_source(cli.clone())),
+    )];
+    for (label, source) in files {
+        layers.push((
+            label.clone(),
+            build(add_config_source(Config::builder(), source.clone())),
+        ));
+    }
+    layers.push((
+        "env".to_string(),
+        build(Config::builder().add_source(loader.load_env())),
+    ));
+    layers
+}
+
+/// The config path of each server defined in `config`, shared or in a profile
+fn defined_servers(config: &Config) -> Vec<String> {
+    let mut paths: Vec<String> = config
+        .get_table("servers")
+        .map(|servers| {
+            servers
+                .keys()
+                .map(|name| format!("servers.{name}"))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    for (profile, value) in config.get_table("profiles").unwrap_or_default() {
+        let servers = value
+            .into_table()
+            .ok()
+            .and_then(|profile| profile.get("servers").cloned())
+            .and_then(|servers| servers.into_table().ok())
+            .unwrap_or_default();
+        paths.extend(
+            servers
+                .keys()
+                .map(|name| format!("profiles.{profile}.servers.{name}")),
+        );
+    }
+
+    paths
+}
+
+/// The layer each server's definition comes from: the last one to define it
+fn server_sources(layers: Vec<(String, Vec<String>)>) -> IndexMap<String, String> {
+    let mut sources = IndexMap::new();
+
+    for (label, paths) in layers {
+        for path in paths {
+            if let Some(previous) = sources.insert(path.clone(), label.clone()) {
+                log::debug!("{path} from {label} overrides {previous}");
+            }
+        }
+    }
+
+    sources
+}
+
 // Helper function to add a config source to the builder
 fn add_config_source(
     builder: config::ConfigBuilder<config::builder::DefaultState>,
-    source: Option<ConfigSource>,
+    source: ConfigSource,
 ) -> config::ConfigBuilder<config::builder::DefaultState> {
     match source {
-        Some(ConfigSource::FilePath(file)) => builder.add_source(file),
-        Some(ConfigSource::FileContent(file)) => builder.add_source(file),
-        None => builder,
+        ConfigSource::FilePath(file) => builder.add_source(file),
+        ConfigSource::FileContent(file) => builder.add_source(file),
+    }
+}
+
+/// Load each config file over the defaults on its own, so that a value
+/// that doesn't fit the config can be blamed on the file it's in
+///
+/// Once the files are merged, the config crate can no longer tell which
+/// one a bad value came from.
+fn check_config_files(files: &[(String, ConfigSource)]) -> Result<()> {
+    for (label, source) in files {
+        let builder = Config::builder().add_source(config::File::from_str(
+            include_str!("../config/data/default.toml"),
+            FileFormat::Toml,
+        ));
+        let config = add_config_source(builder, source.clone())
+            .build()
+            .map_err(|err| anyhow!("error in {label}: {err}"))?;
+
+        if let Err(err) = config.clone().try_deserialize::<McpReplConfig>() {
+            return Err(anyhow!(
+                "error in {label}: {}",
+                describe_config_error(&err, &config)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// A deserialization error as "key should be ..., found ...", with the
+/// value the file has at that key
+fn describe_config_error(err: &config::ConfigError, config: &Config) -> String {
+    match err {
+        config::ConfigError::Type {
+            unexpected,
+            expected,
+            key: Some(key),
+            ..
+        } => format!("{key} should be {expected}, found {unexpected}"),
+        config::ConfigError::At {
+            error,
+            key: Some(key),
+            ..
+        } => {
+            let raw = config
+                .get::<config::Value>(key)
+                .map(|value| format!(" (found {value})"))
+                .unwrap_or_default();
+            format!("{key}: {}{raw}", describe_config_error(error, config))
+        }
+        other => other.to_string(),
     }
 }
 
+/// The user config file's path as it's reported, with the home directory
+/// shortened to `~`
+fn user_config_label() -> String {
+    let Some(path) = user_config_path() else {
+        return "the user config file".to_string();
+    };
+
+    match dirs::home_dir().and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf)) {
+        Some(relative) => format!("~/{}", relative.display()),
+        None => path.display().to_string(),
+    }
+}
+
+/// The config files that are read, in increasing order of precedence,
+/// whether or not they exist
+///
+/// `$MCP_CONFIG` is only included when it's set.
+#[must_use]
+pub fn config_search_paths() -> Vec<PathBuf> {
+    let mut paths = vec![system_config_path()];
+    paths.extend(user_config_path());
+    paths.push(PathBuf::from("./mcp-repl.toml"));
+    paths.extend(std::env::var_os("MCP_CONFIG").map(PathBuf::from));
+    paths
+}
+
 fn system_config_path() -> PathBuf {
     PathBuf::from("/etc/mcp-repl/config.toml")
 }
 
-fn user_config_path() -> Option<PathBuf> {
+/// Where the per-user config file lives, e.g. `~/.config/mcp-repl/config.toml`
+#[must_use]
+pub fn user_config_path() -> Option<PathBuf> {
     dirs::config_dir().map(|p| p.join("mcp-repl").join("config.toml"))
 }
 
@@ -313,4 +1386,391 @@ mod tests {
 
         assert!(config.find_server("test-server").is_some());
     }
+
+    #[test]
+    fn test_parse_inherit_env() {
+        let parse = |json: serde_json::Value| serde_json::from_value::<InheritEnv>(json);
+
+        assert_eq!(parse(serde_json::json!("all")).unwrap(), InheritEnv::All);
+        assert_eq!(parse(serde_json::json!("none")).unwrap(), InheritEnv::None);
+        assert_eq!(
+            parse(serde_json::json!(["PATH"])).unwrap(),
+            InheritEnv::Only(vec!["PATH".into()])
+        );
+        assert!(parse(serde_json::json!("some")).is_err());
+        assert!(InheritEnv::default().inherits("HOME"));
+        assert!(!InheritEnv::default().inherits("GITHUB_TOKEN"));
+    }
+
+    #[test]
+    fn test_connect_timeout() {
+        let loader = TestConfigLoader::new().with_config(
+            "./mcp-repl.toml",
+            r#"
+            connect_timeout = "45s"
+
+            [servers.slow]
+            command = "slow-server"
+            connect_timeout = "2m"
+
+            [servers.fast]
+            url = "http://localhost:8080"
+
+            [servers.broken]
+            command = "broken-server"
+            connect_timeout = "soon"
+            "#,
+        );
+
+        let config = McpReplConfig::load(&loader, &CliArgs::default()).unwrap();
+        let timeout = |name: &str| config.connect_timeout(&config.servers[name]).unwrap();
+
+        assert_eq!(timeout("slow"), Duration::from_secs(120));
+        assert_eq!(timeout("fast"), Duration::from_secs(45));
+
+        let err = config.validate().unwrap_err();
+        assert!(format!("{err}").contains("servers.broken"));
+    }
+
+    #[test]
+    fn test_memory_limits() {
+        let loader = TestConfigLoader::new().with_config(
+            "./mcp-repl.toml",
+            r#"
+            event_buffer = 50
+
+            [memory.results]
+            entries = 10
+            bytes = 4096
+
+            [memory.output]
+            bytes = 512
+            "#,
+        );
+
+        let config = McpReplConfig::load(&loader, &CliArgs::default()).unwrap();
+
+        assert_eq!(
+            config.memory_limit(Store::Results),
+            Limit {
+                entries: 10,
+                bytes: 4096
+            }
+        );
+        assert_eq!(
+            config.memory_limit(Store::Events),
+            Limit {
+                entries: 50,
+                ..Store::Events.default_limit()
+            }
+        );
+        assert_eq!(
+            config.memory_limit(Store::Output),
+            Limit {
+                bytes: 512,
+                ..Store::Output.default_limit()
+            }
+        );
+    }
+
+    #[test]
+    fn test_join_url() {
+        let cases = [
+            (
+                "https://host/api",
+                "/github/sse",
+                "https://host/api/github/sse",
+            ),
+            (
+                "https://host/api/",
+                "/github/sse",
+                "https://host/api/github/sse",
+            ),
+            (
+                "https://host/api/",
+                "github/sse",
+                "https://host/api/github/sse",
+            ),
+            (
+                "https://host/api",
+                "github/sse",
+                "https://host/api/github/sse",
+            ),
+            ("https://host//", "//sse", "https://host/sse"),
+            ("https://host", "", "https://host/"),
+            (
+                "https://host/api?key=1",
+                "/sse",
+                "https://host/api/sse?key=1",
+            ),
+            (
+                "https://host/api/?key=1",
+                "sse?v=2",
+                "https://host/api/sse?key=1&v=2",
+            ),
+            ("https://host/api", "/sse?v=2", "https://host/api/sse?v=2"),
+            ("https://host/api?", "/sse", "https://host/api/sse"),
+        ];
+
+        for (base, path, expected) in cases {
+            assert_eq!(join_url(base, path), expected, "{base} + {path}");
+        }
+    }
+
+    #[test]
+    fn test_base_url_and_path() {
+        let loader = TestConfigLoader::new().with_config(
+            "./mcp-repl.toml",
+            r#"
+            base_url = "https://mcp.example.com/v1/"
+
+            [servers.github]
+            path = "/github/sse"
+
+            [servers.direct]
+            url = "http://localhost:8080/sse"
+
+            [profiles.work.servers.jira]
+            path = "jira/sse?team=core"
+            "#,
+        );
+
+        let config = McpReplConfig::load(&loader, &CliArgs::default()).unwrap();
+        let url = |server: &McpServerConfig| match &server.connection {
+            McpConnectionType::Sse { url, path, .. } => {
+                assert_eq!(*path, None);
+                url.clone()
+            }
+            McpConnectionType::Command { .. } => panic!("not an SSE server"),
+        };
+
+        assert_eq!(
+            url(&config.servers["github"]),
+            "https://mcp.example.com/v1/github/sse"
+        );
+        assert_eq!(url(&config.servers["direct"]), "http://localhost:8080/sse");
+        assert_eq!(
+            url(&config.profiles["work"].servers["jira"]),
+            "https://mcp.example.com/v1/jira/sse?team=core"
+        );
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn test_url_or_path_is_validated() {
+        let loader = TestConfigLoader::new().with_config(
+            "./mcp-repl.toml",
+            r#"
+            [servers.both]
+            url = "http://localhost:8080/sse"
+            path = "/sse"
+
+            [servers.neither]
+            connect_timeout = "5s"
+
+            [servers.unjoined]
+            path = "/sse"
+            "#,
+        );
+
+        let config = McpReplConfig::load(&loader, &CliArgs::default()).unwrap();
+        let err = format!("{}", config.validate().unwrap_err());
+
+        assert!(
+            err.contains("servers.both: set either url or path, not both"),
+            "{err}"
+        );
+        assert!(
+            err.contains("servers.neither: set url, or path together with a top-level base_url"),
+            "{err}"
+        );
+        assert!(
+            err.contains("servers.unjoined: path needs a top-level base_url"),
+            "{err}"
+        );
+
+        let config = McpReplConfig {
+            base_url: Some("mcp.example.com".into()),
+            ..McpReplConfig::default()
+        };
+        let err = format!("{}", config.validate().unwrap_err());
+        assert!(err.contains("base_url: 'mcp.example.com'"), "{err}");
+    }
+
+    #[test]
+    fn test_config_errors_name_the_file() {
+        let loader = TestConfigLoader::new()
+            .with_config(
+                "./mcp-repl.toml",
+                r#"
+            [servers.fetch]
+            command = "fetch-server"
+            "#,
+            )
+            .with_config(
+                "~/.config/mcp-repl/config.toml",
+                r#"
+            event_buffer = "lots"
+            "#,
+            );
+
+        let err = format!(
+            "{:#}",
+            McpReplConfig::load(&loader, &CliArgs::default()).unwrap_err()
+        );
+        assert!(
+            err.starts_with(&format!("error in {}:", user_config_label())),
+            "{err}"
+        );
+        assert!(err.contains("event_buffer"), "{err}");
+        assert!(err.contains("lots"), "{err}");
+    }
+
+    #[test]
+    fn test_server_sources() {
+        let loader = TestConfigLoader::new()
+            .with_config(
+                "~/.config/mcp-repl/config.toml",
+                r#"
+            [servers.github]
+            command = "github-server --old"
+
+            [servers.fetch]
+            command = "fetch-server"
+            "#,
+            )
+            .with_config(
+                "./mcp-repl.toml",
+                r#"
+            [servers.github]
+            command = "github-server"
+
+            [profiles.work.servers.jira]
+            command = "jira-server"
+            "#,
+            );
+
+        let config = McpReplConfig::load(&loader, &CliArgs::default()).unwrap();
+        assert!(matches!(
+            &config.servers["github"].connection,
+            McpConnectionType::Command { command, .. } if command == "github-server"
+        ));
+        assert_eq!(config.server_source("github"), Some("./mcp-repl.toml"));
+        assert_eq!(
+            config.server_source("fetch"),
+            Some(user_config_label().as_str())
+        );
+        assert_eq!(config.server_source("jira"), None);
+        assert_eq!(
+            config
+                .with_profile(Some("work"))
+                .unwrap()
+                .server_source("jira"),
+            Some("./mcp-repl.toml")
+        );
+    }
+
+    #[test]
+    fn test_server_sources_later_layers_win() {
+        let sources = server_sources(vec![
+            ("cli".into(), vec!["servers.a".into()]),
+            (
+                "./mcp-repl.toml".into(),
+                vec!["servers.a".into(), "servers.b".into()],
+            ),
+            ("env".into(), vec!["servers.b".into()]),
+        ]);
+
+        assert_eq!(sources["servers.a"], "./mcp-repl.toml");
+        assert_eq!(sources["servers.b"], "env");
+    }
+
+    #[test]
+    fn test_with_profile() {
+        let loader = TestConfigLoader::new().with_config(
+            "./mcp-repl.toml",
+            r#"
+            profile = "work"
+
+            [servers.fetch]
+            command = "fetch-server"
+
+            [profiles.work.servers.jira]
+            command = "jira-server"
+
+            [profiles.home.servers.fetch]
+            command = "other-fetch-server"
+            "#,
+        );
+
+        let config = McpReplConfig::load(&loader, &CliArgs::default()).unwrap();
+
+        let work = config.with_profile(Some("work")).unwrap();
+        assert_eq!(
+            work.servers.keys().collect::<Vec<_>>(),
+            vec!["fetch", "jira"]
+        );
+        assert_eq!(work.profile.as_deref(), Some("work"));
+
+        let shared = config.with_profile(None).unwrap();
+        assert_eq!(shared.servers.keys().collect::<Vec<_>>(), vec!["fetch"]);
+
+        assert!(config.with_profile(Some("home")).is_err());
+        assert!(config.with_profile(Some("missing")).is_err());
+
+        let err = config.validate().unwrap_err();
+        assert!(format!("{err}").contains("profiles.home.servers.fetch"));
+    }
+
+    #[test]
+    fn test_local_tools() {
+        let loader = TestConfigLoader::new().with_config(
+            "./mcp-repl.toml",
+            r#"
+            [servers.local]
+            command = "local-server"
+
+            [tools.local.shout]
+            description = "Upper-case some text"
+            body = "{|| $in.text | str upcase }"
+            params = [
+                { name = "text", required = true },
+                { name = "times", type = "int" },
+            ]
+            "#,
+        );
+
+        let config = McpReplConfig::load(&loader, &CliArgs::default()).unwrap();
+        let shout = &config.tools.local["shout"];
+        assert_eq!(shout.params[0].param_type, "string");
+        assert!(shout.params[0].required);
+        assert!(!shout.params[1].required);
+
+        let err = format!("{}", config.validate().unwrap_err());
+        assert!(err.contains("servers.local: clashes"));
+        assert!(err.contains("tools.local.shout.params.times: unknown type 'int'"));
+    }
+
+    #[test]
+    fn test_display_hook_fallback() {
+        let loader = TestConfigLoader::new().with_config(
+            "./mcp-repl.toml",
+            r#"
+            [hooks]
+            display_tool_output = "{|| table --expand }"
+
+            [servers.fetch]
+            command = "fetch-server"
+
+            [servers.fetch.tools.list]
+            display = "{|| select name url }"
+            "#,
+        );
+
+        let config = McpReplConfig::load(&loader, &CliArgs::default()).unwrap();
+        let display = |tool: &str| config.tool_settings("fetch", tool).display;
+
+        assert_eq!(display("list").as_deref(), Some("{|| select name url }"));
+        assert_eq!(display("get").as_deref(), Some("{|| table --expand }"));
+    }
 }
@@ -0,0 +1,263 @@
+use std::{
+    env, fs,
+    io::IsTerminal,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use indexmap::IndexMap;
+
+use super::user_config_path;
+use crate::util::prompt;
+
+/// Options for `nu-mcp-repl init`
+#[derive(clap::Args, Clone, Debug, Default)]
+pub struct InitArgs {
+    /// Servers to add, separated by commas (filesystem, fetch, github);
+    /// asked for interactively when omitted
+    #[arg(long, value_delimiter = ',')]
+    servers: Vec<String>,
+
+    /// Write the user config file instead of ./mcp-repl.toml
+    #[arg(long)]
+    user: bool,
+
+    /// Overwrite the file if it already exists
+    #[arg(long)]
+    force: bool,
+}
+
+/// A server `init` knows how to set up
+struct ServerTemplate {
+    name: &'static str,
+    description: &'static str,
+    command: &'static str,
+    /// Environment variables the server needs, with the placeholder written
+    /// when no value is given
+    env: &'static [(&'static str, &'static str)],
+}
+
+const TEMPLATES: &[ServerTemplate] = &[
+    ServerTemplate {
+        name: "filesystem",
+        description: "Read and write files under the current directory (npx)",
+        command: "npx -y @modelcontextprotocol/server-filesystem .",
+        env: &[],
+    },
+    ServerTemplate {
+        name: "fetch",
+        description: "Fetch web pages as markdown (uvx)",
+        command: "uvx mcp-server-fetch",
+        env: &[],
+    },
+    ServerTemplate {
+        name: "github",
+        description: "Work with GitHub repositories, issues and pull requests (docker)",
+        command: "docker run -i --rm ghcr.io/github/github-mcp-server",
+        env: &[("GITHUB_PERSONAL_ACCESS_TOKEN", "your-github-token-here")],
+    },
+];
+
+const HEADER: &str =
+    "# Written by `nu-mcp-repl init`. See mcp-repl.toml.example for every setting.\n";
+
+/// Create a config file with the chosen servers
+pub fn run(args: &InitArgs) -> Result<()> {
+    let path = if args.user {
+        user_config_path().context("Couldn't find the user config directory")?
+    } else {
+        PathBuf::from("./mcp-repl.toml")
+    };
+
+    if path.exists() && !args.force {
+        bail!(
+            "{} already exists; pass --force to overwrite it",
+            path.display()
+        );
+    }
+
+    let interactive = args.servers.is_empty();
+    if interactive && !std::io::stdin().is_terminal() {
+        bail!("Pass --servers to choose servers when stdin is not a terminal");
+    }
+
+    let templates = if interactive {
+        choose_templates()?
+    } else {
+        find_templates(&args.servers)?
+    };
+
+    check_commands(&templates)?;
+
+    let mut env_values = IndexMap::new();
+    if interactive {
+        for (name, _) in templates.iter().flat_map(|template| template.env) {
+            let value = prompt::read_line(&format!("{name} (empty to fill in later): "))?;
+            if !value.is_empty() {
+                env_values.insert((*name).to_string(), value);
+            }
+        }
+    }
+
+    write_config(&path, &render(&templates, &env_values))?;
+    crate::success!("Wrote {}", path.display());
+
+    Ok(())
+}
+
+fn choose_templates() -> Result<Vec<&'static ServerTemplate>> {
+    prompt::show("Which servers should be added?")?;
+    for (index, template) in TEMPLATES.iter().enumerate() {
+        prompt::show(&format!(
+            "  {}) {:<10} {}",
+            index + 1,
+            template.name,
+            template.description
+        ))?;
+    }
+
+    loop {
+        let answer = prompt::read_line("Servers (numbers or names, separated by commas): ")?;
+        let choices: Vec<String> = answer
+            .split(',')
+            .map(str::trim)
+            .filter(|choice| !choice.is_empty())
+            .map(|choice| match choice.parse::<usize>() {
+                Ok(index) if (1..=TEMPLATES.len()).contains(&index) => {
+                    TEMPLATES[index - 1].name.to_string()
+                }
+                _ => choice.to_string(),
+            })
+            .collect();
+
+        if choices.is_empty() {
+            crate::warning!("Choose at least one server");
+            continue;
+        }
+
+        match find_templates(&choices) {
+            Ok(templates) => return Ok(templates),
+            Err(err) => crate::warning!("{}", err),
+        }
+    }
+}
+
+fn find_templates(names: &[String]) -> Result<Vec<&'static ServerTemplate>> {
+    names
+        .iter()
+        .map(|name| {
+            TEMPLATES
+                .iter()
+                .find(|template| template.name == name.as_str())
+                .with_context(|| {
+                    format!(
+                        "Unknown server '{name}'; choose from {}",
+                        TEMPLATES
+                            .iter()
+                            .map(|template| template.name)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Make sure the program each chosen server runs is installed
+fn check_commands(templates: &[&ServerTemplate]) -> Result<()> {
+    let missing: Vec<String> = templates
+        .iter()
+        .filter_map(|template| {
+            let program = template.command.split_whitespace().next()?;
+            find_on_path(program)
+                .is_none()
+                .then(|| format!("{} needs `{program}`", template.name))
+        })
+        .collect();
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        bail!(
+            "Some servers need programs that aren't on PATH:\n  {}",
+            missing.join("\n  ")
+        )
+    }
+}
+
+fn find_on_path(program: &str) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Render the config file for the chosen servers
+///
+/// Environment variables without a value in `env_values` get the template's
+/// placeholder.
+fn render(templates: &[&ServerTemplate], env_values: &IndexMap<String, String>) -> String {
+    let mut out = String::from(HEADER);
+
+    for template in templates {
+        out.push_str(&format!(
+            "\n# {}\n[servers.{}]\ncommand = {}\n",
+            template.description,
+            template.name,
+            toml_edit::Value::from(template.command)
+        ));
+
+        for (name, placeholder) in template.env {
+            let value = env_values.get(*name).map_or(*placeholder, String::as_str);
+            out.push_str(&format!("env.{name} = {}\n", toml_edit::Value::from(value)));
+        }
+    }
+
+    out
+}
+
+fn write_config(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::McpReplConfig;
+
+    const FIXTURE: &str = include_str!("data/init.toml");
+
+    #[test]
+    fn test_render_matches_fixture() {
+        let templates: Vec<_> = TEMPLATES.iter().collect();
+        assert_eq!(render(&templates, &IndexMap::new()), FIXTURE);
+    }
+
+    #[test]
+    fn test_rendered_config_is_valid() {
+        let config: McpReplConfig = toml_edit::de::from_str(FIXTURE).unwrap();
+        config.validate().unwrap();
+
+        let names: Vec<_> = config.servers.keys().map(String::as_str).collect();
+        assert_eq!(names, vec!["filesystem", "fetch", "github"]);
+    }
+
+    #[test]
+    fn test_render_fills_in_env_values() {
+        let github = find_templates(&["github".to_string()]).unwrap();
+        let env_values = IndexMap::from([(
+            "GITHUB_PERSONAL_ACCESS_TOKEN".to_string(),
+            "ghp_test".to_string(),
+        )]);
+
+        assert!(
+            render(&github, &env_values)
+                .contains("env.GITHUB_PERSONAL_ACCESS_TOKEN = \"ghp_test\"")
+        );
+    }
+}
@@ -33,9 +33,7 @@ impl TypedValueParser for EnvValueParser {
         if parts.len() != 2 {
             return Err(Error::raw(
                 ErrorKind::InvalidValue,
-                format!(
-                    "Invalid key-value pair: '{s}'. Expected format: 'KEY:VALUE'"
-                ),
+                format!("Invalid key-value pair: '{s}'. Expected format: 'KEY:VALUE'"),
             ));
         }
 
@@ -54,6 +52,7 @@ impl TypedValueParser for EnvValueParser {
 ///
 /// This parser handles values in the format KEY:VALUE and supports multiple
 /// occurrences of the same argument flag, combining them into a single map.
-#[must_use] pub fn parse_env() -> EnvValueParser {
+#[must_use]
+pub fn parse_env() -> EnvValueParser {
     EnvValueParser::default()
 }
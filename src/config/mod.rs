@@ -1,5 +1,12 @@
 mod format;
+pub mod init;
 mod map_parser;
+mod save;
+mod settings;
+mod trust;
 
 pub use format::*;
 pub use map_parser::parse_env;
+pub use save::*;
+pub use settings::*;
+pub use trust::*;
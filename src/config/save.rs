@@ -0,0 +1,114 @@
+use anyhow::{Context, Result};
+use toml_edit::{DocumentMut, Item, Table};
+
+use super::McpServerConfig;
+
+/// The comment written at the top of files produced by `mcp save`
+pub const GENERATED_HEADER: &str =
+    "# Server entries in this file were written by `mcp save` from a REPL session.\n";
+
+/// Merge server configurations into the text of an existing TOML config file
+///
+/// Each server replaces the entry with the same name under `[servers]`.
+/// Everything else in the file (other servers, `[defaults]`, unrelated keys
+/// and their comments) is kept as it was.
+pub fn merge_servers_into_toml<'a>(
+    existing: &str,
+    servers: impl IntoIterator<Item = (&'a str, &'a McpServerConfig)>,
+) -> Result<String> {
+    let mut document: DocumentMut = existing
+        .parse()
+        .context("the existing config file is not valid TOML")?;
+
+    let servers_item = document.entry("servers").or_insert_with(|| {
+        let mut table = Table::new();
+        table.set_implicit(true);
+        Item::Table(table)
+    });
+    let Some(servers_table) = servers_item.as_table_mut() else {
+        anyhow::bail!("`servers` in the existing config file is not a table");
+    };
+
+    for (name, server) in servers {
+        let serialized = toml_edit::ser::to_document(server)
+            .with_context(|| format!("failed to serialize server '{name}'"))?;
+        servers_table.insert(name, Item::Table(serialized.as_table().clone()));
+    }
+
+    let rendered = document.to_string();
+    if rendered.starts_with(GENERATED_HEADER) {
+        Ok(rendered)
+    } else {
+        Ok(format!("{GENERATED_HEADER}\n{rendered}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+
+    use super::*;
+    use crate::config::{McpConnectionType, SamplingPolicy, ToolSettings};
+
+    fn command_server(command: &str) -> McpServerConfig {
+        McpServerConfig {
+            connection: McpConnectionType::Command {
+                command: command.to_string(),
+                env: None,
+                connect_timeout: None,
+            },
+            settings: ToolSettings::default(),
+            tools: IndexMap::new(),
+            protocol_version: None,
+            inherit_env: None,
+            trusted: false,
+            initialize_options: None,
+            queue_while_reconnecting: false,
+            reconnect_queue_limit: None,
+            skip_path_check: false,
+            sampling: SamplingPolicy::Refuse,
+            sampling_confirm_tokens: None,
+            auth_refresh_interval: None,
+        }
+    }
+
+    #[test]
+    fn test_merge_keeps_unrelated_keys() {
+        let existing = r#"
+# my defaults
+[defaults]
+timeout = "1m"
+
+[servers.fetch]
+command = "docker run -i --rm mcp/fetch"
+"#;
+        let server = command_server("npx -y agentql-mcp");
+        let merged = merge_servers_into_toml(existing, [("agentql", &server)]).unwrap();
+
+        assert!(merged.starts_with(GENERATED_HEADER));
+        assert!(merged.contains("# my defaults"));
+        assert!(merged.contains("timeout = \"1m\""));
+        assert!(merged.contains("[servers.fetch]"));
+        assert!(merged.contains("[servers.agentql]"));
+        assert!(merged.contains("command = \"npx -y agentql-mcp\""));
+    }
+
+    #[test]
+    fn test_merge_replaces_existing_server() {
+        let existing = "[servers.fs]\ncommand = \"old\"\n";
+        let server = command_server("new");
+        let merged = merge_servers_into_toml(existing, [("fs", &server)]).unwrap();
+
+        assert!(!merged.contains("\"old\""));
+        assert!(merged.contains("command = \"new\""));
+    }
+
+    #[test]
+    fn test_merge_writes_header_once() {
+        let server = command_server("cmd");
+        let first = merge_servers_into_toml("", [("fs", &server)]).unwrap();
+        let second = merge_servers_into_toml(&first, [("fs", &server)]).unwrap();
+
+        assert_eq!(second.matches(GENERATED_HEADER).count(), 1);
+    }
+}
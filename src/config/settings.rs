@@ -0,0 +1,441 @@
+use std::time::Duration;
+
+use nu_protocol::{Record, Span, Value};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Timeout used for tool calls when no configuration layer sets one
+pub const DEFAULT_TOOL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long to wait for a server to start and finish the `initialize` handshake
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The largest content block shown inline; larger ones are saved to a file
+pub const DEFAULT_MAX_RESULT_BYTES: u64 = 4 * 1024 * 1024;
+
+/// How many optional parameters of a tool get a flag of their own
+pub const DEFAULT_MAX_FLAGS: usize = 32;
+
+/// How long a call may run before a warning says it looks stuck
+pub const DEFAULT_STUCK_AFTER: Duration = Duration::from_secs(5 * 60);
+
+/// A duration written in the config file in humantime format (e.g. `"30s"`, `"5m"`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConfigDuration(pub Duration);
+
+impl Serialize for ConfigDuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&humantime::format_duration(self.0).to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for ConfigDuration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        humantime::parse_duration(&raw)
+            .map(ConfigDuration)
+            .map_err(|err| serde::de::Error::custom(format!("invalid duration '{raw}': {err}")))
+    }
+}
+
+/// How the text in a tool result is turned into Nushell values
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultFormat {
+    /// Return each text block as a single string
+    #[default]
+    Text,
+    /// Split text into a list of lines, or a table when every line is a JSON object
+    Lines,
+    /// Parse every line of text as JSON
+    Ndjson,
+    /// Parse each text block as JSON, streaming the elements of a large array
+    Json,
+}
+
+impl ResultFormat {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Lines => "lines",
+            Self::Ndjson => "ndjson",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Where a list-style tool puts its items and the cursor for the next page
+///
+/// Used by `--all` on generated commands, which calls the tool again with
+/// the cursor until there are no more pages.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct PaginationConfig {
+    /// The parameter the cursor is sent back in
+    pub cursor_param: String,
+    /// The field of the result that holds the next page's cursor
+    pub cursor_field: String,
+    /// The field of the result that holds the page's items
+    pub items_field: String,
+}
+
+impl Default for PaginationConfig {
+    fn default() -> Self {
+        Self {
+            cursor_param: "cursor".into(),
+            cursor_field: "nextCursor".into(),
+            items_field: "items".into(),
+        }
+    }
+}
+
+impl PaginationConfig {
+    /// Convert the setting into a Nushell record for display
+    #[must_use]
+    pub fn to_value(&self, span: Span) -> Value {
+        let mut record = Record::new();
+        record.push("cursor_param", Value::string(&self.cursor_param, span));
+        record.push("cursor_field", Value::string(&self.cursor_field, span));
+        record.push("items_field", Value::string(&self.items_field, span));
+        Value::record(record, span)
+    }
+}
+
+/// Settings that control how tool calls are made
+///
+/// The same set of knobs can be specified globally (`[defaults]`), per server
+/// (`[servers.<name>]`) and per tool (`[servers.<name>.tools.<tool>]`). Every
+/// field is optional so that each layer only overrides what it sets.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ToolSettings {
+    /// How long to wait for a tool call before giving up (`"0s"` waits forever)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<ConfigDuration>,
+    /// How long a call may run before warning that it looks stuck
+    ///
+    /// Tool calls with a `timeout` no longer than this are never warned about.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stuck_after: Option<ConfigDuration>,
+    /// How many times to retry a call that failed before its request was sent
+    ///
+    /// A request the server may have received isn't sent again, even if it
+    /// failed or timed out, since the tool may already have done its work.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<u32>,
+    /// How long a result may be served from the cache for identical arguments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_ttl: Option<ConfigDuration>,
+    /// Ask for confirmation before each call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirm: Option<bool>,
+    /// Don't register the tool at all
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disabled: Option<bool>,
+    /// Split text results into lines (shorthand for `format = "lines"`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub split_lines: Option<bool>,
+    /// How text results are converted into values
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<ResultFormat>,
+    /// Prompt for missing required parameters instead of failing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_missing_args: Option<bool>,
+    /// Save content blocks larger than this many bytes to a file (`0` never does)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_result_bytes: Option<u64>,
+    /// A Nushell closure that successful results are piped through for display
+    ///
+    /// It only gets the built-in commands that work on data; see `util::eval`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display: Option<String>,
+    /// How to follow the tool's result pages with `--all`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<PaginationConfig>,
+    /// Give only this many optional parameters a flag; the rest are passed
+    /// with `--rest` (`0` gives every parameter a flag)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_flags: Option<usize>,
+    /// Refuse calls to tools the server marks as deprecated, instead of
+    /// warning on the first one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deny_deprecated: Option<bool>,
+    /// Return a result that is a single empty or whitespace-only text block
+    /// as that text, instead of nothing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_empty: Option<bool>,
+}
+
+impl ToolSettings {
+    /// Fill in every unset field from `fallback`
+    #[must_use]
+    pub fn or(&self, fallback: &Self) -> Self {
+        // `format` and `split_lines` describe the same thing, so a layer that
+        // sets either one overrides both in the fallback
+        let result_format = if self.result_format().is_some() {
+            self
+        } else {
+            fallback
+        };
+
+        Self {
+            timeout: self.timeout.or(fallback.timeout),
+            stuck_after: self.stuck_after.or(fallback.stuck_after),
+            retry: self.retry.or(fallback.retry),
+            cached_ttl: self.cached_ttl.or(fallback.cached_ttl),
+            confirm: self.confirm.or(fallback.confirm),
+            disabled: self.disabled.or(fallback.disabled),
+            prompt_missing_args: self.prompt_missing_args.or(fallback.prompt_missing_args),
+            max_result_bytes: self.max_result_bytes.or(fallback.max_result_bytes),
+            display: self.display.clone().or_else(|| fallback.display.clone()),
+            pagination: self
+                .pagination
+                .clone()
+                .or_else(|| fallback.pagination.clone()),
+            max_flags: self.max_flags.or(fallback.max_flags),
+            deny_deprecated: self.deny_deprecated.or(fallback.deny_deprecated),
+            keep_empty: self.keep_empty.or(fallback.keep_empty),
+            split_lines: result_format.split_lines,
+            format: result_format.format,
+        }
+    }
+
+    /// The result format set by this layer, if any
+    fn result_format(&self) -> Option<ResultFormat> {
+        self.format.or_else(|| {
+            self.split_lines.map(|split| {
+                if split {
+                    ResultFormat::Lines
+                } else {
+                    ResultFormat::Text
+                }
+            })
+        })
+    }
+
+    /// Resolve the settings for a tool in precedence order: per-tool, then
+    /// per-server, then global, then built-in defaults.
+    #[must_use]
+    pub fn resolve(global: &Self, server: &Self, tool: Option<&Self>) -> EffectiveToolSettings {
+        let merged = tool
+            .map_or_else(|| server.clone(), |tool| tool.or(server))
+            .or(global);
+
+        EffectiveToolSettings {
+            timeout: merged.timeout.map_or(DEFAULT_TOOL_TIMEOUT, |d| d.0),
+            stuck_after: merged.stuck_after.map_or(DEFAULT_STUCK_AFTER, |d| d.0),
+            retry: merged.retry.unwrap_or(0),
+            cached_ttl: merged.cached_ttl.map(|d| d.0),
+            confirm: merged.confirm.unwrap_or(false),
+            disabled: merged.disabled.unwrap_or(false),
+            prompt_missing_args: merged.prompt_missing_args.unwrap_or(false),
+            max_result_bytes: merged.max_result_bytes.unwrap_or(DEFAULT_MAX_RESULT_BYTES),
+            format: merged.result_format().unwrap_or_default(),
+            display: merged.display,
+            pagination: merged.pagination,
+            max_flags: merged.max_flags.unwrap_or(DEFAULT_MAX_FLAGS),
+            deny_deprecated: merged.deny_deprecated.unwrap_or(false),
+            keep_empty: merged.keep_empty.unwrap_or(false),
+        }
+    }
+}
+
+/// The settings that apply to a single tool after all layers were resolved
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct EffectiveToolSettings {
+    /// A zero timeout means the call is never timed out
+    pub timeout: Duration,
+    pub stuck_after: Duration,
+    pub retry: u32,
+    pub cached_ttl: Option<Duration>,
+    pub confirm: bool,
+    pub disabled: bool,
+    pub prompt_missing_args: bool,
+    /// Zero means blocks are never saved to a file
+    pub max_result_bytes: u64,
+    pub format: ResultFormat,
+    /// The source of the closure results are piped through, if any
+    pub display: Option<String>,
+    pub pagination: Option<PaginationConfig>,
+    /// Zero means every optional parameter gets a flag
+    pub max_flags: usize,
+    pub deny_deprecated: bool,
+    pub keep_empty: bool,
+}
+
+impl Default for EffectiveToolSettings {
+    fn default() -> Self {
+        ToolSettings::resolve(&ToolSettings::default(), &ToolSettings::default(), None)
+    }
+}
+
+impl EffectiveToolSettings {
+    /// The same settings with a different result format, for per-call overrides
+    #[must_use]
+    pub fn with_format(&self, format: ResultFormat) -> Self {
+        Self {
+            format,
+            ..self.clone()
+        }
+    }
+
+    /// The same settings without a result size limit, for `--full`
+    #[must_use]
+    pub fn without_result_limit(&self) -> Self {
+        Self {
+            max_result_bytes: 0,
+            ..self.clone()
+        }
+    }
+
+    /// The same settings with a different call timeout, for `tool expect --timeout`
+    #[must_use]
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        Self {
+            timeout,
+            ..self.clone()
+        }
+    }
+
+    /// The same settings, keeping empty text results, for `--keep-empty`
+    #[must_use]
+    pub fn keeping_empty(&self) -> Self {
+        Self {
+            keep_empty: true,
+            ..self.clone()
+        }
+    }
+
+    /// The same settings without the result cache, for `tool raw`
+    #[must_use]
+    pub fn without_cache(&self) -> Self {
+        Self {
+            cached_ttl: None,
+            ..self.clone()
+        }
+    }
+
+    /// Convert the settings into a Nushell record for display
+    #[must_use]
+    pub fn to_value(&self, span: Span) -> Value {
+        let mut record = Record::new();
+        record.push("timeout", duration_value(self.timeout, span));
+        record.push("stuck_after", duration_value(self.stuck_after, span));
+        record.push("retry", Value::int(i64::from(self.retry), span));
+        record.push(
+            "cached_ttl",
+            self.cached_ttl
+                .map_or_else(|| Value::nothing(span), |ttl| duration_value(ttl, span)),
+        );
+        record.push("confirm", Value::bool(self.confirm, span));
+        record.push("disabled", Value::bool(self.disabled, span));
+        record.push(
+            "prompt_missing_args",
+            Value::bool(self.prompt_missing_args, span),
+        );
+        record.push(
+            "max_result_bytes",
+            Value::filesize(
+                i64::try_from(self.max_result_bytes).unwrap_or(i64::MAX),
+                span,
+            ),
+        );
+        record.push("format", Value::string(self.format.as_str(), span));
+        record.push(
+            "display",
+            self.display.as_ref().map_or_else(
+                || Value::nothing(span),
+                |source| Value::string(source, span),
+            ),
+        );
+        record.push(
+            "pagination",
+            self.pagination.as_ref().map_or_else(
+                || Value::nothing(span),
+                |pagination| pagination.to_value(span),
+            ),
+        );
+        record.push(
+            "max_flags",
+            Value::int(i64::try_from(self.max_flags).unwrap_or(i64::MAX), span),
+        );
+        record.push("deny_deprecated", Value::bool(self.deny_deprecated, span));
+        record.push("keep_empty", Value::bool(self.keep_empty, span));
+        Value::record(record, span)
+    }
+}
+
+fn duration_value(duration: Duration, span: Span) -> Value {
+    Value::duration(i64::try_from(duration.as_nanos()).unwrap_or(i64::MAX), span)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Option<ConfigDuration> {
+        Some(ConfigDuration(Duration::from_secs(secs)))
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        let global = ToolSettings {
+            timeout: secs(30),
+            retry: Some(1),
+            ..ToolSettings::default()
+        };
+        let server = ToolSettings {
+            timeout: secs(60),
+            confirm: Some(true),
+            ..ToolSettings::default()
+        };
+        let tool = ToolSettings {
+            timeout: secs(300),
+            ..ToolSettings::default()
+        };
+
+        let resolved = ToolSettings::resolve(&global, &server, Some(&tool));
+        assert_eq!(resolved.timeout, Duration::from_secs(300));
+        assert_eq!(resolved.retry, 1);
+        assert!(resolved.confirm);
+
+        let resolved = ToolSettings::resolve(&global, &server, None);
+        assert_eq!(resolved.timeout, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_resolve_defaults() {
+        let resolved = EffectiveToolSettings::default();
+        assert_eq!(resolved.timeout, DEFAULT_TOOL_TIMEOUT);
+        assert_eq!(resolved.stuck_after, DEFAULT_STUCK_AFTER);
+        assert_eq!(resolved.retry, 0);
+        assert_eq!(resolved.cached_ttl, None);
+        assert!(!resolved.confirm);
+        assert!(!resolved.disabled);
+        assert!(!resolved.prompt_missing_args);
+        assert_eq!(resolved.max_result_bytes, DEFAULT_MAX_RESULT_BYTES);
+        assert_eq!(resolved.format, ResultFormat::Text);
+        assert_eq!(resolved.display, None);
+        assert_eq!(resolved.pagination, None);
+        assert!(!resolved.deny_deprecated);
+        assert!(!resolved.keep_empty);
+    }
+
+    #[test]
+    fn test_resolve_result_format() {
+        let server = ToolSettings {
+            format: Some(ResultFormat::Ndjson),
+            ..ToolSettings::default()
+        };
+        let tool = ToolSettings {
+            split_lines: Some(true),
+            ..ToolSettings::default()
+        };
+
+        let resolved = ToolSettings::resolve(&ToolSettings::default(), &server, Some(&tool));
+        assert_eq!(resolved.format, ResultFormat::Lines);
+
+        let resolved = ToolSettings::resolve(&ToolSettings::default(), &server, None);
+        assert_eq!(resolved.format, ResultFormat::Ndjson);
+    }
+}
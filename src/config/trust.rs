@@ -0,0 +1,150 @@
+//! What a server may do without `trusted = true`
+//!
+//! Servers are untrusted by default. Every restriction on an untrusted
+//! server is decided here, so the connection, registration and listing code
+//! can't disagree about what untrusted means.
+
+use rmcp::model::Tool;
+use serde_json::Value as JsonValue;
+
+use super::{EffectiveToolSettings, InheritEnv};
+
+/// The restrictions that apply to one server
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TrustPolicy {
+    trusted: bool,
+}
+
+impl TrustPolicy {
+    /// The policy of a trusted server
+    #[cfg(test)]
+    pub const TRUSTED: Self = Self { trusted: true };
+
+    #[must_use]
+    pub const fn new(trusted: bool) -> Self {
+        Self { trusted }
+    }
+
+    #[must_use]
+    pub const fn is_trusted(self) -> bool {
+        self.trusted
+    }
+
+    /// Whether to answer the server's sampling and elicitation requests
+    ///
+    /// An untrusted server is refused whatever else is configured.
+    #[must_use]
+    pub const fn answers_client_requests(self) -> bool {
+        self.trusted
+    }
+
+    /// The environment a command server inherits
+    ///
+    /// An untrusted server only gets the variables explicitly listed in its
+    /// `inherit_env`; `"all"` and the default list are ignored.
+    #[must_use]
+    pub fn inherit_env(self, configured: Option<&InheritEnv>) -> InheritEnv {
+        match configured {
+            _ if self.trusted => configured.cloned().unwrap_or_default(),
+            Some(InheritEnv::Only(names)) => InheritEnv::Only(names.clone()),
+            _ => InheritEnv::None,
+        }
+    }
+
+    /// The settings a tool is called with
+    ///
+    /// Calls to a destructive tool on an untrusted server always ask for
+    /// confirmation. The confirmation is asked by
+    /// [`prepare_tool_call`](crate::commands::mcp_tools::prepare_tool_call),
+    /// the only way a command can call a tool.
+    #[must_use]
+    pub fn tool_settings(
+        self,
+        tool: &Tool,
+        settings: EffectiveToolSettings,
+    ) -> EffectiveToolSettings {
+        self.call_settings(is_destructive(tool), settings)
+    }
+
+    fn call_settings(
+        self,
+        destructive: bool,
+        settings: EffectiveToolSettings,
+    ) -> EffectiveToolSettings {
+        if self.trusted || !destructive {
+            return settings;
+        }
+
+        EffectiveToolSettings {
+            confirm: true,
+            ..settings
+        }
+    }
+}
+
+/// Whether the server annotated the tool with `destructiveHint: true`
+///
+/// Annotations aren't modelled by the MCP client, so they're read from the
+/// tool as the server sent it.
+#[must_use]
+pub fn is_destructive(tool: &Tool) -> bool {
+    serde_json::to_value(tool).is_ok_and(|json| annotated_destructive(&json))
+}
+
+fn annotated_destructive(tool: &JsonValue) -> bool {
+    tool.pointer("/annotations/destructiveHint")
+        .and_then(JsonValue::as_bool)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const UNTRUSTED: TrustPolicy = TrustPolicy::new(false);
+
+    #[test]
+    fn test_untrusted_refuses_client_requests() {
+        assert!(!UNTRUSTED.answers_client_requests());
+        assert!(TrustPolicy::TRUSTED.answers_client_requests());
+    }
+
+    #[test]
+    fn test_untrusted_confirms_destructive_tools() {
+        assert!(annotated_destructive(
+            &json!({"name": "drop", "annotations": {"destructiveHint": true}})
+        ));
+        assert!(!annotated_destructive(
+            &json!({"name": "read", "annotations": {"destructiveHint": false}})
+        ));
+        assert!(!annotated_destructive(&json!({"name": "read"})));
+
+        let settings = EffectiveToolSettings::default();
+        assert!(UNTRUSTED.call_settings(true, settings.clone()).confirm);
+        assert!(!UNTRUSTED.call_settings(false, settings.clone()).confirm);
+        assert!(!TrustPolicy::TRUSTED.call_settings(true, settings).confirm);
+    }
+
+    #[test]
+    fn test_untrusted_inherits_only_listed_env() {
+        let listed = InheritEnv::Only(vec!["PATH".into()]);
+
+        assert_eq!(UNTRUSTED.inherit_env(None), InheritEnv::None);
+        assert_eq!(
+            UNTRUSTED.inherit_env(Some(&InheritEnv::All)),
+            InheritEnv::None
+        );
+        assert_eq!(UNTRUSTED.inherit_env(Some(&listed)), listed);
+
+        assert_eq!(
+            TrustPolicy::TRUSTED.inherit_env(None),
+            InheritEnv::default()
+        );
+        assert_eq!(
+            TrustPolicy::TRUSTED.inherit_env(Some(&InheritEnv::All)),
+            InheritEnv::All
+        );
+    }
+}
@@ -1,17 +1,74 @@
+use std::{
+    any::Any,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::{LazyLock, mpsc},
+    time::{Duration, Instant},
+};
+
 use async_lock::{Mutex, MutexGuard};
 use async_once_cell::OnceCell;
-use nu_protocol::engine::EngineState;
+use futures::FutureExt;
+use nu_protocol::Signals;
 use tokio::runtime::Runtime;
 
-use crate::mcp_manager::McpClientManager;
+use crate::{mcp_manager::McpClientManager, util::sampling};
+
+static MCP_CLIENT_MANAGER_STORE: OnceCell<Mutex<McpClientManager>> = OnceCell::new();
+
+/// The runtime that all synchronous commands use to drive MCP futures
+static SHARED_RUNTIME: LazyLock<Runtime> = LazyLock::new(|| {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .thread_name("mcp-runtime")
+        .build()
+        .expect("failed to build the shared Tokio runtime")
+});
+
+/// How often a blocked command checks for Ctrl-C and the stuck threshold
+const BLOCK_ON_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The labels of the calls [`block_on_shared`] is waiting for, oldest first
+static RUNNING_CALLS: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+/// The most recent call that is still running, e.g. `server.tool`
+#[must_use]
+pub fn running_call() -> Option<String> {
+    RUNNING_CALLS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .last()
+        .cloned()
+}
 
-/// Extension trait for `EngineState` to add MCP client, manager, and runtime functionality
-pub trait EngineStateExt {
-    // New methods for client manager
-    async fn get_mcp_client_manager(&self) -> MutexGuard<'static, McpClientManager>;
+/// Keeps a call in [`RUNNING_CALLS`] until it is dropped
+///
+/// [`block_on_shared`] starts one for its watchdog's label; a call waited
+/// for without a watchdog can start its own.
+pub struct RunningCall(String);
+
+impl RunningCall {
+    /// Report `label` from [`running_call`] until this is dropped
+    #[must_use]
+    pub fn start(label: &str) -> Self {
+        RUNNING_CALLS
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .push(label.to_string());
+        Self(label.to_string())
+    }
 }
 
-static MCP_CLIENT_MANAGER_STORE: OnceCell<Mutex<McpClientManager>> = OnceCell::new();
+impl Drop for RunningCall {
+    fn drop(&mut self) {
+        let mut running = RUNNING_CALLS
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if let Some(index) = running.iter().rposition(|label| *label == self.0) {
+            running.remove(index);
+        }
+    }
+}
 
 pub async fn get_mcp_client_manager() -> MutexGuard<'static, McpClientManager> {
     MCP_CLIENT_MANAGER_STORE
@@ -22,13 +79,109 @@ pub async fn get_mcp_client_manager() -> MutexGuard<'static, McpClientManager> {
 }
 
 pub fn get_mcp_client_manager_sync() -> MutexGuard<'static, McpClientManager> {
-    let rt = Runtime::new().unwrap();
-    rt.block_on(get_mcp_client_manager())
+    shared_runtime().block_on(get_mcp_client_manager())
 }
 
-impl EngineStateExt for EngineState {
-    // Get the MCP client manager
-    async fn get_mcp_client_manager(&self) -> MutexGuard<'static, McpClientManager> {
-        get_mcp_client_manager().await
+/// Get the shared runtime
+///
+/// # Panics
+///
+/// Panics the first time it is called if the runtime can't be created.
+pub fn shared_runtime() -> &'static Runtime {
+    &SHARED_RUNTIME
+}
+
+/// Warns when a blocked call runs for longer than expected
+#[derive(Clone, Copy)]
+pub struct Watchdog<'a> {
+    /// What is running, e.g. `server.tool`
+    pub label: &'a str,
+    /// How long to wait before warning
+    pub stuck_after: Duration,
+}
+
+/// Why [`block_on_shared`] didn't produce the future's output
+#[derive(Debug)]
+pub enum BlockOnError {
+    /// The user pressed Ctrl-C; the future was aborted
+    Interrupted,
+    /// The future panicked with this message
+    Panicked(String),
+}
+
+/// Run a future on the shared runtime and wait for it from synchronous code
+///
+/// Unlike `Runtime::block_on`, this is safe to call from a thread that is
+/// already inside a Tokio context. While waiting, the engine's signals are
+/// polled so Ctrl-C aborts the future, and the watchdog prints a single
+/// warning once the future has been running past its threshold. A panic in
+/// the future is caught and returned with its message instead of hanging.
+/// Sampling requests that need the user's consent meanwhile are asked
+/// about from here; see [`sampling::Foreground`].
+/// Until it returns, the watchdog's label is what [`running_call`] reports.
+pub fn block_on_shared<F>(
+    future: F,
+    signals: &Signals,
+    watchdog: Option<Watchdog<'_>>,
+) -> Result<F::Output, BlockOnError>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+
+    let handle = shared_runtime().spawn(async move {
+        let output = AssertUnwindSafe(future)
+            .catch_unwind()
+            .await
+            .map_err(|payload| BlockOnError::Panicked(panic_message(payload.as_ref())));
+        let _ = sender.send(output);
+    });
+
+    let _running = watchdog
+        .as_ref()
+        .map(|watchdog| RunningCall::start(watchdog.label));
+    let started = Instant::now();
+    let mut warned = false;
+    let foreground = sampling::Foreground::enter();
+
+    loop {
+        match receiver.recv_timeout(BLOCK_ON_POLL_INTERVAL) {
+            Ok(output) => return output,
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err(BlockOnError::Panicked(
+                    "the task ended without producing a result".into(),
+                ));
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+        }
+
+        if signals.interrupted() {
+            handle.abort();
+            return Err(BlockOnError::Interrupted);
+        }
+
+        if let Some(foreground) = &foreground {
+            foreground.ask_questions();
+        }
+
+        if let Some(watchdog) = &watchdog {
+            if !warned && started.elapsed() >= watchdog.stuck_after {
+                warned = true;
+                crate::warning!(
+                    "'{}' has been running for {} and may be stuck; press Ctrl-C to cancel it",
+                    watchdog.label,
+                    humantime::format_duration(watchdog.stuck_after)
+                );
+            }
+        }
     }
 }
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| (*message).to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".into())
+}
@@ -1,11 +1,16 @@
 #![deny(missing_docs, unused)]
 //! MCP REPL for Nushell
-use std::env;
+use std::{
+    env,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 use ::config::{Map, Source, Value};
 use anyhow::{Context, Result};
-use clap::Parser;
-use config::{McpConnectionType, McpReplConfig, parse_env};
+use clap::{CommandFactory, Parser};
+use config::{McpConnectionType, McpReplConfig, init::InitArgs, parse_env};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
@@ -14,6 +19,7 @@ pub(crate) mod config;
 pub(crate) mod engine;
 pub(crate) mod mcp;
 pub(crate) mod mcp_manager;
+pub(crate) mod proxy;
 pub(crate) mod shell;
 pub(crate) mod util;
 
@@ -23,20 +29,77 @@ pub(crate) mod util;
     about = "Nushell-based REPL for MCP (Model Context Protocol)"
 )]
 pub(crate) struct CliArgs {
-    /// Enable verbose logging
+    /// Enable verbose logging, and show each tool call's request and response
     #[arg(short, long, env = "MCP_VERBOSE")]
     verbose: bool,
 
+    /// Write log messages to this file instead of stderr
+    #[arg(long, env = "MCP_LOG_FILE")]
+    log_file: Option<PathBuf>,
+
     /// Path to config file
     #[arg(short, long, env = "MCP_CONFIG")]
     config: Option<String>,
 
+    /// Register tools from cached schemas without contacting any server
+    #[arg(long, env = "MCP_OFFLINE")]
+    offline: bool,
+
+    /// Don't show progress while the servers connect, only a summary
+    #[arg(short, long, env = "MCP_QUIET")]
+    quiet: bool,
+
+    /// Load and validate the configuration, then exit without connecting
+    #[arg(long)]
+    check_config: bool,
+
+    /// Connect the servers of this profile along with the shared ones
+    #[arg(long, add = clap_complete::ArgValueCandidates::new(profile_names))]
+    profile: Option<String>,
+
+    /// Run these commands and exit instead of starting the REPL
+    #[arg(long)]
+    commands: Option<String>,
+
+    /// Run this command after the servers connect, after the config's
+    /// `startup_commands`; can be given several times
+    #[arg(long = "startup-cmd", value_name = "COMMAND")]
+    startup_cmd: Vec<String>,
+
+    /// With --commands, cancel the script and exit with code 124 after this long
+    #[arg(long, value_parser = humantime::parse_duration)]
+    max_runtime: Option<std::time::Duration>,
+
     #[command(subcommand)]
-    connection: Option<ConnectionType>,
+    command: Option<CliCommand>,
+}
+
+/// Subcommands of `nu-mcp-repl`
+#[derive(Clone, Debug, clap::Subcommand)]
+pub(crate) enum CliCommand {
+    /// Create a config file with a few common servers
+    Init(InitArgs),
+    /// Start a configured command server and pass its protocol through
+    /// stdin/stdout, logging every message (see --log-file)
+    Proxy {
+        /// The configured server to proxy
+        server: String,
+    },
+    /// Print a completion script for this command line (not the REPL)
+    ///
+    /// The script asks `nu-mcp-repl` for completions as you type, so values
+    /// such as profile names come from the current config.
+    #[command(hide = true)]
+    Completions {
+        /// The shell to generate completions for
+        shell: clap_complete::Shell,
+    },
+    #[command(flatten)]
+    Connection(ConnectionType),
 }
 
 /// Type of MCP connection to establish
-#[derive(Clone, Debug, Deserialize, Serialize, clap::Parser)]
+#[derive(Clone, Debug, Deserialize, Serialize, clap::Subcommand)]
 pub(crate) enum ConnectionType {
     /// SSE-based MCP server (HTTP Server-Sent Events)
     Sse { name: String, url: String },
@@ -58,7 +121,7 @@ fn to_value<'a>(value: &(impl Serialize + Deserialize<'a>)) -> Value {
 impl Source for CliArgs {
     fn collect(&self) -> ::std::result::Result<Map<String, Value>, ::config::ConfigError> {
         let mut servers: Map<String, Value> = ::config::Map::new();
-        if let Some(connection) = &self.connection {
+        if let Some(CliCommand::Connection(connection)) = &self.command {
             // first, create a `ServerConfig`
             match connection {
                 ConnectionType::Sse { name, url } => {
@@ -66,6 +129,8 @@ impl Source for CliArgs {
                         name.to_string(),
                         to_value(&McpConnectionType::Sse {
                             url: url.to_string(),
+                            path: None,
+                            connect_timeout: None,
                         }),
                     );
                 }
@@ -75,6 +140,7 @@ impl Source for CliArgs {
                         to_value(&McpConnectionType::Command {
                             command: command.to_string(),
                             env: env.clone(),
+                            connect_timeout: None,
                         }),
                     );
                 }
@@ -93,24 +159,149 @@ impl Source for CliArgs {
     }
 }
 
-fn main() -> Result<()> {
-    // Initialize logging with filter for prompt warnings
-    let default_level = if env::var("RUST_LOG").is_ok() {
-        "info"
-    } else {
-        "warn"
+/// The environment variable the completion script sets when it asks
+/// `nu-mcp-repl` for completions
+const COMPLETE_VAR: &str = "COMPLETE";
+
+/// Generate the completion script for the `nu-mcp-repl` command line
+///
+/// It's the same script `COMPLETE=<shell> nu-mcp-repl` prints; see
+/// `clap_complete::CompleteEnv`.
+fn completion_script(shell: clap_complete::Shell) -> Result<String> {
+    let completer = clap_complete::env::Shells::builtins()
+        .completer(&shell.to_string())
+        .with_context(|| format!("Completions aren't supported for {shell}"))?;
+    let mut script = Vec::new();
+    completer
+        .write_registration(
+            COMPLETE_VAR,
+            "nu-mcp-repl",
+            "nu-mcp-repl",
+            "nu-mcp-repl",
+            &mut script,
+        )
+        .context("Failed to generate the completion script")?;
+    Ok(String::from_utf8_lossy(&script).into_owned())
+}
+
+/// The profiles in the config, for completing `--profile`
+///
+/// A config that doesn't load completes nothing rather than failing.
+fn profile_names() -> Vec<clap_complete::CompletionCandidate> {
+    McpReplConfig::env(&CliArgs::default())
+        .map(|config| profile_candidates(&config))
+        .unwrap_or_default()
+}
+
+/// The names of the profiles in `config`, in the order they're written
+fn profile_candidates(config: &McpReplConfig) -> Vec<clap_complete::CompletionCandidate> {
+    config
+        .profiles
+        .keys()
+        .map(clap_complete::CompletionCandidate::new)
+        .collect()
+}
+
+/// Where log messages go, and their level when `RUST_LOG` doesn't set one
+#[derive(Debug, PartialEq, Eq)]
+struct LogSetup<'a> {
+    /// Stderr when unset
+    file: Option<&'a Path>,
+    default_level: log::LevelFilter,
+}
+
+/// Decide where to log from `--log-file`, `--verbose` and whether `RUST_LOG` is set
+///
+/// Stderr only gets warnings by default, so they don't bury the banner. A
+/// log file gets everything from info up, or from debug up with `--verbose`.
+fn log_setup(log_file: Option<&Path>, verbose: bool, rust_log: bool) -> LogSetup<'_> {
+    let default_level = match log_file {
+        Some(_) if verbose => log::LevelFilter::Debug,
+        Some(_) => log::LevelFilter::Info,
+        None if rust_log => log::LevelFilter::Info,
+        None => log::LevelFilter::Warn,
     };
 
-    env_logger::Builder::from_env(env_logger::Env::default().filter_or("RUST_LOG", default_level))
-        .filter_module("nu_cli::prompt_update", log::LevelFilter::Error)
-        .init();
+    LogSetup {
+        file: log_file,
+        default_level,
+    }
+}
+
+/// Initialize logging, with a filter for prompt warnings
+fn init_logging(args: &CliArgs) -> Result<()> {
+    let setup = log_setup(
+        args.log_file.as_deref(),
+        args.verbose,
+        env::var("RUST_LOG").is_ok(),
+    );
+
+    let mut builder = env_logger::Builder::from_env(
+        env_logger::Env::default().filter_or("RUST_LOG", setup.default_level.as_str()),
+    );
+    builder.filter_module("nu_cli::prompt_update", log::LevelFilter::Error);
+
+    if let Some(path) = setup.file {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open log file '{}'", path.display()))?;
+        builder
+            .target(env_logger::Target::Pipe(Box::new(file)))
+            .write_style(env_logger::WriteStyle::Never);
+    }
+
+    builder.init();
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    // Answer the completion script, which runs us with $COMPLETE set
+    clap_complete::CompleteEnv::with_factory(CliArgs::command)
+        .var(COMPLETE_VAR)
+        .complete();
 
     // Parse command line arguments
     let args = CliArgs::parse();
-    let config = McpReplConfig::env(&args).context("Failed to load configuration")?;
+    init_logging(&args)?;
+    util::payload_log::init(args.verbose, args.log_file.is_some());
+
+    // `init` creates the config file, so it runs before one is loaded
+    match &args.command {
+        Some(CliCommand::Init(init)) => return config::init::run(init),
+        Some(CliCommand::Completions { shell }) => {
+            std::io::stdout()
+                .write_all(completion_script(*shell)?.as_bytes())
+                .context("Failed to write the completion script")?;
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    // Flushes the remaining spans when the REPL exits
+    let _telemetry = util::telemetry::init().context("Failed to set up telemetry")?;
+
+    let mut config = McpReplConfig::env(&args).context("Failed to load configuration")?;
+    if let Some(profile) = &args.profile {
+        config.profile = Some(profile.clone());
+    }
 
     log::trace!("Args {args:#?}");
 
+    if args.check_config {
+        config.validate()?;
+        config.check_programs()?;
+        crate::success!("Configuration is valid ({} servers)", config.servers.len());
+        return Ok(());
+    }
+
+    // The proxy speaks MCP on stdout, so it never starts the REPL
+    if let Some(CliCommand::Proxy { server }) = &args.command {
+        let config = config.with_profile(config.profile.as_deref())?;
+        return proxy::run(&config, server);
+    }
+
     if args.verbose {
         log::info!("Starting MCP REPL in verbose mode");
     }
@@ -121,11 +312,37 @@ fn main() -> Result<()> {
 
     let rt = tokio::runtime::Runtime::new().context("Failed to create runtime")?;
 
-    rt.block_on(repl.register(&config))
+    rt.block_on(repl.register(&config, args.offline, args.quiet))
         .context("Failed to register MCP clients")?;
 
+    let startup = shell::StartupCommands {
+        commands: config
+            .startup_commands
+            .iter()
+            .chain(&args.startup_cmd)
+            .cloned()
+            .collect(),
+        strict: config.startup_strict,
+    };
+
+    // A script runs without the REPL, so --max-runtime only applies to it
+    if let Some(commands) = args.commands {
+        let max_runtime = args
+            .max_runtime
+            .or_else(|| config.max_runtime.map(|max_runtime| max_runtime.0));
+        let result = repl.run_commands(&startup, commands, max_runtime);
+        let _stragglers = util::tasks::shutdown(util::tasks::SHUTDOWN_GRACE);
+        return result;
+    }
+    if args.max_runtime.is_some() {
+        log::info!("--max-runtime only applies to --commands scripts; ignoring it");
+    }
+
     // Run the REPL and handle any errors
-    match repl.run() {
+    let result = repl.run(&startup);
+    // Nothing started in the background may outlive the session
+    let _stragglers = util::tasks::shutdown(util::tasks::SHUTDOWN_GRACE);
+    match result {
         Ok(()) => {
             log::debug!("MCP REPL session ended");
             Ok(())
@@ -136,3 +353,71 @@ fn main() -> Result<()> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::ValueEnum;
+
+    use super::*;
+
+    #[test]
+    fn test_log_setup() {
+        let file = Path::new("/tmp/mcp-repl.log");
+        let stderr = |default_level| LogSetup {
+            file: None,
+            default_level,
+        };
+        let to_file = |default_level| LogSetup {
+            file: Some(file),
+            default_level,
+        };
+
+        // Without a log file, stderr behaves as it always has
+        assert_eq!(
+            log_setup(None, false, false),
+            stderr(log::LevelFilter::Warn)
+        );
+        assert_eq!(log_setup(None, true, false), stderr(log::LevelFilter::Warn));
+        assert_eq!(log_setup(None, false, true), stderr(log::LevelFilter::Info));
+
+        assert_eq!(
+            log_setup(Some(file), false, false),
+            to_file(log::LevelFilter::Info)
+        );
+        assert_eq!(
+            log_setup(Some(file), true, false),
+            to_file(log::LevelFilter::Debug)
+        );
+    }
+
+    #[test]
+    fn test_completion_scripts() {
+        for shell in clap_complete::Shell::value_variants() {
+            let script = completion_script(*shell).unwrap();
+            assert!(
+                script.contains("nu-mcp-repl"),
+                "{shell} script lacks the binary"
+            );
+            assert!(
+                script.contains(COMPLETE_VAR),
+                "{shell} script doesn't ask for completions"
+            );
+        }
+    }
+
+    #[test]
+    fn test_profile_candidates() {
+        let mut config = McpReplConfig::default();
+        for name in ["work", "home"] {
+            config
+                .profiles
+                .insert(name.to_string(), crate::config::ProfileConfig::default());
+        }
+
+        let names: Vec<_> = profile_candidates(&config)
+            .iter()
+            .map(|candidate| candidate.get_value().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["work", "home"]);
+    }
+}
@@ -1,47 +1,396 @@
-use std::{borrow::Cow, sync::Arc};
+use std::{
+    borrow::Cow,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock, PoisonError, RwLock, RwLockWriteGuard},
+    time::{Duration, Instant},
+};
 
 use anyhow::{Context, Result, anyhow};
 use indexmap::IndexMap;
 use log::{debug, info, warn};
 use rmcp::{
-    RoleClient, ServiceExt,
-    model::{CallToolRequestParam, ClientInfo, Content, Resource, ResourceTemplate, Tool},
-    service::RunningService,
-    transport::TokioChildProcess,
+    ClientHandler, Peer, RoleClient, ServiceExt,
+    model::{
+        CallToolRequest, CallToolRequestMethod, CallToolRequestParam, CallToolResult,
+        CancelledNotificationParam, ClientInfo, ClientRequest, CreateMessageRequestMethod,
+        CreateMessageRequestParam, CreateMessageResult, GetPromptRequestParam, GetPromptResult,
+        JsonObject, LoggingMessageNotificationParam, ProgressNotificationParam, Prompt,
+        ProtocolVersion, ReadResourceRequestParam, ReadResourceResult, RequestId, Resource,
+        ResourceTemplate, ResourceUpdatedNotificationParam, ServerInfo, ServerResult,
+        SubscribeRequestParam, Tool, UnsubscribeRequestParam,
+    },
+    service::{PeerRequestOptions, RunningService, ServiceError},
 };
 use serde_json::Value;
-use tokio::process::Command;
+use tokio::process::{Child, Command};
+
+use crate::{
+    config::{DEFAULT_CONNECT_TIMEOUT, InheritEnv, McpConnectionType, SamplingPolicy, TrustPolicy},
+    util::{
+        events::EventLog,
+        payload_log,
+        program::check_program,
+        reconnect::{ReconnectGate, send_in_turn},
+        sampling::{self, SamplingOptions},
+        snapshot::SchemaSnapshot,
+        sse::CountingSseClient,
+        stats::ServerStats,
+        telemetry::{Operation, traced},
+        transport::{InitializeCapture, stdio_transport},
+    },
+};
+
+/// MCP protocol revisions this client knows how to speak
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2024-11-05", "2025-03-26"];
+
+/// Options that control how a connection is initialized
+#[derive(Clone, Debug)]
+pub struct ConnectOptions {
+    /// Request this protocol version instead of the latest one we support
+    pub protocol_version: Option<String>,
+    /// The server name notifications are recorded under
+    pub server_name: String,
+    /// Where the server's notifications are recorded
+    pub events: EventLog,
+    /// How long to wait for the connection and the `initialize` handshake
+    pub connect_timeout: Duration,
+    /// Which of our environment variables a command server receives
+    pub inherit_env: InheritEnv,
+    /// The directory a command server starts in, and that a relative
+    /// `command` is resolved against; the process's own directory if unset
+    pub cwd: Option<PathBuf>,
+    /// Whether the server's own requests, like sampling, are answered
+    pub trust: TrustPolicy,
+    /// Fields merged over the `initialize` request's parameters
+    pub initialize_options: Option<serde_json::Map<String, Value>>,
+    /// How many tool calls may wait for a restart in progress; zero fails
+    /// them straight away
+    pub reconnect_queue: usize,
+    /// Check that a command server's program exists before starting it
+    pub path_check: bool,
+    /// How the server's sampling requests are handled, if it's trusted
+    pub sampling: SamplingOptions,
+    /// The counters to carry on with, when restarting; new ones if unset
+    pub stats: Option<Arc<ServerStats>>,
+}
+
+impl Default for ConnectOptions {
+    fn default() -> Self {
+        Self {
+            protocol_version: None,
+            server_name: String::new(),
+            events: EventLog::default(),
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            inherit_env: InheritEnv::default(),
+            cwd: None,
+            trust: TrustPolicy::default(),
+            initialize_options: None,
+            reconnect_queue: 0,
+            path_check: true,
+            sampling: SamplingOptions::default(),
+            stats: None,
+        }
+    }
+}
+
+impl ConnectOptions {
+    /// Build the handler that sends the `initialize` parameters and records
+    /// the server's notifications
+    fn handler(&self) -> Result<NotificationRecorder> {
+        let mut client_info = ClientInfo::default();
+
+        if let Some(version) = &self.protocol_version {
+            client_info.protocol_version =
+                serde_json::from_value(Value::String(version.clone()))
+                    .with_context(|| format!("Invalid protocol_version '{version}'"))?;
+        }
+        if let Some(options) = &self.initialize_options {
+            client_info = with_initialize_options(&client_info, options)?;
+        }
 
-use crate::config::McpConnectionType;
+        Ok(NotificationRecorder {
+            server: self.server_name.clone(),
+            client_info,
+            events: self.events.clone(),
+            trust: self.trust,
+            sampling: self.sampling,
+        })
+    }
+}
+
+/// Client handler that records every server notification in the event log
+#[derive(Clone, Debug)]
+pub struct NotificationRecorder {
+    server: String,
+    client_info: ClientInfo,
+    events: EventLog,
+    trust: TrustPolicy,
+    sampling: SamplingOptions,
+}
+
+impl NotificationRecorder {
+    fn record(&self, method: &str, params: impl serde::Serialize) -> std::future::Ready<()> {
+        let params = serde_json::to_value(params).unwrap_or(Value::Null);
+        self.events.push(&self.server, method, &params);
+        std::future::ready(())
+    }
+}
+
+impl ClientHandler for NotificationRecorder {
+    fn on_cancelled(
+        &self,
+        params: CancelledNotificationParam,
+    ) -> impl Future<Output = ()> + Send + '_ {
+        self.record("notifications/cancelled", params)
+    }
+
+    fn on_progress(
+        &self,
+        params: ProgressNotificationParam,
+    ) -> impl Future<Output = ()> + Send + '_ {
+        self.record("notifications/progress", params)
+    }
+
+    fn on_logging_message(
+        &self,
+        params: LoggingMessageNotificationParam,
+    ) -> impl Future<Output = ()> + Send + '_ {
+        self.record("notifications/message", params)
+    }
+
+    fn on_resource_updated(
+        &self,
+        params: ResourceUpdatedNotificationParam,
+    ) -> impl Future<Output = ()> + Send + '_ {
+        self.record("notifications/resources/updated", params)
+    }
+
+    fn on_resource_list_changed(&self) -> impl Future<Output = ()> + Send + '_ {
+        self.record("notifications/resources/list_changed", ())
+    }
+
+    fn on_tool_list_changed(&self) -> impl Future<Output = ()> + Send + '_ {
+        self.record("notifications/tools/list_changed", ())
+    }
+
+    fn on_prompt_list_changed(&self) -> impl Future<Output = ()> + Send + '_ {
+        self.record("notifications/prompts/list_changed", ())
+    }
+
+    fn create_message(
+        &self,
+        params: CreateMessageRequestParam,
+    ) -> impl Future<Output = Result<CreateMessageResult, rmcp::Error>> + Send + '_ {
+        async move {
+            if !self.trust.answers_client_requests() {
+                warn!(
+                    "Refused a sampling request from untrusted server '{}'",
+                    self.server
+                );
+                return Err(rmcp::Error::invalid_request(
+                    format!(
+                        "server '{}' is not trusted; set `trusted = true` in its configuration to allow sampling",
+                        self.server
+                    ),
+                    None,
+                ));
+            }
+
+            if self.sampling.policy == SamplingPolicy::Prompt {
+                let request = serde_json::to_value(&params).unwrap_or(Value::Null);
+                sampling::ask_consent(&self.server, request, self.sampling.confirm_tokens)
+                    .await
+                    .map_err(|reason| rmcp::Error::invalid_request(reason, None))?;
+                info!(
+                    "Sampling request from '{}' allowed, but no model is configured to answer it",
+                    self.server
+                );
+            }
+
+            Err(rmcp::Error::method_not_found::<CreateMessageRequestMethod>())
+        }
+    }
+
+    fn get_peer(&self) -> Option<Peer<RoleClient>> {
+        None
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleClient>) {
+        drop(peer);
+    }
+
+    fn get_info(&self) -> ClientInfo {
+        self.client_info.clone()
+    }
+}
+
+/// A live connection to a server
+#[derive(Debug)]
+struct Connection {
+    service: RunningService<RoleClient, NotificationRecorder>,
+    /// The server process, for servers started from a command
+    process: Mutex<Option<Child>>,
+}
+
+impl Connection {
+    /// Close the connection and, for command servers, kill and reap the process
+    async fn shutdown(self: Arc<Self>) {
+        let process = self
+            .process
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+
+        // A request still in flight keeps the service alive; it ends when
+        // the process exits or the transport is dropped
+        match Arc::try_unwrap(self) {
+            Ok(connection) => {
+                if let Err(err) = connection.service.cancel().await {
+                    warn!("Failed to stop the MCP service: {err}");
+                }
+            }
+            Err(_) => debug!("MCP connection still in use while shutting down"),
+        }
+
+        if let Some(mut child) = process {
+            // `kill` waits for the process, so it's reaped when this returns
+            let running = matches!(child.try_wait(), Ok(None));
+            if running {
+                if let Err(err) = child.kill().await {
+                    warn!("Failed to kill the MCP server process: {err}");
+                }
+            }
+        }
+    }
+}
 
 /// Client for interacting with an MCP server
 #[derive(Clone, Debug)]
 pub struct McpClient {
-    client: Arc<RunningService<RoleClient, ClientInfo>>,
+    /// The live connection, shared by every clone so a restart reaches the
+    /// commands that captured this client. `None` when offline or after a
+    /// failed restart.
+    connection: Arc<RwLock<Option<Arc<Connection>>>>,
+    /// The name the server is configured under
+    server_name: String,
+    /// Whether the client was built from a snapshot
+    offline: bool,
+    server_info: ServerInfo,
+    /// The server's answer to `initialize` as it was sent, including the
+    /// fields `server_info` doesn't model
+    initialize_result: Value,
     tools: Vec<Tool>,
     _resources: Vec<Resource>,
     _templates: Vec<ResourceTemplate>,
+    /// Request counters, shared by every clone and kept across restarts
+    stats: Arc<ServerStats>,
+    /// The parameters of the `initialize` request we sent; `None` offline
+    initialize_params: Option<ClientInfo>,
+    /// Holds tool calls back during a restart, shared by every clone
+    reconnect: Arc<ReconnectGate>,
+    /// When the connection was closed by `disconnect` or lost in a failed
+    /// restart; shared by every clone, and cleared by a successful restart
+    disconnected_at: Arc<Mutex<Option<Instant>>>,
+    /// How a command server's process was started; `None` for SSE servers
+    /// and offline
+    launch: Option<Launch>,
     debug: bool,
 }
 
+/// How a command server's process was started, as `mcp env` shows it
+///
+/// This is what was computed from the config, the `inherit_env` policy and
+/// the REPL's environment, so it can differ from what the config says.
+#[derive(Clone, Debug)]
+pub struct Launch {
+    /// The program, resolved against `cwd` if relative, and its arguments,
+    /// including the `-e KEY=VALUE` pairs added for Docker
+    pub argv: Vec<String>,
+    pub cwd: PathBuf,
+    /// The variables the process was started with
+    pub env: IndexMap<String, String>,
+}
+
 impl McpClient {
     /// Create a new MCP client with the specified connection type (async version)
-    pub async fn connect(connection_type: McpConnectionType, debug: bool) -> Result<Self> {
+    pub async fn connect(
+        connection_type: McpConnectionType,
+        options: &ConnectOptions,
+        debug: bool,
+    ) -> Result<Self> {
+        let operation = Operation::Connect {
+            server: &options.server_name,
+        };
+        traced(
+            operation,
+            |_| "ok",
+            Self::connect_untraced(connection_type, options, debug),
+        )
+        .await
+    }
+
+    async fn connect_untraced(
+        connection_type: McpConnectionType,
+        options: &ConnectOptions,
+        debug: bool,
+    ) -> Result<Self> {
+        let started = Instant::now();
+        let handler = options.handler()?;
+        let requested = protocol_version_string(&handler.client_info.protocol_version);
+        let initialize_params = handler.client_info.clone();
+        // Created before connecting, since the SSE transport counts its
+        // reconnects in them
+        let stats = options.stats.clone().unwrap_or_default();
+
         // Initialize the MCP client based on the connection type
-        let client = match connection_type {
-            McpConnectionType::Sse { url } => {
+        let (client, process, launch, sent) = match connection_type {
+            McpConnectionType::Sse { url, .. } if url.is_empty() => Err(anyhow!(
+                "the server has no url; set url, or path together with a top-level base_url"
+            )),
+            McpConnectionType::Sse { url, .. } => {
                 info!("Connecting via SSE: {url}");
-                Self::build_sse_client(&url).await?
+                Self::build_sse_client(&url, handler, options, stats.clone())
+                    .await
+                    .map(|client| (client, None, None, None))
             }
-            McpConnectionType::Command { command, env } => {
+            McpConnectionType::Command { command, env, .. } => {
                 info!("Connecting via command: {command}");
-                Self::build_command_client(&command, &env.unwrap_or_default()).await?
+                Self::build_command_client(
+                    &command,
+                    &env.unwrap_or_default(),
+                    &options.inherit_env,
+                    options.cwd.as_deref(),
+                    handler,
+                    options.connect_timeout,
+                    options.path_check,
+                )
+                .await
+                .map(|(client, process, launch, sent)| (client, Some(process), Some(launch), sent))
             }
-        };
+        }
+        .map_err(|err| explain_version_mismatch(err, &requested))?;
 
         // Get server info and capabilities
-        let server_info = client.peer_info();
-        info!("Connected to server: {server_info:#?}");
+        let server_info = client.peer_info().clone();
+        debug!("Connected to server: {server_info:#?}");
+        // rmcp's SSE transport parses messages itself, so for SSE servers
+        // only what `ServerInfo` models is known
+        let initialize_result =
+            sent.unwrap_or_else(|| serde_json::to_value(&server_info).unwrap_or_default());
+
+        let negotiated = protocol_version_string(&server_info.protocol_version);
+        info!(
+            "Connected to '{}': {} {} (protocol {negotiated})",
+            options.server_name, server_info.server_info.name, server_info.server_info.version
+        );
+        if negotiated != requested && !SUPPORTED_PROTOCOL_VERSIONS.contains(&negotiated.as_str()) {
+            return Err(anyhow!(
+                "The server offered MCP protocol version {negotiated}, which is not supported \
+                 (supported: {}). Set `protocol_version` for this server to pin a version it accepts.",
+                SUPPORTED_PROTOCOL_VERSIONS.join(", ")
+            ));
+        }
 
         let server_capabilities = &server_info.capabilities;
         let has_tools = server_capabilities.tools.as_ref().is_some();
@@ -97,43 +446,297 @@ impl McpClient {
             Vec::new()
         };
 
+        stats.connected(started.elapsed());
+        let reconnect = ReconnectGate::default();
+        reconnect.set_limit(options.reconnect_queue);
+
         // Create the client instance with the loaded data
+        let connection = Connection {
+            service: client,
+            process: Mutex::new(process),
+        };
+
         Ok(Self {
-            client: Arc::new(client),
+            connection: Arc::new(RwLock::new(Some(Arc::new(connection)))),
+            server_name: options.server_name.clone(),
+            offline: false,
+            server_info,
+            initialize_result,
             tools,                 // Store the tools we loaded
             _resources: resources, // Store the resources we loaded
             _templates: templates, // Store the templates we loaded
+            stats,
+            initialize_params: Some(initialize_params),
+            reconnect: Arc::new(reconnect),
+            disconnected_at: Arc::default(),
+            launch,
             debug,
         })
     }
 
+    /// Create a client from a schema snapshot without connecting to the server
+    ///
+    /// The client knows the server's tools, resources and templates, but every
+    /// request fails with an offline mode error.
+    #[must_use]
+    pub fn offline(server_name: &str, snapshot: SchemaSnapshot, debug: bool) -> Self {
+        Self {
+            connection: Arc::new(RwLock::new(None)),
+            server_name: server_name.to_string(),
+            offline: true,
+            initialize_result: serde_json::to_value(&snapshot.server_info).unwrap_or_default(),
+            server_info: snapshot.server_info,
+            tools: snapshot.tools,
+            _resources: snapshot.resources,
+            _templates: snapshot.templates,
+            stats: Arc::default(),
+            initialize_params: None,
+            reconnect: Arc::default(),
+            disconnected_at: Arc::default(),
+            launch: None,
+            debug,
+        }
+    }
+
+    /// How the server's process was started, for command servers
+    #[must_use]
+    pub const fn launch(&self) -> Option<&Launch> {
+        self.launch.as_ref()
+    }
+
+    /// Whether this client was built from a snapshot instead of a connection
+    #[must_use]
+    pub const fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Stop the server and connect to it again
+    ///
+    /// The old connection is closed, and a command server's process reaped,
+    /// before the new one starts. Every clone of this client, including the
+    /// ones captured by registered commands, uses the new connection. The
+    /// returned client also carries what the server reported this time.
+    /// If connecting fails, the clients are left without a connection.
+    pub async fn restart(
+        &self,
+        connection_type: McpConnectionType,
+        options: &ConnectOptions,
+    ) -> Result<Self> {
+        // Tool calls made until the new connection is up wait for it, if the
+        // server queues them
+        self.reconnect.set_limit(options.reconnect_queue);
+        self.reconnect.begin().await;
+
+        let old = self.connection_slot().take();
+        if let Some(old) = old {
+            old.shutdown().await;
+        }
+
+        let options = ConnectOptions {
+            stats: Some(self.stats.clone()),
+            ..options.clone()
+        };
+        let mut client = match Self::connect(connection_type, &options, self.debug).await {
+            Ok(client) => client,
+            Err(err) => {
+                self.mark_disconnected();
+                self.stats.record_failure(format!("{err:#}"));
+                self.reconnect.finish(Err(format!("{err:#}")));
+                return Err(err);
+            }
+        };
+
+        let connection = client.connection_slot().take();
+        *self.connection_slot() = connection;
+        client.connection = self.connection.clone();
+
+        // The counters carried on across the restart; the uptime starts over
+        // from the new connection
+
+        client.reconnect = self.reconnect.clone();
+        client.reconnect.finish(Ok(()));
+
+        *self.disconnected() = None;
+        client.disconnected_at = self.disconnected_at.clone();
+
+        Ok(client)
+    }
+
+    /// Close the connection and, for command servers, stop the process
+    ///
+    /// Every clone of this client is left without a connection.
+    pub async fn disconnect(&self) {
+        self.mark_disconnected();
+        let connection = self.connection_slot().take();
+        if let Some(connection) = connection {
+            connection.shutdown().await;
+        }
+    }
+
+    /// How long ago the connection was closed, if it was
+    ///
+    /// Only this client's own lock is taken, so checking before a call
+    /// doesn't hold up calls to other servers.
+    #[must_use]
+    pub fn disconnected_for(&self) -> Option<Duration> {
+        self.disconnected().map(|at| at.elapsed())
+    }
+
+    /// Note that the connection is gone, if that wasn't noted already
+    pub fn mark_disconnected(&self) {
+        self.disconnected().get_or_insert_with(Instant::now);
+    }
+
+    fn disconnected(&self) -> std::sync::MutexGuard<'_, Option<Instant>> {
+        self.disconnected_at
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn connection_slot(&self) -> RwLockWriteGuard<'_, Option<Arc<Connection>>> {
+        self.connection
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Capture what the server reported so it can be used in offline mode
+    #[must_use]
+    pub fn snapshot(&self) -> SchemaSnapshot {
+        SchemaSnapshot {
+            server_info: self.server_info.clone(),
+            tools: self.tools.clone(),
+            resources: self.get_resources().to_vec(),
+            templates: self.get_templates().to_vec(),
+        }
+    }
+
+    /// The live connection, or an error explaining why requests can't be sent
+    fn service(&self) -> Result<Arc<Connection>> {
+        if self.offline {
+            return Err(anyhow!(
+                "offline mode: request not sent (restart without --offline to connect)"
+            ));
+        }
+
+        self.connection
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+            .ok_or_else(|| {
+                anyhow!("the server is not connected (run `mcp restart` to connect it again)")
+            })
+    }
+
     /// Build an SSE-based MCP client
-    async fn build_sse_client(url: &str) -> Result<RunningService<RoleClient, ClientInfo>> {
-        let transport = rmcp::transport::SseTransport::start(url)
-            .await
-            .context("Failed to start SSE transport")?;
+    ///
+    /// Reconnects of the event stream are counted in `stats`; see
+    /// [`CountingSseClient`].
+    async fn build_sse_client(
+        url: &str,
+        handler: NotificationRecorder,
+        options: &ConnectOptions,
+        stats: Arc<ServerStats>,
+    ) -> Result<RunningService<RoleClient, NotificationRecorder>> {
+        let connect_timeout = options.connect_timeout;
+        // Both phases share one deadline, so the whole connection is bounded
+        let deadline = tokio::time::Instant::now() + connect_timeout;
 
-        let client_info = rmcp::model::ClientInfo::default();
-        let client = client_info
-            .serve(transport)
+        let sse_client = CountingSseClient::new(url, &options.server_name, stats)?;
+        let transport = tokio::time::timeout_at(
+            deadline,
+            rmcp::transport::SseTransport::start_with_client(sse_client),
+        )
+        .await
+        .map_err(|_| connect_timed_out("the SSE connection", connect_timeout))?
+        .context("Failed to start SSE transport")?;
+
+        let client = tokio::time::timeout_at(deadline, handler.serve(transport))
             .await
+            .map_err(|_| connect_timed_out("MCP initialize", connect_timeout))?
             .context("Failed to initialize SSE client")?;
 
         Ok(client)
     }
 
     /// Build a command-based MCP client that launches a subprocess
+    ///
+    /// Also returns the `result` of the server's answer to `initialize`, as it
+    /// was sent.
     async fn build_command_client(
         cmd: &str,
         env: &IndexMap<String, String>,
-    ) -> Result<RunningService<RoleClient, ClientInfo>> {
+        inherit: &InheritEnv,
+        cwd: Option<&Path>,
+        handler: NotificationRecorder,
+        connect_timeout: Duration,
+        path_check: bool,
+    ) -> Result<(
+        RunningService<RoleClient, NotificationRecorder>,
+        Child,
+        Launch,
+        Option<Value>,
+    )> {
+        let (mut process, launch) = Self::spawn_command(cmd, env, inherit, cwd, path_check)?;
+        let stdout = process.stdout.take().context("The process has no stdout")?;
+        let stdin = process.stdin.take().context("The process has no stdin")?;
+
+        info!(
+            "Waiting up to {} for connection to initialize...",
+            humantime::format_duration(connect_timeout)
+        );
+
+        let initialize = InitializeCapture::default();
+        let transport = stdio_transport(stdout, stdin, &handler.server, initialize.clone());
+        let client = tokio::time::timeout(connect_timeout, handler.serve(transport))
+            .await
+            .map_err(|_| connect_timed_out("MCP initialize", connect_timeout))?
+            .context("Failed to initialize command client")?;
+
+        Ok((client, process, launch, initialize.take()))
+    }
+
+    /// Start a command server's process, with its stdin, stdout and stderr
+    /// piped
+    ///
+    /// The process gets the environment the `inherit` policy allows plus
+    /// `env`, and is killed when the returned `Child` is dropped.
+    pub fn spawn_command(
+        cmd: &str,
+        env: &IndexMap<String, String>,
+        inherit: &InheritEnv,
+        cwd: Option<&Path>,
+        path_check: bool,
+    ) -> Result<(Child, Launch)> {
         let mut cmd_args = shell_words::split(cmd).context("Failed to parse command")?;
 
         // Save the command for logging before we consume parts of it
         let all_args = cmd_args.clone(); // Clone before we mutate
 
         let program = cmd_args.remove(0);
-        let mut command = Command::new(&program);
+
+        // A missing program would otherwise only fail the handshake, once
+        // `connect_timeout` has passed
+        if path_check {
+            let cwd = cwd.map_or_else(std::env::current_dir, |cwd| Ok(cwd.to_path_buf()))?;
+            check_program(&program, &cwd, program_path(env).as_deref())?;
+        }
+        let mut command = match cwd {
+            Some(cwd) => {
+                let mut command = Command::new(resolve_program(&program, cwd));
+                command.current_dir(cwd);
+                command
+            }
+            None => Command::new(&program),
+        };
+
+        // Only the variables the policy allows are inherited; explicit `env`
+        // entries are added on top below
+        let mut launch_env = inherit_env(&mut command, inherit);
+        debug!(
+            "Environment for {program}: inherited [{}], set [{}]",
+            launch_env.keys().cloned().collect::<Vec<_>>().join(", "),
+            env.keys().cloned().collect::<Vec<_>>().join(", ")
+        );
 
         // Check if this is a Docker command - Docker needs special handling for interactive mode
         let is_docker = program.contains("docker")
@@ -143,11 +746,9 @@ impl McpClient {
 
         // For Docker commands, we need to pass env vars as -e KEY=VALUE arguments
         // For non-Docker commands, we use the standard envs method
-        if is_docker {
-            // Find Docker 'run' command position (if it exists)
-            let run_pos = cmd_args.iter().position(|arg| arg == "run");
-
-            if let Some(pos) = run_pos {
+        let run_pos = cmd_args.iter().position(|arg| arg == "run");
+        let args = match run_pos {
+            Some(pos) if is_docker => {
                 // Add environment variables as Docker arguments after the 'run' command
                 let mut docker_args = Vec::new();
                 docker_args.extend_from_slice(&cmd_args[..=pos]);
@@ -163,18 +764,34 @@ impl McpClient {
                     docker_args.extend_from_slice(&cmd_args[pos + 1..]);
                 }
 
-                command.args(docker_args);
-            } else {
-                // No 'run' command found, just pass arguments as is
-                command.args(&cmd_args);
-                // Still set environment variables on the process
+                docker_args
+            }
+            // No 'run' command found, or not Docker: pass arguments as is and
+            // set the environment variables on the process
+            _ => {
                 command.envs(env);
+                launch_env.extend(env.clone());
+                cmd_args
             }
-        } else {
-            // Non-Docker command, use standard args and envs
-            command.args(&cmd_args);
-            command.envs(env);
-        }
+        };
+        command.args(&args);
+
+        let launch = Launch {
+            argv: std::iter::once(
+                command
+                    .as_std()
+                    .get_program()
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+            .chain(args)
+            .collect(),
+            cwd: match cwd {
+                Some(cwd) => cwd.to_path_buf(),
+                None => std::env::current_dir()?,
+            },
+            env: launch_env,
+        };
 
         // Set up stdio with special considerations for Docker
         if is_docker {
@@ -184,8 +801,6 @@ impl McpClient {
             command.stdout(std::process::Stdio::piped());
             // Allow stderr to be inherited so we can see Docker's output
             command.stderr(std::process::Stdio::inherit());
-            // Don't kill the process when the parent process exits
-            command.kill_on_drop(false);
         } else {
             // Standard configuration for non-Docker commands
             command.stdin(std::process::Stdio::piped());
@@ -197,31 +812,50 @@ impl McpClient {
         info!("Starting command: {}", shell_words::join(all_args));
         debug!("Command details: {command:#?}");
 
-        let process =
-            TokioChildProcess::new(&mut command).context("Failed to start command process")?;
+        // Spawning is synchronous, so it fails right away rather than timing out
+        let process = command
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to start command process (process spawn)")?;
 
-        let client_info = rmcp::model::ClientInfo::default();
+        Ok((process, launch))
+    }
 
-        // Longer timeout for Docker commands
-        let timeout_duration = if is_docker {
-            tokio::time::Duration::from_secs(60) // Docker might need more time to pull images
-        } else {
-            tokio::time::Duration::from_secs(20)
-        };
+    /// Get the result of the `initialize` handshake
+    #[must_use]
+    pub const fn server_info(&self) -> &ServerInfo {
+        &self.server_info
+    }
 
-        info!(
-            "Waiting up to {} seconds for connection to initialize...",
-            timeout_duration.as_secs()
-        );
+    /// Get the server's answer to `initialize` as JSON, with every field it
+    /// sent
+    ///
+    /// Offline, and for SSE servers, this is `server_info` serialized, so it
+    /// only has the fields rmcp models.
+    #[must_use]
+    pub const fn initialize_result(&self) -> &Value {
+        &self.initialize_result
+    }
 
-        // Add a timeout for the connection
-        let timeout = tokio::time::timeout(timeout_duration, client_info.serve(process))
-            .await
-            .context("Connection timed out")?;
+    /// Get the usage instructions the server sent during initialization
+    #[must_use]
+    pub fn instructions(&self) -> Option<&str> {
+        self.server_info
+            .instructions
+            .as_deref()
+            .filter(|instructions| !instructions.trim().is_empty())
+    }
 
-        let client = timeout.context("Failed to initialize command client")?;
+    /// Get the protocol version negotiated with the server
+    #[must_use]
+    pub fn protocol_version(&self) -> String {
+        protocol_version_string(&self.server_info.protocol_version)
+    }
 
-        Ok(client)
+    /// The parameters of the `initialize` request sent to the server
+    #[must_use]
+    pub const fn initialize_params(&self) -> Option<&ClientInfo> {
+        self.initialize_params.as_ref()
     }
 
     /// Get all available MCP tools
@@ -232,13 +866,161 @@ impl McpClient {
 
     /// Get all available MCP resources
     #[must_use]
-    #[allow(clippy::used_underscore_binding, dead_code)]
+    #[allow(clippy::used_underscore_binding)]
     pub fn get_resources(&self) -> &[Resource] {
         &self._resources
     }
 
+    /// Get all available MCP resource templates
+    #[must_use]
+    #[allow(clippy::used_underscore_binding)]
+    pub fn get_templates(&self) -> &[ResourceTemplate] {
+        &self._templates
+    }
+
+    /// Fetch one page of the server's resources, bypassing the list loaded at connect
+    ///
+    /// Returns the resources along with the cursor for the next page, which is
+    /// `None` on the last page.
+    pub async fn list_resources_page(
+        &self,
+        cursor: Option<String>,
+    ) -> Result<(Vec<Resource>, Option<String>)> {
+        let result = self
+            .service()?
+            .service
+            .list_resources(Some(PaginatedRequestParamInner { cursor }))
+            .await
+            .context("Failed to list resources")?;
+
+        Ok((result.resources, result.next_cursor))
+    }
+
+    /// Read a resource by URI
+    pub async fn read_resource(&self, uri: &str) -> Result<ReadResourceResult> {
+        if self.debug {
+            info!("MCP READ RESOURCE: {uri}");
+        }
+
+        self.stats.record_request();
+        let read = async {
+            self.service()?
+                .service
+                .read_resource(ReadResourceRequestParam {
+                    uri: uri.to_string(),
+                })
+                .await
+                .with_context(|| format!("Failed to read resource {uri}"))
+        };
+
+        match read.await {
+            Ok(result) => {
+                self.stats.record_received(content_length(&result.contents));
+                Ok(result)
+            }
+            Err(err) => {
+                self.stats.record_failure(format!("{err:#}"));
+                Err(err)
+            }
+        }
+    }
+
+    /// List every prompt the server offers, following its pages
+    pub async fn list_prompts(&self) -> Result<Vec<Prompt>> {
+        self.service()?
+            .service
+            .list_all_prompts()
+            .await
+            .context("Failed to list prompts")
+    }
+
+    /// Get a prompt's messages with `arguments` filled in
+    pub async fn get_prompt(&self, name: &str, arguments: JsonObject) -> Result<GetPromptResult> {
+        if self.debug {
+            info!("MCP GET PROMPT: {name}");
+        }
+
+        self.stats.record_request();
+        let get = async {
+            self.service()?
+                .service
+                .get_prompt(GetPromptRequestParam {
+                    name: name.to_string(),
+                    arguments: Some(arguments),
+                })
+                .await
+                .with_context(|| format!("Failed to get prompt {name}"))
+        };
+
+        match get.await {
+            Ok(result) => {
+                self.stats.record_received(content_length(&result.messages));
+                Ok(result)
+            }
+            Err(err) => {
+                self.stats.record_failure(format!("{err:#}"));
+                Err(err)
+            }
+        }
+    }
+
+    /// Whether the server offers prompts
+    #[must_use]
+    pub const fn supports_prompts(&self) -> bool {
+        self.server_info().capabilities.prompts.is_some()
+    }
+
+    /// Ask the server to send `notifications/resources/updated` when a resource changes
+    pub async fn subscribe_resource(&self, uri: &str) -> Result<()> {
+        self.service()?
+            .service
+            .subscribe(SubscribeRequestParam {
+                uri: uri.to_string(),
+            })
+            .await
+            .with_context(|| format!("Failed to subscribe to {uri}"))
+    }
+
+    /// Stop the notifications started by [`Self::subscribe_resource`]
+    pub async fn unsubscribe_resource(&self, uri: &str) -> Result<()> {
+        self.service()?
+            .service
+            .unsubscribe(UnsubscribeRequestParam {
+                uri: uri.to_string(),
+            })
+            .await
+            .with_context(|| format!("Failed to unsubscribe from {uri}"))
+    }
+
+    /// Whether the server supports resource subscriptions
+    #[must_use]
+    pub fn supports_subscriptions(&self) -> bool {
+        self.server_info()
+            .capabilities
+            .resources
+            .as_ref()
+            .and_then(|resources| resources.subscribe)
+            .unwrap_or(false)
+    }
+
+    /// The request counters of this server
+    #[must_use]
+    pub fn stats(&self) -> &Arc<ServerStats> {
+        &self.stats
+    }
+
     /// Call an MCP tool with the provided parameters
-    pub async fn call_tool(&self, tool_name: &str, params: Value) -> Result<Vec<Content>> {
+    ///
+    /// The result is returned as-is, including results the server flagged
+    /// with `isError`; callers decide how to treat those. Once the request
+    /// is sent, `request_id` holds the JSON-RPC id it went out with, so a
+    /// failure or timeout can name the request the server saw.
+    pub async fn call_tool(
+        &self,
+        tool_name: &str,
+        params: Value,
+        request_id: &OnceLock<RequestId>,
+    ) -> Result<CallToolResult> {
         // Find the tool by name
         let _tool = self
             .tools
@@ -246,33 +1028,398 @@ impl McpClient {
             .find(|t| t.name == tool_name)
             .ok_or_else(|| anyhow!("Tool not found: {}", tool_name))?;
 
-        // Log the request if debug is enabled
-        if self.debug {
-            // Use Nushell formatting for the request parameters
-            let nu_formatted = crate::util::format::format_json_as_nu(&params, None);
+        // Call the tool with the parameters
+        let operation = Operation::ToolCall {
+            server: &self.server_name,
+            tool: tool_name,
+        };
+        let call = async {
+            // During a restart the call may wait for the new connection; the
+            // wait counts against the call's timeout
+            let turn = self.reconnect.wait(&self.server_name, tool_name).await?;
+            let connection = self.service()?;
+            let request = async {
+                let handle = connection
+                    .service
+                    .send_cancellable_request(
+                        ClientRequest::CallToolRequest(CallToolRequest {
+                            method: CallToolRequestMethod,
+                            params: CallToolRequestParam {
+                                name: Cow::Owned(tool_name.to_string()),
+                                arguments: params.as_object().cloned(),
+                            },
+                        }),
+                        PeerRequestOptions::no_options(),
+                    )
+                    .await?;
+                let _ = request_id.set(handle.id.clone());
 
-            info!("MCP REQUEST to '{tool_name}':\n{nu_formatted}");
+                // Show the request in verbose mode
+                if self.debug || payload_log::is_verbose() {
+                    payload_log::show(
+                        &format!("MCP REQUEST #{} to '{tool_name}'", handle.id),
+                        &params,
+                    );
+                } else {
+                    debug!("MCP REQUEST #{} to '{tool_name}'", handle.id);
+                }
+
+                match handle.await_response().await? {
+                    ServerResult::CallToolResult(result) => Ok(result),
+                    _ => Err(ServiceError::UnexpectedResponse),
+                }
+            };
+            send_in_turn(turn, request)
+                .await
+                .context("Failed to call tool")
+        };
+        self.stats.record_request();
+        let result = traced(
+            operation,
+            |result: &CallToolResult| {
+                if result.is_error == Some(true) {
+                    "tool_error"
+                } else {
+                    "ok"
+                }
+            },
+            call,
+        )
+        .await
+        .inspect_err(|err| self.stats.record_failure(format!("{err:#}")))?;
+
+        self.stats.record_received(content_length(&result.content));
+        if result.is_error == Some(true) {
+            self.stats
+                .record_failure(format!("'{tool_name}' returned an error"));
         }
 
-        // Call the tool with the parameters
-        let result = self
-            .client
-            .call_tool(CallToolRequestParam {
-                name: Cow::Owned(tool_name.to_string()),
-                arguments: params.as_object().cloned(),
-            })
-            .await
-            .context("Failed to call tool")?;
+        // Show the response in verbose mode, shortened if it's long
+        if self.debug || payload_log::is_verbose() {
+            payload_log::show(
+                &format!(
+                    "MCP RESPONSE #{} from '{tool_name}'",
+                    request_id
+                        .get()
+                        .map_or_else(String::new, ToString::to_string)
+                ),
+                &serde_json::to_value(&result).unwrap_or_default(),
+            );
+        }
 
-        // Log the response if debug is enabled
-        if self.debug {
-            // Use Nushell formatting for the response
-            let response_value = serde_json::to_value(&result).unwrap_or_default();
-            let nu_formatted = crate::util::format::format_json_as_nu(&response_value, None);
+        Ok(result)
+    }
+}
+
+/// Roughly how many bytes of content a response carried, as serialized
+fn content_length(content: &impl serde::Serialize) -> usize {
+    serde_json::to_vec(content).map_or(0, |bytes| bytes.len())
+}
+
+/// Resolve a program path that contains a directory against `cwd`
+///
+/// Bare names are left alone so they are still looked up on `PATH`. Where a
+/// relative path is resolved when `current_dir` is also set differs between
+/// platforms, so it is made absolute here.
+fn resolve_program(program: &str, cwd: &Path) -> PathBuf {
+    let path = Path::new(program);
+
+    if path.is_relative() && path.components().count() > 1 {
+        cwd.join(path)
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// The `PATH` a command server's program is looked up on: its own `env`
+/// entry if it has one, or ours
+pub fn program_path(env: &IndexMap<String, String>) -> Option<OsString> {
+    env.get("PATH")
+        .map(OsString::from)
+        .or_else(|| std::env::var_os("PATH"))
+}
+
+/// Clear the command's environment and pass only the variables the policy allows
+///
+/// Returns the names of the inherited variables, for logging.
+fn inherit_env(command: &mut Command, policy: &InheritEnv) -> IndexMap<String, String> {
+    let inherited = std::env::vars_os()
+        .filter_map(|(name, value)| {
+            let name = name.into_string().ok()?;
+            policy.inherits(&name).then_some((name, value))
+        })
+        .collect::<Vec<_>>();
+
+    if *policy != InheritEnv::All {
+        command.env_clear();
+        command.envs(inherited.iter().map(|(name, value)| (name, value)));
+    }
+
+    inherited
+        .into_iter()
+        .map(|(name, value)| (name, value.to_string_lossy().into_owned()))
+        .collect()
+}
+
+/// The error for a connection phase that took longer than `connect_timeout`
+fn connect_timed_out(phase: &str, connect_timeout: Duration) -> anyhow::Error {
+    anyhow!(
+        "Timed out during {phase}: the connect_timeout of {} ran out. \
+         Set `connect_timeout` for this server, or globally, to wait longer.",
+        humantime::format_duration(connect_timeout)
+    )
+}
+
+/// Render a protocol version as the string sent on the wire
+/// Merge a server's `initialize_options` over the parameters we'd send
+///
+/// Objects are merged key by key, anything else is replaced. Keys the
+/// `initialize` request has no place for can't be sent, and are logged.
+fn with_initialize_options(
+    client_info: &ClientInfo,
+    options: &serde_json::Map<String, Value>,
+) -> Result<ClientInfo> {
+    let mut params = serde_json::to_value(client_info)?;
+    merge_json(&mut params, &Value::Object(options.clone()));
+
+    let client_info: ClientInfo = serde_json::from_value(params)
+        .context("initialize_options doesn't fit the parameters of the initialize request")?;
+
+    let sent = serde_json::to_value(&client_info)?;
+    for key in missing_keys(&sent, options, "initialize_options") {
+        warn!("{key} has no place in the initialize request and was not sent");
+    }
+
+    Ok(client_info)
+}
+
+fn merge_json(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                merge_json(base.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+/// The paths of the keys in `wanted` that `sent` lacks
+fn missing_keys(sent: &Value, wanted: &serde_json::Map<String, Value>, path: &str) -> Vec<String> {
+    wanted
+        .iter()
+        .flat_map(|(key, value)| {
+            let path = format!("{path}.{key}");
+            match (sent.get(key), value) {
+                (None, _) => vec![path],
+                (Some(sent), Value::Object(wanted)) => missing_keys(sent, wanted, &path),
+                (Some(_), _) => Vec::new(),
+            }
+        })
+        .collect()
+}
+
+fn protocol_version_string(version: &ProtocolVersion) -> String {
+    match serde_json::to_value(version) {
+        Ok(Value::String(version)) => version,
+        _ => String::from("unknown"),
+    }
+}
 
-            info!("MCP RESPONSE from '{tool_name}':\n{nu_formatted}");
+/// Replace an opaque initialization failure with a clearer message when the
+/// server appears to have rejected the protocol version we asked for
+fn explain_version_mismatch(err: anyhow::Error, requested: &str) -> anyhow::Error {
+    let message = format!("{err:#}").to_lowercase();
+
+    if message.contains("protocol") && message.contains("version") {
+        anyhow!(
+            "The server rejected MCP protocol version {requested} (supported: {}): {err:#}. \
+             Set `protocol_version` for this server to request a version it accepts.",
+            SUPPORTED_PROTOCOL_VERSIONS.join(", ")
+        )
+    } else {
+        err
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The variable names a child started with the policy and explicit env sees
+    fn child_env(policy: &InheritEnv, explicit: &[(&str, &str)]) -> Vec<String> {
+        let mut command = Command::new("/usr/bin/env");
+        inherit_env(&mut command, policy);
+        command.envs(explicit.iter().copied());
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let output = runtime.block_on(command.output()).unwrap();
+
+        let mut names: Vec<String> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(name, _)| name.to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_inherit_env_modes() {
+        let explicit = [("MCP_TEST_TOKEN", "secret")];
+
+        assert_eq!(child_env(&InheritEnv::None, &explicit), ["MCP_TEST_TOKEN"]);
+
+        let only = InheritEnv::Only(vec!["PATH".into()]);
+        assert_eq!(child_env(&only, &explicit), ["MCP_TEST_TOKEN", "PATH"]);
+
+        let safe = child_env(&InheritEnv::default(), &explicit);
+        assert!(safe.iter().all(|name| {
+            name == "MCP_TEST_TOKEN"
+                || crate::config::DEFAULT_INHERITED_ENV.contains(&name.as_str())
+        }));
+
+        let all = child_env(&InheritEnv::All, &explicit);
+        let parent = std::env::vars_os().filter_map(|(name, _)| name.into_string().ok());
+        for name in parent {
+            assert!(all.contains(&name), "{name} was not inherited");
         }
+        assert!(all.contains(&"MCP_TEST_TOKEN".to_string()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_initialize_options_are_sent() {
+        // A server that records the initialize request and answers it
+        let dir = std::env::temp_dir().join(format!("mcp-repl-init-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("server.sh");
+        let received = dir.join("initialize.json");
+        std::fs::write(
+            &script,
+            r#"read -r line
+printf '%s\n' "$line" > "$1"
+id=$(printf '%s' "$line" | sed 's/.*"id":\([0-9]*\).*/\1/')
+printf '{"jsonrpc":"2.0","id":%s,"result":{"protocolVersion":"2025-03-26","capabilities":{},"serverInfo":{"name":"mock","version":"1.0.0"}}}\n' "$id"
+cat > /dev/null
+"#,
+        )
+        .unwrap();
+
+        let serde_json::Value::Object(initialize_options) = serde_json::json!({
+            "capabilities": {"experimental": {"acme": {"mode": "fast"}}}
+        }) else {
+            unreachable!()
+        };
+        let options = ConnectOptions {
+            server_name: "mock".into(),
+            initialize_options: Some(initialize_options),
+            ..ConnectOptions::default()
+        };
+        let connection = McpConnectionType::Command {
+            command: format!("sh {} {}", script.display(), received.display()),
+            env: None,
+            connect_timeout: None,
+        };
 
-        Ok(result.content)
+        let client = crate::engine::shared_runtime().block_on(async {
+            let client = McpClient::connect(connection, &options, false)
+                .await
+                .unwrap();
+            client.disconnect().await;
+            client
+        });
+
+        let received: Value =
+            serde_json::from_str(&std::fs::read_to_string(&received).unwrap()).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(received["method"], "initialize");
+        let params = &received["params"];
+        assert_eq!(
+            params["capabilities"]["experimental"]["acme"]["mode"],
+            "fast"
+        );
+        // The defaults are still sent alongside
+        assert!(params["clientInfo"]["name"].is_string());
+
+        let sent = serde_json::to_value(client.initialize_params().unwrap()).unwrap();
+        assert_eq!(sent["capabilities"]["experimental"]["acme"]["mode"], "fast");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_allowed_sampling_request_reaches_the_handler() {
+        // A server that sends a sampling request once it's initialized and
+        // records the answer
+        let dir = std::env::temp_dir().join(format!("mcp-repl-sampling-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("server.sh");
+        let received = dir.join("answer.json");
+        std::fs::write(
+            &script,
+            r#"read -r line
+id=$(printf '%s' "$line" | sed 's/.*"id":\([0-9]*\).*/\1/')
+printf '{"jsonrpc":"2.0","id":%s,"result":{"protocolVersion":"2025-03-26","capabilities":{},"serverInfo":{"name":"mock","version":"1.0.0"}}}\n' "$id"
+printf '{"jsonrpc":"2.0","id":99,"method":"sampling/createMessage","params":{"messages":[{"role":"user","content":{"type":"text","text":"hi"}}],"maxTokens":10}}\n'
+while read -r line; do
+  case "$line" in *'"id":99'*) printf '%s\n' "$line" > "$1.tmp" && mv "$1.tmp" "$1" ;; esac
+done
+"#,
+        )
+        .unwrap();
+
+        let options = ConnectOptions {
+            server_name: "mock-sampling".into(),
+            trust: TrustPolicy::TRUSTED,
+            sampling: SamplingOptions {
+                policy: SamplingPolicy::Prompt,
+                ..SamplingOptions::default()
+            },
+            ..ConnectOptions::default()
+        };
+        let connection = McpConnectionType::Command {
+            command: format!("sh {} {}", script.display(), received.display()),
+            env: None,
+            connect_timeout: None,
+        };
+        sampling::allow("mock-sampling");
+
+        crate::engine::shared_runtime().block_on(async {
+            let client = McpClient::connect(connection, &options, false)
+                .await
+                .unwrap();
+            for _ in 0..50 {
+                if received.exists() {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            client.disconnect().await;
+        });
+
+        let answer: Value =
+            serde_json::from_str(&std::fs::read_to_string(&received).unwrap()).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        sampling::revoke("mock-sampling");
+
+        // Consent was given, so the request got as far as the missing model
+        assert_eq!(answer["error"]["code"], -32601);
+    }
+
+    #[test]
+    fn test_resolve_program() {
+        let cwd = Path::new("/tmp");
+
+        assert_eq!(resolve_program("./server", cwd), Path::new("/tmp/./server"));
+        assert_eq!(
+            resolve_program("bin/server", cwd),
+            Path::new("/tmp/bin/server")
+        );
+        assert_eq!(resolve_program("npx", cwd), Path::new("npx"));
+        assert_eq!(
+            resolve_program("/usr/bin/env", cwd),
+            Path::new("/usr/bin/env")
+        );
     }
 }
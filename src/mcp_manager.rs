@@ -1,14 +1,34 @@
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 use anyhow::Result;
 use derive_new::new;
 use indexmap::IndexMap;
 use log::info;
 use nu_protocol::engine::EngineState;
-use rmcp::model::Tool;
-use todo_by::todo_by;
+use rmcp::model::{ResourceTemplate, Tool};
 
-use crate::commands::utils::ReplClient;
+use crate::{
+    commands::{
+        tool_mapper::{ToolParameter, tool_parameters},
+        utils::ReplClient,
+    },
+    config::{EffectiveToolSettings, McpReplConfig},
+    util::{
+        cache,
+        deprecation::Deprecation,
+        events::EventLog,
+        format::{self, json_to_nu},
+        hash::json_hash,
+        json_numbers,
+        memory::Store,
+        stats::ServerStats,
+        status,
+        uri_template::UriTemplate,
+    },
+};
+
+/// The failure recorded for a server closed with `mcp disconnect`
+pub const DISCONNECTED: &str = "disconnected with `mcp disconnect`";
 
 /// Manager for MCP clients to support multiple simultaneous connections
 #[derive(Default, new)]
@@ -16,22 +36,246 @@ pub struct McpClientManager {
     /// Map of client name to registered tools
     /// This stores the tools registered from each client with their original schemas
     servers: IndexMap<String, RegisteredServer>,
+
+    /// The configuration the servers were registered from, with the active
+    /// profile's servers included
+    config: McpReplConfig,
+
+    /// The configuration as it was loaded, before a profile was applied
+    base_config: McpReplConfig,
+
+    /// The profile whose servers are registered
+    profile: Option<String>,
+
+    /// The servers a profile switch removed, with the profile each one
+    /// belonged to
+    left_with_profile: IndexMap<String, String>,
+
+    /// Whether the servers were registered from snapshots (`--offline`)
+    offline: bool,
+
+    /// The server that registered each command name, in registration order
+    commands: IndexMap<String, String>,
+
+    /// Notifications received from the servers, shared with their connections
+    events: EventLog,
+
+    /// The `[tools.local]` tools that were registered, keyed by name
+    local_tools: IndexMap<String, Tool>,
 }
 
 #[derive(Debug, Clone)]
 pub struct RegisteredServer {
     pub client: Arc<ReplClient>,
     pub tools: IndexMap<String, RegisteredTool>,
+    pub templates: IndexMap<String, RegisteredTemplate>,
+    /// Problems found while mapping this server's tool schemas
+    pub diagnostics: Vec<ToolDiagnostic>,
+    /// Tools the server provides that couldn't be registered
+    pub skipped: Vec<SkippedTool>,
+    /// Why the last restart failed; the server stays disconnected until it
+    /// is restarted successfully
+    pub failure: Option<String>,
+    /// The profile the server came from, or `None` for a shared server
+    pub profile: Option<String>,
+    /// How the server's tools differ from the last session's snapshot
+    pub drift: Vec<SchemaDrift>,
+    /// Request counters, shared with the client so calls update them
+    /// without locking the manager
+    pub stats: Arc<ServerStats>,
 }
 
 impl RegisteredServer {
     #[must_use]
-    pub const fn new(client: Arc<ReplClient>, tools: IndexMap<String, RegisteredTool>) -> Self {
-        Self { client, tools }
+    pub fn new(
+        client: Arc<ReplClient>,
+        tools: IndexMap<String, RegisteredTool>,
+        templates: IndexMap<String, RegisteredTemplate>,
+        diagnostics: Vec<ToolDiagnostic>,
+        skipped: Vec<SkippedTool>,
+    ) -> Self {
+        Self {
+            stats: client.stats().clone(),
+            client,
+            tools,
+            templates,
+            diagnostics,
+            skipped,
+            failure: None,
+            profile: None,
+            drift: Vec::new(),
+        }
     }
 }
 
-todo_by!("2025-04-10", "Actually use these fields");
+/// A tool that was left out when its server was registered
+#[derive(Clone, Debug)]
+pub struct SkippedTool {
+    pub tool: String,
+    pub reason: String,
+}
+
+/// A problem found while mapping a tool's schema onto a command
+#[derive(Clone, Debug)]
+pub struct ToolDiagnostic {
+    pub tool: String,
+    /// The parameter the problem is about, or `None` for the whole schema
+    pub parameter: Option<String>,
+    pub problem: String,
+    /// Whether the tool was skipped because of the problem
+    pub skipped: bool,
+}
+
+/// A tool whose input schema differs from the one cached last session
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SchemaDrift {
+    pub tool: String,
+    /// `added`, `removed` or `changed`
+    pub change: &'static str,
+    /// What changed about the parameters, for a changed tool
+    pub detail: Option<String>,
+}
+
+/// Compare the tools a server reported last session with the current ones
+///
+/// Schemas are compared by [`json_hash`], so key order and the order of
+/// `required` don't count as changes.
+#[must_use]
+pub fn schema_drift(cached: &[Tool], current: &[Tool]) -> Vec<SchemaDrift> {
+    let by_name = |tools: &[Tool]| -> IndexMap<String, Tool> {
+        tools
+            .iter()
+            .map(|tool| (tool.name.to_string(), tool.clone()))
+            .collect()
+    };
+    let cached = by_name(cached);
+    let current = by_name(current);
+    let mut drift = Vec::new();
+
+    for (name, tool) in &current {
+        match cached.get(name) {
+            None => drift.push(SchemaDrift {
+                tool: name.clone(),
+                change: "added",
+                detail: None,
+            }),
+            Some(old)
+                if json_hash(&old.schema_as_json_value())
+                    != json_hash(&tool.schema_as_json_value()) =>
+            {
+                drift.push(SchemaDrift {
+                    tool: name.clone(),
+                    change: "changed",
+                    detail: Some(parameter_changes(old, tool)),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+
+    drift.extend(
+        cached
+            .keys()
+            .filter(|name| !current.contains_key(*name))
+            .map(|name| SchemaDrift {
+                tool: name.clone(),
+                change: "removed",
+                detail: None,
+            }),
+    );
+
+    drift
+}
+
+/// Describe how the parameters of a tool changed, e.g. `removed 'query',
+/// added 'q'`
+fn parameter_changes(old: &Tool, new: &Tool) -> String {
+    let old_params = tool_parameters(old);
+    let new_params = tool_parameters(new);
+    let find = |params: &[ToolParameter], name: &str| {
+        params.iter().find(|param| param.name == name).cloned()
+    };
+
+    let mut changes = Vec::new();
+    for param in &old_params {
+        match find(&new_params, &param.name) {
+            None => changes.push(format!("removed '{}'", param.name)),
+            Some(now) if json_hash(&now.schema) != json_hash(&param.schema) => {
+                changes.push(format!("'{}' changed type or constraints", param.name));
+            }
+            Some(now) if now.required != param.required => changes.push(format!(
+                "'{}' is now {}",
+                param.name,
+                if now.required { "required" } else { "optional" }
+            )),
+            Some(_) => {}
+        }
+    }
+    changes.extend(
+        new_params
+            .iter()
+            .filter(|param| find(&old_params, &param.name).is_none())
+            .map(|param| format!("added '{}'", param.name)),
+    );
+
+    if changes.is_empty() {
+        "the input schema changed outside its parameters".into()
+    } else {
+        changes.join(", ")
+    }
+}
+
+/// How a server's tools changed between two registrations
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ToolChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Tools that kept their name but whose input schema changed
+    pub changed: Vec<String>,
+    /// Tools whose input schema is the same as before
+    pub unchanged: Vec<String>,
+}
+
+impl ToolChanges {
+    fn between(
+        old: &IndexMap<String, RegisteredTool>,
+        new: &IndexMap<String, RegisteredTool>,
+    ) -> Self {
+        let mut changes = Self::default();
+
+        for (name, tool) in new {
+            match old.get(name) {
+                None => changes.added.push(name.clone()),
+                Some(previous) if previous.identity() != tool.identity() => {
+                    changes.changed.push(name.clone());
+                }
+                Some(_) => changes.unchanged.push(name.clone()),
+            }
+        }
+
+        changes.removed = old
+            .keys()
+            .filter(|name| !new.contains_key(*name))
+            .cloned()
+            .collect();
+
+        changes
+    }
+
+    /// Tools whose cached results no longer apply
+    pub fn stale(&self) -> impl Iterator<Item = &str> {
+        self.changed.iter().chain(&self.removed).map(String::as_str)
+    }
+}
+
+/// What makes two registrations the same tool: the server, the tool name
+/// and a hash of its input schema
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ToolIdentity {
+    pub server: String,
+    pub name: String,
+    pub schema_hash: String,
+}
 
 /// A tool that has been registered with the system
 #[derive(Clone, Debug)]
@@ -40,18 +284,62 @@ pub struct RegisteredTool {
     pub tool: Tool,
 
     /// The namespace of the client,
-    #[allow(dead_code)]
     pub namespace: String,
-    #[allow(dead_code)]
     pub name: String,
 
-    /// The raw schema JSON from the tool
-    #[allow(dead_code)]
-    pub raw_schema: nu_protocol::Value,
+    /// The input schema as a Nushell value, converted on first use by
+    /// [`Self::raw_schema`]
+    ///
+    /// Most schemas are never inspected, and converting hundreds of them
+    /// would slow down startup.
+    pub raw_schema: OnceLock<nu_protocol::Value>,
+
+    /// A stable hash of the input schema, see [`crate::util::hash::json_hash`]
+    pub schema_hash: String,
 
     /// The client this tool belongs to
-    #[allow(dead_code)]
     pub client: Arc<ReplClient>,
+
+    /// The call settings resolved from the configuration
+    pub settings: EffectiveToolSettings,
+
+    /// How the server marked the tool as deprecated, if it did
+    pub deprecation: Option<Deprecation>,
+}
+
+impl RegisteredTool {
+    /// The identity of this registration
+    #[must_use]
+    pub fn identity(&self) -> ToolIdentity {
+        ToolIdentity {
+            server: self.namespace.clone(),
+            name: self.name.clone(),
+            schema_hash: self.schema_hash.clone(),
+        }
+    }
+
+    /// The tool's input schema as a Nushell value
+    pub fn raw_schema(&self) -> &nu_protocol::Value {
+        self.raw_schema.get_or_init(|| {
+            json_to_nu(
+                &self.tool.schema_as_json_value(),
+                Some(nu_protocol::Span::unknown()),
+            )
+        })
+    }
+}
+
+/// A resource template that has been registered as a `resource` command
+#[derive(Clone, Debug)]
+pub struct RegisteredTemplate {
+    /// The MCP resource template object
+    pub template: ResourceTemplate,
+
+    /// The command-friendly name (`resource <server>.<name>`)
+    pub name: String,
+
+    /// The parsed URI template
+    pub uri_template: UriTemplate,
 }
 
 impl McpClientManager {
@@ -65,15 +353,586 @@ impl McpClientManager {
         // Store the client by name
         info!("Registering tools from client '{name}'...");
         // engine_state.get_mcp_client_manager()
-        let tools = crate::commands::mcp_tools::register_mcp_tools(&name, engine_state, client)?;
-        self.servers.insert(name, tools);
+        let mut server = crate::commands::mcp_tools::register_mcp_tools(
+            &name,
+            engine_state,
+            client,
+            &self.config,
+        )?;
+        self.record_commands(&name, &mut server);
+        server.profile = self.server_profile(&name);
+        self.left_with_profile.shift_remove(&name);
+
+        // Re-registering a server keeps its position, so the order servers
+        // are listed in always follows the config
+        self.servers.insert(name, server);
+
+        Ok(())
+    }
+
+    /// Track which server each command came from, reporting commands that
+    /// replaced one registered by a different server
+    ///
+    /// The command registered last wins, so with servers registered in
+    /// config order the same config always produces the same winner.
+    fn record_commands(&mut self, name: &str, server: &mut RegisteredServer) {
+        let commands = server
+            .tools
+            .keys()
+            .map(|tool| (format!("tool {name}.{tool}"), tool.clone()))
+            .chain(server.templates.values().map(|template| {
+                (
+                    format!("resource {name}.{}", template.name),
+                    template.name.clone(),
+                )
+            }))
+            .collect::<Vec<_>>();
+
+        // Commands the server no longer provides are forgotten
+        self.commands.retain(|command, origin| {
+            origin != name || commands.iter().any(|(registered, _)| registered == command)
+        });
+
+        for (command, item) in commands {
+            let Some(previous) = self.commands.insert(command.clone(), name.to_string()) else {
+                continue;
+            };
+
+            if previous == name {
+                continue;
+            }
 
+            crate::warning!(
+                "`{}` from server '{}' replaces the command registered by server '{}'",
+                command,
+                name,
+                previous
+            );
+
+            server.diagnostics.push(ToolDiagnostic {
+                tool: item,
+                parameter: None,
+                problem: format!(
+                    "`{command}` was also registered by server '{previous}'; this registration wins"
+                ),
+                skipped: false,
+            });
+
+            if let Some(shadowed) = self.servers.get_mut(&previous) {
+                let shadowed_item = command
+                    .split_once(' ')
+                    .and_then(|(_, qualified)| qualified.strip_prefix(previous.as_str()))
+                    .and_then(|item| item.strip_prefix('.'))
+                    .unwrap_or(&command)
+                    .to_string();
+
+                shadowed.diagnostics.push(ToolDiagnostic {
+                    tool: shadowed_item,
+                    parameter: None,
+                    problem: format!(
+                        "`{command}` was replaced by the command from server '{name}'"
+                    ),
+                    skipped: true,
+                });
+            }
+        }
+    }
+
+    /// Replace the record of a server after it was restarted, returning how
+    /// its tools changed
+    pub fn replace_server(&mut self, name: &str, server: RegisteredServer) -> ToolChanges {
+        let changes = self
+            .servers
+            .get(name)
+            .map(|old| ToolChanges::between(&old.tools, &server.tools))
+            .unwrap_or_default();
+
+        // The drift since last session still applies after a restart
+        let drift = self
+            .servers
+            .get(name)
+            .map(|old| old.drift.clone())
+            .unwrap_or_default();
+        self.servers
+            .insert(name.to_string(), RegisteredServer { drift, ..server });
+        self.notify_completer();
+        changes
+    }
+
+    /// Tell an external completer listening on `MCP_COMPLETER_SOCKET` that
+    /// the tool commands changed
+    pub fn notify_completer(&self) {
+        crate::util::completer::notify_tools_changed(self.commands.len());
+    }
+
+    /// Record how a server's tools differ from the last session's snapshot
+    pub fn set_drift(&mut self, name: &str, drift: Vec<SchemaDrift>) {
+        if let Some(server) = self.servers.get_mut(name) {
+            server.drift = drift;
+        }
+    }
+
+    /// Keep a server whose restart failed, recording why
+    pub fn mark_failed(&mut self, name: &str, failure: String) {
+        if let Some(server) = self.servers.get_mut(name) {
+            server.failure = Some(failure);
+        }
+    }
+
+    /// Keep a server that was disconnected on purpose, until it's restarted
+    pub fn mark_disconnected(&mut self, name: &str) {
+        self.mark_failed(name, DISCONNECTED.to_string());
+    }
+
+    /// Set the configuration used to resolve settings for registered tools
+    ///
+    /// The servers of the profile named in the config are included; it's an
+    /// error if there is no such profile.
+    pub fn set_config(&mut self, config: McpReplConfig) -> Result<()> {
+        let effective = config.with_profile(config.profile.as_deref())?;
+        self.apply_memory_limits(&effective);
+        json_numbers::set_policy(effective.json_numbers());
+        format::set_hyperlinks(effective.hyperlinks());
+        self.profile.clone_from(&config.profile);
+        self.base_config = config;
+        self.config = effective;
         Ok(())
     }
 
+    /// The configuration as it was loaded, with every profile
+    #[must_use]
+    pub const fn base_config(&self) -> &McpReplConfig {
+        &self.base_config
+    }
+
+    /// The profile whose servers are registered
+    #[must_use]
+    pub fn active_profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// The profile a server belonged to, if switching away from that profile
+    /// removed it
+    #[must_use]
+    pub fn left_with_profile(&self, name: &str) -> Option<&str> {
+        self.left_with_profile.get(name).map(String::as_str)
+    }
+
+    /// The profile a configured server belongs to, or `None` if it is shared
+    fn server_profile(&self, name: &str) -> Option<String> {
+        if self.base_config.servers.contains_key(name) {
+            None
+        } else {
+            self.profile.clone()
+        }
+    }
+
+    /// Replace the active profile's servers with another profile's in one step
+    ///
+    /// `config` is the configuration with the new profile applied, and
+    /// `connected` the servers of that profile that connected. Shared servers
+    /// are kept. The servers that were removed are returned so they can be
+    /// disconnected.
+    pub fn switch_profile(
+        &mut self,
+        profile: &str,
+        config: McpReplConfig,
+        connected: IndexMap<String, RegisteredServer>,
+    ) -> Vec<(String, RegisteredServer)> {
+        let leaving: Vec<String> = self
+            .servers
+            .iter()
+            .filter(|(_, server)| server.profile.is_some())
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in &leaving {
+            if let Some(old) = self
+                .servers
+                .get(name)
+                .and_then(|server| server.profile.clone())
+            {
+                self.left_with_profile.insert(name.clone(), old);
+            }
+        }
+
+        let removed = leaving
+            .iter()
+            .filter_map(|name| {
+                self.servers
+                    .shift_remove(name)
+                    .map(|server| (name.clone(), server))
+            })
+            .collect();
+        self.commands.retain(|_, origin| !leaving.contains(origin));
+
+        self.apply_memory_limits(&config);
+        json_numbers::set_policy(config.json_numbers());
+        format::set_hyperlinks(config.hyperlinks());
+        self.config = config;
+        self.profile = Some(profile.to_string());
+
+        for (name, mut server) in connected {
+            self.record_commands(&name, &mut server);
+            server.profile = Some(profile.to_string());
+            self.left_with_profile.shift_remove(&name);
+            self.servers.insert(name, server);
+        }
+        self.notify_completer();
+
+        removed
+    }
+
+    /// Keep the session's stores within the configuration's `[memory]` limits
+    fn apply_memory_limits(&self, config: &McpReplConfig) {
+        self.events.set_limit(config.memory_limit(Store::Events));
+        cache::set_limit(config.memory_limit(Store::Results));
+        status::OUTPUT.set_limit(config.memory_limit(Store::Output));
+    }
+
+    /// Get the configuration the servers were registered from
+    #[must_use]
+    pub const fn config(&self) -> &McpReplConfig {
+        &self.config
+    }
+
+    /// Mark the session as offline, where no server is ever contacted
+    pub const fn set_offline(&mut self, offline: bool) {
+        self.offline = offline;
+    }
+
+    /// Whether the session was started with `--offline`
+    #[must_use]
+    pub const fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// The log the servers' notifications are recorded in
+    #[must_use]
+    pub const fn events(&self) -> &EventLog {
+        &self.events
+    }
+
     /// Get all registered clients
     #[must_use]
     pub const fn get_servers(&self) -> &IndexMap<String, RegisteredServer> {
         &self.servers
     }
+
+    /// Remember the local tools that were registered as commands
+    pub fn set_local_tools(&mut self, tools: IndexMap<String, Tool>) {
+        self.local_tools = tools;
+    }
+
+    /// The registered `[tools.local]` tools, keyed by name
+    #[must_use]
+    pub const fn local_tools(&self) -> &IndexMap<String, Tool> {
+        &self.local_tools
+    }
+
+    /// Get a single registered server by name
+    #[must_use]
+    pub fn get_server(&self, name: &str) -> Option<&RegisteredServer> {
+        self.servers.get(name)
+    }
+
+    /// Find a tool by its namespaced name (`server.tool`)
+    #[must_use]
+    pub fn find_tool(&self, namespaced_name: &str) -> Option<(&str, &RegisteredTool)> {
+        let (server_name, tool_name) = namespaced_name.split_once('.')?;
+        let (server_name, server) = self.servers.get_key_value(server_name)?;
+        let tool = server.tools.get(tool_name)?;
+        Some((server_name.as_str(), tool))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use rmcp::model::ServerInfo;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        mcp::McpClient,
+        util::{cache::ToolResultCache, snapshot::SchemaSnapshot},
+    };
+
+    pub(crate) fn mock_client(name: &str, tools: &[&str]) -> Arc<ReplClient> {
+        mock_client_with_schema(name, tools, json!({"type": "object", "properties": {}}))
+    }
+
+    pub(crate) fn mock_client_with_schema(
+        name: &str,
+        tools: &[&str],
+        schema: serde_json::Value,
+    ) -> Arc<ReplClient> {
+        let serde_json::Value::Object(schema) = schema else {
+            panic!("a tool schema must be an object");
+        };
+
+        let snapshot = SchemaSnapshot {
+            server_info: ServerInfo::default(),
+            tools: tools
+                .iter()
+                .map(|tool| Tool::new(tool.to_string(), "A mock tool", Arc::new(schema.clone())))
+                .collect(),
+            resources: Vec::new(),
+            templates: Vec::new(),
+        };
+
+        Arc::new(ReplClient {
+            name: name.to_string(),
+            client: McpClient::offline(name, snapshot, false),
+            cache: ToolResultCache::default(),
+            _debug: false,
+        })
+    }
+
+    fn register(
+        manager: &mut McpClientManager,
+        engine_state: &mut EngineState,
+        name: &str,
+        tools: &[&str],
+    ) {
+        manager
+            .register_client(name.to_string(), &mock_client(name, tools), engine_state)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_schema_drift() {
+        let tool = |name: &str, schema: serde_json::Value| {
+            let serde_json::Value::Object(schema) = schema else {
+                panic!("a tool schema must be an object");
+            };
+            Tool::new(name.to_string(), "A mock tool", Arc::new(schema))
+        };
+
+        let cached = [
+            tool(
+                "search",
+                json!({"type": "object", "properties": {"query": {"type": "string"}, "limit": {"type": "integer"}}, "required": ["query", "limit"]}),
+            ),
+            tool("list", json!({"type": "object", "properties": {}})),
+            tool("old", json!({"type": "object"})),
+        ];
+        let current = [
+            tool(
+                "search",
+                json!({"required": ["limit", "q"], "properties": {"limit": {"type": "integer"}, "q": {"type": "string"}}, "type": "object"}),
+            ),
+            tool("list", json!({"properties": {}, "type": "object"})),
+            tool("new", json!({"type": "object"})),
+        ];
+
+        let drift = schema_drift(&cached, &current);
+        assert_eq!(
+            drift,
+            vec![
+                SchemaDrift {
+                    tool: "search".into(),
+                    change: "changed",
+                    detail: Some("removed 'query', added 'q'".into()),
+                },
+                SchemaDrift {
+                    tool: "new".into(),
+                    change: "added",
+                    detail: None,
+                },
+                SchemaDrift {
+                    tool: "old".into(),
+                    change: "removed",
+                    detail: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replace_server_reports_tool_changes() {
+        let mut engine_state = EngineState::new();
+        let mut manager = McpClientManager::default();
+        register(&mut manager, &mut engine_state, "fs", &["read", "write"]);
+
+        let restarted = crate::commands::mcp_tools::restarted_server(
+            "fs",
+            &engine_state,
+            &mock_client("fs", &["read", "list"]),
+            manager.config(),
+        );
+        let changes = manager.replace_server("fs", restarted);
+
+        assert_eq!(
+            changes,
+            ToolChanges {
+                added: vec!["list".into()],
+                removed: vec!["write".into()],
+                changed: Vec::new(),
+                unchanged: vec!["read".into()],
+            }
+        );
+        assert!(manager.find_tool("fs.list").is_some());
+    }
+
+    #[test]
+    fn test_replace_server_reports_schema_changes() {
+        let mut engine_state = EngineState::new();
+        let mut manager = McpClientManager::default();
+        register(&mut manager, &mut engine_state, "web", &["search"]);
+        let before = manager.find_tool("web.search").unwrap().1.identity();
+
+        let restarted = crate::commands::mcp_tools::restarted_server(
+            "web",
+            &engine_state,
+            &mock_client_with_schema(
+                "web",
+                &["search"],
+                json!({"type": "object", "properties": {"query": {"type": "string"}}}),
+            ),
+            manager.config(),
+        );
+        let changes = manager.replace_server("web", restarted);
+
+        assert_eq!(changes.changed, vec!["search".to_string()]);
+        assert!(changes.unchanged.is_empty());
+        assert_eq!(changes.stale().collect::<Vec<_>>(), vec!["search"]);
+
+        let after = manager.find_tool("web.search").unwrap().1.identity();
+        assert_eq!((&after.server, &after.name), (&before.server, &before.name));
+        assert_ne!(after, before);
+    }
+
+    #[test]
+    fn test_switch_profile_keeps_shared_servers() {
+        let mut engine_state = EngineState::new();
+        let mut manager = McpClientManager::default();
+        let mut config: McpReplConfig = toml_edit::de::from_str(
+            r#"
+            profile = "work"
+
+            [servers.fetch]
+            command = "fetch-server"
+
+            [profiles.work.servers.jira]
+            command = "jira-server"
+
+            [profiles.home.servers.notes]
+            command = "notes-server"
+            "#,
+        )
+        .unwrap();
+        manager.set_config(config.clone()).unwrap();
+
+        register(&mut manager, &mut engine_state, "fetch", &["fetch"]);
+        register(&mut manager, &mut engine_state, "jira", &["search"]);
+        assert_eq!(
+            manager.get_server("jira").unwrap().profile.as_deref(),
+            Some("work")
+        );
+        assert_eq!(manager.get_server("fetch").unwrap().profile, None);
+
+        config = manager.base_config().with_profile(Some("home")).unwrap();
+        let notes = crate::commands::mcp_tools::restarted_server(
+            "notes",
+            &engine_state,
+            &mock_client("notes", &["read"]),
+            &config,
+        );
+        let removed =
+            manager.switch_profile("home", config, IndexMap::from([("notes".into(), notes)]));
+
+        assert_eq!(
+            removed
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["jira"]
+        );
+        assert_eq!(
+            manager.get_servers().keys().collect::<Vec<_>>(),
+            vec!["fetch", "notes"]
+        );
+        assert_eq!(manager.active_profile(), Some("home"));
+        assert_eq!(manager.left_with_profile("jira"), Some("work"));
+        assert_eq!(manager.left_with_profile("fetch"), None);
+        assert!(manager.find_tool("jira.search").is_none());
+        assert!(!manager.commands.contains_key("tool jira.search"));
+    }
+
+    #[test]
+    fn test_unregistrable_tools_are_skipped_with_a_reason() {
+        let mut engine_state = EngineState::new();
+        let mut manager = McpClientManager::default();
+        register(
+            &mut manager,
+            &mut engine_state,
+            "fs",
+            &["read", "read", "bad name", "write"],
+        );
+
+        let server = manager.get_server("fs").unwrap();
+        assert_eq!(
+            server.tools.keys().collect::<Vec<_>>(),
+            vec!["read", "write"]
+        );
+
+        let skipped: Vec<_> = server
+            .skipped
+            .iter()
+            .map(|tool| tool.tool.as_str())
+            .collect();
+        assert_eq!(skipped, vec!["read", "bad name"]);
+        assert!(server.skipped.iter().all(|tool| !tool.reason.is_empty()));
+    }
+
+    #[test]
+    fn test_duplicate_command_follows_registration_order() {
+        let mut engine_state = EngineState::new();
+        let mut manager = McpClientManager::default();
+
+        // Both register `tool a.b.c`
+        register(&mut manager, &mut engine_state, "a", &["b.c"]);
+        register(&mut manager, &mut engine_state, "a.b", &["c"]);
+
+        assert_eq!(
+            manager.commands.get("tool a.b.c").map(String::as_str),
+            Some("a.b")
+        );
+
+        let winner = &manager.get_server("a.b").unwrap().diagnostics;
+        assert!(
+            winner
+                .iter()
+                .any(|diagnostic| diagnostic.tool == "c" && diagnostic.problem.contains("'a'"))
+        );
+
+        let shadowed = &manager.get_server("a").unwrap().diagnostics;
+        assert!(
+            shadowed
+                .iter()
+                .any(|diagnostic| diagnostic.tool == "b.c" && diagnostic.skipped)
+        );
+    }
+
+    #[test]
+    fn test_shared_tool_names_and_reregistration() {
+        let mut engine_state = EngineState::new();
+        let mut manager = McpClientManager::default();
+
+        register(&mut manager, &mut engine_state, "one", &["search"]);
+        register(&mut manager, &mut engine_state, "two", &["search"]);
+        register(&mut manager, &mut engine_state, "one", &["search", "fetch"]);
+
+        let names: Vec<&str> = manager.get_servers().keys().map(String::as_str).collect();
+        assert_eq!(names, vec!["one", "two"]);
+        assert!(
+            manager
+                .get_servers()
+                .values()
+                .all(|server| server.diagnostics.is_empty())
+        );
+        assert_eq!(
+            manager.commands.get("tool one.fetch").map(String::as_str),
+            Some("one")
+        );
+    }
 }
@@ -0,0 +1,206 @@
+//! `nu-mcp-repl proxy <server>`: pass a server's protocol through, logging it
+//!
+//! The configured server is started as it would be for the REPL, with the
+//! same command, environment and `inherit_env` policy, and the proxy then
+//! copies JSON-RPC messages between its own stdin/stdout and the server's,
+//! a line at a time. Every request, response and notification is forwarded
+//! unchanged in both directions, including `initialize`, so the client on
+//! the other side talks to the server as if directly. Each message is logged
+//! at info level; run with `--log-file` to capture the traffic.
+//!
+//! Nothing but protocol messages may be written to stdout here, so this
+//! path never starts the Nushell REPL or prints status messages.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use futures::future::{Either, select};
+use serde_json::Value as JsonValue;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::{
+    config::{McpConnectionType, McpReplConfig},
+    engine::shared_runtime,
+    mcp::McpClient,
+};
+
+/// How long the server may take to answer and exit once the client is gone
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// Which way a message is going
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Direction {
+    ToServer,
+    ToClient,
+}
+
+impl Direction {
+    const fn arrow(self) -> &'static str {
+        match self {
+            Self::ToServer => "->",
+            Self::ToClient => "<-",
+        }
+    }
+}
+
+/// Proxy `server` over stdin/stdout until either side closes
+pub fn run(config: &McpReplConfig, server: &str) -> Result<()> {
+    let server_config = config.servers.get(server).ok_or_else(|| {
+        anyhow!(
+            "Unknown server '{server}' (configured: {})",
+            config
+                .servers
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    })?;
+    let McpConnectionType::Command { command, env, .. } = &server_config.connection else {
+        return Err(anyhow!(
+            "'{server}' is an SSE server; only command servers can be proxied"
+        ));
+    };
+    let options = server_config.connect_options();
+    let cwd = std::env::current_dir().context("Failed to read the current directory")?;
+
+    shared_runtime().block_on(async {
+        let (mut process, launch) = McpClient::spawn_command(
+            command,
+            &env.clone().unwrap_or_default(),
+            &options.inherit_env,
+            Some(&cwd),
+            options.path_check,
+        )?;
+        log::info!("Proxying '{server}': {}", shell_words::join(&launch.argv));
+
+        let server_stdin = process.stdin.take().context("The process has no stdin")?;
+        let server_stdout = process.stdout.take().context("The process has no stdout")?;
+        if let Some(mut server_stderr) = process.stderr.take() {
+            // The server's own diagnostics go where ours do
+            shared_runtime().spawn(async move {
+                let _ = tokio::io::copy(&mut server_stderr, &mut tokio::io::stderr()).await;
+            });
+        }
+
+        let to_server = Box::pin(forward(
+            tokio::io::stdin(),
+            server_stdin,
+            server,
+            Direction::ToServer,
+        ));
+        let to_client = Box::pin(forward(
+            server_stdout,
+            tokio::io::stdout(),
+            server,
+            Direction::ToClient,
+        ));
+
+        match select(to_server, to_client).await {
+            // The client closed its end, which closes the server's stdin;
+            // pass on whatever the server still sends while it exits
+            Either::Left((result, to_client)) => {
+                log::info!("The client closed the connection to '{server}'");
+                result?;
+                if tokio::time::timeout(SHUTDOWN_GRACE, to_client)
+                    .await
+                    .is_err()
+                {
+                    log::warn!("'{server}' didn't exit after its stdin closed; stopping it");
+                }
+            }
+            Either::Right((result, _)) => {
+                log::info!("'{server}' closed the connection");
+                result?;
+            }
+        }
+
+        let _ = process.kill().await;
+        Ok(())
+    })
+}
+
+/// Copy messages from `reader` to `writer`, logging each, until `reader` ends
+///
+/// `writer` is dropped when this returns, which closes it.
+async fn forward(
+    reader: impl AsyncRead + Unpin,
+    mut writer: impl AsyncWrite + Unpin,
+    server: &str,
+    direction: Direction,
+) -> Result<()> {
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        log::info!(
+            target: "mcp_proxy",
+            "{server} {} {}: {line}",
+            direction.arrow(),
+            describe_message(&line)
+        );
+        writer.write_all(line.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
+        writer.flush().await?;
+    }
+
+    Ok(())
+}
+
+/// A short description of a message for the log, e.g. `request 3 tools/call`
+fn describe_message(line: &str) -> String {
+    let Ok(message) = serde_json::from_str::<JsonValue>(line) else {
+        return "not JSON".into();
+    };
+
+    let id = message.get("id").map(ToString::to_string);
+    let method = message.get("method").and_then(JsonValue::as_str);
+    match (id, method) {
+        (Some(id), Some(method)) => format!("request {id} {method}"),
+        (None, Some(method)) => format!("notification {method}"),
+        (Some(id), None) if message.get("error").is_some() => format!("error {id}"),
+        (Some(id), None) => format!("response {id}"),
+        (None, None) => "unknown message".into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_message() {
+        assert_eq!(
+            describe_message(r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{}}"#),
+            "request 3 tools/call"
+        );
+        assert_eq!(
+            describe_message(r#"{"jsonrpc":"2.0","method":"notifications/tools/list_changed"}"#),
+            "notification notifications/tools/list_changed"
+        );
+        assert_eq!(
+            describe_message(r#"{"jsonrpc":"2.0","id":"a","result":{}}"#),
+            r#"response "a""#
+        );
+        assert_eq!(
+            describe_message(r#"{"jsonrpc":"2.0","id":4,"error":{"code":-1,"message":"no"}}"#),
+            "error 4"
+        );
+        assert_eq!(describe_message("Starting server..."), "not JSON");
+    }
+
+    #[test]
+    fn test_forward_copies_lines_until_the_reader_closes() {
+        let input: &[u8] =
+            b"{\"jsonrpc\":\"2.0\",\"method\":\"a\"}\n{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{}}";
+        let mut output = Vec::new();
+
+        shared_runtime()
+            .block_on(forward(input, &mut output, "mock", Direction::ToClient))
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"jsonrpc\":\"2.0\",\"method\":\"a\"}\n{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{}}\n"
+        );
+    }
+}
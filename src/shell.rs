@@ -1,17 +1,39 @@
-use std::{collections::HashMap, sync::Arc, time::Instant};
+use std::{
+    collections::HashMap,
+    io::IsTerminal,
+    sync::{Arc, atomic::AtomicBool},
+    time::{Duration, Instant},
+};
 
-use anyhow::{Context, Result};
+use anyhow::{Result, anyhow};
 use async_lock::{Mutex, OnceCell};
 use log::{debug, info};
+use nu_cli::EvaluateCommandsOpts;
 use nu_cmd_lang::create_default_context;
 use nu_protocol::{
-    Config, HistoryConfig, HistoryFileFormat, Span, Value,
+    Config, HistoryConfig, HistoryFileFormat, PipelineData, Record, ShellError, Signals, Span,
+    Spanned, Value,
+    debugger::WithoutDebug,
     engine::{EngineState, Stack, StateWorkingSet},
 };
-use tokio::runtime::Runtime;
 
 use crate::{
-    commands::help::McpHelpCommand, config::McpReplConfig, engine::get_mcp_client_manager,
+    commands::{
+        help::McpHelpCommand, local_tools::register_local_tools, mcp::server_summary,
+        utils::error_text,
+    },
+    config::{McpReplConfig, McpServerConfig},
+    engine::get_mcp_client_manager,
+    mcp_manager::{SchemaDrift, schema_drift},
+    util::{
+        connect_progress::ConnectProgress,
+        eval,
+        format::render_table,
+        last_call,
+        max_runtime::{RuntimeBudget, exit_over_budget},
+        snapshot::SchemaSnapshot,
+        state_dir,
+    },
 };
 
 // Define a static variable to hold our custom history path
@@ -20,6 +42,19 @@ static HISTORY_PATH: OnceCell<Mutex<Option<String>>> = OnceCell::new();
 // Import Nushell's help commands directly
 use crate::commands::builtin::add_shell_command_context;
 
+/// Commands run once the servers are registered, before the prompt appears
+/// or a `--commands` script starts
+///
+/// They come from `startup_commands` in the config, followed by each
+/// `--startup-cmd`.
+#[derive(Clone, Debug, Default)]
+pub struct StartupCommands {
+    pub commands: Vec<String>,
+    /// Stop at the first command that fails, instead of reporting it and
+    /// running the rest
+    pub strict: bool,
+}
+
 /// `McpRepl` integrates Nushell with the MCP functionality
 pub struct McpRepl {
     /// Nushell engine state
@@ -41,15 +76,21 @@ impl McpRepl {
         };
 
         // Initialize hooks with empty values - don't set to None
-        config.hooks.display_output = None;
         config.hooks.command_not_found = None;
         config.hooks.env_change = HashMap::new();
-        config.hooks.pre_prompt = Vec::new();
-        config.hooks.pre_execution = Vec::new();
+        // Results are rendered as usual, then their URIs made hyperlinks
+        // where the `hyperlinks` setting allows
+        config.hooks.display_output =
+            Some(Value::string("table | mcp output --links", Span::unknown()));
+        // Messages from the background wait while the prompt is up, see
+        // `util::status::OutputBroker`
+        config.hooks.pre_prompt = vec![Value::string("mcp output --prompt-shown", Span::unknown())];
+        config.hooks.pre_execution =
+            vec![Value::string("mcp output --prompt-left", Span::unknown())];
 
         // Customize history configuration for MCP-REPL
         // Create a separate history file in the .mcp-repl directory
-        let history_config = Self::create_custom_history_config()?;
+        let history_config = Self::create_custom_history_config();
         config.history = history_config;
 
         // Apply the config
@@ -72,8 +113,13 @@ impl McpRepl {
             );
         }
 
-        // Set up minimal environment variables required for commands to work
-        stack.add_env_var("PWD".into(), Value::string("/", Span::unknown()));
+        // Start in the directory the REPL was launched from; `cd` keeps PWD
+        // up to date from here, and servers are started relative to it
+        let cwd = std::env::current_dir().map_or_else(
+            |_| "/".to_string(),
+            |cwd| cwd.to_string_lossy().into_owned(),
+        );
+        stack.add_env_var("PWD".into(), Value::string(cwd, Span::unknown()));
 
         // Add PROMPT_COMMAND to display a simple prompt
         stack.add_env_var(
@@ -93,7 +139,7 @@ impl McpRepl {
         info!("Initialized minimal Nushell engine state");
 
         // Register custom MCP commands
-        Self::register_mcp_commands(&mut engine_state);
+        Self::register_mcp_commands(&mut engine_state, &mut stack);
         debug!("Registered MCP commands in engine state");
 
         Ok(Self {
@@ -103,7 +149,7 @@ impl McpRepl {
     }
 
     /// Register MCP-specific Nushell commands and essential Nushell commands
-    fn register_mcp_commands(engine_state: &mut EngineState) {
+    fn register_mcp_commands(engine_state: &mut EngineState, stack: &mut Stack) {
         // Register custom commands from our commands module
         crate::commands::register_all(engine_state);
 
@@ -148,28 +194,156 @@ impl McpRepl {
 
         let mut working_set = StateWorkingSet::new(engine_state);
         working_set.add_decl(Box::new(McpHelpCommand));
+        // `$mcp_last` and `$mcp_last_call` are empty until the first tool call
+        for var in last_call::declare(&mut working_set) {
+            stack.add_var(var, Value::nothing(Span::unknown()));
+        }
         let delta = working_set.render();
         if let Err(err) = engine_state.merge_delta(delta) {
             log::warn!("Error registering custom help command: {err:?}");
         }
     }
 
-    pub async fn register(&mut self, config: &McpReplConfig) -> Result<()> {
+    /// Connect the configured servers and register their tools
+    ///
+    /// Progress is shown on a status line while they connect, unless
+    /// `quiet` is set or stderr isn't a terminal.
+    pub async fn register(
+        &mut self,
+        config: &McpReplConfig,
+        offline: bool,
+        quiet: bool,
+    ) -> Result<()> {
+        // The active profile's servers are registered along with the shared ones
+        let config = {
+            let mut manager = get_mcp_client_manager().await;
+            manager.set_config(config.clone())?;
+            manager.set_offline(offline);
+            manager.config().clone()
+        };
+
+        if !config.http_commands_enabled() {
+            let mut working_set = StateWorkingSet::new(&self.engine_state);
+            eval::hide_commands(&mut working_set, eval::HTTP_COMMANDS);
+            let delta = working_set.render();
+            self.engine_state.merge_delta(delta)?;
+        }
+
+        // Local tools don't need a server, so they're there even offline
+        if !config.tools.local.is_empty() {
+            let mut working_set = StateWorkingSet::new(&self.engine_state);
+            let tools = register_local_tools(&mut working_set, &config.tools.local);
+            let delta = working_set.render();
+            self.engine_state.merge_delta(delta)?;
+            get_mcp_client_manager().await.set_local_tools(tools);
+        }
+
+        let progress = (!offline).then(|| {
+            ConnectProgress::start(
+                config.servers.len(),
+                !quiet && std::io::stderr().is_terminal(),
+            )
+        });
         for (name, server) in &config.servers {
-            crate::info!("Registering MCP client: {name}");
-            let client = server.to_client(name).await?;
-            get_mcp_client_manager().await.register_client(
-                name.clone(),
-                &client,
-                &mut self.engine_state,
-            )?;
+            let mut drift = Vec::new();
+            let client = if offline {
+                crate::info!("Registering MCP client from its cached schema: {name}");
+                let Some(client) = McpServerConfig::to_offline_client(name)? else {
+                    crate::warning!(
+                        "No cached schema for '{}'; connect to it once without --offline",
+                        name
+                    );
+                    continue;
+                };
+                client
+            } else {
+                crate::info!("Registering MCP client: {name}");
+                let events = get_mcp_client_manager().await.events().clone();
+                let connect_timeout = config.connect_timeout(server)?;
+                let cwd = self.engine_state.cwd(Some(&self.stack))?;
+                if let Some(progress) = &progress {
+                    progress.connecting(name);
+                }
+                let connected = server
+                    .to_client(name, &events, connect_timeout, cwd.as_std_path())
+                    .await;
+                if let Some(progress) = &progress {
+                    progress.finished(connected.is_ok());
+                }
+                let client = connected?;
+                match SchemaSnapshot::load(name) {
+                    Ok(Some(cached)) => {
+                        drift = schema_drift(&cached.tools, client.get_tools());
+                        report_drift(name, &drift);
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        log::warn!("Failed to read the cached schema for '{name}': {err:#}")
+                    }
+                }
+                if let Err(err) = client.snapshot().save(name) {
+                    log::warn!("Failed to cache the schema for '{name}': {err:#}");
+                }
+                client
+            };
+
+            let mut manager = get_mcp_client_manager().await;
+            manager.register_client(name.clone(), &client, &mut self.engine_state)?;
+            manager.set_drift(name, drift);
+        }
+        get_mcp_client_manager().await.notify_completer();
+        if let Some(progress) = progress {
+            progress.finish();
+        }
+
+        if !offline {
+            for (name, server) in &config.servers {
+                if let Some(interval) = server.auth_refresh_interval {
+                    crate::util::auth::spawn_auto_refresh(name.clone(), interval.0);
+                }
+            }
         }
 
+        self.print_server_banner().await;
+
         Ok(())
     }
 
+    /// Print a table of the registered servers once they're all connected
+    async fn print_server_banner(&mut self) {
+        let rows: Vec<Value> = {
+            let manager = get_mcp_client_manager().await;
+            let span = Span::unknown();
+            manager
+                .get_servers()
+                .iter()
+                .map(|(name, server)| {
+                    let summary = server_summary(name, server, manager.config(), span);
+                    let mut row = Record::new();
+                    for column in ["name", "status", "transport", "tools"] {
+                        if let Some(value) = summary.get(column) {
+                            row.push(column, value.clone());
+                        }
+                    }
+                    Value::record(row, span)
+                })
+                .collect()
+        };
+
+        if rows.is_empty() {
+            return;
+        }
+
+        crate::info!(
+            "MCP servers:\n{}",
+            render_table(&self.engine_state, &mut self.stack, rows)
+        );
+    }
+
     /// Run the REPL with support for dynamic command registration
-    pub fn run(&mut self) -> Result<()> {
+    pub fn run(&mut self, startup: &StartupCommands) -> Result<()> {
+        self.run_startup(startup)?;
+
         // Run Nushell REPL for one session
         let start_time = Instant::now();
         let repl_result = nu_cli::evaluate_repl(
@@ -179,28 +353,141 @@ impl McpRepl {
             None, // load_std_lib
             start_time,
         );
+        crate::util::status::OUTPUT.prompt_left();
 
         repl_result.map_err(|e| anyhow::anyhow!("Error during REPL evaluation: {}", e))
     }
 
-    /// Create a custom history configuration for MCP-REPL
-    fn create_custom_history_config() -> Result<HistoryConfig> {
-        // Create a custom history path in the user's home directory
-        let home_dir = dirs::home_dir().context("Could not determine home directory")?;
-        let mcp_repl_dir = home_dir.join(".mcp-repl");
+    /// Run a script non-interactively and return when it's done
+    ///
+    /// With a `max_runtime`, a script that is still running when it runs out
+    /// is cancelled and the process exits with
+    /// [`MAX_RUNTIME_EXIT_CODE`](crate::util::max_runtime::MAX_RUNTIME_EXIT_CODE).
+    pub fn run_commands(
+        &mut self,
+        startup: &StartupCommands,
+        commands: String,
+        max_runtime: Option<Duration>,
+    ) -> Result<()> {
+        self.engine_state.is_interactive = false;
+
+        let interrupt = Arc::new(AtomicBool::new(false));
+        self.engine_state
+            .set_signals(Signals::new(interrupt.clone()));
+
+        let budget = max_runtime.map(|max_runtime| {
+            RuntimeBudget::arm(max_runtime, interrupt, move |stage| {
+                exit_over_budget(max_runtime, stage)
+            })
+        });
+
+        // Startup commands count against the budget too, so one that hangs
+        // can't keep the script from ever finishing
+        let result = self.run_startup(startup).and_then(|()| {
+            nu_cli::evaluate_commands(
+                &Spanned {
+                    item: commands,
+                    span: Span::unknown(),
+                },
+                &mut self.engine_state,
+                &mut self.stack.clone(),
+                PipelineData::empty(),
+                EvaluateCommandsOpts {
+                    table_mode: None,
+                    error_style: None,
+                    no_newline: false,
+                },
+            )
+            .map_err(|e| anyhow::anyhow!("Error while running --commands: {}", e))
+        });
 
-        // Create the directory if it doesn't exist
-        if !mcp_repl_dir.exists() {
-            std::fs::create_dir_all(&mcp_repl_dir)
-                .context("Failed to create .mcp-repl directory")?;
+        if let Some(budget) = budget {
+            budget.finish();
         }
 
-        // Use a custom history file
-        let history_file = mcp_repl_dir.join("history.txt");
-        info!("Using custom history file: {}", history_file.display());
+        result
+    }
+
+    /// Run the startup commands in order
+    ///
+    /// A command that fails is reported and the rest still run, unless
+    /// `strict` is set; then the failure is returned and nothing after it
+    /// runs.
+    fn run_startup(&mut self, startup: &StartupCommands) -> Result<()> {
+        for (index, command) in startup.commands.iter().enumerate() {
+            let Err(err) = self.eval_source(command, "startup", true) else {
+                continue;
+            };
+
+            let problem = format!(
+                "Startup command {} failed: {}\n  the command was: {command}",
+                index + 1,
+                error_text(&err)
+            );
+            if startup.strict {
+                return Err(
+                    anyhow!(problem).context("startup_strict is set, so the REPL won't start")
+                );
+            }
+            crate::warning!("{}", problem);
+        }
+
+        Ok(())
+    }
+
+    /// Parse and run `source` on the REPL's own engine and stack, printing
+    /// its output as the prompt would
+    ///
+    /// What it defines, such as an `alias` or `$env` variable, is kept for
+    /// the rest of the session. Unlike `nu_cli::evaluate_commands`, a parse
+    /// error is returned instead of exiting the process. When `restricted`,
+    /// it's parsed without the `http` and `source` commands, which the
+    /// session gets back afterwards; see `util::eval`.
+    pub fn eval_source(
+        &mut self,
+        source: &str,
+        fname: &str,
+        restricted: bool,
+    ) -> Result<(), ShellError> {
+        let mut working_set = StateWorkingSet::new(&self.engine_state);
+        let hidden = if restricted {
+            eval::hide_restricted(&mut working_set)
+        } else {
+            Vec::new()
+        };
+        let block = nu_parser::parse(&mut working_set, Some(fname), source.as_bytes(), false);
+        eval::unhide_commands(&mut working_set, hidden);
+
+        if let Some(err) = working_set.parse_errors.first() {
+            return Err(ShellError::GenericError {
+                error: "Parse error".into(),
+                msg: err.to_string(),
+                span: Some(err.span()),
+                help: None,
+                inner: Vec::new(),
+            });
+        }
 
-        // The history file path will be used in custom configuration
+        let delta = working_set.render();
+        self.engine_state.merge_delta(delta)?;
+
+        let data = nu_engine::eval_block::<WithoutDebug>(
+            &self.engine_state,
+            &mut self.stack,
+            &block,
+            PipelineData::empty(),
+        )?;
+        if let PipelineData::Value(Value::Error { error, .. }, _) = data {
+            return Err(*error);
+        }
+        data.print_table(&self.engine_state, &mut self.stack, false, false)
+    }
 
+    /// Create a custom history configuration for MCP-REPL
+    ///
+    /// When there is nowhere to keep the history file, history is only kept
+    /// for the session instead of stopping the REPL from starting.
+    fn create_custom_history_config() -> HistoryConfig {
         // Create a custom history configuration
         let history_config = HistoryConfig {
             file_format: HistoryFileFormat::Plaintext,
@@ -209,18 +496,46 @@ impl McpRepl {
             isolation: true, // Ensure MCP REPL history is isolated from standard Nushell history
         };
 
+        // The history file goes in ~/.mcp-repl, or $MCP_STATE_DIR
+        let mcp_repl_dir = match state_dir::state_dir().and_then(state_dir::create) {
+            Ok(dir) => dir,
+            Err(err) => {
+                crate::warning!("History won't be saved: {:#}", err);
+                return history_config;
+            }
+        };
+
+        // Use a custom history file
+        let history_file = mcp_repl_dir.join("history.txt");
+        info!("Using custom history file: {}", history_file.display());
+
         // Store the history file path for reference and debug it
         debug!("Custom MCP history file set at: {}", history_file.display());
 
         // Update the history path in the static
-        let rt = Runtime::new()?;
-        let history_path = rt.block_on(async {
+        let history_path = crate::engine::shared_runtime().block_on(async {
             HISTORY_PATH
                 .get_or_init(|| async { Mutex::new(None) })
                 .await
         });
         *history_path.lock_blocking() = Some(history_file.to_string_lossy().to_string());
 
-        Ok(history_config)
+        history_config
     }
 }
+
+/// Print one line about the tools that changed since the last session
+fn report_drift(server: &str, drift: &[SchemaDrift]) {
+    if drift.is_empty() {
+        return;
+    }
+
+    let tools: Vec<&str> = drift.iter().map(|drift| drift.tool.as_str()).collect();
+    crate::warning!(
+        "{}: {} {} changed since last session ({}); run `tool diagnostics --drift` for details",
+        server,
+        tools.len(),
+        if tools.len() == 1 { "tool" } else { "tools" },
+        tools.join(", ")
+    );
+}
@@ -1,7 +1,40 @@
 use nu_protocol::{IntoPipelineData, PipelineData, Span, Value};
+pub mod annotations;
+pub mod args_file;
+pub mod auth;
+pub mod cache;
+pub mod completer;
+pub mod connect_progress;
+pub mod deprecation;
 pub mod error;
+pub mod eval;
+pub mod events;
 pub mod format;
+pub mod glob;
+pub mod hash;
+pub mod json_numbers;
+pub mod json_stream;
+pub mod last_call;
+pub mod last_error;
+pub mod max_runtime;
+pub mod memory;
+pub mod mime;
+pub mod payload_log;
+pub mod program;
+pub mod prompt;
+pub mod reconnect;
+pub mod resource_link;
+pub mod sampling;
+pub mod snapshot;
+pub mod spill;
+pub mod sse;
+pub mod state_dir;
+pub mod stats;
 pub mod status;
+pub mod tasks;
+pub mod telemetry;
+pub mod transport;
+pub mod uri_template;
 
 #[derive(Clone, Debug, Default)]
 pub struct NuValueMap {
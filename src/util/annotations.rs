@@ -0,0 +1,177 @@
+//! The `audience` and `priority` annotations on a result's content blocks
+//!
+//! Servers use them to say which blocks of a result are meant for the user
+//! and which for the model, and which matter most. Like tool annotations,
+//! they're read from the blocks' JSON rather than the client's types.
+
+use nu_protocol::{Record, ShellError, Span, Spanned, Value};
+use rmcp::model::Content;
+use serde_json::Value as JsonValue;
+
+/// Who a call's result blocks are kept for (`--audience`)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Audience {
+    #[default]
+    All,
+    User,
+    Assistant,
+}
+
+impl Audience {
+    /// Parse the value of `--audience`
+    pub fn parse(value: &Spanned<String>) -> Result<Self, ShellError> {
+        match value.item.as_str() {
+            "all" => Ok(Self::All),
+            "user" => Ok(Self::User),
+            "assistant" => Ok(Self::Assistant),
+            other => Err(ShellError::GenericError {
+                error: format!("Unknown audience '{other}'"),
+                msg: "expected user, assistant or all".into(),
+                span: Some(value.span),
+                help: None,
+                inner: Vec::new(),
+            }),
+        }
+    }
+
+    /// Keep the blocks meant for this audience
+    ///
+    /// A block without an `audience` annotation is meant for everyone.
+    #[must_use]
+    pub fn filter(self, contents: Vec<Content>) -> Vec<Content> {
+        let role = match self {
+            Self::All => return contents,
+            Self::User => "user",
+            Self::Assistant => "assistant",
+        };
+
+        contents
+            .into_iter()
+            .filter(|content| {
+                BlockAnnotations::of(content).is_none_or(|annotations| {
+                    annotations.audience.is_empty()
+                        || annotations.audience.iter().any(|audience| audience == role)
+                })
+            })
+            .collect()
+    }
+}
+
+/// The annotations of a single content block
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BlockAnnotations {
+    pub audience: Vec<String>,
+    pub priority: Option<f64>,
+}
+
+impl BlockAnnotations {
+    /// The block's annotations, or `None` if it has neither an audience nor
+    /// a priority
+    #[must_use]
+    pub fn of(content: &Content) -> Option<Self> {
+        let json = serde_json::to_value(&content.annotations).ok()?;
+
+        let audience: Vec<String> = json
+            .get("audience")
+            .and_then(JsonValue::as_array)
+            .map(|roles| {
+                roles
+                    .iter()
+                    .filter_map(JsonValue::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let priority = json.get("priority").and_then(JsonValue::as_f64);
+
+        (!audience.is_empty() || priority.is_some()).then_some(Self { audience, priority })
+    }
+
+    /// A block's converted value as a row with `audience` and `priority` columns
+    #[must_use]
+    pub fn to_row(annotations: Option<&Self>, content: Value, span: Span) -> Value {
+        let mut record = Record::new();
+        record.push("content", content);
+        record.push(
+            "audience",
+            match annotations {
+                Some(annotations) if !annotations.audience.is_empty() => Value::list(
+                    annotations
+                        .audience
+                        .iter()
+                        .map(|audience| Value::string(audience, span))
+                        .collect(),
+                    span,
+                ),
+                _ => Value::nothing(span),
+            },
+        );
+        record.push(
+            "priority",
+            annotations
+                .and_then(|annotations| annotations.priority)
+                .map_or_else(
+                    || Value::nothing(span),
+                    |priority| Value::float(priority, span),
+                ),
+        );
+        Value::record(record, span)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rmcp::model::RawContent;
+    use serde_json::json;
+
+    use super::*;
+
+    fn block(text: &str, annotations: JsonValue) -> Content {
+        serde_json::from_value(json!({"type": "text", "text": text, "annotations": annotations}))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_filter_by_audience() {
+        let text = |contents: Vec<Content>| -> Vec<String> {
+            contents
+                .into_iter()
+                .map(|content| match content.raw {
+                    RawContent::Text(text) => text.text,
+                    _ => unreachable!("only text blocks are filtered"),
+                })
+                .collect()
+        };
+        let contents = vec![
+            block("summary", json!({"audience": ["user"], "priority": 1.0})),
+            block(
+                "details",
+                json!({"audience": ["assistant"], "priority": 0.2}),
+            ),
+            block("both", json!({"audience": ["user", "assistant"]})),
+            Content::text("plain"),
+        ];
+
+        assert_eq!(
+            text(Audience::User.filter(contents.clone())),
+            ["summary", "both", "plain"]
+        );
+        assert_eq!(
+            text(Audience::Assistant.filter(contents.clone())),
+            ["details", "both", "plain"]
+        );
+        assert_eq!(text(Audience::All.filter(contents)).len(), 4);
+    }
+
+    #[test]
+    fn test_block_annotations() {
+        assert_eq!(
+            BlockAnnotations::of(&block("a", json!({"audience": ["user"], "priority": 0.5}))),
+            Some(BlockAnnotations {
+                audience: vec!["user".into()],
+                priority: Some(0.5),
+            })
+        );
+        assert_eq!(BlockAnnotations::of(&Content::text("a")), None);
+    }
+}
@@ -0,0 +1,241 @@
+//! Tool arguments read from a file with `--args-file`
+
+use nu_protocol::{
+    ShellError, Span, Spanned,
+    engine::{EngineState, Stack},
+};
+use serde_json::Value as JsonValue;
+
+use crate::commands::utils::convert_nu_value_to_json_value;
+
+/// The extensions `--args-file` knows how to parse
+const ARGS_FILE_FORMATS: &[&str] = &["json", "nuon", "toml", "yaml", "yml"];
+
+/// Read the arguments object from an `--args-file`
+///
+/// The file is parsed according to its extension. A relative path is
+/// resolved against the engine's working directory.
+pub fn read_args_file(
+    engine_state: &EngineState,
+    stack: &Stack,
+    path: &Spanned<String>,
+) -> Result<serde_json::Map<String, JsonValue>, ShellError> {
+    let cwd = engine_state.cwd(Some(stack))?;
+    let resolved = nu_path::expand_path_with(&path.item, &cwd, true);
+
+    let extension = resolved
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    if !ARGS_FILE_FORMATS.contains(&extension.as_str()) {
+        return Err(ShellError::GenericError {
+            error: format!("Can't tell the format of '{}'", resolved.display()),
+            msg: "unknown file extension".into(),
+            span: Some(path.span),
+            help: Some(format!(
+                "--args-file reads {} files",
+                ARGS_FILE_FORMATS.join(", ")
+            )),
+            inner: Vec::new(),
+        });
+    }
+
+    let text = std::fs::read_to_string(&resolved).map_err(|err| ShellError::GenericError {
+        error: format!("Failed to read '{}'", resolved.display()),
+        msg: err.to_string(),
+        span: Some(path.span),
+        help: None,
+        inner: Vec::new(),
+    })?;
+
+    let args =
+        parse_args(&text, &extension, path.span).map_err(|problem| ShellError::GenericError {
+            error: format!("Failed to parse '{}'", resolved.display()),
+            msg: problem,
+            span: Some(path.span),
+            help: Some(format!("--args-file expects a {extension} object")),
+            inner: Vec::new(),
+        })?;
+
+    match args {
+        JsonValue::Object(args) => Ok(args),
+        other => Err(ShellError::GenericError {
+            error: format!("'{}' doesn't hold an object", resolved.display()),
+            msg: format!("got {}", json_kind(&other)),
+            span: Some(path.span),
+            help: Some("The file must hold a single object of tool arguments".into()),
+            inner: Vec::new(),
+        }),
+    }
+}
+
+/// Add the arguments from `--args-file`, if it was given, to the explicit ones
+pub fn with_args_file(
+    engine_state: &EngineState,
+    stack: &Stack,
+    path: Option<&Spanned<String>>,
+    explicit: serde_json::Map<String, JsonValue>,
+) -> Result<serde_json::Map<String, JsonValue>, ShellError> {
+    match path {
+        Some(path) => Ok(merge_args(
+            read_args_file(engine_state, stack, path)?,
+            explicit,
+            &path.item,
+        )),
+        None => Ok(explicit),
+    }
+}
+
+/// Merge arguments from a file with the ones given on the command line
+///
+/// An argument given explicitly replaces the file's value, with a warning
+/// so the override doesn't go unnoticed.
+pub fn merge_args(
+    mut from_file: serde_json::Map<String, JsonValue>,
+    explicit: serde_json::Map<String, JsonValue>,
+    path: &str,
+) -> serde_json::Map<String, JsonValue> {
+    for (name, value) in explicit {
+        if from_file
+            .get(&name)
+            .is_some_and(|existing| *existing != value)
+        {
+            crate::warning!(
+                "'{}' is set in {} and on the command line; using the command line value",
+                name,
+                path
+            );
+        }
+        from_file.insert(name, value);
+    }
+
+    from_file
+}
+
+/// Parse the text of an arguments file, reporting where a syntax error is
+fn parse_args(text: &str, extension: &str, span: Span) -> Result<JsonValue, String> {
+    match extension {
+        "json" => {
+            serde_json::from_str(text).map_err(|err| at(err.line(), err.column(), &err.to_string()))
+        }
+        "toml" => toml_edit::de::from_str(text).map_err(|err| {
+            let message = err.message().trim().to_string();
+            match err.span() {
+                Some(range) => {
+                    let (line, column) = line_column(text, range.start);
+                    at(line, column, &message)
+                }
+                None => message,
+            }
+        }),
+        "yaml" | "yml" => serde_yaml::from_str(text).map_err(|err| match err.location() {
+            Some(location) => at(location.line(), location.column(), &err.to_string()),
+            None => err.to_string(),
+        }),
+        _ => {
+            let value =
+                nuon::from_nuon(text, Some(span)).map_err(|err| nuon_problem(text, &err))?;
+            convert_nu_value_to_json_value(&value, span).map_err(|err| err.to_string())
+        }
+    }
+}
+
+/// Describe a nuon error, with the position of the parse error it wraps
+fn nuon_problem(text: &str, err: &ShellError) -> String {
+    let position = match err {
+        ShellError::GenericError { inner, .. } => inner.iter().find_map(|inner| match inner {
+            ShellError::OutsideSpannedLabeledError { msg, span, .. } => {
+                Some((msg.clone(), span.start))
+            }
+            _ => None,
+        }),
+        _ => None,
+    };
+
+    match position {
+        Some((msg, offset)) if offset <= text.len() => {
+            let (line, column) = line_column(text, offset);
+            at(line, column, &msg)
+        }
+        _ => err.to_string(),
+    }
+}
+
+fn at(line: usize, column: usize, message: &str) -> String {
+    format!("line {line}, column {column}: {message}")
+}
+
+/// The 1-based line and column of a byte offset
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .map_or(0, |last| last.chars().count())
+        + 1;
+    (line, column)
+}
+
+const fn json_kind(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "a boolean",
+        JsonValue::Number(_) => "a number",
+        JsonValue::String(_) => "a string",
+        JsonValue::Array(_) => "a list",
+        JsonValue::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_args_by_extension() {
+        let span = Span::test_data();
+        let expected = json!({"name": "api", "replicas": 3});
+
+        assert_eq!(
+            parse_args(r#"{"name": "api", "replicas": 3}"#, "json", span).unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_args("{name: api, replicas: 3}", "nuon", span).unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_args("name = \"api\"\nreplicas = 3\n", "toml", span).unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_args("name: api\nreplicas: 3\n", "yaml", span).unwrap(),
+            expected
+        );
+
+        let problem = parse_args("{\n  \"name\": \"api\",\n  oops\n}", "json", span).unwrap_err();
+        assert!(problem.starts_with("line 3, column 3:"), "{problem}");
+        let problem = parse_args("name = \"api\"\nreplicas = \n", "toml", span).unwrap_err();
+        assert!(problem.starts_with("line 2,"), "{problem}");
+    }
+
+    #[test]
+    fn test_explicit_args_win() {
+        let from_file = json!({"name": "api", "replicas": 3});
+        let explicit = json!({"replicas": 5});
+        let (JsonValue::Object(from_file), JsonValue::Object(explicit)) = (from_file, explicit)
+        else {
+            unreachable!()
+        };
+
+        let merged = merge_args(from_file, explicit, "spec.json");
+        assert_eq!(
+            JsonValue::Object(merged),
+            json!({"name": "api", "replicas": 5})
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! Picking up rotated credentials, for `mcp auth refresh` and
+//! `auth_refresh_interval`
+//!
+//! Neither transport can change its credentials on a live connection: the
+//! SSE transport is started from a URL alone, with no request headers to
+//! update, and a command server reads its `env` once, when it starts. So a
+//! refresh always reconnects, which reads the server's configuration and
+//! environment again. Tool calls made meanwhile wait for the new connection
+//! when the server sets `queue_while_reconnecting`.
+
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+
+use super::tasks;
+use crate::{config::McpConnectionType, engine::get_mcp_client_manager};
+
+/// Why refreshing this server's credentials needs a reconnect
+#[must_use]
+pub const fn reconnect_reason(connection: &McpConnectionType) -> &'static str {
+    match connection {
+        McpConnectionType::Sse { .. } => "the SSE transport has no request headers to update",
+        McpConnectionType::Command { .. } => {
+            "a command server reads its environment only when it starts"
+        }
+    }
+}
+
+/// Reconnect to `server` every `interval`, for as long as it's configured so
+pub fn spawn_auto_refresh(server: String, interval: Duration) {
+    tasks::spawn(format!("auth refresh for '{server}'"), async move {
+        loop {
+            tokio::time::sleep(interval).await;
+
+            match refresh(&server, interval).await {
+                Ok(true) => log::info!("Refreshed the credentials for '{server}'"),
+                Ok(false) => return,
+                Err(err) => {
+                    let msg = format!("{err:#}");
+                    crate::warning!(
+                        "Failed to refresh the credentials for '{}': {}",
+                        server,
+                        msg
+                    );
+                    get_mcp_client_manager().await.mark_failed(&server, msg);
+                }
+            }
+        }
+    });
+}
+
+/// Reconnect once; false if the server no longer refreshes at this interval
+///
+/// The tools aren't registered again, since that needs the engine state;
+/// commands keep working because they share the reconnected client's
+/// connection. `mcp restart` picks up tools that changed.
+async fn refresh(server: &str, interval: Duration) -> Result<bool> {
+    let manager = get_mcp_client_manager().await;
+    if manager.is_offline() {
+        return Ok(false);
+    }
+
+    let (Some(registered), Some(config)) = (
+        manager.get_server(server),
+        manager.config().servers.get(server),
+    ) else {
+        return Ok(false);
+    };
+    if config.auth_refresh_interval.map(|interval| interval.0) != Some(interval) {
+        return Ok(false);
+    }
+
+    let client = registered.client.clone();
+    let config = config.clone();
+    let events = manager.events().clone();
+    let connect_timeout = manager.config().connect_timeout(&config)?;
+    drop(manager);
+
+    let cwd = std::env::current_dir()
+        .map_err(|err| anyhow!("can't read the current directory: {err}"))?;
+    config
+        .restart_client(&client, &events, connect_timeout, &cwd)
+        .await?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconnect_reason() {
+        let sse = McpConnectionType::Sse {
+            url: "http://localhost:8080/sse".into(),
+            path: None,
+            connect_timeout: None,
+        };
+        let command = McpConnectionType::Command {
+            command: "server".into(),
+            env: None,
+            connect_timeout: None,
+        };
+
+        assert!(reconnect_reason(&sse).contains("SSE"));
+        assert!(reconnect_reason(&command).contains("environment"));
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError, RwLock},
+    time::{Duration, Instant},
+};
+
+use rmcp::model::Content;
+
+use super::memory::{Limit, Store, Usage};
+
+/// The limit of each server's cache, from `memory.results`
+static LIMIT: RwLock<Limit> = RwLock::new(Store::Results.default_limit());
+
+/// Keep every cache within `limit` from its next insert on
+pub fn set_limit(limit: Limit) {
+    *LIMIT.write().unwrap_or_else(PoisonError::into_inner) = limit;
+}
+
+fn limit() -> Limit {
+    *LIMIT.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A cache of tool results keyed by tool name and arguments
+///
+/// Entries are only served while they are younger than the TTL passed to
+/// [`ToolResultCache::get`], which comes from the tool's `cached_ttl` setting.
+/// When the cache holds more than its limit, the least recently used
+/// entries are evicted.
+#[derive(Clone, Debug, Default)]
+pub struct ToolResultCache {
+    entries: Arc<Mutex<CachedResults>>,
+}
+
+#[derive(Debug, Default)]
+struct CachedResults {
+    results: HashMap<String, CachedResult>,
+    bytes: usize,
+    evicted: u64,
+}
+
+#[derive(Debug)]
+struct CachedResult {
+    stored_at: Instant,
+    last_used: Instant,
+    /// Approximate size of the key and contents
+    size: usize,
+    contents: Vec<Content>,
+}
+
+impl ToolResultCache {
+    /// Build the cache key for a call
+    #[must_use]
+    pub fn key(tool_name: &str, params: &serde_json::Map<String, serde_json::Value>) -> String {
+        format!(
+            "{tool_name}\u{0}{}",
+            serde_json::Value::Object(params.clone())
+        )
+    }
+
+    /// Look up a cached result that is younger than `ttl`
+    #[must_use]
+    pub fn get(&self, key: &str, ttl: Duration) -> Option<Vec<Content>> {
+        let mut entries = self.entries.lock().ok()?;
+
+        match entries.results.get_mut(key) {
+            Some(entry) if entry.stored_at.elapsed() <= ttl => {
+                entry.last_used = Instant::now();
+                Some(entry.contents.clone())
+            }
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Drop every cached result for a tool, whatever its arguments
+    pub fn invalidate_tool(&self, tool_name: &str) {
+        let prefix = format!("{tool_name}\u{0}");
+        if let Ok(mut entries) = self.entries.lock() {
+            let stale: Vec<_> = entries
+                .results
+                .keys()
+                .filter(|key| key.starts_with(&prefix))
+                .cloned()
+                .collect();
+            for key in stale {
+                entries.remove(&key);
+            }
+        }
+    }
+
+    /// Store a result in the cache, within the `memory.results` limit
+    pub fn insert(&self, key: String, contents: Vec<Content>) {
+        self.insert_within(key, contents, limit());
+    }
+
+    /// Store a result, then evict the least recently used ones until the
+    /// cache is within `limit`
+    pub fn insert_within(&self, key: String, contents: Vec<Content>, limit: Limit) {
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+
+        let size = key.len() + serde_json::to_string(&contents).map_or(0, |json| json.len());
+        entries.remove(&key);
+        entries.bytes += size;
+        let now = Instant::now();
+        entries.results.insert(
+            key,
+            CachedResult {
+                stored_at: now,
+                last_used: now,
+                size,
+                contents,
+            },
+        );
+
+        while limit.exceeded_by(entries.results.len(), entries.bytes) {
+            let Some(oldest) = entries
+                .results
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            entries.remove(&oldest);
+            entries.evicted += 1;
+        }
+    }
+
+    /// How many results are cached and their approximate size
+    #[must_use]
+    pub fn usage(&self) -> Usage {
+        let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        Usage {
+            entries: entries.results.len(),
+            bytes: entries.bytes,
+            evicted: entries.evicted,
+        }
+    }
+}
+
+impl CachedResults {
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.results.remove(key) {
+            self.bytes -= entry.size;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalidate_tool_keeps_other_tools() {
+        let cache = ToolResultCache::default();
+        let params = serde_json::Map::new();
+        let ttl = Duration::from_secs(60);
+
+        for tool in ["search", "search_v2"] {
+            cache.insert(ToolResultCache::key(tool, &params), Vec::new());
+        }
+        cache.invalidate_tool("search");
+
+        assert!(
+            cache
+                .get(&ToolResultCache::key("search", &params), ttl)
+                .is_none()
+        );
+        assert!(
+            cache
+                .get(&ToolResultCache::key("search_v2", &params), ttl)
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let cache = ToolResultCache::default();
+        let ttl = Duration::from_secs(60);
+        let key = |query: &str| {
+            let mut params = serde_json::Map::new();
+            params.insert("q".into(), query.into());
+            ToolResultCache::key("search", &params)
+        };
+        let result = |text: &str| vec![Content::text(text)];
+        let size = |query: &str, text: &str| {
+            key(query).len() + serde_json::to_string(&result(text)).unwrap().len()
+        };
+
+        let limit = Limit {
+            entries: 2,
+            bytes: 1024,
+        };
+        cache.insert_within(key("a"), result("first"), limit);
+        cache.insert_within(key("b"), result("second"), limit);
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(cache.get(&key("a"), ttl).is_some());
+        cache.insert_within(key("c"), result("third"), limit);
+
+        assert!(cache.get(&key("b"), ttl).is_none());
+        assert!(cache.get(&key("a"), ttl).is_some());
+        assert_eq!(
+            cache.usage(),
+            Usage {
+                entries: 2,
+                bytes: size("a", "first") + size("c", "third"),
+                evicted: 1,
+            }
+        );
+
+        // A result bigger than the byte limit evicts everything, itself too
+        let limit = Limit {
+            entries: 10,
+            bytes: size("d", "fourth"),
+        };
+        cache.insert_within(key("d"), result("fourth"), limit);
+        assert_eq!(
+            cache.usage(),
+            Usage {
+                entries: 1,
+                bytes: size("d", "fourth"),
+                evicted: 3,
+            }
+        );
+        cache.insert_within(key("e"), result(&"x".repeat(100)), limit);
+        assert_eq!(cache.usage().entries, 0);
+        assert_eq!(cache.usage().bytes, 0);
+        assert_eq!(cache.usage().evicted, 5);
+    }
+}
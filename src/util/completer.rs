@@ -0,0 +1,84 @@
+//! Telling an external completer that the tool commands changed
+//!
+//! Completers like carapace only know the commands they were given. One
+//! that runs for a long time can listen on a unix socket and set
+//! `MCP_COMPLETER_SOCKET` to its path; whenever the registered tools change,
+//! a line of JSON is written to it, and the completer can run
+//! `tool completions-dump` again.
+
+use serde_json::json;
+
+/// The environment variable naming the completer's socket
+pub const COMPLETER_SOCKET_ENV: &str = "MCP_COMPLETER_SOCKET";
+
+/// The line written to the socket, e.g. `{"event":"tools_changed","commands":42}`
+#[must_use]
+pub fn tools_changed_message(commands: usize) -> String {
+    format!(
+        "{}\n",
+        json!({"event": "tools_changed", "commands": commands})
+    )
+}
+
+/// Notify the completer named by `MCP_COMPLETER_SOCKET`, if there is one
+///
+/// A completer that isn't listening is only logged: completions going
+/// stale isn't worth failing a restart over.
+pub fn notify_tools_changed(commands: usize) {
+    let Some(path) = std::env::var_os(COMPLETER_SOCKET_ENV) else {
+        return;
+    };
+
+    if let Err(err) = send(
+        std::path::Path::new(&path),
+        &tools_changed_message(commands),
+    ) {
+        log::debug!(
+            "Failed to notify the completer at {}: {err}",
+            path.to_string_lossy()
+        );
+    }
+}
+
+#[cfg(unix)]
+fn send(path: &std::path::Path, message: &str) -> std::io::Result<()> {
+    use std::{io::Write, os::unix::net::UnixStream, time::Duration};
+
+    let mut stream = UnixStream::connect(path)?;
+    stream.set_write_timeout(Some(Duration::from_millis(200)))?;
+    stream.write_all(message.as_bytes())
+}
+
+#[cfg(not(unix))]
+fn send(_path: &std::path::Path, _message: &str) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "completer sockets are only supported on unix",
+    ))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{io::Read, os::unix::net::UnixListener};
+
+    use super::*;
+
+    #[test]
+    fn test_send_tools_changed() {
+        let path =
+            std::env::temp_dir().join(format!("mcp-repl-completer-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        send(&path, &tools_changed_message(3)).unwrap();
+
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut received = String::new();
+        stream.read_to_string(&mut received).unwrap();
+        assert!(received.ends_with('\n'));
+        let message: serde_json::Value = serde_json::from_str(&received).unwrap();
+        assert_eq!(message, json!({"event": "tools_changed", "commands": 3}));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
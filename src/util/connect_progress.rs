@@ -0,0 +1,217 @@
+//! The status line shown while the servers connect at startup
+//!
+//! Servers connect one after another, and a slow one used to leave nothing
+//! on screen between "Registering MCP client" and the banner, or a timeout.
+//! [`ConnectProgress`] keeps a line at the bottom of stderr that says which
+//! server it's waiting for and for how long, redrawn by a background thread
+//! through the output broker, and marks each server as it connects or fails.
+//! When stderr isn't a terminal, or with `--quiet`, only the summary at the
+//! end is written.
+
+use std::{
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use super::status::{OUTPUT, Stream};
+
+/// The spinner's frames, one per redraw
+const FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// How often the status line is redrawn
+const TICK: Duration = Duration::from_millis(100);
+
+/// Progress through connecting the configured servers
+pub struct ConnectProgress {
+    state: Arc<Mutex<Progress>>,
+    /// Whether the status line and the per-server marks are shown
+    live: bool,
+    stop: Arc<AtomicBool>,
+    ticker: Option<JoinHandle<()>>,
+}
+
+#[derive(Debug)]
+struct Progress {
+    total: usize,
+    connected: usize,
+    failed: usize,
+    /// The server being connected, and since when
+    current: Option<(String, Instant)>,
+    started: Instant,
+}
+
+impl ConnectProgress {
+    /// Start on `total` servers, with a status line if `live`
+    #[must_use]
+    pub fn start(total: usize, live: bool) -> Self {
+        let state = Arc::new(Mutex::new(Progress::new(total, Instant::now())));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let ticker = (live && total > 0).then(|| {
+            let (state, stop) = (state.clone(), stop.clone());
+            thread::spawn(move || {
+                for frame in FRAMES.iter().cycle() {
+                    if stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let line = lock(&state).status_line(frame, Instant::now());
+                    OUTPUT.show_status_line(&line);
+                    thread::sleep(TICK);
+                }
+            })
+        });
+
+        Self {
+            state,
+            live,
+            stop,
+            ticker,
+        }
+    }
+
+    /// Note that `server` is being connected
+    pub fn connecting(&self, server: &str) {
+        lock(&self.state).current = Some((server.to_string(), Instant::now()));
+    }
+
+    /// Note whether the server being connected made it
+    pub fn finished(&self, connected: bool) {
+        let mark = {
+            let mut state = lock(&self.state);
+            if connected {
+                state.connected += 1;
+            } else {
+                state.failed += 1;
+            }
+            state.current.take().map(|(server, since)| {
+                let mark = if connected { "✓" } else { "✗" };
+                format!("{mark} {server} ({})\n", seconds(since.elapsed()))
+            })
+        };
+
+        if let Some(mark) = mark.filter(|_| self.live) {
+            OUTPUT.write(Stream::Stdout, &mark);
+        }
+    }
+
+    /// Take the status line down and report how the servers did
+    pub fn finish(mut self) {
+        self.stop_ticker();
+        let summary = lock(&self.state).summary(Instant::now());
+        if let Some(summary) = summary {
+            crate::info!("{}", summary);
+        }
+    }
+
+    fn stop_ticker(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(ticker) = self.ticker.take() {
+            let _ = ticker.join();
+            OUTPUT.clear_status_line();
+        }
+    }
+}
+
+impl Drop for ConnectProgress {
+    /// A failed startup doesn't leave the status line behind
+    fn drop(&mut self) {
+        self.stop_ticker();
+    }
+}
+
+impl Progress {
+    const fn new(total: usize, started: Instant) -> Self {
+        Self {
+            total,
+            connected: 0,
+            failed: 0,
+            current: None,
+            started,
+        }
+    }
+
+    fn status_line(&self, frame: &str, now: Instant) -> String {
+        let done = self.connected + self.failed;
+        let elapsed = now.duration_since(self.started).as_secs();
+        match &self.current {
+            Some((server, _)) => format!(
+                "{frame} Connecting to {server} ({}/{}), {elapsed}s elapsed…",
+                done + 1,
+                self.total
+            ),
+            None => format!(
+                "{frame} Connecting {done}/{}, {elapsed}s elapsed…",
+                self.total
+            ),
+        }
+    }
+
+    /// The closing line, or `None` when there was nothing to connect
+    fn summary(&self, now: Instant) -> Option<String> {
+        if self.total == 0 {
+            return None;
+        }
+
+        let took = seconds(now.duration_since(self.started));
+        Some(if self.failed == 0 {
+            format!(
+                "Connected {} of {} servers in {took}",
+                self.connected, self.total
+            )
+        } else {
+            format!(
+                "Connected {} of {} servers in {took}; {} failed",
+                self.connected, self.total, self.failed
+            )
+        })
+    }
+}
+
+/// A duration as seconds with one decimal, e.g. `12.3s`
+fn seconds(duration: Duration) -> String {
+    format!("{:.1}s", duration.as_secs_f64())
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_line_and_summary() {
+        let started = Instant::now();
+        let mut progress = Progress::new(5, started);
+        let later = started + Duration::from_millis(12_300);
+
+        assert_eq!(
+            progress.status_line("⠋", later),
+            "⠋ Connecting 0/5, 12s elapsed…"
+        );
+        progress.connected = 1;
+        progress.current = Some(("github".into(), started));
+        assert_eq!(
+            progress.status_line("⠙", later),
+            "⠙ Connecting to github (2/5), 12s elapsed…"
+        );
+
+        progress.connected = 5;
+        assert_eq!(
+            progress.summary(later).unwrap(),
+            "Connected 5 of 5 servers in 12.3s"
+        );
+        progress.connected = 4;
+        progress.failed = 1;
+        assert_eq!(
+            progress.summary(later).unwrap(),
+            "Connected 4 of 5 servers in 12.3s; 1 failed"
+        );
+        assert_eq!(Progress::new(0, started).summary(later), None);
+    }
+}
@@ -0,0 +1,257 @@
+//! Tools that their server marks as deprecated
+//!
+//! There's no standard marker, so several common ones are recognized: a
+//! `deprecated: true` keyword, an `x-deprecated` extension (`true`, the
+//! replacement's name, or a record with a `replacement`), or a description
+//! starting with `[deprecated]`. The keywords count on the tool itself, in
+//! its annotations, at the top of its input schema and in the schema's
+//! annotations. Like the `destructiveHint`, they're read from the tool's JSON
+//! rather than the client's types.
+//!
+//! The first call to a deprecated tool prints a warning, once per session;
+//! with `deny_deprecated = true` the call is refused instead.
+
+use std::{
+    collections::BTreeSet,
+    sync::{LazyLock, Mutex, PoisonError},
+};
+
+use nu_protocol::{ShellError, Span};
+use rmcp::model::Tool;
+use serde_json::Value as JsonValue;
+
+/// The description prefix that marks a tool as deprecated
+const DESCRIPTION_PREFIX: &str = "[deprecated]";
+
+/// Keys naming the tool to use instead, next to a `deprecated` keyword or
+/// inside an `x-deprecated` record
+const REPLACEMENT_KEYS: &[&str] = &["x-replacement", "replacement", "replacedBy", "replaced_by"];
+
+/// The tools that were already warned about this session, as `server.tool`
+static WARNED: LazyLock<Mutex<BTreeSet<String>>> = LazyLock::new(Mutex::default);
+
+/// A tool's deprecation marker
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Deprecation {
+    /// The tool to use instead, when the server named one
+    pub replacement: Option<String>,
+}
+
+impl Deprecation {
+    /// Whether `tool` is deprecated, and what replaces it
+    #[must_use]
+    pub fn of(tool: &Tool) -> Option<Self> {
+        let json = serde_json::to_value(tool).ok()?;
+        Self::of_json(&json)
+    }
+
+    fn of_json(tool: &JsonValue) -> Option<Self> {
+        let schema = tool.get("inputSchema");
+        let places = [
+            Some(tool),
+            tool.get("annotations"),
+            schema,
+            schema.and_then(|schema| schema.get("annotations")),
+        ];
+
+        let mut deprecated = tool
+            .get("description")
+            .and_then(JsonValue::as_str)
+            .is_some_and(has_description_prefix);
+        let mut replacement = None;
+        for marker in places.into_iter().flatten().filter_map(marker) {
+            deprecated = true;
+            replacement = replacement.or(marker.replacement);
+        }
+
+        deprecated.then_some(Self { replacement })
+    }
+
+    /// What's printed, or raised, when the tool is called
+    #[must_use]
+    pub fn message(&self, server: &str, tool: &str) -> String {
+        match &self.replacement {
+            Some(replacement) => {
+                format!("'{server}.{tool}' is deprecated; use '{replacement}' instead")
+            }
+            None => format!("'{server}.{tool}' is deprecated"),
+        }
+    }
+}
+
+/// The marker in one object of the tool's JSON, if it has one
+fn marker(object: &JsonValue) -> Option<Deprecation> {
+    let sibling_replacement = || replacement_in(object);
+
+    match object.get("x-deprecated") {
+        Some(JsonValue::Bool(true)) => {
+            return Some(Deprecation {
+                replacement: sibling_replacement(),
+            });
+        }
+        Some(JsonValue::String(replacement)) => {
+            return Some(Deprecation {
+                replacement: non_empty(replacement).or_else(sibling_replacement),
+            });
+        }
+        Some(details @ JsonValue::Object(_)) => {
+            return Some(Deprecation {
+                replacement: replacement_in(details).or_else(sibling_replacement),
+            });
+        }
+        _ => {}
+    }
+
+    (object.get("deprecated") == Some(&JsonValue::Bool(true))).then(|| Deprecation {
+        replacement: sibling_replacement(),
+    })
+}
+
+fn replacement_in(object: &JsonValue) -> Option<String> {
+    REPLACEMENT_KEYS
+        .iter()
+        .find_map(|key| object.get(key).and_then(JsonValue::as_str))
+        .and_then(non_empty)
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+fn has_description_prefix(description: &str) -> bool {
+    description
+        .trim_start()
+        .get(..DESCRIPTION_PREFIX.len())
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(DESCRIPTION_PREFIX))
+}
+
+/// Warn about a call to a deprecated tool, or refuse it with `deny_deprecated`
+///
+/// The warning is printed for the first call to each tool in a session.
+pub fn check_call(server: &str, tool: &Tool, deny: bool, span: Span) -> Result<(), ShellError> {
+    let Some(deprecation) = Deprecation::of(tool) else {
+        return Ok(());
+    };
+
+    if deny {
+        return Err(ShellError::GenericError {
+            error: format!("Tool '{server}.{}' is deprecated", tool.name),
+            msg: "calls to deprecated tools are denied by `deny_deprecated`".into(),
+            span: Some(span),
+            help: Some(deprecation.replacement.map_or_else(
+                || {
+                    format!(
+                        "Set `deny_deprecated = false` for servers.{server}.tools.{} to allow it",
+                        tool.name
+                    )
+                },
+                |replacement| format!("Use '{replacement}' instead"),
+            )),
+            inner: Vec::new(),
+        });
+    }
+
+    if first_use(server, &tool.name) {
+        crate::warning!("{}", deprecation.message(server, &tool.name));
+    }
+    Ok(())
+}
+
+/// Whether this is the first call to the tool this session
+fn first_use(server: &str, tool: &str) -> bool {
+    WARNED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(format!("{server}.{tool}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn replaced_by(name: &str) -> Option<Deprecation> {
+        Some(Deprecation {
+            replacement: Some(name.into()),
+        })
+    }
+
+    #[test]
+    fn test_deprecation_markers() {
+        let schema = json!({"type": "object"});
+        assert_eq!(
+            Deprecation::of_json(&json!({"name": "a", "inputSchema": schema.clone()})),
+            None
+        );
+        assert_eq!(
+            Deprecation::of_json(&json!({
+                "name": "a",
+                "inputSchema": {"type": "object", "deprecated": true}
+            })),
+            Some(Deprecation::default())
+        );
+        assert_eq!(
+            Deprecation::of_json(&json!({
+                "name": "a",
+                "inputSchema": {"type": "object", "x-deprecated": {"replacement": "b"}}
+            })),
+            replaced_by("b")
+        );
+        assert_eq!(
+            Deprecation::of_json(&json!({
+                "name": "a",
+                "inputSchema": schema,
+                "annotations": {"x-deprecated": "b"}
+            })),
+            replaced_by("b")
+        );
+        assert_eq!(
+            Deprecation::of_json(&json!({
+                "name": "a",
+                "inputSchema": {"annotations": {"deprecated": true, "x-replacement": "b"}}
+            })),
+            replaced_by("b")
+        );
+        assert_eq!(
+            Deprecation::of_json(&json!({
+                "name": "a",
+                "description": "  [Deprecated] Use b",
+                "inputSchema": {"x-deprecated": false}
+            })),
+            Some(Deprecation::default())
+        );
+    }
+
+    #[test]
+    fn test_deprecation_message() {
+        assert_eq!(
+            replaced_by("b").unwrap().message("fs", "a"),
+            "'fs.a' is deprecated; use 'b' instead"
+        );
+        assert_eq!(
+            Deprecation::default().message("fs", "a"),
+            "'fs.a' is deprecated"
+        );
+    }
+
+    #[test]
+    fn test_warns_once_and_denies() {
+        let tool: Tool = serde_json::from_value(json!({
+            "name": "old_search",
+            "inputSchema": {"type": "object", "x-deprecated": "search"}
+        }))
+        .unwrap();
+        let span = Span::test_data();
+
+        let err = check_call("deprecation-test", &tool, true, span).unwrap_err();
+        assert!(
+            format!("{err:?}").contains("Use 'search' instead"),
+            "{err:?}"
+        );
+
+        check_call("deprecation-test", &tool, false, span).unwrap();
+        assert!(!first_use("deprecation-test", "old_search"));
+    }
+}
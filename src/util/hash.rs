@@ -0,0 +1,84 @@
+//! Stable hashes of JSON values
+
+use serde_json::Value as JsonValue;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Hash a JSON value so that equal values hash the same whatever their key order
+///
+/// This is 64-bit FNV-1a over a serialization with sorted object keys, so the
+/// hash stays the same across runs and builds, unlike `DefaultHasher`. The
+/// names in a JSON Schema `required` list are a set, so they are sorted too.
+#[must_use]
+pub fn json_hash(value: &JsonValue) -> String {
+    let mut canonical = String::new();
+    write_canonical(value, &mut canonical);
+
+    let hash = canonical.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    });
+
+    format!("{hash:016x}")
+}
+
+fn write_canonical(value: &JsonValue, out: &mut String) {
+    match value {
+        JsonValue::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            out.push('{');
+            for (index, (key, value)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                out.push_str(&JsonValue::String(key.clone()).to_string());
+                out.push(':');
+                match value {
+                    JsonValue::Array(names) if key == "required" => {
+                        let mut names = names.clone();
+                        names.sort_by_key(ToString::to_string);
+                        write_canonical(&JsonValue::Array(names), out);
+                    }
+                    value => write_canonical(value, out),
+                }
+            }
+            out.push('}');
+        }
+        JsonValue::Array(items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_json_hash_ignores_key_order() {
+        let a = json!({"type": "object", "properties": {"path": {"type": "string"}}});
+        let b = json!({"properties": {"path": {"type": "string"}}, "type": "object"});
+        let c = json!({"type": "object", "properties": {"path": {"type": "integer"}}});
+
+        assert_eq!(json_hash(&a), json_hash(&b));
+        assert_ne!(json_hash(&a), json_hash(&c));
+        assert_eq!(
+            json_hash(&json!({"required": ["a", "b"]})),
+            json_hash(&json!({"required": ["b", "a"]}))
+        );
+        assert_ne!(json_hash(&json!(["a", "b"])), json_hash(&json!(["b", "a"])));
+        assert_eq!(json_hash(&a).len(), 16);
+    }
+}
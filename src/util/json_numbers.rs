@@ -0,0 +1,265 @@
+//! JSON numbers that don't fit a Nushell int or float
+//!
+//! An ID above `i64::MAX` or a price with twenty significant digits can't be
+//! a Nushell int or float without changing its value. The `json_numbers`
+//! setting decides what happens to them: `string-fallback`, the default,
+//! keeps their digits as a string; `strict` fails with the number's path;
+//! `lossy` converts them to the nearest float.
+//!
+//! Integers beyond `i64` are still exact in a parsed `serde_json::Value`, but
+//! decimals are rounded to `f64` as they're parsed. So JSON text is parsed
+//! with [`parse`], which first turns the numbers that would be rounded into
+//! marked strings that the conversion recognizes. Structured results arrive
+//! already parsed, so only their large integers can be kept.
+
+use std::{
+    borrow::Cow,
+    sync::{PoisonError, RwLock},
+};
+
+use nu_protocol::{Span, Value};
+use serde_json::{Number, Value as JsonValue};
+
+use super::error::{McpResult, generic_error};
+use crate::config::JsonNumbers;
+
+/// Starts the strings that stand in for numbers [`parse`] would round; a
+/// private use character, so no server sends it by accident
+const MARKER: char = '\u{E000}';
+
+static POLICY: RwLock<JsonNumbers> = RwLock::new(JsonNumbers::StringFallback);
+
+/// Use `policy` from now on
+pub fn set_policy(policy: JsonNumbers) {
+    *POLICY.write().unwrap_or_else(PoisonError::into_inner) = policy;
+}
+
+/// The policy set by the configuration
+#[must_use]
+pub fn policy() -> JsonNumbers {
+    *POLICY.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Parse JSON text, keeping the numbers a `f64` or `i64` would change
+pub fn parse(text: &str) -> serde_json::Result<JsonValue> {
+    serde_json::from_str(&mark_imprecise(text, policy()))
+}
+
+/// `text` with every number that wouldn't survive parsing made a marked
+/// string, unless the policy is `lossy`
+#[must_use]
+pub fn mark_imprecise(text: &str, policy: JsonNumbers) -> Cow<'_, str> {
+    if policy == JsonNumbers::Lossy {
+        return Cow::Borrowed(text);
+    }
+
+    let mut marked = String::new();
+    let mut copied = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        if c == '"' {
+            in_string = true;
+            continue;
+        }
+        if c != '-' && !c.is_ascii_digit() {
+            continue;
+        }
+
+        let mut end = start + c.len_utf8();
+        while let Some(&(at, c)) = chars.peek() {
+            if !matches!(c, '0'..='9' | '.' | 'e' | 'E' | '+' | '-') {
+                break;
+            }
+            end = at + c.len_utf8();
+            chars.next();
+        }
+
+        let literal = &text[start..end];
+        if is_imprecise(literal) {
+            marked.push_str(&text[copied..start]);
+            marked.push('"');
+            marked.push(MARKER);
+            marked.push_str(literal);
+            marked.push('"');
+            copied = end;
+        }
+    }
+
+    if copied == 0 {
+        return Cow::Borrowed(text);
+    }
+    marked.push_str(&text[copied..]);
+    Cow::Owned(marked)
+}
+
+/// Whether parsing `literal` would change its value
+fn is_imprecise(literal: &str) -> bool {
+    if !literal.contains(['.', 'e', 'E']) {
+        return literal.parse::<i64>().is_err() && literal.parse::<f64>().is_ok();
+    }
+
+    literal
+        .parse::<f64>()
+        .is_ok_and(|float| significant_digits(literal) != significant_digits(&format!("{float:e}")))
+}
+
+/// The digits of a number's mantissa without leading or trailing zeros
+fn significant_digits(number: &str) -> String {
+    let mantissa = number.split(['e', 'E']).next().unwrap_or_default();
+    let digits: String = mantissa.chars().filter(char::is_ascii_digit).collect();
+    digits.trim_matches('0').to_string()
+}
+
+/// The number a marked string stands in for, if `text` is one
+#[must_use]
+pub fn marked_number(text: &str) -> Option<&str> {
+    text.strip_prefix(MARKER)
+}
+
+/// A number from a parsed value, under `policy`
+///
+/// `path` is where it is, for the error in `strict` mode.
+pub fn number_to_value(
+    number: &Number,
+    path: &str,
+    policy: JsonNumbers,
+    span: Span,
+) -> McpResult<Value> {
+    if let Some(val) = number.as_i64() {
+        return Ok(Value::int(val, span));
+    }
+    if number.is_u64() {
+        return imprecise_to_value(&number.to_string(), path, policy, span);
+    }
+    number.as_f64().map_or_else(
+        || {
+            Err(generic_error(
+                format!("Unexpected numeric value, cannot convert {number} into i64 or f64"),
+                None,
+                span,
+            ))
+        },
+        |val| Ok(Value::float(val, span)),
+    )
+}
+
+/// A number that doesn't fit an int or float, written as `literal`
+pub fn imprecise_to_value(
+    literal: &str,
+    path: &str,
+    policy: JsonNumbers,
+    span: Span,
+) -> McpResult<Value> {
+    match policy {
+        JsonNumbers::Lossy => literal.parse::<f64>().map_or_else(
+            |_| Ok(Value::string(literal, span)),
+            |val| Ok(Value::float(val, span)),
+        ),
+        JsonNumbers::Strict => Err(generic_error(
+            format!("The number {literal} at {path} doesn't fit a Nushell int or float"),
+            "Set `json_numbers = \"string-fallback\"` to get it as a string, or \"lossy\" to round it"
+                .to_string(),
+            span,
+        )),
+        JsonNumbers::StringFallback => {
+            log::debug!("Kept the number {literal} at {path} as a string");
+            Ok(Value::string(literal, span))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::commands::utils::convert_json_value_with_policy;
+
+    fn convert(json: &JsonValue, policy: JsonNumbers) -> McpResult<Value> {
+        convert_json_value_with_policy(json, policy, Span::test_data())
+    }
+
+    #[test]
+    fn test_numbers_beyond_int_and_float() {
+        let span = Span::test_data();
+        let ids = json!({"ids": [1, u64::MAX]});
+        let price = serde_json::from_str(&mark_imprecise(
+            r#"{"price": 12345678901234567890.12}"#,
+            JsonNumbers::StringFallback,
+        ))
+        .unwrap();
+
+        let converted = convert(&ids, JsonNumbers::StringFallback).unwrap();
+        assert_eq!(
+            converted.get_data_by_key("ids"),
+            Some(Value::list(
+                vec![
+                    Value::int(1, span),
+                    Value::string("18446744073709551615", span)
+                ],
+                span
+            ))
+        );
+        let converted = convert(&price, JsonNumbers::StringFallback).unwrap();
+        assert_eq!(
+            converted.get_data_by_key("price"),
+            Some(Value::string("12345678901234567890.12", span))
+        );
+
+        let err = convert(&ids, JsonNumbers::Strict).unwrap_err();
+        assert!(
+            format!("{err:?}").contains("18446744073709551615 at ids.1"),
+            "{err:?}"
+        );
+        let err = convert(&price, JsonNumbers::Strict).unwrap_err();
+        assert!(format!("{err:?}").contains("at price"), "{err:?}");
+
+        let converted = convert(&ids, JsonNumbers::Lossy).unwrap();
+        assert_eq!(
+            converted.get_data_by_key("ids"),
+            Some(Value::list(
+                vec![
+                    Value::int(1, span),
+                    Value::float(1.844_674_407_370_955_2e19, span)
+                ],
+                span
+            ))
+        );
+    }
+
+    #[test]
+    fn test_mark_imprecise() {
+        let text = r#"{"id": 18446744073709551615, "price": 12345678901234567890.12, "small": -1.5, "name": "9223372036854775808"}"#;
+        let marked = mark_imprecise(text, JsonNumbers::StringFallback);
+        let json: JsonValue = serde_json::from_str(&marked).unwrap();
+
+        assert_eq!(
+            json["id"].as_str().and_then(marked_number),
+            Some("18446744073709551615")
+        );
+        assert_eq!(
+            json["price"].as_str().and_then(marked_number),
+            Some("12345678901234567890.12")
+        );
+        assert_eq!(json["small"], JsonValue::from(-1.5));
+        assert_eq!(json["name"], "9223372036854775808");
+
+        assert_eq!(mark_imprecise(text, JsonNumbers::Lossy), text);
+        assert!(matches!(
+            mark_imprecise("[1, 0.1, 2.50, -3e10]", JsonNumbers::Strict),
+            Cow::Borrowed(_)
+        ));
+    }
+}
@@ -0,0 +1,174 @@
+//! Parsing a JSON array one element at a time, for `format = "json"`
+//!
+//! Parsing a large array into a `serde_json::Value` and then converting
+//! that into a Nushell value holds the result three times over. Elements
+//! parsed one at a time can be converted and passed on as they are read,
+//! so only the text and the element in hand are held.
+
+use std::borrow::Cow;
+
+use serde_json::Value as JsonValue;
+
+use super::json_numbers;
+
+/// The elements of a JSON array, parsed as they're asked for
+pub struct JsonArrayElements {
+    text: String,
+    /// Where the next separator or element starts
+    pos: usize,
+    first: bool,
+    finished: bool,
+}
+
+impl JsonArrayElements {
+    /// Start reading `text`, or give it back if it isn't a JSON array
+    ///
+    /// Only the opening bracket is checked; a malformed array is reported
+    /// by the element where it goes wrong.
+    pub fn new(text: String) -> Result<Self, String> {
+        let start = text.len() - text.trim_start().len();
+        if !text[start..].starts_with('[') {
+            return Err(text);
+        }
+
+        // Numbers a float would round become strings first, see `json_numbers`
+        let text = match json_numbers::mark_imprecise(&text, json_numbers::policy()) {
+            Cow::Owned(marked) => Some(marked),
+            Cow::Borrowed(_) => None,
+        }
+        .unwrap_or(text);
+
+        Ok(Self {
+            text,
+            pos: start + 1,
+            first: true,
+            finished: false,
+        })
+    }
+
+    /// How many bytes of the text have been read so far
+    #[cfg(test)]
+    #[must_use]
+    pub const fn consumed(&self) -> usize {
+        self.pos
+    }
+
+    fn next_element(&mut self) -> Result<Option<JsonValue>, String> {
+        self.skip_whitespace();
+        match self.text.as_bytes().get(self.pos) {
+            Some(b']') => {
+                self.pos += 1;
+                return self.end();
+            }
+            Some(b',') if !self.first => self.pos += 1,
+            _ if self.first => {}
+            Some(_) => return Err(format!("expected ',' or ']' at byte {}", self.pos)),
+            None => return Err("the array isn't closed".into()),
+        }
+        self.first = false;
+
+        let mut values =
+            serde_json::Deserializer::from_str(&self.text[self.pos..]).into_iter::<JsonValue>();
+        match values.next() {
+            Some(Ok(value)) => {
+                self.pos += values.byte_offset();
+                Ok(Some(value))
+            }
+            Some(Err(err)) => Err(format!("element at byte {}: {err}", self.pos)),
+            None => Err("the array isn't closed".into()),
+        }
+    }
+
+    /// Check that nothing but whitespace follows the closing bracket
+    fn end(&mut self) -> Result<Option<JsonValue>, String> {
+        self.skip_whitespace();
+        if self.pos < self.text.len() {
+            return Err(format!(
+                "unexpected text after the array at byte {}",
+                self.pos
+            ));
+        }
+        Ok(None)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+}
+
+impl Iterator for JsonArrayElements {
+    type Item = Result<JsonValue, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match self.next_element() {
+            Ok(Some(value)) => Some(Ok(value)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(err) => {
+                self.finished = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn elements(text: &str) -> Result<Vec<JsonValue>, String> {
+        JsonArrayElements::new(text.to_string()).unwrap().collect()
+    }
+
+    #[test]
+    fn test_elements() {
+        assert_eq!(
+            elements(r#" [1, "a,]", {"b": [2, 3]}, null] "#).unwrap(),
+            vec![
+                json!(1),
+                json!("a,]"),
+                json!({"b": [2, 3]}),
+                JsonValue::Null
+            ]
+        );
+        assert_eq!(elements("[]").unwrap(), Vec::<JsonValue>::new());
+        assert!(JsonArrayElements::new(r#"{"a": 1}"#.into()).is_err());
+    }
+
+    #[test]
+    fn test_malformed_arrays() {
+        assert!(elements("[1, 2").is_err());
+        assert!(elements("[1,]").is_err());
+        assert!(elements("[1 2]").is_err());
+        assert!(elements("[1] trailing").is_err());
+    }
+
+    #[test]
+    fn test_reads_only_what_is_taken() {
+        let text = format!(
+            "[{}]",
+            (0..200_000)
+                .map(|id| format!(r#"{{"id": {id}, "name": "item {id}"}}"#))
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        let total = text.len();
+
+        let mut elements = JsonArrayElements::new(text).unwrap();
+        let first: Vec<JsonValue> = elements.by_ref().take(10).map(Result::unwrap).collect();
+
+        assert_eq!(first[9], json!({"id": 9, "name": "item 9"}));
+        // Only the first ten elements were parsed, not the whole array
+        assert!(elements.consumed() < 1_000);
+        assert!(total > 1_000_000);
+    }
+}
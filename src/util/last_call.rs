@@ -0,0 +1,167 @@
+//! `$mcp_last` and `$mcp_last_call`, the result and details of the last tool call
+
+use std::time::Duration;
+
+use nu_protocol::{
+    Record, Span, Type, Value, VarId,
+    engine::{EngineState, Stack, StateWorkingSet},
+};
+use serde_json::Value as JsonValue;
+
+use super::format::json_to_nu;
+
+/// The variable holding the converted result of the last successful call
+pub const LAST_RESULT_VAR: &str = "$mcp_last";
+
+/// The variable holding the server, tool, arguments and duration of that call
+pub const LAST_CALL_VAR: &str = "$mcp_last_call";
+
+/// A successful tool call, as recorded in `$mcp_last_call`
+pub struct LastCall<'a> {
+    pub server: &'a str,
+    pub tool: &'a str,
+    pub args: &'a serde_json::Map<String, JsonValue>,
+    pub duration: Duration,
+    /// The text of a blank result, which `$mcp_last` has as nothing
+    pub original: Option<&'a str>,
+}
+
+/// Declare both variables so that scripts can refer to them
+///
+/// Returns their ids; each must be given a value on the stack, or using
+/// the variable before the first call fails.
+pub fn declare(working_set: &mut StateWorkingSet) -> [VarId; 2] {
+    [LAST_RESULT_VAR, LAST_CALL_VAR].map(|name| {
+        working_set.add_variable(name.as_bytes().to_vec(), Span::unknown(), Type::Any, false)
+    })
+}
+
+/// Set the variables after a successful call
+///
+/// `$mcp_last_call` only has an `original` column when the result was blank
+/// text.
+///
+/// They're set on the stack the command ran with, which the REPL keeps
+/// between prompts. Calls made inside a closure, such as in `each`, run on a
+/// child stack and don't change them.
+pub fn remember(
+    engine_state: &EngineState,
+    stack: &mut Stack,
+    call: &LastCall<'_>,
+    result: Value,
+    span: Span,
+) {
+    let (Some(result_var), Some(call_var)) = (
+        find_variable(engine_state, LAST_RESULT_VAR),
+        find_variable(engine_state, LAST_CALL_VAR),
+    ) else {
+        return;
+    };
+
+    let mut record = Record::new();
+    record.push("server", Value::string(call.server, span));
+    record.push("tool", Value::string(call.tool, span));
+    record.push(
+        "args",
+        json_to_nu(&JsonValue::Object(call.args.clone()), Some(span)),
+    );
+    record.push(
+        "duration",
+        Value::duration(
+            i64::try_from(call.duration.as_nanos()).unwrap_or(i64::MAX),
+            span,
+        ),
+    );
+
+    if let Some(original) = call.original {
+        record.push("original", Value::string(original, span));
+    }
+
+    stack.add_var(result_var, result);
+    stack.add_var(call_var, Value::record(record, span));
+}
+
+fn find_variable(engine_state: &EngineState, name: &str) -> Option<VarId> {
+    engine_state
+        .active_overlays(&[])
+        .rev()
+        .find_map(|overlay| overlay.vars.get(name.as_bytes()).copied())
+}
+
+#[cfg(test)]
+mod tests {
+    use nu_protocol::PipelineData;
+
+    use super::*;
+
+    #[test]
+    fn test_remember_last_call() {
+        let mut engine_state = nu_cmd_lang::create_default_context();
+        let mut stack = Stack::new();
+
+        let mut working_set = StateWorkingSet::new(&engine_state);
+        for var in declare(&mut working_set) {
+            stack.add_var(var, Value::nothing(Span::test_data()));
+        }
+        let delta = working_set.render();
+        engine_state.merge_delta(delta).unwrap();
+
+        let eval = |stack: &mut Stack, source: &str| {
+            let mut working_set = StateWorkingSet::new(&engine_state);
+            let block = nu_parser::parse(&mut working_set, None, source.as_bytes(), false);
+            assert!(working_set.parse_errors.is_empty(), "{source}");
+            nu_engine::eval_block::<nu_protocol::debugger::WithoutDebug>(
+                &engine_state,
+                stack,
+                &block,
+                PipelineData::empty(),
+            )
+            .and_then(|data| data.into_value(Span::test_data()))
+            .unwrap()
+        };
+
+        // Empty until the first call
+        assert!(eval(&mut stack, "$mcp_last").is_nothing());
+
+        let serde_json::Value::Object(args) = serde_json::json!({"state": "open"}) else {
+            unreachable!()
+        };
+        let span = Span::test_data();
+        let call = LastCall {
+            server: "github",
+            tool: "list_issues",
+            args: &args,
+            duration: Duration::from_millis(120),
+            original: None,
+        };
+        let result = Value::list(vec![Value::int(1, span), Value::int(2, span)], span);
+        remember(&engine_state, &mut stack, &call, result, span);
+
+        assert_eq!(eval(&mut stack, "$mcp_last | length").as_int().unwrap(), 2);
+        assert_eq!(
+            eval(&mut stack, "$mcp_last_call.tool").as_str().unwrap(),
+            "list_issues"
+        );
+        assert_eq!(
+            eval(&mut stack, "$mcp_last_call.args.state")
+                .as_str()
+                .unwrap(),
+            "open"
+        );
+        assert!(eval(&mut stack, "$mcp_last_call.original?").is_nothing());
+
+        // A blank result is nothing, with the text it held beside it
+        let call = LastCall {
+            original: Some("\n"),
+            ..call
+        };
+        remember(&engine_state, &mut stack, &call, Value::nothing(span), span);
+        assert!(eval(&mut stack, "$mcp_last").is_nothing());
+        assert_eq!(
+            eval(&mut stack, "$mcp_last_call.original")
+                .as_str()
+                .unwrap(),
+            "\n"
+        );
+    }
+}
@@ -0,0 +1,33 @@
+//! `$env.LAST_MCP_ERROR`, the most recent failed MCP call
+//!
+//! Scripts can branch on it without `--try`: it's a record of the failed
+//! call's `server`, `tool`, `kind` and `message` (plus its `request_id`), and
+//! it's removed again by the next call that succeeds.
+
+use nu_protocol::{
+    Span, Value,
+    engine::{EngineState, Stack},
+};
+
+use super::error::ToolCallError;
+
+/// The environment variable holding the most recent failure
+pub const LAST_MCP_ERROR_ENV: &str = "LAST_MCP_ERROR";
+
+/// Record a failed call
+///
+/// Like `$mcp_last`, it's set on the stack the command ran with, so calls
+/// made inside a closure don't change it.
+pub fn remember(stack: &mut Stack, error: &ToolCallError, span: Span) {
+    remember_value(stack, error.to_value(span));
+}
+
+/// Record a failure already described by a `--try` `error` record
+pub fn remember_value(stack: &mut Stack, error: Value) {
+    stack.add_env_var(LAST_MCP_ERROR_ENV.to_string(), error);
+}
+
+/// Forget the last failure after a call that succeeded
+pub fn clear(engine_state: &EngineState, stack: &mut Stack) {
+    stack.remove_env_var(engine_state, LAST_MCP_ERROR_ENV);
+}
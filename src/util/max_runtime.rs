@@ -0,0 +1,154 @@
+//! The `--max-runtime` budget for scripts run with `--commands`
+
+use std::{
+    io::Write,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use super::status::{self, Stream};
+use crate::engine::{get_mcp_client_manager, running_call, shared_runtime};
+
+/// The exit code of a script that ran past its budget, the same as `timeout(1)`
+pub const MAX_RUNTIME_EXIT_CODE: i32 = 124;
+
+/// How long in-flight calls get to notice they were cancelled
+const CANCEL_GRACE: Duration = Duration::from_millis(500);
+
+/// How long the servers get to shut down before the process exits anyway
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// Interrupts a script that runs longer than its budget
+pub struct RuntimeBudget {
+    finished: mpsc::Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl RuntimeBudget {
+    /// Start counting down `budget`
+    ///
+    /// When it runs out, `interrupt` is set, which cancels the MCP call in
+    /// flight the way Ctrl-C does, and `on_expired` is called with the call
+    /// that was running, if any.
+    pub fn arm(
+        budget: Duration,
+        interrupt: Arc<AtomicBool>,
+        on_expired: impl FnOnce(Option<String>) + Send + 'static,
+    ) -> Self {
+        let (finished, receiver) = mpsc::channel();
+
+        let thread = std::thread::spawn(move || {
+            if let Err(mpsc::RecvTimeoutError::Timeout) = receiver.recv_timeout(budget) {
+                let stage = running_call();
+                interrupt.store(true, Ordering::Relaxed);
+                on_expired(stage);
+            }
+        });
+
+        Self { finished, thread }
+    }
+
+    /// Stop the countdown once the script is done
+    ///
+    /// If the budget already ran out, this waits for `on_expired` to finish.
+    pub fn finish(self) {
+        let _ = self.finished.send(());
+        let _ = self.thread.join();
+    }
+}
+
+/// Shut everything down and exit with [`MAX_RUNTIME_EXIT_CODE`]
+///
+/// Output the script already printed is flushed first, then the servers are
+/// disconnected the same way `mcp restart` stops them, so no child process
+/// outlives the REPL.
+pub fn exit_over_budget(budget: Duration, stage: Option<String>) -> ! {
+    let _ = std::io::stdout().flush();
+
+    // Give the cancelled call a moment to unwind before its server goes away
+    std::thread::sleep(CANCEL_GRACE);
+    shared_runtime().block_on(async {
+        if tokio::time::timeout(SHUTDOWN_GRACE, disconnect_all())
+            .await
+            .is_err()
+        {
+            log::warn!("Servers were still shutting down after {SHUTDOWN_GRACE:?}");
+        }
+    });
+
+    let stage = stage.map_or_else(
+        || "while running Nushell code".to_string(),
+        |stage| format!("while waiting for '{stage}'"),
+    );
+    status::OUTPUT.write(
+        Stream::Stderr,
+        &format!(
+            "Error: the script exceeded --max-runtime of {} {stage}\n",
+            humantime::format_duration(budget)
+        ),
+    );
+
+    std::process::exit(MAX_RUNTIME_EXIT_CODE)
+}
+
+async fn disconnect_all() {
+    let clients: Vec<_> = get_mcp_client_manager()
+        .await
+        .get_servers()
+        .values()
+        .map(|server| server.client.clone())
+        .collect();
+
+    for client in clients {
+        client.client.disconnect().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nu_protocol::Signals;
+
+    use super::*;
+    use crate::engine::{BlockOnError, Watchdog, block_on_shared};
+
+    #[test]
+    fn test_budget_cancels_a_call_that_never_returns() {
+        let interrupt = Arc::new(AtomicBool::new(false));
+        let signals = Signals::new(interrupt.clone());
+        let (expired, stage) = mpsc::channel();
+
+        let budget = RuntimeBudget::arm(Duration::from_millis(200), interrupt, move |running| {
+            let _ = expired.send(running);
+        });
+
+        // A server that never replies
+        let result = block_on_shared(
+            std::future::pending::<()>(),
+            &signals,
+            Some(Watchdog {
+                label: "slow.wait",
+                stuck_after: Duration::from_secs(60),
+            }),
+        );
+        budget.finish();
+
+        assert!(matches!(result, Err(BlockOnError::Interrupted)));
+        assert_eq!(stage.recv().unwrap().as_deref(), Some("slow.wait"));
+    }
+
+    #[test]
+    fn test_budget_is_disarmed_when_the_script_finishes() {
+        let interrupt = Arc::new(AtomicBool::new(false));
+        let budget = RuntimeBudget::arm(Duration::from_secs(60), interrupt.clone(), |_| {
+            panic!("the budget ran out");
+        });
+        budget.finish();
+
+        assert!(!interrupt.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,123 @@
+//! Limits on the state a session keeps in memory
+//!
+//! Cached tool results, the event feed and the messages held back at the
+//! prompt grow with every call and notification. Each of these stores has a
+//! [`Limit`] on its entries and approximate bytes, set from the `[memory]`
+//! table; when a store goes over either one it evicts entries, least
+//! recently used or oldest first, and counts them. `mcp memory` reports each
+//! store's [`Usage`].
+//!
+//! Eviction never renumbers what's left: events keep their sequence
+//! numbers, so `mcp events --follow` can tell which ones it missed.
+
+/// A store that's kept within a [`Limit`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Store {
+    /// Each server's cached tool results, least recently used evicted first
+    Results,
+    /// The notifications `mcp events` shows, oldest evicted first
+    Events,
+    /// Background messages held back at the prompt, oldest evicted first
+    Output,
+}
+
+impl Store {
+    /// The store's key in the `[memory]` table
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Results => "results",
+            Self::Events => "events",
+            Self::Output => "output",
+        }
+    }
+
+    /// The limit used when the configuration doesn't set one
+    #[must_use]
+    pub const fn default_limit(self) -> Limit {
+        match self {
+            Self::Results => Limit {
+                entries: 1000,
+                bytes: 64 * 1024 * 1024,
+            },
+            Self::Events => Limit {
+                entries: super::events::DEFAULT_EVENT_BUFFER,
+                bytes: 16 * 1024 * 1024,
+            },
+            Self::Output => Limit {
+                entries: 1000,
+                bytes: 1024 * 1024,
+            },
+        }
+    }
+}
+
+/// The most entries and approximate bytes a store keeps
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limit {
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+impl Limit {
+    /// Whether a store holding `entries` entries of `bytes` in total is over
+    /// the limit
+    #[must_use]
+    pub const fn exceeded_by(self, entries: usize, bytes: usize) -> bool {
+        entries > self.entries || bytes > self.bytes
+    }
+}
+
+/// What a store holds now, and how many entries it has evicted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub entries: usize,
+    pub bytes: usize,
+    pub evicted: u64,
+}
+
+/// The resident set size of this process, where the platform reports it
+#[must_use]
+pub fn resident_bytes() -> Option<u64> {
+    std::fs::read_to_string("/proc/self/status")
+        .ok()
+        .as_deref()
+        .and_then(parse_resident_bytes)
+}
+
+/// The `VmRSS` line of `/proc/self/status`, in bytes
+fn parse_resident_bytes(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: u64 = line
+        .trim_start_matches("VmRSS:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_resident_bytes() {
+        let status =
+            "Name:\tnu-mcp-repl\nVmPeak:\t  912344 kB\nVmRSS:\t   48212 kB\nThreads:\t12\n";
+        assert_eq!(parse_resident_bytes(status), Some(48212 * 1024));
+        assert_eq!(parse_resident_bytes("Name:\tnu-mcp-repl\n"), None);
+    }
+
+    #[test]
+    fn test_limit_exceeded_by() {
+        let limit = Limit {
+            entries: 2,
+            bytes: 100,
+        };
+        assert!(!limit.exceeded_by(2, 100));
+        assert!(limit.exceeded_by(3, 10));
+        assert!(limit.exceeded_by(1, 101));
+    }
+}
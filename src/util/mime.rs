@@ -0,0 +1,98 @@
+//! Conversion of resource contents into Nushell values based on MIME type
+
+use base64::Engine;
+use nu_protocol::{Span, Value};
+use rmcp::model::ResourceContents;
+
+use super::{format::json_to_nu, glob::glob_matches, json_numbers};
+
+/// Converts the text of a resource into a value
+type TextConverter = fn(&str, Span) -> Value;
+
+/// Converters for text contents, checked in order against the MIME type
+///
+/// An entry ending in `/` matches every subtype, and `+json` suffixes are
+/// treated as JSON.
+const TEXT_CONVERTERS: &[(&str, TextConverter)] = &[
+    ("application/json", parse_json),
+    ("+json", parse_json),
+    ("text/", plain_text),
+];
+
+/// Convert the contents of a resource into a Nushell value
+///
+/// Text is converted according to its MIME type, falling back to a plain
+/// string. Blobs are decoded into binary values.
+#[must_use]
+pub fn resource_contents_to_value(contents: &ResourceContents, span: Span) -> Value {
+    match contents {
+        ResourceContents::TextResourceContents {
+            mime_type, text, ..
+        } => text_converter(mime_type.as_deref())(text, span),
+        ResourceContents::BlobResourceContents { blob, .. } => {
+            base64::engine::general_purpose::STANDARD
+                .decode(blob)
+                .map_or_else(
+                    |_| Value::string(blob, span),
+                    |bytes| Value::binary(bytes, span),
+                )
+        }
+    }
+}
+
+/// Match a MIME type against a glob such as `text/*` or `image/png`
+///
+/// `*` matches any run of characters and `?` a single character. Parameters
+/// like `; charset=utf-8` are ignored and the comparison is case-insensitive.
+#[must_use]
+pub fn mime_matches(pattern: &str, mime_type: &str) -> bool {
+    let essence = mime_type.split(';').next().unwrap_or_default().trim();
+
+    glob_matches(&pattern.trim().to_lowercase(), &essence.to_lowercase())
+}
+
+fn text_converter(mime_type: Option<&str>) -> TextConverter {
+    let Some(mime_type) = mime_type else {
+        return plain_text;
+    };
+    let essence = mime_type.split(';').next().unwrap_or_default().trim();
+
+    TEXT_CONVERTERS
+        .iter()
+        .find(|(pattern, _)| {
+            if pattern.starts_with('+') {
+                essence.ends_with(pattern)
+            } else if pattern.ends_with('/') {
+                essence.starts_with(pattern)
+            } else {
+                essence == *pattern
+            }
+        })
+        .map_or(plain_text as TextConverter, |(_, converter)| *converter)
+}
+
+fn parse_json(text: &str, span: Span) -> Value {
+    json_numbers::parse(text).map_or_else(
+        |_| Value::string(text, span),
+        |json| json_to_nu(&json, Some(span)),
+    )
+}
+
+fn plain_text(text: &str, span: Span) -> Value {
+    Value::string(text, span)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mime_matches() {
+        assert!(mime_matches("text/*", "text/plain; charset=utf-8"));
+        assert!(mime_matches("*/json", "application/json"));
+        assert!(mime_matches("image/PNG", "image/png"));
+        assert!(mime_matches("image/p?g", "image/png"));
+        assert!(!mime_matches("text/*", "application/json"));
+        assert!(!mime_matches("text", "text/plain"));
+    }
+}
@@ -0,0 +1,183 @@
+//! Tool call payloads shown in verbose mode
+//!
+//! With `--verbose`, each tool call's arguments and response are written to
+//! stderr, formatted as Nushell values. A large response would wrap for
+//! hundreds of lines and bury the prompt, so only its first and last lines
+//! are shown, each cut to the terminal width, with a marker counting the
+//! lines left out. With `--log-file`, the full payload is written to the
+//! log as well. `mcp debug --max-lines` changes the limit for the session.
+
+use std::{
+    io::Write,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use serde_json::Value as JsonValue;
+
+use super::{
+    format::{format_json_as_nu, terminal_width, truncate_line},
+    status::{self, Stream},
+};
+
+/// How many lines of a payload are shown unless `mcp debug` changes it
+pub const DEFAULT_MAX_LINES: usize = 40;
+
+/// The log target full payloads are written under
+const TRAFFIC_TARGET: &str = "mcp_traffic";
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+static LOG_FILE: AtomicBool = AtomicBool::new(false);
+static MAX_LINES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_LINES);
+
+/// Set up from `--verbose` and whether `--log-file` was given
+pub fn init(verbose: bool, log_file: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+    LOG_FILE.store(log_file, Ordering::Relaxed);
+}
+
+/// Whether payloads are shown
+#[must_use]
+pub fn is_verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
+/// Whether full payloads are written to a log file
+#[must_use]
+pub fn has_log_file() -> bool {
+    LOG_FILE.load(Ordering::Relaxed)
+}
+
+/// How many lines of a payload are shown; zero shows all of them
+#[must_use]
+pub fn max_lines() -> usize {
+    MAX_LINES.load(Ordering::Relaxed)
+}
+
+/// Change how many lines of a payload are shown, for `mcp debug`
+pub fn set_max_lines(max_lines: usize) {
+    MAX_LINES.store(max_lines, Ordering::Relaxed);
+}
+
+/// Show `payload` under `label`, shortened, and log it in full
+pub fn show(label: &str, payload: &JsonValue) {
+    let formatted = format_json_as_nu(payload, None);
+    let mut log = |full: &str| log::debug!(target: TRAFFIC_TARGET, "{full}");
+
+    let mut shown = Vec::new();
+    write_payload(
+        label,
+        &formatted,
+        max_lines(),
+        terminal_width(),
+        &mut shown,
+        has_log_file().then_some(&mut log as &mut dyn FnMut(&str)),
+    );
+    status::OUTPUT.write(Stream::Stderr, &String::from_utf8_lossy(&shown));
+}
+
+/// Write the shortened payload to `terminal` and the full one to `log`
+fn write_payload(
+    label: &str,
+    formatted: &str,
+    max_lines: usize,
+    width: usize,
+    terminal: &mut impl Write,
+    log: Option<&mut dyn FnMut(&str)>,
+) {
+    let logged = log.is_some();
+    if let Some(log) = log {
+        log(&format!("{label}:\n{formatted}"));
+    }
+
+    let shown = elide(formatted, max_lines, width, logged);
+    let _ = terminal.write_all(format!("{label}:\n{shown}\n").as_bytes());
+}
+
+/// `text` cut to `max_lines` lines of at most `width` characters
+///
+/// The lines left out come from the middle, so both the start and the end
+/// of the payload are shown. Zero `max_lines` shows the text as it is.
+fn elide(text: &str, max_lines: usize, width: usize, logged: bool) -> String {
+    if max_lines == 0 {
+        return text.to_string();
+    }
+
+    let lines: Vec<&str> = text.lines().collect();
+    let cut = |line: &&str| truncate_line(line, width);
+    if lines.len() <= max_lines {
+        return lines.iter().map(cut).collect::<Vec<_>>().join("\n");
+    }
+
+    let head = max_lines.div_ceil(2);
+    let tail = max_lines - head;
+    let omitted = lines.len() - max_lines;
+    let marker = if logged {
+        format!("… {omitted} more lines (the full payload is in the log file)")
+    } else {
+        format!("… {omitted} more lines (run with --log-file to keep the full payload)")
+    };
+
+    lines[..head]
+        .iter()
+        .map(cut)
+        .chain(std::iter::once(marker))
+        .chain(lines[lines.len() - tail..].iter().map(cut))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered_lines(count: usize) -> String {
+        (1..=count)
+            .map(|n| format!("line {n}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_elide_keeps_start_and_end() {
+        let shown = elide(&numbered_lines(100), 4, 80, false);
+        assert_eq!(
+            shown,
+            "line 1\nline 2\n… 96 more lines (run with --log-file to keep the full payload)\nline 99\nline 100"
+        );
+
+        assert_eq!(elide(&numbered_lines(3), 4, 80, false), numbered_lines(3));
+        assert_eq!(
+            elide(&numbered_lines(100), 0, 80, false),
+            numbered_lines(100)
+        );
+        assert_eq!(elide("a long line", 4, 8, false), "a long…");
+    }
+
+    #[test]
+    fn test_full_payload_reaches_the_log() {
+        let payload = numbered_lines(500);
+        let mut terminal = Vec::new();
+        let mut logged = Vec::new();
+        let mut log = |full: &str| logged.push(full.to_string());
+
+        write_payload(
+            "MCP RESPONSE #1 from 'search'",
+            &payload,
+            DEFAULT_MAX_LINES,
+            80,
+            &mut terminal,
+            Some(&mut log),
+        );
+
+        let shown = String::from_utf8(terminal).unwrap();
+        assert!(
+            shown.contains("… 460 more lines (the full payload is in the log file)"),
+            "{shown}"
+        );
+        assert_eq!(shown.lines().count(), DEFAULT_MAX_LINES + 2);
+        assert_eq!(
+            logged,
+            vec![format!("MCP RESPONSE #1 from 'search':\n{payload}")]
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Finding a command server's program before it's started
+//!
+//! A misspelled program otherwise only shows up as the `initialize`
+//! handshake timing out.
+
+use std::{
+    collections::BTreeSet,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+
+/// Check that `program` exists and can be run
+///
+/// A bare name is looked up on `path`, a program with a directory is
+/// resolved against `cwd`. When a bare name isn't found, the error suggests
+/// a program on `path` with a similar name.
+pub fn check_program(program: &str, cwd: &Path, path: Option<&OsStr>) -> Result<()> {
+    let as_path = Path::new(program);
+
+    if as_path.components().count() > 1 {
+        let resolved = cwd.join(as_path);
+        return if !resolved.is_file() {
+            Err(anyhow!("program '{program}' not found"))
+        } else if !is_executable(&resolved) {
+            Err(anyhow!("program '{program}' is not executable"))
+        } else {
+            Ok(())
+        };
+    }
+
+    let dirs: Vec<PathBuf> = path
+        .map(std::env::split_paths)
+        .into_iter()
+        .flatten()
+        .collect();
+    let found: Vec<PathBuf> = dirs
+        .iter()
+        .flat_map(|dir| candidates(dir, program))
+        .filter(|candidate| candidate.is_file())
+        .collect();
+
+    if found.iter().any(|candidate| is_executable(candidate)) {
+        return Ok(());
+    }
+    if let Some(candidate) = found.first() {
+        return Err(anyhow!(
+            "program '{program}' on PATH is not executable: {}",
+            candidate.display()
+        ));
+    }
+
+    match nu_protocol::did_you_mean(&program_names(&dirs), program) {
+        Some(suggestion) => Err(anyhow!(
+            "program '{program}' not found on PATH; did you mean '{suggestion}'?"
+        )),
+        None => Err(anyhow!("program '{program}' not found on PATH")),
+    }
+}
+
+/// The paths `program` could be at in `dir`, with each of the executable
+/// extensions on Windows
+fn candidates(dir: &Path, program: &str) -> Vec<PathBuf> {
+    let mut candidates = vec![dir.join(program)];
+
+    if cfg!(windows) && Path::new(program).extension().is_none() {
+        let extensions = std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".into());
+        candidates.extend(
+            extensions
+                .split(';')
+                .filter(|extension| !extension.is_empty())
+                .map(|extension| dir.join(format!("{program}{extension}"))),
+        );
+    }
+
+    candidates
+}
+
+/// The names of the files in `dirs`, to suggest from
+fn program_names(dirs: &[PathBuf]) -> BTreeSet<String> {
+    dirs.iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .collect()
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+
+    #[test]
+    fn test_check_program() {
+        let dir = std::env::temp_dir().join(format!("mcp-repl-program-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, mode: u32| {
+            let path = dir.join(name);
+            std::fs::write(&path, "#!/bin/sh\n").unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        };
+        write("npx", 0o755);
+        write("notes.txt", 0o644);
+
+        let path = Some(dir.as_os_str());
+        let check =
+            |program: &str| check_program(program, &dir, path).map_err(|err| err.to_string());
+
+        assert!(check("npx").is_ok());
+        assert!(check("./npx").is_ok());
+        assert_eq!(
+            check("npxx").unwrap_err(),
+            "program 'npxx' not found on PATH; did you mean 'npx'?"
+        );
+        assert_eq!(
+            check("completely-different").unwrap_err(),
+            "program 'completely-different' not found on PATH"
+        );
+        assert!(check("notes.txt").unwrap_err().contains("not executable"));
+        assert_eq!(
+            check("./missing").unwrap_err(),
+            "program './missing' not found"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,308 @@
+//! Simple interactive prompts on the controlling terminal
+
+use std::{
+    io::{self, BufRead, Write},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use nu_ansi_term::Color;
+use reedline::{
+    Completer, DefaultPrompt, DefaultPromptSegment, Editor, Emacs, KeyCode, KeyModifiers, Menu,
+    MenuEvent, Painter, Reedline, ReedlineEvent, ReedlineMenu, Signal, Suggestion,
+    default_emacs_keybindings,
+};
+
+/// Ask a yes/no question and wait for the answer
+///
+/// Anything other than `y` or `yes` (case-insensitive) counts as "no".
+pub fn confirm(question: &str) -> io::Result<bool> {
+    let answer = read_line(&format!("{question} [y/N] "))?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Show a line of text ahead of a prompt, such as a description or a choice
+pub fn show(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    writeln!(stdout, "{text}")?;
+    stdout.flush()
+}
+
+/// Print a prompt and read a single line of input, without the trailing newline
+pub fn read_line(prompt: &str) -> io::Result<String> {
+    let mut stdout = io::stdout();
+    stdout.write_all(prompt.as_bytes())?;
+    stdout.flush()?;
+
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// The name of the menu [`choose_many`] shows
+const CHOICE_MENU: &str = "mcp choices";
+
+/// Show a checkbox list and ask which of the items to pick
+///
+/// The list is a reedline menu: arrow keys or Tab move between items, Space
+/// ticks or clears the highlighted one and Enter accepts the ticked items.
+/// Ctrl-C or Ctrl-D picks nothing. Returns the indices of the picked items,
+/// in list order.
+pub fn choose_many(question: &str, items: &[String]) -> io::Result<Vec<usize>> {
+    show(question)?;
+    show("  (↑/↓ to move, Space to tick, Enter to accept, Ctrl-C to cancel)")?;
+
+    let picked = Arc::new(Mutex::new(vec![false; items.len()]));
+    let menu = ChoiceMenu::new(items, picked.clone());
+
+    let mut keybindings = default_emacs_keybindings();
+    let menu_event =
+        |event| ReedlineEvent::UntilFound(vec![ReedlineEvent::Menu(CHOICE_MENU.into()), event]);
+    for (modifiers, key, event) in [
+        (KeyModifiers::NONE, KeyCode::Down, ReedlineEvent::MenuDown),
+        (KeyModifiers::NONE, KeyCode::Tab, ReedlineEvent::MenuNext),
+        (KeyModifiers::NONE, KeyCode::Up, ReedlineEvent::MenuUp),
+        (
+            KeyModifiers::SHIFT,
+            KeyCode::BackTab,
+            ReedlineEvent::MenuPrevious,
+        ),
+    ] {
+        keybindings.add_binding(modifiers, key, menu_event(event));
+    }
+    // Choosing the highlighted item closes a reedline menu, so Space opens it
+    // again straight away
+    keybindings.add_binding(
+        KeyModifiers::NONE,
+        KeyCode::Char(' '),
+        ReedlineEvent::Multiple(vec![
+            ReedlineEvent::Menu(CHOICE_MENU.into()),
+            ReedlineEvent::Enter,
+            ReedlineEvent::Menu(CHOICE_MENU.into()),
+        ]),
+    );
+    // Enter accepts the list instead of choosing the highlighted item
+    keybindings.add_binding(
+        KeyModifiers::NONE,
+        KeyCode::Enter,
+        ReedlineEvent::Multiple(vec![ReedlineEvent::Esc, ReedlineEvent::Enter]),
+    );
+
+    let mut editor = Reedline::create()
+        .with_edit_mode(Box::new(Emacs::new(keybindings)))
+        .with_menu(ReedlineMenu::EngineCompleter(Box::new(menu)));
+    let prompt = DefaultPrompt::new(
+        DefaultPromptSegment::Basic("pick".into()),
+        DefaultPromptSegment::Empty,
+    );
+
+    match editor.read_line(&prompt)? {
+        Signal::Success(_) => Ok(picked_indices(&picked)),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// The indices of the ticked items, in list order
+fn picked_indices(picked: &Mutex<Vec<bool>>) -> Vec<usize> {
+    picked
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .enumerate()
+        .filter_map(|(index, picked)| picked.then_some(index))
+        .collect()
+}
+
+/// The checkbox list of [`choose_many`]
+///
+/// Reedline menus pick one completion and put it in the line buffer. This
+/// one keeps the buffer as it is: choosing an item ticks or clears it in the
+/// list it shares with [`choose_many`], which reads it once the line is
+/// accepted.
+struct ChoiceMenu {
+    items: Vec<Suggestion>,
+    picked: Arc<Mutex<Vec<bool>>>,
+    /// The highlighted item
+    cursor: usize,
+    active: bool,
+}
+
+impl ChoiceMenu {
+    fn new(items: &[String], picked: Arc<Mutex<Vec<bool>>>) -> Self {
+        Self {
+            items: items
+                .iter()
+                .map(|item| Suggestion {
+                    value: item.clone(),
+                    ..Suggestion::default()
+                })
+                .collect(),
+            picked,
+            cursor: 0,
+            // The list is shown as soon as the prompt is
+            active: true,
+        }
+    }
+
+    fn rows(&self) -> u16 {
+        u16::try_from(self.items.len()).unwrap_or(u16::MAX)
+    }
+
+    fn move_cursor(&mut self, forward: bool) {
+        let count = self.items.len().max(1);
+        self.cursor = if forward {
+            (self.cursor + 1) % count
+        } else {
+            (self.cursor + count - 1) % count
+        };
+    }
+}
+
+impl Menu for ChoiceMenu {
+    fn name(&self) -> &str {
+        CHOICE_MENU
+    }
+
+    fn indicator(&self) -> &str {
+        ""
+    }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn menu_event(&mut self, event: MenuEvent) {
+        match event {
+            MenuEvent::Activate(_) => self.active = true,
+            MenuEvent::Deactivate => self.active = false,
+            MenuEvent::NextElement | MenuEvent::MoveDown | MenuEvent::NextPage => {
+                self.move_cursor(true);
+            }
+            MenuEvent::PreviousElement | MenuEvent::MoveUp | MenuEvent::PreviousPage => {
+                self.move_cursor(false);
+            }
+            // The cursor stays where it was when the menu closes and opens
+            // again, so Space can tick one item after another
+            MenuEvent::Edit(_) | MenuEvent::MoveLeft | MenuEvent::MoveRight => {}
+        }
+    }
+
+    fn can_quick_complete(&self) -> bool {
+        false
+    }
+
+    fn can_partially_complete(
+        &mut self,
+        _values_updated: bool,
+        _editor: &mut Editor,
+        _completer: &mut dyn Completer,
+    ) -> bool {
+        false
+    }
+
+    fn update_values(&mut self, _editor: &mut Editor, _completer: &mut dyn Completer) {}
+
+    fn update_working_details(
+        &mut self,
+        _editor: &mut Editor,
+        _completer: &mut dyn Completer,
+        _painter: &Painter,
+    ) {
+    }
+
+    fn replace_in_buffer(&self, _editor: &mut Editor) {
+        if let Some(picked) = self
+            .picked
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_mut(self.cursor)
+        {
+            *picked = !*picked;
+        }
+    }
+
+    fn menu_required_lines(&self, _terminal_columns: u16) -> u16 {
+        self.rows()
+    }
+
+    fn menu_string(&self, available_lines: u16, use_ansi_coloring: bool) -> String {
+        let picked = self.picked.lock().unwrap_or_else(PoisonError::into_inner);
+        let shown = usize::from(available_lines).max(1);
+        // Scroll just far enough to keep the highlighted item in view
+        let first = (self.cursor + 1).saturating_sub(shown);
+
+        self.items
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(shown)
+            .map(|(index, item)| {
+                let check = if picked.get(index).copied().unwrap_or(false) {
+                    'x'
+                } else {
+                    ' '
+                };
+                let line = format!("[{check}] {}", item.value);
+                match (index == self.cursor, use_ansi_coloring) {
+                    (true, true) => format!("> {}", Color::Green.bold().reverse().paint(line)),
+                    (true, false) => format!("> {line}"),
+                    (false, _) => format!("  {line}"),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\r\n")
+    }
+
+    fn min_rows(&self) -> u16 {
+        self.rows()
+    }
+
+    fn get_values(&self) -> &[Suggestion] {
+        &self.items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn menu(items: &[&str]) -> ChoiceMenu {
+        let items: Vec<String> = items.iter().map(ToString::to_string).collect();
+        ChoiceMenu::new(&items, Arc::new(Mutex::new(vec![false; items.len()])))
+    }
+
+    #[test]
+    fn test_choice_menu_ticks_the_highlighted_item() {
+        let mut menu = menu(&["github", "fs", "db"]);
+        let mut editor = Editor::default();
+        assert!(menu.is_active());
+
+        menu.replace_in_buffer(&mut editor);
+        menu.menu_event(MenuEvent::Deactivate);
+        menu.menu_event(MenuEvent::Activate(false));
+        menu.menu_event(MenuEvent::MoveUp);
+        menu.replace_in_buffer(&mut editor);
+        assert_eq!(picked_indices(&menu.picked), vec![0, 2]);
+        assert_eq!(
+            menu.menu_string(3, false),
+            "  [x] github\r\n  [ ] fs\r\n> [x] db"
+        );
+
+        // Choosing an item again clears it, and the buffer is left alone
+        menu.replace_in_buffer(&mut editor);
+        assert_eq!(picked_indices(&menu.picked), vec![0]);
+        assert_eq!(editor.get_buffer(), "");
+    }
+
+    #[test]
+    fn test_choice_menu_scrolls_to_the_cursor() {
+        let mut menu = menu(&["a", "b", "c", "d"]);
+        for _ in 0..3 {
+            menu.menu_event(MenuEvent::NextElement);
+        }
+        assert_eq!(menu.menu_string(2, false), "  [ ] c\r\n> [ ] d");
+
+        menu.menu_event(MenuEvent::NextElement);
+        assert_eq!(menu.menu_string(2, false), "> [ ] a\r\n  [ ] b");
+    }
+}
@@ -0,0 +1,247 @@
+//! Tool calls parked while a server reconnects, for `queue_while_reconnecting`
+
+use std::{
+    future::Future,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicUsize, Ordering},
+    },
+    task::Poll,
+};
+
+use anyhow::{Result, anyhow};
+use tokio::sync::OwnedMutexGuard;
+
+/// How many calls may wait for a reconnect when the config doesn't say
+pub const DEFAULT_RECONNECT_QUEUE: usize = 16;
+
+/// Holds calls back while the connection is being replaced
+///
+/// While a reconnect is in progress the gate holds the `replay` lock, and
+/// parked calls queue up for it. Tokio's mutex is fair, so once the gate
+/// lets go the calls get it one at a time, in the order they arrived, and
+/// each hands it on as soon as its request is sent. A call that's cancelled
+/// or times out while parked just leaves the queue.
+#[derive(Debug, Default)]
+pub struct ReconnectGate {
+    replay: Arc<tokio::sync::Mutex<()>>,
+    /// The `replay` lock, while a reconnect is in progress
+    held: Mutex<Option<OwnedMutexGuard<()>>>,
+    /// Why the last reconnect failed, if it did
+    failure: Mutex<Option<String>>,
+    /// How many calls may be parked; zero means calls aren't queued
+    limit: AtomicUsize,
+    parked: AtomicUsize,
+}
+
+impl ReconnectGate {
+    /// Queue up to `limit` calls during a reconnect; zero fails them instead
+    pub fn set_limit(&self, limit: usize) {
+        self.limit.store(limit, Ordering::Relaxed);
+    }
+
+    /// Start holding calls back
+    ///
+    /// Waits for the calls parked by a previous reconnect to be sent first.
+    pub async fn begin(&self) {
+        let guard = self.replay.clone().lock_owned().await;
+        *lock(&self.failure) = None;
+        *lock(&self.held) = Some(guard);
+    }
+
+    /// Let the parked calls go, or fail all of them with the reconnect error
+    pub fn finish(&self, outcome: Result<(), String>) {
+        if let Err(err) = outcome {
+            *lock(&self.failure) = Some(err);
+        }
+        lock(&self.held).take();
+    }
+
+    fn is_reconnecting(&self) -> bool {
+        lock(&self.held).is_some()
+    }
+
+    /// Wait for a reconnect in progress to finish, if queueing is enabled
+    ///
+    /// Returns the caller's turn to send its request, which must be passed to
+    /// [`send_in_turn`], or `None` when the call should go ahead as usual.
+    pub async fn wait(&self, server: &str, tool: &str) -> Result<Option<OwnedMutexGuard<()>>> {
+        let limit = self.limit.load(Ordering::Relaxed);
+        if limit == 0 || !self.is_reconnecting() {
+            return Ok(None);
+        }
+
+        let Some(parked) = Parked::new(&self.parked, limit) else {
+            return Err(anyhow!(
+                "{limit} calls are already waiting for '{server}' to reconnect"
+            ));
+        };
+        crate::info!(
+            "'{}' is reconnecting; {}.{} will be sent once it's back ({} waiting)",
+            server,
+            server,
+            tool,
+            parked.position
+        );
+
+        let turn = self.replay.clone().lock_owned().await;
+        drop(parked);
+
+        match lock(&self.failure).as_ref() {
+            Some(err) => Err(anyhow!("reconnecting to '{server}' failed: {err}")),
+            None => Ok(Some(turn)),
+        }
+    }
+}
+
+/// Send `request`, then give the next parked call its turn
+///
+/// The turn is handed on once the request has been polled, and so queued
+/// for the transport, rather than when its response arrives.
+pub async fn send_in_turn<T>(
+    turn: Option<OwnedMutexGuard<()>>,
+    request: impl Future<Output = T>,
+) -> T {
+    let mut request = std::pin::pin!(request);
+    if let Some(turn) = turn {
+        if let Poll::Ready(output) = futures::poll!(&mut request) {
+            return output;
+        }
+        drop(turn);
+    }
+    request.await
+}
+
+/// A place in the queue, given up when the call gets its turn or is dropped
+struct Parked<'a> {
+    count: &'a AtomicUsize,
+    position: usize,
+}
+
+impl<'a> Parked<'a> {
+    fn new(count: &'a AtomicUsize, limit: usize) -> Option<Self> {
+        count
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |parked| {
+                (parked < limit).then_some(parked + 1)
+            })
+            .ok()
+            .map(|before| Self {
+                count,
+                position: before + 1,
+            })
+    }
+}
+
+impl Drop for Parked<'_> {
+    fn drop(&mut self) {
+        self.count.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::engine::shared_runtime;
+
+    /// Park a call that records `id` once it's sent, and give it time to queue
+    async fn park(
+        gate: &Arc<ReconnectGate>,
+        sent: &Arc<Mutex<Vec<usize>>>,
+        id: usize,
+    ) -> tokio::task::JoinHandle<Result<()>> {
+        let (gate, sent) = (gate.clone(), sent.clone());
+        let call = tokio::spawn(async move {
+            let turn = gate.wait("flaky", "echo").await?;
+            send_in_turn(turn, async { lock(&sent).push(id) }).await;
+            Ok(())
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        call
+    }
+
+    #[test]
+    fn test_calls_go_ahead_without_a_reconnect() {
+        let gate = ReconnectGate::default();
+        gate.set_limit(4);
+        let turn = shared_runtime()
+            .block_on(gate.wait("flaky", "echo"))
+            .unwrap();
+        assert!(turn.is_none());
+    }
+
+    #[test]
+    fn test_parked_calls_replay_in_order() {
+        let gate = Arc::new(ReconnectGate::default());
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        gate.set_limit(4);
+
+        shared_runtime().block_on(async {
+            gate.begin().await;
+            let mut calls = Vec::new();
+            for id in 0..3 {
+                calls.push(park(&gate, &sent, id).await);
+            }
+            assert!(lock(&sent).is_empty());
+
+            gate.finish(Ok(()));
+            for call in calls {
+                call.await.unwrap().unwrap();
+            }
+        });
+
+        assert_eq!(*lock(&sent), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_failed_reconnect_fails_parked_calls() {
+        let gate = Arc::new(ReconnectGate::default());
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        gate.set_limit(4);
+
+        let errors = shared_runtime().block_on(async {
+            gate.begin().await;
+            let first = park(&gate, &sent, 0).await;
+            let second = park(&gate, &sent, 1).await;
+            gate.finish(Err("connection refused".into()));
+
+            [first.await.unwrap(), second.await.unwrap()]
+        });
+
+        for error in errors {
+            let error = error.unwrap_err().to_string();
+            assert!(error.contains("connection refused"), "{error}");
+        }
+        assert!(lock(&sent).is_empty());
+    }
+
+    #[test]
+    fn test_queue_limit_and_timeouts() {
+        let gate = Arc::new(ReconnectGate::default());
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        gate.set_limit(1);
+
+        shared_runtime().block_on(async {
+            gate.begin().await;
+
+            // A call that times out while parked gives up its place
+            let timed_out =
+                tokio::time::timeout(Duration::from_millis(20), gate.wait("flaky", "echo")).await;
+            assert!(timed_out.is_err());
+
+            let parked = park(&gate, &sent, 0).await;
+            let full = gate.wait("flaky", "echo").await.unwrap_err();
+            assert!(full.to_string().contains("already waiting"), "{full}");
+
+            gate.finish(Ok(()));
+            parked.await.unwrap().unwrap();
+        });
+
+        assert_eq!(*lock(&sent), vec![0]);
+    }
+}
@@ -0,0 +1,215 @@
+//! `resource_link` content blocks, which point at a resource instead of embedding it
+//!
+//! rmcp doesn't know this block type and would reject the whole result, so
+//! the stdio transport rewrites each link into an embedded resource whose
+//! MIME type is [`RESOURCE_LINK_MIME`] and whose text is the link itself.
+//! [`ResourceLink::from_content`] turns such a block back into a link.
+
+use nu_protocol::{Record, Span, Value};
+use rmcp::model::{RawContent, RawEmbeddedResource, ResourceContents};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value as JsonValue, json};
+
+use super::mime::resource_contents_to_value;
+
+/// The MIME type that marks an embedded resource as a rewritten link
+pub const RESOURCE_LINK_MIME: &str = "application/vnd.nu-mcp-repl.resource-link+json";
+
+/// How many links `--follow-links` reads in a single result
+pub const MAX_FOLLOWED_LINKS: usize = 20;
+
+/// A reference to a resource returned by a tool
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceLink {
+    pub uri: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// The server that returned the link
+    #[serde(default)]
+    pub server: String,
+    /// What reading the resource returned, once the link was followed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contents: Option<Vec<ResourceContents>>,
+    /// Why following the link failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ResourceLink {
+    /// The link a content block carries, if it is a rewritten link
+    #[must_use]
+    pub fn from_content(raw: &RawContent) -> Option<Self> {
+        let RawContent::Resource(RawEmbeddedResource {
+            resource:
+                ResourceContents::TextResourceContents {
+                    mime_type: Some(mime_type),
+                    text,
+                    ..
+                },
+        }) = raw
+        else {
+            return None;
+        };
+
+        if mime_type != RESOURCE_LINK_MIME {
+            return None;
+        }
+
+        serde_json::from_str(text).ok()
+    }
+
+    /// The content block that carries the link
+    #[must_use]
+    pub fn to_content(&self) -> RawContent {
+        RawContent::resource(ResourceContents::TextResourceContents {
+            uri: self.uri.clone(),
+            mime_type: Some(RESOURCE_LINK_MIME.into()),
+            text: serde_json::to_string(self).unwrap_or_default(),
+        })
+    }
+
+    /// The fetched contents of a followed link, or a record describing it
+    ///
+    /// A link that failed to follow is described with an `error` column.
+    #[must_use]
+    pub fn to_value(&self, span: Span) -> Value {
+        if let Some(contents) = &self.contents {
+            let mut values: Vec<Value> = contents
+                .iter()
+                .map(|contents| resource_contents_to_value(contents, span))
+                .collect();
+
+            return match values.len() {
+                0 => Value::nothing(span),
+                1 => values.remove(0),
+                _ => Value::list(values, span),
+            };
+        }
+
+        let mut record = Record::new();
+        record.push("type", Value::string("resource_link", span));
+        record.push("uri", Value::string(&self.uri, span));
+        record.push("name", Value::string(&self.name, span));
+        record.push(
+            "mime_type",
+            self.mime_type
+                .as_ref()
+                .map_or_else(|| Value::nothing(span), |mime| Value::string(mime, span)),
+        );
+        record.push("server", Value::string(&self.server, span));
+        if let Some(error) = &self.error {
+            record.push("error", Value::string(error, span));
+        }
+        Value::record(record, span)
+    }
+}
+
+/// Rewrite the `resource_link` blocks of a tool result so rmcp can parse it
+///
+/// `message` is a JSON-RPC message from `server`; anything other than a
+/// result with a `content` list is left alone.
+pub fn encode_resource_links(message: &mut JsonValue, server: &str) {
+    let Some(blocks) = message
+        .pointer_mut("/result/content")
+        .and_then(JsonValue::as_array_mut)
+    else {
+        return;
+    };
+
+    for block in blocks {
+        if block.get("type").and_then(JsonValue::as_str) != Some("resource_link") {
+            continue;
+        }
+
+        let Ok(mut link) = serde_json::from_value::<ResourceLink>(block.clone()) else {
+            continue;
+        };
+        // Only the REPL fills these in
+        link.server = server.to_string();
+        link.contents = None;
+        link.error = None;
+
+        let annotations = block.get("annotations").cloned();
+        *block = json!({
+            "type": "resource",
+            "resource": {
+                "uri": link.uri,
+                "mimeType": RESOURCE_LINK_MIME,
+                "text": serde_json::to_string(&link).unwrap_or_default(),
+            },
+        });
+        if let Some(annotations) = annotations {
+            block["annotations"] = annotations;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rmcp::model::CallToolResult;
+
+    use super::*;
+
+    #[test]
+    fn test_resource_links_survive_parsing() {
+        let mut message = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "content": [
+                    {"type": "text", "text": "Found one file"},
+                    {
+                        "type": "resource_link",
+                        "uri": "file:///notes.md",
+                        "name": "notes.md",
+                        "mimeType": "text/markdown"
+                    }
+                ]
+            }
+        });
+        encode_resource_links(&mut message, "fs");
+
+        let result: CallToolResult = serde_json::from_value(message["result"].clone()).unwrap();
+        assert!(ResourceLink::from_content(&result.content[0].raw).is_none());
+
+        let link = ResourceLink::from_content(&result.content[1].raw).unwrap();
+        assert_eq!(link.uri, "file:///notes.md");
+        assert_eq!(link.name, "notes.md");
+        assert_eq!(link.mime_type.as_deref(), Some("text/markdown"));
+        assert_eq!(link.server, "fs");
+
+        let value = link.to_value(Span::test_data());
+        assert_eq!(
+            value.get_data_by_key("type").unwrap().as_str().unwrap(),
+            "resource_link"
+        );
+        assert!(value.get_data_by_key("error").is_none());
+
+        let broken = ResourceLink {
+            error: Some("not found".into()),
+            ..link.clone()
+        };
+        let value = broken.to_value(Span::test_data());
+        assert_eq!(
+            value.get_data_by_key("error").unwrap().as_str().unwrap(),
+            "not found"
+        );
+
+        let followed = ResourceLink {
+            contents: Some(vec![ResourceContents::TextResourceContents {
+                uri: link.uri.clone(),
+                mime_type: Some("application/json".into()),
+                text: r#"{"title": "Notes"}"#.into(),
+            }]),
+            ..link
+        };
+        let value = followed.to_value(Span::test_data());
+        assert_eq!(
+            value.get_data_by_key("title").unwrap().as_str().unwrap(),
+            "Notes"
+        );
+    }
+}
@@ -0,0 +1,391 @@
+//! Asking the user before a server's sampling request goes any further
+//!
+//! With `sampling = "prompt"`, each `sampling/createMessage` request from a
+//! trusted server is shown in full: the requesting server, the system
+//! prompt, each message with its role, the model hints and the token limit.
+//! Small requests are allowed with `y`; larger ones need `yes` typed out.
+//! `mcp sampling allow <server>` skips the question for the rest of the
+//! session.
+//!
+//! Requests arrive on the runtime while a command waits for its tool call,
+//! and the question is asked by the thread that waits, which holds the
+//! terminal: the request is queued for the [`Foreground`] and the handler
+//! waits for its answer. With no command waiting in the foreground, such as
+//! when the REPL sits at its prompt, there is no one to ask and the request
+//! is refused. No model is configured to answer requests yet, so those the
+//! user allows are then refused as unsupported.
+//!
+//! Requests are read through their JSON form, so any field the client
+//! doesn't model is still shown.
+
+use std::{
+    collections::{BTreeSet, VecDeque},
+    io::IsTerminal,
+    sync::{LazyLock, Mutex, MutexGuard, PoisonError},
+};
+
+use serde_json::Value as JsonValue;
+use tokio::sync::oneshot;
+
+use super::{
+    format::{terminal_width, wrap_markdown},
+    prompt,
+};
+use crate::config::SamplingPolicy;
+
+/// Requests estimated at more tokens than this need `yes` typed to allow
+pub const DEFAULT_SAMPLING_CONFIRM_TOKENS: u64 = 4096;
+
+/// Roughly how many characters of text make a token
+const CHARS_PER_TOKEN: u64 = 4;
+
+/// The servers allowed to sample for the rest of the session
+static ALLOWED: LazyLock<Mutex<BTreeSet<String>>> = LazyLock::new(Mutex::default);
+
+/// The consent questions waiting for the foreground
+static QUESTIONS: LazyLock<Mutex<Questions>> = LazyLock::new(Mutex::default);
+
+/// Held while a question is on the terminal, so two waiting threads never
+/// ask at once
+static ASKING: Mutex<()> = Mutex::new(());
+
+/// How a server's sampling requests are handled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SamplingOptions {
+    pub policy: SamplingPolicy,
+    pub confirm_tokens: u64,
+}
+
+impl Default for SamplingOptions {
+    fn default() -> Self {
+        Self {
+            policy: SamplingPolicy::Refuse,
+            confirm_tokens: DEFAULT_SAMPLING_CONFIRM_TOKENS,
+        }
+    }
+}
+
+/// Allow a server's sampling requests without asking, until it's revoked
+pub fn allow(server: &str) {
+    allowed_servers().insert(server.to_string());
+}
+
+/// Ask again before a server's sampling requests; false if it wasn't allowed
+pub fn revoke(server: &str) -> bool {
+    allowed_servers().remove(server)
+}
+
+/// Whether the server's sampling requests are allowed without asking
+#[must_use]
+pub fn is_allowed(server: &str) -> bool {
+    allowed_servers().contains(server)
+}
+
+fn allowed_servers() -> MutexGuard<'static, BTreeSet<String>> {
+    ALLOWED.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A request waiting for the user's consent
+struct Question {
+    server: String,
+    request: JsonValue,
+    confirm_tokens: u64,
+    answer: oneshot::Sender<Result<(), String>>,
+}
+
+/// The questions not yet asked, and how many threads can ask them
+#[derive(Default)]
+struct Questions {
+    waiting: usize,
+    pending: VecDeque<Question>,
+}
+
+fn questions() -> MutexGuard<'static, Questions> {
+    QUESTIONS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Show the request and ask whether to go on
+///
+/// Servers allowed with `mcp sampling allow` aren't asked. Returns why the
+/// request was refused: the user said no, or there was no one to ask.
+pub async fn ask_consent(
+    server: &str,
+    request: JsonValue,
+    confirm_tokens: u64,
+) -> Result<(), String> {
+    if is_allowed(server) {
+        return Ok(());
+    }
+
+    let (answer, answered) = oneshot::channel();
+    {
+        let mut questions = questions();
+        if questions.waiting == 0 {
+            return Err(format!(
+                "sampling requests from '{server}' need consent, and no command is waiting in the foreground to ask; run `mcp sampling allow {server}` first"
+            ));
+        }
+        questions.pending.push_back(Question {
+            server: server.to_string(),
+            request,
+            confirm_tokens,
+            answer,
+        });
+    }
+
+    answered.await.unwrap_or_else(|_| {
+        Err(format!(
+            "the command waiting in the foreground finished before the sampling request from '{server}' was asked about"
+        ))
+    })
+}
+
+/// A thread waiting in the foreground, which asks the queued questions
+///
+/// [`crate::engine::block_on_shared`] enters the foreground while it waits
+/// and calls [`Foreground::ask_questions`] each time it polls. Questions
+/// still queued when the last thread leaves are refused.
+pub struct Foreground(());
+
+impl Foreground {
+    /// Enter the foreground, or `None` without a terminal to ask on
+    ///
+    /// Unit tests share the terminal `cargo test` runs in, and must never
+    /// stop to ask.
+    #[must_use]
+    pub fn enter() -> Option<Self> {
+        (std::io::stdin().is_terminal() && !cfg!(test)).then(Self::register)
+    }
+
+    fn register() -> Self {
+        questions().waiting += 1;
+        Self(())
+    }
+
+    /// Ask the queued questions one at a time and send back the answers
+    pub fn ask_questions(&self) {
+        let Ok(_asking) = ASKING.try_lock() else {
+            return;
+        };
+
+        loop {
+            let Some(question) = questions().pending.pop_front() else {
+                break;
+            };
+            let _ = question.answer.send(ask(
+                &question.server,
+                &question.request,
+                question.confirm_tokens,
+            ));
+        }
+    }
+}
+
+impl Drop for Foreground {
+    fn drop(&mut self) {
+        let mut questions = questions();
+        questions.waiting -= 1;
+        if questions.waiting == 0 {
+            questions.pending.clear();
+        }
+    }
+}
+
+/// Describe the request and read the user's answer
+///
+/// A command is running, so the description is written right away and the
+/// answer is read from the terminal the command holds.
+fn ask(server: &str, request: &JsonValue, confirm_tokens: u64) -> Result<(), String> {
+    crate::info!("{}", describe_request(server, request, terminal_width()));
+
+    let tokens = estimated_tokens(request);
+    let allowed = if tokens > confirm_tokens {
+        prompt::read_line(&format!(
+            "This request may use about {tokens} tokens. Type yes to allow it: "
+        ))
+        .map(|answer| answer.trim() == "yes")
+    } else {
+        prompt::confirm("Allow this sampling request?")
+    }
+    .map_err(|err| format!("failed to read consent: {err}"))?;
+
+    if allowed {
+        Ok(())
+    } else {
+        Err(format!(
+            "the user declined the sampling request from '{server}'"
+        ))
+    }
+}
+
+/// The request as text to show before asking
+#[must_use]
+pub fn describe_request(server: &str, request: &JsonValue, width: usize) -> String {
+    let indent = |text: &str, by: usize| {
+        let prefix = " ".repeat(by);
+        wrap_markdown(text, width.saturating_sub(by))
+            .lines()
+            .map(|line| format!("{prefix}{line}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let mut lines = vec![format!("Sampling request from '{server}'")];
+
+    if let Some(max_tokens) = request.get("maxTokens").and_then(JsonValue::as_u64) {
+        lines.push(format!("  max tokens: {max_tokens}"));
+    }
+
+    let hints: Vec<&str> = request
+        .pointer("/modelPreferences/hints")
+        .and_then(JsonValue::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|hint| hint.get("name").and_then(JsonValue::as_str))
+        .collect();
+    if !hints.is_empty() {
+        lines.push(format!("  model hints: {}", hints.join(", ")));
+    }
+
+    if let Some(system) = request.get("systemPrompt").and_then(JsonValue::as_str) {
+        lines.push("  system prompt:".into());
+        lines.push(indent(system, 4));
+    }
+
+    lines.push("  messages:".into());
+    for message in request
+        .get("messages")
+        .and_then(JsonValue::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let role = message
+            .get("role")
+            .and_then(JsonValue::as_str)
+            .unwrap_or("unknown");
+        lines.push(format!("    {role}:"));
+        lines.push(indent(&message_text(message), 6));
+    }
+
+    lines.join("\n")
+}
+
+/// The tokens a request may use: its `maxTokens` and its text
+#[must_use]
+pub fn estimated_tokens(request: &JsonValue) -> u64 {
+    let max_tokens = request
+        .get("maxTokens")
+        .and_then(JsonValue::as_u64)
+        .unwrap_or(0);
+
+    let system = request
+        .get("systemPrompt")
+        .and_then(JsonValue::as_str)
+        .map_or(0, str::len);
+    let messages: usize = request
+        .get("messages")
+        .and_then(JsonValue::as_array)
+        .into_iter()
+        .flatten()
+        .map(|message| message_text(message).len())
+        .sum();
+    let chars = u64::try_from(system + messages).unwrap_or(u64::MAX);
+
+    max_tokens.saturating_add(chars / CHARS_PER_TOKEN)
+}
+
+/// A message's text, or a placeholder for an image
+fn message_text(message: &JsonValue) -> String {
+    let content = &message["content"];
+    match content.get("type").and_then(JsonValue::as_str) {
+        Some("text") => content["text"].as_str().unwrap_or_default().to_string(),
+        Some(other) => format!(
+            "[{other}: {}]",
+            content
+                .get("mimeType")
+                .and_then(JsonValue::as_str)
+                .unwrap_or("unknown type")
+        ),
+        None => content.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn request() -> JsonValue {
+        json!({
+            "systemPrompt": "You summarize issues.",
+            "messages": [
+                {"role": "user", "content": {"type": "text", "text": "Summarize issue 12"}},
+                {"role": "assistant", "content": {"type": "image", "data": "", "mimeType": "image/png"}},
+            ],
+            "modelPreferences": {"hints": [{"name": "claude-3-sonnet"}, {"name": "gpt"}]},
+            "maxTokens": 500,
+        })
+    }
+
+    #[test]
+    fn test_describe_request() {
+        let description = describe_request("github", &request(), 80);
+
+        assert_eq!(
+            description,
+            "Sampling request from 'github'
+  max tokens: 500
+  model hints: claude-3-sonnet, gpt
+  system prompt:
+    You summarize issues.
+  messages:
+    user:
+      Summarize issue 12
+    assistant:
+      [image: image/png]"
+        );
+    }
+
+    #[test]
+    fn test_estimated_tokens() {
+        // 500 + (21 + 18 + 18) / 4
+        assert_eq!(estimated_tokens(&request()), 514);
+        assert_eq!(estimated_tokens(&json!({})), 0);
+    }
+
+    #[test]
+    fn test_consent_needs_someone_to_ask() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let ask = || ask_consent("sampling-queue-test", request(), 10);
+
+        // At the prompt, nothing waits in the foreground to ask
+        let refused = runtime.block_on(ask()).unwrap_err();
+        assert!(refused.contains("mcp sampling allow"), "{refused}");
+
+        // A question left unasked when the foreground is left is refused
+        let foreground = Foreground::register();
+        let pending = runtime.spawn(ask());
+        runtime.block_on(tokio::task::yield_now());
+        assert_eq!(questions().pending.len(), 1);
+        drop(foreground);
+        let refused = runtime.block_on(pending).unwrap().unwrap_err();
+        assert!(refused.contains("finished before"), "{refused}");
+
+        // An allowed server isn't asked at all
+        allow("sampling-queue-test");
+        assert_eq!(runtime.block_on(ask()), Ok(()));
+        revoke("sampling-queue-test");
+    }
+
+    #[test]
+    fn test_allow_and_revoke() {
+        assert!(!is_allowed("sampling-test"));
+        allow("sampling-test");
+        assert!(is_allowed("sampling-test"));
+        assert!(revoke("sampling-test"));
+        assert!(!revoke("sampling-test"));
+    }
+}
@@ -0,0 +1,72 @@
+//! On-disk snapshots of what each server reported when it was last connected
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use rmcp::model::{Resource, ResourceTemplate, ServerInfo, Tool};
+use serde::{Deserialize, Serialize};
+
+/// The tool, resource and template definitions a server reported
+///
+/// A snapshot is written after every successful connection and read back in
+/// offline mode, so commands can be registered without starting the server.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SchemaSnapshot {
+    pub server_info: ServerInfo,
+    pub tools: Vec<Tool>,
+    #[serde(default)]
+    pub resources: Vec<Resource>,
+    #[serde(default)]
+    pub templates: Vec<ResourceTemplate>,
+}
+
+impl SchemaSnapshot {
+    /// Write the snapshot for a server, replacing any previous one
+    pub fn save(&self, server: &str) -> Result<()> {
+        let path = snapshot_path(server)?;
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Read the snapshot for a server, or `None` if it was never connected
+    pub fn load(server: &str) -> Result<Option<Self>> {
+        let path = snapshot_path(server)?;
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let json = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let snapshot = serde_json::from_str(&json)
+            .with_context(|| format!("Invalid schema snapshot {}", path.display()))?;
+
+        Ok(Some(snapshot))
+    }
+}
+
+/// The directory schema snapshots are stored in
+fn snapshot_dir() -> Result<PathBuf> {
+    Ok(super::state_dir::cache_dir()?.join("schemas"))
+}
+
+fn snapshot_path(server: &str) -> Result<PathBuf> {
+    let file_name: String = server
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    Ok(snapshot_dir()?.join(format!("{file_name}.json")))
+}
@@ -0,0 +1,107 @@
+//! Writing oversized result blocks to disk instead of rendering them
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use anyhow::{Context, Result};
+use nu_protocol::{Record, ShellError, Span, Value};
+
+/// How much of a spilled text block is kept as a preview
+pub const PREVIEW_BYTES: usize = 16 * 1024;
+
+/// Distinguishes files spilled within the same second
+static SPILLED: AtomicU64 = AtomicU64::new(0);
+
+/// Where spilled results are written: `~/.mcp-repl/results`, or `results`
+/// in `$MCP_STATE_DIR`
+pub fn results_dir() -> Result<PathBuf> {
+    Ok(super::state_dir::state_dir()?.join("results"))
+}
+
+/// Write a block that is too large to show to a file in [`results_dir`]
+///
+/// Returns `{truncated: true, size, path, preview}`, where the preview is the
+/// start of the text for text blocks and nothing for binary ones.
+pub fn spill(bytes: &[u8], extension: &str, text: bool, span: Span) -> Value {
+    match results_dir().and_then(|dir| spill_into(&dir, bytes, extension)) {
+        Ok(path) => spilled_record(bytes, &path, text, span),
+        Err(err) => Value::error(
+            ShellError::GenericError {
+                error: "Failed to save a large tool result".into(),
+                msg: format!("{err:#}"),
+                span: Some(span),
+                help: Some("Pass --full to return the result inline".into()),
+                inner: Vec::new(),
+            },
+            span,
+        ),
+    }
+}
+
+fn spill_into(dir: &Path, bytes: &[u8], extension: &str) -> Result<PathBuf> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let path = dir.join(format!(
+        "{}-{}-{}.{extension}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        std::process::id(),
+        SPILLED.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&path, bytes).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    Ok(path)
+}
+
+fn spilled_record(bytes: &[u8], path: &Path, text: bool, span: Span) -> Value {
+    let mut record = Record::new();
+    record.push("truncated", Value::bool(true, span));
+    record.push(
+        "size",
+        Value::filesize(i64::try_from(bytes.len()).unwrap_or(i64::MAX), span),
+    );
+    record.push("path", Value::string(path.to_string_lossy(), span));
+    record.push(
+        "preview",
+        if text {
+            Value::string(preview(bytes), span)
+        } else {
+            Value::nothing(span)
+        },
+    );
+    Value::record(record, span)
+}
+
+/// The first [`PREVIEW_BYTES`] of a text block, cut at a character boundary
+fn preview(bytes: &[u8]) -> String {
+    let end = bytes.len().min(PREVIEW_BYTES);
+    match std::str::from_utf8(&bytes[..end]) {
+        Ok(text) => text.to_string(),
+        // The cut landed inside a character; keep everything before it
+        Err(err) => String::from_utf8_lossy(&bytes[..err.valid_up_to()]).into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spill_writes_file_and_preview() {
+        let dir = std::env::temp_dir().join(format!("mcp-repl-spill-{}", std::process::id()));
+        let text = "é".repeat(PREVIEW_BYTES);
+
+        let path = spill_into(&dir, text.as_bytes(), "txt").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), text);
+
+        let record = spilled_record(text.as_bytes(), &path, true, Span::test_data());
+        let preview = record.get_data_by_key("preview").unwrap();
+        let preview = preview.as_str().unwrap();
+        assert_eq!(preview.len(), PREVIEW_BYTES);
+        assert!(text.starts_with(preview));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
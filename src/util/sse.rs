@@ -0,0 +1,106 @@
+//! Noticing when an SSE server's event stream is reopened
+//!
+//! rmcp reconnects a dropped SSE stream by itself and says nothing, so a
+//! flaky gateway only shows up as a call that stalls. [`CountingSseClient`]
+//! wraps the HTTP client the transport opens the stream with: every open
+//! after the first is a reconnect, which is counted in the server's
+//! [`ServerStats`] and reported with a status line.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use futures::future::BoxFuture;
+use rmcp::{
+    model::ClientJsonRpcMessage,
+    transport::sse::{BoxedSseStream, ReqwestSseClient, SseClient, SseTransportError},
+};
+
+use super::stats::ServerStats;
+
+/// An SSE client that counts the times the event stream is reopened
+#[derive(Clone)]
+pub struct CountingSseClient {
+    inner: ReqwestSseClient,
+    server: String,
+    stats: Arc<ServerStats>,
+    /// Whether the first stream has been opened, so the next one is a
+    /// reconnect
+    opened: Arc<AtomicBool>,
+}
+
+impl CountingSseClient {
+    pub fn new(url: &str, server: &str, stats: Arc<ServerStats>) -> anyhow::Result<Self> {
+        Ok(Self {
+            inner: ReqwestSseClient::new(url)?,
+            server: server.to_string(),
+            stats,
+            opened: Arc::default(),
+        })
+    }
+}
+
+impl SseClient<reqwest::Error> for CountingSseClient {
+    fn connect(
+        &self,
+        last_event_id: Option<String>,
+    ) -> BoxFuture<'static, Result<BoxedSseStream, SseTransportError<reqwest::Error>>> {
+        let connect = self.inner.connect(last_event_id);
+        let (server, stats, opened) =
+            (self.server.clone(), self.stats.clone(), self.opened.clone());
+
+        Box::pin(async move {
+            let stream = connect.await?;
+            if opened.swap(true, Ordering::Relaxed) {
+                let times = stats.record_sse_reconnect();
+                crate::warning!(
+                    "{}: SSE stream reconnected ({} time this session)",
+                    server,
+                    ordinal(times)
+                );
+            }
+            Ok(stream)
+        })
+    }
+
+    fn post(
+        &self,
+        session_id: &str,
+        message: ClientJsonRpcMessage,
+    ) -> BoxFuture<'static, Result<(), SseTransportError<reqwest::Error>>> {
+        self.inner.post(session_id, message)
+    }
+}
+
+/// `1st`, `2nd`, `3rd`, `4th`, ..., `11th`, ..., `21st`
+fn ordinal(n: u64) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ordinal() {
+        let ordinals: Vec<String> = [1, 2, 3, 4, 11, 12, 13, 21, 22, 101, 111]
+            .into_iter()
+            .map(ordinal)
+            .collect();
+        assert_eq!(
+            ordinals,
+            [
+                "1st", "2nd", "3rd", "4th", "11th", "12th", "13th", "21st", "22nd", "101st",
+                "111th"
+            ]
+        );
+    }
+}
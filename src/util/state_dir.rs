@@ -0,0 +1,59 @@
+//! Where history, cached schemas and spilled results are kept
+//!
+//! History and spilled results live in `~/.mcp-repl`, schema snapshots in
+//! the platform's cache directory. `MCP_STATE_DIR` puts all of them in one
+//! directory instead. In a container or CI job there may be no home
+//! directory, or one that can't be written; the features that need these
+//! directories are turned off then, and the REPL still starts.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, anyhow};
+
+/// The environment variable that overrides every state directory
+pub const STATE_DIR_ENV: &str = "MCP_STATE_DIR";
+
+/// The directory for history and spilled results: `$MCP_STATE_DIR` or
+/// `~/.mcp-repl`
+pub fn state_dir() -> Result<PathBuf> {
+    if let Some(dir) = override_dir() {
+        return Ok(dir);
+    }
+
+    dirs::home_dir()
+        .map(|home| home.join(".mcp-repl"))
+        .ok_or_else(|| unavailable("the home directory"))
+}
+
+/// The directory for schema snapshots: `$MCP_STATE_DIR/cache` or
+/// `mcp-repl` in the platform's cache directory
+pub fn cache_dir() -> Result<PathBuf> {
+    if let Some(dir) = override_dir() {
+        return Ok(dir.join("cache"));
+    }
+
+    dirs::cache_dir()
+        .map(|cache| cache.join("mcp-repl"))
+        .ok_or_else(|| unavailable("the cache directory"))
+}
+
+/// Create `dir` if it doesn't exist yet
+pub fn create(dir: PathBuf) -> Result<PathBuf> {
+    std::fs::create_dir_all(&dir).with_context(|| {
+        format!(
+            "Failed to create {}; set {STATE_DIR_ENV} to a writable directory",
+            dir.display()
+        )
+    })?;
+    Ok(dir)
+}
+
+fn override_dir() -> Option<PathBuf> {
+    std::env::var_os(STATE_DIR_ENV)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+fn unavailable(what: &str) -> anyhow::Error {
+    anyhow!("Could not determine {what}; set {STATE_DIR_ENV} to keep state elsewhere")
+}
@@ -0,0 +1,175 @@
+//! Per-server request counters, shown by `mcp list --stats` and `mcp info`
+
+use std::{
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Local};
+use nu_protocol::{Record, Span, Value};
+
+/// What happened on a server's connection since it connected
+///
+/// The counters are atomics, so calls update them without taking the
+/// client manager's lock. They're shared by every clone of the client and
+/// survive restarts; only `connected_at` and `connect_time` are reset by a
+/// reconnect.
+#[derive(Debug, Default)]
+pub struct ServerStats {
+    requests: AtomicU64,
+    failures: AtomicU64,
+    /// Times rmcp reopened an SSE server's event stream
+    sse_reconnects: AtomicU64,
+    bytes_received: AtomicU64,
+    connected_at: Mutex<Option<DateTime<Local>>>,
+    /// How long connecting took, up to the end of the handshake
+    connect_time: Mutex<Option<Duration>>,
+    last_error: Mutex<Option<(DateTime<Local>, String)>>,
+}
+
+impl ServerStats {
+    /// Note that the connection was (re)established just now, after
+    /// `connect_time`
+    pub fn connected(&self, connect_time: Duration) {
+        *lock(&self.connected_at) = Some(Local::now());
+        *lock(&self.connect_time) = Some(connect_time);
+    }
+
+    pub fn record_request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count `bytes` of content received in a response
+    pub fn record_received(&self, bytes: usize) {
+        self.bytes_received
+            .fetch_add(u64::try_from(bytes).unwrap_or(u64::MAX), Ordering::Relaxed);
+    }
+
+    /// Count a failed request and remember why it failed
+    pub fn record_failure(&self, error: impl Into<String>) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        *lock(&self.last_error) = Some((Local::now(), error.into()));
+    }
+
+    /// Count a reopened SSE event stream, returning how many there have been
+    pub fn record_sse_reconnect(&self) -> u64 {
+        self.sse_reconnects.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Clear the counters and the last error, for `mcp stats --reset`
+    pub fn reset(&self) {
+        self.requests.store(0, Ordering::Relaxed);
+        self.failures.store(0, Ordering::Relaxed);
+        self.sse_reconnects.store(0, Ordering::Relaxed);
+        self.bytes_received.store(0, Ordering::Relaxed);
+        *lock(&self.last_error) = None;
+    }
+
+    /// The stats as columns: uptime, connect time, counters and the last error
+    #[must_use]
+    pub fn to_record(&self, span: Span) -> Record {
+        let count = |counter: &AtomicU64| {
+            Value::int(
+                i64::try_from(counter.load(Ordering::Relaxed)).unwrap_or(i64::MAX),
+                span,
+            )
+        };
+        let date = |date: DateTime<Local>| Value::date(date.fixed_offset(), span);
+
+        let connected_at = *lock(&self.connected_at);
+        let connect_time = *lock(&self.connect_time);
+        let last_error = lock(&self.last_error).clone();
+
+        let mut record = Record::new();
+        record.push(
+            "connected_at",
+            connected_at.map_or_else(|| Value::nothing(span), date),
+        );
+        record.push(
+            "uptime",
+            connected_at.map_or_else(
+                || Value::nothing(span),
+                |connected_at| {
+                    let uptime = Local::now() - connected_at;
+                    Value::duration(uptime.num_nanoseconds().unwrap_or(i64::MAX), span)
+                },
+            ),
+        );
+        record.push(
+            "connect_time",
+            connect_time.map_or_else(
+                || Value::nothing(span),
+                |took| Value::duration(i64::try_from(took.as_nanos()).unwrap_or(i64::MAX), span),
+            ),
+        );
+        record.push("requests", count(&self.requests));
+        record.push("failures", count(&self.failures));
+        record.push("sse_reconnects", count(&self.sse_reconnects));
+        record.push(
+            "bytes_received",
+            Value::filesize(
+                i64::try_from(self.bytes_received.load(Ordering::Relaxed)).unwrap_or(i64::MAX),
+                span,
+            ),
+        );
+        match last_error {
+            Some((at, error)) => {
+                record.push("last_error", Value::string(error, span));
+                record.push("last_error_at", date(at));
+            }
+            None => {
+                record.push("last_error", Value::nothing(span));
+                record.push("last_error_at", Value::nothing(span));
+            }
+        }
+
+        record
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_record_and_reset() {
+        let stats = ServerStats::default();
+        let span = Span::test_data();
+        assert!(stats.to_record(span).get("uptime").unwrap().is_nothing());
+
+        stats.connected(Duration::from_millis(1500));
+        stats.record_request();
+        stats.record_received(120);
+        stats.record_request();
+        stats.record_failure("Failed to call tool: connection closed");
+        assert_eq!(stats.record_sse_reconnect(), 1);
+
+        let record = stats.to_record(span);
+        assert_eq!(record.get("requests").unwrap().as_int().unwrap(), 2);
+        assert_eq!(record.get("failures").unwrap().as_int().unwrap(), 1);
+        assert_eq!(record.get("sse_reconnects").unwrap().as_int().unwrap(), 1);
+        assert_eq!(
+            record.get("connect_time").unwrap().as_duration().unwrap(),
+            1_500_000_000
+        );
+        assert_eq!(
+            record.get("last_error").unwrap().as_str().unwrap(),
+            "Failed to call tool: connection closed"
+        );
+
+        stats.reset();
+        let record = stats.to_record(span);
+        assert_eq!(record.get("requests").unwrap().as_int().unwrap(), 0);
+        assert_eq!(record.get("sse_reconnects").unwrap().as_int().unwrap(), 0);
+        assert!(record.get("last_error").unwrap().is_nothing());
+        // Resetting the counters doesn't reset the uptime
+        assert!(!record.get("connected_at").unwrap().is_nothing());
+    }
+}
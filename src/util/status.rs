@@ -1,13 +1,27 @@
 #![allow(dead_code)]
 //! Status message utilities for the MCP REPL
 //! Provides pretty-formatted status messages that stand out from regular logging
+//!
+//! Messages can arrive at any time: a credential refresh fails, a watched
+//! resource changes, a reconnect starts, all while the user is typing. Written
+//! straight to the terminal, they'd land in the middle of the line being
+//! edited. So everything here goes through one [`OutputBroker`], which holds
+//! messages back while the REPL sits at its prompt and writes them, in order,
+//! as soon as the next command starts or the next prompt is drawn. Outside
+//! the interactive prompt, and in `--commands` scripts, it writes right away.
 
-use std::io::{self, Write};
+use std::{
+    collections::VecDeque,
+    io::{self, IsTerminal, Write},
+    sync::{LazyLock, Mutex, MutexGuard, PoisonError},
+};
 
 use nu_ansi_term;
 use nu_color_config::StyleComputer;
 use nu_protocol::{Span, Value};
 
+use super::memory::{Limit, Store, Usage};
+
 /// Level of status message
 #[derive(Debug, Clone, Copy)]
 pub enum Level {
@@ -88,5 +102,425 @@ pub fn print_status(message: &str, prefix: &str, level: Level) {
     let styled_prefix = style.paint(format!("[{prefix}]"));
 
     // Print to stdout (no log noise)
-    let _ = io::stdout().write_all(format!("{styled_prefix} {message}\n").as_bytes());
+    OUTPUT.write(Stream::Stdout, &format!("{styled_prefix} {message}\n"));
+}
+
+/// The broker all output that can arrive in the background goes through
+pub static OUTPUT: LazyLock<OutputBroker> =
+    LazyLock::new(|| OutputBroker::new(Box::new(write_to_terminal)));
+
+/// Where a message is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Returns to the start of the line and erases it, to replace a status line
+const CLEAR_LINE: &str = "\r\x1b[2K";
+
+/// Writes whole messages to the terminal
+pub type Sink = Box<dyn Fn(Stream, &str) + Send + Sync>;
+
+fn write_to_terminal(stream: Stream, message: &str) {
+    // Each message is written in one go, so another can't split it
+    let _ = match stream {
+        Stream::Stdout => {
+            let mut stdout = io::stdout().lock();
+            stdout
+                .write_all(message.as_bytes())
+                .and_then(|()| stdout.flush())
+        }
+        Stream::Stderr => io::stderr().lock().write_all(message.as_bytes()),
+    };
+}
+
+/// Serializes messages from every thread and keeps them off the prompt
+///
+/// nu-cli builds the line editor itself and doesn't hand out reedline's
+/// external printer, so messages that arrive while the user is typing are
+/// queued instead. The REPL's `pre_prompt` and `pre_execution` hooks run
+/// `mcp output`, which tells the broker when the prompt is up.
+///
+/// A status line, like the progress shown while servers connect, stays at
+/// the bottom of stderr: it's erased before each message and drawn again
+/// after it.
+pub struct OutputBroker {
+    sink: Sink,
+    state: Mutex<BrokerState>,
+}
+
+struct BrokerState {
+    at_prompt: bool,
+    queued: VecDeque<(Stream, String)>,
+    limit: Limit,
+    /// The size of the queued messages
+    bytes: usize,
+    evicted: u64,
+    /// Messages evicted since the queue was last written
+    unreported: u64,
+    /// The status line on screen
+    status_line: Option<String>,
+}
+
+impl OutputBroker {
+    /// A broker that writes through `sink`, starting away from the prompt
+    #[must_use]
+    pub fn new(sink: Sink) -> Self {
+        Self {
+            sink,
+            state: Mutex::new(BrokerState {
+                at_prompt: false,
+                queued: VecDeque::new(),
+                limit: Store::Output.default_limit(),
+                bytes: 0,
+                evicted: 0,
+                unreported: 0,
+                status_line: None,
+            }),
+        }
+    }
+
+    /// Hold back at most `limit` at the prompt, dropping the oldest first
+    pub fn set_limit(&self, limit: Limit) {
+        let mut state = self.state();
+        state.limit = limit;
+        state.truncate();
+    }
+
+    /// Write `message`, or queue it while the prompt is up
+    pub fn write(&self, stream: Stream, message: &str) {
+        let mut state = self.state();
+        if state.at_prompt {
+            state.bytes += message.len();
+            state.queued.push_back((stream, message.to_string()));
+            state.truncate();
+        } else {
+            if state.status_line.is_some() {
+                (self.sink)(Stream::Stderr, CLEAR_LINE);
+            }
+            self.flush(&mut state);
+            (self.sink)(stream, message);
+            if let Some(line) = &state.status_line {
+                (self.sink)(Stream::Stderr, line);
+            }
+        }
+    }
+
+    /// Show `line` as the status line, replacing the one on screen
+    ///
+    /// Nothing is drawn while the prompt is up.
+    pub fn show_status_line(&self, line: &str) {
+        let mut state = self.state();
+        if state.at_prompt {
+            return;
+        }
+        (self.sink)(Stream::Stderr, &format!("{CLEAR_LINE}{line}"));
+        state.status_line = Some(line.to_string());
+    }
+
+    /// Erase the status line, if one is on screen
+    pub fn clear_status_line(&self) {
+        let mut state = self.state();
+        if state.status_line.take().is_some() {
+            (self.sink)(Stream::Stderr, CLEAR_LINE);
+        }
+    }
+
+    /// The prompt is about to be drawn: write what's queued, then hold
+    /// messages back until the next command starts
+    pub fn prompt_shown(&self) {
+        let mut state = self.state();
+        if state.status_line.take().is_some() {
+            (self.sink)(Stream::Stderr, CLEAR_LINE);
+        }
+        self.flush(&mut state);
+        state.at_prompt = true;
+    }
+
+    /// A command is starting, or the REPL is exiting: write what's queued,
+    /// then write messages as they come
+    pub fn prompt_left(&self) {
+        let mut state = self.state();
+        state.at_prompt = false;
+        self.flush(&mut state);
+    }
+
+    /// Whether messages are being held back for the prompt
+    #[must_use]
+    pub fn at_prompt(&self) -> bool {
+        self.state().at_prompt
+    }
+
+    /// How many messages are waiting for the prompt to go away
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.state().queued.len()
+    }
+
+    /// How many messages are held back and their size
+    #[must_use]
+    pub fn usage(&self) -> Usage {
+        let state = self.state();
+        Usage {
+            entries: state.queued.len(),
+            bytes: state.bytes,
+            evicted: state.evicted,
+        }
+    }
+
+    fn flush(&self, state: &mut BrokerState) {
+        if state.unreported > 0 {
+            (self.sink)(
+                Stream::Stderr,
+                &format!(
+                    "({} older background messages were dropped; raise memory.output to keep more)\n",
+                    state.unreported
+                ),
+            );
+            state.unreported = 0;
+        }
+        for (stream, message) in state.queued.drain(..) {
+            (self.sink)(stream, &message);
+        }
+        state.bytes = 0;
+    }
+
+    fn state(&self) -> MutexGuard<'_, BrokerState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl BrokerState {
+    fn truncate(&mut self) {
+        while self.limit.exceeded_by(self.queued.len(), self.bytes) {
+            let Some((_, message)) = self.queued.pop_front() else {
+                break;
+            };
+            self.bytes -= message.len();
+            self.evicted += 1;
+            self.unreported += 1;
+        }
+    }
+}
+
+/// A heading for grouped output, bold unless colors are turned off
+#[must_use]
+pub fn heading(text: &str, use_color: bool) -> String {
+    if use_color {
+        nu_ansi_term::Style::new().bold().paint(text).to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// The `[mcp:<server>]` tag that starts errors coming from an MCP server
+///
+/// It tells them apart from Nushell's own parse and shell errors in the
+/// scrollback. It's styled only when errors go to a terminal.
+#[must_use]
+pub fn mcp_tag(server: &str) -> String {
+    let tag = format!("[mcp:{server}]");
+    if io::stderr().is_terminal() {
+        nu_ansi_term::Style::new()
+            .fg(nu_ansi_term::Color::Magenta)
+            .bold()
+            .paint(tag)
+            .to_string()
+    } else {
+        tag
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+        thread,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_broker_keeps_messages_whole_and_in_order() {
+        const WRITERS: usize = 8;
+        const MESSAGES: usize = 200;
+
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let sink_written = written.clone();
+        let broker = Arc::new(OutputBroker::new(Box::new(
+            move |stream: Stream, message: &str| {
+                // Written a piece at a time, so a second writer would show up
+                // between the pieces
+                let mut written = sink_written.lock().unwrap();
+                for line in message.split_inclusive('\n') {
+                    written.push((stream, line.to_string()));
+                    thread::yield_now();
+                }
+            },
+        )));
+
+        // The REPL going back and forth between the prompt and a script's
+        // commands, the way its hooks do
+        let done = Arc::new(AtomicBool::new(false));
+        let script = {
+            let broker = broker.clone();
+            let done = done.clone();
+            thread::spawn(move || {
+                while !done.load(Ordering::Relaxed) {
+                    broker.prompt_shown();
+                    thread::yield_now();
+                    broker.prompt_left();
+                }
+            })
+        };
+
+        let writers: Vec<_> = (0..WRITERS)
+            .map(|writer| {
+                let broker = broker.clone();
+                thread::spawn(move || {
+                    for n in 0..MESSAGES {
+                        broker.write(
+                            Stream::Stdout,
+                            &format!("{writer}:{n}:start\n{writer}:{n}:middle\n{writer}:{n}:end\n"),
+                        );
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        done.store(true, Ordering::Relaxed);
+        script.join().unwrap();
+        broker.prompt_left();
+
+        let written = written.lock().unwrap();
+        assert_eq!(written.len(), WRITERS * MESSAGES * 3);
+
+        let mut next = BTreeMap::new();
+        for message in written.chunks(3) {
+            let lines: Vec<&str> = message.iter().map(|(_, line)| line.trim_end()).collect();
+            let (writer, n) = lines[0]
+                .strip_suffix(":start")
+                .and_then(|id| id.split_once(':'))
+                .unwrap_or_else(|| panic!("interleaved: {lines:?}"));
+            assert_eq!(
+                lines,
+                [
+                    format!("{writer}:{n}:start"),
+                    format!("{writer}:{n}:middle"),
+                    format!("{writer}:{n}:end")
+                ],
+                "interleaved"
+            );
+
+            let expected = next.entry(writer.to_string()).or_insert(0);
+            assert_eq!(n.parse::<usize>().unwrap(), *expected, "out of order");
+            *expected += 1;
+        }
+    }
+
+    #[test]
+    fn test_broker_holds_messages_at_the_prompt() {
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let sink_written = written.clone();
+        let broker = OutputBroker::new(Box::new(move |_: Stream, message: &str| {
+            sink_written.lock().unwrap().push(message.to_string());
+        }));
+
+        broker.write(Stream::Stdout, "before");
+        broker.prompt_shown();
+        broker.write(Stream::Stderr, "typing 1");
+        broker.write(Stream::Stdout, "typing 2");
+        assert_eq!(*written.lock().unwrap(), ["before"]);
+        assert_eq!(broker.queued(), 2);
+
+        broker.prompt_left();
+        broker.write(Stream::Stdout, "running");
+        assert_eq!(
+            *written.lock().unwrap(),
+            ["before", "typing 1", "typing 2", "running"]
+        );
+        assert_eq!(broker.queued(), 0);
+    }
+
+    #[test]
+    fn test_broker_keeps_the_status_line_below_messages() {
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let sink_written = written.clone();
+        let broker = OutputBroker::new(Box::new(move |stream: Stream, message: &str| {
+            sink_written
+                .lock()
+                .unwrap()
+                .push((stream, message.to_string()));
+        }));
+
+        broker.show_status_line("connecting 1/2");
+        broker.write(Stream::Stdout, "[INFO] Registering MCP client: fs\n");
+        broker.show_status_line("connecting 2/2");
+        broker.clear_status_line();
+        broker.write(Stream::Stdout, "done\n");
+
+        assert_eq!(
+            *written.lock().unwrap(),
+            [
+                (Stream::Stderr, format!("{CLEAR_LINE}connecting 1/2")),
+                (Stream::Stderr, CLEAR_LINE.to_string()),
+                (
+                    Stream::Stdout,
+                    "[INFO] Registering MCP client: fs\n".to_string()
+                ),
+                (Stream::Stderr, "connecting 1/2".to_string()),
+                (Stream::Stderr, format!("{CLEAR_LINE}connecting 2/2")),
+                (Stream::Stderr, CLEAR_LINE.to_string()),
+                (Stream::Stdout, "done\n".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_broker_drops_the_oldest_held_messages() {
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let sink_written = written.clone();
+        let broker = OutputBroker::new(Box::new(move |_: Stream, message: &str| {
+            sink_written.lock().unwrap().push(message.to_string());
+        }));
+        broker.set_limit(Limit {
+            entries: 3,
+            bytes: 13,
+        });
+
+        broker.prompt_shown();
+        for message in ["one", "two", "three", "four"] {
+            broker.write(Stream::Stderr, message);
+        }
+        assert_eq!(
+            broker.usage(),
+            Usage {
+                entries: 3,
+                bytes: 12,
+                evicted: 1,
+            }
+        );
+        broker.write(Stream::Stderr, "five");
+        assert_eq!(
+            broker.usage(),
+            Usage {
+                entries: 3,
+                bytes: 13,
+                evicted: 2,
+            }
+        );
+
+        broker.prompt_left();
+        let written = written.lock().unwrap();
+        assert!(written[0].starts_with("(2 older background messages were dropped"));
+        assert_eq!(written[1..], ["three", "four", "five"]);
+        assert_eq!(broker.usage().bytes, 0);
+    }
 }
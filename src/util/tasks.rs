@@ -0,0 +1,144 @@
+//! Background tasks that have to stop when the REPL exits
+//!
+//! Features that keep working in the background, like credential refreshes
+//! and resource watches, spawn their tasks here instead of on the shared
+//! runtime directly. When the REPL exits, [`shutdown`] cancels them all,
+//! waits a moment for them to stop, and names the ones that didn't, so no
+//! task outlives the session or panics during teardown.
+
+use std::{
+    future::Future,
+    sync::{LazyLock, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use futures::future::select;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::engine::shared_runtime;
+
+/// How long background tasks get to stop when the REPL exits
+pub const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+/// The tasks of this session
+static TASKS: LazyLock<TaskRegistry> = LazyLock::new(TaskRegistry::default);
+
+/// Named tasks on the shared runtime that are cancelled together
+#[derive(Default)]
+pub struct TaskRegistry {
+    cancel: CancellationToken,
+    tasks: Mutex<Vec<(String, JoinHandle<()>)>>,
+}
+
+impl TaskRegistry {
+    /// Run `task` on the shared runtime until it ends or the registry shuts down
+    pub fn spawn(&self, name: impl Into<String>, task: impl Future<Output = ()> + Send + 'static) {
+        let cancelled = self.cancel.clone().cancelled_owned();
+        let handle = shared_runtime().spawn(async move {
+            select(Box::pin(cancelled), Box::pin(task)).await;
+        });
+
+        let mut tasks = self.tasks();
+        tasks.retain(|(_, handle)| !handle.is_finished());
+        tasks.push((name.into(), handle));
+    }
+
+    /// The names of the tasks that are still running
+    #[cfg(test)]
+    #[must_use]
+    pub fn running(&self) -> Vec<String> {
+        self.tasks()
+            .iter()
+            .filter(|(_, handle)| !handle.is_finished())
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Cancel every task and wait up to `grace` for them all to stop
+    ///
+    /// A task stops at its next `.await`; one that's stuck in blocking code
+    /// is aborted after the grace period. Returns the names of those, which
+    /// are also logged.
+    #[must_use]
+    pub fn shutdown(&self, grace: Duration) -> Vec<String> {
+        self.cancel.cancel();
+        let tasks = std::mem::take(&mut *self.tasks());
+        if tasks.is_empty() {
+            return Vec::new();
+        }
+
+        shared_runtime().block_on(async {
+            let deadline = tokio::time::Instant::now() + grace;
+            let mut stragglers = Vec::new();
+
+            for (name, mut handle) in tasks {
+                if tokio::time::timeout_at(deadline, &mut handle)
+                    .await
+                    .is_err()
+                {
+                    handle.abort();
+                    log::warn!(
+                        "Background task '{name}' didn't stop within {}; aborting it",
+                        humantime::format_duration(grace)
+                    );
+                    stragglers.push(name);
+                }
+            }
+
+            stragglers
+        })
+    }
+
+    fn tasks(&self) -> MutexGuard<'_, Vec<(String, JoinHandle<()>)>> {
+        self.tasks.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Run a background task for the rest of the session, see [`TaskRegistry::spawn`]
+pub fn spawn(name: impl Into<String>, task: impl Future<Output = ()> + Send + 'static) {
+    TASKS.spawn(name, task);
+}
+
+/// Stop the session's background tasks, see [`TaskRegistry::shutdown`]
+#[must_use]
+pub fn shutdown(grace: Duration) -> Vec<String> {
+    TASKS.shutdown(grace)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, time::Instant};
+
+    use super::*;
+
+    #[test]
+    fn test_shutdown_stops_tasks_within_the_grace_period() {
+        let registry = TaskRegistry::default();
+        for name in ["keepalive", "watch", "refresh"] {
+            registry.spawn(name, async {
+                loop {
+                    tokio::time::sleep(Duration::from_secs(3600)).await;
+                }
+            });
+        }
+        registry.spawn("done", async {});
+
+        // A task that blocks its thread can't be cancelled, only abandoned
+        let (started, has_started) = mpsc::channel();
+        registry.spawn("stuck", async move {
+            let _ = started.send(());
+            std::thread::sleep(Duration::from_secs(1));
+        });
+        has_started.recv().unwrap();
+        assert!(registry.running().contains(&"keepalive".to_string()));
+
+        let grace = Duration::from_millis(200);
+        let began = Instant::now();
+        let stragglers = registry.shutdown(grace);
+
+        assert!(began.elapsed() < grace + Duration::from_millis(500));
+        assert_eq!(stragglers, vec!["stuck"]);
+        assert!(registry.running().is_empty());
+    }
+}
@@ -0,0 +1,290 @@
+//! Tracing spans for connects and tool calls (the `telemetry` feature)
+//!
+//! With the feature on, every connect and tool call gets a span carrying the
+//! server, the tool, the duration and the outcome. The spans are exported
+//! over OTLP when `MCP_OTLP_ENDPOINT` is set, and logged at debug level
+//! otherwise. Without the feature, [`traced`] just awaits the future.
+
+use std::future::Future;
+
+/// What a traced span covers
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(not(feature = "telemetry"), allow(dead_code))]
+pub enum Operation<'a> {
+    /// Connecting to a server and loading its tools (`mcp.connect`)
+    Connect { server: &'a str },
+    /// A single tool call (`mcp.tool_call`)
+    ToolCall { server: &'a str, tool: &'a str },
+}
+
+/// Run `future` inside a span for `operation`
+///
+/// The span's `outcome` is `error` when the future fails, and otherwise
+/// whatever `ok_outcome` says about the result.
+#[cfg(feature = "telemetry")]
+pub async fn traced<T, E, F>(
+    operation: Operation<'_>,
+    ok_outcome: impl FnOnce(&T) -> &'static str,
+    future: F,
+) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    use tracing::{Instrument, field::Empty};
+
+    let span = match operation {
+        Operation::Connect { server } => tracing::info_span!(
+            target: TARGET,
+            "mcp.connect",
+            server,
+            duration_ms = Empty,
+            outcome = Empty
+        ),
+        Operation::ToolCall { server, tool } => tracing::info_span!(
+            target: TARGET,
+            "mcp.tool_call",
+            server,
+            tool,
+            duration_ms = Empty,
+            outcome = Empty
+        ),
+    };
+
+    let started = std::time::Instant::now();
+    let result = future.instrument(span.clone()).await;
+
+    span.record(
+        "duration_ms",
+        u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+    );
+    span.record(
+        "outcome",
+        match &result {
+            Ok(value) => ok_outcome(value),
+            Err(_) => "error",
+        },
+    );
+
+    result
+}
+
+/// Run `future`; spans are only recorded with the `telemetry` feature
+#[cfg(not(feature = "telemetry"))]
+pub async fn traced<T, E, F>(
+    _operation: Operation<'_>,
+    _ok_outcome: impl FnOnce(&T) -> &'static str,
+    future: F,
+) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    future.await
+}
+
+/// Keeps the span exporter running; dropping it flushes what is left
+#[derive(Default)]
+pub struct Telemetry {
+    #[cfg(feature = "telemetry")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+/// Set up span recording; a no-op without the `telemetry` feature
+#[cfg(not(feature = "telemetry"))]
+#[allow(clippy::unnecessary_wraps)]
+pub fn init() -> anyhow::Result<Telemetry> {
+    Ok(Telemetry::default())
+}
+
+#[cfg(feature = "telemetry")]
+pub use enabled::init;
+
+/// The target every span from this module is created with, so the
+/// subscriber can leave out spans from dependencies
+#[cfg(feature = "telemetry")]
+const TARGET: &str = "mcp_repl::telemetry";
+
+#[cfg(feature = "telemetry")]
+mod enabled {
+    use std::fmt::{self, Write as _};
+
+    use anyhow::{Context as _, Result};
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::WithExportConfig as _;
+    use opentelemetry_sdk::{Resource, trace::SdkTracerProvider};
+    use tracing::{
+        Subscriber,
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+    };
+    use tracing_subscriber::{
+        Layer,
+        filter::filter_fn,
+        layer::{Context, SubscriberExt as _},
+        registry::LookupSpan,
+    };
+
+    use super::{TARGET, Telemetry};
+
+    /// The variable that turns on OTLP export
+    const OTLP_ENDPOINT_VAR: &str = "MCP_OTLP_ENDPOINT";
+
+    /// Install the global subscriber: OTLP export when `MCP_OTLP_ENDPOINT`
+    /// is set, debug-level log lines otherwise
+    pub fn init() -> Result<Telemetry> {
+        let ours = || filter_fn(|metadata| metadata.target() == TARGET);
+
+        let Ok(endpoint) = std::env::var(OTLP_ENDPOINT_VAR) else {
+            let layer = SpanLogLayer::new(|name: &str, fields: &str| log::debug!("{name}{fields}"));
+            tracing::subscriber::set_global_default(
+                tracing_subscriber::registry().with(layer.with_filter(ours())),
+            )
+            .context("Failed to install the tracing subscriber")?;
+            return Ok(Telemetry::default());
+        };
+
+        // The exporter's connection lives on the runtime that is entered
+        // when it's built, so use the one that outlives every command
+        let _runtime = crate::engine::shared_runtime().enter();
+
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(&endpoint)
+            .build()
+            .with_context(|| format!("Failed to set up OTLP export to {endpoint}"))?;
+
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(env!("CARGO_PKG_NAME"))
+                    .build(),
+            )
+            .build();
+
+        let layer = tracing_opentelemetry::layer()
+            .with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+            .with_filter(ours());
+        tracing::subscriber::set_global_default(tracing_subscriber::registry().with(layer))
+            .context("Failed to install the tracing subscriber")?;
+
+        Ok(Telemetry {
+            provider: Some(provider),
+        })
+    }
+
+    impl Drop for Telemetry {
+        fn drop(&mut self) {
+            if let Some(provider) = self.provider.take() {
+                if let Err(err) = provider.shutdown() {
+                    log::warn!("Failed to flush the remaining spans: {err}");
+                }
+            }
+        }
+    }
+
+    /// The fields recorded on a span so far, as ` name=value` pairs
+    #[derive(Default)]
+    struct SpanFields(String);
+
+    impl Visit for SpanFields {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            let _ = write!(self.0, " {}={value}", field.name());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            let _ = write!(self.0, " {}={value:?}", field.name());
+        }
+    }
+
+    /// Hands each span's name and fields to a callback when the span closes
+    pub(super) struct SpanLogLayer<F> {
+        on_close: F,
+    }
+
+    impl<F> SpanLogLayer<F>
+    where
+        F: Fn(&str, &str) + Send + Sync + 'static,
+    {
+        pub(super) const fn new(on_close: F) -> Self {
+            Self { on_close }
+        }
+    }
+
+    impl<S, F> Layer<S> for SpanLogLayer<F>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+        F: Fn(&str, &str) + Send + Sync + 'static,
+    {
+        fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+            let mut fields = SpanFields::default();
+            attrs.record(&mut fields);
+            if let Some(span) = ctx.span(id) {
+                span.extensions_mut().insert(fields);
+            }
+        }
+
+        fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+            if let Some(span) = ctx.span(id) {
+                if let Some(fields) = span.extensions_mut().get_mut::<SpanFields>() {
+                    values.record(fields);
+                }
+            }
+        }
+
+        fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+            if let Some(span) = ctx.span(&id) {
+                let extensions = span.extensions();
+                let fields = extensions
+                    .get::<SpanFields>()
+                    .map_or("", |fields| fields.0.as_str());
+                (self.on_close)(span.name(), fields);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "telemetry"))]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tracing_subscriber::layer::SubscriberExt as _;
+
+    use super::{enabled::SpanLogLayer, *};
+
+    #[test]
+    fn test_spans_have_names_and_attributes() {
+        let closed = Arc::new(Mutex::new(Vec::new()));
+        let sink = closed.clone();
+        let subscriber = tracing_subscriber::registry().with(SpanLogLayer::new(
+            move |name: &str, fields: &str| {
+                sink.lock().unwrap().push(format!("{name}{fields}"));
+            },
+        ));
+
+        tracing::subscriber::with_default(subscriber, || {
+            futures::executor::block_on(async {
+                let _ = traced(Operation::Connect { server: "fs" }, |_: &()| "ok", async {
+                    Ok::<_, ()>(())
+                })
+                .await;
+                let _ = traced(
+                    Operation::ToolCall {
+                        server: "fs",
+                        tool: "read",
+                    },
+                    |_: &()| "ok",
+                    async { Err::<(), _>("failed") },
+                )
+                .await;
+            });
+        });
+
+        let closed = closed.lock().unwrap();
+        assert_eq!(closed.len(), 2);
+        assert!(closed[0].starts_with("mcp.connect server=fs"));
+        assert!(closed[0].contains(" duration_ms="));
+        assert!(closed[0].ends_with(" outcome=ok"));
+        assert!(closed[1].starts_with("mcp.tool_call server=fs tool=read"));
+        assert!(closed[1].ends_with(" outcome=error"));
+    }
+}
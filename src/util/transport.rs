@@ -0,0 +1,270 @@
+//! JSON-RPC over a command server's stdin and stdout
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+use futures::{Sink, Stream};
+use rmcp::model::{ClientJsonRpcMessage, ServerJsonRpcMessage};
+use serde_json::Value as JsonValue;
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::{ChildStdin, ChildStdout},
+};
+
+use super::resource_link::encode_resource_links;
+
+/// The transport for a command server
+///
+/// Requests are written with rmcp's own codec. Messages from the server are
+/// read a line at a time and adjusted before rmcp parses them, so that
+/// content rmcp doesn't know (like `resource_link` blocks) survives. The
+/// server's answer to `initialize` is kept in `initialize` as it was sent.
+pub fn stdio_transport(
+    stdout: ChildStdout,
+    stdin: ChildStdin,
+    server: &str,
+    initialize: InitializeCapture,
+) -> (
+    impl Sink<ClientJsonRpcMessage, Error = std::io::Error> + Send + 'static,
+    impl Stream<Item = ServerJsonRpcMessage> + Send + 'static,
+) {
+    let lines = BufReader::new(stdout).lines();
+    let messages = futures::stream::unfold(
+        (lines, LineDecoder::new(server, initialize)),
+        |(mut lines, mut decoder)| async move {
+            loop {
+                match lines.next_line().await {
+                    Ok(Some(line)) => {
+                        if let Some(message) = decoder.decode(&line) {
+                            return Some((message, (lines, decoder)));
+                        }
+                    }
+                    Ok(None) => return None,
+                    Err(err) => {
+                        log::error!("Failed to read from '{}': {err}", decoder.server);
+                        return None;
+                    }
+                }
+            }
+        },
+    );
+
+    (rmcp::transport::io::from_async_write(stdin), messages)
+}
+
+/// The `result` of a server's answer to `initialize`, before rmcp parses it
+///
+/// rmcp turns the answer into a `ServerInfo`, which drops every field it
+/// doesn't model, such as capabilities from newer protocol versions. The
+/// transport keeps the JSON it read so `mcp info --raw` can show all of it.
+#[derive(Clone, Debug, Default)]
+pub struct InitializeCapture(Arc<Mutex<Option<JsonValue>>>);
+
+impl InitializeCapture {
+    /// Keep `message`'s result if it's the first answer to `initialize`
+    fn record(&self, message: &JsonValue) {
+        let Some(result) = message.get("result") else {
+            return;
+        };
+        let initialize =
+            result.get("protocolVersion").is_some() && result.get("capabilities").is_some();
+        if initialize {
+            self.slot().get_or_insert_with(|| result.clone());
+        }
+    }
+
+    /// The result that was recorded, if the server has answered
+    #[must_use]
+    pub fn take(&self) -> Option<JsonValue> {
+        self.slot().take()
+    }
+
+    fn slot(&self) -> std::sync::MutexGuard<'_, Option<JsonValue>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// How much of a stray line is shown when reporting it
+const SNIPPET_CHARS: usize = 120;
+
+/// Something other than JSON-RPC on a server's stdout, after it started talking
+#[derive(Debug)]
+pub struct StdoutCorruption {
+    pub server: String,
+    pub snippet: String,
+}
+
+impl std::fmt::Display for StdoutCorruption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "'{}' wrote something other than JSON-RPC to stdout: {}\nThe line was skipped. \
+             MCP servers must only write protocol messages to stdout; configure the server \
+             to send its logging to stderr or a file.",
+            self.server, self.snippet
+        )
+    }
+}
+
+/// Tells a server's messages apart from the other things it writes to stdout
+///
+/// Many servers print a banner or package manager warnings before they
+/// start speaking JSON-RPC. Until the first message arrives, such lines are
+/// skipped with a warning. After that, a stray line means the server's
+/// output is corrupted, which is reported as a [`StdoutCorruption`].
+struct LineDecoder {
+    server: String,
+    started: bool,
+    initialize: InitializeCapture,
+}
+
+impl LineDecoder {
+    fn new(server: &str, initialize: InitializeCapture) -> Self {
+        Self {
+            server: server.to_string(),
+            started: false,
+            initialize,
+        }
+    }
+
+    /// Parse a line from the server, or `None` for a line that isn't a message
+    fn decode(&mut self, line: &str) -> Option<ServerJsonRpcMessage> {
+        if line.trim().is_empty() {
+            return None;
+        }
+
+        let Ok(mut message) = serde_json::from_str::<JsonValue>(line) else {
+            self.stray_line(line);
+            return None;
+        };
+        self.initialize.record(&message);
+        encode_resource_links(&mut message, &self.server);
+
+        match serde_json::from_value(message) {
+            Ok(message) => {
+                self.started = true;
+                Some(message)
+            }
+            Err(err) if self.started => {
+                log::error!(
+                    "'{}' sent a message that isn't valid JSON-RPC: {err}",
+                    self.server
+                );
+                None
+            }
+            Err(_) => {
+                self.stray_line(line);
+                None
+            }
+        }
+    }
+
+    fn stray_line(&self, line: &str) {
+        if self.started {
+            let corruption = StdoutCorruption {
+                server: self.server.clone(),
+                snippet: snippet(line),
+            };
+            log::error!("{corruption}");
+            crate::error!("{}", corruption);
+        } else {
+            log::warn!(
+                "Ignoring output from '{}' before its first JSON-RPC message: {}",
+                self.server,
+                snippet(line)
+            );
+        }
+    }
+}
+
+fn snippet(line: &str) -> String {
+    let line = line.trim();
+    if line.chars().count() <= SNIPPET_CHARS {
+        return line.to_string();
+    }
+
+    let mut snippet: String = line.chars().take(SNIPPET_CHARS).collect();
+    snippet.push('…');
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTIFICATION: &str = r#"{"jsonrpc":"2.0","method":"notifications/tools/list_changed"}"#;
+
+    #[test]
+    fn test_skips_banner_before_first_message() {
+        let mut decoder = LineDecoder::new("chatty", InitializeCapture::default());
+
+        assert!(
+            decoder
+                .decode("npm WARN deprecated left-pad@1.0.0")
+                .is_none()
+        );
+        assert!(
+            decoder
+                .decode(r#"{"level": "info", "msg": "starting"}"#)
+                .is_none()
+        );
+        assert!(!decoder.started);
+
+        assert!(decoder.decode(NOTIFICATION).is_some());
+        assert!(decoder.started);
+
+        // Garbage later on is reported, but the session carries on
+        assert!(decoder.decode("Listening on port 3000").is_none());
+        assert!(decoder.decode(NOTIFICATION).is_some());
+    }
+
+    #[test]
+    fn test_records_the_initialize_result_as_sent() {
+        let initialize = InitializeCapture::default();
+        let mut decoder = LineDecoder::new("future", initialize.clone());
+        let answer = r#"{"jsonrpc":"2.0","id":0,"result":{"protocolVersion":"2025-03-26","capabilities":{"tools":{},"experimental":{"x-future":{"depth":3}}},"serverInfo":{"name":"future","version":"2.0.0"},"unmodeled":[1,2]}}"#;
+
+        assert!(decoder.decode(NOTIFICATION).is_some());
+        assert!(decoder.decode(answer).is_some());
+        // Later answers don't replace the first
+        let later = answer.replace("2.0.0", "3.0.0");
+        assert!(decoder.decode(&later).is_some());
+
+        let result = initialize.take().unwrap();
+        assert_eq!(result["serverInfo"]["version"], "2.0.0");
+        assert_eq!(
+            result["capabilities"]["experimental"]["x-future"]["depth"],
+            3
+        );
+        assert_eq!(result["unmodeled"], serde_json::json!([1, 2]));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fake_server_with_banner() {
+        use futures::StreamExt;
+
+        let script = format!(
+            "echo 'Welcome to the chatty server v1.0'; echo '> chatty@1.0.0 start'; echo '{NOTIFICATION}'; echo 'debug: still here'; echo '{NOTIFICATION}'"
+        );
+
+        let messages = crate::engine::shared_runtime().block_on(async {
+            let mut child = tokio::process::Command::new("sh")
+                .arg("-c")
+                .arg(&script)
+                .stdin(std::process::Stdio::piped())
+                .stdout(std::process::Stdio::piped())
+                .spawn()
+                .unwrap();
+            let stdout = child.stdout.take().unwrap();
+            let stdin = child.stdin.take().unwrap();
+
+            let (_sink, stream) =
+                stdio_transport(stdout, stdin, "chatty", InitializeCapture::default());
+            let messages: Vec<ServerJsonRpcMessage> = stream.collect().await;
+            let _ = child.wait().await;
+            messages
+        });
+
+        assert_eq!(messages.len(), 2);
+    }
+}
@@ -0,0 +1,252 @@
+//! A small implementation of the URI templates used by MCP resource templates
+//!
+//! Only the expressions that show up in practice are supported: simple
+//! (`{var}`), reserved (`{+var}`), fragment (`{#var}`) and the query forms
+//! (`{?a,b}` and `{&a,b}`). Variables in query expressions are optional; all
+//! other variables are required.
+
+use std::fmt::Write;
+
+use indexmap::IndexMap;
+
+/// A parsed URI template
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UriTemplate {
+    parts: Vec<Part>,
+}
+
+/// A variable that can be filled in when expanding a template
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TemplateVariable {
+    pub name: String,
+    pub required: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Expression {
+        operator: Operator,
+        names: Vec<String>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operator {
+    Simple,
+    Reserved,
+    Fragment,
+    Query,
+    QueryContinuation,
+}
+
+impl UriTemplate {
+    /// Parse a template, failing on unterminated or unsupported expressions
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+
+            let Some(end) = rest[start..].find('}') else {
+                return Err(format!("unterminated expression in '{template}'"));
+            };
+            let expression = &rest[start + 1..start + end];
+
+            let (operator, names) = match expression.chars().next() {
+                Some('+') => (Operator::Reserved, &expression[1..]),
+                Some('#') => (Operator::Fragment, &expression[1..]),
+                Some('?') => (Operator::Query, &expression[1..]),
+                Some('&') => (Operator::QueryContinuation, &expression[1..]),
+                Some(c) if c.is_ascii_alphanumeric() || c == '_' => (Operator::Simple, expression),
+                _ => return Err(format!("unsupported expression '{{{expression}}}'")),
+            };
+
+            let names: Vec<String> = names
+                .split(',')
+                .map(|name| name.trim().trim_end_matches('*').to_string())
+                .filter(|name| !name.is_empty())
+                .collect();
+
+            if names.is_empty() {
+                return Err(format!("empty expression in '{template}'"));
+            }
+
+            parts.push(Part::Expression { operator, names });
+            rest = &rest[start + end + 1..];
+        }
+
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// The template's variables, in the order they first appear
+    #[must_use]
+    pub fn variables(&self) -> Vec<TemplateVariable> {
+        let mut variables: Vec<TemplateVariable> = Vec::new();
+
+        for part in &self.parts {
+            let Part::Expression { operator, names } = part else {
+                continue;
+            };
+
+            for name in names {
+                if variables.iter().any(|variable| &variable.name == name) {
+                    continue;
+                }
+
+                variables.push(TemplateVariable {
+                    name: name.clone(),
+                    required: !matches!(operator, Operator::Query | Operator::QueryContinuation),
+                });
+            }
+        }
+
+        variables
+    }
+
+    /// Expand the template with the given values
+    ///
+    /// Missing optional variables are left out; a missing required variable
+    /// is an error.
+    pub fn expand(&self, values: &IndexMap<String, String>) -> Result<String, String> {
+        let mut uri = String::new();
+
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => uri.push_str(literal),
+                Part::Expression { operator, names } => {
+                    let mut first = true;
+
+                    for name in names {
+                        let Some(value) = values.get(name) else {
+                            if matches!(operator, Operator::Query | Operator::QueryContinuation) {
+                                continue;
+                            }
+                            return Err(format!("missing value for '{name}'"));
+                        };
+
+                        match (operator, first) {
+                            (Operator::Query, true) => uri.push('?'),
+                            (Operator::Query | Operator::QueryContinuation, _) => uri.push('&'),
+                            (Operator::Fragment, true) => uri.push('#'),
+                            (_, false) => uri.push(','),
+                            _ => {}
+                        }
+
+                        if matches!(operator, Operator::Query | Operator::QueryContinuation) {
+                            uri.push_str(name);
+                            uri.push('=');
+                        }
+
+                        let allow_reserved =
+                            matches!(operator, Operator::Reserved | Operator::Fragment);
+                        uri.push_str(&percent_encode(value, allow_reserved));
+                        first = false;
+                    }
+                }
+            }
+        }
+
+        Ok(uri)
+    }
+}
+
+/// Percent-encode everything except unreserved (and optionally reserved) characters
+fn percent_encode(value: &str, allow_reserved: bool) -> String {
+    const RESERVED: &str = ":/?#[]@!$&'()*+,;=";
+    let mut encoded = String::with_capacity(value.len());
+
+    for byte in value.bytes() {
+        let c = char::from(byte);
+        if c.is_ascii_alphanumeric()
+            || "-._~".contains(c)
+            || (allow_reserved && RESERVED.contains(c))
+        {
+            encoded.push(c);
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> IndexMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_variables() {
+        let template =
+            UriTemplate::parse("github://repos/{owner}/{repo}/issues{?state,page}").unwrap();
+        let variables = template.variables();
+
+        assert_eq!(
+            variables,
+            vec![
+                TemplateVariable {
+                    name: "owner".into(),
+                    required: true
+                },
+                TemplateVariable {
+                    name: "repo".into(),
+                    required: true
+                },
+                TemplateVariable {
+                    name: "state".into(),
+                    required: false
+                },
+                TemplateVariable {
+                    name: "page".into(),
+                    required: false
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expand() {
+        let template =
+            UriTemplate::parse("github://repos/{owner}/{repo}/issues{?state,page}").unwrap();
+
+        assert_eq!(
+            template
+                .expand(&values(&[
+                    ("owner", "wycats"),
+                    ("repo", "mcp repl"),
+                    ("page", "2")
+                ]))
+                .unwrap(),
+            "github://repos/wycats/mcp%20repl/issues?page=2"
+        );
+        assert!(template.expand(&values(&[("owner", "wycats")])).is_err());
+
+        let reserved = UriTemplate::parse("file:///{+path}").unwrap();
+        assert_eq!(
+            reserved
+                .expand(&values(&[("path", "src/main.rs")]))
+                .unwrap(),
+            "file:///src/main.rs"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(UriTemplate::parse("github://{owner").is_err());
+        assert!(UriTemplate::parse("github://{}").is_err());
+    }
+}
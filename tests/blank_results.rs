@@ -0,0 +1,33 @@
+//! A blank text result is nothing, and the text it held is kept aside
+
+#![cfg(unix)]
+
+mod common;
+
+use common::{INITIALIZE, call_server_script, run_commands, test_dir, write_servers};
+
+/// The tools of a server with one tool, `query`
+const TOOLS: &str = r#"[{"name":"query","inputSchema":{"type":"object","properties":{}}}]"#;
+
+#[test]
+fn test_blank_results_keep_their_text_in_the_last_call() {
+    let dir = test_dir("blank-results");
+    // printf turns the doubled backslashes into JSON escapes
+    let script = call_server_script(
+        INITIALIZE,
+        TOOLS,
+        r#"{"content":[{"type":"text","text":" \\n\\t"}]}"#,
+    );
+    write_servers(&dir, &script, &["db"]);
+
+    let (success, stdout, stderr) = run_commands(
+        &dir,
+        "tool db.query | metadata | get content_type | print; \
+         $mcp_last_call.original | to json --raw",
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(success, "stderr: {stderr}");
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines, ["text/plain", r#"" \n\t""#], "{stdout}");
+}
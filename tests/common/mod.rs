@@ -0,0 +1,91 @@
+//! Running the REPL against mock command servers
+
+// Each test binary uses only some of these
+#![allow(dead_code)]
+
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The `initialize` result of a server that has tools and nothing else
+pub const INITIALIZE: &str = r#"{"protocolVersion":"2025-03-26","capabilities":{"tools":{}},"serverInfo":{"name":"mock","version":"1.0.0"}}"#;
+
+/// A server that answers `initialize` with `initialize` and `tools/list`
+/// with the JSON array `tools`, as a shell script
+///
+/// Neither may contain a `%`, since they're written with `printf`.
+pub fn server_script(initialize: &str, tools: &str) -> String {
+    r#"while read -r line; do
+  id=$(printf '%s' "$line" | sed -n 's/.*"id":\([0-9]*\).*/\1/p')
+  case "$line" in
+    *'"method":"initialize"'*) printf '{"jsonrpc":"2.0","id":%s,"result":INITIALIZE}\n' "$id" ;;
+    *'"method":"tools/list"'*) printf '{"jsonrpc":"2.0","id":%s,"result":{"tools":TOOLS}}\n' "$id" ;;
+  esac
+done
+"#
+    .replace("INITIALIZE", initialize)
+    .replace("TOOLS", tools)
+}
+
+/// A server like [`server_script`] that also answers every `tools/call`
+/// with `result`, logging each call to `calls.log` next to the script
+///
+/// `result` may not contain a `%` either.
+pub fn call_server_script(initialize: &str, tools: &str, result: &str) -> String {
+    server_script(initialize, tools).replace(
+        "  esac",
+        &r#"    *'"method":"tools/call"'*)
+      printf '%s\n' "$line" >> "$(dirname "$0")/calls.log"
+      printf '{"jsonrpc":"2.0","id":%s,"result":RESULT}\n' "$id" ;;
+  esac"#
+            .replace("RESULT", result),
+    )
+}
+
+/// A fresh directory for one test, named after it
+pub fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mcp-repl-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Write `script` to `dir` and configure one command server running it under
+/// each of `names`
+pub fn write_servers(dir: &Path, script: &str, names: &[&str]) {
+    let path = dir.join("server.sh");
+    std::fs::write(&path, script).unwrap();
+    let mut config = String::new();
+    for name in names {
+        writeln!(
+            config,
+            "[servers.{name}]\ncommand = \"sh {}\"",
+            path.display()
+        )
+        .unwrap();
+    }
+    std::fs::write(dir.join("config.toml"), config).unwrap();
+}
+
+/// Run the REPL in `dir` with `dir/config.toml` and state kept in `dir`,
+/// returning whether it succeeded, its stdout and its stderr
+pub fn run(dir: &Path, args: &[&str]) -> (bool, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_nu-mcp-repl"))
+        .args(args)
+        .current_dir(dir)
+        .env("MCP_CONFIG", dir.join("config.toml"))
+        .env("MCP_STATE_DIR", dir.join("state"))
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    )
+}
+
+/// Run `commands` in the REPL, as [`run`] does
+pub fn run_commands(dir: &Path, commands: &str) -> (bool, String, String) {
+    run(dir, &["--commands", commands])
+}
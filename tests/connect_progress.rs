@@ -0,0 +1,28 @@
+//! Without a terminal, startup reports only a summary of the servers it
+//! connected, and `mcp list --stats` has each server's connect time
+
+#![cfg(unix)]
+
+mod common;
+
+use common::{INITIALIZE, run_commands, server_script, test_dir, write_servers};
+
+#[test]
+fn test_startup_summary_and_connect_time() {
+    let dir = test_dir("progress");
+    write_servers(&dir, &server_script(INITIALIZE, "[]"), &["fs", "web"]);
+
+    let (success, stdout, stderr) = run_commands(
+        &dir,
+        "mcp list --stats | each {|server| $server.connect_time > 0sec } | to json --raw",
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(success, "stderr: {stderr}");
+    assert!(stdout.contains("Connected 2 of 2 servers in"), "{stdout}");
+    assert!(stdout.contains("[true,true]"), "{stdout}");
+    // No status line or per-server marks when stderr isn't a terminal
+    assert!(!stderr.contains("Connecting"), "{stderr}");
+    assert!(!stderr.contains('\u{1b}'), "{stderr}");
+    assert!(!stdout.contains('✓'), "{stdout}");
+}
@@ -0,0 +1,38 @@
+//! Generated tool commands can be found with `help --find`
+
+#![cfg(unix)]
+
+mod common;
+
+use common::{INITIALIZE, run_commands, server_script, test_dir, write_servers};
+
+/// The tools of a server with one tool, `create_issue`
+const TOOLS: &str = r#"[{"name":"create_issue","description":"Open a new issue in a repository","inputSchema":{"type":"object","properties":{"title":{"type":"string"}}}}]"#;
+
+#[test]
+fn test_help_find_returns_tool_commands() {
+    let dir = test_dir("help-find");
+    write_servers(&dir, &server_script(INITIALIZE, TOOLS), &["github"]);
+
+    let run = |search: &str| {
+        let (success, stdout, stderr) = run_commands(
+            &dir,
+            &format!("help --find {search} | get name | to json --raw"),
+        );
+        assert!(success, "stderr: {stderr}");
+        stdout
+    };
+
+    let by_description = run("issue");
+    let by_server = run("github");
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(
+        by_description.contains("tool github.create_issue"),
+        "{by_description}"
+    );
+    assert!(
+        by_server.contains("tool github.create_issue"),
+        "{by_server}"
+    );
+}
@@ -0,0 +1,71 @@
+//! `--max-runtime` stops a script stuck on a server that never answers
+
+#![cfg(unix)]
+
+mod common;
+
+use std::{
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use common::{INITIALIZE, server_script, test_dir, write_servers};
+
+/// The tools of a server with one tool, `query`
+const TOOLS: &str = r#"[{"name":"query","inputSchema":{"type":"object","properties":{}}}]"#;
+
+/// Whether the process `pid` is still running, rather than gone or a zombie
+fn is_running(pid: &str) -> bool {
+    let output = Command::new("ps")
+        .args(["-o", "stat=", "-p", pid])
+        .output()
+        .unwrap();
+    let stat = String::from_utf8_lossy(&output.stdout);
+    !stat.trim().is_empty() && !stat.trim().starts_with('Z')
+}
+
+#[test]
+fn test_max_runtime_cancels_a_stuck_call() {
+    let dir = test_dir("max-runtime");
+    // The server records its pid and never answers `tools/call`
+    let script = format!(
+        "echo $$ > \"$(dirname \"$0\")/server.pid\"\n{}",
+        server_script(INITIALIZE, TOOLS)
+    );
+    write_servers(&dir, &script, &["db"]);
+
+    let started = Instant::now();
+    let output = Command::new(env!("CARGO_BIN_EXE_nu-mcp-repl"))
+        .args([
+            "--max-runtime",
+            "2s",
+            "--commands",
+            "print 'before the call'; tool db.query",
+        ])
+        .current_dir(&dir)
+        .env("MCP_CONFIG", dir.join("config.toml"))
+        .env("MCP_STATE_DIR", dir.join("state"))
+        .output()
+        .unwrap();
+    let elapsed = started.elapsed();
+    let pid = std::fs::read_to_string(dir.join("server.pid")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(124), "stderr: {stderr}");
+    // Well before the 30 second call timeout
+    assert!(elapsed < Duration::from_secs(20), "{elapsed:?}");
+    assert!(stdout.contains("before the call"), "{stdout}");
+    assert!(
+        stderr.contains("the script exceeded --max-runtime of 2s while waiting for 'db.query'"),
+        "{stderr}"
+    );
+
+    // Give the orphaned server a moment to be reaped
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while is_running(pid.trim()) && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    assert!(!is_running(pid.trim()), "server {pid} is still running");
+}
@@ -0,0 +1,56 @@
+//! `mcp disconnect` picks servers by name, glob or `--all`
+
+#![cfg(unix)]
+
+mod common;
+
+use common::{INITIALIZE, run_commands, server_script, test_dir, write_servers};
+
+#[test]
+fn test_disconnect_expands_patterns_and_summarizes() {
+    let dir = test_dir("disconnect");
+    write_servers(
+        &dir,
+        &server_script(INITIALIZE, "[]"),
+        &["proj-api", "proj-db", "github"],
+    );
+
+    let by_pattern = run_commands(
+        &dir,
+        "{disconnected: (mcp disconnect 'proj-*'), list: (mcp list | select name status)} | to json --raw",
+    );
+    let without_names = run_commands(&dir, "mcp disconnect");
+    let with_all = run_commands(&dir, "mcp disconnect --all | get server | to json --raw");
+    let unmatched = run_commands(&dir, "mcp disconnect github 'web-*'");
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let (success, stdout, stderr) = by_pattern;
+    assert!(success, "stderr: {stderr}");
+    assert!(
+        stdout.contains(
+            r#""disconnected":[{"server":"proj-api","status":"ok","detail":"disconnected"},{"server":"proj-db","status":"ok","detail":"disconnected"}]"#
+        ),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains(
+            r#"{"name":"proj-db","status":"disconnected"},{"name":"github","status":"connected"}"#
+        ),
+        "{stdout}"
+    );
+
+    let (success, _, stderr) = without_names;
+    assert!(!success);
+    assert!(stderr.contains("--all"), "{stderr}");
+
+    let (success, stdout, stderr) = with_all;
+    assert!(success, "stderr: {stderr}");
+    assert!(
+        stdout.contains(r#"["proj-api","proj-db","github"]"#),
+        "{stdout}"
+    );
+
+    let (success, _, stderr) = unmatched;
+    assert!(!success);
+    assert!(stderr.contains("No MCP server matches 'web-*'"), "{stderr}");
+}
@@ -0,0 +1,52 @@
+//! `mcp info --raw` returns the server's answer to `initialize` unchanged
+
+#![cfg(unix)]
+
+mod common;
+
+use common::{run_commands, server_script, test_dir, write_servers};
+
+/// An `initialize` result that declares an experimental capability and a
+/// field no protocol version has
+const INITIALIZE: &str = r#"{"protocolVersion":"2025-03-26","capabilities":{"tools":{},"experimental":{"x-mock/holograms":{"depth":3,"modes":["red","blue"]}}},"serverInfo":{"name":"mock","version":"1.0.0"},"instructions":"Ask for holograms","x-unmodeled":{"nested":true}}"#;
+
+#[test]
+fn test_raw_info_keeps_fields_the_repl_does_not_model() {
+    let dir = test_dir("info-raw");
+    write_servers(&dir, &server_script(INITIALIZE, "[]"), &["mock"]);
+
+    let raw = run_commands(&dir, "mcp info mock --raw | to json --raw");
+    let restarted = run_commands(
+        &dir,
+        "mcp restart mock | ignore; mcp info mock --raw | get capabilities.experimental | to json --raw",
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let (success, stdout, stderr) = raw;
+    assert!(success, "stderr: {stderr}");
+    assert!(
+        stdout
+            .contains(r#""experimental":{"x-mock/holograms":{"depth":3,"modes":["red","blue"]}}"#),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains(r#""x-unmodeled":{"nested":true}"#),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains(r#""instructions":"Ask for holograms""#),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains(r#""protocolVersion":"2025-03-26""#),
+        "{stdout}"
+    );
+
+    // A restart records the answer from the new connection
+    let (success, stdout, stderr) = restarted;
+    assert!(success, "stderr: {stderr}");
+    assert!(
+        stdout.contains(r#"{"x-mock/holograms":{"depth":3,"modes":["red","blue"]}}"#),
+        "{stdout}"
+    );
+}
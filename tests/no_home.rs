@@ -0,0 +1,52 @@
+//! The REPL runs where there's no home directory to keep state in
+
+use std::process::Command;
+
+#[test]
+fn test_runs_without_a_usable_home() {
+    let dir = std::env::temp_dir().join(format!("mcp-repl-no-home-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    // A file can't have a .mcp-repl directory created in it, even by root
+    let home = dir.join("home");
+    std::fs::write(&home, "").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_nu-mcp-repl"))
+        .args(["--commands", "tool list"])
+        .current_dir(&dir)
+        .env("HOME", &home)
+        .env("XDG_CONFIG_HOME", &home)
+        .env("XDG_CACHE_HOME", &home)
+        .env_remove("MCP_STATE_DIR")
+        .env_remove("MCP_CONFIG")
+        .output()
+        .unwrap();
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_runs_without_home() {
+    let dir = std::env::temp_dir().join(format!("mcp-repl-unset-home-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_nu-mcp-repl"))
+        .args(["--commands", "tool list"])
+        .current_dir(&dir)
+        .env_remove("HOME")
+        .env_remove("MCP_STATE_DIR")
+        .env_remove("MCP_CONFIG")
+        .output()
+        .unwrap();
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
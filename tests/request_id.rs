@@ -0,0 +1,43 @@
+//! A failed call names the JSON-RPC id the server received it with
+
+#![cfg(unix)]
+
+mod common;
+
+use common::{INITIALIZE, run_commands, server_script, test_dir, write_servers};
+
+/// The tools of a server with one tool, `query`
+const TOOLS: &str = r#"[{"name":"query","inputSchema":{"type":"object","properties":{}}}]"#;
+
+#[test]
+fn test_failed_calls_report_the_id_the_server_saw() {
+    let dir = test_dir("request-id");
+    // Every call fails, and each request line is logged as the server got it
+    let script = server_script(INITIALIZE, TOOLS).replace(
+        "  esac",
+        r#"    *'"method":"tools/call"'*)
+      printf '%s\n' "$line" >> "$(dirname "$0")/calls.log"
+      printf '{"jsonrpc":"2.0","id":%s,"error":{"code":-32000,"message":"it broke"}}\n' "$id" ;;
+  esac"#,
+    );
+    write_servers(&dir, &script, &["db"]);
+
+    let (success, stdout, stderr) = run_commands(
+        &dir,
+        "tool db.query --try | get error | select request_id message | to json --raw",
+    );
+    let calls = std::fs::read_to_string(dir.join("calls.log")).unwrap_or_default();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(success, "stderr: {stderr}");
+    let error: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    let id = error["request_id"].as_i64().unwrap();
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .starts_with(&format!("request id {id} to server db failed")),
+        "{error}"
+    );
+    assert!(calls.contains(&format!(r#""id":{id},"#)), "{calls}");
+}
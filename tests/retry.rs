@@ -0,0 +1,34 @@
+//! `retry` only sends a call again when its request never left
+
+#![cfg(unix)]
+
+mod common;
+
+use common::{INITIALIZE, run_commands, server_script, test_dir, write_servers};
+
+/// The tools of a server with one tool, `query`
+const TOOLS: &str = r#"[{"name":"query","inputSchema":{"type":"object","properties":{}}}]"#;
+
+#[test]
+fn test_calls_the_server_received_arent_retried() {
+    let dir = test_dir("retry");
+    // Every call fails after the server has logged it
+    let script = server_script(INITIALIZE, TOOLS).replace(
+        "  esac",
+        r#"    *'"method":"tools/call"'*)
+      printf '%s\n' "$line" >> "$(dirname "$0")/calls.log"
+      printf '{"jsonrpc":"2.0","id":%s,"error":{"code":-32000,"message":"it broke"}}\n' "$id" ;;
+  esac"#,
+    );
+    write_servers(&dir, &script, &["db"]);
+    let config = std::fs::read_to_string(dir.join("config.toml")).unwrap();
+    std::fs::write(dir.join("config.toml"), format!("{config}retry = 2\n")).unwrap();
+
+    let (success, _, stderr) = run_commands(&dir, "tool db.query");
+    let calls = std::fs::read_to_string(dir.join("calls.log")).unwrap_or_default();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(!success);
+    assert!(stderr.contains("it broke"), "{stderr}");
+    assert_eq!(calls.lines().count(), 1, "{calls}");
+}
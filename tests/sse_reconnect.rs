@@ -0,0 +1,153 @@
+//! A dropped SSE stream is reconnected, reported and counted
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    process::Command,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+};
+
+/// An SSE server that drops its event stream once, after the tools are listed
+struct FlakyServer {
+    /// The event stream responses are written to
+    events: Mutex<Option<TcpStream>>,
+    dropped: AtomicBool,
+}
+
+impl FlakyServer {
+    fn start() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = Arc::new(Self {
+            events: Mutex::new(None),
+            dropped: AtomicBool::new(false),
+        });
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let server = server.clone();
+                thread::spawn(move || server.handle(stream));
+            }
+        });
+        port
+    }
+
+    fn handle(&self, mut stream: TcpStream) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let mut length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).unwrap();
+            if header.trim().is_empty() {
+                break;
+            }
+            if let Some(value) = header.to_lowercase().strip_prefix("content-length:") {
+                length = value.trim().parse().unwrap();
+            }
+        }
+
+        if request_line.starts_with("GET") {
+            stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n",
+                )
+                .unwrap();
+            // A reconnected stream carries on the same session
+            if !self.dropped.load(Ordering::SeqCst) {
+                stream
+                    .write_all(b"event: endpoint\ndata: /message?sessionId=1\n\n")
+                    .unwrap();
+            }
+            stream.flush().unwrap();
+            *self.events.lock().unwrap() = Some(stream);
+            return;
+        }
+
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        let body = String::from_utf8(body).unwrap();
+        stream
+            .write_all(b"HTTP/1.1 202 Accepted\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+
+        let Some(id) = body
+            .split("\"id\":")
+            .nth(1)
+            .map(|rest| rest.split([',', '}']).next().unwrap().to_string())
+        else {
+            return;
+        };
+        let result = if body.contains("\"initialize\"") {
+            r#"{"protocolVersion":"2024-11-05","capabilities":{"tools":{}},"serverInfo":{"name":"flaky","version":"1.0.0"}}"#
+        } else if body.contains("\"tools/list\"") {
+            r#"{"tools":[]}"#
+        } else {
+            "{}"
+        };
+
+        let mut events = self.events.lock().unwrap();
+        if let Some(events) = events.as_mut() {
+            let message = format!(
+                "event: message\ndata: {{\"jsonrpc\":\"2.0\",\"id\":{id},\"result\":{result}}}\n\n"
+            );
+            events.write_all(message.as_bytes()).unwrap();
+            events.flush().unwrap();
+        }
+        let drop_stream =
+            body.contains("\"tools/list\"") && !self.dropped.swap(true, Ordering::SeqCst);
+        if let Some(events) = events.take_if(|_| drop_stream) {
+            events.shutdown(std::net::Shutdown::Both).unwrap();
+        }
+    }
+}
+
+fn run(dir: &Path, commands: &str) -> (bool, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_nu-mcp-repl"))
+        .args(["--commands", commands])
+        .current_dir(dir)
+        .env("MCP_CONFIG", dir.join("config.toml"))
+        .env("MCP_STATE_DIR", dir.join("state"))
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    )
+}
+
+#[test]
+fn test_sse_reconnect_is_reported_and_counted() {
+    let port = FlakyServer::start();
+    let dir = std::env::temp_dir().join(format!("mcp-repl-sse-reconnect-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("config.toml"),
+        format!("[servers.flaky]\nurl = \"http://127.0.0.1:{port}/sse\"\n"),
+    )
+    .unwrap();
+
+    // rmcp waits a moment before reconnecting
+    let (success, stdout, stderr) = run(
+        &dir,
+        "sleep 3sec; mcp list --stats | select name sse_reconnects | to json --raw",
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(success, "stderr: {stderr}");
+    assert!(
+        stdout.contains("flaky: SSE stream reconnected (1st time this session)"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains(r#"[{"name":"flaky","sse_reconnects":1}]"#),
+        "{stdout}"
+    );
+}
@@ -0,0 +1,93 @@
+//! `startup_commands` and `--startup-cmd` run in order before a script
+
+mod common;
+
+use std::path::Path;
+
+use common::test_dir;
+
+/// Run the REPL in `dir` with `config` as its configuration
+fn run(dir: &Path, config: &str, args: &[&str]) -> (bool, String, String) {
+    std::fs::write(dir.join("config.toml"), config).unwrap();
+    common::run(dir, args)
+}
+
+#[test]
+fn test_startup_commands_run_in_order_before_the_script() {
+    let dir = test_dir("startup");
+
+    let in_order = run(
+        &dir,
+        r#"startup_commands = ["'first-output'", "alias greet = echo 'aliased-output'"]"#,
+        &["--startup-cmd", "'second-output'", "--commands", "greet"],
+    );
+    let lenient = run(
+        &dir,
+        r#"startup_commands = ["error make {msg: 'startup-boom'}", "'after-failure'"]"#,
+        &["--commands", "'script-output'"],
+    );
+    let strict = run(
+        &dir,
+        r#"
+        startup_commands = ["error make {msg: 'startup-boom'}", "'after-failure'"]
+        startup_strict = true
+        "#,
+        &["--commands", "'script-output'"],
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let (success, stdout, stderr) = in_order;
+    assert!(success, "stderr: {stderr}");
+    let position = |text: &str| {
+        stdout
+            .find(text)
+            .unwrap_or_else(|| panic!("{text} missing from {stdout}"))
+    };
+    assert!(position("first-output") < position("second-output"));
+    // The alias defined at startup is there for the script
+    assert!(position("second-output") < position("aliased-output"));
+
+    let (success, stdout, stderr) = lenient;
+    assert!(success, "stderr: {stderr}");
+    // Warnings go to stdout with the rest of the REPL's messages
+    assert!(
+        stdout.contains("Startup command 1 failed") && stdout.contains("startup-boom"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("after-failure"), "{stdout}");
+    assert!(stdout.contains("script-output"), "{stdout}");
+
+    let (success, stdout, stderr) = strict;
+    assert!(!success);
+    assert!(stderr.contains("startup_strict"), "{stderr}");
+    assert!(!stdout.contains("after-failure"), "{stdout}");
+    assert!(!stdout.contains("script-output"), "{stdout}");
+}
+
+#[test]
+fn test_startup_commands_run_without_http_but_the_session_keeps_it() {
+    let dir = test_dir("restricted");
+    let has_http_get =
+        "scope commands | where name == 'http get' | length | $'http-get-commands: ($in)'";
+
+    let restricted = run(
+        &dir,
+        r#"startup_commands = ["http get http://127.0.0.1:9"]"#,
+        &["--commands", has_http_get],
+    );
+    let disabled = run(
+        &dir,
+        "enable_http_commands = false",
+        &["--commands", has_http_get],
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let (success, stdout, stderr) = restricted;
+    assert!(success, "stderr: {stderr}");
+    assert!(stdout.contains("Startup command 1 failed"), "{stdout}");
+    assert!(stdout.contains("http-get-commands: 1"), "{stdout}");
+
+    let (success, stdout, stderr) = disabled;
+    assert!(success, "stderr: {stderr}");
+    assert!(stdout.contains("http-get-commands: 0"), "{stdout}");
+}
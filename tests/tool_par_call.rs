@@ -0,0 +1,70 @@
+//! `tool par-call` converts each server's outcome into a row the way a single call would
+
+#![cfg(unix)]
+
+mod common;
+
+use common::{INITIALIZE, call_server_script, run_commands, test_dir, write_servers};
+
+/// The tools of a server with one tool, `query`
+const TOOLS: &str = r#"[{"name":"query","inputSchema":{"type":"object","properties":{}}}]"#;
+
+/// A result the tool flagged as an error
+const IS_ERROR: &str = r#"{"content":[{"type":"text","text":"no such table"}],"isError":true}"#;
+
+#[test]
+fn test_tool_errors_are_failed_rows() {
+    let dir = test_dir("par-call-is-error");
+    write_servers(
+        &dir,
+        &call_server_script(INITIALIZE, TOOLS, IS_ERROR),
+        &["a", "b"],
+    );
+
+    let (success, stdout, stderr) = run_commands(
+        &dir,
+        "tool par-call query | select server success result error | to json --raw",
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(success, "stderr: {stderr}");
+    assert!(
+        stdout.contains(
+            r#"[{"server":"a","success":false,"result":null,"error":"no such table"},{"server":"b","success":false,"result":null,"error":"no such table"}]"#
+        ),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn test_results_go_through_display_hooks() {
+    let dir = test_dir("par-call-display");
+    write_servers(
+        &dir,
+        &call_server_script(
+            INITIALIZE,
+            TOOLS,
+            r#"{"content":[{"type":"text","text":"found"}]}"#,
+        ),
+        &["a", "b"],
+    );
+    // Only `b`, the last server written, has a display hook
+    let config = std::fs::read_to_string(dir.join("config.toml")).unwrap();
+    std::fs::write(
+        dir.join("config.toml"),
+        format!("{config}display = \"str upcase\"\n"),
+    )
+    .unwrap();
+
+    let (success, stdout, stderr) = run_commands(
+        &dir,
+        "tool par-call query | select server result | to json --raw",
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(success, "stderr: {stderr}");
+    assert!(
+        stdout.contains(r#"[{"server":"a","result":"found"},{"server":"b","result":"FOUND"}]"#),
+        "{stdout}"
+    );
+}
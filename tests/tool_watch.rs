@@ -0,0 +1,35 @@
+//! `tool watch` reports a tool's own errors in the error column
+
+#![cfg(unix)]
+
+mod common;
+
+use common::{INITIALIZE, call_server_script, run_commands, test_dir, write_servers};
+
+/// The tools of a server with one tool, `status`
+const TOOLS: &str = r#"[{"name":"status","inputSchema":{"type":"object","properties":{}}}]"#;
+
+/// A result the tool flagged as an error
+const IS_ERROR: &str = r#"{"content":[{"type":"text","text":"build not found"}],"isError":true}"#;
+
+#[test]
+fn test_tool_errors_fill_the_error_column() {
+    let dir = test_dir("watch-is-error");
+    write_servers(
+        &dir,
+        &call_server_script(INITIALIZE, TOOLS, IS_ERROR),
+        &["ci"],
+    );
+
+    let (success, stdout, stderr) = run_commands(
+        &dir,
+        "tool watch ci.status --times 1 | select result error | to json --raw",
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(success, "stderr: {stderr}");
+    assert!(
+        stdout.contains(r#"[{"result":null,"error":"build not found"}]"#),
+        "{stdout}"
+    );
+}
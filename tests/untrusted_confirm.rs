@@ -0,0 +1,53 @@
+//! Commands that call a tool can't skip the confirmation an untrusted
+//! server's destructive tools need
+
+#![cfg(unix)]
+
+mod common;
+
+use common::{INITIALIZE, call_server_script, run_commands, test_dir, write_servers};
+
+/// The tools of a server with one destructive tool, `drop_table`
+const TOOLS: &str = r#"[{"name":"drop_table","inputSchema":{"type":"object","properties":{}},"annotations":{"destructiveHint":true}}]"#;
+
+/// What the tool would return if it were called
+const DROPPED: &str = r#"{"content":[{"type":"text","text":"dropped"}]}"#;
+
+#[test]
+fn test_destructive_tools_are_not_called_unconfirmed() {
+    let dir = test_dir("untrusted-confirm");
+    write_servers(
+        &dir,
+        &call_server_script(INITIALIZE, TOOLS, DROPPED),
+        &["db"],
+    );
+
+    let watch = run_commands(&dir, "tool watch db.drop_table --times 1");
+    let par_call = run_commands(
+        &dir,
+        "tool par-call drop_table | select success error | to json --raw",
+    );
+    let expect = run_commands(&dir, "tool expect db.drop_table");
+    let called = dir.join("calls.log").exists();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // --commands runs aren't interactive, so there's no one to confirm
+    let (success, _, stderr) = watch;
+    assert!(!success);
+    assert!(stderr.contains("requires confirmation"), "{stderr}");
+
+    let (success, stdout, stderr) = par_call;
+    assert!(success, "stderr: {stderr}");
+    assert!(
+        stdout.contains(
+            r#"[{"success":false,"error":"Tool 'db.drop_table' requires confirmation"}]"#
+        ),
+        "{stdout}"
+    );
+
+    let (success, _, stderr) = expect;
+    assert!(!success);
+    assert!(stderr.contains("requires confirmation"), "{stderr}");
+
+    assert!(!called, "the server was sent a tools/call");
+}